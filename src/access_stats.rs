@@ -0,0 +1,401 @@
+//! Analytics from web server access logs
+//!
+//! Static hosting has no backend to count visits, but the web server still
+//! logs every request. `cv stats ingest <access.log>` parses logs in the
+//! Common or Combined Log Format, adds the page views, external referrers and
+//! status codes to `stats/access_stats.json` and writes a report to
+//! `stats/report.html`. Both stay out of the output directory, so the numbers
+//! are never published. Entries up to the newest one already ingested are
+//! skipped, so a log can be ingested again after it has grown.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use flate2::read::GzDecoder;
+use im::OrdMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Where the aggregated statistics are stored
+pub const STATS_FILE: &str = "stats/access_stats.json";
+
+/// Where the HTML report is written
+pub const REPORT_FILE: &str = "stats/report.html";
+
+/// Rows per table in the report
+const REPORT_ROWS: usize = 20;
+
+/// Days shown in the daily views chart
+const REPORT_DAYS: usize = 30;
+
+/// User agent fragments of crawlers, whose requests aren't page views
+const BOT_MARKERS: [&str; 5] = ["bot", "crawl", "spider", "slurp", "preview"];
+
+/// One request from an access log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    /// When the request was received
+    pub time: DateTime<FixedOffset>,
+    /// HTTP method
+    pub method: String,
+    /// Requested path, without the query string
+    pub path: String,
+    /// Response status code
+    pub status: u16,
+    /// Referer header (Combined Log Format only)
+    pub referrer: Option<String>,
+    /// User-Agent header (Combined Log Format only)
+    pub user_agent: Option<String>,
+}
+
+impl LogEntry {
+    /// Parse a line in the Common or Combined Log Format
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - A line such as `1.2.3.4 - - [10/Oct/2026:13:55:36 +0200] "GET / HTTP/1.1" 200 2326`
+    ///
+    /// # Returns
+    ///
+    /// The entry, or None if the line isn't in either format
+    pub fn parse(line: &str) -> Option<Self> {
+        let captures = log_line_regex().captures(line)?;
+        let header = |i: usize| {
+            captures
+                .get(i)
+                .map(|m| m.as_str())
+                .filter(|value| !value.is_empty() && *value != "-")
+                .map(str::to_string)
+        };
+        let target = &captures[3];
+
+        Some(LogEntry {
+            time: DateTime::parse_from_str(&captures[1], "%d/%b/%Y:%H:%M:%S %z").ok()?,
+            method: captures[2].to_string(),
+            path: target
+                .split(['?', '#'])
+                .next()
+                .unwrap_or(target)
+                .to_string(),
+            status: captures[4].parse().ok()?,
+            referrer: header(5),
+            user_agent: header(6),
+        })
+    }
+
+    /// Whether the request is a person viewing a page, rather than an asset,
+    /// a failed request or a crawler
+    pub fn is_page_view(&self) -> bool {
+        let file_name = self.path.rsplit('/').next().unwrap_or_default();
+        let is_page = !file_name.contains('.') || file_name.ends_with(".html");
+        let is_bot = self.user_agent.as_deref().is_some_and(|agent| {
+            let agent = agent.to_lowercase();
+            BOT_MARKERS.iter().any(|marker| agent.contains(marker))
+        });
+        self.method == "GET" && (200..300).contains(&self.status) && is_page && !is_bot
+    }
+
+    /// The page, with `index.html` folded into its directory
+    pub fn page(&self) -> String {
+        match self.path.strip_suffix("index.html") {
+            Some(dir) if dir.ends_with('/') => dir.to_string(),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+/// What an ingest added
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestSummary {
+    /// New requests counted
+    pub requests: u64,
+    /// New page views counted
+    pub page_views: u64,
+    /// Entries skipped because they were ingested before
+    pub already_ingested: u64,
+    /// Lines that aren't in a supported log format
+    pub unparsed: u64,
+}
+
+/// Aggregated access statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessStats {
+    /// Views per page
+    #[serde(default)]
+    pub page_views: OrdMap<String, u64>,
+    /// Page views per referring host, other than the site itself
+    #[serde(default)]
+    pub referrers: OrdMap<String, u64>,
+    /// Requests per status code
+    #[serde(default)]
+    pub status_codes: OrdMap<u16, u64>,
+    /// Page views per day
+    #[serde(default)]
+    pub daily_views: OrdMap<NaiveDate, u64>,
+    /// Requests of any kind
+    #[serde(default)]
+    pub requests: u64,
+    /// Time of the newest ingested entry
+    #[serde(default)]
+    pub last_entry: Option<DateTime<FixedOffset>>,
+}
+
+impl AccessStats {
+    /// Load the statistics, or start empty if the file doesn't exist
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the JSON file
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the statistics or an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read access stats from {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse access stats from {}", path.display()))
+    }
+
+    /// Save the statistics
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write access stats to {}", path.display()))
+    }
+
+    /// Add the entries of a log that are newer than anything ingested before
+    ///
+    /// # Arguments
+    ///
+    /// * `log` - Contents of an access log
+    /// * `own_host` - Host of the site, whose referrers are internal navigation
+    ///
+    /// # Returns
+    ///
+    /// What was added
+    pub fn ingest(&mut self, log: &str, own_host: Option<&str>) -> IngestSummary {
+        let ingested_until = self.last_entry;
+        let mut summary = IngestSummary::default();
+
+        for line in log.lines().filter(|line| !line.trim().is_empty()) {
+            let Some(entry) = LogEntry::parse(line) else {
+                summary.unparsed += 1;
+                continue;
+            };
+            if ingested_until.is_some_and(|until| entry.time <= until) {
+                summary.already_ingested += 1;
+                continue;
+            }
+
+            summary.requests += 1;
+            self.requests += 1;
+            *self.status_codes.entry(entry.status).or_default() += 1;
+            self.last_entry = self.last_entry.max(Some(entry.time));
+
+            if entry.is_page_view() {
+                summary.page_views += 1;
+                *self.page_views.entry(entry.page()).or_default() += 1;
+                *self.daily_views.entry(entry.time.date_naive()).or_default() += 1;
+                if let Some(host) = entry.referrer.as_deref().and_then(url_host) {
+                    if Some(host) != own_host {
+                        *self.referrers.entry(host.to_string()).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Render the statistics as a self-contained HTML page
+    ///
+    /// # Arguments
+    ///
+    /// * `site_name` - Name shown in the title
+    ///
+    /// # Returns
+    ///
+    /// The report
+    pub fn report_html(&self, site_name: &str) -> String {
+        let total_views: u64 = self.page_views.values().sum();
+        let days = self
+            .daily_views
+            .iter()
+            .rev()
+            .take(REPORT_DAYS)
+            .collect::<Vec<_>>();
+        let busiest = days.iter().map(|(_, views)| **views).max().unwrap_or(1);
+        let daily_rows = days
+            .iter()
+            .rev()
+            .map(|(day, views)| {
+                format!(
+                    "<tr><td>{day}</td><td class=\"bar\"><span style=\"width: {}%\"></span></td><td>{views}</td></tr>",
+                    **views * 100 / busiest.max(1)
+                )
+            })
+            .collect::<String>();
+        let status_rows = self
+            .status_codes
+            .iter()
+            .map(|(status, count)| format!("<tr><td>{status}</td><td>{count}</td></tr>"))
+            .collect::<String>();
+        let period = match (
+            self.daily_views.keys().next(),
+            self.daily_views.keys().next_back(),
+        ) {
+            (Some(first), Some(last)) => format!("{first} to {last}"),
+            _ => "No page views yet".to_string(),
+        };
+
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<title>Access statistics - {name}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 56rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 2rem; }}
+td, th {{ padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; }}
+td:last-child, th:last-child {{ text-align: right; }}
+.bar {{ width: 60%; }}
+.bar span {{ display: block; height: 0.8rem; background: #1a5fb4; }}
+</style>
+</head>
+<body>
+<h1>Access statistics - {name}</h1>
+<p>{period}: {total_views} page views, {requests} requests.</p>
+<h2>Daily page views</h2>
+<table>{daily_rows}</table>
+<h2>Pages</h2>
+<table><tr><th>Page</th><th>Views</th></tr>{page_rows}</table>
+<h2>Referrers</h2>
+<table><tr><th>Host</th><th>Views</th></tr>{referrer_rows}</table>
+<h2>Status codes</h2>
+<table><tr><th>Status</th><th>Requests</th></tr>{status_rows}</table>
+</body>
+</html>
+"#,
+            name = escape_html(site_name),
+            requests = self.requests,
+            page_rows = count_rows(&self.page_views),
+            referrer_rows = count_rows(&self.referrers),
+        )
+    }
+}
+
+/// Read an access log, decompressing rotated `.gz` logs
+///
+/// # Arguments
+///
+/// * `path` - Path to the log
+///
+/// # Returns
+///
+/// The contents of the log
+pub fn read_log(path: &Path) -> Result<String> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read log {}", path.display()))?;
+    if path.extension().and_then(|ext| ext.to_str()) != Some("gz") {
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    let mut log = Vec::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut log)
+        .with_context(|| format!("Failed to decompress log {}", path.display()))?;
+    Ok(String::from_utf8_lossy(&log).into_owned())
+}
+
+/// The host of a URL, e.g. of a referrer or the site's `base_url`
+pub fn url_host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then_some(host)
+}
+
+/// Table rows of the most common keys, most common first
+fn count_rows(counts: &OrdMap<String, u64>) -> String {
+    let mut rows = counts.iter().collect::<Vec<_>>();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    rows.iter()
+        .take(REPORT_ROWS)
+        .map(|(key, count)| format!("<tr><td>{}</td><td>{count}</td></tr>", escape_html(key)))
+        .collect()
+}
+
+fn log_line_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"^\S+ \S+ \S+ \[([^\]]+)\] "(\S+) (\S+)[^"]*" (\d{3}) (?:\d+|-)(?: "((?:[^"\\]|\\.)*)" "((?:[^"\\]|\\.)*)")?"#,
+        )
+        .expect("valid log line regex")
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = r#"1.2.3.4 - - [16/Oct/2026:09:00:00 +0200] "GET / HTTP/1.1" 200 5120 "https://www.google.com/" "Mozilla/5.0"
+1.2.3.4 - - [16/Oct/2026:09:00:01 +0200] "GET /css/main.min.css HTTP/1.1" 200 900 "https://cv.example.com/" "Mozilla/5.0"
+1.2.3.4 - - [16/Oct/2026:09:00:05 +0200] "GET /cv.html?utm_source=x HTTP/1.1" 200 8000 "https://cv.example.com/" "Mozilla/5.0"
+5.6.7.8 - - [17/Oct/2026:10:00:00 +0200] "GET /index.html HTTP/1.1" 200 5120 "-" "Googlebot/2.1"
+5.6.7.8 - - [17/Oct/2026:10:00:02 +0200] "GET /missing.html HTTP/1.1" 404 120
+garbage
+"#;
+
+    #[test]
+    fn test_ingest() {
+        let mut stats = AccessStats::default();
+        let summary = stats.ingest(LOG, Some("cv.example.com"));
+
+        assert_eq!(summary.requests, 5);
+        assert_eq!(summary.page_views, 2);
+        assert_eq!(summary.unparsed, 1);
+        assert_eq!(stats.page_views.get("/"), Some(&1));
+        assert_eq!(stats.page_views.get("/cv.html"), Some(&1));
+        assert_eq!(
+            stats.referrers,
+            OrdMap::unit("www.google.com".to_string(), 1)
+        );
+        assert_eq!(stats.status_codes.get(&404), Some(&1));
+        assert_eq!(
+            stats
+                .daily_views
+                .get(&NaiveDate::from_ymd_opt(2026, 10, 16).unwrap()),
+            Some(&2)
+        );
+
+        // Ingesting the same log again adds nothing
+        let again = stats.ingest(LOG, Some("cv.example.com"));
+        assert_eq!(again.requests, 0);
+        assert_eq!(again.already_ingested, 5);
+        assert_eq!(stats.requests, 5);
+
+        let report = stats.report_html("Ada <Lovelace>");
+        assert!(report.contains("Ada &lt;Lovelace&gt;"));
+        assert!(report.contains("<tr><td>www.google.com</td><td>1</td></tr>"));
+    }
+}
@@ -0,0 +1,517 @@
+//! Admin operations run on demand
+//!
+//! Parts of the output that depend on outside data can be refreshed without a
+//! full rebuild, from `cv admin <operation>` or, behind the `admin_token`
+//! secret, with `POST` [`ADMIN_PATH`]`/<operation>` on `cv serve`:
+//!
+//! - `feeds`: Regenerate the blog RSS feed (of every language) in the output
+//!   directory from the posts as they are now, with the CV prepared like the
+//!   build prepares it ([`crate::site_cv`]) from the cached GitHub data
+//! - `warm-cache`: Revalidate every GitHub cache entry the build uses, so the
+//!   next build doesn't wait for the API
+//!
+//! Each operation reports its steps with their timing as they complete, and
+//! the whole run as an [`OperationReport`].
+//!
+//! The site is static, so there is no search index, related-posts table or
+//! render cache to rebuild: search, related posts and pages are all produced
+//! by the build.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Instant;
+
+use crate::content_store::ContentStore;
+use crate::cv_data::Cv;
+use crate::data_sources::{load_data_sources, DATA_SOURCES_MANIFEST};
+use crate::db::{CvSource, Database};
+use crate::github::GitHubClient;
+use crate::github_cache::{GitHubCache, GITHUB_CACHE_PATH};
+use crate::html_generator::generate_rss_feed;
+use crate::package_registry::{merge_packages, RegistryClient};
+use crate::site_config::SiteConfig;
+use crate::site_cv;
+use crate::unified_config::{AppConfig, GITHUB_CACHE_REFRESH_STRATEGY_KEY, OFFLINE_KEY};
+
+/// Path prefix of the admin API on the dev server
+pub const ADMIN_PATH: &str = "/api/admin";
+
+/// Name of the secret holding the bearer token of the admin API
+pub const ADMIN_TOKEN_KEY: &str = "admin_token";
+
+/// Flag file in the data directory that puts the server in maintenance mode
+pub const MAINTENANCE_FILE: &str = "maintenance";
+
+/// An operation that can be run on demand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminOperation {
+    /// Regenerate the RSS feeds
+    Feeds,
+    /// Revalidate the GitHub cache
+    WarmCache,
+}
+
+impl AdminOperation {
+    /// All operations, in the order they are listed
+    pub const ALL: [AdminOperation; 2] = [AdminOperation::Feeds, AdminOperation::WarmCache];
+
+    /// Name of the operation on the command line and in the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminOperation::Feeds => "feeds",
+            AdminOperation::WarmCache => "warm-cache",
+        }
+    }
+}
+
+impl fmt::Display for AdminOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AdminOperation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match AdminOperation::ALL
+            .into_iter()
+            .find(|operation| operation.as_str() == s)
+        {
+            Some(operation) => Ok(operation),
+            None => bail!(
+                "Unknown admin operation '{}', expected one of: {}",
+                s,
+                AdminOperation::ALL
+                    .map(|operation| operation.as_str())
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// A completed step of an operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepReport {
+    /// What the step did
+    pub name: String,
+    /// How long it took, in milliseconds
+    pub millis: u128,
+    /// What came of it, e.g. the file written
+    pub detail: String,
+}
+
+/// A completed operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OperationReport {
+    /// Name of the operation
+    pub operation: String,
+    /// Its steps, in the order they ran
+    pub steps: Vec<StepReport>,
+    /// How long the whole operation took, in milliseconds
+    pub total_millis: u128,
+}
+
+/// Addresses allowed to reach the admin API, or trusted as proxies
+///
+/// Entries are single addresses or CIDR ranges, IPv4 or IPv6, such as
+/// `127.0.0.1` or `10.0.0.0/8`. An empty allowlist allows no address; allow
+/// every one explicitly with `0.0.0.0/0` and `::/0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpAllowlist {
+    ranges: Vec<(IpAddr, u8)>,
+}
+
+impl IpAllowlist {
+    /// Parse the configured entries
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Addresses and CIDR ranges
+    ///
+    /// # Returns
+    ///
+    /// The allowlist, or an error naming the first invalid entry
+    pub fn parse<S: AsRef<str>>(entries: impl IntoIterator<Item = S>) -> Result<Self> {
+        let ranges = entries
+            .into_iter()
+            .map(|entry| {
+                let entry = entry.as_ref().trim();
+                let (address, prefix) = entry.split_once('/').unwrap_or((entry, ""));
+                let address: IpAddr = address
+                    .parse()
+                    .with_context(|| format!("Invalid address: {entry}"))?;
+                let max = if address.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    "" => max,
+                    prefix => prefix
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|prefix| *prefix <= max)
+                        .with_context(|| format!("Invalid prefix: {entry}"))?,
+                };
+                Ok((address, prefix))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(IpAllowlist { ranges })
+    }
+
+    /// Whether the allowlist has any entries
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Whether an address may reach the admin API
+    pub fn allows(&self, address: IpAddr) -> bool {
+        // An IPv4 client behind an IPv6 socket is matched as IPv4
+        let address = match address {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(address, IpAddr::V4),
+            v4 => v4,
+        };
+        self.ranges
+            .iter()
+            .any(|(network, prefix)| match (network, address) {
+                (IpAddr::V4(network), IpAddr::V4(address)) => {
+                    let mask = u32::MAX.checked_shl(32 - u32::from(*prefix)).unwrap_or(0);
+                    u32::from(*network) & mask == u32::from(address) & mask
+                }
+                (IpAddr::V6(network), IpAddr::V6(address)) => {
+                    let mask = u128::MAX.checked_shl(128 - u32::from(*prefix)).unwrap_or(0);
+                    u128::from(*network) & mask == u128::from(address) & mask
+                }
+                _ => false,
+            })
+    }
+}
+
+/// Whether maintenance mode is on
+///
+/// # Arguments
+///
+/// * `flag` - The [`MAINTENANCE_FILE`] flag
+pub fn maintenance_enabled(flag: &Path) -> bool {
+    flag.exists()
+}
+
+/// Switch maintenance mode on or off
+///
+/// The flag is checked on every request, so a running server switches
+/// without a restart.
+///
+/// # Arguments
+///
+/// * `flag` - The [`MAINTENANCE_FILE`] flag
+/// * `enabled` - Whether public APIs should answer `503`
+///
+/// # Returns
+///
+/// A Result indicating whether the flag was written or removed
+pub fn set_maintenance(flag: &Path, enabled: bool) -> Result<()> {
+    if enabled {
+        if let Some(parent) = flag.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(flag, chrono::Utc::now().to_rfc3339())
+            .with_context(|| format!("Failed to write {}", flag.display()))
+    } else {
+        match fs::remove_file(flag) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove {}", flag.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Steps of a running operation, passed on to a progress callback
+struct Steps<'a> {
+    steps: Vec<StepReport>,
+    progress: &'a mut dyn FnMut(&StepReport),
+}
+
+impl Steps<'_> {
+    /// Run and time one step
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - What the step does
+    /// * `step` - The step, returning its detail
+    ///
+    /// # Returns
+    ///
+    /// The step's error, if it failed
+    fn run<F>(&mut self, name: &str, step: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<String>,
+    {
+        let start = Instant::now();
+        let detail = step().with_context(|| format!("{} failed", name))?;
+        let report = StepReport {
+            name: name.to_string(),
+            millis: start.elapsed().as_millis(),
+            detail,
+        };
+        (self.progress)(&report);
+        self.steps.push(report);
+        Ok(())
+    }
+}
+
+/// Run an operation with the configuration in the working directory
+///
+/// # Arguments
+///
+/// * `operation` - The operation
+/// * `progress` - Called with each step as it completes
+///
+/// # Returns
+///
+/// The report of the operation, or the error of the step that failed
+pub fn run(
+    operation: AdminOperation,
+    progress: &mut dyn FnMut(&StepReport),
+) -> Result<OperationReport> {
+    let start = Instant::now();
+    let mut steps = Steps {
+        steps: Vec::new(),
+        progress,
+    };
+
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    // CV data, site configuration and content the build would load
+    if !config.data_sources.is_empty() {
+        steps.run("Load data sources", || {
+            let loaded = load_data_sources(
+                &config.data_sources,
+                Path::new(DATA_SOURCES_MANIFEST),
+                config.is_offline(),
+                false,
+            )?;
+            Ok(format!("{} sources", loaded))
+        })?;
+    }
+    let mut cv = None;
+    steps.run("Load CV data", || {
+        let loaded = load_cv(&config)?;
+        let detail = loaded.personal_info.name.clone();
+        cv = Some(loaded);
+        Ok(detail)
+    })?;
+    let cv = cv.context("CV data was not loaded")?;
+
+    match operation {
+        AdminOperation::Feeds => regenerate_feeds(&cv, &config, &mut steps)?,
+        AdminOperation::WarmCache => warm_cache(&cv, &config, &mut steps)?,
+    }
+
+    Ok(OperationReport {
+        operation: operation.to_string(),
+        steps: steps.steps,
+        total_millis: start.elapsed().as_millis(),
+    })
+}
+
+/// Load the CV from where the build reads it
+fn load_cv(config: &AppConfig) -> Result<Cv> {
+    match config.cv_source()? {
+        CvSource::Json => Cv::from_json(&config.data_path.to_string_lossy()),
+        CvSource::Db => Database::open(config.db_path()).and_then(|db| db.load_cv()),
+    }
+}
+
+/// Write the RSS feed of every language into the output directory
+///
+/// The CV is prepared like the build prepares it, from the cached GitHub data,
+/// so the feeds match the ones the build writes.
+fn regenerate_feeds(cv: &Cv, config: &AppConfig, steps: &mut Steps) -> Result<()> {
+    let site_config = SiteConfig::from_json("config/site.json")?;
+    if !site_config.has_rss_feed() {
+        bail!("The RSS feed is not enabled in config/site.json");
+    }
+    if site_config.base_url.is_none() {
+        bail!("The RSS feed needs base_url in config/site.json for absolute links");
+    }
+    let site_config = site_cv::with_site_data(site_config, config)?;
+    let public_data = config.public_data_config()?;
+
+    let mut cv = cv.clone();
+    steps.run("Prepare CV", || {
+        // Like an offline build: GitHub projects and packages from the cache only
+        let mut cache = GitHubCache::load_or_default(GITHUB_CACHE_PATH);
+        let client = GitHubClient::from_config(&config.clone().with_option(OFFLINE_KEY, "true"));
+        let github = client.fetch_projects_from_sources_cached(&cv.github_sources, &mut cache);
+        if let Ok(ref projects) = github {
+            cv.projects = site_cv::merge_github_projects(&cv, projects);
+        }
+        if !cv.registry_sources.is_empty() {
+            let packages = RegistryClient::new()
+                .with_offline(true)
+                .fetch_packages_cached(&cv.registry_sources, &mut cache);
+            cv.projects = merge_packages(&cv.projects, &packages);
+        }
+        site_cv::add_testimonials(&mut cv, config)?;
+        cv.projects = site_cv::curate_projects(&cv.projects, &site_config);
+        Ok(match github {
+            Ok(_) => format!("{} projects", cv.projects.len()),
+            Err(_) => format!(
+                "{} projects, GitHub projects aren't cached",
+                cv.projects.len()
+            ),
+        })
+    })?;
+
+    let targets = if site_config.is_multilingual() {
+        site_config
+            .languages
+            .iter()
+            .map(|language| {
+                (
+                    Some(language.as_str()),
+                    site_config.for_language(language),
+                    config.output_dir.join(language),
+                )
+            })
+            .collect::<Vec<_>>()
+    } else {
+        vec![(None, site_config.clone(), config.output_dir.clone())]
+    };
+
+    for (language, site_config, output_dir) in targets {
+        let feed_path = output_dir.join("feed.xml");
+        steps.run(&format!("Generate {}", feed_path.display()), || {
+            let cv = site_cv::publishable_cv(&cv, config, &site_config, language, &public_data)?;
+            let store = ContentStore::load(&cv, &site_config)?;
+            std::fs::create_dir_all(&output_dir)?;
+            generate_rss_feed(store.cv(), &site_config, store.posts(), &feed_path)?;
+            Ok(format!("{} posts", store.posts().len()))
+        })?;
+    }
+    Ok(())
+}
+
+/// Revalidate the GitHub data the build fetches and save it to the cache
+fn warm_cache(cv: &Cv, config: &AppConfig, steps: &mut Steps) -> Result<()> {
+    if config.is_offline() {
+        bail!("The GitHub cache can't be warmed offline");
+    }
+
+    // The GitHub client runs its requests on the current tokio runtime
+    let runtime = tokio::runtime::Handle::try_current()
+        .is_err()
+        .then(tokio::runtime::Runtime::new)
+        .transpose()?;
+    let _guard = runtime.as_ref().map(|runtime| runtime.enter());
+
+    // Eager refresh revalidates every entry that has expired
+    let client = GitHubClient::from_config(
+        &config
+            .clone()
+            .with_option(GITHUB_CACHE_REFRESH_STRATEGY_KEY, "eager"),
+    );
+    let mut cache = GitHubCache::load_or_default(GITHUB_CACHE_PATH);
+    cache.begin_build();
+
+    steps.run("Fetch GitHub projects", || {
+        let projects = client.fetch_projects_from_sources_cached(&cv.github_sources, &mut cache)?;
+        Ok(format!("{} projects", projects.len()))
+    })?;
+    if let Some(username) = cv
+        .github_sources
+        .iter()
+        .find_map(|source| source.username.as_ref())
+    {
+        steps.run("Fetch GitHub avatar", || {
+            client.fetch_avatar_cached(username, &mut cache)
+        })?;
+    }
+    steps.run("Fetch GitHub stats", || {
+        let stats = client.fetch_stats_from_sources_cached(&cv.github_sources, &mut cache)?;
+        Ok(if stats.is_some() {
+            "fetched".to_string()
+        } else {
+            "no source asks for stats".to_string()
+        })
+    })?;
+    steps.run("Save GitHub cache", || {
+        let expired = cache.cleanup_expired();
+        let counts = cache.usage.last_build;
+        cache.save(GITHUB_CACHE_PATH)?;
+        Ok(format!(
+            "{} hits, {} fetched, {} not modified, {} expired removed",
+            counts.hits, counts.misses, counts.not_modified, expired
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operation_names() {
+        for operation in AdminOperation::ALL {
+            assert_eq!(
+                operation.as_str().parse::<AdminOperation>().unwrap(),
+                operation
+            );
+        }
+        let error = "reindex".parse::<AdminOperation>().unwrap_err();
+        assert!(error.to_string().contains("feeds, warm-cache"));
+    }
+
+    #[test]
+    fn test_ip_allowlist() {
+        let allowlist = IpAllowlist::parse(["127.0.0.1", "10.0.0.0/8", "2001:db8::/32"]).unwrap();
+        let allows = |address: &str| allowlist.allows(address.parse().unwrap());
+        assert!(allows("127.0.0.1"));
+        assert!(allows("10.20.30.40"));
+        assert!(allows("::ffff:10.1.2.3"));
+        assert!(allows("2001:db8::1"));
+        assert!(!allows("127.0.0.2"));
+        assert!(!allows("192.168.1.1"));
+        assert!(!allows("2001:db9::1"));
+
+        assert!(!IpAllowlist::default().allows("127.0.0.1".parse().unwrap()));
+        assert!(IpAllowlist::parse(["0.0.0.0/0"])
+            .unwrap()
+            .allows("203.0.113.7".parse().unwrap()));
+        assert!(IpAllowlist::parse(["10.0.0.0/33"]).is_err());
+        assert!(IpAllowlist::parse(["localhost"]).is_err());
+    }
+
+    #[test]
+    fn test_maintenance_flag() {
+        let dir = tempfile::tempdir().unwrap();
+        let flag = dir.path().join("data").join(MAINTENANCE_FILE);
+        assert!(!maintenance_enabled(&flag));
+        set_maintenance(&flag, true).unwrap();
+        assert!(maintenance_enabled(&flag));
+        set_maintenance(&flag, false).unwrap();
+        set_maintenance(&flag, false).unwrap();
+        assert!(!maintenance_enabled(&flag));
+    }
+
+    #[test]
+    fn test_steps_report_progress() {
+        let mut seen = Vec::new();
+        let mut progress = |step: &StepReport| seen.push(step.name.clone());
+        let mut steps = Steps {
+            steps: Vec::new(),
+            progress: &mut progress,
+        };
+        steps.run("First", || Ok("done".to_string())).unwrap();
+        let error = steps.run("Second", || bail!("no network")).unwrap_err();
+        assert_eq!(error.to_string(), "Second failed");
+        assert_eq!(steps.steps.len(), 1);
+        assert_eq!(steps.steps[0].detail, "done");
+        drop(steps);
+        assert_eq!(seen, vec!["First"]);
+    }
+}
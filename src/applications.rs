@@ -0,0 +1,569 @@
+//! Job application tracker
+//!
+//! Keeps track of the applications the targeted CVs and cover letters are sent
+//! with: company, role, the variant used, status and when to follow up. They
+//! are stored in the `applications` table of the CV database (`data/cv.db`),
+//! which only holds rows once `cv applications add` is used.
+//!
+//! `cv applications dashboard` writes an overview to `stats/applications.html`
+//! and `cv applications ics` the follow-up dates as reminders to
+//! `stats/follow-ups.ics`; both stay out of the output directory. With the
+//! `applications_token` secret declared, `cv serve` also offers the tracker as
+//! a JSON API on [`APPLICATIONS_PATH`].
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use im::{OrdMap, Vector};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::booking::escape_text;
+use crate::db::Database;
+
+/// Path the dev server serves the applications API on
+pub const APPLICATIONS_PATH: &str = "/api/applications";
+
+/// Secret holding the bearer token the applications API requires
+pub const APPLICATIONS_TOKEN_KEY: &str = "applications_token";
+
+/// Where the dashboard is written
+pub const DASHBOARD_FILE: &str = "stats/applications.html";
+
+/// Where the follow-up reminders are written
+pub const FOLLOW_UPS_FILE: &str = "stats/follow-ups.ics";
+
+/// Columns of the `applications` table, in the order [`from_row`] reads them
+const COLUMNS: &str =
+    "id, company, role, variant, status, applied_on, follow_up, notes, updated_at";
+
+/// Where an application stands
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplicationStatus {
+    /// Not sent yet
+    Planned,
+    /// Sent, waiting for an answer
+    #[default]
+    Applied,
+    /// In the interview process
+    Interviewing,
+    /// An offer was made
+    Offer,
+    /// The offer was accepted
+    Accepted,
+    /// Turned down by the company
+    Rejected,
+    /// Withdrawn by the applicant
+    Withdrawn,
+}
+
+impl ApplicationStatus {
+    /// Every status, in the order of the process
+    pub const ALL: [ApplicationStatus; 7] = [
+        ApplicationStatus::Planned,
+        ApplicationStatus::Applied,
+        ApplicationStatus::Interviewing,
+        ApplicationStatus::Offer,
+        ApplicationStatus::Accepted,
+        ApplicationStatus::Rejected,
+        ApplicationStatus::Withdrawn,
+    ];
+
+    /// Name of the status in the CLI, the API and the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApplicationStatus::Planned => "planned",
+            ApplicationStatus::Applied => "applied",
+            ApplicationStatus::Interviewing => "interviewing",
+            ApplicationStatus::Offer => "offer",
+            ApplicationStatus::Accepted => "accepted",
+            ApplicationStatus::Rejected => "rejected",
+            ApplicationStatus::Withdrawn => "withdrawn",
+        }
+    }
+
+    /// Whether the application still needs following up
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            ApplicationStatus::Planned
+                | ApplicationStatus::Applied
+                | ApplicationStatus::Interviewing
+                | ApplicationStatus::Offer
+        )
+    }
+}
+
+impl fmt::Display for ApplicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ApplicationStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ApplicationStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s.trim().to_lowercase())
+            .with_context(|| {
+                format!(
+                    "Unknown status '{}' (expected {})",
+                    s,
+                    ApplicationStatus::ALL
+                        .map(|status| status.as_str())
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// A tracked application
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Application {
+    pub id: i64,
+    pub company: String,
+    pub role: String,
+    /// CV or cover letter variant sent, e.g. `portfolio` or a cover letter name
+    pub variant: Option<String>,
+    pub status: ApplicationStatus,
+    pub applied_on: Option<NaiveDate>,
+    /// When to follow up, if the application is still open
+    pub follow_up: Option<NaiveDate>,
+    pub notes: Option<String>,
+    /// When the application was last changed, RFC 3339
+    pub updated_at: String,
+}
+
+/// A new application, from the CLI or the API
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewApplication {
+    pub company: String,
+    pub role: String,
+    #[serde(default)]
+    pub variant: Option<String>,
+    #[serde(default)]
+    pub status: ApplicationStatus,
+    #[serde(default)]
+    pub applied_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub follow_up: Option<NaiveDate>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// Changes to an application; fields that are None are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplicationUpdate {
+    #[serde(default)]
+    pub status: Option<ApplicationStatus>,
+    #[serde(default)]
+    pub follow_up: Option<NaiveDate>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+impl Database {
+    /// Track a new application
+    ///
+    /// # Arguments
+    ///
+    /// * `application` - The application
+    ///
+    /// # Returns
+    ///
+    /// The id of the application, or an error if company or role is missing
+    pub fn add_application(&self, application: &NewApplication) -> Result<i64> {
+        if application.company.trim().is_empty() || application.role.trim().is_empty() {
+            anyhow::bail!("An application needs a company and a role");
+        }
+        self.connection
+            .execute(
+                "INSERT INTO applications
+                 (company, role, variant, status, applied_on, follow_up, notes, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    application.company.trim(),
+                    application.role.trim(),
+                    application.variant,
+                    application.status.as_str(),
+                    application.applied_on.map(|date| date.to_string()),
+                    application.follow_up.map(|date| date.to_string()),
+                    application.notes,
+                    Utc::now().to_rfc3339()
+                ],
+            )
+            .context("Failed to store application")?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// All tracked applications, oldest first
+    pub fn applications(&self) -> Result<Vector<Application>> {
+        let mut statement = self
+            .connection
+            .prepare(&format!("SELECT {} FROM applications ORDER BY id", COLUMNS))?;
+        let rows = statement
+            .query_map([], raw_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read applications")?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /// The application with the given id, if there is one
+    pub fn application(&self, id: i64) -> Result<Option<Application>> {
+        self.connection
+            .query_row(
+                &format!("SELECT {} FROM applications WHERE id = ?1", COLUMNS),
+                params![id],
+                raw_row,
+            )
+            .optional()
+            .context("Failed to read application")?
+            .map(from_row)
+            .transpose()
+    }
+
+    /// Change the status, follow-up date or notes of an application
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the application
+    /// * `update` - The changes
+    ///
+    /// # Returns
+    ///
+    /// The updated application, or an error if there is none with the id
+    pub fn update_application(&self, id: i64, update: &ApplicationUpdate) -> Result<Application> {
+        let current = self
+            .application(id)?
+            .with_context(|| format!("No application #{}", id))?;
+        let status = update.status.unwrap_or(current.status);
+        let follow_up = update.follow_up.or(current.follow_up);
+        let notes = update.notes.clone().or(current.notes);
+
+        self.connection
+            .execute(
+                "UPDATE applications SET status = ?1, follow_up = ?2, notes = ?3, updated_at = ?4
+                 WHERE id = ?5",
+                params![
+                    status.as_str(),
+                    follow_up.map(|date| date.to_string()),
+                    notes,
+                    Utc::now().to_rfc3339(),
+                    id
+                ],
+            )
+            .context("Failed to update application")?;
+        self.application(id)?
+            .with_context(|| format!("No application #{}", id))
+    }
+}
+
+/// Columns of a row as stored, before parsing status and dates
+type RawRow = (
+    i64,
+    String,
+    String,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+);
+
+fn raw_row(row: &Row) -> rusqlite::Result<RawRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+    ))
+}
+
+fn from_row(row: RawRow) -> Result<Application> {
+    let (id, company, role, variant, status, applied_on, follow_up, notes, updated_at) = row;
+    let date = |value: Option<String>| {
+        value
+            .map(|date| date.parse::<NaiveDate>())
+            .transpose()
+            .with_context(|| format!("Invalid date in application #{}", id))
+    };
+    Ok(Application {
+        id,
+        company,
+        role,
+        variant,
+        status: status.parse()?,
+        applied_on: date(applied_on)?,
+        follow_up: date(follow_up)?,
+        notes,
+        updated_at,
+    })
+}
+
+/// Render the follow-up dates of open applications as an iCalendar document
+///
+/// Each follow-up is an all-day event with a reminder at 09:00 that day.
+///
+/// # Arguments
+///
+/// * `applications` - Tracked applications
+/// * `now` - Time of the export, for the event stamps
+///
+/// # Returns
+///
+/// The calendar, with CRLF line endings as RFC 5545 requires
+pub fn follow_up_calendar(applications: &Vector<Application>, now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let events = applications
+        .iter()
+        .filter(|application| application.status.is_open())
+        .filter_map(|application| {
+            let date = application.follow_up?;
+            let summary = escape_text(&format!(
+                "Follow up: {} at {}",
+                application.role, application.company
+            ));
+            let description = escape_text(&format!(
+                "Status: {}{}",
+                application.status,
+                application
+                    .notes
+                    .as_ref()
+                    .map(|notes| format!("\n{}", notes))
+                    .unwrap_or_default()
+            ));
+            Some(
+                [
+                    "BEGIN:VEVENT".to_string(),
+                    format!("UID:application-{}@cv-generator", application.id),
+                    format!("DTSTAMP:{stamp}"),
+                    format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+                    format!(
+                        "DTEND;VALUE=DATE:{}",
+                        (date + Duration::days(1)).format("%Y%m%d")
+                    ),
+                    format!("SUMMARY:{summary}"),
+                    format!("DESCRIPTION:{description}"),
+                    "BEGIN:VALARM".to_string(),
+                    "ACTION:DISPLAY".to_string(),
+                    format!("DESCRIPTION:{summary}"),
+                    "TRIGGER:PT9H".to_string(),
+                    "END:VALARM".to_string(),
+                    "END:VEVENT".to_string(),
+                ]
+                .join("\r\n"),
+            )
+        });
+
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//cv-generator//applications//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:Application follow-ups".to_string(),
+    ]
+    .into_iter()
+    .chain(events)
+    .chain(["END:VCALENDAR".to_string()]);
+
+    lines.collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+/// Render the applications as a self-contained HTML page
+///
+/// # Arguments
+///
+/// * `applications` - Tracked applications
+/// * `name` - Name of the CV owner, for the title
+/// * `today` - Date the page is written; follow-ups before it are overdue
+///
+/// # Returns
+///
+/// The dashboard HTML
+pub fn dashboard_html(applications: &Vector<Application>, name: &str, today: NaiveDate) -> String {
+    let counts = applications
+        .iter()
+        .fold(OrdMap::<ApplicationStatus, usize>::new(), |counts, app| {
+            counts.update_with(app.status, 1, |count, one| count + one)
+        });
+    let count_rows = counts
+        .iter()
+        .map(|(status, count)| format!("<tr><td>{status}</td><td>{count}</td></tr>"))
+        .collect::<String>();
+
+    let mut due = applications
+        .iter()
+        .filter(|app| app.status.is_open())
+        .filter_map(|app| app.follow_up.map(|date| (date, app)))
+        .collect::<Vec<_>>();
+    due.sort_by_key(|(date, app)| (*date, app.id));
+    let due_rows = due
+        .iter()
+        .map(|(date, app)| {
+            format!(
+                "<tr{}><td>{date}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                if *date < today {
+                    " class=\"overdue\""
+                } else {
+                    ""
+                },
+                escape_html(&app.company),
+                escape_html(&app.role),
+                app.status
+            )
+        })
+        .collect::<String>();
+
+    let application_rows = applications
+        .iter()
+        .rev()
+        .map(|app| {
+            format!(
+                "<tr><td>#{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                app.id,
+                escape_html(&app.company),
+                escape_html(&app.role),
+                escape_html(app.variant.as_deref().unwrap_or("")),
+                app.status,
+                app.applied_on.map(|date| date.to_string()).unwrap_or_default(),
+                escape_html(app.notes.as_deref().unwrap_or("")),
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<title>Applications - {name}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 64rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 2rem; }}
+td, th {{ padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; }}
+.overdue td {{ color: #c01c28; font-weight: 600; }}
+</style>
+</head>
+<body>
+<h1>Applications - {name}</h1>
+<p>{total} applications, {open} open. Updated {today}.</p>
+<h2>Status</h2>
+<table><tr><th>Status</th><th>Applications</th></tr>{count_rows}</table>
+<h2>Follow-ups</h2>
+<table><tr><th>Date</th><th>Company</th><th>Role</th><th>Status</th></tr>{due_rows}</table>
+<h2>All applications</h2>
+<table><tr><th></th><th>Company</th><th>Role</th><th>Variant</th><th>Status</th><th>Applied</th><th>Notes</th></tr>{application_rows}</table>
+</body>
+</html>
+"#,
+        name = escape_html(name),
+        total = applications.len(),
+        open = applications
+            .iter()
+            .filter(|app| app.status.is_open())
+            .count(),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(text: &str) -> NaiveDate {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_track_applications() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("cv.db")).unwrap();
+        assert!(db.applications().unwrap().is_empty());
+
+        let id = db
+            .add_application(&NewApplication {
+                company: "Acme".to_string(),
+                role: "Engineer".to_string(),
+                variant: Some("portfolio".to_string()),
+                applied_on: Some(date("2026-10-01")),
+                follow_up: Some(date("2026-10-15")),
+                ..NewApplication::default()
+            })
+            .unwrap();
+        assert!(db.add_application(&NewApplication::default()).is_err());
+
+        let updated = db
+            .update_application(
+                id,
+                &ApplicationUpdate {
+                    status: Some(ApplicationStatus::Interviewing),
+                    notes: Some("Call with the CTO".to_string()),
+                    ..ApplicationUpdate::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.status, ApplicationStatus::Interviewing);
+        assert_eq!(updated.follow_up, Some(date("2026-10-15")));
+        assert_eq!(db.applications().unwrap(), im::vector![updated]);
+        assert!(db
+            .update_application(id + 1, &ApplicationUpdate::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_follow_up_calendar() {
+        let application = |id, status, follow_up: Option<&str>| Application {
+            id,
+            company: "Acme, Inc".to_string(),
+            role: "Engineer".to_string(),
+            variant: None,
+            status,
+            applied_on: None,
+            follow_up: follow_up.map(date),
+            notes: None,
+            updated_at: String::new(),
+        };
+        let applications = im::vector![
+            application(1, ApplicationStatus::Applied, Some("2026-10-20")),
+            application(2, ApplicationStatus::Rejected, Some("2026-10-21")),
+            application(3, ApplicationStatus::Offer, None),
+        ];
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 8, 0, 0).unwrap();
+
+        let calendar = follow_up_calendar(&applications, now);
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        assert!(calendar.contains("UID:application-1@cv-generator\r\n"));
+        assert!(calendar.contains("DTSTART;VALUE=DATE:20261020\r\nDTEND;VALUE=DATE:20261021\r\n"));
+        assert!(calendar.contains("SUMMARY:Follow up: Engineer at Acme\\, Inc\r\n"));
+
+        let dashboard = dashboard_html(&applications, "Ada", date("2026-10-21"));
+        assert!(dashboard.contains("3 applications, 2 open"));
+        assert!(dashboard.contains("<tr class=\"overdue\"><td>2026-10-20</td>"));
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(
+            "Interviewing".parse::<ApplicationStatus>().unwrap(),
+            ApplicationStatus::Interviewing
+        );
+        assert!("ghosted".parse::<ApplicationStatus>().is_err());
+    }
+}
@@ -119,7 +119,7 @@ fn main() -> Result<()> {
     });
 
     let typst_config = site_config
-        .get_typst_config()
+        .pdf_typst_config()
         .context("Failed to get Typst configuration")?;
 
     // Generate output path next to input file
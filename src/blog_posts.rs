@@ -2,11 +2,16 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use gray_matter::Matter;
 use im::Vector;
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd, TextMergeStream};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 
+use crate::charts::render_charts;
+use crate::citations::{cite, load_references, render_bibliography, Reference};
+use crate::front_matter::CustomFields;
+
 /// Represents a blog post with front matter metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlogPost {
@@ -28,6 +33,43 @@ pub struct BlogPost {
     pub published: Option<bool>,
     /// Author name (optional, defaults to CV name)
     pub author: Option<String>,
+    /// Whether the post is featured on the home page
+    #[serde(default)]
+    pub featured: bool,
+    /// Whether the post is pinned to the top of the blog and home page
+    #[serde(default)]
+    pub pinned: bool,
+    /// Whether the post shows a reading progress bar and section links
+    #[serde(default)]
+    pub reading_progress: bool,
+    /// Headings with anchors, only collected when `reading_progress` is on
+    #[serde(default)]
+    pub sections: Vector<PostSection>,
+    /// Date of the last revision, from the front matter
+    #[serde(default)]
+    pub updated: Option<DateTime<Utc>>,
+    /// Date after which the post should be reviewed
+    #[serde(default)]
+    pub review_after: Option<DateTime<Utc>>,
+    /// Whether the post shows an "out of date" notice (set per build)
+    #[serde(default)]
+    pub stale: bool,
+    /// Front matter fields that aren't built in, for templates
+    #[serde(default)]
+    pub fields: CustomFields,
+}
+
+/// A heading of a post that can be linked to directly
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostSection {
+    /// Anchor id of the heading
+    pub id: String,
+    /// Heading text
+    pub title: String,
+    /// Heading level (1-6)
+    pub level: u8,
+    /// Estimated reading time in minutes before the heading
+    pub minute: u32,
 }
 
 /// Front matter structure for blog posts
@@ -40,6 +82,17 @@ struct BlogFrontMatter {
     reading_time: Option<u32>,
     published: Option<bool>,
     author: Option<String>,
+    featured: Option<bool>,
+    pinned: Option<bool>,
+    reading_progress: Option<bool>,
+    updated: Option<String>,
+    review_after: Option<String>,
+    #[serde(default)]
+    references: Vector<Reference>,
+    bibliography: Option<String>,
+    nocite: Option<String>,
+    #[serde(flatten)]
+    fields: CustomFields,
 }
 
 impl BlogPost {
@@ -62,7 +115,7 @@ impl BlogPost {
             .unwrap_or("post")
             .to_string();
 
-        Self::from_markdown(&content, slug)
+        Self::parse(&content, slug, path.parent())
     }
 
     /// Parse markdown content with front matter
@@ -76,6 +129,11 @@ impl BlogPost {
     ///
     /// Result containing the parsed BlogPost or an error
     pub fn from_markdown(content: &str, slug: String) -> Result<Self> {
+        Self::parse(content, slug, None)
+    }
+
+    /// Parse markdown content, resolving the bibliography file against `base_dir`
+    fn parse(content: &str, slug: String, base_dir: Option<&Path>) -> Result<Self> {
         let matter = Matter::<gray_matter::engine::YAML>::new();
         let parsed = matter
             .parse::<BlogFrontMatter>(content)
@@ -84,15 +142,19 @@ impl BlogPost {
         // Extract front matter, returning an error if it's missing
         let front_matter = parsed.data.context("Blog post requires front matter")?;
 
-        // Parse date
-        let date = DateTime::parse_from_rfc3339(&front_matter.date)
-            .or_else(|_| {
-                // Try parsing as date only (YYYY-MM-DD)
-                let date_str = format!("{}T00:00:00Z", front_matter.date);
-                DateTime::parse_from_rfc3339(&date_str)
-            })
-            .context("Failed to parse blog post date")?
-            .with_timezone(&Utc);
+        let date = parse_date(&front_matter.date).context("Failed to parse blog post date")?;
+        let updated = front_matter
+            .updated
+            .as_deref()
+            .map(parse_date)
+            .transpose()
+            .context("Failed to parse blog post updated date")?;
+        let review_after = front_matter
+            .review_after
+            .as_deref()
+            .map(parse_date)
+            .transpose()
+            .context("Failed to parse blog post review_after date")?;
 
         // Convert markdown to HTML
         let mut options = Options::empty();
@@ -102,9 +164,30 @@ impl BlogPost {
         options.insert(Options::ENABLE_SMART_PUNCTUATION);
         options.insert(Options::ENABLE_HEADING_ATTRIBUTES);
 
-        let parser = Parser::new_ext(&parsed.content, options);
+        let references = load_references(
+            front_matter.references,
+            front_matter.bibliography.as_deref(),
+            base_dir,
+        )?;
+
+        let events: Vec<Event> =
+            TextMergeStream::new(Parser::new_ext(&parsed.content, options)).collect();
+        let (events, cited) = if references.is_empty() {
+            (events, Vector::new())
+        } else {
+            cite(events, &references, front_matter.nocite.as_deref())
+        };
+        let events = render_charts(events)?;
+        let reading_progress = front_matter.reading_progress.unwrap_or(false);
+        let (events, sections) = if reading_progress {
+            anchor_headings(events)
+        } else {
+            (events, Vector::new())
+        };
+
         let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
+        html::push_html(&mut html_output, events.into_iter());
+        html_output.push_str(&render_bibliography(&cited, false));
 
         // Calculate reading time if not provided
         let reading_time = front_matter.reading_time.or_else(|| {
@@ -126,6 +209,14 @@ impl BlogPost {
             reading_time,
             published: front_matter.published,
             author: front_matter.author,
+            featured: front_matter.featured.unwrap_or(false),
+            pinned: front_matter.pinned.unwrap_or(false),
+            reading_progress,
+            sections,
+            updated,
+            review_after,
+            stale: false,
+            fields: front_matter.fields,
         })
     }
 
@@ -135,6 +226,133 @@ impl BlogPost {
     }
 }
 
+/// Parse a front matter date, either RFC 3339 or `YYYY-MM-DD`
+///
+/// # Arguments
+///
+/// * `value` - The date as written in the front matter
+///
+/// # Returns
+///
+/// The date in UTC; a date without a time is midnight
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", value)))
+        .with_context(|| format!("Invalid date '{}'", value))
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Give every heading an anchor id and an estimated reading position
+///
+/// Headings keep an explicit `{#id}` if they have one. Each heading gets a
+/// `data-minute` attribute with the reading time before it, and a `#` link
+/// to itself for sharing.
+///
+/// # Arguments
+///
+/// * `events` - Markdown events of the post
+///
+/// # Returns
+///
+/// The updated events and the headings found
+fn anchor_headings(events: Vec<Event<'_>>) -> (Vec<Event<'_>>, Vector<PostSection>) {
+    let mut output = Vec::with_capacity(events.len());
+    let mut sections = Vector::new();
+    let mut used_ids = HashSet::new();
+    let mut words = 0;
+    // Index of the open heading's start event in `output`, and its text
+    let mut heading: Option<(usize, String)> = None;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                heading = Some((output.len(), String::new()));
+                output.push(event);
+            }
+            Event::Text(ref text) | Event::Code(ref text) => {
+                match heading.as_mut() {
+                    Some((_, title)) => title.push_str(text),
+                    None => words += text.split_whitespace().count(),
+                }
+                output.push(event);
+            }
+            Event::End(TagEnd::Heading(_)) if heading.is_some() => {
+                let (start, title) = heading.take().unwrap_or_default();
+                let minute = (words / 200) as u32;
+
+                if let Event::Start(Tag::Heading {
+                    level,
+                    id,
+                    classes,
+                    mut attrs,
+                }) = std::mem::replace(&mut output[start], Event::SoftBreak)
+                {
+                    let id = id
+                        .map(|id| id.to_string())
+                        .unwrap_or_else(|| unique_id(&title, &mut used_ids));
+                    attrs.push(("data-minute".into(), Some(minute.to_string().into())));
+
+                    output.push(Event::InlineHtml(CowStr::from(format!(
+                        r##" <a class="heading-anchor" href="#{}" aria-label="Link to this section">#</a>"##,
+                        escape_attribute(&id)
+                    ))));
+                    sections.push_back(PostSection {
+                        id: id.clone(),
+                        title: title.trim().to_string(),
+                        level: level as u8,
+                        minute,
+                    });
+                    output[start] = Event::Start(Tag::Heading {
+                        level,
+                        id: Some(id.into()),
+                        classes,
+                        attrs,
+                    });
+                }
+                output.push(event);
+            }
+            other => output.push(other),
+        }
+    }
+
+    (output, sections)
+}
+
+/// Turn heading text into an id that isn't used yet (`intro`, `intro-2`, ...)
+fn unique_id(title: &str, used_ids: &mut HashSet<String>) -> String {
+    let slug = title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() {
+        "section".to_string()
+    } else {
+        slug
+    };
+
+    let id = (1..)
+        .map(|n| {
+            if n == 1 {
+                slug.clone()
+            } else {
+                format!("{}-{}", slug, n)
+            }
+        })
+        .find(|id| !used_ids.contains(id))
+        .unwrap_or(slug);
+    used_ids.insert(id.clone());
+    id
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
+
 /// Loads all blog posts from a directory
 ///
 /// # Arguments
@@ -177,9 +395,9 @@ pub fn load_posts_from_directory(dir_path: &Path) -> Result<Vector<BlogPost>> {
         }
     }
 
-    // Sort posts by date (newest first)
+    // Sort posts pinned first, then by date (newest first)
     let mut posts_vec: Vec<_> = posts.into_iter().collect();
-    posts_vec.sort_by(|a, b| b.date.cmp(&a.date));
+    posts_vec.sort_by_key(|b| (std::cmp::Reverse(b.pinned), std::cmp::Reverse(b.date)));
 
     Ok(posts_vec.into_iter().collect())
 }
@@ -273,4 +491,114 @@ This is a draft post."#;
 
         assert!(!post.is_published());
     }
+
+    #[test]
+    fn test_featured_and_pinned_post() {
+        let content = r#"---
+title: "Announcement"
+date: "2024-02-01"
+featured: true
+pinned: true
+---
+
+Read this first."#;
+
+        let post = BlogPost::from_markdown(content, "announcement".to_string()).unwrap();
+        assert!(post.featured);
+        assert!(post.pinned);
+
+        let post =
+            BlogPost::from_markdown("---\ntitle: A\ndate: 2024-01-01\n---\nText", "a".into())
+                .unwrap();
+        assert!(!post.featured);
+        assert!(!post.pinned);
+    }
+
+    #[test]
+    fn test_custom_front_matter_fields() {
+        let content = r#"---
+title: "Part two"
+date: 2024-03-01
+reading_time: 4
+tags: [rust]
+series: "Rust in production"
+part: 2
+---
+
+Text"#;
+
+        let post = BlogPost::from_markdown(content, "part-two".to_string()).unwrap();
+        assert_eq!(post.reading_time, Some(4));
+        assert_eq!(post.tags.len(), 1);
+        assert_eq!(post.fields.text("series").unwrap(), "Rust in production");
+        assert_eq!(post.fields.text("part").unwrap(), "2");
+        assert!(post.fields.get("title").is_none());
+    }
+
+    #[test]
+    fn test_reading_progress_anchors() {
+        let words = "word ".repeat(450);
+        let content = format!(
+            "---\ntitle: Long\ndate: 2024-03-01\nreading_progress: true\n---\n\n## Getting `started`\n\n{words}\n\n## Details {{#custom}}\n\nMore.\n\n## Getting started\n"
+        );
+
+        let post = BlogPost::from_markdown(&content, "long".to_string()).unwrap();
+
+        assert!(post.reading_progress);
+        let ids: Vec<_> = post.sections.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["getting-started", "custom", "getting-started-2"]);
+        assert_eq!(post.sections[0].title, "Getting started");
+        assert_eq!(post.sections[1].minute, 2);
+        assert!(post.content.contains(r#"<h2 id="custom" data-minute="2">"#));
+        assert!(post.content.contains(r##"href="#getting-started""##));
+
+        // Opt-in only
+        let plain = BlogPost::from_markdown(
+            "---\ntitle: A\ndate: 2024-01-01\n---\n## Heading",
+            "a".into(),
+        )
+        .unwrap();
+        assert!(plain.sections.is_empty());
+        assert!(plain.content.contains("<h2>Heading</h2>"));
+    }
+
+    #[test]
+    fn test_citations() {
+        let content = r#"---
+title: "Cited"
+date: 2024-01-01
+references:
+  - key: lamport94
+    author: Leslie Lamport
+    title: "LaTeX: A Document Preparation System"
+    publisher: Addison-Wesley
+    year: 1994
+  - key: knuth84
+    author: Knuth, Donald E.
+    title: Literate Programming
+    year: "1984"
+---
+Literate programming [@knuth84] predates LaTeX [@lamport94; @knuth84], see [@missing].
+
+```
+[@knuth84]
+```
+"#;
+        let post = BlogPost::from_markdown(content, "cited".to_string()).unwrap();
+
+        assert!(post.content.contains(
+            r##"Literate programming (<a class="citation" href="#ref-knuth84">Knuth, 1984</a>) predates"##
+        ));
+        assert!(post.content.contains(
+            r##"(<a class="citation" href="#ref-lamport94">Lamport, 1994</a>; <a class="citation" href="#ref-knuth84">Knuth, 1984</a>)"##
+        ));
+        assert!(post.content.contains("see [@missing]."));
+        assert!(post.content.contains("<code>[@knuth84]\n</code>"));
+
+        // Cited works, alphabetically
+        let knuth = post.content.find(r#"<li id="ref-knuth84">"#).unwrap();
+        let lamport = post.content.find(r#"<li id="ref-lamport94">"#).unwrap();
+        assert!(post.content.contains("<h2>References</h2>"));
+        assert!(knuth < lamport);
+    }
 }
@@ -0,0 +1,138 @@
+//! Availability calendar for the "book a call" section
+//!
+//! The weekly availability from the `booking` site config is published as an
+//! iCalendar file with one recurring event per slot, so visitors without an
+//! account at the scheduling provider can still see when a call fits.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, Utc};
+use std::fs;
+use std::path::Path;
+
+use crate::site_config::{AvailabilitySlot, BookingConfig};
+
+/// File name of the availability calendar in the output directory
+pub const AVAILABILITY_FILE: &str = "availability.ics";
+
+/// Render the availability as an iCalendar document
+///
+/// # Arguments
+///
+/// * `config` - Booking configuration with the weekly slots
+/// * `name` - Name of the CV owner, for the event titles
+/// * `now` - Time of the build; the first events are in the week starting then
+///
+/// # Returns
+///
+/// The calendar, with CRLF line endings as RFC 5545 requires
+pub fn availability_calendar(config: &BookingConfig, name: &str, now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let summary = escape_text(&format!("Available for a call with {name}"));
+
+    let events = config.availability.iter().map(|slot| {
+        let date = first_occurrence(slot, now.date_naive());
+        let weekday = slot.day.to_string()[..2].to_uppercase();
+        [
+            "BEGIN:VEVENT".to_string(),
+            format!(
+                "UID:availability-{}-{}@cv-generator",
+                weekday.to_lowercase(),
+                slot.start.format("%H%M")
+            ),
+            format!("DTSTAMP:{stamp}"),
+            date_time_property("DTSTART", date, slot.start, config.timezone()),
+            date_time_property("DTEND", date, slot.end, config.timezone()),
+            format!("RRULE:FREQ=WEEKLY;BYDAY={weekday}"),
+            format!("SUMMARY:{summary}"),
+            "TRANSP:TRANSPARENT".to_string(),
+            "END:VEVENT".to_string(),
+        ]
+        .join("\r\n")
+    });
+
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//cv-generator//availability//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{summary}"),
+    ]
+    .into_iter()
+    .chain(events)
+    .chain(["END:VCALENDAR".to_string()]);
+
+    lines.collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+/// Write the availability calendar
+///
+/// # Arguments
+///
+/// * `config` - Booking configuration with the weekly slots
+/// * `name` - Name of the CV owner
+/// * `path` - Path of the `.ics` file
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_availability_ics(config: &BookingConfig, name: &str, path: &Path) -> Result<()> {
+    fs::write(path, availability_calendar(config, name, Utc::now()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The first date on or after `from` that falls on the slot's day
+fn first_occurrence(slot: &AvailabilitySlot, from: NaiveDate) -> NaiveDate {
+    let days_ahead =
+        (7 + slot.day.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + Duration::days(i64::from(days_ahead))
+}
+
+/// A DTSTART/DTEND property, in UTC or with a TZID parameter
+fn date_time_property(name: &str, date: NaiveDate, time: NaiveTime, timezone: &str) -> String {
+    let local = date.and_time(time).format("%Y%m%dT%H%M%S");
+    if timezone == "UTC" {
+        format!("{name}:{local}Z")
+    } else {
+        format!("{name};TZID={timezone}:{local}")
+    }
+}
+
+/// Escape a TEXT value (RFC 5545, section 3.3.11)
+pub(crate) fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_availability_calendar() {
+        let config: BookingConfig = serde_json::from_str(
+            r#"{
+                "timezone": "Europe/Copenhagen",
+                "availability": [
+                    {"day": "monday", "start": "09:00", "end": "12:00"},
+                    {"day": "Thu", "start": "14:30", "end": "16:00"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        // A Wednesday
+        let now = Utc.with_ymd_and_hms(2026, 10, 14, 8, 0, 0).unwrap();
+
+        let calendar = availability_calendar(&config, "Ada, Countess", now);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(calendar.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 2);
+        assert!(calendar.contains("DTSTART;TZID=Europe/Copenhagen:20261019T090000\r\n"));
+        assert!(calendar.contains("DTEND;TZID=Europe/Copenhagen:20261015T160000\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=WEEKLY;BYDAY=TH\r\n"));
+        assert!(calendar.contains("SUMMARY:Available for a call with Ada\\, Countess\r\n"));
+    }
+}
@@ -0,0 +1,96 @@
+//! Build metadata
+//!
+//! Records which generator version, source commit and Typst version a site was
+//! built with, so a deployed page can be traced back to the commit it came
+//! from. The metadata is exposed to templates as `site_config.build_info`,
+//! rendered as `<meta>` tags on every page and, optionally, on an
+//! `about-this-site.html` page.
+
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::typst_generator;
+
+/// Metadata about the current build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Version of cv-generator that built the site
+    pub generator_version: String,
+    /// Full commit hash of the site sources, if built from a git checkout
+    pub git_commit: Option<String>,
+    /// Build time in RFC 3339 format (UTC)
+    pub build_date: String,
+    /// Typst version used for the PDF, if available
+    pub typst_version: Option<String>,
+}
+
+impl BuildInfo {
+    /// Collect metadata for a build starting now
+    ///
+    /// `SOURCE_DATE_EPOCH` is honored for the build date, so reproducible
+    /// builds produce identical output.
+    ///
+    /// # Returns
+    ///
+    /// The build metadata
+    pub fn collect() -> Self {
+        BuildInfo {
+            generator_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: git_commit(),
+            build_date: build_date(),
+            typst_version: typst_generator::typst_version(),
+        }
+    }
+
+    /// The first eight characters of the commit hash
+    pub fn short_commit(&self) -> Option<&str> {
+        self.git_commit
+            .as_deref()
+            .map(|commit| &commit[..commit.len().min(8)])
+    }
+}
+
+/// Get the commit hash of the working directory, if it is a git checkout
+fn git_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+}
+
+/// Get the build time, from `SOURCE_DATE_EPOCH` if set
+fn build_date() -> String {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0))
+        .unwrap_or_else(Utc::now)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        let info = BuildInfo {
+            generator_version: "1.0.0".to_string(),
+            git_commit: Some("0123456789abcdef0123456789abcdef01234567".to_string()),
+            build_date: "2024-01-01T00:00:00Z".to_string(),
+            typst_version: None,
+        };
+        assert_eq!(info.short_commit(), Some("01234567"));
+
+        let collected = BuildInfo::collect();
+        assert_eq!(collected.generator_version, env!("CARGO_PKG_VERSION"));
+        assert!(chrono::DateTime::parse_from_rfc3339(&collected.build_date).is_ok());
+    }
+}
@@ -0,0 +1,312 @@
+//! Project case studies from markdown
+//!
+//! A case study is `content/projects/<slug>.md`: front matter with the
+//! screenshots and outcomes, and a markdown narrative. Each one is merged with
+//! the matching project from the CV data (usually fetched from GitHub) and
+//! rendered as `projects/<slug>.html`, so the detail page has the narrative
+//! and the live repository data side by side.
+
+use anyhow::{Context, Result};
+use gray_matter::Matter;
+use im::Vector;
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::charts::render_charts;
+use crate::cv_data::Project;
+use crate::front_matter::CustomFields;
+use crate::html_generator::filters::slug_str;
+
+/// Directory the case studies are loaded from
+pub const CASE_STUDIES_DIR: &str = "content/projects";
+
+/// A project case study written in markdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseStudy {
+    /// URL slug, the file name without `.md`
+    pub slug: String,
+    /// Page title (default: the project's display name)
+    pub title: Option<String>,
+    /// Name of the project to merge with (default: the project whose slug matches)
+    pub project: Option<String>,
+    /// One-line summary shown under the title
+    pub summary: Option<String>,
+    /// Role in the project
+    pub role: Option<String>,
+    /// Screenshot paths relative to the site root
+    pub screenshots: Vector<String>,
+    /// Results of the project, shown as a list
+    pub outcomes: Vector<String>,
+    /// HTML content rendered from markdown
+    pub content: String,
+    /// Front matter fields that aren't built in, for templates
+    #[serde(default)]
+    pub fields: CustomFields,
+}
+
+/// Front matter structure for case studies
+#[derive(Debug, Default, Deserialize)]
+struct CaseStudyFrontMatter {
+    title: Option<String>,
+    project: Option<String>,
+    summary: Option<String>,
+    role: Option<String>,
+    #[serde(default)]
+    screenshots: Vector<String>,
+    #[serde(default)]
+    outcomes: Vector<String>,
+    #[serde(flatten)]
+    fields: CustomFields,
+}
+
+/// A case study with the project data it was merged with
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectDetail {
+    /// Project data from the CV, including the fetched GitHub fields
+    pub project: Project,
+    /// The case study written for the project
+    pub case_study: CaseStudy,
+}
+
+impl CaseStudy {
+    /// Parse markdown content with front matter
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Markdown content with optional front matter
+    /// * `slug` - URL slug for the case study
+    ///
+    /// # Returns
+    ///
+    /// Result containing the parsed CaseStudy or an error
+    pub fn from_markdown(content: &str, slug: String) -> Result<Self> {
+        let matter = Matter::<gray_matter::engine::YAML>::new();
+        let parsed = matter
+            .parse::<Option<CaseStudyFrontMatter>>(content)
+            .context("Failed to parse case study markdown")?;
+        let front_matter = parsed.data.flatten().unwrap_or_default();
+
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_FOOTNOTES);
+        options.insert(Options::ENABLE_SMART_PUNCTUATION);
+
+        let mut html_output = String::new();
+        let events = render_charts(Parser::new_ext(&parsed.content, options).collect())?;
+        html::push_html(&mut html_output, events.into_iter());
+
+        Ok(CaseStudy {
+            slug,
+            title: front_matter.title,
+            project: front_matter.project,
+            summary: front_matter.summary,
+            role: front_matter.role,
+            screenshots: front_matter.screenshots,
+            outcomes: front_matter.outcomes,
+            content: html_output,
+            fields: front_matter.fields,
+        })
+    }
+
+    /// Whether this case study is about a project
+    ///
+    /// Matches the `project` front matter against the project's name or
+    /// display name, or without it, the file's slug against the project's.
+    pub fn describes(&self, project: &Project) -> bool {
+        let display_name = project.display_name.as_ref().unwrap_or(&project.name);
+        match &self.project {
+            Some(name) => {
+                name.eq_ignore_ascii_case(&project.name) || name.eq_ignore_ascii_case(display_name)
+            }
+            None => self.slug == slug_str(display_name),
+        }
+    }
+}
+
+/// Loads all case studies from a directory
+///
+/// # Arguments
+///
+/// * `dir_path` - Path to the directory containing markdown files
+///
+/// # Returns
+///
+/// Result containing the case studies sorted by slug, or an error
+pub fn load_case_studies(dir_path: &Path) -> Result<Vector<CaseStudy>> {
+    if !dir_path.exists() {
+        return Ok(Vector::new());
+    }
+
+    let entries = fs::read_dir(dir_path).with_context(|| {
+        format!(
+            "Failed to read case studies directory: {}",
+            dir_path.display()
+        )
+    })?;
+
+    let mut case_studies = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("md") {
+            continue;
+        }
+
+        let slug = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("project")
+            .to_string();
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read case study: {}", path.display()))?;
+        match CaseStudy::from_markdown(&content, slug) {
+            Ok(case_study) => case_studies.push(case_study),
+            Err(e) => eprintln!(
+                "Warning: Failed to load case study {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    case_studies.sort_by(|a, b| a.slug.cmp(&b.slug));
+    Ok(case_studies.into_iter().collect())
+}
+
+/// Pair each case study with the project it describes
+///
+/// Case studies without a matching project are skipped with a warning. A
+/// project without a screenshot of its own gets the first one from its case
+/// study.
+///
+/// # Arguments
+///
+/// * `projects` - Projects from the CV data
+/// * `case_studies` - Loaded case studies
+///
+/// # Returns
+///
+/// The project detail pages to generate
+pub fn merge_case_studies(
+    projects: &Vector<Project>,
+    case_studies: &Vector<CaseStudy>,
+) -> Vector<ProjectDetail> {
+    case_studies
+        .iter()
+        .filter_map(|case_study| {
+            let project = projects
+                .iter()
+                .find(|project| case_study.describes(project));
+            if project.is_none() {
+                eprintln!(
+                    "Warning: No project found for case study {}.md",
+                    case_study.slug
+                );
+            }
+            project.map(|project| ProjectDetail {
+                project: Project {
+                    screenshot: project
+                        .screenshot
+                        .clone()
+                        .or_else(|| case_study.screenshots.head().cloned()),
+                    case_study: Some(case_study.slug.clone()),
+                    ..project.clone()
+                },
+                case_study: case_study.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Link projects to their case study pages
+///
+/// # Arguments
+///
+/// * `projects` - Projects from the CV data
+/// * `details` - Merged case studies
+///
+/// # Returns
+///
+/// The projects, with `case_study` and `screenshot` set from their case study
+pub fn link_case_studies(
+    projects: &Vector<Project>,
+    details: &Vector<ProjectDetail>,
+) -> Vector<Project> {
+    projects
+        .iter()
+        .map(|project| {
+            details
+                .iter()
+                .find(|detail| detail.project.name == project.name)
+                .map(|detail| detail.project.clone())
+                .unwrap_or_else(|| project.clone())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_case_studies() {
+        let project = |name: &str, display_name: &str| Project {
+            name: name.to_string(),
+            description: String::new(),
+            url: None,
+            repository: None,
+            technologies: Vector::new(),
+            highlights: Vector::new(),
+            stars: Some(42),
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
+            language: None,
+            language_icon: None,
+            display_name: Some(display_name.to_string()),
+        };
+        let projects = im::vector![
+            project("Cv Generator - Rust", "Cv Generator"),
+            project("dotfiles - Shell", "dotfiles")
+        ];
+        let case_studies = im::vector![
+            CaseStudy::from_markdown(
+                "---\nsummary: One source, three formats\nscreenshots:\n  - img/cv.png\noutcomes:\n  - Sub-second builds\n---\nWhy I built it.",
+                "cv-generator".to_string(),
+            )
+            .unwrap(),
+            CaseStudy::from_markdown(
+                "---\nproject: DOTFILES\n---\nMy setup.",
+                "setup".to_string()
+            )
+            .unwrap(),
+            CaseStudy::from_markdown("No project.", "unknown".to_string()).unwrap(),
+        ];
+
+        let details = merge_case_studies(&projects, &case_studies);
+
+        assert_eq!(details.len(), 2);
+        assert_eq!(details[0].project.stars, Some(42));
+        assert_eq!(details[0].project.screenshot.as_deref(), Some("img/cv.png"));
+        assert_eq!(
+            details[0].case_study.outcomes,
+            im::vector!["Sub-second builds".to_string()]
+        );
+        assert!(details[0]
+            .case_study
+            .content
+            .contains("<p>Why I built it.</p>"));
+        assert_eq!(details[1].project.name, "dotfiles - Shell");
+
+        let linked = link_case_studies(&projects, &details);
+        assert_eq!(linked[1].case_study.as_deref(), Some("setup"));
+    }
+}
@@ -0,0 +1,550 @@
+//! Charts rendered to static SVG
+//!
+//! Blog posts, static pages and case studies can include a chart as a fenced
+//! code block in the `chart` language, with the data inline as CSV or JSON:
+//!
+//! ````markdown
+//! ```chart type=bar title="Languages"
+//! language,2023,2024
+//! Rust,40,55
+//! Scala,30,20
+//! ```
+//! ````
+//!
+//! The block is replaced by an SVG at build time, so pages don't ship a
+//! JavaScript chart library. Bar and line charts take one series per numeric
+//! column; pie charts use the first. The colors come from the theme through
+//! the `chart-series-N` classes, since inline styles are ruled out by the
+//! Content Security Policy.
+
+use anyhow::{Context, Result};
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use regex::Regex;
+use serde_json::Value;
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::sync::OnceLock;
+
+/// Language of the fenced code blocks rendered as charts
+pub const CHART_LANGUAGE: &str = "chart";
+
+/// Number of `chart-series-N` color classes before they repeat
+const SERIES_COLORS: usize = 6;
+
+const WIDTH: f64 = 600.0;
+const HEIGHT: f64 = 300.0;
+const MARGIN_LEFT: f64 = 48.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 16.0;
+const MARGIN_BOTTOM: f64 = 40.0;
+
+/// Kind of chart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartType {
+    Bar,
+    Line,
+    Pie,
+}
+
+impl ChartType {
+    /// Parse a chart type name
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "bar" => Ok(Self::Bar),
+            "line" => Ok(Self::Line),
+            "pie" => Ok(Self::Pie),
+            other => anyhow::bail!("Unknown chart type '{}', expected bar, line or pie", other),
+        }
+    }
+
+    /// Name of the chart type, as in `type=bar`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bar => "bar",
+            Self::Line => "line",
+            Self::Pie => "pie",
+        }
+    }
+}
+
+/// A named series of values, one per label
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+/// Chart data: labels along the x axis (or pie slices) and the series
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartData {
+    pub labels: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+impl ChartData {
+    /// Parse inline chart data
+    ///
+    /// JSON is either an object of label to value, or an array of objects
+    /// with a `label` and one number per series. CSV has a header row, the
+    /// labels in the first column and one series per further column.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - The data, as CSV or JSON
+    ///
+    /// # Returns
+    ///
+    /// The parsed data
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = source.trim();
+        let data = if source.starts_with('{') || source.starts_with('[') {
+            Self::from_json(&serde_json::from_str(source).context("Invalid chart JSON")?)?
+        } else {
+            Self::from_csv(source)?
+        };
+        if data.labels.is_empty() || data.series.is_empty() {
+            anyhow::bail!("Chart has no data");
+        }
+        Ok(data)
+    }
+
+    fn from_csv(source: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(source.as_bytes());
+        let headers = reader.headers().context("Invalid chart CSV")?.clone();
+        let mut data = ChartData {
+            labels: Vec::new(),
+            series: headers
+                .iter()
+                .skip(1)
+                .map(|name| Series {
+                    name: name.to_string(),
+                    values: Vec::new(),
+                })
+                .collect(),
+        };
+        for record in reader.records() {
+            let record = record.context("Invalid chart CSV")?;
+            data.labels
+                .push(record.get(0).unwrap_or_default().to_string());
+            for (series, value) in data.series.iter_mut().zip(record.iter().skip(1)) {
+                series.values.push(
+                    value
+                        .parse()
+                        .with_context(|| format!("'{}' is not a number", value))?,
+                );
+            }
+        }
+        Ok(data)
+    }
+
+    fn from_json(value: &Value) -> Result<Self> {
+        let number = |label: &str, value: &Value| {
+            value
+                .as_f64()
+                .with_context(|| format!("Value for '{}' is not a number", label))
+        };
+        match value {
+            Value::Object(values) => Ok(ChartData {
+                labels: values.keys().cloned().collect(),
+                series: vec![Series {
+                    name: String::new(),
+                    values: values
+                        .iter()
+                        .map(|(label, value)| number(label, value))
+                        .collect::<Result<_>>()?,
+                }],
+            }),
+            Value::Array(rows) => {
+                let mut data = ChartData {
+                    labels: Vec::new(),
+                    series: Vec::new(),
+                };
+                for row in rows {
+                    let row = row.as_object().context("Chart rows must be objects")?;
+                    let label = row
+                        .get("label")
+                        .and_then(Value::as_str)
+                        .context("Chart row without a label")?;
+                    for (name, value) in row.iter().filter(|(name, _)| *name != "label") {
+                        let position = match data.series.iter().position(|s| &s.name == name) {
+                            Some(position) => position,
+                            None => {
+                                data.series.push(Series {
+                                    name: name.clone(),
+                                    values: vec![0.0; data.labels.len()],
+                                });
+                                data.series.len() - 1
+                            }
+                        };
+                        data.series[position].values.push(number(label, value)?);
+                    }
+                    data.labels.push(label.to_string());
+                    // Rows missing a series count as zero
+                    for series in data.series.iter_mut() {
+                        series.values.resize(data.labels.len(), 0.0);
+                    }
+                }
+                Ok(data)
+            }
+            _ => anyhow::bail!("Chart JSON must be an object or an array"),
+        }
+    }
+}
+
+/// Render a chart as a figure with an SVG
+///
+/// # Arguments
+///
+/// * `chart_type` - Kind of chart
+/// * `title` - Caption and accessible name of the chart
+/// * `data` - The data to plot
+///
+/// # Returns
+///
+/// The HTML of the chart
+pub fn render_chart(
+    chart_type: ChartType,
+    title: Option<&str>,
+    data: &ChartData,
+) -> Result<String> {
+    let svg = match chart_type {
+        ChartType::Bar => bar_chart(data),
+        ChartType::Line => line_chart(data),
+        ChartType::Pie => pie_chart(data)?,
+    };
+    let legend_entries: Vec<&str> = match chart_type {
+        ChartType::Pie => data.labels.iter().map(String::as_str).collect(),
+        _ if data.series.len() > 1 => data.series.iter().map(|s| s.name.as_str()).collect(),
+        _ => Vec::new(),
+    };
+
+    let label = title.unwrap_or("Chart");
+    let mut html = format!(
+        "<figure class=\"chart chart-{}\">\n<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" role=\"img\" aria-label=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n<title>{}</title>\n{}</svg>\n",
+        chart_type.name(),
+        escape_xml(label),
+        escape_xml(label),
+        svg
+    );
+    if !legend_entries.is_empty() {
+        html.push_str("<ul class=\"chart-legend\">");
+        for (i, entry) in legend_entries.iter().enumerate() {
+            let _ = write!(
+                html,
+                "<li><span class=\"chart-swatch chart-series-{}\"></span>{}</li>",
+                i % SERIES_COLORS,
+                escape_xml(entry)
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+    if let Some(title) = title {
+        let _ = writeln!(html, "<figcaption>{}</figcaption>", escape_xml(title));
+    }
+    html.push_str("</figure>\n");
+    Ok(html)
+}
+
+/// Replace `chart` code blocks with rendered charts
+///
+/// # Arguments
+///
+/// * `events` - Markdown events of a document
+///
+/// # Returns
+///
+/// The events with each chart block replaced by its HTML
+pub fn render_charts(events: Vec<Event<'_>>) -> Result<Vec<Event<'_>>> {
+    let mut output = Vec::with_capacity(events.len());
+    let mut chart: Option<(String, String)> = None;
+
+    for event in events {
+        if let Some((info, source)) = chart.as_mut() {
+            match event {
+                Event::Text(text) => source.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => {
+                    let html = chart_block(info, source)
+                        .with_context(|| format!("Invalid chart ```{}", info))?;
+                    output.push(Event::Html(CowStr::from(html)));
+                    chart = None;
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))
+                if info.split_whitespace().next() == Some(CHART_LANGUAGE) =>
+            {
+                chart = Some((info.to_string(), String::new()));
+            }
+            event => output.push(event),
+        }
+    }
+    Ok(output)
+}
+
+/// Render a chart code block from its info string and content
+fn chart_block(info: &str, source: &str) -> Result<String> {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let attribute = ATTRIBUTE.get_or_init(|| {
+        Regex::new(r#"(\w+)=(?:"([^"]*)"|(\S+))"#).expect("valid chart attribute regex")
+    });
+    let mut chart_type = ChartType::Bar;
+    let mut title = None;
+    for captures in attribute.captures_iter(info) {
+        let value = captures
+            .get(2)
+            .or_else(|| captures.get(3))
+            .map_or("", |m| m.as_str());
+        match &captures[1] {
+            "type" => chart_type = ChartType::parse(value)?,
+            "title" => title = Some(value.to_string()),
+            other => anyhow::bail!("Unknown chart attribute '{}'", other),
+        }
+    }
+    render_chart(chart_type, title.as_deref(), &ChartData::parse(source)?)
+}
+
+fn bar_chart(data: &ChartData) -> String {
+    let (scale, mut svg) = value_axis(data);
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let group_width = plot_width / data.labels.len() as f64;
+    let bar_width = group_width * 0.8 / data.series.len() as f64;
+
+    for (i, label) in data.labels.iter().enumerate() {
+        let group_x = MARGIN_LEFT + group_width * i as f64;
+        for (s, series) in data.series.iter().enumerate() {
+            let value = series.values[i];
+            let (top, bottom) = (scale(value.max(0.0)), scale(value.min(0.0)));
+            let _ = writeln!(
+                svg,
+                "<rect class=\"chart-series-{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><title>{}: {}</title></rect>",
+                s % SERIES_COLORS,
+                number(group_x + group_width * 0.1 + bar_width * s as f64),
+                number(top),
+                number(bar_width),
+                number(bottom - top),
+                escape_xml(label),
+                number(value)
+            );
+        }
+        svg.push_str(&category_label(label, group_x + group_width / 2.0));
+    }
+    svg
+}
+
+fn line_chart(data: &ChartData) -> String {
+    let (scale, mut svg) = value_axis(data);
+    let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+    let step = plot_width / data.labels.len() as f64;
+    let x = |i: usize| MARGIN_LEFT + step * (i as f64 + 0.5);
+
+    for (s, series) in data.series.iter().enumerate() {
+        let points = series
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| format!("{},{}", number(x(i)), number(scale(*value))))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(
+            svg,
+            "<polyline class=\"chart-line chart-series-{}\" points=\"{}\"/>",
+            s % SERIES_COLORS,
+            points
+        );
+        for (i, value) in series.values.iter().enumerate() {
+            let _ = writeln!(
+                svg,
+                "<circle class=\"chart-series-{}\" cx=\"{}\" cy=\"{}\" r=\"4\"><title>{}: {}</title></circle>",
+                s % SERIES_COLORS,
+                number(x(i)),
+                number(scale(*value)),
+                escape_xml(&data.labels[i]),
+                number(*value)
+            );
+        }
+    }
+    for (i, label) in data.labels.iter().enumerate() {
+        svg.push_str(&category_label(label, x(i)));
+    }
+    svg
+}
+
+fn pie_chart(data: &ChartData) -> Result<String> {
+    let values = &data.series[0].values;
+    if values.iter().any(|value| *value < 0.0) {
+        anyhow::bail!("Pie charts can't show negative values");
+    }
+    let total: f64 = values.iter().sum();
+    if total <= 0.0 {
+        anyhow::bail!("Pie chart values add up to zero");
+    }
+
+    let (cx, cy, r) = (WIDTH / 2.0, HEIGHT / 2.0, HEIGHT / 2.0 - MARGIN_TOP);
+    let point = |angle: f64| (cx + r * angle.cos(), cy + r * angle.sin());
+    let mut svg = String::new();
+    let mut angle = -PI / 2.0;
+    for (i, (label, value)) in data.labels.iter().zip(values).enumerate() {
+        let share = value / total;
+        let tooltip = format!(
+            "<title>{}: {} ({}%)</title>",
+            escape_xml(label),
+            number(*value),
+            number((share * 100.0 * 10.0).round() / 10.0)
+        );
+        if share >= 1.0 {
+            let _ = writeln!(
+                svg,
+                "<circle class=\"chart-series-{}\" cx=\"{}\" cy=\"{}\" r=\"{}\">{}</circle>",
+                i % SERIES_COLORS,
+                number(cx),
+                number(cy),
+                number(r),
+                tooltip
+            );
+        } else if share > 0.0 {
+            let (x1, y1) = point(angle);
+            angle += share * 2.0 * PI;
+            let (x2, y2) = point(angle);
+            let _ = writeln!(
+                svg,
+                "<path class=\"chart-series-{}\" d=\"M{},{} L{},{} A{},{} 0 {} 1 {},{} Z\">{}</path>",
+                i % SERIES_COLORS,
+                number(cx),
+                number(cy),
+                number(x1),
+                number(y1),
+                number(r),
+                number(r),
+                u8::from(share > 0.5),
+                number(x2),
+                number(y2),
+                tooltip
+            );
+        }
+    }
+    Ok(svg)
+}
+
+/// The y axis with gridlines, and a function mapping values to y coordinates
+fn value_axis(data: &ChartData) -> (impl Fn(f64) -> f64, String) {
+    let values = data.series.iter().flat_map(|series| series.values.iter());
+    let highest = values.clone().fold(0.0, |max: f64, v| max.max(*v));
+    let lowest = values.fold(0.0, |min: f64, v| min.min(*v));
+    // About four gridlines at round values, including zero
+    let step = match nice_ceiling((highest - lowest) / 4.0) {
+        step if step > 0.0 => step,
+        _ => 1.0,
+    };
+    let min = (lowest / step).floor() * step;
+    let max = (highest / step).ceil().max(1.0) * step;
+    let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+    let scale = move |value: f64| MARGIN_TOP + (max - value) / (max - min) * plot_height;
+
+    let mut svg = String::new();
+    let gridlines = ((max - min) / step).round() as usize;
+    for i in 0..=gridlines {
+        let value = min + step * i as f64;
+        let y = number(scale(value));
+        let _ = writeln!(
+            svg,
+            "<line class=\"chart-grid\" x1=\"{MARGIN_LEFT}\" y1=\"{y}\" x2=\"{}\" y2=\"{y}\"/>",
+            WIDTH - MARGIN_RIGHT
+        );
+        let _ = writeln!(
+            svg,
+            "<text class=\"chart-axis\" x=\"{}\" y=\"{y}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+            MARGIN_LEFT - 8.0,
+            number(value)
+        );
+    }
+    (scale, svg)
+}
+
+/// Label under a bar group or line point
+fn category_label(label: &str, x: f64) -> String {
+    format!(
+        "<text class=\"chart-axis\" x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+        number(x),
+        HEIGHT - MARGIN_BOTTOM + 20.0,
+        escape_xml(label)
+    )
+}
+
+/// Round up to 1, 2, 2.5 or 5 times a power of ten, for readable axis values
+fn nice_ceiling(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 0.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 2.5, 5.0, 10.0]
+        .iter()
+        .map(|step| step * magnitude)
+        .find(|candidate| *candidate >= value)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Format a number with at most two decimals
+fn number(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded == 0.0 {
+        "0".to_string()
+    } else {
+        rounded.to_string()
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{html, Parser};
+
+    #[test]
+    fn test_chart_data() {
+        let csv = ChartData::parse("language,2023,2024\nRust,40,55\nScala,30,20").unwrap();
+        assert_eq!(csv.labels, vec!["Rust", "Scala"]);
+        assert_eq!(csv.series[1].name, "2024");
+        assert_eq!(csv.series[1].values, vec![55.0, 20.0]);
+
+        let json = ChartData::parse(
+            r#"[{"label": "Jan", "commits": 12}, {"label": "Feb", "commits": 7, "reviews": 3}]"#,
+        )
+        .unwrap();
+        assert_eq!(json.labels, vec!["Jan", "Feb"]);
+        assert_eq!(json.series[0].values, vec![12.0, 7.0]);
+        assert_eq!(json.series[1].values, vec![0.0, 3.0]);
+
+        assert_eq!(nice_ceiling(13.75), 20.0);
+        assert_eq!(nice_ceiling(42.0), 50.0);
+        assert_eq!(nice_ceiling(2.2), 2.5);
+        assert!(ChartData::parse("a,b\nx,lots").is_err());
+    }
+
+    #[test]
+    fn test_render_charts() {
+        let markdown = "Intro\n\n```chart type=pie title=\"Time & effort\"\n{\"Rust\": 3, \"Scala\": 1}\n```\n\n```rust\nfn main() {}\n```\n";
+        let events = render_charts(Parser::new(markdown).collect()).unwrap();
+        let mut output = String::new();
+        html::push_html(&mut output, events.into_iter());
+
+        assert!(output.contains("<figure class=\"chart chart-pie\">"));
+        assert!(output.contains("aria-label=\"Time &amp; effort\""));
+        assert!(output.contains("<title>Rust: 3 (75%)</title>"));
+        assert!(output.contains("<figcaption>Time &amp; effort</figcaption>"));
+        assert!(output.contains("<code class=\"language-rust\">"));
+
+        let invalid = "```chart type=radar\nx,y\na,1\n```\n";
+        assert!(render_charts(Parser::new(invalid).collect()).is_err());
+    }
+}
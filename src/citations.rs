@@ -0,0 +1,607 @@
+//! Citations and bibliographies
+//!
+//! Blog posts can cite works with `[@key]`, or `[@key1; @key2]` for several at
+//! once. The works come from a `references` list in the front matter, a BibTeX
+//! file named by `bibliography`, or both. Citations render author-year style,
+//! e.g. "(Knuth, 1984)", and link to a "References" section appended to the
+//! post, which lists the cited works alphabetically in a consistent,
+//! APA-like format. Static pages support the same syntax, and with
+//! `nocite: "@*"` a page such as a publications list shows every reference
+//! without citing each one. Each entry of such a list comes with a "Cite"
+//! snippet holding its BibTeX, and `cv export --format bibtex` writes them all
+//! to a `.bib` file.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// A work that can be cited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Reference {
+    /// Citation key, as in `[@key]`
+    pub key: String,
+    /// BibTeX entry type, e.g. `article` or `inproceedings`
+    #[serde(default, rename = "type")]
+    pub kind: Option<String>,
+    /// Authors separated by ` and `, each as `Last, First` or `First Last`
+    #[serde(default)]
+    pub author: String,
+    /// Title of the work
+    #[serde(default)]
+    pub title: String,
+    /// Publication year
+    #[serde(default, deserialize_with = "deserialize_year")]
+    pub year: Option<String>,
+    /// Journal, proceedings or website the work appeared in
+    #[serde(default)]
+    pub container: Option<String>,
+    /// Publisher
+    #[serde(default)]
+    pub publisher: Option<String>,
+    /// DOI, without the `https://doi.org/` prefix
+    #[serde(default)]
+    pub doi: Option<String>,
+    /// Link to the work
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl Reference {
+    /// Author-year label used in citations, e.g. `Knuth & Lamport, 1984`
+    pub fn label(&self) -> String {
+        let names: Vec<_> = self.authors().map(|(last, _)| last).collect();
+        let authors = match names.as_slice() {
+            [] => self.title.clone(),
+            [one] => one.clone(),
+            [first, second] => format!("{} & {}", first, second),
+            [first, ..] => format!("{} et al.", first),
+        };
+        format!("{}, {}", authors, self.year.as_deref().unwrap_or("n.d."))
+    }
+
+    /// Link to the work, preferring the DOI
+    pub fn link(&self) -> Option<String> {
+        self.doi
+            .as_deref()
+            .map(|doi| {
+                format!(
+                    "https://doi.org/{}",
+                    doi.trim_start_matches("https://doi.org/")
+                )
+            })
+            .or_else(|| self.url.clone())
+    }
+
+    /// The work as a BibTeX entry
+    ///
+    /// Without an entry type, works in a journal or proceedings are written as
+    /// `@article`, other published works as `@book` and the rest as `@misc`.
+    ///
+    /// # Returns
+    ///
+    /// e.g. `@article{knuth84,\n  author = {Knuth, Donald E.},\n  ...\n}\n`
+    pub fn to_bibtex(&self) -> String {
+        let kind = self.kind.clone().unwrap_or_else(|| {
+            if self.container.is_some() {
+                "article".to_string()
+            } else if self.publisher.is_some() {
+                "book".to_string()
+            } else {
+                "misc".to_string()
+            }
+        });
+        let container_field = match kind.as_str() {
+            "inproceedings" | "incollection" | "conference" => "booktitle",
+            "article" => "journal",
+            _ => "howpublished",
+        };
+
+        let fields = [
+            (
+                "author",
+                Some(&self.author).filter(|author| !author.is_empty()),
+            ),
+            ("title", Some(&self.title).filter(|title| !title.is_empty())),
+            (container_field, self.container.as_ref()),
+            ("publisher", self.publisher.as_ref()),
+            ("year", self.year.as_ref()),
+            ("doi", self.doi.as_ref()),
+            ("url", self.url.as_ref()),
+        ];
+        let fields: String = fields
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| format!(",\n  {} = {{{}}}", name, value)))
+            .collect();
+
+        format!("@{}{{{}{}\n}}\n", kind, self.key, fields)
+    }
+
+    /// Authors as (last name, given names) pairs
+    fn authors(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.author
+            .split(" and ")
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| match name.split_once(',') {
+                Some((last, given)) => (last.trim().to_string(), given.trim().to_string()),
+                None => match name.rsplit_once(' ') {
+                    Some((given, last)) => (last.to_string(), given.trim().to_string()),
+                    None => (name.to_string(), String::new()),
+                },
+            })
+    }
+}
+
+/// Accept both `year: 1984` and `year: "1984"` in front matter
+fn deserialize_year<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Year {
+        Number(i64),
+        Text(String),
+    }
+
+    Ok(
+        Option::<Year>::deserialize(deserializer)?.map(|year| match year {
+            Year::Number(year) => year.to_string(),
+            Year::Text(year) => year,
+        }),
+    )
+}
+
+/// Collect the references of a post
+///
+/// # Arguments
+///
+/// * `references` - References from the front matter
+/// * `bibliography` - Path of a BibTeX file, relative to `base_dir`
+/// * `base_dir` - Directory of the post
+///
+/// # Returns
+///
+/// The references from both sources; front matter entries win on duplicate keys
+pub fn load_references(
+    references: Vector<Reference>,
+    bibliography: Option<&str>,
+    base_dir: Option<&Path>,
+) -> Result<Vector<Reference>> {
+    let Some(bibliography) = bibliography else {
+        return Ok(references);
+    };
+
+    let path = base_dir
+        .map(|dir| dir.join(bibliography))
+        .unwrap_or_else(|| bibliography.into());
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read bibliography: {}", path.display()))?;
+    let from_file = parse_bibtex(&content)
+        .with_context(|| format!("Failed to parse bibliography: {}", path.display()))?;
+
+    Ok(references
+        .iter()
+        .cloned()
+        .chain(
+            from_file
+                .into_iter()
+                .filter(|entry| references.iter().all(|r| r.key != entry.key)),
+        )
+        .collect())
+}
+
+/// Parse the entries of a BibTeX file
+///
+/// Supports the fields used in a bibliography (`author`, `title`, `year`,
+/// `journal`/`booktitle`/`howpublished`, `publisher`, `doi`, `url`). `@string`, `@preamble`
+/// and `@comment` blocks are skipped, and braces used for capitalization are
+/// removed from values.
+///
+/// # Arguments
+///
+/// * `content` - BibTeX source
+///
+/// # Returns
+///
+/// The references in file order
+pub fn parse_bibtex(content: &str) -> Result<Vector<Reference>> {
+    let mut references = Vector::new();
+    let mut rest = content;
+
+    while let Some(at) = rest.find('@') {
+        rest = &rest[at + 1..];
+        let open = rest
+            .find(['{', '('])
+            .context("Expected '{' after the entry type")?;
+        let entry_type = rest[..open].trim().to_lowercase();
+        let (body, after) = balanced(&rest[open..]).context("Unterminated BibTeX entry")?;
+        rest = after;
+
+        if matches!(entry_type.as_str(), "string" | "preamble" | "comment") {
+            continue;
+        }
+
+        let (key, fields) = body.split_once(',').unwrap_or((body, ""));
+        let mut reference = Reference {
+            key: key.trim().to_string(),
+            kind: Some(entry_type),
+            ..Reference::default()
+        };
+        for (name, value) in parse_fields(fields)? {
+            match name.as_str() {
+                "author" => reference.author = value,
+                "editor" if reference.author.is_empty() => reference.author = value,
+                "title" => reference.title = value,
+                "year" => reference.year = Some(value),
+                "journal" | "booktitle" | "howpublished" => reference.container = Some(value),
+                "publisher" | "institution" | "school" => reference.publisher = Some(value),
+                "doi" => reference.doi = Some(value),
+                "url" => reference.url = Some(value),
+                _ => {}
+            }
+        }
+        references.push_back(reference);
+    }
+
+    Ok(references)
+}
+
+/// Split `{...}` or `(...)` off the start of `text`
+///
+/// # Returns
+///
+/// The contents between the delimiters and the text after the closing one
+fn balanced(text: &str) -> Option<(&str, &str)> {
+    let open = text.chars().next()?;
+    let close = if open == '(' { ')' } else { '}' };
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some((&text[1..i], &text[i + 1..]));
+            }
+        }
+    }
+    None
+}
+
+/// Parse the `name = value` fields of an entry
+fn parse_fields(mut text: &str) -> Result<Vec<(String, String)>> {
+    let mut fields = Vec::new();
+
+    loop {
+        text = text.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        let Some((name, value)) = text.split_once('=') else {
+            break;
+        };
+        let name = name.trim().to_lowercase();
+        let value = value.trim_start();
+
+        let (raw, after) = match value.chars().next() {
+            Some('{') => {
+                balanced(value).with_context(|| format!("Unterminated field: {}", name))?
+            }
+            Some('"') => {
+                let end = value[1..]
+                    .find('"')
+                    .with_context(|| format!("Unterminated field: {}", name))?;
+                (&value[1..end + 1], &value[end + 2..])
+            }
+            _ => {
+                let end = value.find(',').unwrap_or(value.len());
+                (value[..end].trim(), &value[end..])
+            }
+        };
+
+        let cleaned = raw
+            .replace(['{', '}'], "")
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        fields.push((name, cleaned));
+        text = after;
+    }
+
+    Ok(fields)
+}
+
+/// Replace `[@key]` citations in the text of a post with links to its references
+///
+/// Citations in code blocks are left alone, and so are unknown keys, with a
+/// warning.
+///
+/// # Arguments
+///
+/// * `events` - Markdown events of the post, with adjacent text merged
+/// * `references` - Works the post can cite
+/// * `nocite` - Keys listed in the bibliography without being cited, as in
+///   pandoc: `"@key1, @key2"`, or `"@*"` for all of them
+///
+/// # Returns
+///
+/// The updated events and the cited references, in bibliography order
+pub fn cite<'a>(
+    events: Vec<Event<'a>>,
+    references: &Vector<Reference>,
+    nocite: Option<&str>,
+) -> (Vec<Event<'a>>, Vector<Reference>) {
+    let mut output = Vec::with_capacity(events.len());
+    let mut cited: Vec<Reference> = Vec::new();
+    let mut in_code_block = false;
+
+    for event in events {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                output.push(event);
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                output.push(event);
+            }
+            Event::Text(text) if !in_code_block && text.contains("[@") => {
+                let mut last = 0;
+                for captures in citation_regex().captures_iter(&text) {
+                    let whole = captures.get_match();
+                    let keys: Vec<_> = captures["keys"]
+                        .split(';')
+                        .map(|key| key.trim().trim_start_matches('@'))
+                        .collect();
+                    let Some(found) = keys
+                        .iter()
+                        .map(|key| references.iter().find(|r| r.key == *key))
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        println!("⚠️  Unknown citation: {}", whole.as_str());
+                        continue;
+                    };
+
+                    let links: Vec<_> = found
+                        .iter()
+                        .map(|reference| {
+                            if !cited.contains(reference) {
+                                cited.push((*reference).clone());
+                            }
+                            format!(
+                                r##"<a class="citation" href="#ref-{}">{}</a>"##,
+                                escape_html(&reference.key),
+                                escape_html(&reference.label())
+                            )
+                        })
+                        .collect();
+
+                    output.push(Event::Text(text[last..whole.start()].to_string().into()));
+                    output.push(Event::InlineHtml(CowStr::from(format!(
+                        "({})",
+                        links.join("; ")
+                    ))));
+                    last = whole.end();
+                }
+                output.push(Event::Text(text[last..].to_string().into()));
+            }
+            other => output.push(other),
+        }
+    }
+
+    if let Some(nocite) = nocite {
+        let keys: Vec<_> = nocite
+            .split([',', ';'])
+            .map(|key| key.trim().trim_start_matches('@'))
+            .collect();
+        for reference in references.iter() {
+            let listed = keys.iter().any(|key| *key == "*" || *key == reference.key);
+            if listed && !cited.contains(reference) {
+                cited.push(reference.clone());
+            }
+        }
+    }
+
+    cited.sort_by_cached_key(|reference| {
+        (
+            reference.label().to_lowercase(),
+            reference.title.to_lowercase(),
+        )
+    });
+    (output, cited.into_iter().collect())
+}
+
+/// Write references as a BibTeX file
+///
+/// # Arguments
+///
+/// * `references` - The works to write
+///
+/// # Returns
+///
+/// The entries separated by blank lines
+pub fn to_bibtex(references: &Vector<Reference>) -> String {
+    references
+        .iter()
+        .map(Reference::to_bibtex)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Render the "References" section for the cited works
+///
+/// # Arguments
+///
+/// * `cited` - Cited references, in bibliography order
+/// * `cite_snippets` - Whether each entry gets a "Cite" snippet with its BibTeX
+///
+/// # Returns
+///
+/// The section HTML, or an empty string if nothing was cited
+pub fn render_bibliography(cited: &Vector<Reference>, cite_snippets: bool) -> String {
+    if cited.is_empty() {
+        return String::new();
+    }
+
+    let entries: String = cited
+        .iter()
+        .map(|reference| {
+            let snippet = if cite_snippets {
+                format!(
+                    "\n<details class=\"cite\"><summary>Cite</summary><pre><code class=\"language-bibtex\">{}</code></pre></details>",
+                    escape_html(&reference.to_bibtex())
+                )
+            } else {
+                String::new()
+            };
+            format!(
+                "<li id=\"ref-{}\">{}{}</li>\n",
+                escape_html(&reference.key),
+                format_reference(reference),
+                snippet
+            )
+        })
+        .collect();
+
+    format!(
+        "<section class=\"bibliography\" id=\"references\">\n<h2>References</h2>\n<ol class=\"references\">\n{}</ol>\n</section>\n",
+        entries
+    )
+}
+
+/// Format a reference as HTML: authors, year, title, container, publisher and link
+///
+/// # Arguments
+///
+/// * `reference` - The work to format
+///
+/// # Returns
+///
+/// e.g. `Knuth, D. E. (1984). Literate programming. <em>The Computer Journal</em>. <a ...>`
+pub fn format_reference(reference: &Reference) -> String {
+    let authors: Vec<_> = reference
+        .authors()
+        .map(|(last, given)| {
+            let initials: Vec<_> = given
+                .split_whitespace()
+                .filter_map(|name| name.chars().next())
+                .map(|initial| format!("{}.", initial))
+                .collect();
+            if initials.is_empty() {
+                last
+            } else {
+                format!("{}, {}", last, initials.join(" "))
+            }
+        })
+        .collect();
+    let authors = match authors.as_slice() {
+        [] => String::new(),
+        [one] => one.clone(),
+        [init @ .., last] => format!("{}, & {}", init.join(", "), last),
+    };
+
+    let mut parts = Vec::new();
+    if !authors.is_empty() {
+        parts.push(escape_html(&authors));
+    }
+    parts.push(format!(
+        "({}).",
+        escape_html(reference.year.as_deref().unwrap_or("n.d."))
+    ));
+    if !reference.title.is_empty() {
+        parts.push(format!(
+            "{}.",
+            escape_html(reference.title.trim_end_matches('.'))
+        ));
+    }
+    if let Some(container) = &reference.container {
+        parts.push(format!("<em>{}</em>.", escape_html(container)));
+    }
+    if let Some(publisher) = &reference.publisher {
+        parts.push(format!("{}.", escape_html(publisher)));
+    }
+    if let Some(link) = reference.link() {
+        parts.push(format!(r#"<a href="{0}">{0}</a>"#, escape_html(&link)));
+    }
+
+    parts.join(" ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Matches `[@key]` and `[@key1; @key2]`
+fn citation_regex() -> &'static Regex {
+    static CITATION: OnceLock<Regex> = OnceLock::new();
+    CITATION.get_or_init(|| Regex::new(r"\[(?P<keys>@[\w:./-]+(?:\s*;\s*@[\w:./-]+)*)\]").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIBTEX: &str = r#"
+@string{cj = "The Computer Journal"}
+
+@article{knuth84,
+  author  = {Knuth, Donald E.},
+  title   = {Literate {Programming}},
+  journal = {The Computer Journal},
+  year    = 1984,
+  doi     = {10.1093/comjnl/27.2.97},
+}
+
+@book{sicp,
+  author    = "Harold Abelson and Gerald Jay Sussman and Julie Sussman",
+  title     = "Structure and Interpretation of Computer Programs",
+  publisher = {MIT Press},
+  year      = {1996}
+}
+"#;
+
+    #[test]
+    fn test_parse_bibtex() {
+        let references = parse_bibtex(BIBTEX).unwrap();
+        assert_eq!(references.len(), 2);
+
+        let knuth = &references[0];
+        assert_eq!(knuth.key, "knuth84");
+        assert_eq!(knuth.title, "Literate Programming");
+        assert_eq!(knuth.year.as_deref(), Some("1984"));
+        assert_eq!(knuth.label(), "Knuth, 1984");
+        assert_eq!(
+            format_reference(knuth),
+            r#"Knuth, D. E. (1984). Literate Programming. <em>The Computer Journal</em>. <a href="https://doi.org/10.1093/comjnl/27.2.97">https://doi.org/10.1093/comjnl/27.2.97</a>"#
+        );
+
+        let sicp = &references[1];
+        assert_eq!(sicp.label(), "Abelson et al., 1996");
+        assert_eq!(
+            format_reference(sicp),
+            "Abelson, H., Sussman, G. J., &amp; Sussman, J. (1996). Structure and Interpretation of Computer Programs. MIT Press."
+        );
+    }
+
+    #[test]
+    fn test_to_bibtex() {
+        let references = parse_bibtex(BIBTEX).unwrap();
+        assert_eq!(
+            references[0].to_bibtex(),
+            "@article{knuth84,\n  author = {Knuth, Donald E.},\n  title = {Literate Programming},\n  journal = {The Computer Journal},\n  year = {1984},\n  doi = {10.1093/comjnl/27.2.97}\n}\n"
+        );
+        assert_eq!(parse_bibtex(&to_bibtex(&references)).unwrap(), references);
+
+        let note = Reference {
+            key: "talk".to_string(),
+            title: "A Talk".to_string(),
+            ..Reference::default()
+        };
+        assert_eq!(note.to_bibtex(), "@misc{talk,\n  title = {A Talk}\n}\n");
+    }
+}
@@ -0,0 +1,313 @@
+//! Typed client for the APIs of a deployed instance
+//!
+//! Behind the `client` feature, so tools that only talk to a running
+//! [`crate::dev_server`] don't hand-roll requests. The client speaks the same
+//! request and response types the server parses:
+//!
+//! - blog drafts go to the submission inbox ([`crate::submissions`])
+//! - testimonials are queued for review ([`crate::testimonials`])
+//! - reactions to blog posts ([`crate::reactions`])
+//! - the private application tracker ([`crate::applications`]), which needs a token
+//!
+//! There is no image or CV API on the server yet; the CV is read from
+//! `data/cv_data.json` at build time.
+//!
+//! Errors carry the server's `{"error": ...}` message. Calls are async and use
+//! `reqwest`, so the client runs on any tokio runtime.
+
+use anyhow::{bail, Context, Result};
+use reqwest::{Method, RequestBuilder, Response, StatusCode, Url};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::applications::{Application, ApplicationUpdate, NewApplication, APPLICATIONS_PATH};
+use crate::reactions::{Reaction, ReactionCounts, ReactionSubmission, REACTIONS_PATH};
+use crate::submissions::{DraftSubmission, SUBMISSION_INBOX_PATH};
+use crate::testimonials::{TestimonialSubmission, SUBMISSION_PATH};
+
+/// Client for the APIs of one deployed instance
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+/// Error body the server sends with non-success statuses
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Body of `201 Created` from the applications API
+#[derive(Debug, Deserialize)]
+struct Created {
+    id: i64,
+}
+
+impl ApiClient {
+    /// Create a client for an instance
+    ///
+    /// # Arguments
+    ///
+    /// * `base_url` - Where the instance is served, e.g. `https://example.com`;
+    ///   a path prefix such as `https://example.com/cv` is kept
+    ///
+    /// # Returns
+    ///
+    /// The client, or an error if `base_url` is not an http(s) URL
+    pub fn new(base_url: &str) -> Result<Self> {
+        let url = Url::parse(base_url).with_context(|| format!("Invalid base URL: {base_url}"))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            bail!("Base URL must be http or https: {base_url}");
+        }
+        Ok(ApiClient {
+            base_url: url.as_str().trim_end_matches('/').to_string(),
+            token: None,
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Send `token` as `Authorization: Bearer` with every request
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The applications or submissions token of the instance
+    ///
+    /// # Returns
+    ///
+    /// The client with the token
+    pub fn with_token(self, token: impl Into<String>) -> Self {
+        ApiClient {
+            token: Some(token.into()),
+            ..self
+        }
+    }
+
+    /// Submit a blog draft to the submission inbox
+    ///
+    /// # Arguments
+    ///
+    /// * `draft` - The draft
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating whether the draft was queued for review
+    pub async fn submit_draft(&self, draft: &DraftSubmission) -> Result<()> {
+        let request = self
+            .request(Method::POST, SUBMISSION_INBOX_PATH)?
+            .json(draft);
+        send(request).await.map(|_| ())
+    }
+
+    /// Submit a testimonial for review
+    ///
+    /// # Arguments
+    ///
+    /// * `testimonial` - The testimonial
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating whether the testimonial was queued for review
+    pub async fn submit_testimonial(&self, testimonial: &TestimonialSubmission) -> Result<()> {
+        let request = self
+            .request(Method::POST, SUBMISSION_PATH)?
+            .json(testimonial);
+        send(request).await.map(|_| ())
+    }
+
+    /// Count the reactions to a blog post
+    ///
+    /// # Arguments
+    ///
+    /// * `slug` - Slug of the post
+    ///
+    /// # Returns
+    ///
+    /// The counts, or an error if there is no such post
+    pub async fn reactions(&self, slug: &str) -> Result<ReactionCounts> {
+        let request = self.request(Method::GET, &reactions_path(slug))?;
+        json(send(request).await?).await
+    }
+
+    /// React to a blog post
+    ///
+    /// # Arguments
+    ///
+    /// * `slug` - Slug of the post
+    /// * `reaction` - The reaction
+    ///
+    /// # Returns
+    ///
+    /// The counts after the reaction
+    pub async fn react(&self, slug: &str, reaction: Reaction) -> Result<ReactionCounts> {
+        let request = self
+            .request(Method::POST, &reactions_path(slug))?
+            .json(&ReactionSubmission { reaction });
+        json(send(request).await?).await
+    }
+
+    /// List the tracked applications
+    ///
+    /// # Returns
+    ///
+    /// All applications
+    pub async fn applications(&self) -> Result<Vec<Application>> {
+        let request = self.request(Method::GET, APPLICATIONS_PATH)?;
+        json(send(request).await?).await
+    }
+
+    /// Get one application
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the application
+    ///
+    /// # Returns
+    ///
+    /// The application, or None if there is none with that id
+    pub async fn application(&self, id: i64) -> Result<Option<Application>> {
+        let request = self.request(Method::GET, &application_path(id))?;
+        let response = request.send().await.context("Request failed")?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        json(check(response).await?).await.map(Some)
+    }
+
+    /// Track a new application
+    ///
+    /// # Arguments
+    ///
+    /// * `application` - The application
+    ///
+    /// # Returns
+    ///
+    /// The id of the new application
+    pub async fn add_application(&self, application: &NewApplication) -> Result<i64> {
+        let request = self
+            .request(Method::POST, APPLICATIONS_PATH)?
+            .json(application);
+        json::<Created>(send(request).await?)
+            .await
+            .map(|created| created.id)
+    }
+
+    /// Change the status, follow-up or notes of an application
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the application
+    /// * `update` - The changes; fields that are None are kept
+    ///
+    /// # Returns
+    ///
+    /// The changed application
+    pub async fn update_application(
+        &self,
+        id: i64,
+        update: &ApplicationUpdate,
+    ) -> Result<Application> {
+        let request = self
+            .request(Method::PATCH, &application_path(id))?
+            .json(update);
+        json(send(request).await?).await
+    }
+
+    /// URL of an API path on the instance
+    fn url(&self, path: &str) -> Result<Url> {
+        let url = format!("{}{}", self.base_url, path);
+        Url::parse(&url).with_context(|| format!("Invalid URL: {url}"))
+    }
+
+    /// A request to an API path, with the token if there is one
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        let request = self.http.request(method, self.url(path)?);
+        Ok(match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        })
+    }
+}
+
+fn reactions_path(slug: &str) -> String {
+    format!("{}/{}", REACTIONS_PATH, slug)
+}
+
+fn application_path(id: i64) -> String {
+    format!("{}/{}", APPLICATIONS_PATH, id)
+}
+
+/// Send a request and fail on a non-success status
+async fn send(request: RequestBuilder) -> Result<Response> {
+    check(request.send().await.context("Request failed")?).await
+}
+
+/// Turn a non-success status into an error with the server's message
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    bail!("{}: {}", status, error_message(&body))
+}
+
+/// Parse a JSON response body
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    response.json().await.context("Invalid JSON in response")
+}
+
+/// The `error` of a JSON error body, or the body itself
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<ErrorBody>(body)
+        .map(|body| body.error)
+        .unwrap_or_else(|_| body.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_keep_path_prefix() {
+        let client = ApiClient::new("https://example.com/cv/").unwrap();
+        assert_eq!(
+            client.url(&reactions_path("hello-world")).unwrap().as_str(),
+            "https://example.com/cv/api/reactions/hello-world"
+        );
+        assert_eq!(
+            client.url(&application_path(3)).unwrap().as_str(),
+            "https://example.com/cv/api/applications/3"
+        );
+        assert!(ApiClient::new("ftp://example.com").is_err());
+    }
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            error_message(r#"{"error":"Missing or invalid token"}"#),
+            "Missing or invalid token"
+        );
+        assert_eq!(error_message("Bad Gateway\n"), "Bad Gateway");
+    }
+
+    #[test]
+    fn test_bodies_match_server() {
+        let submission = ReactionSubmission {
+            reaction: Reaction::Useful,
+        };
+        let parsed: ReactionSubmission =
+            serde_json::from_str(&serde_json::to_string(&submission).unwrap()).unwrap();
+        assert_eq!(parsed.reaction, Reaction::Useful);
+
+        let update = ApplicationUpdate {
+            notes: Some("Call on Monday".to_string()),
+            ..Default::default()
+        };
+        let parsed: ApplicationUpdate =
+            serde_json::from_str(&serde_json::to_string(&update).unwrap()).unwrap();
+        assert_eq!(parsed.status, None);
+        assert_eq!(parsed.notes.as_deref(), Some("Call on Monday"));
+    }
+}
@@ -1,12 +1,14 @@
 use anyhow::{Context, Result};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use super::search::normalize;
 use super::{ColorPalette, ColorSchemeProvider};
 
 /// Cache wrapper for any provider
 pub struct CachedProvider<P: ColorSchemeProvider> {
     provider: P,
     cache_dir: String,
+    offline: bool,
 }
 
 impl<P: ColorSchemeProvider> CachedProvider<P> {
@@ -14,8 +16,14 @@ impl<P: ColorSchemeProvider> CachedProvider<P> {
         CachedProvider {
             provider,
             cache_dir: cache_dir.to_string(),
+            offline: false,
         }
     }
+
+    /// Only read schemes from the cache, failing on the ones not cached
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
 }
 
 impl<P: ColorSchemeProvider> ColorSchemeProvider for CachedProvider<P> {
@@ -28,7 +36,12 @@ impl<P: ColorSchemeProvider> ColorSchemeProvider for CachedProvider<P> {
         let cache_key = format!("{}-{}", name, variant_suffix);
         let cache_file = Path::new(&self.cache_dir).join(format!("{}.json", cache_key));
 
-        // Try to load from cache
+        // Try to load from cache, under this name or one written differently
+        let cache_file = if cache_file.exists() {
+            cache_file
+        } else {
+            cached_match(Path::new(&self.cache_dir), &cache_key).unwrap_or(cache_file)
+        };
         if cache_file.exists() {
             if let Ok(content) = std::fs::read_to_string(&cache_file) {
                 if let Ok(palette) = serde_json::from_str(&content) {
@@ -38,6 +51,14 @@ impl<P: ColorSchemeProvider> ColorSchemeProvider for CachedProvider<P> {
             }
         }
 
+        if self.offline {
+            anyhow::bail!(
+                "Color scheme '{}' is not cached in {} and the build is offline",
+                name,
+                self.cache_dir
+            );
+        }
+
         // Fetch from provider
         println!(
             "🌐 Fetching color scheme from {}: {}",
@@ -54,6 +75,9 @@ impl<P: ColorSchemeProvider> ColorSchemeProvider for CachedProvider<P> {
     }
 
     fn list_available(&self) -> Result<Vec<String>> {
+        if self.offline {
+            anyhow::bail!("Color schemes can't be listed while the build is offline");
+        }
         self.provider.list_available()
     }
 
@@ -61,3 +85,19 @@ impl<P: ColorSchemeProvider> ColorSchemeProvider for CachedProvider<P> {
         self.provider.provider_name()
     }
 }
+
+/// A cached scheme whose key is `cache_key` up to case and separators
+fn cached_match(cache_dir: &Path, cache_key: &str) -> Option<PathBuf> {
+    let key = normalize(cache_key);
+    std::fs::read_dir(cache_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .find(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+                && path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .is_some_and(|stem| normalize(stem) == key)
+        })
+}
@@ -0,0 +1,269 @@
+//! Curated theme collections
+//!
+//! Catppuccin, Rosé Pine, Gruvbox and Nord publish their own terminal themes.
+//! A curated provider knows which repository a collection keeps them in and
+//! which variants it has, so a scheme is picked by collection and variant
+//! ("Catppuccin" with variant "latte", or "Catppuccin Latte") without knowing
+//! the file names, and the variants are listed without asking GitHub.
+
+use anyhow::Result;
+
+use super::github::{GitHubSchemeProvider, SchemeFormat};
+use super::search::normalize;
+use super::{ColorPalette, ColorSchemeProvider};
+
+/// Sources with a curated provider, as they are written in the site config
+pub const CURATED_SOURCES: [&str; 4] = ["catppuccin", "rose-pine", "gruvbox", "nord"];
+
+/// A variant of a curated collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variant {
+    /// Full name of the scheme, e.g. "Catppuccin Mocha"
+    pub name: &'static str,
+    /// The variant on its own, e.g. "mocha"
+    pub variant: &'static str,
+    /// Name of the upstream file, without its extension
+    pub file: &'static str,
+}
+
+/// Provider of a curated theme collection
+pub struct CuratedProvider {
+    name: &'static str,
+    repository: GitHubSchemeProvider,
+    variants: &'static [Variant],
+}
+
+impl CuratedProvider {
+    /// Catppuccin: Latte, Frappé, Macchiato and Mocha (the default)
+    pub fn catppuccin() -> Self {
+        CuratedProvider {
+            name: "Catppuccin",
+            repository: iterm2_repository("catppuccin/iterm", "main", "colors"),
+            variants: &[
+                Variant {
+                    name: "Catppuccin Mocha",
+                    variant: "mocha",
+                    file: "catppuccin-mocha",
+                },
+                Variant {
+                    name: "Catppuccin Macchiato",
+                    variant: "macchiato",
+                    file: "catppuccin-macchiato",
+                },
+                Variant {
+                    name: "Catppuccin Frappé",
+                    variant: "frappe",
+                    file: "catppuccin-frappe",
+                },
+                Variant {
+                    name: "Catppuccin Latte",
+                    variant: "latte",
+                    file: "catppuccin-latte",
+                },
+            ],
+        }
+    }
+
+    /// Rosé Pine: the main variant (the default), Moon and Dawn
+    pub fn rose_pine() -> Self {
+        CuratedProvider {
+            name: "Rosé Pine",
+            repository: iterm2_repository("rose-pine/iterm", "main", ""),
+            variants: &[
+                Variant {
+                    name: "Rosé Pine",
+                    variant: "main",
+                    file: "rose-pine",
+                },
+                Variant {
+                    name: "Rosé Pine Moon",
+                    variant: "moon",
+                    file: "rose-pine-moon",
+                },
+                Variant {
+                    name: "Rosé Pine Dawn",
+                    variant: "dawn",
+                    file: "rose-pine-dawn",
+                },
+            ],
+        }
+    }
+
+    /// Gruvbox: Dark (the default) and Light
+    pub fn gruvbox() -> Self {
+        CuratedProvider {
+            name: "Gruvbox",
+            repository: iterm2_repository("morhetz/gruvbox-contrib", "master", "iterm2"),
+            variants: &[
+                Variant {
+                    name: "Gruvbox Dark",
+                    variant: "dark",
+                    file: "gruvbox-dark",
+                },
+                Variant {
+                    name: "Gruvbox Light",
+                    variant: "light",
+                    file: "gruvbox-light",
+                },
+            ],
+        }
+    }
+
+    /// Nord, which has a single variant
+    pub fn nord() -> Self {
+        CuratedProvider {
+            name: "Nord",
+            repository: iterm2_repository("nordtheme/iterm2", "develop", "src/xml"),
+            variants: &[Variant {
+                name: "Nord",
+                variant: "nord",
+                file: "Nord",
+            }],
+        }
+    }
+
+    /// The curated provider of a source
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - A source from the site config, e.g. "rose-pine"
+    ///
+    /// # Returns
+    ///
+    /// The provider, if the source is a curated collection
+    pub fn for_source(source: &str) -> Option<Self> {
+        let source = normalize(source);
+        [
+            Self::catppuccin(),
+            Self::rose_pine(),
+            Self::gruvbox(),
+            Self::nord(),
+        ]
+        .into_iter()
+        .find(|collection| normalize(collection.name) == source)
+    }
+
+    /// The variant a scheme name and variant pick
+    ///
+    /// The name is the full name of a variant or its file name; the name of
+    /// the collection picks the variant given separately, or the default.
+    /// Names are compared ignoring case, separators and accents.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the scheme, e.g. "Catppuccin Latte" or "catppuccin"
+    /// * `variant` - Variant of the scheme, e.g. "latte"
+    ///
+    /// # Returns
+    ///
+    /// The variant, if the collection has it
+    pub fn find(&self, name: &str, variant: Option<&str>) -> Option<&'static Variant> {
+        let name = normalize(name);
+        if name == normalize(self.name) {
+            return match variant {
+                Some(wanted) => {
+                    let wanted = normalize(wanted);
+                    self.variants
+                        .iter()
+                        .find(|found| normalize(found.variant) == wanted)
+                }
+                None => self.variants.first(),
+            };
+        }
+        self.variants
+            .iter()
+            .find(|found| normalize(found.name) == name || normalize(found.file) == name)
+    }
+}
+
+/// A repository of iTerm2 color files in `path`
+fn iterm2_repository(repo: &str, branch: &str, path: &str) -> GitHubSchemeProvider {
+    GitHubSchemeProvider {
+        repo: repo.to_string(),
+        branch: branch.to_string(),
+        path: path.to_string(),
+        format: SchemeFormat::ITerm2,
+    }
+}
+
+impl ColorSchemeProvider for CuratedProvider {
+    fn fetch(&self, name: &str, variant: Option<&str>) -> Result<ColorPalette> {
+        let Some(found) = self.find(name, variant) else {
+            anyhow::bail!(
+                "{} has no scheme '{}'{}",
+                self.name,
+                name,
+                variant.map(|v| format!(" ({v})")).unwrap_or_default()
+            );
+        };
+        self.repository.fetch(found.file, None)
+    }
+
+    fn list_available(&self) -> Result<Vec<String>> {
+        Ok(self
+            .variants
+            .iter()
+            .map(|found| found.name.to_string())
+            .collect())
+    }
+
+    fn provider_name(&self) -> &str {
+        self.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_source() {
+        for source in CURATED_SOURCES {
+            assert!(CuratedProvider::for_source(source).is_some(), "{source}");
+        }
+        assert_eq!(
+            CuratedProvider::for_source("Rosé Pine")
+                .unwrap()
+                .provider_name(),
+            "Rosé Pine"
+        );
+        assert!(CuratedProvider::for_source("iterm2").is_none());
+    }
+
+    #[test]
+    fn test_find_variant() {
+        let catppuccin = CuratedProvider::catppuccin();
+        let file = |name, variant| catppuccin.find(name, variant).map(|found| found.file);
+
+        assert_eq!(file("Catppuccin", None), Some("catppuccin-mocha"));
+        assert_eq!(file("catppuccin", Some("Latte")), Some("catppuccin-latte"));
+        assert_eq!(file("Catppuccin Frappe", None), Some("catppuccin-frappe"));
+        assert_eq!(
+            file("catppuccin-macchiato", None),
+            Some("catppuccin-macchiato")
+        );
+        assert_eq!(file("Catppuccin", Some("espresso")), None);
+        assert_eq!(file("Dracula", None), None);
+
+        let rose_pine = CuratedProvider::rose_pine();
+        assert_eq!(
+            rose_pine.find("Rose Pine Moon", None).unwrap().name,
+            "Rosé Pine Moon"
+        );
+        assert_eq!(rose_pine.find("rose-pine", None).unwrap().variant, "main");
+    }
+
+    #[test]
+    fn test_list_available() {
+        let gruvbox = CuratedProvider::gruvbox();
+        assert_eq!(
+            gruvbox.list_available().unwrap(),
+            vec!["Gruvbox Dark", "Gruvbox Light"]
+        );
+        assert!(gruvbox
+            .fetch("Gruvbox", Some("material"))
+            .unwrap_err()
+            .to_string()
+            .contains("Gruvbox has no scheme 'Gruvbox' (material)"));
+    }
+}
@@ -21,6 +21,39 @@ pub enum SchemeFormat {
     XResources, // Xresources format
 }
 
+impl SchemeFormat {
+    /// Extension of scheme files in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SchemeFormat::ITerm2 => "itermcolors",
+            SchemeFormat::Json => "json",
+            SchemeFormat::Yaml => "yaml",
+            SchemeFormat::Toml => "toml",
+            SchemeFormat::XResources => "Xresources",
+        }
+    }
+
+    /// The format a file extension names
+    ///
+    /// # Arguments
+    ///
+    /// * `extension` - Extension of a scheme file, without the dot
+    ///
+    /// # Returns
+    ///
+    /// The format, if the extension is one of a known format
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_lowercase().as_str() {
+            "itermcolors" | "plist" => Some(SchemeFormat::ITerm2),
+            "json" => Some(SchemeFormat::Json),
+            "yaml" | "yml" => Some(SchemeFormat::Yaml),
+            "toml" => Some(SchemeFormat::Toml),
+            "xresources" | "xrdb" => Some(SchemeFormat::XResources),
+            _ => None,
+        }
+    }
+}
+
 impl GitHubSchemeProvider {
     pub fn new(repo: &str, format: SchemeFormat) -> Self {
         GitHubSchemeProvider {
@@ -61,13 +94,7 @@ impl GitHubSchemeProvider {
 
 impl ColorSchemeProvider for GitHubSchemeProvider {
     fn fetch(&self, name: &str, _variant: Option<&str>) -> Result<ColorPalette> {
-        let file_name = match self.format {
-            SchemeFormat::ITerm2 => format!("{}.itermcolors", name),
-            SchemeFormat::Json => format!("{}.json", name),
-            SchemeFormat::Yaml => format!("{}.yaml", name),
-            SchemeFormat::Toml => format!("{}.toml", name),
-            SchemeFormat::XResources => format!("{}.Xresources", name),
-        };
+        let file_name = format!("{}.{}", name, self.format.extension());
 
         let path = if self.path.is_empty() {
             file_name
@@ -109,14 +136,7 @@ impl ColorSchemeProvider for GitHubSchemeProvider {
 
         let content_str = String::from_utf8(content).context("Invalid UTF-8 in decoded content")?;
 
-        // Parse based on format
-        match self.format {
-            SchemeFormat::ITerm2 => self.parse_iterm2(&content_str),
-            SchemeFormat::Json => self.parse_json(&content_str),
-            SchemeFormat::Toml => self.parse_toml(&content_str),
-            SchemeFormat::Yaml => self.parse_yaml(&content_str),
-            SchemeFormat::XResources => self.parse_xresources(&content_str),
-        }
+        self.parse(&content_str)
     }
 
     fn list_available(&self) -> Result<Vec<String>> {
@@ -167,6 +187,25 @@ impl ColorSchemeProvider for GitHubSchemeProvider {
 }
 
 impl GitHubSchemeProvider {
+    /// Parse a scheme file in this provider's format
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - Contents of the scheme file
+    ///
+    /// # Returns
+    ///
+    /// The palette, or an error if the file isn't a scheme in the format
+    pub fn parse(&self, content: &str) -> Result<ColorPalette> {
+        match self.format {
+            SchemeFormat::ITerm2 => self.parse_iterm2(content),
+            SchemeFormat::Json => self.parse_json(content),
+            SchemeFormat::Toml => self.parse_toml(content),
+            SchemeFormat::Yaml => self.parse_yaml(content),
+            SchemeFormat::XResources => self.parse_xresources(content),
+        }
+    }
+
     fn parse_iterm2(&self, content: &str) -> Result<ColorPalette> {
         // Parse the plist XML format
         // iTerm2 colors are in a plist with color components as real numbers 0-1
@@ -390,19 +429,18 @@ impl GitHubSchemeProvider {
         })
     }
 
-    fn json_to_palette(&self, _json: &serde_json::Value) -> Result<ColorPalette> {
-        // Placeholder - would need to handle specific JSON schema
-        Err(anyhow::anyhow!("JSON parsing not yet fully implemented"))
+    fn json_to_palette(&self, json: &serde_json::Value) -> Result<ColorPalette> {
+        keyed_palette(json, "JSON")
     }
 
-    fn toml_to_palette(&self, _toml: &toml::Value) -> Result<ColorPalette> {
-        // Placeholder
-        Err(anyhow::anyhow!("TOML parsing not yet fully implemented"))
+    fn toml_to_palette(&self, toml: &toml::Value) -> Result<ColorPalette> {
+        let table = serde_json::to_value(toml).context("Failed to read TOML color scheme")?;
+        keyed_palette(&table, "TOML")
     }
 
-    fn yaml_to_palette(&self, _yaml: &serde_yaml::Value) -> Result<ColorPalette> {
-        // Placeholder
-        Err(anyhow::anyhow!("YAML parsing not yet fully implemented"))
+    fn yaml_to_palette(&self, yaml: &serde_yaml::Value) -> Result<ColorPalette> {
+        let table = serde_json::to_value(yaml).context("Failed to read YAML color scheme")?;
+        keyed_palette(&table, "YAML")
     }
 
     fn parse_ghostty_toml(&self, toml: &toml::Value) -> Result<ColorPalette> {
@@ -438,3 +476,172 @@ impl GitHubSchemeProvider {
         })
     }
 }
+
+/// Names of the ANSI colors 0-7 in keyed schemes; Windows Terminal calls
+/// magenta purple
+const ANSI_NAMES: [&[&str]; 8] = [
+    &["black"],
+    &["red"],
+    &["green"],
+    &["yellow"],
+    &["blue"],
+    &["magenta", "purple"],
+    &["cyan"],
+    &["white"],
+];
+
+/// The base16 color each ANSI color 0-15 is shown in
+const BASE16_ANSI: [&str; 16] = [
+    "base00", "base08", "base0b", "base0a", "base0d", "base0e", "base0c", "base05", "base03",
+    "base08", "base0b", "base0a", "base0d", "base0e", "base0c", "base07",
+];
+
+/// ANSI colors 0-15 of a scheme that doesn't set them
+const DEFAULT_ANSI: [&str; 16] = [
+    "#000000", "#ff0000", "#00ff00", "#ffff00", "#0000ff", "#ff00ff", "#00ffff", "#ffffff",
+    "#808080", "#ff8080", "#80ff80", "#ffff80", "#8080ff", "#ff80ff", "#80ffff", "#ffffff",
+];
+
+/// Palette of a JSON, YAML or TOML scheme
+///
+/// Colors are looked up by key, ignoring case and separators, in the layouts
+/// these formats are written in: flat keys as Windows Terminal and this
+/// crate's cache write them (`brightBlack`, `bright_black`), Alacritty's
+/// `colors.normal` and `colors.bright` tables, numbered `color0` to
+/// `color15`, and base16's `base00` to `base0F`. Colors may start with `#`,
+/// `0x` or nothing.
+///
+/// # Arguments
+///
+/// * `table` - The parsed scheme
+/// * `format` - Name of the format, for errors
+///
+/// # Returns
+///
+/// The palette, or an error if the scheme has no background or foreground
+fn keyed_palette(table: &serde_json::Value, format: &str) -> Result<ColorPalette> {
+    let mut colors = HashMap::new();
+    collect_colors(table, "", &mut colors);
+    let find = |keys: &[String]| keys.iter().find_map(|key| colors.get(key).cloned());
+    let base16 = |key: &str| [key.to_string(), format!("palette.{key}")];
+
+    let ansi: [String; 16] = std::array::from_fn(|index| {
+        let bright = index >= 8;
+        let mut keys = Vec::new();
+        for name in ANSI_NAMES[index % 8] {
+            if bright {
+                keys.extend([
+                    format!("bright{name}"),
+                    format!("colors.bright.{name}"),
+                    format!("bright.{name}"),
+                ]);
+            } else {
+                keys.extend([
+                    name.to_string(),
+                    format!("colors.normal.{name}"),
+                    format!("normal.{name}"),
+                ]);
+            }
+        }
+        keys.push(format!("color{index}"));
+        keys.extend(base16(BASE16_ANSI[index]));
+        find(&keys).unwrap_or_else(|| DEFAULT_ANSI[index].to_string())
+    });
+    let special = |name: &str, table: &str, base: &str| {
+        let mut keys = vec![
+            name.to_string(),
+            format!("colors.{table}.{name}"),
+            format!("{table}.{name}"),
+        ];
+        keys.extend(base16(base));
+        find(&keys)
+    };
+
+    let (Some(background), Some(foreground)) = (
+        special("background", "primary", "base00"),
+        special("foreground", "primary", "base05"),
+    ) else {
+        anyhow::bail!("No background and foreground colors in the {format} color scheme");
+    };
+    let cursor = find(&[
+        "cursorcolor".to_string(),
+        "cursor".to_string(),
+        "colors.cursor.cursor".to_string(),
+        "cursor.cursor".to_string(),
+    ]);
+    let selection = find(&[
+        "selectionbackground".to_string(),
+        "selection".to_string(),
+        "colors.selection.background".to_string(),
+        "selection.background".to_string(),
+        "base02".to_string(),
+        "palette.base02".to_string(),
+    ]);
+
+    let [black, red, green, yellow, blue, magenta, cyan, white, bright_black, bright_red, bright_green, bright_yellow, bright_blue, bright_magenta, bright_cyan, bright_white] =
+        ansi;
+    Ok(ColorPalette {
+        black,
+        red,
+        green,
+        yellow,
+        blue,
+        magenta,
+        cyan,
+        white,
+        bright_black,
+        bright_red,
+        bright_green,
+        bright_yellow,
+        bright_blue,
+        bright_magenta,
+        bright_cyan,
+        bright_white,
+        background,
+        foreground,
+        cursor,
+        selection,
+    })
+}
+
+/// Collect the colors of a parsed scheme by key path
+///
+/// Keys are lowercased without separators and nested tables are joined with
+/// `.`, so Alacritty's `[colors.normal] black` is `colors.normal.black`.
+fn collect_colors(value: &serde_json::Value, path: &str, colors: &mut HashMap<String, String>) {
+    match value {
+        serde_json::Value::Object(table) => {
+            for (key, value) in table {
+                let key: String = key
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .flat_map(char::to_lowercase)
+                    .collect();
+                let path = if path.is_empty() {
+                    key
+                } else {
+                    format!("{path}.{key}")
+                };
+                collect_colors(value, &path, colors);
+            }
+        }
+        serde_json::Value::String(text) => {
+            if let Some(color) = hex_color(text) {
+                colors.insert(path.to_string(), color);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A hex color written as `#rrggbb`, `0xrrggbb` or `rrggbb`, as `#rrggbb`
+fn hex_color(text: &str) -> Option<String> {
+    let text = text.trim();
+    let hex = text
+        .strip_prefix('#')
+        .or_else(|| text.strip_prefix("0x"))
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    (matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| format!("#{hex}"))
+}
@@ -1,41 +1,83 @@
 use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 
 use super::github::{GitHubSchemeProvider, SchemeFormat};
 use super::{ColorPalette, ColorSchemeProvider};
 
 /// Local file system provider for color schemes
-#[allow(dead_code)]
+///
+/// Schemes in different formats can share the directory: each file is parsed
+/// in the format its extension names (`.itermcolors`, `.json`, `.yaml`,
+/// `.toml` or `.Xresources`), and files without one in `format`. A scheme is
+/// fetched by its file name, with or without the extension.
 pub struct LocalSchemeProvider {
+    /// Directory holding the scheme files
     pub directory: String,
+    /// Format of the files whose extension doesn't name one
     pub format: SchemeFormat,
 }
 
+impl LocalSchemeProvider {
+    /// The scheme files in the directory, without hidden files
+    fn files(&self) -> Result<Vec<PathBuf>> {
+        let entries = std::fs::read_dir(&self.directory)
+            .with_context(|| format!("Failed to read schemes directory {}", self.directory))?;
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && !file_name(path).starts_with('.'))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// The file of a scheme: one named `name`, or `name` with an extension
+    fn find(&self, name: &str) -> Result<PathBuf> {
+        let exact = Path::new(&self.directory).join(name);
+        if exact.is_file() {
+            return Ok(exact);
+        }
+        self.files()?
+            .into_iter()
+            .find(|path| path.file_stem().and_then(|stem| stem.to_str()) == Some(name))
+            .with_context(|| format!("No color scheme '{}' in {}", name, self.directory))
+    }
+
+    /// The format of a scheme file, from its extension
+    fn format_of(&self, path: &Path) -> SchemeFormat {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(SchemeFormat::from_extension)
+            .unwrap_or_else(|| self.format.clone())
+    }
+}
+
+/// File name of a path, or an empty string if it has none
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
 impl ColorSchemeProvider for LocalSchemeProvider {
     fn fetch(&self, name: &str, _variant: Option<&str>) -> Result<ColorPalette> {
-        let file_path = format!("{}/{}", self.directory, name);
+        let file_path = self.find(name)?;
         let content = std::fs::read_to_string(&file_path)
-            .with_context(|| format!("Failed to read scheme from {}", file_path))?;
+            .with_context(|| format!("Failed to read scheme from {}", file_path.display()))?;
 
-        match self.format {
-            SchemeFormat::XResources => {
-                GitHubSchemeProvider::new("", self.format.clone()).parse_xresources(&content)
-            }
-            _ => Err(anyhow::anyhow!("Local format not yet implemented")),
-        }
+        GitHubSchemeProvider::new("", self.format_of(&file_path))
+            .parse(&content)
+            .with_context(|| format!("Failed to parse scheme {}", file_path.display()))
     }
 
     fn list_available(&self) -> Result<Vec<String>> {
-        let entries =
-            std::fs::read_dir(&self.directory).context("Failed to read schemes directory")?;
-
-        let schemes = entries
-            .filter_map(|entry| {
-                entry
-                    .ok()
-                    .and_then(|e| e.file_name().to_str().map(|s| s.to_string()))
-            })
+        let mut schemes: Vec<String> = self
+            .files()?
+            .iter()
+            .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()))
+            .map(|stem| stem.to_string())
             .collect();
-
+        schemes.dedup();
         Ok(schemes)
     }
 
@@ -43,3 +85,152 @@ impl ColorSchemeProvider for LocalSchemeProvider {
         "Local"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A provider over a directory with one scheme file
+    fn provider(file: &str, content: &str) -> (TempDir, LocalSchemeProvider) {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(file), content).unwrap();
+        let provider = LocalSchemeProvider {
+            directory: dir.path().to_str().unwrap().to_string(),
+            format: SchemeFormat::XResources,
+        };
+        (dir, provider)
+    }
+
+    #[test]
+    fn test_json_scheme() {
+        // Windows Terminal
+        let (_dir, provider) = provider(
+            "Campbell.json",
+            r##"{
+                "name": "Campbell",
+                "background": "#0C0C0C",
+                "foreground": "#CCCCCC",
+                "red": "#C50F1F",
+                "purple": "#881798",
+                "brightBlack": "#767676",
+                "cursorColor": "#FFFFFF",
+                "selectionBackground": "#FFFFFF"
+            }"##,
+        );
+        let palette = provider.fetch("Campbell", None).unwrap();
+        assert_eq!(palette.background, "#0C0C0C");
+        assert_eq!(palette.foreground, "#CCCCCC");
+        assert_eq!(palette.red, "#C50F1F");
+        assert_eq!(palette.magenta, "#881798");
+        assert_eq!(palette.bright_black, "#767676");
+        assert_eq!(palette.cursor.as_deref(), Some("#FFFFFF"));
+        // Colors the scheme doesn't set keep their defaults
+        assert_eq!(palette.green, "#00ff00");
+    }
+
+    #[test]
+    fn test_yaml_scheme() {
+        // base16
+        let (_dir, provider) = provider(
+            "ocean.yaml",
+            concat!(
+                "scheme: \"Ocean\"\n",
+                "base00: \"2b303b\"\n",
+                "base02: \"4f5b66\"\n",
+                "base05: \"c0c5ce\"\n",
+                "base08: \"bf616a\"\n",
+                "base0D: \"8fa1b3\"\n",
+            ),
+        );
+        let palette = provider.fetch("ocean.yaml", None).unwrap();
+        assert_eq!(palette.background, "#2b303b");
+        assert_eq!(palette.foreground, "#c0c5ce");
+        assert_eq!(palette.red, "#bf616a");
+        assert_eq!(palette.bright_red, "#bf616a");
+        assert_eq!(palette.blue, "#8fa1b3");
+        assert_eq!(palette.selection.as_deref(), Some("#4f5b66"));
+    }
+
+    #[test]
+    fn test_toml_scheme() {
+        // Alacritty
+        let (_dir, provider) = provider(
+            "nord.toml",
+            concat!(
+                "[colors.primary]\n",
+                "background = \"#2e3440\"\n",
+                "foreground = \"#d8dee9\"\n",
+                "[colors.normal]\n",
+                "blue = \"0x81a1c1\"\n",
+                "[colors.bright]\n",
+                "black = \"#4c566a\"\n",
+                "[colors.cursor]\n",
+                "cursor = \"#d8dee9\"\n",
+            ),
+        );
+        let palette = provider.fetch("nord", None).unwrap();
+        assert_eq!(palette.background, "#2e3440");
+        assert_eq!(palette.blue, "#81a1c1");
+        assert_eq!(palette.bright_black, "#4c566a");
+        assert_eq!(palette.cursor.as_deref(), Some("#d8dee9"));
+    }
+
+    #[test]
+    fn test_xresources_scheme() {
+        // No extension, so in the provider's format
+        let (_dir, provider) = provider(
+            "dracula",
+            concat!(
+                "! Dracula\n",
+                "*.foreground: #F8F8F2\n",
+                "*.background: #282A36\n",
+                "*.color1: #FF5555\n",
+            ),
+        );
+        let palette = provider.fetch("dracula", None).unwrap();
+        assert_eq!(palette.background, "#282A36");
+        assert_eq!(palette.foreground, "#F8F8F2");
+        assert_eq!(palette.red, "#FF5555");
+    }
+
+    #[test]
+    fn test_iterm2_scheme() {
+        let color = |key: &str, red: f64, green: f64, blue: f64| {
+            format!(
+                "<key>{key}</key><dict>\
+                 <key>Blue Component</key><real>{blue}</real>\
+                 <key>Green Component</key><real>{green}</real>\
+                 <key>Red Component</key><real>{red}</real></dict>"
+            )
+        };
+        let plist = format!(
+            "<plist version=\"1.0\"><dict>{}{}{}</dict></plist>",
+            color("Ansi 4 Color", 0.0, 0.0, 1.0),
+            color("Background Color", 0.0, 0.0, 0.0),
+            color("Foreground Color", 1.0, 1.0, 1.0),
+        );
+        let (_dir, provider) = provider("Basic.itermcolors", &plist);
+        let palette = provider.fetch("Basic", None).unwrap();
+        assert_eq!(palette.blue, "#0000FF");
+        assert_eq!(palette.background, "#000000");
+        assert_eq!(palette.foreground, "#FFFFFF");
+    }
+
+    #[test]
+    fn test_list_and_missing_schemes() {
+        let (dir, provider) = provider("b.toml", "background = \"#000000\"");
+        std::fs::write(dir.path().join("a.json"), "{}").unwrap();
+        std::fs::write(dir.path().join(".hidden"), "").unwrap();
+
+        assert_eq!(provider.list_available().unwrap(), vec!["a", "b"]);
+        assert!(provider
+            .fetch("c", None)
+            .unwrap_err()
+            .to_string()
+            .contains("No color scheme 'c'"));
+        // A scheme without a foreground isn't one
+        let error = format!("{:#}", provider.fetch("a", None).unwrap_err());
+        assert!(error.contains("No background and foreground colors in the JSON color scheme"));
+    }
+}
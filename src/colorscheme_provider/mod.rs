@@ -2,16 +2,20 @@ use anyhow::Result;
 
 // Module declarations
 pub mod cached;
+pub mod curated;
 pub mod github;
 pub mod local;
 pub mod palette;
+pub mod search;
 
 // Re-exports for convenience
 pub use cached::CachedProvider;
+pub use curated::{CuratedProvider, CURATED_SOURCES};
 pub use github::{GitHubSchemeProvider, SchemeFormat};
 #[allow(unused_imports)] // Public API export
 pub use local::LocalSchemeProvider;
 pub use palette::{ColorPalette, ToCss};
+pub use search::SchemeNotFound;
 
 /// Trait for color scheme providers - the "type class" pattern
 pub trait ColorSchemeProvider {
@@ -19,11 +23,54 @@ pub trait ColorSchemeProvider {
     fn fetch(&self, name: &str, variant: Option<&str>) -> Result<ColorPalette>;
 
     /// List available schemes
-    #[allow(dead_code)]
     fn list_available(&self) -> Result<Vec<String>>;
 
     /// Get provider name
     fn provider_name(&self) -> &str;
+
+    /// Fetch a color scheme by a loosely written name
+    ///
+    /// A name the provider doesn't have is looked up among the schemes it
+    /// lists, ignoring case and separators, so "Rose Pine Moon" finds
+    /// `rose-pine-moon`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the scheme
+    /// * `variant` - Variant of the scheme
+    ///
+    /// # Returns
+    ///
+    /// The palette, or a [`SchemeNotFound`] error with the closest names when
+    /// there is no such scheme; when the schemes can't be listed, the error of
+    /// the fetch
+    fn fetch_matching(&self, name: &str, variant: Option<&str>) -> Result<ColorPalette> {
+        let error = match self.fetch(name, variant) {
+            Ok(palette) => return Ok(palette),
+            Err(error) => error,
+        };
+        let Ok(names) = self.list_available() else {
+            return Err(error);
+        };
+
+        match search::resolve(name, &names) {
+            Some(found) if found != name => {
+                println!("🎨 Using color scheme '{}' for '{}'", found, name);
+                self.fetch(found, variant)
+            }
+            Some(_) => Err(error),
+            None => Err(SchemeNotFound {
+                name: name.to_string(),
+                provider: self.provider_name().to_string(),
+                suggestions: search::rank(name, &names)
+                    .into_iter()
+                    .take(5)
+                    .map(|found| found.name)
+                    .collect(),
+            }
+            .into()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -159,22 +206,19 @@ mod tests {
 
         assert_eq!(provider.provider_name(), "Local");
 
-        // Test error case for unsupported format with temporary file
+        // Test error case for an invalid file with temporary file
         let temp_dir = tempdir().unwrap();
         let test_file = temp_dir.path().join("test.json");
         std::fs::write(&test_file, "test content").unwrap();
 
-        let unsupported_provider = LocalSchemeProvider {
+        let invalid_provider = LocalSchemeProvider {
             directory: temp_dir.path().to_str().unwrap().to_string(),
             format: SchemeFormat::Json,
         };
 
-        let result = unsupported_provider.fetch("test.json", None);
+        let result = invalid_provider.fetch("test.json", None);
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("Local format not yet implemented"));
+        assert!(format!("{:#}", result.unwrap_err()).contains("Failed to parse JSON color scheme"));
     }
 
     #[test]
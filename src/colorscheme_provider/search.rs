@@ -0,0 +1,239 @@
+//! Fuzzy matching of color scheme names
+//!
+//! Providers name their files differently: "Rose Pine Moon", "rose-pine-moon"
+//! and "rose_pine_moon" are the same scheme, and so is "Rosé Pine Moon".
+//! Names are compared by their letters and digits only, ignoring case and
+//! accents, and names that don't match are
+//! ranked by how close they come, so a misspelled scheme gets suggestions.
+
+use std::fmt;
+
+/// Score of a name that matches the query exactly, up to case and separators
+const EXACT: u32 = 1000;
+
+/// A scheme name and how well it matches a query, higher is better
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeMatch {
+    /// Name of the scheme, as the provider lists it
+    pub name: String,
+    /// How well it matches
+    pub score: u32,
+}
+
+/// A configured scheme that no provider has
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeNotFound {
+    /// The configured name
+    pub name: String,
+    /// Provider that was searched
+    pub provider: String,
+    /// The closest names, best first
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for SchemeNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Color scheme '{}' not found in {}",
+            self.name, self.provider
+        )?;
+        match self.suggestions.as_slice() {
+            [] => Ok(()),
+            [only] => write!(f, "; did you mean '{only}'?"),
+            [rest @ .., last] => {
+                let rest = rest
+                    .iter()
+                    .map(|name| format!("'{name}'"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "; did you mean {rest} or '{last}'?")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemeNotFound {}
+
+/// A name reduced to its lowercase letters and digits, without accents
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .map(without_accent)
+        .collect()
+}
+
+/// The letter a Latin letter with an accent is written as without it
+fn without_accent(c: char) -> char {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        c => c,
+    }
+}
+
+/// The name that is the same as `query` up to case and separators
+///
+/// # Arguments
+///
+/// * `query` - A scheme name as configured
+/// * `names` - The names a provider has
+///
+/// # Returns
+///
+/// The provider's name for the scheme, if it has it
+pub fn resolve<'a>(query: &str, names: &'a [String]) -> Option<&'a str> {
+    let query = normalize(query);
+    names
+        .iter()
+        .find(|name| normalize(name) == query)
+        .map(String::as_str)
+}
+
+/// Rank scheme names by how well they match a query
+///
+/// Exact matches come first, then names starting with the query, names
+/// containing it, names containing all its words, and finally names a few
+/// typos away. Names that match in none of these ways are left out.
+///
+/// # Arguments
+///
+/// * `query` - What to look for, e.g. "rose pine"
+/// * `names` - The names to rank
+///
+/// # Returns
+///
+/// The matching names, best first; equal scores are ordered by name
+pub fn rank(query: &str, names: &[String]) -> Vec<SchemeMatch> {
+    let normalized = normalize(query);
+    let words: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .map(normalize)
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut matches: Vec<SchemeMatch> = names
+        .iter()
+        .filter_map(|name| {
+            let score = score(&normalized, &words, &normalize(name))?;
+            Some(SchemeMatch {
+                name: name.clone(),
+                score,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    matches
+}
+
+/// Score of one normalized name against the normalized query and its words
+fn score(query: &str, words: &[String], name: &str) -> Option<u32> {
+    if query.is_empty() {
+        return None;
+    }
+    // Shorter names are closer to the query
+    let extra = name.len().saturating_sub(query.len()).min(99) as u32;
+
+    if name == query {
+        Some(EXACT)
+    } else if name.starts_with(query) {
+        Some(800 - extra)
+    } else if name.contains(query) {
+        Some(600 - extra)
+    } else if words.iter().all(|word| name.contains(word.as_str())) {
+        Some(400 - extra)
+    } else {
+        let distance = edit_distance(query, name);
+        let allowed = (query.chars().count() / 4).max(1);
+        (distance <= allowed).then(|| 200 - (distance.min(19) as u32) * 10)
+    }
+}
+
+/// Levenshtein distance between two strings, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        [
+            "rose-pine-moon",
+            "rose-pine-dawn",
+            "rose-pine",
+            "Dracula",
+            "Dracula+",
+            "Tokyo Night Storm",
+            "Solarized Dark",
+        ]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn test_resolve() {
+        let names = names();
+        assert_eq!(resolve("Rose Pine Moon", &names), Some("rose-pine-moon"));
+        assert_eq!(
+            resolve("tokyo_night_storm", &names),
+            Some("Tokyo Night Storm")
+        );
+        assert_eq!(resolve("Rosé Pine Moon", &names), Some("rose-pine-moon"));
+        assert_eq!(resolve("Rose Pine Moo", &names), None);
+    }
+
+    #[test]
+    fn test_rank() {
+        let names = names();
+        let ranked = |query| {
+            rank(query, &names)
+                .into_iter()
+                .map(|found| found.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranked("rose pine"),
+            vec!["rose-pine", "rose-pine-dawn", "rose-pine-moon"]
+        );
+        assert_eq!(ranked("dracula")[0], "Dracula");
+        // All words, in any order
+        assert_eq!(ranked("storm tokyo"), vec!["Tokyo Night Storm"]);
+        // Typos
+        assert_eq!(ranked("Solarised Dark"), vec!["Solarized Dark"]);
+        assert!(ranked("gruvbox").is_empty());
+    }
+
+    #[test]
+    fn test_scheme_not_found_message() {
+        let error = SchemeNotFound {
+            name: "Rose Pine Moo".to_string(),
+            provider: "GitHub".to_string(),
+            suggestions: vec!["rose-pine-moon".to_string(), "rose-pine".to_string()],
+        };
+        assert_eq!(
+            error.to_string(),
+            "Color scheme 'Rose Pine Moo' not found in GitHub; did you mean 'rose-pine-moon' or 'rose-pine'?"
+        );
+    }
+}
@@ -0,0 +1,329 @@
+//! Cross-output consistency checks
+//!
+//! After a build, the outputs of each site (every language and profile) are
+//! checked against the content they were built from and against each other:
+//!
+//! - every published post, page and case study has its HTML page
+//! - the RSS feed lists every published post, and only posts with a page
+//! - every menu entry that isn't external resolves to a file in the output
+//! - every configured PDF variant exists
+//!
+//! Disagreements are reported as [`Discrepancy`] warnings; they never fail
+//! the build. This tree has no sitemap or search index, so there is nothing
+//! to check them against yet.
+
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::content_store::ContentStore;
+use crate::html_generator::filters::join_url;
+use crate::site_config::SiteConfig;
+
+/// File name of the RSS feed in a site's output
+const FEED_FILE: &str = "feed.xml";
+
+/// The output a discrepancy was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Output {
+    /// The HTML pages
+    Html,
+    /// The RSS feed
+    Feed,
+    /// The navigation menu
+    Navigation,
+    /// The PDFs
+    Pdf,
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Output::Html => "html",
+            Output::Feed => "feed",
+            Output::Navigation => "navigation",
+            Output::Pdf => "pdf",
+        })
+    }
+}
+
+/// Outputs that don't agree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    /// The output that is wrong
+    pub output: Output,
+    /// The file or URL concerned
+    pub subject: String,
+    /// What is wrong
+    pub message: String,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.output, self.subject, self.message)
+    }
+}
+
+/// One built site: the whole site, a language or a profile
+#[derive(Debug, Clone)]
+pub struct SiteCheck {
+    /// Directory the site was built into
+    pub output_dir: PathBuf,
+    /// Site configuration the site was built with
+    pub site_config: SiteConfig,
+    /// Content the site was built from
+    pub store: ContentStore,
+    /// PDFs the configured variants were written to
+    pub pdfs: Vec<PathBuf>,
+}
+
+impl SiteCheck {
+    /// Check the site's outputs against each other
+    ///
+    /// # Returns
+    ///
+    /// The discrepancies, empty if the outputs agree
+    pub fn run(&self) -> Vec<Discrepancy> {
+        let mut discrepancies = self.pages();
+        discrepancies.extend(self.feed());
+        discrepancies.extend(self.navigation());
+        discrepancies.extend(self.pdfs.iter().filter(|pdf| !pdf.is_file()).map(|pdf| {
+            Discrepancy {
+                output: Output::Pdf,
+                subject: pdf.display().to_string(),
+                message: "configured PDF variant is missing".to_string(),
+            }
+        }));
+        discrepancies
+    }
+
+    /// Pages missing for published posts, pages and case studies
+    fn pages(&self) -> Vec<Discrepancy> {
+        let posts = self
+            .store
+            .posts()
+            .iter()
+            .filter(|_| self.store.has_blog())
+            .map(|post| format!("blog/{}.html", post.slug));
+        let pages = self
+            .store
+            .pages()
+            .iter()
+            .map(|page| format!("{}.html", page.slug));
+        let projects = self
+            .store
+            .projects()
+            .iter()
+            .map(|detail| format!("projects/{}.html", detail.case_study.slug));
+
+        posts
+            .chain(pages)
+            .chain(projects)
+            .map(|path| self.output_dir.join(path))
+            .filter(|path| !path.is_file())
+            .map(|path| Discrepancy {
+                output: Output::Html,
+                subject: path.display().to_string(),
+                message: "published content has no page".to_string(),
+            })
+            .collect()
+    }
+
+    /// Posts missing from the feed, and feed items without a page
+    fn feed(&self) -> Vec<Discrepancy> {
+        let feed_path = self.output_dir.join(FEED_FILE);
+        let base_url = match self.site_config.base_url.as_deref() {
+            Some(base_url) if self.site_config.has_rss_feed() && self.store.has_blog() => base_url,
+            _ => return Vec::new(),
+        };
+        let subject = feed_path.display().to_string();
+        let Ok(feed) = fs::read_to_string(&feed_path) else {
+            return vec![Discrepancy {
+                output: Output::Feed,
+                subject,
+                message: "the feed is enabled but wasn't generated".to_string(),
+            }];
+        };
+
+        let listed = feed_links(&feed);
+        let missing = self.store.posts().iter().filter_map(|post| {
+            let link = join_url(&format!("blog/{}.html", post.slug), base_url);
+            (!listed.contains(&link)).then(|| Discrepancy {
+                output: Output::Feed,
+                subject: subject.clone(),
+                message: format!("published post '{}' isn't in the feed", post.slug),
+            })
+        });
+        let prefix = format!("{}/", base_url.trim_end_matches('/'));
+        let dangling = listed.iter().filter_map(|link| {
+            let page = self.output_dir.join(link.strip_prefix(&prefix)?);
+            (!page.is_file()).then(|| Discrepancy {
+                output: Output::Feed,
+                subject: subject.clone(),
+                message: format!("item {link} has no page"),
+            })
+        });
+        missing.chain(dangling).collect()
+    }
+
+    /// Menu entries that don't resolve to a file
+    fn navigation(&self) -> Vec<Discrepancy> {
+        self.site_config
+            .menu
+            .iter()
+            .filter(|item| !item.external.unwrap_or(false))
+            .filter(|item| !resolves(&self.output_dir, &item.path))
+            .map(|item| Discrepancy {
+                output: Output::Navigation,
+                subject: item.path.clone(),
+                message: format!(
+                    "menu entry '{}' has no page in {}",
+                    item.label,
+                    self.output_dir.display()
+                ),
+            })
+            .collect()
+    }
+}
+
+/// The item links of an RSS feed
+fn feed_links(feed: &str) -> BTreeSet<String> {
+    static ITEM_LINK: OnceLock<Regex> = OnceLock::new();
+    let item_link = ITEM_LINK
+        .get_or_init(|| Regex::new(r"(?s)<item>.*?<link>([^<]+)</link>.*?</item>").unwrap());
+    item_link
+        .captures_iter(feed)
+        .map(|captures| captures[1].replace("&amp;", "&"))
+        .collect()
+}
+
+/// Whether a menu path resolves to a file in the output
+///
+/// Links elsewhere, fragments and `mailto:` links always resolve; a directory
+/// resolves to its `index.html`, and a path without an extension to its
+/// `.html` page.
+fn resolves(output_dir: &Path, path: &str) -> bool {
+    if path.contains("://") || path.starts_with("mailto:") || path.starts_with('#') {
+        return true;
+    }
+    let path = path
+        .split(['#', '?'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('/');
+    let file = output_dir.join(path);
+    if path.is_empty() || path.ends_with('/') {
+        return file.join("index.html").is_file();
+    }
+    file.is_file()
+        || file.join("index.html").is_file()
+        || (file.extension().is_none() && file.with_extension("html").is_file())
+}
+
+/// Print the discrepancies of a build, if there are any
+pub fn print_warnings(discrepancies: &[Discrepancy]) {
+    if discrepancies.is_empty() {
+        return;
+    }
+    println!("⚠️  Outputs disagree ({}):", discrepancies.len());
+    for discrepancy in discrepancies {
+        println!("   {discrepancy}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::Cv;
+    use serde_json::json;
+
+    #[test]
+    fn test_consistent_site() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = dir.path().join("content");
+        let output_dir = dir.path().join("dist");
+        fs::create_dir_all(&content).unwrap();
+        fs::create_dir_all(output_dir.join("blog")).unwrap();
+        fs::write(
+            content.join("hello.md"),
+            "---\ntitle: Hello\ndate: 2025-01-01\n---\nHello",
+        )
+        .unwrap();
+        fs::write(
+            content.join("second.md"),
+            "---\ntitle: Second\ndate: 2025-02-01\n---\nAgain",
+        )
+        .unwrap();
+
+        let site_config: SiteConfig = serde_json::from_value(json!({
+            "menu": [
+                { "label": "Home", "path": "index.html" },
+                { "label": "Blog", "path": "/blog/" },
+                { "label": "About", "path": "about" },
+                { "label": "GitHub", "path": "https://github.com", "external": true }
+            ],
+            "blog": { "directory": content, "rss_feed": true },
+            "base_url": "https://example.com"
+        }))
+        .unwrap();
+        let store = ContentStore::load(&Cv::create_minimal(), &site_config).unwrap();
+        for file in ["index.html", "blog/index.html", "blog/hello.html"] {
+            fs::write(output_dir.join(file), "").unwrap();
+        }
+        fs::write(
+            output_dir.join(FEED_FILE),
+            concat!(
+                "<rss><channel><link>https://example.com/blog.html</link>",
+                "<item><link>https://example.com/blog/hello.html</link></item>",
+                "<item><link>https://example.com/blog/old.html</link></item>",
+                "</channel></rss>"
+            ),
+        )
+        .unwrap();
+
+        let check = SiteCheck {
+            output_dir: output_dir.clone(),
+            site_config,
+            store,
+            pdfs: vec![output_dir.join("cv.pdf")],
+        };
+        let found: Vec<String> = check
+            .run()
+            .iter()
+            .map(|discrepancy| {
+                discrepancy
+                    .to_string()
+                    .replace(&output_dir.display().to_string(), "dist")
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                "[html] dist/blog/second.html: published content has no page",
+                "[feed] dist/feed.xml: published post 'second' isn't in the feed",
+                "[feed] dist/feed.xml: item https://example.com/blog/old.html has no page",
+                "[navigation] about: menu entry 'About' has no page in dist",
+                "[pdf] dist/cv.pdf: configured PDF variant is missing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolves() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("blog")).unwrap();
+        fs::write(dir.path().join("index.html"), "").unwrap();
+        fs::write(dir.path().join("cv.html"), "").unwrap();
+        fs::write(dir.path().join("blog/index.html"), "").unwrap();
+
+        assert!(resolves(dir.path(), "/"));
+        assert!(resolves(dir.path(), "cv.html#skills"));
+        assert!(resolves(dir.path(), "/cv"));
+        assert!(resolves(dir.path(), "blog"));
+        assert!(resolves(dir.path(), "mailto:ada@example.com"));
+        assert!(!resolves(dir.path(), "/projects.html"));
+    }
+}
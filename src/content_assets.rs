@@ -0,0 +1,255 @@
+//! Files referenced from markdown
+//!
+//! Posts, pages and case studies refer to images and other files in the
+//! content tree with `@/`, wherever the markdown file itself is:
+//! `![Diagram](@/images/diagram.png)` is `content/images/diagram.png`. The
+//! build checks that every referenced file exists, copies it to `media/` in
+//! the output and points the reference at the copy, relative to the page it
+//! ends up on. Referenced images load lazily, and get their size and
+//! fingerprint like every other image on the site.
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::cv_schema::closest;
+use crate::front_matter::ContentType;
+
+/// Root of the content tree `@/` references resolve against
+pub const CONTENT_DIR: &str = "content";
+
+/// Directory in the output the referenced files are copied to
+pub const MEDIA_DIR: &str = "media";
+
+/// Resolve the `@/` references of rendered markdown
+///
+/// # Arguments
+///
+/// * `html` - Rendered markdown of a post, page or case study
+/// * `content` - What it is, which decides where its page is
+/// * `slug` - Its slug, for errors
+/// * `content_dir` - Root of the content tree
+///
+/// # Returns
+///
+/// The HTML pointing at the copies in `media/`, and the referenced files
+/// relative to the content tree, or an error naming every reference to a
+/// file that doesn't exist
+pub fn resolve_assets(
+    html: &str,
+    content: ContentType,
+    slug: &str,
+    content_dir: &Path,
+) -> Result<(String, BTreeSet<String>)> {
+    if !html.contains("=\"@/") {
+        return Ok((html.to_string(), BTreeSet::new()));
+    }
+
+    static IMAGE: OnceLock<Regex> = OnceLock::new();
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let image = IMAGE
+        .get_or_init(|| Regex::new(r#"<img\s[^>]*\bsrc="@/[^>]*>"#).expect("valid image regex"));
+    let reference = REFERENCE.get_or_init(|| {
+        Regex::new(r#"(?P<attribute>\s(?:src|href)=")@/(?P<path>[^"?#]*)(?P<suffix>[^"]*)""#)
+            .expect("valid reference regex")
+    });
+
+    let lazy = image.replace_all(html, |captures: &Captures| {
+        let tag = &captures[0];
+        if tag.contains(" loading=") {
+            return tag.to_string();
+        }
+        let (start, end) = match tag.strip_suffix("/>") {
+            Some(start) => (start, " />"),
+            None => (&tag[..tag.len() - 1], ">"),
+        };
+        format!(
+            "{} loading=\"lazy\" decoding=\"async\"{end}",
+            start.trim_end()
+        )
+    });
+
+    // Posts are in blog/, while pages and case studies resolve from the root
+    let prefix = match content {
+        ContentType::Post => "../",
+        ContentType::Page | ContentType::Project => "",
+    };
+    let mut assets = BTreeSet::new();
+    let mut missing = Vec::new();
+    let resolved = reference.replace_all(&lazy, |captures: &Captures| {
+        let written = &captures["path"];
+        let Some(path) = normalize(&percent_decode(written)) else {
+            missing.push(format!("@/{written} is outside {}", content_dir.display()));
+            return captures[0].to_string();
+        };
+        if !content_dir.join(&path).is_file() {
+            let suggestion = suggest(content_dir, &path)
+                .map(|known| format!(", did you mean @/{known}?"))
+                .unwrap_or_default();
+            missing.push(format!(
+                "@/{written} not found in {}{suggestion}",
+                content_dir.display()
+            ));
+        }
+        let url = format!(
+            "{}{prefix}{MEDIA_DIR}/{written}{}\"",
+            &captures["attribute"], &captures["suffix"]
+        );
+        assets.insert(path);
+        url
+    });
+
+    if !missing.is_empty() {
+        anyhow::bail!("{content} '{slug}': {}", missing.join("; "));
+    }
+    Ok((resolved.into_owned(), assets))
+}
+
+/// Copy referenced files from the content tree to `media/` in the output
+///
+/// # Arguments
+///
+/// * `assets` - The files, relative to the content tree
+/// * `content_dir` - Root of the content tree
+/// * `output_dir` - Directory the site is generated into
+///
+/// # Returns
+///
+/// The number of files copied
+pub fn copy_assets(
+    assets: &BTreeSet<String>,
+    content_dir: &Path,
+    output_dir: &Path,
+) -> Result<usize> {
+    for asset in assets {
+        let source = content_dir.join(asset);
+        let target = output_dir.join(MEDIA_DIR).join(asset);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::copy(&source, &target).with_context(|| {
+            format!(
+                "Failed to copy {} to {}",
+                source.display(),
+                target.display()
+            )
+        })?;
+    }
+    Ok(assets.len())
+}
+
+/// A path in the content tree without `.` and `..`, or None if it leaves the tree
+fn normalize(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+    (!segments.is_empty()).then(|| segments.join("/"))
+}
+
+/// Decode the `%XX` escapes markdown rendering adds to URLs
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| path.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The file in the same directory of the content tree with the closest name
+fn suggest(content_dir: &Path, path: &str) -> Option<String> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let names: Vec<String> = fs::read_dir(content_dir.join(dir))
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect();
+    closest(name, names.iter()).map(|known| match dir {
+        "" => known.clone(),
+        dir => format!("{dir}/{known}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("images")).unwrap();
+        fs::write(dir.path().join("images/my diagram.png"), "png").unwrap();
+        fs::write(dir.path().join("talk.pdf"), "pdf").unwrap();
+
+        let html = concat!(
+            "<p><img src=\"@/images/my%20diagram.png\" alt=\"Diagram\" />\n",
+            "<a href=\"@/talk.pdf#page=2\">Slides</a> <a href=\"https://example.com\">Elsewhere</a></p>"
+        );
+        let (post, assets) = resolve_assets(html, ContentType::Post, "hello", dir.path()).unwrap();
+        assert_eq!(
+            post,
+            concat!(
+                "<p><img src=\"../media/images/my%20diagram.png\" alt=\"Diagram\" loading=\"lazy\" decoding=\"async\" />\n",
+                "<a href=\"../media/talk.pdf#page=2\">Slides</a> <a href=\"https://example.com\">Elsewhere</a></p>"
+            )
+        );
+        assert_eq!(
+            assets,
+            BTreeSet::from(["images/my diagram.png".to_string(), "talk.pdf".to_string()])
+        );
+
+        // Pages resolve from the site root
+        let (page, _) = resolve_assets(html, ContentType::Page, "about", dir.path()).unwrap();
+        assert!(page.contains("src=\"media/images/my%20diagram.png\""));
+
+        let output_dir = dir.path().join("dist");
+        assert_eq!(copy_assets(&assets, dir.path(), &output_dir).unwrap(), 2);
+        assert!(output_dir.join("media/images/my diagram.png").is_file());
+    }
+
+    #[test]
+    fn test_missing_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("images")).unwrap();
+        fs::write(dir.path().join("images/diagram.png"), "png").unwrap();
+
+        let html =
+            "<img src=\"@/images/diagram.pgn\" alt=\"\" /><a href=\"@/../secrets.txt\">x</a>";
+        let error = resolve_assets(html, ContentType::Page, "about", dir.path())
+            .unwrap_err()
+            .to_string();
+        assert!(error.starts_with("page 'about': @/images/diagram.pgn not found in"));
+        assert!(error.contains("did you mean @/images/diagram.png?"));
+        assert!(error.contains("@/../secrets.txt is outside"));
+
+        // Markdown without references is left alone
+        let (html, assets) =
+            resolve_assets("<p>Hi</p>", ContentType::Post, "hello", dir.path()).unwrap();
+        assert_eq!(html, "<p>Hi</p>");
+        assert!(assets.is_empty());
+    }
+}
@@ -0,0 +1,833 @@
+//! Content Security Policy generation
+//!
+//! Pages don't all need the same policy: a blog post embedding a YouTube video
+//! needs `frame-src` for YouTube, a page with math needs the KaTeX CDN, while
+//! the plain CV needs neither. This module inspects the rendered HTML of a page,
+//! detects the features it actually uses and computes the narrowest policy
+//! that still lets the page work. Inline scripts are allowed by hash rather
+//! than with `'unsafe-inline'` wherever possible.
+//!
+//! Stylesheets are only in place once the site is finished, so
+//! [`apply_site_csp`] then scans them for the origins of imported styles,
+//! fonts and images, updates every page's policy with them, and returns the
+//! policy of the whole site for the server configurations' response headers.
+//! Nothing is listed by hand, so adding a font CDN or analytics only takes
+//! rebuilding.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use im::Vector;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::html_generator::fingerprint::{css_files, html_files};
+
+/// Stylesheet origins and the origins their font files are served from,
+/// which the generated output doesn't show
+const STYLESHEET_FONT_ORIGINS: [(&str, &str); 2] = [
+    ("https://fonts.googleapis.com", "https://fonts.gstatic.com"),
+    ("https://www.nerdfonts.com", "https://www.nerdfonts.com"),
+];
+
+/// Extensions of font files referenced from stylesheets
+const FONT_EXTENSIONS: [&str; 5] = ["woff2", "woff", "ttf", "otf", "eot"];
+
+/// CDN serving KaTeX for math rendering
+const MATH_CDN_ORIGIN: &str = "https://cdn.jsdelivr.net";
+
+/// Analytics script origins and the origins they report to
+const ANALYTICS_ORIGINS: [(&str, &str); 4] = [
+    ("https://plausible.io", "https://plausible.io"),
+    ("https://cdn.usefathom.com", "https://cdn.usefathom.com"),
+    (
+        "https://static.cloudflareinsights.com",
+        "https://cloudflareinsights.com",
+    ),
+    (
+        "https://www.googletagmanager.com",
+        "https://www.google-analytics.com",
+    ),
+];
+
+/// Features of a rendered page that affect its Content Security Policy
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageFeatures {
+    /// CSP source expressions (`'sha256-...'`) for inline `<script>` blocks
+    pub inline_script_hashes: Vector<String>,
+    /// Whether the page uses inline event handlers such as `onclick=`
+    pub inline_event_handlers: bool,
+    /// Whether the page uses `style="..."` attributes or `<style>` blocks
+    pub inline_styles: bool,
+    /// Origins of external scripts
+    pub script_origins: Vector<String>,
+    /// Origins of embedded iframes (videos, code sandboxes, ...)
+    pub embed_origins: Vector<String>,
+    /// Origins of stylesheets, linked or imported
+    pub style_origins: Vector<String>,
+    /// Origins of font files
+    pub font_origins: Vector<String>,
+    /// Origins of images, icons and CSS backgrounds
+    pub image_origins: Vector<String>,
+    /// Whether the page renders math with KaTeX
+    pub math: bool,
+    /// Origins analytics scripts send events to
+    pub analytics_origins: Vector<String>,
+    /// Origins of APIs the page's scripts call, such as post reactions
+    pub api_origins: Vector<String>,
+}
+
+impl PageFeatures {
+    /// Detect the features used by a rendered HTML page
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - The rendered page
+    ///
+    /// # Returns
+    ///
+    /// The detected features
+    pub fn detect(html: &str) -> Self {
+        let mut features = Self::default();
+
+        for captures in script_regex().captures_iter(html) {
+            let attrs = captures.name("attrs").map_or("", |m| m.as_str());
+            let body = captures.name("body").map_or("", |m| m.as_str());
+
+            match attribute_value(attrs, "src") {
+                Some(src) => {
+                    if let Some(origin) = origin_of(src) {
+                        push_unique(&mut features.script_origins, origin);
+                    }
+                    if let Some(origin) = analytics_endpoint(attrs, src) {
+                        push_unique(&mut features.analytics_origins, origin);
+                    }
+                }
+                None if is_executable_script(attrs) && !body.trim().is_empty() => {
+                    push_unique(&mut features.inline_script_hashes, script_hash(body));
+                }
+                None => {}
+            }
+        }
+
+        features.inline_event_handlers = event_handler_regex().is_match(html);
+        features.inline_styles = html.contains(" style=\"") || html.contains("<style");
+
+        for tag in link_regex().find_iter(html) {
+            let attributes = tag_attributes(tag.as_str());
+            let Some(origin) = attributes.get("href").and_then(|href| origin_of(href)) else {
+                continue;
+            };
+            let rel = attributes
+                .get("rel")
+                .map(|rel| rel.to_ascii_lowercase())
+                .unwrap_or_default();
+            let rel = rel.split_whitespace().collect::<Vec<_>>();
+            if rel.contains(&"stylesheet") {
+                push_unique(&mut features.style_origins, origin);
+            } else if rel.contains(&"preload")
+                && attributes.get("as").map(String::as_str) == Some("font")
+            {
+                push_unique(&mut features.font_origins, origin);
+            } else if rel.iter().any(|rel| rel.contains("icon")) {
+                push_unique(&mut features.image_origins, origin);
+            }
+        }
+        for tag in image_regex().find_iter(html) {
+            let attributes = tag_attributes(tag.as_str());
+            let sources = attributes.get("src").map(String::as_str).into_iter().chain(
+                attributes
+                    .get("srcset")
+                    .into_iter()
+                    .flat_map(|srcset| srcset_urls(srcset)),
+            );
+            for origin in sources.filter_map(origin_of) {
+                push_unique(&mut features.image_origins, origin);
+            }
+        }
+        for captures in style_regex().captures_iter(html) {
+            features.merge(&Self::detect_css(&captures[1]));
+        }
+
+        features.embed_origins = iframe_regex()
+            .captures_iter(html)
+            .filter_map(|c| c.get(1).and_then(|m| origin_of(m.as_str())))
+            .fold(Vector::new(), |mut acc, origin| {
+                push_unique(&mut acc, origin);
+                acc
+            });
+
+        features.math = html.contains("class=\"math") || html.contains("katex");
+
+        features.api_origins = api_regex()
+            .captures_iter(html)
+            .filter_map(|c| c.get(1).and_then(|m| origin_of(m.as_str())))
+            .fold(Vector::new(), |mut acc, origin| {
+                push_unique(&mut acc, origin);
+                acc
+            });
+
+        features.add_stylesheet_fonts();
+        for (_, connect) in ANALYTICS_ORIGINS
+            .iter()
+            .filter(|(script, _)| features.script_origins.iter().any(|o| o == script))
+        {
+            push_unique(&mut features.analytics_origins, connect.to_string());
+        }
+
+        features
+    }
+
+    /// Detect the origins a stylesheet loads from
+    ///
+    /// # Arguments
+    ///
+    /// * `css` - The stylesheet, or the contents of a `<style>` block
+    ///
+    /// # Returns
+    ///
+    /// The features with the origins of imported stylesheets, fonts and images
+    pub fn detect_css(css: &str) -> Self {
+        let mut features = Self::default();
+
+        for captures in css_url_regex().captures_iter(css) {
+            if let Some(import) = captures.name("import") {
+                if let Some(origin) = origin_of(import.as_str()) {
+                    push_unique(&mut features.style_origins, origin);
+                }
+                continue;
+            }
+            let url = captures.name("url").map_or("", |m| m.as_str());
+            let Some(origin) = origin_of(url) else {
+                continue;
+            };
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            let is_font = path.rsplit_once('.').is_some_and(|(_, ext)| {
+                FONT_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+            });
+            if is_font {
+                push_unique(&mut features.font_origins, origin);
+            } else {
+                push_unique(&mut features.image_origins, origin);
+            }
+        }
+
+        features.add_stylesheet_fonts();
+        features
+    }
+
+    /// Add the features of another page or stylesheet
+    pub fn merge(&mut self, other: &PageFeatures) {
+        let lists = [
+            (&mut self.inline_script_hashes, &other.inline_script_hashes),
+            (&mut self.script_origins, &other.script_origins),
+            (&mut self.embed_origins, &other.embed_origins),
+            (&mut self.style_origins, &other.style_origins),
+            (&mut self.font_origins, &other.font_origins),
+            (&mut self.image_origins, &other.image_origins),
+            (&mut self.analytics_origins, &other.analytics_origins),
+            (&mut self.api_origins, &other.api_origins),
+        ];
+        for (values, others) in lists {
+            for value in others.iter() {
+                push_unique(values, value.clone());
+            }
+        }
+        self.inline_event_handlers |= other.inline_event_handlers;
+        self.inline_styles |= other.inline_styles;
+        self.math |= other.math;
+    }
+
+    /// Add the font origins of known font stylesheets
+    fn add_stylesheet_fonts(&mut self) {
+        for (_, fonts) in STYLESHEET_FONT_ORIGINS
+            .iter()
+            .filter(|(style, _)| self.style_origins.iter().any(|o| o == style))
+        {
+            push_unique(&mut self.font_origins, fonts.to_string());
+        }
+    }
+}
+
+/// A Content Security Policy as an ordered list of directives
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentSecurityPolicy {
+    directives: Vector<(String, Vector<String>)>,
+}
+
+impl ContentSecurityPolicy {
+    /// Compute the policy for a page from its features
+    ///
+    /// # Arguments
+    ///
+    /// * `features` - Features detected in the page
+    ///
+    /// # Returns
+    ///
+    /// The narrowest policy that allows those features
+    pub fn for_page(features: &PageFeatures) -> Self {
+        let mut script_src = sources(&["'self'"]);
+        features
+            .script_origins
+            .iter()
+            .for_each(|o| push_unique(&mut script_src, o.clone()));
+        if features.inline_event_handlers {
+            // Handlers can't be hashed without 'unsafe-hashes', and browsers
+            // ignore 'unsafe-inline' once a hash is present, so fall back fully
+            push_unique(&mut script_src, "'unsafe-inline'".to_string());
+        } else {
+            features
+                .inline_script_hashes
+                .iter()
+                .for_each(|h| push_unique(&mut script_src, h.clone()));
+        }
+
+        let mut style_src = sources(&["'self'"]);
+        features
+            .style_origins
+            .iter()
+            .for_each(|o| push_unique(&mut style_src, o.clone()));
+        if features.inline_styles {
+            style_src.push_back("'unsafe-inline'".to_string());
+        }
+
+        let mut font_src = sources(&["'self'", "data:"]);
+        features
+            .font_origins
+            .iter()
+            .for_each(|o| push_unique(&mut font_src, o.clone()));
+
+        let mut img_src = sources(&["'self'", "data:"]);
+        features
+            .image_origins
+            .iter()
+            .for_each(|o| push_unique(&mut img_src, o.clone()));
+
+        let mut connect_src = sources(&["'self'"]);
+        features
+            .analytics_origins
+            .iter()
+            .for_each(|o| push_unique(&mut connect_src, o.clone()));
+        features
+            .api_origins
+            .iter()
+            .for_each(|o| push_unique(&mut connect_src, o.clone()));
+
+        if features.math {
+            push_unique(&mut script_src, MATH_CDN_ORIGIN.to_string());
+            push_unique(&mut style_src, MATH_CDN_ORIGIN.to_string());
+            push_unique(&mut font_src, MATH_CDN_ORIGIN.to_string());
+        }
+
+        let frame_src = if features.embed_origins.is_empty() {
+            sources(&["'none'"])
+        } else {
+            features.embed_origins.clone()
+        };
+
+        Self {
+            directives: Vector::from_iter([
+                ("default-src".to_string(), sources(&["'self'"])),
+                ("script-src".to_string(), script_src),
+                ("style-src".to_string(), style_src),
+                ("font-src".to_string(), font_src),
+                ("img-src".to_string(), img_src),
+                ("connect-src".to_string(), connect_src),
+                ("frame-src".to_string(), frame_src),
+                ("object-src".to_string(), sources(&["'none'"])),
+                ("base-uri".to_string(), sources(&["'self'"])),
+                ("form-action".to_string(), sources(&["'self'"])),
+                ("frame-ancestors".to_string(), sources(&["'none'"])),
+            ]),
+        }
+    }
+
+    /// Get the sources of a directive
+    pub fn directive(&self, name: &str) -> Option<&Vector<String>> {
+        self.directives
+            .iter()
+            .find(|(directive, _)| directive == name)
+            .map(|(_, sources)| sources)
+    }
+
+    /// Render the policy as an HTTP header value
+    pub fn to_header_value(&self) -> String {
+        self.render(|_| true)
+    }
+
+    /// Render the policy as a `<meta http-equiv>` tag
+    ///
+    /// `frame-ancestors` is left out because browsers ignore it in meta tags.
+    pub fn to_meta_tag(&self) -> String {
+        format!(
+            r#"<meta http-equiv="Content-Security-Policy" content="{}">"#,
+            self.render(|name| name != "frame-ancestors")
+        )
+    }
+
+    fn render<F: Fn(&str) -> bool>(&self, include: F) -> String {
+        self.directives
+            .iter()
+            .filter(|(name, _)| include(name))
+            .map(|(name, sources)| {
+                format!(
+                    "{} {}",
+                    name,
+                    sources.iter().cloned().collect::<Vec<_>>().join(" ")
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Compute the policy for a rendered page and insert it as a meta tag
+///
+/// The tag goes right after `<head>` so it applies before any resource is
+/// referenced. Pages without a head are returned unchanged. Call this on the
+/// final markup: inline script hashes break if the page is minified afterwards.
+///
+/// # Arguments
+///
+/// * `html` - The rendered page
+///
+/// # Returns
+///
+/// The page with a Content-Security-Policy meta tag
+pub fn apply_csp_meta(html: &str) -> String {
+    insert_csp_meta(html, &PageFeatures::default())
+}
+
+/// Insert the policy of a page, allowing what its stylesheets load
+fn insert_csp_meta(html: &str, stylesheets: &PageFeatures) -> String {
+    let Some(head) = head_regex().find(html) else {
+        return html.to_string();
+    };
+
+    let mut features = PageFeatures::detect(html);
+    features.merge(stylesheets);
+    let policy = ContentSecurityPolicy::for_page(&features);
+    format!(
+        "{}{}{}",
+        &html[..head.end()],
+        policy.to_meta_tag(),
+        &html[head.end()..]
+    )
+}
+
+/// Derive the policies of a finished site from its pages and stylesheets
+///
+/// The pages' meta tags are updated to allow what the stylesheets load,
+/// which wasn't known when the pages were written. Pages without a policy,
+/// such as copied static pages, are left as they are. Call this once the
+/// static assets are in place and before pages are encrypted.
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+///
+/// # Returns
+///
+/// The policy allowing every page of the site, for response headers
+pub fn apply_site_csp(output_dir: &Path) -> Result<ContentSecurityPolicy> {
+    let mut stylesheets = PageFeatures::default();
+    for file in css_files(output_dir, output_dir)?.iter() {
+        let path = output_dir.join(file);
+        let css = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        stylesheets.merge(&PageFeatures::detect_css(&css));
+    }
+
+    let mut site = stylesheets.clone();
+    for page in html_files(output_dir, output_dir)?.iter() {
+        let path = output_dir.join(page);
+        let html = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        site.merge(&PageFeatures::detect(&html));
+
+        if let Some(meta) = csp_meta_regex().find(&html) {
+            let html = format!("{}{}", &html[..meta.start()], &html[meta.end()..]);
+            fs::write(&path, insert_csp_meta(&html, &stylesheets))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(ContentSecurityPolicy::for_page(&site))
+}
+
+/// Compute the CSP hash source for an inline script body
+pub fn script_hash(body: &str) -> String {
+    let digest = Sha256::digest(body.as_bytes());
+    format!(
+        "'sha256-{}'",
+        base64::engine::general_purpose::STANDARD.encode(digest)
+    )
+}
+
+/// Extract the scheme and host of an absolute http(s) URL
+fn origin_of(url: &str) -> Option<String> {
+    let url = if url.starts_with("//") {
+        format!("https:{}", url)
+    } else {
+        url.to_string()
+    };
+    let (scheme, rest) = url.split_once("://")?;
+    if scheme != "http" && scheme != "https" {
+        return None;
+    }
+    let host = rest.split(['/', '?', '#']).next()?;
+    (!host.is_empty()).then(|| format!("{}://{}", scheme, host))
+}
+
+/// Read an attribute value from a tag's attribute string
+fn attribute_value<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    attrs
+        .split_whitespace()
+        .find_map(|attr| attr.strip_prefix(name)?.strip_prefix('='))
+        .map(|value| value.trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Origin a privacy-friendly analytics script reports to
+///
+/// GoatCounter sends to its `data-goatcounter` endpoint. Plausible and umami
+/// send to their `data-api` or `data-host-url` if set, otherwise to where the
+/// script is served from, which may be a self-hosted instance.
+fn analytics_endpoint(attrs: &str, src: &str) -> Option<String> {
+    if let Some(endpoint) = attribute_value(attrs, "data-goatcounter") {
+        return origin_of(endpoint);
+    }
+    let is_analytics = attribute_value(attrs, "data-domain").is_some()
+        || attribute_value(attrs, "data-website-id").is_some();
+    if !is_analytics {
+        return None;
+    }
+    attribute_value(attrs, "data-api")
+        .or_else(|| attribute_value(attrs, "data-host-url"))
+        .and_then(origin_of)
+        .or_else(|| origin_of(src))
+}
+
+/// Whether a script tag contains code the browser executes
+///
+/// Data blocks such as `application/ld+json` aren't subject to `script-src`.
+fn is_executable_script(attrs: &str) -> bool {
+    match attribute_value(attrs, "type") {
+        None => true,
+        Some(kind) => matches!(
+            kind.to_ascii_lowercase().as_str(),
+            "text/javascript" | "application/javascript" | "module"
+        ),
+    }
+}
+
+/// Attributes of a tag by lowercase name, quoted or unquoted
+pub(crate) fn tag_attributes(tag: &str) -> std::collections::HashMap<String, String> {
+    attribute_regex()
+        .captures_iter(tag)
+        .filter_map(|captures| {
+            let value = captures
+                .name("double")
+                .or_else(|| captures.name("single"))
+                .or_else(|| captures.name("bare"))?;
+            Some((
+                captures["name"].to_ascii_lowercase(),
+                value.as_str().to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// URLs of a `srcset`, e.g. `a.png 1x, b.png 2x`
+fn srcset_urls(srcset: &str) -> impl Iterator<Item = &str> {
+    srcset
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+}
+
+fn sources(values: &[&str]) -> Vector<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+fn push_unique(values: &mut Vector<String>, value: String) {
+    if !values.contains(&value) {
+        values.push_back(value);
+    }
+}
+
+fn script_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?is)<script(?P<attrs>[^>]*)>(?P<body>.*?)</script>").expect("valid regex")
+    })
+}
+
+fn iframe_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?i)<iframe[^>]*\ssrc=["']([^"']+)["']"#).expect("valid regex"))
+}
+
+fn api_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)<[a-z][^>]*\sdata-reactions=["']([^"']+)["']"#).expect("valid regex")
+    })
+}
+
+fn link_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<link\b[^>]*>").expect("valid regex"))
+}
+
+fn image_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<(?:img|source)\b[^>]*>").expect("valid regex"))
+}
+
+fn style_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?is)<style[^>]*>(.*?)</style>").expect("valid regex"))
+}
+
+/// Matches `url(...)` and `@import "..."` in CSS, naming imports
+fn css_url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"(?i)@import\s+(?:url\(\s*)?["']?(?P<import>[^"')\s;]+)|url\(\s*["']?(?P<url>[^"')\s]+)"#,
+        )
+        .expect("valid regex")
+    })
+}
+
+/// Matches attributes, quoted or unquoted as minified HTML has them
+fn attribute_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(
+            r#"\s(?P<name>[a-zA-Z][\w:-]*)\s*=\s*(?:"(?P<double>[^"]*)"|'(?P<single>[^']*)'|(?P<bare>[^\s"'>]+))"#,
+        )
+        .expect("valid regex")
+    })
+}
+
+fn csp_meta_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r#"(?i)<meta\s+http-equiv="?Content-Security-Policy"?[^>]*>"#)
+            .expect("valid regex")
+    })
+}
+
+fn event_handler_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r#"(?i)<[a-z][^>]*\son[a-z]+\s*="#).expect("valid regex"))
+}
+
+fn head_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)<head(\s[^>]*)?>").expect("valid regex"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_page_policy() {
+        let html = r#"<html><head></head><body><p>CV</p><script src="js/scripts.js"></script></body></html>"#;
+        let policy = ContentSecurityPolicy::for_page(&PageFeatures::detect(html));
+
+        assert_eq!(
+            policy.directive("script-src").unwrap(),
+            &sources(&["'self'"])
+        );
+        assert_eq!(
+            policy.directive("frame-src").unwrap(),
+            &sources(&["'none'"])
+        );
+        assert!(!policy.to_header_value().contains("unsafe-inline"));
+    }
+
+    #[test]
+    fn test_inline_script_is_hashed() {
+        let html =
+            "<script>console.log('hi');</script><script type=\"application/ld+json\">{}</script>";
+        let features = PageFeatures::detect(html);
+
+        assert_eq!(
+            features.inline_script_hashes,
+            Vector::from_iter([script_hash("console.log('hi');")])
+        );
+        let policy = ContentSecurityPolicy::for_page(&features);
+        assert!(policy
+            .directive("script-src")
+            .unwrap()
+            .contains(&script_hash("console.log('hi');")));
+    }
+
+    #[test]
+    fn test_event_handlers_fall_back_to_unsafe_inline() {
+        let html = r#"<script>go()</script><button onclick="go()">Go</button>"#;
+        let policy = ContentSecurityPolicy::for_page(&PageFeatures::detect(html));
+        let script_src = policy.directive("script-src").unwrap();
+
+        assert!(script_src.contains(&"'unsafe-inline'".to_string()));
+        assert!(!script_src.iter().any(|s| s.starts_with("'sha256-")));
+    }
+
+    #[test]
+    fn test_embeds_math_and_analytics() {
+        let html = r#"
+            <iframe src="https://www.youtube-nocookie.com/embed/abc" allowfullscreen></iframe>
+            <span class="math inline">x^2</span>
+            <script defer src="https://plausible.io/js/script.js"></script>
+            <div class="post-reactions" data-reactions="https://api.example.com/api/reactions/hello" hidden></div>
+        "#;
+        let policy = ContentSecurityPolicy::for_page(&PageFeatures::detect(html));
+
+        assert_eq!(
+            policy.directive("frame-src").unwrap(),
+            &sources(&["https://www.youtube-nocookie.com"])
+        );
+        assert!(policy
+            .directive("font-src")
+            .unwrap()
+            .contains(&MATH_CDN_ORIGIN.to_string()));
+        assert!(policy
+            .directive("connect-src")
+            .unwrap()
+            .contains(&"https://plausible.io".to_string()));
+        assert!(policy
+            .directive("connect-src")
+            .unwrap()
+            .contains(&"https://api.example.com".to_string()));
+    }
+
+    #[test]
+    fn test_analytics_endpoints() {
+        let html = r#"
+            <script defer src="https://gc.zgo.at/count.js" data-goatcounter="https://ada.goatcounter.com/count"></script>
+            <script defer src="https://stats.example.com/script.js" data-website-id="abc" data-domains="example.com"></script>
+        "#;
+        let policy = ContentSecurityPolicy::for_page(&PageFeatures::detect(html));
+
+        assert!(policy
+            .directive("script-src")
+            .unwrap()
+            .contains(&"https://gc.zgo.at".to_string()));
+        assert_eq!(
+            policy.directive("connect-src").unwrap(),
+            &sources(&[
+                "'self'",
+                "https://ada.goatcounter.com",
+                "https://stats.example.com"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_stylesheet_and_image_origins() {
+        let css = r#"
+            @import url("https://fonts.googleapis.com/css2?family=Inter&display=swap");
+            @font-face { font-family: "Icons"; src: url(https://cdn.example.com/icons.woff2) format("woff2"); }
+            .hero { background: url('https://images.example.com/hero.jpg'); }
+            .logo { background: url(img/logo.svg); }
+        "#;
+        let features = PageFeatures::detect_css(css);
+        assert_eq!(
+            features.style_origins,
+            sources(&["https://fonts.googleapis.com"])
+        );
+        assert_eq!(
+            features.font_origins,
+            sources(&["https://cdn.example.com", "https://fonts.gstatic.com"])
+        );
+        assert_eq!(
+            features.image_origins,
+            sources(&["https://images.example.com"])
+        );
+
+        let html = r#"<link rel="stylesheet" href="https://cdn.example.com/theme.css">
+            <link rel=icon href=https://icons.example.com/favicon.png>
+            <img src="https://avatars.example.com/me.png" srcset="img/me.png 1x, https://cdn.example.com/me@2x.png 2x">"#;
+        let policy = ContentSecurityPolicy::for_page(&PageFeatures::detect(html));
+        assert_eq!(
+            policy.directive("style-src").unwrap(),
+            &sources(&["'self'", "https://cdn.example.com"])
+        );
+        assert_eq!(
+            policy.directive("img-src").unwrap(),
+            &sources(&[
+                "'self'",
+                "data:",
+                "https://icons.example.com",
+                "https://avatars.example.com",
+                "https://cdn.example.com"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_apply_site_csp() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("css")).unwrap();
+        std::fs::write(
+            dir.path().join("css/main.css"),
+            r#"@import url("https://fonts.googleapis.com/css2?family=Inter");"#,
+        )
+        .unwrap();
+        let page = apply_csp_meta(
+            r#"<html><head></head><body><iframe src="https://www.youtube-nocookie.com/embed/abc"></iframe></body></html>"#,
+        );
+        assert!(!page.contains("fonts.googleapis.com"));
+        std::fs::write(dir.path().join("index.html"), page).unwrap();
+        std::fs::write(dir.path().join("static.html"), "<html><head></head></html>").unwrap();
+
+        let policy = apply_site_csp(dir.path()).unwrap();
+        assert!(policy
+            .directive("font-src")
+            .unwrap()
+            .contains(&"https://fonts.gstatic.com".to_string()));
+        assert!(policy
+            .directive("frame-src")
+            .unwrap()
+            .contains(&"https://www.youtube-nocookie.com".to_string()));
+
+        // The page's own policy now allows the stylesheet's fonts, once
+        let page = std::fs::read_to_string(dir.path().join("index.html")).unwrap();
+        assert_eq!(page.matches("Content-Security-Policy").count(), 1);
+        assert!(page.contains("https://fonts.googleapis.com"));
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("static.html")).unwrap(),
+            "<html><head></head></html>"
+        );
+    }
+
+    #[test]
+    fn test_apply_csp_meta() {
+        let html =
+            "<!doctype html><html lang=\"en\"><head><title>CV</title></head><body></body></html>";
+        let with_meta = apply_csp_meta(html);
+
+        assert!(with_meta.contains("<head><meta http-equiv=\"Content-Security-Policy\""));
+        assert!(!with_meta.contains("frame-ancestors"));
+        assert_eq!(apply_csp_meta("<p>fragment</p>"), "<p>fragment</p>");
+    }
+
+    #[test]
+    fn test_written_page_hashes_match_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.html");
+        let html =
+            "<html><head></head><body><script>\n  const theme = 'dark';\n</script></body></html>";
+
+        crate::html_generator::utils::write_html_page(path.to_str().unwrap(), html).unwrap();
+
+        // Whatever minification did to the script, the policy hashes what was written
+        let written = std::fs::read_to_string(&path).unwrap();
+        let body = script_regex().captures(&written).unwrap()["body"].to_string();
+        assert!(written.contains(&script_hash(&body)));
+    }
+}
@@ -0,0 +1,379 @@
+//! All the content of a site, loaded once
+//!
+//! A [`ContentStore`] holds the CV, the blog posts, the static pages and the
+//! project case studies as the site shows them, with their cross-references
+//! resolved: posts link to the CV entries they mention and to archived copies
+//! of their outbound links, tags use their canonical names, stale posts carry
+//! their notice, `@/` references point at the files they name, and case
+//! studies are merged with the projects they describe.
+//! The HTML pages, the feed, webhooks and exports read the content from a
+//! store instead of loading the content directories themselves.
+
+use anyhow::Result;
+use chrono::Utc;
+use im::{HashMap, Vector};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::blog_posts::{group_posts_by_tags, load_posts_from_directory, BlogPost};
+use crate::case_studies::{
+    link_case_studies, load_case_studies, merge_case_studies, CaseStudy, ProjectDetail,
+    CASE_STUDIES_DIR,
+};
+use crate::citations::Reference;
+use crate::content_assets::{resolve_assets, CONTENT_DIR};
+use crate::cross_links::{add_cross_links, link_targets};
+use crate::cv_data::Cv;
+use crate::front_matter::{self, ContentType};
+use crate::link_archive::{
+    add_archive_links, LinkArchive, LinkArchiver, DEFAULT_LINK_ARCHIVE_PATH,
+};
+use crate::markdown_pages::{self, load_pages_from_directory, Page};
+use crate::site_config::SiteConfig;
+use crate::staleness::{self, StaleContent};
+
+/// The content of a site with its cross-references resolved
+#[derive(Debug, Clone)]
+pub struct ContentStore {
+    cv: Cv,
+    posts: Vector<BlogPost>,
+    tag_groups: HashMap<String, Vector<BlogPost>>,
+    pages: Vector<Page>,
+    projects: Vector<ProjectDetail>,
+    assets: BTreeSet<String>,
+    has_blog: bool,
+    stale: Vec<StaleContent>,
+    warnings: Vec<String>,
+}
+
+impl ContentStore {
+    /// Load the content a site configuration points at
+    ///
+    /// Content that is due for review and front matter that doesn't match
+    /// its schema are collected for [`ContentStore::print_reports`].
+    /// A `@/` reference to a file that doesn't exist is an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `cv` - CV data, with the projects the case studies describe
+    /// * `site_config` - Site configuration with the content directories
+    ///
+    /// # Returns
+    ///
+    /// The store, or an error if a content directory can't be read or
+    /// content refers to a missing file
+    pub fn load(cv: &Cv, site_config: &SiteConfig) -> Result<Self> {
+        let blog_dir = blog_directory(site_config);
+        let posts = match blog_dir {
+            Some(blog_dir) => load_posts_from_directory(blog_dir)?,
+            None => Vector::new(),
+        };
+        let posts = resolve_posts(posts, cv, site_config)?;
+
+        let pages_dir = site_config
+            .pages
+            .as_ref()
+            .map(|_| pages_directory(site_config))
+            .filter(|pages_dir| pages_dir.exists());
+        let pages = match pages_dir {
+            Some(pages_dir) => load_pages_from_directory(pages_dir)?,
+            None => Vector::new(),
+        };
+
+        // Stale posts may get a notice
+        let (posts, stale) = match &site_config.staleness {
+            Some(staleness) => {
+                let now = Utc::now();
+                let (posts, mut stale) = match blog_dir {
+                    Some(blog_dir) => staleness::check_posts(&posts, blog_dir, staleness, now),
+                    None => (posts, Vec::new()),
+                };
+                if let Some(pages_dir) = pages_dir {
+                    stale.extend(staleness::check_pages(&pages, pages_dir, staleness, now));
+                }
+                (posts, stale)
+            }
+            None => (posts, Vec::new()),
+        };
+
+        let case_studies = load_case_studies(Path::new(CASE_STUDIES_DIR))?;
+
+        // Markdown refers to files in the content tree with @/
+        let content_dir = Path::new(CONTENT_DIR);
+        let mut assets = BTreeSet::new();
+        let mut resolve = |html: &str, content: ContentType, slug: &str| {
+            let (html, used) = resolve_assets(html, content, slug, content_dir)?;
+            assets.extend(used);
+            Ok::<_, anyhow::Error>(html)
+        };
+        let posts = posts
+            .iter()
+            .map(|post| {
+                Ok(BlogPost {
+                    content: resolve(&post.content, ContentType::Post, &post.slug)?,
+                    ..post.clone()
+                })
+            })
+            .collect::<Result<Vector<_>>>()?;
+        let pages = pages
+            .iter()
+            .map(|page| {
+                Ok(Page {
+                    content: resolve(&page.content, ContentType::Page, &page.slug)?,
+                    ..page.clone()
+                })
+            })
+            .collect::<Result<Vector<_>>>()?;
+        let case_studies = case_studies
+            .iter()
+            .map(|study| {
+                Ok(CaseStudy {
+                    content: resolve(&study.content, ContentType::Project, &study.slug)?,
+                    ..study.clone()
+                })
+            })
+            .collect::<Result<Vector<_>>>()?;
+
+        // Custom front matter fields are checked against the declared ones
+        let schema = &site_config.front_matter;
+        let warnings = posts
+            .iter()
+            .flat_map(|post| {
+                front_matter::check(ContentType::Post, &post.slug, &post.fields, schema)
+            })
+            .chain(pages.iter().flat_map(|page| {
+                front_matter::check(ContentType::Page, &page.slug, &page.fields, schema)
+            }))
+            .chain(case_studies.iter().flat_map(|study| {
+                front_matter::check(ContentType::Project, &study.slug, &study.fields, schema)
+            }))
+            .collect();
+
+        // The project cards link to the case studies' detail pages
+        let projects = merge_case_studies(&cv.projects, &case_studies);
+        let cv = Cv {
+            projects: link_case_studies(&cv.projects, &projects),
+            ..cv.clone()
+        };
+
+        Ok(ContentStore {
+            cv,
+            tag_groups: group_posts_by_tags(&posts),
+            posts,
+            pages,
+            projects,
+            assets,
+            has_blog: blog_dir.is_some(),
+            stale,
+            warnings,
+        })
+    }
+
+    /// The CV, its projects linked to their case studies
+    pub fn cv(&self) -> &Cv {
+        &self.cv
+    }
+
+    /// Whether the site has a blog directory
+    pub fn has_blog(&self) -> bool {
+        self.has_blog
+    }
+
+    /// The published blog posts, newest first
+    pub fn posts(&self) -> &Vector<BlogPost> {
+        &self.posts
+    }
+
+    /// The blog post with a slug
+    pub fn post(&self, slug: &str) -> Option<&BlogPost> {
+        self.posts.iter().find(|post| post.slug == slug)
+    }
+
+    /// The blog posts by tag
+    pub fn tag_groups(&self) -> &HashMap<String, Vector<BlogPost>> {
+        &self.tag_groups
+    }
+
+    /// The blog posts with a tag, by its canonical name
+    pub fn tagged(&self, tag: &str) -> Vector<BlogPost> {
+        self.tag_groups.get(tag).cloned().unwrap_or_default()
+    }
+
+    /// The static pages
+    pub fn pages(&self) -> &Vector<Page> {
+        &self.pages
+    }
+
+    /// The static page with a slug
+    pub fn page(&self, slug: &str) -> Option<&Page> {
+        self.pages.iter().find(|page| page.slug == slug)
+    }
+
+    /// The publications listed on the static pages
+    pub fn publications(&self) -> Vector<Reference> {
+        markdown_pages::publications(&self.pages)
+    }
+
+    /// The projects with a case study
+    pub fn projects(&self) -> &Vector<ProjectDetail> {
+        &self.projects
+    }
+
+    /// The project whose case study has a slug
+    pub fn project(&self, slug: &str) -> Option<&ProjectDetail> {
+        self.projects
+            .iter()
+            .find(|detail| detail.case_study.slug == slug)
+    }
+
+    /// The files referenced with `@/`, relative to the content tree
+    pub fn assets(&self) -> &BTreeSet<String> {
+        &self.assets
+    }
+
+    /// Print the content due for review and the front matter warnings
+    pub fn print_reports(&self) {
+        staleness::print_report(&self.stale);
+        front_matter::print_warnings(&self.warnings);
+    }
+}
+
+/// Directory of the blog posts, if the blog is configured and the directory exists
+pub fn blog_directory(site_config: &SiteConfig) -> Option<&Path> {
+    site_config
+        .blog
+        .as_ref()
+        .map(|blog_config| Path::new(blog_config.directory.as_deref().unwrap_or("content/blog")))
+        .filter(|blog_dir| blog_dir.exists())
+}
+
+/// Directory of the static pages, whether or not it exists
+pub fn pages_directory(site_config: &SiteConfig) -> &Path {
+    Path::new(
+        site_config
+            .pages
+            .as_ref()
+            .and_then(|pages| pages.directory.as_deref())
+            .unwrap_or("content/pages"),
+    )
+}
+
+/// Resolve the links of blog posts as the site shows them
+///
+/// Outbound links are archived and linked to the CV when that is configured,
+/// and tags use their canonical names.
+fn resolve_posts(
+    posts: Vector<BlogPost>,
+    cv: &Cv,
+    site_config: &SiteConfig,
+) -> Result<Vector<BlogPost>> {
+    let posts = if site_config.archives_links() && !posts.is_empty() {
+        archive_outbound_links(&posts, site_config)?
+    } else {
+        posts
+    };
+    let posts = match site_config
+        .blog
+        .as_ref()
+        .and_then(|blog| blog.cross_links.as_ref())
+    {
+        Some(cross_links) => {
+            let targets = link_targets(cv, cross_links);
+            posts
+                .iter()
+                .map(|post| BlogPost {
+                    content: add_cross_links(&post.content, &targets),
+                    ..post.clone()
+                })
+                .collect()
+        }
+        None => posts,
+    };
+    Ok(site_config.taxonomy.canonicalize_tags(&posts))
+}
+
+/// Archive the outbound links of blog posts and add fallback links to them
+///
+/// # Arguments
+///
+/// * `posts` - Published blog posts
+/// * `site_config` - Site configuration with `base_url`
+///
+/// # Returns
+///
+/// The posts with an "archived" link next to every archived outbound link
+fn archive_outbound_links(
+    posts: &Vector<BlogPost>,
+    site_config: &SiteConfig,
+) -> Result<Vector<BlogPost>> {
+    let archive_path = Path::new(DEFAULT_LINK_ARCHIVE_PATH);
+    let mut archive = LinkArchive::load_or_default(archive_path);
+
+    // Offline builds only add the links archived by earlier builds
+    if !site_config.offline {
+        let checked =
+            LinkArchiver::new().archive_posts(posts, site_config.base_url.as_deref(), &mut archive);
+        if checked > 0 {
+            archive.save(archive_path)?;
+        }
+    }
+
+    Ok(posts
+        .iter()
+        .map(|post| BlogPost {
+            content: add_archive_links(&post.content, &archive),
+            ..post.clone()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn test_load_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let blog_dir = dir.path().join("blog");
+        let pages_dir = dir.path().join("pages");
+        fs::create_dir_all(&blog_dir).unwrap();
+        fs::create_dir_all(&pages_dir).unwrap();
+        fs::write(
+            blog_dir.join("hello.md"),
+            "---\ntitle: Hello\ndate: 2025-01-01\ntags: [rust]\n---\nHello",
+        )
+        .unwrap();
+        fs::write(
+            blog_dir.join("draft.md"),
+            "---\ntitle: Draft\ndate: 2025-02-01\npublished: false\n---\nSoon",
+        )
+        .unwrap();
+        fs::write(pages_dir.join("about.md"), "---\ntitle: About\n---\nMe").unwrap();
+
+        let site_config = SiteConfig {
+            blog: Some(serde_json::from_value(json!({ "directory": blog_dir })).unwrap()),
+            pages: Some(serde_json::from_value(json!({ "directory": pages_dir })).unwrap()),
+            ..Default::default()
+        };
+        let store = ContentStore::load(&Cv::create_minimal(), &site_config).unwrap();
+
+        assert!(store.has_blog());
+        assert_eq!(store.posts().len(), 1);
+        assert_eq!(store.post("hello").unwrap().title, "Hello");
+        assert!(store.post("draft").is_none());
+        assert_eq!(store.tagged("rust").len(), 1);
+        assert!(store.tagged("go").is_empty());
+        assert_eq!(store.page("about").unwrap().title, "About");
+        assert!(store.publications().is_empty());
+    }
+
+    #[test]
+    fn test_load_without_content() {
+        let store = ContentStore::load(&Cv::create_minimal(), &SiteConfig::default()).unwrap();
+        assert!(!store.has_blog());
+        assert!(store.posts().is_empty());
+        assert!(store.pages().is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+//! Cross-linking of CV entities in blog posts
+//!
+//! With `blog.cross_links` configured, the first mention of a project or skill
+//! from the CV data in a post links to its card on the projects page or its
+//! entry in the CV's skills section. Mentions are matched case-sensitively on
+//! word boundaries, so "Go" links but "go" doesn't. Text in code, links,
+//! headings and abbreviations is never linked, and names in the `exclude` list
+//! are skipped.
+
+use im::Vector;
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::cv_data::Cv;
+use crate::html_generator::filters::slug_str;
+use crate::site_config::CrossLinkConfig;
+
+/// Elements whose text is never linked
+const SKIPPED_ELEMENTS: [&str; 15] = [
+    "a", "abbr", "code", "dfn", "pre", "kbd", "samp", "script", "style", "h1", "h2", "h3", "h4",
+    "h5", "h6",
+];
+
+/// A name that can be linked, and where it links to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossLinkTarget {
+    /// Name as it appears in posts
+    pub term: String,
+    /// Link target, relative to a blog post
+    pub href: String,
+}
+
+/// Collect the projects and skills that posts can link to
+///
+/// # Arguments
+///
+/// * `cv` - CV data with projects and skill categories
+/// * `config` - Cross-linking configuration with the exclusion list
+///
+/// # Returns
+///
+/// The link targets, longest names first so "Rust Analyzer" wins over "Rust"
+pub fn link_targets(cv: &Cv, config: &CrossLinkConfig) -> Vector<CrossLinkTarget> {
+    let excluded = |term: &str| {
+        config
+            .exclude
+            .iter()
+            .any(|exclude| exclude.eq_ignore_ascii_case(term))
+    };
+
+    let projects = cv.projects.iter().flat_map(|project| {
+        let href = format!("../projects.html#project-{}", slug_str(&project.name));
+        [Some(project.name.clone()), project.display_name.clone()]
+            .into_iter()
+            .flatten()
+            .map(move |term| CrossLinkTarget {
+                term,
+                href: href.clone(),
+            })
+    });
+    let skills = cv
+        .skill_categories
+        .iter()
+        .flat_map(|category| category.skills.iter())
+        .map(|skill| CrossLinkTarget {
+            term: skill.name.clone(),
+            href: format!("../cv.html#skill-{}", slug_str(&skill.name)),
+        });
+
+    let mut targets: Vec<CrossLinkTarget> = Vec::new();
+    for target in projects.chain(skills) {
+        let term = target.term.trim();
+        if term.is_empty() || excluded(term) || targets.iter().any(|t| t.term == term) {
+            continue;
+        }
+        targets.push(CrossLinkTarget {
+            term: term.to_string(),
+            href: target.href,
+        });
+    }
+
+    targets.sort_by_key(|target| std::cmp::Reverse(target.term.len()));
+    targets.into_iter().collect()
+}
+
+/// Link the first mention of each target in post HTML
+///
+/// # Arguments
+///
+/// * `html` - Post HTML
+/// * `targets` - Names to link, longest first
+///
+/// # Returns
+///
+/// The HTML with cross-links
+pub fn add_cross_links(html: &str, targets: &Vector<CrossLinkTarget>) -> String {
+    let mut linked: Vec<&str> = Vec::new();
+    map_text(html, |text| link_text(text, targets, &mut linked))
+}
+
+/// Rewrite the text of HTML outside code, links, headings and abbreviations
+///
+/// # Arguments
+///
+/// * `html` - HTML to rewrite
+/// * `rewrite` - Rewrites a run of text between two tags
+///
+/// # Returns
+///
+/// The HTML with its text rewritten and its tags unchanged
+pub(crate) fn map_text(html: &str, mut rewrite: impl FnMut(&str) -> String) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut skip_depth = 0usize;
+
+    for token in token_regex().find_iter(html) {
+        let token = token.as_str();
+        if let Some(tag) = token.strip_prefix('<') {
+            let closing = tag.starts_with('/');
+            let name: String = tag
+                .trim_start_matches('/')
+                .chars()
+                .take_while(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_lowercase();
+            if SKIPPED_ELEMENTS.contains(&name.as_str()) {
+                if closing {
+                    skip_depth = skip_depth.saturating_sub(1);
+                } else {
+                    skip_depth += 1;
+                }
+            }
+            output.push_str(token);
+        } else if skip_depth > 0 {
+            output.push_str(token);
+        } else {
+            output.push_str(&rewrite(token));
+        }
+    }
+
+    output
+}
+
+/// Link mentions in a run of text outside tags
+fn link_text<'a>(
+    text: &str,
+    targets: &'a Vector<CrossLinkTarget>,
+    linked: &mut Vec<&'a str>,
+) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    loop {
+        // Earliest mention of a target that isn't linked yet; targets are
+        // sorted longest first, so the longest name wins a tie
+        let next = targets
+            .iter()
+            .filter(|target| !linked.contains(&target.href.as_str()))
+            .filter_map(|target| {
+                find_word(rest, &escape_html(&target.term)).map(|(start, end)| (start, end, target))
+            })
+            .min_by_key(|(start, _, _)| *start);
+
+        let Some((start, end, target)) = next else {
+            output.push_str(rest);
+            return output;
+        };
+
+        output.push_str(&rest[..start]);
+        output.push_str(&format!(
+            r#"<a class="cross-link" href="{}">{}</a>"#,
+            escape_html(&target.href),
+            &rest[start..end]
+        ));
+        linked.push(&target.href);
+        rest = &rest[end..];
+    }
+}
+
+/// Find `term` in `text` as a whole word
+///
+/// # Returns
+///
+/// The byte range of the first match
+pub(crate) fn find_word(text: &str, term: &str) -> Option<(usize, usize)> {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    // "C" is not mentioned in "C++" or "C#"
+    let continues = |c: Option<char>| is_word(c) || matches!(c, Some('+' | '#'));
+
+    text.match_indices(term)
+        .map(|(start, _)| (start, start + term.len()))
+        .find(|&(start, end)| {
+            !is_word(text[..start].chars().next_back()) && !continues(text[end..].chars().next())
+        })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Splits HTML into tags and the text between them
+fn token_regex() -> &'static Regex {
+    static TOKEN: OnceLock<Regex> = OnceLock::new();
+    TOKEN.get_or_init(|| Regex::new(r"<[^>]*>|[^<]+").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::{PersonalInfo, Project, SkillCategory};
+    use im::{vector, HashMap};
+
+    fn cv() -> Cv {
+        let project = |name: &str, display_name: Option<&str>| Project {
+            name: name.to_string(),
+            description: String::new(),
+            url: None,
+            repository: None,
+            technologies: Vector::new(),
+            highlights: Vector::new(),
+            stars: None,
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
+            language: None,
+            language_icon: None,
+            display_name: display_name.map(str::to_string),
+        };
+
+        Cv {
+            personal_info: PersonalInfo {
+                name: "Ada".to_string(),
+                title: "Engineer".to_string(),
+                email: String::new(),
+                phone: None,
+                website: None,
+                location: None,
+                summary: String::new(),
+                social_links: HashMap::new(),
+                profile_image: None,
+                github_avatar_url: None,
+                summaries: Default::default(),
+            },
+            experiences: Vector::new(),
+            education: Vector::new(),
+            skill_categories: vector![SkillCategory {
+                name: "Languages".to_string(),
+                skills: vector!["Rust".into(), "C".into(), "Go".into()],
+                tags: Vector::new(),
+            }],
+            projects: vector![project("cv", Some("CV Generator"))],
+            languages: HashMap::new(),
+            certifications: Vector::new(),
+            github_sources: Vector::new(),
+            registry_sources: Vector::new(),
+            testimonials: Vector::new(),
+            github_stats: None,
+        }
+    }
+
+    #[test]
+    fn test_link_targets() {
+        let config = CrossLinkConfig {
+            exclude: vector!["c".to_string()],
+        };
+        let targets = link_targets(&cv(), &config);
+
+        let terms: Vec<_> = targets.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(terms, ["CV Generator", "Rust", "cv", "Go"]);
+        assert_eq!(targets[0].href, "../projects.html#project-cv");
+        assert_eq!(targets[1].href, "../cv.html#skill-rust");
+    }
+
+    #[test]
+    fn test_add_cross_links() {
+        let targets = link_targets(&cv(), &CrossLinkConfig::default());
+        let html = r#"<h2>Rust</h2>
+<pre><code>fn main() {} // Rust
+</code></pre>
+<p>Using <code>Rust</code> and <a href="https://go.dev">Go</a>, I wrote the CV Generator in Rust. Rust again, and C, not C++ or Rusty. Let's go.</p>
+<p>More Go.</p>"#;
+
+        let linked = add_cross_links(html, &targets);
+
+        assert!(linked.contains("<h2>Rust</h2>"));
+        assert!(linked.contains("// Rust\n</code></pre>"));
+        assert!(linked.contains("<code>Rust</code>"));
+        assert!(linked.contains(
+            r#"the <a class="cross-link" href="../projects.html#project-cv">CV Generator</a> in <a class="cross-link" href="../cv.html#skill-rust">Rust</a>. Rust again"#
+        ));
+        assert!(linked.contains(
+            r#"and <a class="cross-link" href="../cv.html#skill-c">C</a>, not C++ or Rusty. Let's go."#
+        ));
+        // First occurrence only: Go inside the link doesn't count, the next one does
+        assert!(linked.contains(r#"More <a class="cross-link" href="../cv.html#skill-go">Go</a>."#));
+        assert_eq!(linked.matches("cross-link").count(), 4);
+    }
+}
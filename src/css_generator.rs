@@ -43,21 +43,90 @@
 //!         variant: Some("default".to_string()),
 //!         url: None,
 //!         custom_colors: None,
+//!         light: None,
+//!         dark: None,
 //!     };
 //!
-//!     generate_colorscheme_css(&config, "dist/css/generated/colorscheme.css")?;
+//!     generate_colorscheme_css(&config, "dist/css/generated/colorscheme.css", false)?;
 //!     Ok(())
 //! }
 //! ```
 
 use anyhow::{Context, Result};
+use im::OrdMap;
+use std::cmp::Reverse;
 use std::fs;
 use std::path::Path;
 
 use crate::colorscheme_provider::{
-    CachedProvider, ColorSchemeProvider, GitHubSchemeProvider, SchemeFormat, ToCss,
+    search, CachedProvider, ColorPalette, ColorSchemeProvider, CuratedProvider,
+    GitHubSchemeProvider, SchemeFormat, SchemeNotFound, ToCss,
 };
-use crate::site_config::ColorschemeConfig;
+use crate::html_generator::filters::slug_str;
+use crate::site_config::{ColorschemeConfig, SchemeSource, TagStyle, TypstColors};
+
+/// Wrap a provider in the color scheme cache
+fn cached_provider<P: ColorSchemeProvider + 'static>(
+    provider: P,
+    offline: bool,
+) -> Box<dyn ColorSchemeProvider> {
+    Box::new(CachedProvider::new(provider, COLORSCHEME_CACHE_DIR).with_offline(offline))
+}
+
+/// Colorscheme roles a tag accent can name instead of a color
+const COLOR_ROLES: [&str; 6] = [
+    "primary",
+    "secondary",
+    "accent",
+    "error",
+    "warning",
+    "success",
+];
+
+/// Build the CSS giving tagged pages their accent color
+///
+/// Tag pages and posts carry their styled tag in `data-tag`; the accent
+/// replaces the primary and accent colors of the colorscheme on them.
+///
+/// # Arguments
+///
+/// * `tag_styles` - Styles by tag name
+///
+/// # Returns
+///
+/// The CSS, or an error if an accent isn't a plain color value
+pub fn tag_accent_css(tag_styles: &OrdMap<String, TagStyle>) -> Result<String> {
+    let mut css = String::from("/* Tag accent colors */\n");
+    for (tag, style) in tag_styles.iter() {
+        let Some(accent) = style.accent.as_deref().map(str::trim) else {
+            continue;
+        };
+        if accent.is_empty() || accent.contains([';', '{', '}', '<']) {
+            anyhow::bail!("Invalid accent color for tag {}: '{}'", tag, accent);
+        }
+
+        let color = if COLOR_ROLES.contains(&accent) {
+            format!("var(--color-{})", accent)
+        } else {
+            accent.to_string()
+        };
+        css.push_str(&format!(
+            "[data-tag=\"{}\"] {{\n  --color-primary: {};\n  --color-accent: {};\n}}\n",
+            slug_str(tag),
+            color,
+            color
+        ));
+    }
+    Ok(css)
+}
+
+/// Generate the CSS giving tagged pages their accent color
+///
+/// The file is written even without accents, since `main.css` imports it.
+pub fn generate_tag_accent_css(tag_styles: &OrdMap<String, TagStyle>, path: &str) -> Result<()> {
+    fs::write(path, tag_accent_css(tag_styles)?)
+        .with_context(|| format!("Failed to write tag accent CSS file to {path}"))
+}
 
 /// Check if CSS file needs to be regenerated based on config hash
 fn needs_regeneration(config: &ColorschemeConfig, css_path: &str) -> Result<bool> {
@@ -68,87 +137,245 @@ fn needs_regeneration(config: &ColorschemeConfig, css_path: &str) -> Result<bool
         return Ok(true);
     }
 
-    // Create a simple hash of the configuration to detect changes
-    let config_hash = format!(
-        "{}{}{}",
-        config.name,
-        config.variant.as_deref().unwrap_or("default"),
-        config.source.as_deref().unwrap_or("iterm2")
-    );
-
     // Check if hash comment is in the file
     let css_content = fs::read_to_string(css_path)
         .with_context(|| format!("Failed to read existing CSS file: {css_path}"))?;
 
-    let expected_comment = format!("/* Config hash: {} */", config_hash);
+    let expected_comment = format!("/* Config hash: {} */", config_hash(config));
 
     // If the hash comment is not found or doesn't match, regeneration is needed
     Ok(!css_content.contains(&expected_comment))
 }
 
-/// Generate CSS from a colorscheme configuration using providers
+/// A simple hash of the configuration to detect changes
+fn config_hash(config: &ColorschemeConfig) -> String {
+    let scheme_hash = |scheme: &SchemeSource| {
+        format!(
+            "{}{}{}",
+            scheme.name,
+            scheme.variant.as_deref().unwrap_or("default"),
+            scheme.source.as_deref().unwrap_or("iterm2")
+        )
+    };
+    match config.pair() {
+        Some((light, dark)) => format!("{}|{}", scheme_hash(&light), scheme_hash(&dark)),
+        None => scheme_hash(&config.scheme()),
+    }
+}
+
+/// Directory where fetched color schemes are cached
+const COLORSCHEME_CACHE_DIR: &str = ".cache/colorschemes";
+
+/// Select the provider of a colorscheme source
 ///
-/// This is the smart, simple approach that leverages existing infrastructure
-/// instead of hardcoding color values. Includes caching to improve build performance.
-pub fn generate_colorscheme_css(config: &ColorschemeConfig, path: &str) -> Result<()> {
-    // Check if regeneration is needed
-    if !needs_regeneration(config, path)? {
-        println!("Using cached colorscheme CSS: {path}");
-        return Ok(());
+/// # Arguments
+///
+/// * `scheme` - The scheme, with its source and URL
+/// * `offline` - Whether to only read color schemes from the cache
+///
+/// # Returns
+///
+/// The provider: a curated collection such as `catppuccin`, a GitHub
+/// source, or iTerm2 schemes when the source is unknown
+fn provider_for(scheme: &SchemeSource, offline: bool) -> Box<dyn ColorSchemeProvider> {
+    if let Some(collection) = scheme
+        .source
+        .as_deref()
+        .and_then(CuratedProvider::for_source)
+    {
+        return cached_provider(collection, offline);
     }
-    // Select the appropriate provider based on source
-    let provider: Box<dyn ColorSchemeProvider> = match config.source.as_deref() {
-        Some("ghostty-colors") | Some("ghostty") => Box::new(CachedProvider::new(
-            GitHubSchemeProvider::ghostty_colors(),
-            ".cache/colorschemes",
-        )),
-        Some("iterm2") | Some("iTerm2-Color-Schemes") => Box::new(CachedProvider::new(
-            GitHubSchemeProvider::iterm2_schemes(),
-            ".cache/colorschemes",
-        )),
-        Some("base16") => Box::new(CachedProvider::new(
-            GitHubSchemeProvider::base16_schemes(),
-            ".cache/colorschemes",
-        )),
+    match scheme.source.as_deref() {
+        Some("ghostty-colors") | Some("ghostty") => {
+            cached_provider(GitHubSchemeProvider::ghostty_colors(), offline)
+        }
+        Some("iterm2") | Some("iTerm2-Color-Schemes") => {
+            cached_provider(GitHubSchemeProvider::iterm2_schemes(), offline)
+        }
+        Some("base16") => cached_provider(GitHubSchemeProvider::base16_schemes(), offline),
         Some(custom_repo) if custom_repo.contains('/') => {
             // Custom GitHub repository
-            let format = detect_format_from_url(config.url.as_deref());
-            Box::new(CachedProvider::new(
-                GitHubSchemeProvider::new(custom_repo, format),
-                ".cache/colorschemes",
-            ))
+            let format = detect_format_from_url(scheme.url.as_deref());
+            cached_provider(GitHubSchemeProvider::new(custom_repo, format), offline)
         }
         _ => {
             // Default to iTerm2 schemes (most comprehensive collection)
-            Box::new(CachedProvider::new(
-                GitHubSchemeProvider::iterm2_schemes(),
-                ".cache/colorschemes",
-            ))
+            cached_provider(GitHubSchemeProvider::iterm2_schemes(), offline)
         }
-    };
+    }
+}
 
-    // Fetch the color palette
+/// Fetch the palette of a scheme from its provider
+///
+/// The name is matched loosely, as in the build.
+///
+/// # Arguments
+///
+/// * `scheme` - The scheme, with its source and variant
+/// * `offline` - Whether to only read color schemes from the cache
+///
+/// # Returns
+///
+/// The palette and the name of the provider it came from
+pub fn fetch_palette(scheme: &SchemeSource, offline: bool) -> Result<(ColorPalette, String)> {
+    let provider = provider_for(scheme, offline);
     let palette = provider
-        .fetch(&config.name, config.variant.as_deref())
+        .fetch_matching(&scheme.name, scheme.variant.as_deref())
         .with_context(|| {
             format!(
                 "Failed to fetch colorscheme '{}' from {}",
-                config.name,
+                scheme.name,
                 provider.provider_name()
             )
         })?;
+    Ok((palette, provider.provider_name().to_string()))
+}
+
+/// CSS for a light and dark pair of palettes
+///
+/// The light palette is the default and the dark one follows the system
+/// preference; the `.theme-light` and `.theme-dark` classes set by the
+/// theme toggle override both.
+///
+/// # Arguments
+///
+/// * `light` - Name and palette of the light theme
+/// * `dark` - Name and palette of the dark theme
+///
+/// # Returns
+///
+/// The CSS
+pub fn pair_css(light: (&str, &ColorPalette), dark: (&str, &ColorPalette)) -> String {
+    let (light_name, light) = light;
+    let (dark_name, dark) = dark;
+    let mut css = format!("/* Light Theme ({light_name}) */\n");
+    css.push_str(&theme_block(":root", light, "light", ""));
+    css.push('\n');
+    css.push_str(&theme_block(".theme-light", light, "light", ""));
+    css.push_str(&format!("\n/* Dark Theme ({dark_name}) */\n"));
+    css.push_str("@media (prefers-color-scheme: dark) {\n");
+    css.push_str(&theme_block(":root:not(.theme-light)", dark, "dark", "  "));
+    css.push_str("}\n\n");
+    css.push_str(&theme_block(".theme-dark", dark, "dark", ""));
+    css
+}
+
+/// The variables of a palette under a selector, indented by `indent`
+fn theme_block(selector: &str, palette: &ColorPalette, color_scheme: &str, indent: &str) -> String {
+    let mut block = format!("{indent}{selector} {{\n{indent}  color-scheme: {color_scheme};\n");
+    for line in palette
+        .to_css_variables()
+        .lines()
+        .filter(|line| line.trim_start().starts_with("--"))
+    {
+        block.push_str(&format!("{indent}{line}\n"));
+    }
+    block.push_str(&format!("{indent}}}\n"));
+    block
+}
+
+/// The variables of the configured scheme or pair, and the providers they
+/// came from
+fn colorscheme_css(config: &ColorschemeConfig, offline: bool) -> Result<(String, String)> {
+    if let Some((light, dark)) = config.pair() {
+        let (light_palette, light_provider) = fetch_palette(&light, offline)
+            .with_context(|| format!("Failed to fetch {} for the light theme", light.name))?;
+        let (dark_palette, dark_provider) = fetch_palette(&dark, offline)
+            .with_context(|| format!("Failed to fetch {} for the dark theme", dark.name))?;
+
+        let providers = if light_provider == dark_provider {
+            light_provider
+        } else {
+            format!("{light_provider}, {dark_provider}")
+        };
+        let css = pair_css((&light.name, &light_palette), (&dark.name, &dark_palette));
+        Ok((css, providers))
+    } else {
+        let (palette, provider) = fetch_palette(&config.scheme(), offline)?;
+
+        // Generate CSS variables for single theme
+        let mut css = palette.to_css_variables();
+
+        // Also generate for theme classes
+        let is_dark = detect_if_dark(&palette);
+        let theme_class = if is_dark {
+            ".theme-dark"
+        } else {
+            ".theme-light"
+        };
+        css.push('\n');
+        css.push_str(&palette.to_theme_css(theme_class));
+        Ok((css, provider))
+    }
+}
+
+/// The colors of the PDFs, taken from the website's colorscheme
+///
+/// PDFs are printed on light paper, so they take the light side of a pair.
+/// The text and accents follow the site: headings and links in its primary
+/// color, secondary text in its muted color.
+///
+/// # Arguments
+///
+/// * `config` - The colorscheme configuration
+/// * `offline` - Whether to only read color schemes from the cache
+///
+/// # Returns
+///
+/// The colors, or `None` when the site only has a dark scheme
+pub fn print_colors(config: &ColorschemeConfig, offline: bool) -> Result<Option<TypstColors>> {
+    let scheme = config
+        .pair()
+        .map_or_else(|| config.scheme(), |(light, _)| light);
+    let (palette, _) = fetch_palette(&scheme, offline)?;
+    if detect_if_dark(&palette) {
+        return Ok(None);
+    }
+    typst_colors(&palette).map(Some)
+}
+
+/// Map a palette to the Typst colors, as the website's CSS maps it to roles
+fn typst_colors(palette: &ColorPalette) -> Result<TypstColors> {
+    let hex = |color: &str| {
+        parse_hex_color(color)
+            .map(|(r, g, b)| format!("#{r:02x}{g:02x}{b:02x}"))
+            .ok_or_else(|| anyhow::anyhow!("Invalid palette color '{color}'"))
+    };
+    Ok(TypstColors {
+        fill: hex(&palette.background)?,
+        accent: hex(&palette.blue)?,
+        text_light: hex(&palette.white)?,
+        text_medium: hex(&palette.bright_black)?,
+        text_dark: hex(&palette.foreground)?,
+    })
+}
+
+/// Generate CSS from a colorscheme configuration using providers
+///
+/// This is the smart, simple approach that leverages existing infrastructure
+/// instead of hardcoding color values. Includes caching to improve build performance.
+/// Offline builds only read color schemes from the cache.
+///
+/// With a light and dark pair, each side is fetched from its own provider and
+/// the dark one follows `prefers-color-scheme`; see [`pair_css`]. Scheme names
+/// are matched loosely; one that can't be found is reported with the closest
+/// names, and the site keeps its default colors.
+pub fn generate_colorscheme_css(
+    config: &ColorschemeConfig,
+    path: &str,
+    offline: bool,
+) -> Result<()> {
+    // Check if regeneration is needed
+    if !needs_regeneration(config, path)? {
+        println!("Using cached colorscheme CSS: {path}");
+        return Ok(());
+    }
 
     // Generate CSS
     let mut css_content = String::new();
 
     // Add config hash for caching
-    let config_hash = format!(
-        "{}{}{}",
-        config.name,
-        config.variant.as_deref().unwrap_or("default"),
-        config.source.as_deref().unwrap_or("iterm2")
-    );
-    css_content.push_str(&format!("/* Config hash: {} */\n", config_hash));
+    css_content.push_str(&format!("/* Config hash: {} */\n", config_hash(config)));
 
     // Add header comment
     css_content.push_str(&format!(
@@ -161,63 +388,23 @@ pub fn generate_colorscheme_css(config: &ColorschemeConfig, path: &str) -> Resul
         css_content.push_str(&format!("/* Source: {} */\n", url));
     }
 
-    css_content.push_str(&format!("/* Provider: {} */\n\n", provider.provider_name()));
-
-    // Generate both light and dark themes for Rose Pine
-    if config.name.to_lowercase().contains("rose pine") {
-        // Generate light theme (Rose Pine Dawn)
-        let light_palette = provider
-            .fetch("Rose Pine Dawn", None)
-            .with_context(|| "Failed to fetch Rose Pine Dawn for light theme")?;
-
-        // Generate dark theme (Rose Pine Moon)
-        let dark_palette = provider
-            .fetch("Rose Pine Moon", None)
-            .with_context(|| "Failed to fetch Rose Pine Moon for dark theme")?;
-
-        // Generate CSS for both themes
-        css_content.push_str("/* Light Theme (Rose Pine Dawn) */\n");
-        css_content.push_str(":root {\n");
-        css_content.push_str(
-            &light_palette
-                .to_css_variables()
-                .replace(":root {\n", "")
-                .replace("}\n", ""),
-        );
-        css_content.push_str("}\n\n");
-
-        css_content.push_str(".theme-light {\n");
-        css_content.push_str(
-            &light_palette
-                .to_css_variables()
-                .replace(":root {\n", "")
-                .replace("}\n", ""),
-        );
-        css_content.push_str("}\n\n");
-
-        css_content.push_str("/* Dark Theme (Rose Pine Moon) */\n");
-        css_content.push_str(".theme-dark {\n");
-        css_content.push_str(
-            &dark_palette
-                .to_css_variables()
-                .replace(":root {\n", "")
-                .replace("}\n", ""),
-        );
-        css_content.push_str("}\n");
-    } else {
-        // Generate CSS variables for single theme
-        css_content.push_str(&palette.to_css_variables());
-
-        // Also generate for theme classes
-        let is_dark = detect_if_dark(&palette);
-        let theme_class = if is_dark {
-            ".theme-dark"
-        } else {
-            ".theme-light"
-        };
-        css_content.push('\n');
-        css_content.push_str(&palette.to_theme_css(theme_class));
-    }
+    let (css, providers) = match colorscheme_css(config, offline) {
+        Ok(generated) => generated,
+        Err(e) => {
+            let Some(not_found) = e.downcast_ref::<SchemeNotFound>() else {
+                return Err(e);
+            };
+            // A misspelled scheme shouldn't fail the build, nor leave the old colors
+            println!("⚠️  {not_found}; keeping the default colors");
+            if Path::new(path).exists() {
+                fs::remove_file(path)
+                    .with_context(|| format!("Failed to remove stale colorscheme CSS {path}"))?;
+            }
+            return Ok(());
+        }
+    };
+    css_content.push_str(&format!("/* Provider: {} */\n\n", providers));
+    css_content.push_str(&css);
 
     // Write the CSS file
     fs::write(path, css_content)
@@ -228,7 +415,7 @@ pub fn generate_colorscheme_css(config: &ColorschemeConfig, path: &str) -> Resul
         "  Source: {}",
         config.source.as_deref().unwrap_or("default")
     );
-    println!("  Provider: {}", provider.provider_name());
+    println!("  Provider: {}", providers);
 
     Ok(())
 }
@@ -280,20 +467,91 @@ fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
 }
 
 /// List available color schemes from configured provider
-#[allow(dead_code)]
 pub fn list_available_schemes(source: Option<&str>) -> Result<Vec<String>> {
-    let provider: Box<dyn ColorSchemeProvider> = match source {
-        Some("ghostty-colors") | Some("ghostty") => {
-            Box::new(GitHubSchemeProvider::ghostty_colors())
-        }
-        Some("iterm2") | Some("iTerm2-Color-Schemes") => {
-            Box::new(GitHubSchemeProvider::iterm2_schemes())
+    provider_for(&source_scheme(source), false).list_available()
+}
+
+/// Sources `cv theme search` looks in when none is given: the curated
+/// collections, then the large ones
+pub const SCHEME_SOURCES: [&str; 7] = [
+    "catppuccin",
+    "rose-pine",
+    "gruvbox",
+    "nord",
+    "iterm2",
+    "ghostty",
+    "base16",
+];
+
+/// A color scheme found by [`search_schemes`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemeSearchResult {
+    /// Name of the scheme, as the source lists it
+    pub name: String,
+    /// Source to configure with it
+    pub source: String,
+    /// How well it matches the query, higher is better
+    pub score: u32,
+}
+
+/// Search color schemes by name across sources
+///
+/// Names are matched loosely, see [`search::rank`]. A source that can't be
+/// listed is reported and skipped.
+///
+/// # Arguments
+///
+/// * `query` - What to look for, e.g. "rose pine"
+/// * `sources` - Sources to search, e.g. [`SCHEME_SOURCES`]
+///
+/// # Returns
+///
+/// The matching schemes, best first, or an error if no source could be listed
+pub fn search_schemes(query: &str, sources: &[&str]) -> Result<Vec<SchemeSearchResult>> {
+    let mut listings = Vec::new();
+    let mut last_error = None;
+    for source in sources {
+        match list_available_schemes(Some(source)) {
+            Ok(names) => listings.push((source.to_string(), names)),
+            Err(e) => {
+                println!("⚠️  Could not list {} color schemes: {:#}", source, e);
+                last_error = Some(e);
+            }
         }
-        Some("base16") => Box::new(GitHubSchemeProvider::base16_schemes()),
-        _ => Box::new(GitHubSchemeProvider::iterm2_schemes()),
-    };
+    }
+    match last_error {
+        Some(e) if listings.is_empty() => Err(e.context("No color scheme source could be listed")),
+        _ => Ok(rank_listings(query, &listings)),
+    }
+}
 
-    provider.list_available()
+/// Rank the schemes of several sources together
+fn rank_listings(query: &str, listings: &[(String, Vec<String>)]) -> Vec<SchemeSearchResult> {
+    let mut results: Vec<SchemeSearchResult> = listings
+        .iter()
+        .flat_map(|(source, names)| {
+            search::rank(query, names)
+                .into_iter()
+                .map(|found| SchemeSearchResult {
+                    name: found.name,
+                    source: source.clone(),
+                    score: found.score,
+                })
+        })
+        .collect();
+    // Stable, so equal scores keep the order of the sources
+    results.sort_by_key(|result| Reverse(result.score));
+    results
+}
+
+/// A scheme with only a source, for picking its provider
+fn source_scheme(source: Option<&str>) -> SchemeSource {
+    SchemeSource {
+        name: String::new(),
+        source: source.map(str::to_string),
+        url: None,
+        variant: None,
+    }
 }
 
 #[cfg(test)]
@@ -310,6 +568,8 @@ mod tests {
             variant: None,
             url: None,
             custom_colors: None,
+            light: None,
+            dark: None,
         }
     }
 
@@ -440,7 +700,7 @@ mod tests {
 
     #[test]
     fn test_needs_regeneration_correct_hash() {
-        let config = create_test_config("Rose Pine Moon", Some("iterm2"));
+        let config = create_test_config("Dracula", Some("iterm2"));
         let temp_dir = tempdir().unwrap();
         let css_path = temp_dir
             .path()
@@ -468,6 +728,102 @@ mod tests {
         assert!(!result, "Should not need regeneration when hash matches");
     }
 
+    #[test]
+    fn test_colorscheme_pair() {
+        let mut config = create_test_config("Dracula", Some("iterm2"));
+        assert_eq!(config.pair(), None);
+
+        // The unconfigured side is the main scheme, sources are inherited
+        config.light = Some(SchemeSource {
+            name: "Solarized Light".to_string(),
+            source: None,
+            url: None,
+            variant: None,
+        });
+        let (light, dark) = config.pair().unwrap();
+        assert_eq!(light.source.as_deref(), Some("iterm2"));
+        assert_eq!(dark, config.scheme());
+
+        config.dark = Some(SchemeSource {
+            name: "Tokyo Night".to_string(),
+            source: Some("ghostty".to_string()),
+            url: None,
+            variant: Some("storm".to_string()),
+        });
+        let (_, dark) = config.pair().unwrap();
+        assert_eq!(dark.source.as_deref(), Some("ghostty"));
+        assert_eq!(
+            config_hash(&config),
+            "Solarized Lightdefaultiterm2|Tokyo Nightstormghostty"
+        );
+
+        // Rose Pine pairs its own variants
+        let rose_pine = create_test_config("Rose Pine Moon", None);
+        let (light, dark) = rose_pine.pair().unwrap();
+        assert_eq!(light.name, "Rose Pine Dawn");
+        assert_eq!(dark.name, "Rose Pine Moon");
+    }
+
+    #[test]
+    fn test_pair_css() {
+        let provider = MockProvider::new();
+        let dawn = provider.fetch("Rose Pine Dawn", None).unwrap();
+        let moon = provider.fetch("Rose Pine Moon", None).unwrap();
+
+        let css = pair_css(("Rose Pine Dawn", &dawn), ("Rose Pine Moon", &moon));
+        let root = css.find(":root {").unwrap();
+        let media = css.find("@media (prefers-color-scheme: dark) {").unwrap();
+        let manual = css.find(".theme-dark {").unwrap();
+        assert!(root < media && media < manual);
+        assert!(
+            css.contains(".theme-light {\n  color-scheme: light;\n  --color-background: #FAF4ED;")
+        );
+        assert!(css.contains(
+            "  :root:not(.theme-light) {\n    color-scheme: dark;\n    --color-background: #232136;"
+        ));
+        assert!(css[manual..].contains("--color-background: #232136;"));
+    }
+
+    #[test]
+    fn test_typst_colors() {
+        let provider = MockProvider::new();
+        let dawn = provider.fetch("Rose Pine Dawn", None).unwrap();
+        let colors = typst_colors(&dawn).unwrap();
+        assert_eq!(colors.fill, "#faf4ed");
+        assert_eq!(colors.accent, dawn.blue.to_lowercase());
+        assert_eq!(colors.text_dark, dawn.foreground.to_lowercase());
+        assert!(colors.validate().is_ok());
+
+        let broken = ColorPalette {
+            blue: "blue".to_string(),
+            ..dawn
+        };
+        assert!(typst_colors(&broken).is_err());
+    }
+
+    #[test]
+    fn test_rank_listings() {
+        let listings = vec![
+            (
+                "iterm2".to_string(),
+                vec!["Rose Pine Moon".to_string(), "Dracula".to_string()],
+            ),
+            (
+                "ghostty".to_string(),
+                vec!["rose-pine-moon".to_string(), "rose-pine".to_string()],
+            ),
+        ];
+        let results = rank_listings("rose pine moon", &listings);
+        let found = results
+            .iter()
+            .map(|result| (result.name.as_str(), result.source.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            vec![("Rose Pine Moon", "iterm2"), ("rose-pine-moon", "ghostty")]
+        );
+    }
+
     #[test]
     fn test_detect_if_dark_light_theme() {
         let light_palette = crate::colorscheme_provider::ColorPalette {
@@ -634,4 +990,27 @@ mod tests {
         let result = provider.fetch("Non-existent Theme", None);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_tag_accent_css() {
+        let style = |accent: &str| TagStyle {
+            accent: Some(accent.to_string()),
+            og_image: None,
+        };
+        let styles = OrdMap::from(vec![
+            ("Rust".to_string(), style("#dea584")),
+            ("Machine Learning".to_string(), style("secondary")),
+            ("Notes".to_string(), TagStyle::default()),
+        ]);
+
+        let css = tag_accent_css(&styles).unwrap();
+        assert!(css.contains(
+            "[data-tag=\"machine-learning\"] {\n  --color-primary: var(--color-secondary);"
+        ));
+        assert!(css.contains("[data-tag=\"rust\"] {\n  --color-primary: #dea584;"));
+        assert!(!css.contains("notes"));
+
+        let styles = OrdMap::unit("Rust".to_string(), style("red; } body { color: red"));
+        assert!(tag_accent_css(&styles).is_err());
+    }
 }
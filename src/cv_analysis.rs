@@ -0,0 +1,275 @@
+//! Chronology checks of the CV data
+//!
+//! Experiences and education are listed newest first, whatever their order in
+//! the CV data: [`sort_chronologically`] orders them by start date, ongoing
+//! entries first among those that started at the same time.
+//!
+//! [`analyze`] looks for dates that don't add up: entries that end before they
+//! start, jobs that overlap by more than [`OVERLAP_MONTHS`] and gaps of more
+//! than [`GAP_MONTHS`] between jobs. The build reports them as warnings, or
+//! fails on them with `date_check = "error"` in `config.toml` (see
+//! [`crate::unified_config::DateCheck`]). Overlaps and gaps can be legitimate,
+//! e.g. a side project or a sabbatical, so they never change the data.
+
+use chrono::NaiveDate;
+use im::Vector;
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::cv_data::{Cv, Education, Experience};
+use crate::cv_date::CvDate;
+
+/// Months two jobs may share without being reported, e.g. a handover month
+pub const OVERLAP_MONTHS: i64 = 1;
+
+/// Months between two jobs that are not reported as a gap
+pub const GAP_MONTHS: i64 = 3;
+
+/// A date problem in the CV data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateIssue {
+    /// An entry that ends before it starts
+    EndsBeforeStart { entry: String },
+    /// Two jobs that run at the same time for some months
+    Overlap {
+        first: String,
+        second: String,
+        months: i64,
+    },
+    /// Months without a job between two jobs
+    Gap {
+        before: String,
+        after: String,
+        months: i64,
+    },
+}
+
+impl fmt::Display for DateIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateIssue::EndsBeforeStart { entry } => {
+                write!(f, "{} ends before it starts", entry)
+            }
+            DateIssue::Overlap {
+                first,
+                second,
+                months,
+            } => write!(f, "{} and {} overlap by {} months", first, second, months),
+            DateIssue::Gap {
+                before,
+                after,
+                months,
+            } => write!(f, "{} months between {} and {}", months, before, after),
+        }
+    }
+}
+
+/// A CV entry as a range of months, see [`CvDate::first_month`]
+struct Period {
+    name: String,
+    first: i64,
+    last: i64,
+}
+
+impl Period {
+    fn new(name: String, start: &CvDate, end: Option<&CvDate>, today: NaiveDate) -> Self {
+        Period {
+            name,
+            first: start.first_month(today),
+            last: end.unwrap_or(&CvDate::Present).last_month(today),
+        }
+    }
+}
+
+fn experience_name(experience: &Experience) -> String {
+    format!("'{} at {}'", experience.position, experience.company)
+}
+
+fn education_name(education: &Education) -> String {
+    format!("'{}, {}'", education.degree, education.institution)
+}
+
+/// Order experiences and education newest first
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+///
+/// # Returns
+///
+/// A copy with experiences and education sorted by start date, descending;
+/// entries that started at the same time are ordered by end date, ongoing
+/// ones first
+pub fn sort_chronologically(cv: &Cv) -> Cv {
+    let key =
+        |start: &CvDate, end: Option<&CvDate>| Reverse((*start, *end.unwrap_or(&CvDate::Present)));
+
+    let mut experiences = cv.experiences.iter().cloned().collect::<Vec<_>>();
+    experiences.sort_by_key(|e| key(&e.start_date, e.end_date.as_ref()));
+    let mut education = cv.education.iter().cloned().collect::<Vec<_>>();
+    education.sort_by_key(|e| key(&e.start_date, e.end_date.as_ref()));
+
+    Cv {
+        experiences: experiences.into_iter().collect(),
+        education: education.into_iter().collect(),
+        ..cv.clone()
+    }
+}
+
+/// Find dates in the CV data that don't add up
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+/// * `today` - The date ongoing entries run until
+///
+/// # Returns
+///
+/// Entries that end before they start, then overlapping jobs, then gaps
+/// between jobs, each in chronological order
+pub fn analyze(cv: &Cv, today: NaiveDate) -> Vector<DateIssue> {
+    let jobs = cv
+        .experiences
+        .iter()
+        .map(|e| {
+            Period::new(
+                experience_name(e),
+                &e.start_date,
+                e.end_date.as_ref(),
+                today,
+            )
+        })
+        .collect::<Vec<_>>();
+    let studies = cv
+        .education
+        .iter()
+        .map(|e| Period::new(education_name(e), &e.start_date, e.end_date.as_ref(), today))
+        .collect::<Vec<_>>();
+
+    let backwards = jobs
+        .iter()
+        .chain(studies.iter())
+        .filter(|period| period.last < period.first)
+        .map(|period| DateIssue::EndsBeforeStart {
+            entry: period.name.clone(),
+        })
+        .collect::<Vector<_>>();
+
+    let mut jobs = jobs
+        .into_iter()
+        .filter(|job| job.last >= job.first)
+        .collect::<Vec<_>>();
+    jobs.sort_by_key(|job| (job.first, job.last));
+
+    let overlaps = jobs.iter().enumerate().flat_map(|(i, first)| {
+        jobs[i + 1..].iter().filter_map(move |second| {
+            let months = first.last.min(second.last) - second.first + 1;
+            (months > OVERLAP_MONTHS).then(|| DateIssue::Overlap {
+                first: first.name.clone(),
+                second: second.name.clone(),
+                months,
+            })
+        })
+    });
+
+    // Gaps are measured from the latest end of the jobs so far, so a job
+    // inside a longer one doesn't open a gap
+    let (gaps, _) = jobs.iter().fold(
+        (Vector::new(), None::<&Period>),
+        |(mut gaps, latest), job| {
+            if let Some(before) = latest {
+                let months = job.first - before.last - 1;
+                if months > GAP_MONTHS {
+                    gaps.push_back(DateIssue::Gap {
+                        before: before.name.clone(),
+                        after: job.name.clone(),
+                        months,
+                    });
+                }
+            }
+            match latest {
+                Some(before) if before.last >= job.last => (gaps, latest),
+                _ => (gaps, Some(job)),
+            }
+        },
+    );
+
+    backwards.into_iter().chain(overlaps).chain(gaps).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn experience(position: &str, start: &str, end: Option<&str>) -> Experience {
+        Experience {
+            company: "Acme".to_string(),
+            position: position.to_string(),
+            start_date: start.parse().unwrap(),
+            end_date: end.map(|date| date.parse().unwrap()),
+            location: None,
+            description: String::new(),
+            achievements: Vector::new(),
+            technologies: Vector::new(),
+            tags: Vector::new(),
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 17).unwrap()
+    }
+
+    #[test]
+    fn test_sort_chronologically() {
+        let mut cv = Cv::create_minimal();
+        cv.experiences = im::vector![
+            experience("Intern", "2015-06", Some("2015-09")),
+            experience("Lead", "2021-03", None),
+            experience("Engineer", "2016", Some("2021-02")),
+            experience("Advisor", "2021-03", Some("2022-01")),
+        ];
+
+        let positions = sort_chronologically(&cv)
+            .experiences
+            .iter()
+            .map(|e| e.position.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec!["Lead", "Advisor", "Engineer", "Intern"]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let mut cv = Cv::create_minimal();
+        cv.experiences = im::vector![
+            experience("Lead", "2021-02", None),
+            experience("Engineer", "2016-01", Some("2021-02")),
+            experience("Consultant", "2019-01", Some("2019-06")),
+            experience("Intern", "2015-01", Some("2015-06")),
+            experience("Typo", "2014-05", Some("2013-01")),
+        ];
+
+        let issues = analyze(&cv, today())
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            issues,
+            vec![
+                "'Typo at Acme' ends before it starts",
+                "'Engineer at Acme' and 'Consultant at Acme' overlap by 6 months",
+                "6 months between 'Intern at Acme' and 'Engineer at Acme'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_consistent_history() {
+        let mut cv = Cv::create_minimal();
+        cv.experiences = im::vector![
+            experience("Lead", "2021-03", None),
+            experience("Engineer", "2018", Some("2021-03")),
+            experience("Intern", "2017-06", Some("2017-09")),
+        ];
+        assert_eq!(analyze(&cv, today()), Vector::new());
+    }
+}
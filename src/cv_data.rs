@@ -1,8 +1,13 @@
 use anyhow::{Context, Result};
-use im::Vector;
+use chrono::{DateTime, Utc};
+use im::{HashSet, OrdMap, Vector};
 use serde::{Deserialize, Serialize};
 use std::{env, fs};
 
+use crate::cv_date::{self, CvDate, CvDuration};
+use crate::github::GitHubStats;
+use crate::testimonials::Testimonial;
+
 /// Represents personal information in a CV
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PersonalInfo {
@@ -13,6 +18,9 @@ pub struct PersonalInfo {
     pub website: Option<String>,
     pub location: Option<String>,
     pub summary: String,
+    /// Summaries for tailored profiles, by tag (see [`crate::profiles`])
+    #[serde(default, skip_serializing_if = "OrdMap::is_empty")]
+    pub summaries: OrdMap<String, String>,
     pub social_links: im::HashMap<String, String>,
     /// Optional custom profile image (will use GitHub avatar if not provided)
     pub profile_image: Option<String>,
@@ -26,12 +34,15 @@ pub struct PersonalInfo {
 pub struct Experience {
     pub company: String,
     pub position: String,
-    pub start_date: String,
-    pub end_date: Option<String>,
+    pub start_date: CvDate,
+    pub end_date: Option<CvDate>,
     pub location: Option<String>,
     pub description: String,
     pub achievements: Vector<String>,
     pub technologies: Vector<String>,
+    /// Tailored profiles the entry belongs to (see [`crate::profiles`])
+    #[serde(default, skip_serializing_if = "Vector::is_empty")]
+    pub tags: Vector<String>,
 }
 
 /// Represents an education entry
@@ -40,18 +51,200 @@ pub struct Education {
     pub institution: String,
     pub degree: String,
     pub field: String,
-    pub start_date: String,
-    pub end_date: Option<String>,
+    pub start_date: CvDate,
+    pub end_date: Option<CvDate>,
     pub location: Option<String>,
     pub gpa: Option<String>,
     pub achievements: Vector<String>,
 }
 
+impl Experience {
+    /// Format when the experience ran, e.g. `Jan 2020 – Present`
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Language code of the output, e.g. `en`
+    pub fn period(&self, language: &str) -> String {
+        cv_date::format_range(&self.start_date, self.end_date.as_ref(), language)
+    }
+
+    /// How long the experience ran, up to today if it is ongoing
+    pub fn duration(&self) -> CvDuration {
+        CvDuration::until_today(&self.start_date, self.end_date.as_ref())
+    }
+}
+
+impl Education {
+    /// Format when the education ran, e.g. `Sep 2016 – May 2020`
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Language code of the output, e.g. `en`
+    pub fn period(&self, language: &str) -> String {
+        cv_date::format_range(&self.start_date, self.end_date.as_ref(), language)
+    }
+
+    /// How long the education ran, up to today if it is ongoing
+    pub fn duration(&self) -> CvDuration {
+        CvDuration::until_today(&self.start_date, self.end_date.as_ref())
+    }
+}
+
 /// Represents a skill category and its skills
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SkillCategory {
     pub name: String,
-    pub skills: Vector<String>,
+    pub skills: Vector<Skill>,
+    /// Tailored profiles the category belongs to (see [`crate::profiles`])
+    #[serde(default, skip_serializing_if = "Vector::is_empty")]
+    pub tags: Vector<String>,
+}
+
+impl SkillCategory {
+    /// Names of the skills, in order
+    pub fn skill_names(&self) -> Vector<String> {
+        self.skills.iter().map(|skill| skill.name.clone()).collect()
+    }
+}
+
+/// How well a skill is mastered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SkillLevel {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+impl SkillLevel {
+    /// Highest [`rank`](Self::rank) of a level
+    pub const MAX_RANK: usize = 4;
+
+    /// Position of the level from 1 (beginner) to [`MAX_RANK`](Self::MAX_RANK) (expert)
+    pub fn rank(&self) -> usize {
+        *self as usize + 1
+    }
+
+    /// Name of the level for display, e.g. `Advanced`
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkillLevel::Beginner => "Beginner",
+            SkillLevel::Intermediate => "Intermediate",
+            SkillLevel::Advanced => "Advanced",
+            SkillLevel::Expert => "Expert",
+        }
+    }
+}
+
+/// A skill, with how well and how long it has been used
+///
+/// The CV data may write a skill as just its name, `"Rust"`, or as an object
+/// with the details: `{"name": "Rust", "level": "expert", "years": 6}`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "SkillEntry")]
+pub struct Skill {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<SkillLevel>,
+    /// Years of experience with the skill
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub years: Option<u32>,
+    /// When the skill was last used, `present` if it still is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<CvDate>,
+    /// Related terms, e.g. the libraries used with a language
+    #[serde(skip_serializing_if = "Vector::is_empty")]
+    pub keywords: Vector<String>,
+}
+
+/// A skill as the CV data writes it, a name or the full details
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SkillEntry {
+    Name(String),
+    Details {
+        name: String,
+        #[serde(default)]
+        level: Option<SkillLevel>,
+        #[serde(default)]
+        years: Option<u32>,
+        #[serde(default)]
+        last_used: Option<CvDate>,
+        #[serde(default)]
+        keywords: Vector<String>,
+    },
+}
+
+impl From<SkillEntry> for Skill {
+    fn from(entry: SkillEntry) -> Self {
+        match entry {
+            SkillEntry::Name(name) => Skill::from(name),
+            SkillEntry::Details {
+                name,
+                level,
+                years,
+                last_used,
+                keywords,
+            } => Skill {
+                name,
+                level,
+                years,
+                last_used,
+                keywords,
+            },
+        }
+    }
+}
+
+impl From<String> for Skill {
+    fn from(name: String) -> Self {
+        Skill {
+            name,
+            level: None,
+            years: None,
+            last_used: None,
+            keywords: Vector::new(),
+        }
+    }
+}
+
+impl From<&str> for Skill {
+    fn from(name: &str) -> Self {
+        Skill::from(name.to_string())
+    }
+}
+
+impl std::fmt::Display for Skill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl Skill {
+    /// Years and last use for display, e.g. `6 yrs, last used 2023`
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Language code of the output, e.g. `en`
+    ///
+    /// # Returns
+    ///
+    /// The details, or an empty string if the skill has neither
+    pub fn details(&self, language: &str) -> String {
+        let years = self
+            .years
+            .map(|years| CvDuration { years, months: 0 }.format(language));
+        let last_used = self
+            .last_used
+            .filter(|last_used| *last_used != CvDate::Present)
+            .map(|last_used| format!("last used {}", last_used.format(language)));
+        years
+            .into_iter()
+            .chain(last_used)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
 }
 
 /// Represents a project
@@ -66,6 +259,20 @@ pub struct Project {
     pub stars: Option<u32>,
     pub owner_username: Option<String>,
     pub owner_avatar: Option<String>,
+    pub screenshot: Option<String>,
+    /// Package registry the project is published to, e.g. "crates.io"
+    pub registry: Option<String>,
+    /// Page of the package on its registry
+    pub package_url: Option<String>,
+    /// Latest published version
+    pub version: Option<String>,
+    /// Downloads: all time on crates.io, the last month on npm and PyPI
+    pub downloads: Option<u64>,
+    /// Last push to the repository, for ordering by recent activity
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    pub case_study: Option<String>,
     #[serde(skip)]
     pub language: Option<String>,
     #[serde(skip)]
@@ -79,6 +286,38 @@ pub struct Project {
 pub struct GitHubSource {
     pub username: Option<String>,
     pub organization: Option<String>,
+    /// Fetch aggregate stars, contributions and languages for the user
+    #[serde(default)]
+    pub stats: bool,
+}
+
+/// A package registry published packages are listed from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Registry {
+    #[serde(rename = "crates.io")]
+    CratesIo,
+    #[serde(rename = "npm")]
+    Npm,
+    #[serde(rename = "pypi")]
+    PyPi,
+}
+
+impl Registry {
+    /// Name of the registry, as in the CV data
+    pub fn name(&self) -> &'static str {
+        match self {
+            Registry::CratesIo => "crates.io",
+            Registry::Npm => "npm",
+            Registry::PyPi => "pypi",
+        }
+    }
+}
+
+/// Represents published packages to list as projects
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegistrySource {
+    pub registry: Registry,
+    pub packages: Vector<String>,
 }
 
 /// Top-level CV structure that contains all CV data
@@ -93,9 +332,143 @@ pub struct Cv {
     pub certifications: Vector<String>,
     #[serde(default)]
     pub github_sources: Vector<GitHubSource>,
+    /// Packages on crates.io, npm or PyPI, listed alongside the GitHub projects
+    #[serde(default)]
+    pub registry_sources: Vector<RegistrySource>,
+    /// Testimonials, merged in from `data/testimonials.json` at build time
+    #[serde(default)]
+    pub testimonials: Vector<Testimonial>,
+    /// GitHub activity of the user, fetched at build time for sources with `stats` set
+    #[serde(default)]
+    pub github_stats: Option<GitHubStats>,
+}
+
+/// Fields that can be toggled with the public data configuration
+pub const PUBLIC_DATA_FIELDS: &[&str] = &[
+    "name",
+    "title",
+    "email",
+    "phone",
+    "website",
+    "location",
+    "summary",
+    "social_links",
+    "profile_image",
+    "experiences",
+    "education",
+    "skill_categories",
+    "projects",
+    "languages",
+    "certifications",
+    "testimonials",
+];
+
+/// Which CV fields may be published
+///
+/// Built from the `public_data` setting (or `--public-data`), a
+/// comma-separated list of [`PUBLIC_DATA_FIELDS`]; `*` publishes everything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicDataConfig {
+    fields: HashSet<String>,
+}
+
+impl PublicDataConfig {
+    /// Build the configuration from a list of public field names
+    ///
+    /// # Arguments
+    ///
+    /// * `fields` - Field names to publish, or `*` for all fields
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or an error naming an unknown field
+    pub fn from_fields(fields: &Vector<String>) -> Result<Self> {
+        let fields = fields
+            .iter()
+            .map(|field| field.trim())
+            .filter(|field| !field.is_empty())
+            .try_fold(HashSet::new(), |fields, field| match field {
+                "*" => Ok(PUBLIC_DATA_FIELDS.iter().map(|f| f.to_string()).collect()),
+                _ if PUBLIC_DATA_FIELDS.contains(&field) => Ok(fields.update(field.to_string())),
+                _ => Err(anyhow::anyhow!(
+                    "Unknown public data field '{}'. Valid fields: {}",
+                    field,
+                    PUBLIC_DATA_FIELDS.join(", ")
+                )),
+            })?;
+
+        Ok(PublicDataConfig { fields })
+    }
+
+    /// Configuration that publishes every field
+    pub fn all() -> Self {
+        PublicDataConfig {
+            fields: PUBLIC_DATA_FIELDS.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    /// Whether a field may be published
+    pub fn is_public(&self, field: &str) -> bool {
+        self.fields.contains(field)
+    }
+
+    /// The value if its field is public, otherwise an empty value
+    fn keep<T: Clone + Default>(&self, field: &str, value: &T) -> T {
+        if self.is_public(field) {
+            value.clone()
+        } else {
+            T::default()
+        }
+    }
 }
 
 impl Cv {
+    /// Create a copy with only the publicly visible fields
+    ///
+    /// Hidden text fields become empty, hidden optional fields `None` and
+    /// hidden sections empty, so every generator can render the result as is.
+    ///
+    /// # Arguments
+    ///
+    /// * `public` - Which fields may be published
+    ///
+    /// # Returns
+    ///
+    /// The filtered CV
+    pub fn filtered(&self, public: &PublicDataConfig) -> Self {
+        let info = &self.personal_info;
+        Cv {
+            personal_info: PersonalInfo {
+                name: public.keep("name", &info.name),
+                title: public.keep("title", &info.title),
+                email: public.keep("email", &info.email),
+                phone: public.keep("phone", &info.phone),
+                website: public.keep("website", &info.website),
+                location: public.keep("location", &info.location),
+                summary: public.keep("summary", &info.summary),
+                summaries: public.keep("summary", &info.summaries),
+                social_links: public.keep("social_links", &info.social_links),
+                profile_image: public.keep("profile_image", &info.profile_image),
+                github_avatar_url: public.keep("profile_image", &info.github_avatar_url),
+            },
+            experiences: public.keep("experiences", &self.experiences),
+            education: public.keep("education", &self.education),
+            skill_categories: public.keep("skill_categories", &self.skill_categories),
+            projects: public.keep("projects", &self.projects),
+            languages: public.keep("languages", &self.languages),
+            certifications: public.keep("certifications", &self.certifications),
+            github_sources: self.github_sources.clone(),
+            registry_sources: self.registry_sources.clone(),
+            // Unmoderated testimonials are never published
+            testimonials: public
+                .keep("testimonials", &self.testimonials)
+                .into_iter()
+                .filter(Testimonial::is_approved)
+                .collect(),
+            github_stats: public.keep("projects", &self.github_stats),
+        }
+    }
+
     /// Load CV data from a JSON file
     ///
     /// # Arguments
@@ -141,20 +514,25 @@ impl Cv {
     /// A Result containing the parsed CV data or an error
     pub fn from_json_str(json_str: &str, source: &str) -> Result<Self> {
         serde_json::from_str(json_str).with_context(|| {
+            let problems = crate::cv_schema::validate_json(json_str)
+                .unwrap_or_default()
+                .iter()
+                .map(|problem| format!("  {}:{}", source, problem))
+                .collect::<Vec<_>>()
+                .join("\n");
             format!(
                 "Failed to parse CV data from: {}\n\
                                       \n\
-                                      JSON parsing error. Please check:\n\
-                                      - Valid JSON syntax (use https://jsonlint.com/ to validate)\n\
-                                      - Required fields are present (name, title, email, summary)\n\
-                                      - See example structure in README.md or data/cv_data.json",
-                source
+                                      {}\n\
+                                      \n\
+                                      Run `cv validate {}` to check the data against the schema",
+                source, problems, source
             )
         })
     }
 
     /// Create a minimal CV data structure for testing
-    #[cfg(test)]
+    #[cfg(any(test, feature = "test-util"))]
     pub fn create_minimal() -> Self {
         Cv {
             personal_info: PersonalInfo {
@@ -168,6 +546,7 @@ impl Cv {
                 social_links: im::HashMap::new(),
                 profile_image: None,
                 github_avatar_url: None,
+                summaries: OrdMap::new(),
             },
             experiences: Vector::new(),
             education: Vector::new(),
@@ -176,6 +555,9 @@ impl Cv {
             languages: im::HashMap::new(),
             certifications: Vector::new(),
             github_sources: Vector::new(),
+            registry_sources: Vector::new(),
+            testimonials: Vector::new(),
+            github_stats: None,
         }
     }
 }
@@ -183,6 +565,7 @@ impl Cv {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testimonials::TestimonialStatus;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -304,6 +687,7 @@ mod tests {
             social_links,
             profile_image: Some("profile.jpg".to_string()),
             github_avatar_url: Some("https://github.com/avatar.jpg".to_string()),
+            summaries: OrdMap::new(),
         };
 
         let json = serde_json::to_string(&personal_info).unwrap();
@@ -324,8 +708,8 @@ mod tests {
         let experience = Experience {
             company: "Tech Corp".to_string(),
             position: "Senior Engineer".to_string(),
-            start_date: "2020-01".to_string(),
-            end_date: Some("2023-12".to_string()),
+            start_date: CvDate::Month(2020, 1),
+            end_date: Some(CvDate::Month(2023, 12)),
             location: Some("Remote".to_string()),
             description: "Developed scalable systems".to_string(),
             achievements: Vector::from(vec![
@@ -333,6 +717,7 @@ mod tests {
                 "Improved performance by 50%".to_string(),
             ]),
             technologies: Vector::from(vec!["Rust".to_string(), "TypeScript".to_string()]),
+            tags: Vector::new(),
         };
 
         assert_eq!(experience.company, "Tech Corp");
@@ -352,6 +737,13 @@ mod tests {
             stars: Some(150),
             owner_username: Some("user".to_string()),
             owner_avatar: Some("https://github.com/user.jpg".to_string()),
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
             language: Some("Rust".to_string()),
             language_icon: Some("🦀".to_string()),
             display_name: Some("awesome-project".to_string()),
@@ -375,6 +767,13 @@ mod tests {
             stars: None,
             owner_username: None,
             owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
             language: Some("Rust".to_string()),
             language_icon: Some("🦀".to_string()),
             display_name: Some("test-project".to_string()),
@@ -395,16 +794,41 @@ mod tests {
     fn test_skill_category_creation() {
         let skill_category = SkillCategory {
             name: "Programming Languages".to_string(),
-            skills: Vector::from(vec![
-                "Rust".to_string(),
-                "TypeScript".to_string(),
-                "Python".to_string(),
-            ]),
+            skills: Vector::from(vec!["Rust".into(), "TypeScript".into(), "Python".into()]),
+            tags: Vector::new(),
         };
 
         assert_eq!(skill_category.name, "Programming Languages");
         assert_eq!(skill_category.skills.len(), 3);
-        assert!(skill_category.skills.contains(&"Rust".to_string()));
+        assert!(skill_category.skills.contains(&Skill::from("Rust")));
+    }
+
+    #[test]
+    fn test_skill_from_name_or_details() {
+        let skills: Vector<Skill> = serde_json::from_str(
+            r#"["Go", {"name": "Rust", "level": "expert", "years": 6, "last_used": "2023-05", "keywords": ["tokio"]}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(skills[0], Skill::from("Go"));
+        let rust = &skills[1];
+        assert_eq!(rust.level, Some(SkillLevel::Expert));
+        assert_eq!(
+            rust.level.map(|level| level.rank()),
+            Some(SkillLevel::MAX_RANK)
+        );
+        assert_eq!(rust.details("en"), "6 yrs, last used May 2023");
+        assert_eq!(skills[0].details("en"), "");
+
+        // Written back as objects, leaving out what isn't set
+        assert_eq!(
+            serde_json::to_value(&skills).unwrap(),
+            serde_json::json!([
+                {"name": "Go"},
+                {"name": "Rust", "level": "expert", "years": 6, "last_used": "2023-05", "keywords": ["tokio"]}
+            ])
+        );
+        assert!(serde_json::from_str::<Skill>(r#"{"name": "Rust", "level": "guru"}"#).is_err());
     }
 
     #[test]
@@ -412,6 +836,7 @@ mod tests {
         let github_source = GitHubSource {
             username: Some("johndoe".to_string()),
             organization: Some("awesome-org".to_string()),
+            stats: false,
         };
 
         assert_eq!(github_source.username, Some("johndoe".to_string()));
@@ -421,6 +846,7 @@ mod tests {
         let user_only = GitHubSource {
             username: Some("jane".to_string()),
             organization: None,
+            stats: true,
         };
 
         assert_eq!(user_only.username, Some("jane".to_string()));
@@ -433,8 +859,8 @@ mod tests {
             institution: "University of Technology".to_string(),
             degree: "Bachelor of Science".to_string(),
             field: "Computer Science".to_string(),
-            start_date: "2016-09".to_string(),
-            end_date: Some("2020-05".to_string()),
+            start_date: CvDate::Month(2016, 9),
+            end_date: Some(CvDate::Month(2020, 5)),
             location: Some("Tech City".to_string()),
             gpa: Some("3.8".to_string()),
             achievements: Vector::from(vec![
@@ -449,4 +875,143 @@ mod tests {
         assert_eq!(education.gpa, Some("3.8".to_string()));
         assert_eq!(education.achievements.len(), 2);
     }
+
+    fn full_cv() -> Cv {
+        let mut cv = Cv::create_minimal();
+        cv.personal_info.social_links =
+            im::HashMap::unit("GitHub".to_string(), "https://github.com/test".to_string());
+        cv.personal_info.profile_image = Some("img/profile.jpg".to_string());
+        cv.personal_info.github_avatar_url = Some("https://example.com/a.png".to_string());
+        cv.experiences = Vector::unit(Experience {
+            company: "Acme".to_string(),
+            position: "Engineer".to_string(),
+            start_date: CvDate::Month(2020, 1),
+            end_date: None,
+            location: None,
+            description: "Built things".to_string(),
+            achievements: Vector::new(),
+            technologies: Vector::new(),
+            tags: Vector::new(),
+        });
+        cv.education = Vector::unit(Education {
+            institution: "University".to_string(),
+            degree: "BSc".to_string(),
+            field: "CS".to_string(),
+            start_date: CvDate::Year(2016),
+            end_date: None,
+            location: None,
+            gpa: None,
+            achievements: Vector::new(),
+        });
+        cv.skill_categories = Vector::unit(SkillCategory {
+            name: "Languages".to_string(),
+            skills: Vector::unit("Rust".into()),
+            tags: Vector::new(),
+        });
+        cv.projects = Vector::unit(Project {
+            name: "cv".to_string(),
+            description: "CV generator".to_string(),
+            url: None,
+            repository: None,
+            technologies: Vector::new(),
+            highlights: Vector::new(),
+            stars: None,
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
+            language: None,
+            language_icon: None,
+            display_name: None,
+        });
+        cv.languages = im::HashMap::unit("English".to_string(), "Native".to_string());
+        cv.certifications = Vector::unit("AWS".to_string());
+        cv.testimonials = Vector::unit(Testimonial {
+            quote: "A pleasure to work with".to_string(),
+            author: "Grace".to_string(),
+            relation: None,
+            link: None,
+            status: TestimonialStatus::Approved,
+            submitted_at: None,
+        });
+        cv
+    }
+
+    #[test]
+    fn test_filtered_keeps_everything_when_all_public() {
+        let cv = full_cv();
+        let filtered = cv.filtered(&PublicDataConfig::all());
+        assert_eq!(
+            serde_json::to_value(&filtered).unwrap(),
+            serde_json::to_value(&cv).unwrap()
+        );
+        assert!(filtered.personal_info.github_avatar_url.is_some());
+
+        // Testimonials still waiting for moderation are dropped
+        let mut with_pending = cv.clone();
+        with_pending.testimonials.push_back(Testimonial {
+            status: TestimonialStatus::Pending,
+            ..cv.testimonials[0].clone()
+        });
+        assert_eq!(
+            with_pending
+                .filtered(&PublicDataConfig::all())
+                .testimonials
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_filtered_hides_each_field() {
+        let cv = full_cv();
+        let full = serde_json::to_value(&cv).unwrap();
+
+        for hidden in PUBLIC_DATA_FIELDS {
+            let fields = PUBLIC_DATA_FIELDS
+                .iter()
+                .filter(|field| *field != hidden)
+                .map(|field| field.to_string())
+                .collect::<Vector<_>>();
+            let public = PublicDataConfig::from_fields(&fields).unwrap();
+            let filtered = serde_json::to_value(cv.filtered(&public)).unwrap();
+
+            // Exactly the hidden field is emptied, everything else is untouched
+            let mut expected = full.clone();
+            let slot = match expected["personal_info"].get_mut(*hidden) {
+                Some(slot) => slot,
+                None => &mut expected[*hidden],
+            };
+            *slot = match (hidden, &*slot) {
+                (&("phone" | "website" | "location" | "profile_image"), _) => {
+                    serde_json::Value::Null
+                }
+                (_, serde_json::Value::String(_)) => serde_json::json!(""),
+                (_, serde_json::Value::Array(_)) => serde_json::json!([]),
+                _ => serde_json::json!({}),
+            };
+            assert_eq!(filtered, expected, "hiding '{}'", hidden);
+        }
+
+        let public = PublicDataConfig::from_fields(&Vector::unit("name".to_string())).unwrap();
+        assert!(cv
+            .filtered(&public)
+            .personal_info
+            .github_avatar_url
+            .is_none());
+    }
+
+    #[test]
+    fn test_public_data_config_rejects_unknown_fields() {
+        let fields = Vector::from(vec!["name".to_string(), "ssn".to_string()]);
+        let error = PublicDataConfig::from_fields(&fields).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown public data field 'ssn'"));
+    }
 }
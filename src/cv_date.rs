@@ -0,0 +1,504 @@
+//! Dates of CV entries
+//!
+//! Experiences and education run from a [`CvDate`] to an optional one, where
+//! no end date (or `"present"`) means the entry is ongoing. The CV data writes
+//! dates as `YYYY`, `YYYY-MM` or `YYYY-MM-DD`, and they are kept at that
+//! precision: `"2020"` is displayed as a year, not as January 2020.
+//!
+//! Dates are formatted in the language of the generated pages, e.g. `Jan 2020`
+//! or `janv. 2020`, and the time between two dates as a [`CvDuration`] like
+//! `3 yrs 2 mos`. Both the askama templates and the Typst generator use them
+//! through [`Experience::period`](crate::cv_data::Experience::period) and
+//! [`Experience::duration`](crate::cv_data::Experience::duration).
+
+use anyhow::{bail, Result};
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+/// A date in the CV data, at the precision it was written with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CvDate {
+    /// `YYYY`
+    Year(i32),
+    /// `YYYY-MM`, with the month from 1
+    Month(i32, u32),
+    /// `YYYY-MM-DD`
+    Day(NaiveDate),
+    /// `present`: the entry is ongoing
+    Present,
+}
+
+impl CvDate {
+    /// The year of the date, or `None` for [`CvDate::Present`]
+    pub fn year(&self) -> Option<i32> {
+        match self {
+            CvDate::Year(year) | CvDate::Month(year, _) => Some(*year),
+            CvDate::Day(date) => Some(date.year()),
+            CvDate::Present => None,
+        }
+    }
+
+    /// The month of the date, if it has one, from 1
+    pub fn month(&self) -> Option<u32> {
+        match self {
+            CvDate::Month(_, month) => Some(*month),
+            CvDate::Day(date) => Some(date.month()),
+            CvDate::Year(_) | CvDate::Present => None,
+        }
+    }
+
+    /// Format the date for people, in a language
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Language code like `en` or `de-AT`; unsupported
+    ///   languages fall back to English
+    ///
+    /// # Returns
+    ///
+    /// E.g. `2020`, `Jan 2020`, `Jan 15, 2020` or `Present` in English
+    pub fn format(&self, language: &str) -> String {
+        let locale = Locale::of(language);
+        match self {
+            CvDate::Year(year) => year.to_string(),
+            CvDate::Month(year, month) => format!("{} {}", locale.month(*month), year),
+            CvDate::Day(date) if locale.day_first => {
+                format!(
+                    "{}{} {} {}",
+                    date.day(),
+                    locale.day_suffix,
+                    locale.month(date.month()),
+                    date.year()
+                )
+            }
+            CvDate::Day(date) => format!(
+                "{} {}, {}",
+                locale.month(date.month()),
+                date.day(),
+                date.year()
+            ),
+            CvDate::Present => locale.present.to_string(),
+        }
+    }
+
+    /// Months since year 0 of the first month the date covers
+    pub(crate) fn first_month(&self, today: NaiveDate) -> i64 {
+        match self {
+            CvDate::Year(year) => i64::from(*year) * 12,
+            _ => self.last_month(today),
+        }
+    }
+
+    /// Months since year 0 of the last month the date covers
+    pub(crate) fn last_month(&self, today: NaiveDate) -> i64 {
+        match self {
+            CvDate::Year(year) => i64::from(*year) * 12 + 11,
+            CvDate::Month(year, month) => i64::from(*year) * 12 + i64::from(*month) - 1,
+            CvDate::Day(date) => i64::from(date.year()) * 12 + i64::from(date.month0()),
+            CvDate::Present => i64::from(today.year()) * 12 + i64::from(today.month0()),
+        }
+    }
+
+    /// Key to order dates by: chronologically by the start of the period they
+    /// cover, with less precise dates first and [`CvDate::Present`] last
+    fn sort_key(&self) -> (i32, u32, u32, u8) {
+        match self {
+            CvDate::Year(year) => (*year, 1, 1, 0),
+            CvDate::Month(year, month) => (*year, *month, 1, 1),
+            CvDate::Day(date) => (date.year(), date.month(), date.day(), 2),
+            CvDate::Present => (i32::MAX, 0, 0, 3),
+        }
+    }
+}
+
+impl Ord for CvDate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl PartialOrd for CvDate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for CvDate {
+    /// The form written in the CV data, e.g. `2020-01`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvDate::Year(year) => write!(f, "{:04}", year),
+            CvDate::Month(year, month) => write!(f, "{:04}-{:02}", year, month),
+            CvDate::Day(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            CvDate::Present => f.write_str("present"),
+        }
+    }
+}
+
+impl FromStr for CvDate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("present") {
+            return Ok(CvDate::Present);
+        }
+
+        let parts = s.split('-').collect::<Vec<_>>();
+        let number = |part: &str, digits: usize| {
+            (part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| part.parse::<u32>().ok())
+                .flatten()
+        };
+        let date = match parts.as_slice() {
+            [year] => number(year, 4).map(|year| CvDate::Year(year as i32)),
+            [year, month] => number(year, 4)
+                .zip(number(month, 2).filter(|month| (1..=12).contains(month)))
+                .map(|(year, month)| CvDate::Month(year as i32, month)),
+            [_, _, _] => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .map(CvDate::Day),
+            _ => None,
+        };
+        match date {
+            Some(date) => Ok(date),
+            None => bail!(
+                "Invalid date '{}': expected YYYY, YYYY-MM, YYYY-MM-DD or present",
+                s
+            ),
+        }
+    }
+}
+
+impl Serialize for CvDate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CvDate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Format a date range for people, in a language
+///
+/// # Arguments
+///
+/// * `start` - First date
+/// * `end` - Last date; `None` means the range is ongoing
+/// * `language` - Language code, see [`CvDate::format`]
+///
+/// # Returns
+///
+/// E.g. `Jan 2020 – Present`, or just `2020` when both ends are the same
+pub fn format_range(start: &CvDate, end: Option<&CvDate>, language: &str) -> String {
+    let end = end.unwrap_or(&CvDate::Present);
+    if start == end {
+        start.format(language)
+    } else {
+        format!("{} – {}", start.format(language), end.format(language))
+    }
+}
+
+/// Time between two CV dates, in whole months
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct CvDuration {
+    pub years: u32,
+    pub months: u32,
+}
+
+impl CvDuration {
+    /// Compute the time covered by a date range
+    ///
+    /// Both ends count in full, like on LinkedIn: January to March 2020 is
+    /// three months, and `2019` to `2020` two years.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - First date
+    /// * `end` - Last date; `None` or [`CvDate::Present`] means up to `today`
+    /// * `today` - The date ongoing ranges end at
+    ///
+    /// # Returns
+    ///
+    /// The duration, zero when the range ends before it starts
+    pub fn between(start: &CvDate, end: Option<&CvDate>, today: NaiveDate) -> Self {
+        let first = start.first_month(today);
+        let last = end.unwrap_or(&CvDate::Present).last_month(today);
+        let total = u32::try_from(last - first + 1).unwrap_or(0);
+        CvDuration {
+            years: total / 12,
+            months: total % 12,
+        }
+    }
+
+    /// Compute the time covered by a date range up to today
+    pub fn until_today(start: &CvDate, end: Option<&CvDate>) -> Self {
+        Self::between(start, end, Local::now().date_naive())
+    }
+
+    /// Total number of months
+    pub fn total_months(&self) -> u32 {
+        self.years * 12 + self.months
+    }
+
+    /// Format the duration for people, in a language
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - Language code, see [`CvDate::format`]
+    ///
+    /// # Returns
+    ///
+    /// E.g. `3 yrs 2 mos`, `1 yr` or `5 mos` in English
+    pub fn format(&self, language: &str) -> String {
+        let locale = Locale::of(language);
+        let unit = |count: u32, (one, many): (&str, &str)| {
+            format!("{} {}", count, if count == 1 { one } else { many })
+        };
+        match (self.years, self.months) {
+            (0, months) => unit(months, locale.months_unit),
+            (years, 0) => unit(years, locale.years_unit),
+            (years, months) => format!(
+                "{} {}",
+                unit(years, locale.years_unit),
+                unit(months, locale.months_unit)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for CvDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format("en"))
+    }
+}
+
+/// Words and order used to format dates in a language
+struct Locale {
+    months: [&'static str; 12],
+    present: &'static str,
+    /// Singular and plural
+    years_unit: (&'static str, &'static str),
+    /// Singular and plural
+    months_unit: (&'static str, &'static str),
+    /// Whether full dates are written day first, e.g. `15 janv. 2020`
+    day_first: bool,
+    /// Written after the day, e.g. the `.` of `15. Jan. 2020`
+    day_suffix: &'static str,
+}
+
+impl Locale {
+    const ENGLISH: Locale = Locale {
+        months: [
+            "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+        ],
+        present: "Present",
+        years_unit: ("yr", "yrs"),
+        months_unit: ("mo", "mos"),
+        day_first: false,
+        day_suffix: "",
+    };
+    const DANISH: Locale = Locale {
+        months: [
+            "jan.", "feb.", "mar.", "apr.", "maj", "jun.", "jul.", "aug.", "sep.", "okt.", "nov.",
+            "dec.",
+        ],
+        present: "Nu",
+        years_unit: ("år", "år"),
+        months_unit: ("md.", "mdr."),
+        day_first: true,
+        day_suffix: ".",
+    };
+    const GERMAN: Locale = Locale {
+        months: [
+            "Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.",
+            "Dez.",
+        ],
+        present: "Heute",
+        years_unit: ("J.", "J."),
+        months_unit: ("Mon.", "Mon."),
+        day_first: true,
+        day_suffix: ".",
+    };
+    const SPANISH: Locale = Locale {
+        months: [
+            "ene.", "feb.", "mar.", "abr.", "may.", "jun.", "jul.", "ago.", "sept.", "oct.",
+            "nov.", "dic.",
+        ],
+        present: "Actualidad",
+        years_unit: ("año", "años"),
+        months_unit: ("mes", "meses"),
+        day_first: true,
+        day_suffix: "",
+    };
+    const FRENCH: Locale = Locale {
+        months: [
+            "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.",
+            "nov.", "déc.",
+        ],
+        present: "Présent",
+        years_unit: ("an", "ans"),
+        months_unit: ("mois", "mois"),
+        day_first: true,
+        day_suffix: "",
+    };
+    const DUTCH: Locale = Locale {
+        months: [
+            "jan.", "feb.", "mrt.", "apr.", "mei", "jun.", "jul.", "aug.", "sep.", "okt.", "nov.",
+            "dec.",
+        ],
+        present: "Heden",
+        years_unit: ("jr", "jr"),
+        months_unit: ("mnd", "mnd"),
+        day_first: true,
+        day_suffix: "",
+    };
+    const NORWEGIAN: Locale = Locale {
+        months: [
+            "jan.", "feb.", "mar.", "apr.", "mai", "jun.", "jul.", "aug.", "sep.", "okt.", "nov.",
+            "des.",
+        ],
+        present: "Nå",
+        years_unit: ("år", "år"),
+        months_unit: ("md.", "mnd."),
+        day_first: true,
+        day_suffix: ".",
+    };
+    const SWEDISH: Locale = Locale {
+        months: [
+            "jan.", "feb.", "mars", "apr.", "maj", "juni", "juli", "aug.", "sep.", "okt.", "nov.",
+            "dec.",
+        ],
+        present: "Nu",
+        years_unit: ("år", "år"),
+        months_unit: ("mån", "mån"),
+        day_first: true,
+        day_suffix: "",
+    };
+
+    /// The locale of a language code like `en` or `nb-NO`
+    fn of(language: &str) -> &'static Locale {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match primary.as_str() {
+            "da" => &Self::DANISH,
+            "de" => &Self::GERMAN,
+            "es" => &Self::SPANISH,
+            "fr" => &Self::FRENCH,
+            "nl" => &Self::DUTCH,
+            "nb" | "nn" | "no" => &Self::NORWEGIAN,
+            "sv" => &Self::SWEDISH,
+            _ => &Self::ENGLISH,
+        }
+    }
+
+    fn month(&self, month: u32) -> &'static str {
+        self.months[(month.clamp(1, 12) - 1) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> CvDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(date("2020"), CvDate::Year(2020));
+        assert_eq!(date("2020-03"), CvDate::Month(2020, 3));
+        assert_eq!(
+            date("2020-03-15"),
+            CvDate::Day(NaiveDate::from_ymd_opt(2020, 3, 15).unwrap())
+        );
+        assert_eq!(date(" Present "), CvDate::Present);
+        for s in ["2020", "2020-03", "2020-03-15", "present"] {
+            assert_eq!(date(s).to_string(), s);
+        }
+        for invalid in ["", "20", "2020-13", "2020-3", "2020-02-30", "March 2020"] {
+            assert!(invalid.parse::<CvDate>().is_err(), "{invalid}");
+        }
+
+        let json = serde_json::to_string(&date("2020-03")).unwrap();
+        assert_eq!(json, "\"2020-03\"");
+        assert_eq!(
+            serde_json::from_str::<CvDate>(&json).unwrap(),
+            date("2020-03")
+        );
+        assert!(serde_json::from_str::<CvDate>("\"soon\"").is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut dates = vec![
+            date("present"),
+            date("2021-01-10"),
+            date("2020-06"),
+            date("2021"),
+            date("2021-01"),
+        ];
+        dates.sort();
+        assert_eq!(
+            dates,
+            vec![
+                date("2020-06"),
+                date("2021"),
+                date("2021-01"),
+                date("2021-01-10"),
+                date("present"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_localized_formatting() {
+        assert_eq!(date("2020-03").format("en"), "Mar 2020");
+        assert_eq!(date("2020-03").format("fr-CA"), "mars 2020");
+        assert_eq!(date("2020-03-15").format("en"), "Mar 15, 2020");
+        assert_eq!(date("2020-03-15").format("de"), "15. März 2020");
+        assert_eq!(date("2020").format("da"), "2020");
+        assert_eq!(date("present").format("nb"), "Nå");
+        assert_eq!(date("2020-03").format("xx"), "Mar 2020");
+
+        assert_eq!(
+            format_range(&date("2020-03"), None, "en"),
+            "Mar 2020 – Present"
+        );
+        assert_eq!(
+            format_range(&date("2020"), Some(&date("2020")), "en"),
+            "2020"
+        );
+    }
+
+    #[test]
+    fn test_duration() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        let between = |start: &str, end: Option<&str>| {
+            CvDuration::between(&date(start), end.map(date).as_ref(), today)
+        };
+
+        assert_eq!(between("2020-01", Some("2020-03")).format("en"), "3 mos");
+        assert_eq!(between("2019", Some("2020")).format("en"), "2 yrs");
+        assert_eq!(between("2023-09", None).format("en"), "3 yrs 2 mos");
+        assert_eq!(
+            between("2023-09", Some("present")),
+            between("2023-09", None)
+        );
+        assert_eq!(between("2025-10-20", None).format("en"), "1 yr 1 mo");
+        assert_eq!(between("2025-10", None).format("de"), "1 J. 1 Mon.");
+        assert_eq!(between("2022-01", Some("2021-06")).total_months(), 0);
+    }
+}
@@ -0,0 +1,520 @@
+//! Structural diff and three-way merge of CV data
+//!
+//! `cv diff <a.json> <b.json>` compares two CV files field by field rather
+//! than line by line. List entries are matched by what they are, not where
+//! they are: experiences by position and company, education by degree and
+//! institution, skill categories and projects by name, and lists of strings
+//! such as skills by value. A reordered list is no change, and a new
+//! experience is one added entry rather than a shifted list.
+//!
+//! `cv merge <base> <ours> <theirs>` combines two versions edited from a
+//! common base, such as projects refreshed from GitHub on one side and hand
+//! edits on the other. A change made on one side only is taken; a field
+//! changed differently on both sides is a conflict.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::cv_data::Cv;
+
+/// Location of a field, e.g. `experiences["Engineer at Acme"].end_date`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldPath(Vector<String>);
+
+impl FieldPath {
+    fn field(&self, name: &str) -> Self {
+        let mut segments = self.0.clone();
+        segments.push_back(name.to_string());
+        Self(segments)
+    }
+
+    fn entry(&self, label: &str) -> Self {
+        let mut segments = self.0.clone();
+        segments.push_back(format!("[{}]", Value::String(label.to_string())));
+        Self(segments)
+    }
+}
+
+impl fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.0.iter().enumerate() {
+            if i > 0 && !segment.starts_with('[') {
+                f.write_str(".")?;
+            }
+            f.write_str(segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// A difference between two versions of the CV
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// An entry or field only in the new version
+    Added { path: FieldPath, value: Value },
+    /// An entry or field only in the old version
+    Removed { path: FieldPath, value: Value },
+    /// A value that differs
+    Changed {
+        path: FieldPath,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}{}", path, detail(value)),
+            Change::Removed { path, value } => write!(f, "- {}{}", path, detail(value)),
+            Change::Changed { path, old, new } => {
+                write!(f, "~ {}: {} → {}", path, summary(old), summary(new))
+            }
+        }
+    }
+}
+
+/// A field changed differently on both sides of a merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// Where the versions disagree
+    pub path: FieldPath,
+    /// The common ancestor's value, if it had the field
+    pub base: Option<Value>,
+    /// Our value, if we kept the field
+    pub ours: Option<Value>,
+    /// Their value, if they kept the field
+    pub theirs: Option<Value>,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .map(summary)
+                .unwrap_or_else(|| "(removed)".to_string())
+        };
+        write!(
+            f,
+            "! {}: base {}, ours {}, theirs {}",
+            self.path,
+            side(&self.base),
+            side(&self.ours),
+            side(&self.theirs)
+        )
+    }
+}
+
+/// Which side wins a conflict
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Side {
+    /// The version the merge is made into
+    #[default]
+    Ours,
+    /// The version merged in
+    Theirs,
+}
+
+impl std::str::FromStr for Side {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ours" => Ok(Side::Ours),
+            "theirs" => Ok(Side::Theirs),
+            other => anyhow::bail!("Unknown side '{}' (expected ours or theirs)", other),
+        }
+    }
+}
+
+/// Compare two versions of the CV
+///
+/// # Arguments
+///
+/// * `old` - The version to compare from
+/// * `new` - The version to compare to
+///
+/// # Returns
+///
+/// The changes from `old` to `new`, in the order of the data
+pub fn diff(old: &Cv, new: &Cv) -> Result<Vector<Change>> {
+    let mut changes = Vector::new();
+    diff_values(
+        &FieldPath::default(),
+        None,
+        Some(&to_value(old)?),
+        Some(&to_value(new)?),
+        &mut changes,
+    );
+    Ok(changes)
+}
+
+/// Merge two versions of the CV made from a common base
+///
+/// # Arguments
+///
+/// * `base` - The common ancestor
+/// * `ours` - Our version
+/// * `theirs` - Their version
+/// * `prefer` - The side that wins conflicts
+///
+/// # Returns
+///
+/// The merged CV and the conflicts that were decided by `prefer`
+pub fn merge(base: &Cv, ours: &Cv, theirs: &Cv, prefer: Side) -> Result<(Cv, Vector<Conflict>)> {
+    let mut conflicts = Vector::new();
+    let merged = merge_values(
+        &FieldPath::default(),
+        None,
+        Some(&to_value(base)?),
+        Some(&to_value(ours)?),
+        Some(&to_value(theirs)?),
+        prefer,
+        &mut conflicts,
+    )
+    .unwrap_or(Value::Null);
+    let cv = serde_json::from_value(merged).context("Merged CV data is invalid")?;
+    Ok((cv, conflicts))
+}
+
+fn to_value(cv: &Cv) -> Result<Value> {
+    serde_json::to_value(cv).context("Failed to serialize CV data")
+}
+
+fn diff_values(
+    path: &FieldPath,
+    key: Option<&str>,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vector<Change>,
+) {
+    match (old, new) {
+        (old, new) if old == new => {}
+        (None, Some(new)) => changes.push_back(Change::Added {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        (Some(old), None) => changes.push_back(Change::Removed {
+            path: path.clone(),
+            value: old.clone(),
+        }),
+        (Some(Value::Object(old)), Some(Value::Object(new))) => {
+            for name in keys([old, new]) {
+                diff_values(
+                    &path.field(&name),
+                    Some(&name),
+                    old.get(&name),
+                    new.get(&name),
+                    changes,
+                );
+            }
+        }
+        (Some(Value::Array(old)), Some(Value::Array(new))) => {
+            match (entries(key, old), entries(key, new)) {
+                (Some(old), Some(new)) => {
+                    for label in labels([&old, &new]) {
+                        diff_values(
+                            &path.entry(&label),
+                            None,
+                            find(&old, &label),
+                            find(&new, &label),
+                            changes,
+                        );
+                    }
+                }
+                _ => changes.push_back(changed(path, old, new)),
+            }
+        }
+        (Some(old), Some(new)) => changes.push_back(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        (None, None) => {}
+    }
+}
+
+fn changed(path: &FieldPath, old: &[Value], new: &[Value]) -> Change {
+    Change::Changed {
+        path: path.clone(),
+        old: Value::Array(old.to_vec()),
+        new: Value::Array(new.to_vec()),
+    }
+}
+
+fn merge_values(
+    path: &FieldPath,
+    key: Option<&str>,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    prefer: Side,
+    conflicts: &mut Vector<Conflict>,
+) -> Option<Value> {
+    if ours == theirs || base == theirs {
+        return ours.cloned();
+    }
+    if base == ours {
+        return theirs.cloned();
+    }
+
+    match (ours, theirs) {
+        (Some(Value::Object(ours)), Some(Value::Object(theirs))) => {
+            let empty = Map::new();
+            let base = base.and_then(Value::as_object).unwrap_or(&empty);
+            let merged = keys([ours, theirs])
+                .into_iter()
+                .filter_map(|name| {
+                    merge_values(
+                        &path.field(&name),
+                        Some(&name),
+                        base.get(&name),
+                        ours.get(&name),
+                        theirs.get(&name),
+                        prefer,
+                        conflicts,
+                    )
+                    .map(|value| (name, value))
+                })
+                .collect::<Map<_, _>>();
+            return Some(Value::Object(merged));
+        }
+        (Some(Value::Array(ours_items)), Some(Value::Array(theirs_items))) => {
+            let empty = Vec::new();
+            let base_items = base.and_then(Value::as_array).unwrap_or(&empty);
+            if let (Some(base), Some(ours), Some(theirs)) = (
+                entries(key, base_items),
+                entries(key, ours_items),
+                entries(key, theirs_items),
+            ) {
+                let merged = labels([&ours, &theirs])
+                    .into_iter()
+                    .filter_map(|label| {
+                        merge_values(
+                            &path.entry(&label),
+                            None,
+                            find(&base, &label),
+                            find(&ours, &label),
+                            find(&theirs, &label),
+                            prefer,
+                            conflicts,
+                        )
+                    })
+                    .collect();
+                return Some(Value::Array(merged));
+            }
+        }
+        _ => {}
+    }
+
+    conflicts.push_back(Conflict {
+        path: path.clone(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    });
+    match prefer {
+        Side::Ours => ours.cloned(),
+        Side::Theirs => theirs.cloned(),
+    }
+}
+
+/// Field names of objects, in order of first appearance
+fn keys<const N: usize>(objects: [&Map<String, Value>; N]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    objects
+        .iter()
+        .flat_map(|object| object.keys())
+        .filter(|name| seen.insert(name.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Entry labels of lists, in order of first appearance
+fn labels<const N: usize>(lists: [&Vec<(String, &Value)>; N]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    lists
+        .iter()
+        .flat_map(|list| list.iter().map(|(label, _)| label))
+        .filter(|label| seen.insert(label.as_str()))
+        .cloned()
+        .collect()
+}
+
+fn find<'a>(entries: &[(String, &'a Value)], label: &str) -> Option<&'a Value> {
+    entries
+        .iter()
+        .find(|(entry, _)| entry == label)
+        .map(|(_, value)| *value)
+}
+
+/// Label the entries of a list by what they are
+///
+/// # Arguments
+///
+/// * `key` - Field name of the list, which decides what identifies an entry
+/// * `items` - The entries
+///
+/// # Returns
+///
+/// The entries with their labels, or None if the list can't be matched by
+/// identity and is compared as a whole
+fn entries<'a>(key: Option<&str>, items: &'a [Value]) -> Option<Vec<(String, &'a Value)>> {
+    let labelled = items
+        .iter()
+        .map(|item| identity(key, item).map(|label| (label, item)))
+        .collect::<Option<Vec<_>>>()?;
+    let unique = labelled
+        .iter()
+        .map(|(label, _)| label)
+        .collect::<HashSet<_>>()
+        .len()
+        == labelled.len();
+    unique.then_some(labelled)
+}
+
+/// What identifies an entry of a list, e.g. "Engineer at Acme"
+fn identity(key: Option<&str>, item: &Value) -> Option<String> {
+    if let Value::String(value) = item {
+        return Some(value.clone());
+    }
+    let field = |name: &str| item.get(name).and_then(Value::as_str);
+    match key? {
+        "experiences" => Some(format!("{} at {}", field("position")?, field("company")?)),
+        "education" => Some(format!("{}, {}", field("degree")?, field("institution")?)),
+        "skill_categories" | "projects" | "skills" => field("name").map(str::to_string),
+        "github_sources" => field("username")
+            .or_else(|| field("organization"))
+            .map(str::to_string),
+        "registry_sources" => field("registry").map(str::to_string),
+        "testimonials" => field("author").map(str::to_string),
+        _ => None,
+    }
+}
+
+/// A value for a one-line change, truncated if long
+fn summary(value: &Value) -> String {
+    const MAX_LENGTH: usize = 60;
+    let text = value.to_string();
+    if text.chars().count() > MAX_LENGTH {
+        format!("{}…", text.chars().take(MAX_LENGTH).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// The value of an added or removed entry, unless the path names it already
+fn detail(value: &Value) -> String {
+    match value {
+        Value::Object(_) => String::new(),
+        Value::String(_) => String::new(),
+        other => format!(": {}", summary(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::{Experience, Skill, SkillCategory};
+
+    fn experience(position: &str, end_date: Option<&str>) -> Experience {
+        Experience {
+            company: "Acme".to_string(),
+            position: position.to_string(),
+            start_date: "2020-01".parse().unwrap(),
+            end_date: end_date.map(|date| date.parse().unwrap()),
+            location: None,
+            description: "Built things".to_string(),
+            achievements: Vector::new(),
+            technologies: Vector::new(),
+            tags: Vector::new(),
+        }
+    }
+
+    fn cv(experiences: Vec<Experience>, skills: &[&str]) -> Cv {
+        Cv {
+            experiences: experiences.into_iter().collect(),
+            skill_categories: im::vector![SkillCategory {
+                name: "Languages".to_string(),
+                skills: skills.iter().map(|s| Skill::from(*s)).collect(),
+                tags: Vector::new(),
+            }],
+            ..Cv::create_minimal()
+        }
+    }
+
+    #[test]
+    fn test_diff_matches_entries_by_identity() {
+        let old = cv(vec![experience("Engineer", None)], &["Rust", "Scala", "Go"]);
+        let new = cv(
+            vec![
+                experience("Lead", None),
+                experience("Engineer", Some("2024-06")),
+            ],
+            &["Go", "Rust"],
+        );
+
+        let changes = diff(&old, &new)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                r#"~ experiences["Engineer at Acme"].end_date: null → "2024-06""#,
+                r#"+ experiences["Lead at Acme"]"#,
+                r#"- skill_categories["Languages"].skills["Scala"]"#,
+            ]
+        );
+        assert!(diff(&old, &old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_merge_takes_both_sides() {
+        let base = cv(vec![experience("Engineer", None)], &["Rust", "Scala"]);
+        let ours = cv(
+            vec![experience("Engineer", Some("2024-06"))],
+            &["Rust", "Scala", "Go"],
+        );
+        let theirs = cv(
+            vec![experience("Engineer", None), experience("Lead", None)],
+            &["Rust"],
+        );
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs, Side::Ours).unwrap();
+        assert!(conflicts.is_empty());
+        assert_eq!(merged.experiences.len(), 2);
+        assert_eq!(
+            merged.experiences[0].end_date.map(|date| date.to_string()),
+            Some("2024-06".to_string())
+        );
+        assert_eq!(merged.experiences[1].position, "Lead");
+        assert_eq!(
+            merged.skill_categories[0].skills,
+            im::vector![Skill::from("Rust"), Skill::from("Go")]
+        );
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let base = cv(vec![experience("Engineer", None)], &[]);
+        let ours = cv(vec![experience("Engineer", Some("2024-06"))], &[]);
+        let theirs = cv(vec![experience("Engineer", Some("2024-07"))], &[]);
+
+        let (merged, conflicts) = merge(&base, &ours, &theirs, Side::Theirs).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].path.to_string(),
+            r#"experiences["Engineer at Acme"].end_date"#
+        );
+        assert_eq!(
+            merged.experiences[0].end_date.map(|date| date.to_string()),
+            Some("2024-07".to_string())
+        );
+    }
+}
@@ -0,0 +1,551 @@
+//! JSON Schema validation of the CV data
+//!
+//! serde stops at the first problem in `data/cv_data.json` and skips fields it
+//! doesn't know, so a typo like `"acheivements"` silently drops data. The
+//! crate publishes a JSON Schema for the CV data, [`CV_DATA_SCHEMA`]
+//! (`schema/cv_data.schema.json`, also printed by `cv validate --schema`),
+//! which editors can use for completion. `cv validate` checks the data against
+//! it and reports every unknown field, missing required field, malformed date
+//! and invalid URL or email address with its line and column. The build runs
+//! the same check before it generates anything.
+//!
+//! The validator supports the keywords the schema uses: `$ref` to `$defs`,
+//! `type`, `properties`, `required`, `additionalProperties`, `items`, `enum`,
+//! `pattern`, `minimum` and the `uri`, `email` and `date-time` formats.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/// JSON Schema of `data/cv_data.json`
+pub const CV_DATA_SCHEMA: &str = include_str!("../schema/cv_data.schema.json");
+
+/// A problem in the CV data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    /// JSON pointer of the value, e.g. `/experiences/0/start_date`
+    pub pointer: String,
+    /// Line of the value in the file, from 1
+    pub line: usize,
+    /// Column of the value in the file, from 1
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pointer = if self.pointer.is_empty() {
+            "/"
+        } else {
+            &self.pointer
+        };
+        write!(
+            f,
+            "{}:{}: {} (at {})",
+            self.line, self.column, self.message, pointer
+        )
+    }
+}
+
+/// Validate CV data against the schema
+///
+/// # Arguments
+///
+/// * `source` - Contents of the CV data file
+///
+/// # Returns
+///
+/// Every problem found, in the order of the file; a syntax error is the only
+/// problem reported for a file that isn't JSON
+pub fn validate_json(source: &str) -> Result<Vector<SchemaError>> {
+    let value = match serde_json::from_str::<Value>(source) {
+        Ok(value) => value,
+        Err(e) => {
+            let message = e.to_string();
+            let message = message.split(" at line ").next().unwrap_or(&message);
+            return Ok(Vector::unit(SchemaError {
+                pointer: String::new(),
+                line: e.line(),
+                column: e.column(),
+                message: format!("Invalid JSON: {}", message),
+            }));
+        }
+    };
+    let schema: Value =
+        serde_json::from_str(CV_DATA_SCHEMA).context("The CV data schema is invalid")?;
+
+    let mut problems = Vec::new();
+    check(&schema, &schema, &value, "", &mut problems);
+
+    let positions = locate(source);
+    let mut errors = problems
+        .into_iter()
+        .map(|(pointer, message)| {
+            let (line, column) = position(&positions, &pointer);
+            SchemaError {
+                pointer,
+                line,
+                column,
+                message,
+            }
+        })
+        .collect::<Vec<_>>();
+    errors.sort_by_key(|error| (error.line, error.column));
+    Ok(errors.into_iter().collect())
+}
+
+/// Check a value against a schema, collecting (pointer, message) problems
+fn check(
+    root: &Value,
+    schema: &Value,
+    value: &Value,
+    pointer: &str,
+    problems: &mut Vec<(String, String)>,
+) {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix("#/$defs/")
+            .and_then(|name| root.get("$defs")?.get(name))
+        {
+            Some(definition) => check(root, definition, value, pointer, problems),
+            None => problems.push((
+                pointer.to_string(),
+                format!("Schema reference {} not found", reference),
+            )),
+        }
+        return;
+    }
+
+    if let Some(types) = schema.get("type") {
+        let allowed = match types {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let actual = type_name(value);
+        let matches = allowed
+            .iter()
+            .any(|name| *name == actual || (*name == "number" && actual == "integer"));
+        if !matches {
+            problems.push((
+                pointer.to_string(),
+                format!("Expected {}, found {}", allowed.join(" or "), actual),
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let options = options
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            problems.push((
+                pointer.to_string(),
+                format!("{} is not one of {}", value, options),
+            ));
+        }
+    }
+
+    match value {
+        Value::String(text) => check_string(schema, text, pointer, problems),
+        Value::Number(number) => {
+            if let (Some(minimum), Some(number)) = (
+                schema.get("minimum").and_then(Value::as_f64),
+                number.as_f64(),
+            ) {
+                if number < minimum {
+                    problems.push((
+                        pointer.to_string(),
+                        format!("{} is less than the minimum of {}", number, minimum),
+                    ));
+                }
+            }
+        }
+        Value::Object(object) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(name) {
+                        problems.push((
+                            pointer.to_string(),
+                            format!("Missing required field '{}'", name),
+                        ));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, child) in object {
+                let child_pointer = format!("{}/{}", pointer, escape_pointer(name));
+                match (
+                    properties.and_then(|properties| properties.get(name)),
+                    schema.get("additionalProperties"),
+                ) {
+                    (Some(property), _) => check(root, property, child, &child_pointer, problems),
+                    (None, Some(Value::Bool(false))) => {
+                        let suggestion = properties
+                            .and_then(|properties| closest(name, properties.keys()))
+                            .map(|known| format!(", did you mean '{}'?", known))
+                            .unwrap_or_default();
+                        problems.push((
+                            child_pointer,
+                            format!("Unknown field '{}'{}", name, suggestion),
+                        ));
+                    }
+                    (None, Some(additional @ Value::Object(_))) => {
+                        check(root, additional, child, &child_pointer, problems)
+                    }
+                    (None, _) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(
+                        root,
+                        item_schema,
+                        item,
+                        &format!("{}/{}", pointer, i),
+                        problems,
+                    );
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn check_string(schema: &Value, text: &str, pointer: &str, problems: &mut Vec<(String, String)>) {
+    if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+        let matches = Regex::new(pattern)
+            .map(|regex| regex.is_match(text))
+            .unwrap_or(true);
+        if !matches {
+            let expected = schema
+                .get("description")
+                .and_then(Value::as_str)
+                .map(lowercase_first)
+                .unwrap_or_else(|| format!("text matching {}", pattern));
+            problems.push((
+                pointer.to_string(),
+                format!("'{}' is malformed, expected {}", text, expected),
+            ));
+        }
+    }
+
+    let problem = match schema.get("format").and_then(Value::as_str) {
+        Some("uri") => reqwest::Url::parse(text)
+            .err()
+            .map(|e| format!("'{}' is not a valid URL ({})", text, e)),
+        Some("email") => {
+            (!is_email(text)).then(|| format!("'{}' is not a valid email address", text))
+        }
+        Some("date-time") => chrono::DateTime::parse_from_rfc3339(text).err().map(|_| {
+            format!(
+                "'{}' is not a valid date and time, expected RFC 3339 like 2026-10-17T09:00:00Z",
+                text
+            )
+        }),
+        _ => None,
+    };
+    if let Some(message) = problem {
+        problems.push((pointer.to_string(), message));
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn is_email(text: &str) -> bool {
+    match text.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !text.chars().any(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+fn lowercase_first(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn escape_pointer(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// The known field closest to a misspelled one, if any is close enough
+pub fn closest<'a>(name: &str, known: impl Iterator<Item = &'a String>) -> Option<&'a String> {
+    known
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, candidate)| *distance <= 2.max(candidate.len() / 4))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let first_row = (0..=b.len()).collect::<Vec<_>>();
+    a.chars()
+        .enumerate()
+        .fold(first_row, |previous, (i, a_char)| {
+            let mut row = vec![i + 1; b.len() + 1];
+            for (j, b_char) in b.iter().enumerate() {
+                let substitution = previous[j] + usize::from(a_char != *b_char);
+                row[j + 1] = substitution.min(previous[j + 1] + 1).min(row[j] + 1);
+            }
+            row
+        })[b.len()]
+}
+
+/// Position of a value, or of its closest ancestor that has one
+fn position(positions: &HashMap<String, (usize, usize)>, pointer: &str) -> (usize, usize) {
+    let mut pointer = pointer;
+    loop {
+        if let Some(position) = positions.get(pointer) {
+            return *position;
+        }
+        match pointer.rfind('/') {
+            Some(i) => pointer = &pointer[..i],
+            None => return (1, 1),
+        }
+    }
+}
+
+/// Find where every value starts in a JSON document
+///
+/// # Returns
+///
+/// Line and column by JSON pointer; object members point at their key
+fn locate(source: &str) -> HashMap<String, (usize, usize)> {
+    let mut scanner = Scanner {
+        chars: source.chars().collect(),
+        index: 0,
+        line: 1,
+        column: 1,
+        positions: HashMap::new(),
+    };
+    scanner.value(String::new());
+    scanner.positions
+}
+
+/// Walks a JSON document that is known to be valid, tracking positions
+struct Scanner {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+    positions: HashMap<String, (usize, usize)>,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self, pointer: String) {
+        self.skip_whitespace();
+        self.positions
+            .entry(pointer.clone())
+            .or_insert((self.line, self.column));
+
+        match self.peek() {
+            Some('{') => {
+                self.advance();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some('"') {
+                        self.advance();
+                        break;
+                    }
+                    let position = (self.line, self.column);
+                    let key = self.string();
+                    let child = format!("{}/{}", pointer, escape_pointer(&key));
+                    self.positions.insert(child.clone(), position);
+                    self.skip_whitespace();
+                    self.advance(); // ':'
+                    self.value(child);
+                    self.skip_whitespace();
+                    if self.advance() != Some(',') {
+                        break;
+                    }
+                }
+            }
+            Some('[') => {
+                self.advance();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.advance();
+                    return;
+                }
+                for i in 0.. {
+                    self.value(format!("{}/{}", pointer, i));
+                    self.skip_whitespace();
+                    if self.advance() != Some(',') {
+                        break;
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            Some(_) => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !matches!(c, ',' | ']' | '}') && !c.is_whitespace())
+                {
+                    self.advance();
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn string(&mut self) -> String {
+        self.advance(); // opening quote
+        let mut text = String::new();
+        while let Some(c) = self.advance() {
+            match c {
+                '"' => break,
+                '\\' => match self.advance() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some('u') => {
+                        let hex = (0..4).filter_map(|_| self.advance()).collect::<String>();
+                        text.push(
+                            u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .unwrap_or(char::REPLACEMENT_CHARACTER),
+                        );
+                    }
+                    Some(other) => text.push(other),
+                    None => break,
+                },
+                other => text.push(other),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::Cv;
+
+    #[test]
+    fn test_valid_data_passes() {
+        let source = std::fs::read_to_string("data/cv_data.json").unwrap();
+        assert_eq!(validate_json(&source).unwrap(), Vector::new());
+
+        // Everything the CV serializes to is in the schema
+        let cv = serde_json::to_string_pretty(&Cv::create_minimal()).unwrap();
+        assert_eq!(validate_json(&cv).unwrap(), Vector::new());
+    }
+
+    #[test]
+    fn test_reports_problems_with_positions() {
+        let source = r#"{
+  "personal_info": {
+    "name": "Ada",
+    "title": "Engineer",
+    "email": "ada@example",
+    "website": "example.com",
+    "summary": "Hi",
+    "social_links": {}
+  },
+  "experiences": [
+    {
+      "company": "Acme",
+      "position": "Engineer",
+      "start_date": "2020/01",
+      "description": "Built things",
+      "acheivements": [],
+      "technologies": []
+    }
+  ],
+  "education": [],
+  "skill_categories": [],
+  "projects": [],
+  "languages": {}
+}"#;
+
+        let errors = validate_json(source)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "1:1: Missing required field 'certifications' (at /)",
+                "5:5: 'ada@example' is not a valid email address (at /personal_info/email)",
+                "6:5: 'example.com' is not a valid URL (relative URL without a base) (at /personal_info/website)",
+                "11:5: Missing required field 'achievements' (at /experiences/0)",
+                "14:7: '2020/01' is malformed, expected a date as YYYY, YYYY-MM or YYYY-MM-DD (at /experiences/0/start_date)",
+                "16:7: Unknown field 'acheivements', did you mean 'achievements'? (at /experiences/0/acheivements)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_syntax_errors() {
+        let errors = validate_json("{\n  \"personal_info\": {,\n}").unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].line, errors[0].column), (2, 21));
+        assert!(errors[0].message.starts_with("Invalid JSON: "));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("acheivements", "achievements"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
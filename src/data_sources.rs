@@ -0,0 +1,565 @@
+//! Data sources
+//!
+//! The CV data, site configuration and content can live outside the working
+//! tree: on a `content` branch, behind a URL or in a private directory. They
+//! are declared by their path in the working tree in `config.toml`, together
+//! with where to load them from:
+//!
+//! ```toml
+//! [data_sources."data/cv_data.json"]
+//! git = "origin/content"
+//! sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//!
+//! [data_sources."content"]
+//! git = "origin/content"
+//! local = "../cv-private/content"
+//! required = true
+//! ```
+//!
+//! Locations are tried in the order git, URL, local path, and the first one
+//! that has the data wins and is written to the declared path. When none has
+//! it, the file already in the working tree is used, unless the source is
+//! `required`. A checksum mismatch always fails the build, as does a location
+//! that exists but can't be read.
+//!
+//! What each fetch wrote is recorded in [`DATA_SOURCES_MANIFEST`]. Files a
+//! later fetch no longer has are deleted, so content removed upstream isn't
+//! published any longer, and files changed in the working tree since they
+//! were fetched are never overwritten or deleted unless the build is told to.
+//! Files no fetch has written yet, such as a fresh checkout's own copy on the
+//! first fetch, are taken over.
+
+use anyhow::{Context, Result};
+use im::OrdMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::github::block_on;
+
+/// What the previous fetches wrote, by declared path
+pub const DATA_SOURCES_MANIFEST: &str = "cache/data_sources.json";
+
+/// Files written for each declared path, with the SHA-256 of what was written
+///
+/// The file of a declared file is recorded under the empty path, the files of
+/// a declared directory relative to it.
+type Manifest = BTreeMap<String, BTreeMap<String, String>>;
+
+/// Where the data at a path in the working tree can be loaded from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataSource {
+    /// Git ref holding the data, e.g. `origin/content`
+    #[serde(default)]
+    pub git: Option<String>,
+
+    /// Path of the data in the git ref (default: the declared path)
+    #[serde(default)]
+    pub git_path: Option<String>,
+
+    /// URL of the data; files only
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Local file or directory holding the data
+    #[serde(default)]
+    pub local: Option<PathBuf>,
+
+    /// Expected SHA-256 of the file, as lowercase hex; files only
+    #[serde(default)]
+    pub sha256: Option<String>,
+
+    /// Whether the build fails when no location has the data
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// A file loaded from a data source
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DataFile {
+    /// Path relative to the declared path; empty for a single file
+    relative: PathBuf,
+    /// File content
+    content: Vec<u8>,
+}
+
+impl DataSource {
+    /// Load the data from the first location that has it
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The declared path in the working tree
+    /// * `offline` - Whether to skip the URL
+    ///
+    /// # Returns
+    ///
+    /// The files and a description of where they came from, or None if no
+    /// location has the data
+    fn fetch(&self, path: &str, offline: bool) -> Result<Option<(Vec<DataFile>, String)>> {
+        if let Some(ref reference) = self.git {
+            let git_path = self.git_path.as_deref().unwrap_or(path);
+            if let Some(files) = from_git(reference, git_path)? {
+                return Ok(Some((files, format!("git {}:{}", reference, git_path))));
+            }
+        }
+
+        if let Some(ref url) = self.url {
+            if offline {
+                println!("📴 Skipping {} for {}, the build is offline", url, path);
+            } else if let Some(files) = from_url(url)? {
+                return Ok(Some((files, format!("url {}", url))));
+            }
+        }
+
+        if let Some(ref local) = self.local {
+            if let Some(files) = from_local(local)? {
+                return Ok(Some((files, format!("local {}", local.display()))));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Load every declared data source into the working tree
+///
+/// # Arguments
+///
+/// * `sources` - Data sources by their path in the working tree
+/// * `manifest_path` - Where what was fetched is recorded, usually
+///   [`DATA_SOURCES_MANIFEST`]
+/// * `offline` - Whether to skip URLs
+/// * `overwrite` - Whether to replace files changed in the working tree
+///   since they were fetched
+///
+/// # Returns
+///
+/// The number of data sources loaded
+pub fn load_data_sources(
+    sources: &OrdMap<String, DataSource>,
+    manifest_path: &Path,
+    offline: bool,
+    overwrite: bool,
+) -> Result<usize> {
+    let mut manifest = load_manifest(manifest_path)?;
+    let mut loaded = 0;
+    for (path, source) in sources.iter() {
+        match source.fetch(path, offline)? {
+            Some((files, origin)) => {
+                if let Some(ref expected) = source.sha256 {
+                    verify_checksum(path, &files, expected)?;
+                }
+                let previous = manifest.remove(path).unwrap_or_default();
+                let (written, removed) =
+                    write_files(Path::new(path), &files, &previous, overwrite)?;
+                manifest.insert(path.clone(), written);
+                save_manifest(manifest_path, &manifest)?;
+                println!("📥 Loaded {} from {} ({} files)", path, origin, files.len());
+                if removed > 0 {
+                    println!("🗑️  Removed {} files no longer in {}", removed, path);
+                }
+                loaded += 1;
+            }
+            None if source.required => {
+                anyhow::bail!(
+                    "Required data source {} was not found in any location",
+                    path
+                )
+            }
+            None if Path::new(path).exists() => {
+                println!("ℹ️  No data source has {}, using the local copy", path)
+            }
+            None => println!(
+                "⚠️  No data source has {}, and there is no local copy",
+                path
+            ),
+        }
+    }
+    Ok(loaded)
+}
+
+/// Check a single loaded file against its expected SHA-256
+fn verify_checksum(path: &str, files: &[DataFile], expected: &str) -> Result<()> {
+    let [file] = files else {
+        anyhow::bail!(
+            "Checksums are only supported for files, {} is a directory",
+            path
+        );
+    };
+
+    let actual = format!("{:x}", Sha256::digest(&file.content));
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        anyhow::bail!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            path,
+            expected.trim(),
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Write loaded files under the declared path and delete the ones gone upstream
+///
+/// # Arguments
+///
+/// * `path` - The declared path
+/// * `files` - The loaded files
+/// * `previous` - What the previous fetch wrote, from the manifest
+/// * `overwrite` - Whether to replace or delete files changed locally
+///
+/// # Returns
+///
+/// What was written, for the manifest, and the number of files deleted, or
+/// an error naming the locally changed files if `overwrite` isn't set
+fn write_files(
+    path: &Path,
+    files: &[DataFile],
+    previous: &BTreeMap<String, String>,
+    overwrite: bool,
+) -> Result<(BTreeMap<String, String>, usize)> {
+    let written: BTreeMap<String, String> = files
+        .iter()
+        .map(|file| {
+            (
+                file.relative.to_string_lossy().replace('\\', "/"),
+                format!("{:x}", Sha256::digest(&file.content)),
+            )
+        })
+        .collect();
+    let stale: Vec<&String> = previous
+        .keys()
+        .filter(|relative| !written.contains_key(*relative))
+        .collect();
+
+    // A file is changed locally when it's neither what the previous fetch
+    // wrote nor what this one would write. Files no fetch has written yet,
+    // such as the working tree's copy on the first fetch, are taken over.
+    let changed: Vec<String> = written
+        .iter()
+        .map(|(relative, hash)| (relative, Some(hash)))
+        .chain(stale.iter().map(|relative| (*relative, None)))
+        .filter_map(|(relative, hash)| {
+            let recorded = previous.get(relative)?;
+            let target = target_path(path, relative);
+            let current = format!("{:x}", Sha256::digest(fs::read(&target).ok()?));
+            let unchanged = Some(&current) == hash || *recorded == current;
+            (!unchanged).then(|| target.display().to_string())
+        })
+        .collect();
+    if !changed.is_empty() && !overwrite {
+        anyhow::bail!(
+            "{} changed locally since the last fetch: {}. Commit them upstream, or build with --overwrite-data to replace them",
+            path.display(),
+            changed.join(", ")
+        );
+    }
+
+    for file in files {
+        let target = if file.relative.as_os_str().is_empty() {
+            path.to_path_buf()
+        } else {
+            path.join(&file.relative)
+        };
+        if let Some(parent) = target.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+        }
+        fs::write(&target, &file.content)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+    }
+
+    let mut removed = 0;
+    for relative in stale {
+        let target = target_path(path, relative);
+        match fs::remove_file(&target) {
+            Ok(()) => removed += 1,
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to remove {}", target.display()))
+            }
+        }
+        // Directories emptied by the removal go too, up to the declared path
+        let mut dir = target.parent();
+        while let Some(parent) = dir.filter(|dir| *dir != path && dir.starts_with(path)) {
+            if fs::remove_dir(parent).is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+    }
+    Ok((written, removed))
+}
+
+/// Path in the working tree of a file recorded in the manifest
+fn target_path(path: &Path, relative: &str) -> PathBuf {
+    if relative.is_empty() {
+        path.to_path_buf()
+    } else {
+        path.join(relative)
+    }
+}
+
+/// Read the manifest of the previous fetches, empty if there is none
+fn load_manifest(path: &Path) -> Result<Manifest> {
+    match fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Manifest::new()),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+/// Record what the fetches wrote
+fn save_manifest(path: &Path, manifest: &Manifest) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(manifest)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Load a file or directory from a git ref
+fn from_git(reference: &str, path: &str) -> Result<Option<Vec<DataFile>>> {
+    let path = path.trim_end_matches('/');
+    let object = format!("{}:{}", reference, path);
+    let kind = match git(&["cat-file", "-t", &object]) {
+        Ok(kind) => String::from_utf8_lossy(&kind).trim().to_string(),
+        // A missing ref or path falls through to the next location
+        Err(_) => return Ok(None),
+    };
+
+    match kind.as_str() {
+        "blob" => Ok(Some(vec![DataFile {
+            relative: PathBuf::new(),
+            content: git(&["show", &object])?,
+        }])),
+        "tree" => {
+            let listing = git(&["ls-tree", "-r", "--name-only", reference, "--", path])?;
+            String::from_utf8_lossy(&listing)
+                .lines()
+                .map(|name| {
+                    Ok(DataFile {
+                        relative: Path::new(name)
+                            .strip_prefix(path)
+                            .unwrap_or(Path::new(name))
+                            .to_path_buf(),
+                        content: git(&["show", &format!("{}:{}", reference, name)])?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+                .map(Some)
+        }
+        other => anyhow::bail!("{} is a git {}, not a file or directory", object, other),
+    }
+}
+
+/// Run git and return its output
+fn git(args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+/// Download a file
+fn from_url(url: &str) -> Result<Option<Vec<DataFile>>> {
+    block_on(async {
+        let response = reqwest::get(url)
+            .await
+            .with_context(|| format!("Failed to request {}", url))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let content = response
+            .error_for_status()
+            .with_context(|| format!("Request to {} failed", url))?
+            .bytes()
+            .await
+            .with_context(|| format!("Failed to download {}", url))?;
+        Ok(Some(vec![DataFile {
+            relative: PathBuf::new(),
+            content: content.to_vec(),
+        }]))
+    })
+}
+
+/// Read a local file or directory
+fn from_local(path: &Path) -> Result<Option<Vec<DataFile>>> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_dir() => read_dir(path, path).map(Some),
+        Ok(_) => Ok(Some(vec![DataFile {
+            relative: PathBuf::new(),
+            content: fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        }])),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn read_dir(root: &Path, dir: &Path) -> Result<Vec<DataFile>> {
+    let mut files = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(read_dir(root, &path)?);
+        } else {
+            files.push(DataFile {
+                relative: path.strip_prefix(root).unwrap_or(&path).to_path_buf(),
+                content: fs::read(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+            });
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_local_sources() {
+        let dir = tempfile::tempdir().unwrap();
+        let private = dir.path().join("private");
+        fs::create_dir_all(private.join("blog")).unwrap();
+        fs::write(private.join("cv_data.json"), "{}").unwrap();
+        fs::write(private.join("blog").join("hello.md"), "# Hello").unwrap();
+
+        let cv_data = dir.path().join("data").join("cv_data.json");
+        let content = dir.path().join("content");
+        let source = |local: PathBuf, sha256: Option<&str>| DataSource {
+            git: Some("no-such-ref".to_string()),
+            local: Some(local),
+            sha256: sha256.map(str::to_string),
+            ..DataSource::default()
+        };
+        let sources = OrdMap::from(vec![
+            (
+                cv_data.to_string_lossy().into_owned(),
+                source(
+                    private.join("cv_data.json"),
+                    // SHA-256 of "{}"
+                    Some("44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"),
+                ),
+            ),
+            (
+                content.to_string_lossy().into_owned(),
+                source(private.clone(), None),
+            ),
+        ]);
+
+        let manifest = dir.path().join("cache").join("data_sources.json");
+        assert_eq!(
+            load_data_sources(&sources, &manifest, true, false).unwrap(),
+            2
+        );
+        assert_eq!(fs::read_to_string(&cv_data).unwrap(), "{}");
+        assert_eq!(
+            fs::read_to_string(content.join("blog").join("hello.md")).unwrap(),
+            "# Hello"
+        );
+
+        let tampered = OrdMap::unit(
+            cv_data.to_string_lossy().into_owned(),
+            source(private.join("cv_data.json"), Some("00")),
+        );
+        assert!(load_data_sources(&tampered, &manifest, true, false).is_err());
+
+        let missing = OrdMap::unit(
+            "data/none.json".to_string(),
+            DataSource {
+                required: true,
+                ..source(private.join("none.json"), None)
+            },
+        );
+        assert!(load_data_sources(&missing, &manifest, true, false).is_err());
+    }
+
+    #[test]
+    fn test_refetch_prunes_and_keeps_local_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let upstream = dir.path().join("upstream");
+        fs::create_dir_all(upstream.join("blog")).unwrap();
+        fs::write(upstream.join("blog").join("hello.md"), "# Hello").unwrap();
+        fs::write(upstream.join("blog").join("old.md"), "# Old").unwrap();
+
+        let content = dir.path().join("content");
+        let manifest = dir.path().join("data_sources.json");
+        let sources = OrdMap::unit(
+            content.to_string_lossy().into_owned(),
+            DataSource {
+                local: Some(upstream.clone()),
+                ..DataSource::default()
+            },
+        );
+        load_data_sources(&sources, &manifest, true, false).unwrap();
+        assert!(content.join("blog").join("old.md").is_file());
+
+        // Removed upstream: deleted from the working tree on the next fetch
+        fs::remove_file(upstream.join("blog").join("old.md")).unwrap();
+        load_data_sources(&sources, &manifest, true, false).unwrap();
+        assert!(!content.join("blog").join("old.md").exists());
+
+        // Edited locally: kept, unless overwriting is allowed
+        let hello = content.join("blog").join("hello.md");
+        fs::write(&hello, "# Hello, edited").unwrap();
+        let error = load_data_sources(&sources, &manifest, true, false)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("changed locally since the last fetch"));
+        assert_eq!(fs::read_to_string(&hello).unwrap(), "# Hello, edited");
+
+        load_data_sources(&sources, &manifest, true, true).unwrap();
+        assert_eq!(fs::read_to_string(&hello).unwrap(), "# Hello");
+    }
+
+    #[test]
+    fn test_first_fetch_takes_over_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let upstream = dir.path().join("upstream");
+        fs::create_dir_all(&upstream).unwrap();
+        fs::write(upstream.join("cv_data.json"), r#"{"from": "content"}"#).unwrap();
+
+        // A fresh checkout has its own copy, and no manifest yet
+        let data = dir.path().join("data");
+        fs::create_dir_all(&data).unwrap();
+        fs::write(data.join("cv_data.json"), r#"{"from": "main"}"#).unwrap();
+        let manifest = dir.path().join("cache").join("data_sources.json");
+        let sources = OrdMap::unit(
+            data.to_string_lossy().into_owned(),
+            DataSource {
+                local: Some(upstream.clone()),
+                required: true,
+                ..DataSource::default()
+            },
+        );
+
+        load_data_sources(&sources, &manifest, false, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(data.join("cv_data.json")).unwrap(),
+            r#"{"from": "content"}"#
+        );
+        assert!(manifest.is_file());
+
+        // From then on, local edits are kept
+        fs::write(data.join("cv_data.json"), r#"{"from": "edit"}"#).unwrap();
+        assert!(load_data_sources(&sources, &manifest, false, false).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! CV data in SQLite
+//!
+//! An alternative to `data/cv_data.json` as the source of the CV: build with
+//! `cv build --source db` (or `cv_source = "db"` in `config.toml`) to read it
+//! from `data/cv.db`. Each top-level section of the CV (`personal_info`,
+//! `experiences`, ...) is a row holding its JSON, so a section can be edited
+//! with any SQLite client without touching the rest. `cv db sync` copies the
+//! CV between the JSON file and the database in either direction.
+//!
+//! The same database holds the job applications tracked with
+//! `cv applications` (see [`crate::applications`]), the private notes on
+//! CV entries (see [`crate::prep_notes`]), the webhook deliveries (see
+//! [`crate::webhooks`]) and a log of its own maintenance (see
+//! [`crate::db_maintenance`]).
+//!
+//! The schema is versioned with SQLite's `user_version` and migrated forward
+//! when the database is opened.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+
+use crate::cv_data::Cv;
+
+/// Default path of the CV database
+pub const DEFAULT_DB_PATH: &str = "data/cv.db";
+
+/// Schema migrations, in order; the database's `user_version` is the number applied
+const MIGRATIONS: [&str; 6] = [
+    "CREATE TABLE cv_sections (
+        name TEXT PRIMARY KEY,
+        data TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );",
+    "CREATE TABLE applications (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        company TEXT NOT NULL,
+        role TEXT NOT NULL,
+        variant TEXT,
+        status TEXT NOT NULL,
+        applied_on TEXT,
+        follow_up TEXT,
+        notes TEXT,
+        updated_at TEXT NOT NULL
+    );",
+    "CREATE TABLE notes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        kind TEXT NOT NULL,
+        entry TEXT NOT NULL,
+        body TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );",
+    "CREATE TABLE content_fingerprints (
+        key TEXT PRIMARY KEY,
+        fingerprint TEXT NOT NULL
+    );
+    CREATE TABLE webhook_deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        endpoint TEXT NOT NULL,
+        event TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        status TEXT NOT NULL,
+        attempts INTEGER NOT NULL,
+        next_attempt_at TEXT NOT NULL,
+        response_status INTEGER,
+        last_error TEXT,
+        created_at TEXT NOT NULL,
+        delivered_at TEXT
+    );",
+    "CREATE TABLE maintenance_runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        ran_at TEXT NOT NULL,
+        healthy INTEGER NOT NULL,
+        report TEXT NOT NULL
+    );",
+    // Skills written as plain names become objects, like skills with a level
+    "UPDATE cv_sections SET data = (
+        SELECT json_group_array(json_set(category.value, '$.skills', json((
+            SELECT json_group_array(CASE skill.type
+                WHEN 'text' THEN json_object('name', skill.value)
+                ELSE json(skill.value)
+            END)
+            FROM json_each(category.value, '$.skills') AS skill
+        ))))
+        FROM json_each(cv_sections.data) AS category
+    )
+    WHERE name = 'skill_categories';",
+];
+
+/// Where the build reads the CV from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvSource {
+    /// The JSON file at `data_path`
+    Json,
+    /// The SQLite database at `db_path`
+    Db,
+}
+
+impl fmt::Display for CvSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CvSource::Json => "json",
+            CvSource::Db => "db",
+        })
+    }
+}
+
+impl std::str::FromStr for CvSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "json" => Ok(CvSource::Json),
+            "db" => Ok(CvSource::Db),
+            other => anyhow::bail!("Unknown CV source '{}' (expected json or db)", other),
+        }
+    }
+}
+
+/// The CV database
+#[derive(Debug)]
+pub struct Database {
+    pub(crate) connection: Connection,
+}
+
+impl Database {
+    /// Open the database, creating and migrating it as needed
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the database file
+    ///
+    /// # Returns
+    ///
+    /// The database, or an error if it can't be opened or migrated
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut connection = Connection::open(path)
+            .with_context(|| format!("Failed to open CV database {}", path.display()))?;
+        migrate(&mut connection)
+            .with_context(|| format!("Failed to migrate CV database {}", path.display()))?;
+        Ok(Self { connection })
+    }
+
+    /// Replace the CV in the database
+    pub fn save_cv(&mut self, cv: &Cv) -> Result<()> {
+        let Value::Object(sections) = serde_json::to_value(cv)? else {
+            anyhow::bail!("CV data doesn't serialize to an object");
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let transaction = self.connection.transaction()?;
+        transaction.execute("DELETE FROM cv_sections", [])?;
+        for (name, data) in sections {
+            transaction.execute(
+                "INSERT INTO cv_sections (name, data, updated_at) VALUES (?1, ?2, ?3)",
+                params![name, serde_json::to_string(&data)?, now],
+            )?;
+        }
+        transaction.commit().context("Failed to save CV data")
+    }
+
+    /// Load the CV from the database
+    ///
+    /// # Returns
+    ///
+    /// The CV, or an error if the database holds none or it doesn't parse
+    pub fn load_cv(&self) -> Result<Cv> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT name, data FROM cv_sections")?;
+        let sections = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .map(|row| {
+                let (name, data) = row?;
+                let value = serde_json::from_str::<Value>(&data)
+                    .with_context(|| format!("Invalid JSON in CV section '{}'", name))?;
+                Ok((name, value))
+            })
+            .collect::<Result<Map<_, _>>>()?;
+
+        if sections.is_empty() {
+            anyhow::bail!("The CV database is empty; run `cv db sync --from json --to db` first");
+        }
+        serde_json::from_value(Value::Object(sections))
+            .context("Failed to parse CV data from database")
+    }
+}
+
+/// Apply the migrations the database hasn't had yet
+fn migrate(connection: &mut Connection) -> Result<()> {
+    let version: usize = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!(
+            "Database schema version {} is newer than this cv ({})",
+            version,
+            MIGRATIONS.len()
+        );
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let transaction = connection.transaction()?;
+        transaction.execute_batch(migration)?;
+        transaction.pragma_update(None, "user_version", index + 1)?;
+        transaction.commit()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_cv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cv.db");
+
+        let mut db = Database::open(&path).unwrap();
+        assert!(db.load_cv().is_err());
+
+        let cv = Cv::create_minimal();
+        db.save_cv(&cv).unwrap();
+        db.save_cv(&cv).unwrap();
+
+        // Reopening doesn't rerun the migrations
+        let loaded = Database::open(&path).unwrap().load_cv().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&cv).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_migrate_skills_to_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cv.db");
+
+        let db = Database::open(&path).unwrap();
+        db.connection
+            .execute(
+                "INSERT INTO cv_sections (name, data, updated_at) VALUES ('skill_categories', ?1, '')",
+                [r#"[{"name":"Languages","skills":["Rust",{"name":"Go","level":"expert"}]}]"#],
+            )
+            .unwrap();
+        db.connection
+            .pragma_update(None, "user_version", MIGRATIONS.len() - 1)
+            .unwrap();
+        drop(db);
+
+        let db = Database::open(&path).unwrap();
+        let data: String = db
+            .connection
+            .query_row(
+                "SELECT data FROM cv_sections WHERE name = 'skill_categories'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&data).unwrap(),
+            serde_json::json!([{
+                "name": "Languages",
+                "skills": [{"name": "Rust"}, {"name": "Go", "level": "expert"}]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_cv_source() {
+        assert_eq!("DB".parse::<CvSource>().unwrap(), CvSource::Db);
+        assert_eq!("json".parse::<CvSource>().unwrap(), CvSource::Json);
+        assert!("yaml".parse::<CvSource>().is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Maintenance of the CV database
+//!
+//! `cv db maintain` checks and tidies `data/cv.db`, and builds do the same
+//! when the last run is older than `db_maintenance_days` (7 by default, 0
+//! turns it off):
+//!
+//! 1. `PRAGMA integrity_check`; a damaged database is left alone from here on
+//! 2. `ANALYZE`, so the query planner knows the current table sizes
+//! 3. Free pages are returned to the file system once they add up to
+//!    [`MaintenanceThresholds::vacuum_free_bytes`], with
+//!    `PRAGMA incremental_vacuum`; a database that doesn't use incremental
+//!    auto-vacuum yet is switched to it with one full `VACUUM`
+//! 4. In WAL mode, the write-ahead log is checkpointed and truncated once it
+//!    reaches [`MaintenanceThresholds::checkpoint_wal_bytes`]
+//!
+//! Each run is logged and recorded in the `maintenance_runs` table. The
+//! database is opened per command rather than through a connection pool, and
+//! the site has no health endpoint, so these records and `cv db maintain` are
+//! where the results show up.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::fs;
+use std::time::Instant;
+use tracing::{info, warn};
+
+use crate::db::Database;
+
+/// SQLite's `auto_vacuum` value for incremental vacuuming
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// When free pages and the write-ahead log are worth reclaiming
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceThresholds {
+    /// Bytes of free pages that trigger a vacuum
+    pub vacuum_free_bytes: u64,
+    /// Bytes of write-ahead log that trigger a checkpoint
+    pub checkpoint_wal_bytes: u64,
+}
+
+impl Default for MaintenanceThresholds {
+    fn default() -> Self {
+        Self {
+            vacuum_free_bytes: 1024 * 1024,
+            checkpoint_wal_bytes: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// What a maintenance run found and did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    /// When the run started
+    pub ran_at: DateTime<Utc>,
+    /// Problems reported by the integrity check, empty if there were none
+    pub integrity_problems: Vec<String>,
+    /// Whether the statistics were refreshed
+    pub analyzed: bool,
+    /// Bytes of free pages before vacuuming
+    pub free_bytes: u64,
+    /// Whether free pages were reclaimed
+    pub vacuumed: bool,
+    /// Bytes of write-ahead log before checkpointing, 0 outside WAL mode
+    pub wal_bytes: u64,
+    /// Whether the write-ahead log was checkpointed
+    pub checkpointed: bool,
+    /// Size of the database before the run, in bytes
+    pub size_before: u64,
+    /// Size of the database after the run, in bytes
+    pub size_after: u64,
+    /// How long the run took, in milliseconds
+    pub millis: u128,
+}
+
+impl MaintenanceReport {
+    /// Whether the integrity check passed
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_problems.is_empty()
+    }
+}
+
+/// Whether maintenance is due
+///
+/// # Arguments
+///
+/// * `last_run` - When maintenance last ran, if ever
+/// * `now` - The current time
+/// * `interval_days` - Days between runs, 0 for never
+pub fn is_due(last_run: Option<DateTime<Utc>>, now: DateTime<Utc>, interval_days: u32) -> bool {
+    interval_days > 0
+        && last_run.is_none_or(|last_run| now - last_run >= Duration::days(interval_days.into()))
+}
+
+impl Database {
+    /// Check the integrity of the database and tidy it up
+    ///
+    /// # Arguments
+    ///
+    /// * `thresholds` - When to vacuum and checkpoint
+    ///
+    /// # Returns
+    ///
+    /// What the run found and did; a failed integrity check is reported, not
+    /// returned as an error
+    pub fn maintain(&self, thresholds: &MaintenanceThresholds) -> Result<MaintenanceReport> {
+        let start = Instant::now();
+        let ran_at = Utc::now();
+        let size_before = self.size()?;
+        let mut report = MaintenanceReport {
+            ran_at,
+            integrity_problems: self.integrity_problems()?,
+            analyzed: false,
+            free_bytes: self.pragma("freelist_count")? * self.pragma("page_size")?,
+            vacuumed: false,
+            wal_bytes: 0,
+            checkpointed: false,
+            size_before,
+            size_after: size_before,
+            millis: 0,
+        };
+
+        if report.is_healthy() {
+            self.connection
+                .execute_batch("ANALYZE")
+                .context("Failed to analyze the database")?;
+            report.analyzed = true;
+
+            if report.free_bytes >= thresholds.vacuum_free_bytes {
+                self.vacuum()?;
+                report.vacuumed = true;
+            }
+            // Measured last, analyzing and vacuuming also write to the log
+            report.wal_bytes = self.wal_size();
+            if report.wal_bytes >= thresholds.checkpoint_wal_bytes {
+                self.connection
+                    .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+                    .context("Failed to checkpoint the write-ahead log")?;
+                report.checkpointed = true;
+            }
+            report.size_after = self.size()?;
+        } else {
+            warn!(
+                "CV database failed its integrity check: {}",
+                report.integrity_problems.join("; ")
+            );
+        }
+
+        report.millis = start.elapsed().as_millis();
+        info!(
+            "Database maintenance: {} bytes → {} bytes in {} ms",
+            report.size_before, report.size_after, report.millis
+        );
+        self.connection.execute(
+            "INSERT INTO maintenance_runs (ran_at, healthy, report) VALUES (?1, ?2, ?3)",
+            params![
+                ran_at.to_rfc3339(),
+                report.is_healthy(),
+                serde_json::to_string(&report)?
+            ],
+        )?;
+        Ok(report)
+    }
+
+    /// When maintenance last ran
+    pub fn last_maintenance(&self) -> Result<Option<DateTime<Utc>>> {
+        let ran_at = self
+            .connection
+            .query_row(
+                "SELECT ran_at FROM maintenance_runs ORDER BY id DESC LIMIT 1",
+                [],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?;
+        ran_at
+            .map(|ran_at| Ok(DateTime::parse_from_rfc3339(&ran_at)?.with_timezone(&Utc)))
+            .transpose()
+    }
+
+    /// Problems found by `PRAGMA integrity_check`
+    fn integrity_problems(&self) -> Result<Vec<String>> {
+        let mut statement = self.connection.prepare("PRAGMA integrity_check")?;
+        let rows = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to check the database integrity")?;
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+
+    /// Reclaim free pages, switching to incremental auto-vacuum if needed
+    fn vacuum(&self) -> Result<()> {
+        if self.pragma("auto_vacuum")? as i64 == AUTO_VACUUM_INCREMENTAL {
+            self.connection
+                .execute_batch("PRAGMA incremental_vacuum")
+                .context("Failed to vacuum the database")
+        } else {
+            // Changing auto_vacuum only takes effect with a full vacuum
+            self.connection
+                .execute_batch("PRAGMA auto_vacuum = INCREMENTAL; VACUUM")
+                .context("Failed to vacuum the database")
+        }
+    }
+
+    /// Size of the database in bytes, from its page count
+    fn size(&self) -> Result<u64> {
+        Ok(self.pragma("page_count")? * self.pragma("page_size")?)
+    }
+
+    /// Size of the write-ahead log file, 0 if there is none
+    fn wal_size(&self) -> u64 {
+        self.connection
+            .path()
+            .filter(|path| !path.is_empty())
+            .and_then(|path| fs::metadata(format!("{path}-wal")).ok())
+            .map(|metadata| metadata.len())
+            .unwrap_or(0)
+    }
+
+    /// Read a numeric pragma
+    fn pragma(&self, name: &str) -> Result<u64> {
+        self.connection
+            .pragma_query_value(None, name, |row| row.get::<_, i64>(0))
+            .map(|value| value.max(0) as u64)
+            .with_context(|| format!("Failed to read PRAGMA {}", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintain_reclaims_free_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("cv.db")).unwrap();
+        assert_eq!(db.last_maintenance().unwrap(), None);
+
+        let body = "x".repeat(4096);
+        for _ in 0..100 {
+            db.connection
+                .execute(
+                    "INSERT INTO notes (kind, entry, body, created_at) VALUES ('project', 'cv', ?1, '')",
+                    [&body],
+                )
+                .unwrap();
+        }
+        db.connection.execute("DELETE FROM notes", []).unwrap();
+
+        let thresholds = MaintenanceThresholds {
+            vacuum_free_bytes: 64 * 1024,
+            ..Default::default()
+        };
+        let report = db.maintain(&thresholds).unwrap();
+        assert!(report.is_healthy());
+        assert!(report.analyzed);
+        assert!(report.vacuumed);
+        assert!(report.size_after < report.size_before);
+        assert_eq!(
+            db.pragma("auto_vacuum").unwrap() as i64,
+            AUTO_VACUUM_INCREMENTAL
+        );
+        assert_eq!(db.last_maintenance().unwrap(), Some(report.ran_at));
+
+        // Nothing left to reclaim
+        let report = db.maintain(&thresholds).unwrap();
+        assert!(!report.vacuumed);
+        assert!(!report.checkpointed);
+    }
+
+    #[test]
+    fn test_maintain_checkpoints_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("cv.db")).unwrap();
+        db.connection
+            .pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))
+            .unwrap();
+        db.connection
+            .execute(
+                "INSERT INTO notes (kind, entry, body, created_at) VALUES ('project', 'cv', 'x', '')",
+                [],
+            )
+            .unwrap();
+
+        let thresholds = MaintenanceThresholds {
+            checkpoint_wal_bytes: 1,
+            ..Default::default()
+        };
+        let report = db.maintain(&thresholds).unwrap();
+        assert!(report.wal_bytes > 0);
+        assert!(report.checkpointed);
+        // Only the record of the run is left in the log
+        assert!(db.wal_size() < report.wal_bytes);
+    }
+
+    #[test]
+    fn test_is_due() {
+        let now = Utc::now();
+        assert!(is_due(None, now, 7));
+        assert!(!is_due(Some(now - Duration::days(6)), now, 7));
+        assert!(is_due(Some(now - Duration::days(7)), now, 7));
+        assert!(!is_due(None, now, 0));
+    }
+}
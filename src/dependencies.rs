@@ -98,6 +98,8 @@ fn get_dependency_metadata(name: &str) -> (&'static str, &'static str) {
             "https://github.com/wilsonzlin/minify-html",
             "HTML/CSS optimization",
         ),
+        "lightningcss" => ("https://lightningcss.dev/", "CSS minification"),
+        "oxc_minifier" => ("https://oxc.rs/", "JavaScript minification"),
         "pulldown-cmark" => (
             "https://github.com/raphlinus/pulldown-cmark",
             "Markdown processing",
@@ -0,0 +1,593 @@
+//! Deployment of the built site
+//!
+//! `cv deploy --target <name>` uploads the output directory to one of the
+//! targets configured under `deploy` in the site config:
+//!
+//! - `rsync`: mirrors the directory to a server over SSH
+//! - `s3`: uploads every file to an S3-compatible bucket with its
+//!   `Content-Type` and a `Cache-Control` matching the generated `_headers`
+//! - `github-pages`: replaces a branch with a single commit of the directory
+//!
+//! Deploying never builds; run `cv build` first.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use im::{HashMap, Vector};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::dev_server::content_type_for;
+use crate::html_generator::fingerprint::{is_fingerprinted, is_stored};
+use crate::secrets::{Secret, SecretSource};
+use crate::site_config::DeployBackend;
+
+/// Secret holding the S3 access key id, unless the target names another
+pub const DEFAULT_ACCESS_KEY_SECRET: &str = "s3_access_key_id";
+
+/// Secret holding the S3 secret access key, unless the target names another
+pub const DEFAULT_SECRET_KEY_SECRET: &str = "s3_secret_access_key";
+
+/// Upload the output directory to a deploy target
+///
+/// # Arguments
+///
+/// * `backend` - The configured target
+/// * `output_dir` - The built site
+/// * `secrets` - Declared secrets, for the S3 credentials
+/// * `dry_run` - Only report what would be uploaded
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub async fn deploy(
+    backend: &DeployBackend,
+    output_dir: &Path,
+    secrets: &HashMap<String, SecretSource>,
+    dry_run: bool,
+) -> Result<()> {
+    if !output_dir.join("index.html").is_file() {
+        anyhow::bail!(
+            "No site found in {}, run `cv build` first",
+            output_dir.display()
+        );
+    }
+
+    match backend {
+        DeployBackend::Rsync {
+            destination,
+            port,
+            delete,
+        } => deploy_rsync(
+            output_dir,
+            destination,
+            port.unwrap_or(22),
+            delete.unwrap_or(true),
+            dry_run,
+        ),
+        DeployBackend::S3 {
+            bucket,
+            region,
+            endpoint,
+            prefix,
+            access_key_secret,
+            secret_key_secret,
+        } => {
+            let region = region.as_deref().unwrap_or("us-east-1");
+            let target = S3Target {
+                endpoint: endpoint
+                    .clone()
+                    .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"))
+                    .trim_end_matches('/')
+                    .to_string(),
+                bucket: bucket.clone(),
+                region: region.to_string(),
+                prefix: prefix
+                    .as_deref()
+                    .unwrap_or("")
+                    .trim_matches('/')
+                    .to_string(),
+            };
+            if dry_run {
+                return report_s3_upload(output_dir, &target);
+            }
+            let credentials = S3Credentials {
+                access_key: resolve_secret(
+                    secrets,
+                    access_key_secret
+                        .as_deref()
+                        .unwrap_or(DEFAULT_ACCESS_KEY_SECRET),
+                    "AWS_ACCESS_KEY_ID",
+                )?,
+                secret_key: resolve_secret(
+                    secrets,
+                    secret_key_secret
+                        .as_deref()
+                        .unwrap_or(DEFAULT_SECRET_KEY_SECRET),
+                    "AWS_SECRET_ACCESS_KEY",
+                )?,
+            };
+            deploy_s3(output_dir, &target, &credentials).await
+        }
+        DeployBackend::GithubPages { remote, branch } => deploy_github_pages(
+            output_dir,
+            remote.as_deref().unwrap_or("origin"),
+            branch.as_deref().unwrap_or("gh-pages"),
+            dry_run,
+        ),
+    }
+}
+
+/// Mirror the output directory to a server with rsync
+fn deploy_rsync(
+    output_dir: &Path,
+    destination: &str,
+    port: u16,
+    delete: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // The trailing slash uploads the directory's contents, not the directory
+    let source = format!("{}/", output_dir.display());
+    let mut command = Command::new("rsync");
+    command
+        .args(["--archive", "--compress", "--checksum", "--itemize-changes"])
+        .args(["-e", &format!("ssh -p {port}")]);
+    if delete {
+        command.arg("--delete");
+    }
+    if dry_run {
+        command.arg("--dry-run");
+    }
+
+    println!("🚀 Syncing {} to {}", source, destination);
+    let status = command
+        .arg(&source)
+        .arg(destination)
+        .status()
+        .context("Failed to execute 'rsync'. Is it installed?")?;
+    if !status.success() {
+        anyhow::bail!("rsync failed with {}", status);
+    }
+
+    println!("✅ Deployed to {}", destination);
+    Ok(())
+}
+
+/// Replace a branch of a remote with a single commit of the output directory
+///
+/// The commit is made in a fresh repository in a temporary directory, so the
+/// working copy and its history are never touched.
+fn deploy_github_pages(output_dir: &Path, remote: &str, branch: &str, dry_run: bool) -> Result<()> {
+    let url = git(Path::new("."), &["remote", "get-url", remote])
+        .with_context(|| format!("Failed to find git remote '{}'", remote))?;
+
+    let work_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+    copy_dir(output_dir, work_dir.path())?;
+
+    let message = format!("Deploy site {}", Utc::now().format("%Y-%m-%d %H:%M:%S UTC"));
+    git(work_dir.path(), &["init", "--quiet"])?;
+    git(work_dir.path(), &["checkout", "--quiet", "-b", branch])?;
+    git(work_dir.path(), &["add", "--all"])?;
+    git(work_dir.path(), &["commit", "--quiet", "-m", &message])?;
+
+    if dry_run {
+        println!(
+            "🔍 Would force-push {} files to {} {}",
+            site_files(output_dir)?.len(),
+            remote,
+            branch
+        );
+        return Ok(());
+    }
+
+    println!("🚀 Pushing to {} {}", remote, branch);
+    git(
+        work_dir.path(),
+        &[
+            "push",
+            "--force",
+            "--quiet",
+            &url,
+            &format!("HEAD:{branch}"),
+        ],
+    )?;
+
+    println!("✅ Deployed to branch {} of {}", branch, remote);
+    Ok(())
+}
+
+/// Run a git command and return its trimmed output
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .context("Failed to execute 'git'. Is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    for file in site_files(from)? {
+        let target = to.join(&file);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(from.join(&file), &target).with_context(|| format!("Failed to copy {}", file))?;
+    }
+    Ok(())
+}
+
+/// An S3-compatible bucket to upload to
+struct S3Target {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    prefix: String,
+}
+
+impl S3Target {
+    /// The object key of a file in the output directory
+    fn key(&self, file: &str) -> String {
+        if self.prefix.is_empty() {
+            file.to_string()
+        } else {
+            format!("{}/{}", self.prefix, file)
+        }
+    }
+}
+
+struct S3Credentials {
+    access_key: Secret,
+    secret_key: Secret,
+}
+
+/// Upload every file of the output directory to the bucket
+///
+/// Entries of the content-addressed asset store that are already in the
+/// bucket are skipped.
+async fn deploy_s3(
+    output_dir: &Path,
+    target: &S3Target,
+    credentials: &S3Credentials,
+) -> Result<()> {
+    let files = site_files(output_dir)?;
+    let host = target
+        .endpoint
+        .split_once("://")
+        .map_or(target.endpoint.as_str(), |(_, host)| host)
+        .to_string();
+    let client = reqwest::Client::builder()
+        .user_agent("cv-generator")
+        .build()?;
+
+    println!(
+        "🚀 Uploading {} files to s3://{}/{}",
+        files.len(),
+        target.bucket,
+        target.prefix
+    );
+    let mut unchanged = 0;
+    for file in files.iter() {
+        let path = format!("/{}/{}", target.bucket, uri_encode_path(&target.key(file)));
+        // A store entry's name is its content, so one that's there is up to date
+        if is_stored(file) && object_exists(&client, target, credentials, &host, &path).await? {
+            unchanged += 1;
+            continue;
+        }
+        let body =
+            fs::read(output_dir.join(file)).with_context(|| format!("Failed to read {}", file))?;
+        let headers = vec![
+            (
+                "cache-control".to_string(),
+                cache_control_for(file).to_string(),
+            ),
+            (
+                "content-type".to_string(),
+                content_type_for(Path::new(file)).to_string(),
+            ),
+            ("host".to_string(), host.clone()),
+            (
+                "x-amz-content-sha256".to_string(),
+                hex(&Sha256::digest(&body)),
+            ),
+            (
+                "x-amz-date".to_string(),
+                Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+            ),
+        ];
+        let authorization = sign_request(
+            "PUT",
+            &path,
+            &headers,
+            &target.region,
+            &credentials.access_key,
+            &credentials.secret_key,
+        );
+
+        let request = headers
+            .iter()
+            .filter(|(name, _)| name != "host")
+            .fold(
+                client.put(format!("{}{}", target.endpoint, path)),
+                |request, (name, value)| request.header(name, value),
+            )
+            .header("authorization", authorization)
+            .body(body);
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to upload {}", file))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = response.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to upload {}: HTTP {}\n{}", file, status, message);
+        }
+        println!("  ⬆️  {}", file);
+    }
+
+    if unchanged > 0 {
+        println!("  ⏭️  {} stored assets already uploaded", unchanged);
+    }
+    println!("✅ Deployed to s3://{}/{}", target.bucket, target.prefix);
+    Ok(())
+}
+
+/// Whether an object is in the bucket, with a signed `HEAD` request
+async fn object_exists(
+    client: &reqwest::Client,
+    target: &S3Target,
+    credentials: &S3Credentials,
+    host: &str,
+    path: &str,
+) -> Result<bool> {
+    let headers = vec![
+        ("host".to_string(), host.to_string()),
+        (
+            "x-amz-content-sha256".to_string(),
+            hex(&Sha256::digest(b"")),
+        ),
+        (
+            "x-amz-date".to_string(),
+            Utc::now().format("%Y%m%dT%H%M%SZ").to_string(),
+        ),
+    ];
+    let authorization = sign_request(
+        "HEAD",
+        path,
+        &headers,
+        &target.region,
+        &credentials.access_key,
+        &credentials.secret_key,
+    );
+
+    let response = headers
+        .iter()
+        .filter(|(name, _)| name != "host")
+        .fold(
+            client.head(format!("{}{}", target.endpoint, path)),
+            |request, (name, value)| request.header(name, value),
+        )
+        .header("authorization", authorization)
+        .send()
+        .await
+        .with_context(|| format!("Failed to look up {}", path))?;
+    Ok(response.status().is_success())
+}
+
+/// Print the uploads of a dry run
+fn report_s3_upload(output_dir: &Path, target: &S3Target) -> Result<()> {
+    for file in site_files(output_dir)? {
+        println!(
+            "🔍 {} -> s3://{}/{} ({}, {})",
+            file,
+            target.bucket,
+            target.key(&file),
+            content_type_for(Path::new(&file)),
+            cache_control_for(&file)
+        );
+    }
+    Ok(())
+}
+
+/// Resolve a declared secret, falling back to an environment variable
+fn resolve_secret(
+    secrets: &HashMap<String, SecretSource>,
+    name: &str,
+    env: &str,
+) -> Result<Secret> {
+    let source = secrets.get(name).cloned().unwrap_or_else(|| SecretSource {
+        env: Some(env.to_string()),
+        ..SecretSource::default()
+    });
+    source
+        .resolve()?
+        .map(|(secret, _)| secret)
+        .with_context(|| format!("Secret '{}' is not set (or set {})", name, env))
+}
+
+/// The `Cache-Control` header of an uploaded file
+///
+/// Matches the Netlify `_headers`: hashed and versioned assets are immutable,
+/// pages and the manifest are cached for a day, the service worker never.
+pub fn cache_control_for(file: &str) -> &'static str {
+    const IMMUTABLE_DIRS: [&str; 4] = ["css/", "js/", "img/", "fonts/"];
+
+    if file == "service-worker.js" {
+        "no-cache"
+    } else if is_fingerprinted(file) || IMMUTABLE_DIRS.iter().any(|dir| file.starts_with(dir)) {
+        "public, max-age=31536000, immutable"
+    } else {
+        "public, max-age=86400"
+    }
+}
+
+/// The files of the output directory, relative and with `/` separators
+///
+/// Precompressed `.br` and `.gz` copies are left out; object stores and
+/// GitHub Pages compress on their own.
+fn site_files(dir: &Path) -> Result<Vector<String>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+        for entry in
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else if !matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("br" | "gz")
+            ) {
+                let relative = path.strip_prefix(root)?;
+                files.push(
+                    relative
+                        .components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    files.sort();
+    Ok(files.into_iter().collect())
+}
+
+/// The `Authorization` header of an AWS Signature Version 4 request
+///
+/// `headers` are the signed headers, lowercase and sorted by name, and must
+/// include `host`, `x-amz-date` and `x-amz-content-sha256`.
+fn sign_request(
+    method: &str,
+    path: &str,
+    headers: &[(String, String)],
+    region: &str,
+    access_key: &Secret,
+    secret_key: &Secret,
+) -> String {
+    let header_value = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map_or("", |(_, value)| value.as_str())
+    };
+    let amz_date = header_value("x-amz-date");
+    let date = &amz_date[..8.min(amz_date.len())];
+    let scope = format!("{date}/{region}/s3/aws4_request");
+
+    let canonical_headers = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect::<String>();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        header_value("x-amz-content-sha256")
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signature = hex(&hmac_sha256(
+        &signing_key(secret_key.expose(), date, region, "s3"),
+        string_to_sign.as_bytes(),
+    ));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        access_key.expose()
+    )
+}
+
+/// The SigV4 key for a day, region and service
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    [date, region, service, "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{secret_key}").into_bytes(), |key, part| {
+            hmac_sha256(&key, part.as_bytes()).to_vec()
+        })
+        .try_into()
+        .expect("HMAC-SHA256 output is 32 bytes")
+}
+
+/// HMAC-SHA256 (RFC 2104)
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// URI-encode an object key, keeping the `/` separators
+fn uri_encode_path(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigv4_signing() {
+        // RFC 4231, test case 2
+        assert_eq!(
+            hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        // Example from the AWS Signature Version 4 documentation
+        assert_eq!(
+            hex(&signing_key(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20120215",
+                "us-east-1",
+                "iam"
+            )),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(uri_encode_path("blog/a b+c.html"), "blog/a%20b%2Bc.html");
+    }
+
+    #[test]
+    fn test_cache_control_for() {
+        assert_eq!(
+            cache_control_for("css/main.min.css"),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            cache_control_for("main.0a1b2c3d.css"),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            cache_control_for("assets/3f/2a/3f2a9c1e0b4d5a6f7081923a4b5c6d7e.png"),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(cache_control_for("blog/post.html"), "public, max-age=86400");
+        assert_eq!(cache_control_for("service-worker.js"), "no-cache");
+    }
+}
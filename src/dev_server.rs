@@ -0,0 +1,1764 @@
+//! Development HTTP server with live reload
+//!
+//! Serves the generated `dist/` output locally for `cv serve`. In `--dev` mode
+//! every HTML response gets a small livereload snippet injected, the source
+//! directories are watched for changes, and connected browsers are told to
+//! refresh over a websocket once the rebuild has finished.
+//!
+//! It also accepts testimonial submissions on
+//! [`SUBMISSION_PATH`](crate::testimonials::SUBMISSION_PATH), which are queued
+//! for moderation with `cv testimonials`, drafts on
+//! [`SUBMISSION_INBOX_PATH`](crate::submissions::SUBMISSION_INBOX_PATH), which
+//! are reviewed with `cv submissions`, and serves post reactions on
+//! [`REACTIONS_PATH`](crate::reactions::REACTIONS_PATH) and, behind a token,
+//! the job application tracker on
+//! [`APPLICATIONS_PATH`](crate::applications::APPLICATIONS_PATH) and the
+//! [admin operations](crate::admin) on [`ADMIN_PATH`](crate::admin::ADMIN_PATH).
+//! The admin API only answers the addresses of its
+//! [allowlist](crate::admin::IpAllowlist), and switches a maintenance mode in
+//! which the public APIs answer `503`.
+//! The [OpenAPI document](crate::openapi) of these APIs is served on
+//! [`OPENAPI_PATH`](crate::openapi::OPENAPI_PATH), with a Swagger UI on
+//! [`API_DOCS_PATH`](crate::openapi::API_DOCS_PATH).
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::Local;
+use im::Vector;
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Shutdown, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, warn};
+
+use crate::admin::{self, AdminOperation, IpAllowlist, ADMIN_PATH};
+use crate::applications::{ApplicationUpdate, NewApplication, APPLICATIONS_PATH};
+use crate::db::Database;
+use crate::openapi::{self, API_DOCS_PATH, OPENAPI_PATH};
+use crate::reactions::{self, RateLimiter, ReactionStore, ReactionSubmission, REACTIONS_PATH};
+use crate::request_log::{Redaction, RequestLog};
+use crate::secrets::Secret;
+use crate::security_headers::SecurityHeaders;
+use crate::submissions::{DraftSubmission, SubmissionStore, MAX_DRAFT_SIZE, SUBMISSION_INBOX_PATH};
+use crate::testimonials::{TestimonialStore, TestimonialSubmission, SUBMISSION_PATH};
+
+/// Path the livereload websocket is served on
+pub const LIVE_RELOAD_PATH: &str = "/__livereload";
+
+/// GUID appended to the client key during the websocket handshake (RFC 6455)
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Page in the served directory shown by public APIs in maintenance mode
+pub const MAINTENANCE_PAGE: &str = "maintenance.html";
+
+/// Largest accepted request body, in bytes
+const MAX_BODY_SIZE: usize = 16 * 1024;
+
+/// Longest accepted request line or header line, in bytes
+const MAX_LINE_SIZE: usize = 8 * 1024;
+
+/// Largest accepted request line and headers together, in bytes
+const MAX_HEAD_SIZE: usize = 32 * 1024;
+
+/// How long a client may take to send more of its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most connections handled at once; more are turned away with `503`
+const MAX_CONNECTIONS: usize = 64;
+
+/// Serializes writes to the testimonials file across connection threads
+static TESTIMONIALS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes writes to the submissions file across connection threads
+static SUBMISSIONS_LOCK: Mutex<()> = Mutex::new(());
+
+/// Options for the development server
+#[derive(Debug, Clone)]
+pub struct DevServerOptions {
+    /// Directory to serve (usually `dist/`)
+    pub root: PathBuf,
+    /// Port to listen on
+    pub port: u16,
+    /// Whether to inject the livereload snippet and watch for changes
+    pub live_reload: bool,
+    /// Source paths watched for changes in live reload mode
+    pub watch_paths: Vector<PathBuf>,
+    /// How often the watched paths are polled
+    pub poll_interval: Duration,
+    /// Security headers sent with every response
+    pub security_headers: SecurityHeaders,
+    /// Testimonials file that submissions are queued in, if submissions are accepted
+    pub testimonials: Option<PathBuf>,
+    /// Reactions database, if reactions are served
+    pub reactions: Option<PathBuf>,
+    /// Submissions file that drafts are queued in, if drafts are accepted
+    pub submissions: Option<PathBuf>,
+    /// Bearer token submitters must send, if any
+    pub submissions_token: Option<Secret>,
+    /// CV database holding the applications, if the applications API is served
+    pub applications: Option<PathBuf>,
+    /// Bearer token the applications API requires
+    pub applications_token: Option<Secret>,
+    /// Bearer token the admin API requires, if it is served
+    pub admin_token: Option<Secret>,
+    /// Addresses allowed to reach the admin API; empty allows none
+    pub admin_allowlist: IpAllowlist,
+    /// Reverse proxies whose `X-Forwarded-For` header is trusted
+    pub trusted_proxies: IpAllowlist,
+    /// Query parameters hidden from the request log
+    pub redaction: Redaction,
+    /// Combined Log Format file every request is appended to, if any
+    pub access_log: Option<PathBuf>,
+    /// Flag file that puts the public APIs in maintenance mode, if switchable
+    pub maintenance_flag: Option<PathBuf>,
+}
+
+impl DevServerOptions {
+    /// Create options serving `root` on `port` with the default watch paths
+    pub fn new<P: AsRef<Path>>(root: P, port: u16) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            port,
+            live_reload: false,
+            watch_paths: ["data", "config", "content", "static", "templates"]
+                .iter()
+                .map(PathBuf::from)
+                .collect(),
+            poll_interval: Duration::from_millis(500),
+            security_headers: SecurityHeaders::default().without_hsts(),
+            testimonials: None,
+            reactions: None,
+            submissions: None,
+            submissions_token: None,
+            applications: None,
+            applications_token: None,
+            admin_token: None,
+            admin_allowlist: IpAllowlist::default(),
+            trusted_proxies: IpAllowlist::default(),
+            redaction: Redaction::default(),
+            access_log: None,
+            maintenance_flag: None,
+        }
+    }
+
+    /// Enable or disable live reload
+    pub fn with_live_reload(self, live_reload: bool) -> Self {
+        Self {
+            live_reload,
+            ..self
+        }
+    }
+
+    /// Use the site's security headers (HSTS is dropped, the server is plain HTTP)
+    pub fn with_security_headers(self, security_headers: SecurityHeaders) -> Self {
+        Self {
+            security_headers: security_headers.without_hsts(),
+            ..self
+        }
+    }
+
+    /// Accept testimonial submissions, queueing them in `path`
+    pub fn with_testimonials<P: AsRef<Path>>(self, path: P) -> Self {
+        Self {
+            testimonials: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Accept drafts, queueing them in `path`
+    ///
+    /// With a token, only requests sending it as `Authorization: Bearer` are
+    /// accepted.
+    pub fn with_submissions<P: AsRef<Path>>(self, path: P, token: Option<Secret>) -> Self {
+        Self {
+            submissions: Some(path.as_ref().to_path_buf()),
+            submissions_token: token,
+            ..self
+        }
+    }
+
+    /// Serve the applications in the CV database at `path`
+    ///
+    /// Only requests sending `token` as `Authorization: Bearer` are answered.
+    pub fn with_applications<P: AsRef<Path>>(self, path: P, token: Secret) -> Self {
+        Self {
+            applications: Some(path.as_ref().to_path_buf()),
+            applications_token: Some(token),
+            ..self
+        }
+    }
+
+    /// Serve the admin operations
+    ///
+    /// Only requests sending `token` as `Authorization: Bearer` are answered.
+    pub fn with_admin(self, token: Secret) -> Self {
+        Self {
+            admin_token: Some(token),
+            ..self
+        }
+    }
+
+    /// Log requests with `redaction`, also appending them to `access_log`
+    ///
+    /// Requests are always logged through `tracing`; the access log is in the
+    /// Combined Log Format, for `cv stats ingest`.
+    pub fn with_request_log(self, redaction: Redaction, access_log: Option<PathBuf>) -> Self {
+        Self {
+            redaction,
+            access_log,
+            ..self
+        }
+    }
+
+    /// Take the client's address from `X-Forwarded-For` behind `proxies`
+    ///
+    /// Without trusted proxies, the header is ignored and the client is the
+    /// peer of the connection.
+    pub fn with_trusted_proxies(self, proxies: IpAllowlist) -> Self {
+        Self {
+            trusted_proxies: proxies,
+            ..self
+        }
+    }
+
+    /// Only answer admin requests from the addresses in `allowlist`
+    ///
+    /// Behind a [trusted proxy](Self::with_trusted_proxies), the client's
+    /// address is taken from `X-Forwarded-For`.
+    pub fn with_admin_allowlist(self, allowlist: IpAllowlist) -> Self {
+        Self {
+            admin_allowlist: allowlist,
+            ..self
+        }
+    }
+
+    /// Answer public API requests with `503` while the flag file at `path` exists
+    ///
+    /// The admin API switches the flag, and stays reachable while it is set.
+    pub fn with_maintenance_flag<P: AsRef<Path>>(self, path: P) -> Self {
+        Self {
+            maintenance_flag: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
+    /// Serve post reactions, storing them in the database at `path`
+    pub fn with_reactions<P: AsRef<Path>>(self, path: P) -> Self {
+        Self {
+            reactions: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+}
+
+/// Open websocket connections waiting for reload notifications
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+/// Reactions database and the rate limit of its endpoint, shared by connections
+type Reactions = Arc<Mutex<(ReactionStore, RateLimiter)>>;
+
+/// Serve the output directory until the process is interrupted
+///
+/// # Arguments
+///
+/// * `options` - Server options
+/// * `rebuild` - Called when a watched source changes; connected browsers are
+///   reloaded once it returns successfully
+///
+/// # Returns
+///
+/// A Result indicating success or failure (only returns on bind errors)
+pub fn serve<F>(options: &DevServerOptions, rebuild: F) -> Result<()>
+where
+    F: Fn() -> Result<()> + Send + 'static,
+{
+    let listener = TcpListener::bind(("127.0.0.1", options.port))
+        .with_context(|| format!("Failed to bind dev server to port {}", options.port))?;
+
+    if !options.root.exists() {
+        info!(
+            "{} does not exist yet - run `cv` first to build the site",
+            options.root.display()
+        );
+    }
+
+    println!(
+        "🌐 Serving {} at http://localhost:{}/",
+        options.root.display(),
+        options.port
+    );
+
+    if options.live_reload {
+        println!("🔁 Live reload enabled, watching for changes...");
+    }
+
+    serve_listener(listener, options, rebuild, &AtomicBool::new(false))
+}
+
+/// Serve the output directory on a bound listener until `stop` is set
+///
+/// The flag is checked after every connection, so whoever sets it has to
+/// connect once more to end the loop.
+///
+/// # Arguments
+///
+/// * `listener` - Listener to accept connections on
+/// * `options` - Server options; `port` should be the listener's port
+/// * `rebuild` - Called when a watched source changes in live reload mode
+/// * `stop` - Set to stop accepting connections
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn serve_listener<F>(
+    listener: TcpListener,
+    options: &DevServerOptions,
+    rebuild: F,
+    stop: &AtomicBool,
+) -> Result<()>
+where
+    F: Fn() -> Result<()> + Send + 'static,
+{
+    let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+    let reactions: Option<Reactions> = options
+        .reactions
+        .as_ref()
+        .map(ReactionStore::open)
+        .transpose()?
+        .map(|store| Arc::new(Mutex::new((store, RateLimiter::default()))));
+
+    if options.live_reload {
+        spawn_watcher(options.clone(), Arc::clone(&clients), rebuild);
+    }
+
+    let connections = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        match stream {
+            Ok(mut stream) => {
+                // Each connection has a thread, so their number is capped
+                if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                    debug!("Turning a connection away, {} are open", MAX_CONNECTIONS);
+                    let _ = write_response(
+                        &mut stream,
+                        "503 Service Unavailable",
+                        "text/plain; charset=utf-8",
+                        &options.security_headers,
+                        b"503 Service Unavailable",
+                    );
+                    continue;
+                }
+                let options = options.clone();
+                let clients = Arc::clone(&clients);
+                let reactions = reactions.clone();
+                let connections = Arc::clone(&connections);
+                thread::spawn(move || {
+                    if let Err(e) = handle_connection(stream, &options, &clients, reactions) {
+                        debug!("Dev server connection error: {}", e);
+                    }
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+            Err(e) => warn!("Failed to accept connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Poll the watched paths and rebuild + notify clients on change
+fn spawn_watcher<F>(options: DevServerOptions, clients: Clients, rebuild: F)
+where
+    F: Fn() -> Result<()> + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut last_seen = latest_modification(&options.watch_paths);
+
+        loop {
+            thread::sleep(options.poll_interval);
+
+            let current = latest_modification(&options.watch_paths);
+            if current <= last_seen {
+                continue;
+            }
+
+            println!("🔨 Change detected, rebuilding...");
+            match rebuild() {
+                Ok(()) => {
+                    let notified = notify_reload(&clients);
+                    println!("✅ Rebuild finished, reloaded {} browser(s)", notified);
+                }
+                Err(e) => warn!("Rebuild failed: {}", e),
+            }
+
+            // Pick up anything written during the rebuild so it doesn't retrigger
+            last_seen = latest_modification(&options.watch_paths).max(current);
+        }
+    });
+}
+
+/// Send a reload message to every connected client, dropping closed ones
+///
+/// # Returns
+///
+/// The number of clients that were notified
+fn notify_reload(clients: &Clients) -> usize {
+    let frame = websocket_text_frame("reload");
+    let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+    clients.retain_mut(|stream| stream.write_all(&frame).is_ok());
+    clients.len()
+}
+
+/// The request line and headers of a request
+struct Request {
+    /// Request method
+    method: String,
+    /// Request target, with its query string
+    target: String,
+    /// Protocol of the request line
+    protocol: String,
+    /// Request headers
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    /// The value of a header, matched case-insensitively
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Status and body length of a written response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sent {
+    /// Status code
+    status: u16,
+    /// Length of the body, in bytes
+    bytes: usize,
+}
+
+/// Handle a single HTTP request, logging it once it's answered
+fn handle_connection(
+    stream: TcpStream,
+    options: &DevServerOptions,
+    clients: &Clients,
+    reactions: Option<Reactions>,
+) -> Result<()> {
+    let started = Instant::now();
+    let time = Local::now().fixed_offset();
+    // A client that stops sending doesn't hold its thread for long
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut budget = MAX_HEAD_SIZE;
+    let head = read_head_line(&mut reader, &mut budget)
+        .and_then(|request_line| Ok((request_line, read_headers(&mut reader, &mut budget)?)));
+    let (request_line, headers) = match head {
+        Ok(head) => head,
+        Err(e) if e.is::<HeadTooLarge>() => {
+            let mut stream = stream;
+            write_response(
+                &mut stream,
+                "431 Request Header Fields Too Large",
+                "text/plain; charset=utf-8",
+                &options.security_headers,
+                b"431 Request Header Fields Too Large",
+            )?;
+            // Closing with the rest unread would reset the connection before
+            // the client has read the answer; a client that never finishes
+            // sending just runs into the read timeout
+            stream.shutdown(Shutdown::Write)?;
+            let _ = std::io::copy(&mut reader.take(MAX_HEAD_SIZE as u64), &mut std::io::sink());
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let mut parts = request_line.split_whitespace();
+    let request = Request {
+        method: parts.next().unwrap_or("GET").to_string(),
+        target: parts.next().unwrap_or("/").to_string(),
+        protocol: parts.next().unwrap_or("HTTP/1.1").to_string(),
+        headers,
+    };
+    let address = client_address(&stream, &request.headers, &options.trusted_proxies);
+
+    let sent = dispatch(stream, reader, &request, options, clients, reactions)?;
+    let log = RequestLog {
+        address,
+        time,
+        method: request.method.clone(),
+        target: options.redaction.target(&request.target),
+        protocol: request.protocol.clone(),
+        status: sent.status,
+        bytes: sent.bytes,
+        latency: started.elapsed(),
+        referrer: request
+            .header("referer")
+            .map(|referrer| options.redaction.target(referrer)),
+        user_agent: request.header("user-agent").map(str::to_string),
+    };
+    info!(
+        "{} {} {} {} ms",
+        log.method,
+        log.target,
+        log.status,
+        log.latency.as_millis()
+    );
+    if let Some(ref path) = options.access_log {
+        if let Err(e) = log.append(path) {
+            warn!("{:#}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Route a request to the API or file that answers it
+///
+/// # Returns
+///
+/// The status and body length of the response
+fn dispatch(
+    mut stream: TcpStream,
+    mut reader: BufReader<TcpStream>,
+    request: &Request,
+    options: &DevServerOptions,
+    clients: &Clients,
+    reactions: Option<Reactions>,
+) -> Result<Sent> {
+    let live_reload = options.live_reload;
+    let method = request.method.as_str();
+    let target = request.target.as_str();
+    let headers = &request.headers;
+
+    let request_path = target.split(['?', '#']).next().unwrap_or("/");
+    let public_api = [
+        REACTIONS_PATH,
+        APPLICATIONS_PATH,
+        SUBMISSION_INBOX_PATH,
+        SUBMISSION_PATH,
+    ]
+    .iter()
+    .any(|prefix| request_path == *prefix || request_path.starts_with(&format!("{prefix}/")));
+    if public_api
+        && options
+            .maintenance_flag
+            .as_deref()
+            .is_some_and(admin::maintenance_enabled)
+    {
+        let (content_type, body) = maintenance_response(&options.root, headers);
+        return write_response(
+            &mut stream,
+            "503 Service Unavailable",
+            content_type,
+            &options.security_headers,
+            body.as_bytes(),
+        );
+    }
+
+    if let Some(reactions) = reactions {
+        if let Some(slug) = request_path
+            .strip_prefix(REACTIONS_PATH)
+            .and_then(|rest| rest.strip_prefix('/'))
+        {
+            let address = client_address(&stream, headers, &options.trusted_proxies);
+            let (status, body) = match handle_reaction(
+                method,
+                slug,
+                &mut reader,
+                headers,
+                &address,
+                &options.root,
+                &reactions,
+            ) {
+                Ok(response) => response,
+                Err(e) => (
+                    "400 Bad Request",
+                    serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                ),
+            };
+            return write_response(
+                &mut stream,
+                status,
+                "application/json",
+                &options.security_headers,
+                body.as_bytes(),
+            );
+        }
+    }
+
+    if let Some(ref path) = options.applications {
+        if let Some(rest) = request_path.strip_prefix(APPLICATIONS_PATH) {
+            // A missing token never authorizes, the tracker is private
+            let (status, body) = if options.applications_token.is_none()
+                || !is_authorized(headers, options.applications_token.as_ref())
+            {
+                (
+                    "401 Unauthorized",
+                    r#"{"error":"Missing or invalid token"}"#.to_string(),
+                )
+            } else {
+                match handle_application(method, rest, &mut reader, headers, path) {
+                    Ok(response) => response,
+                    Err(e) => (
+                        "400 Bad Request",
+                        serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                    ),
+                }
+            };
+            return write_response(
+                &mut stream,
+                status,
+                "application/json",
+                &options.security_headers,
+                body.as_bytes(),
+            );
+        }
+    }
+
+    if let Some(ref token) = options.admin_token {
+        if let Some(rest) = request_path.strip_prefix(ADMIN_PATH) {
+            let allowed = client_address(&stream, headers, &options.trusted_proxies)
+                .parse::<IpAddr>()
+                .is_ok_and(|address| options.admin_allowlist.allows(address));
+            let (status, body) = if !allowed {
+                (
+                    "403 Forbidden",
+                    r#"{"error":"Address not allowed"}"#.to_string(),
+                )
+            } else if !is_authorized(headers, Some(token)) {
+                (
+                    "401 Unauthorized",
+                    r#"{"error":"Missing or invalid token"}"#.to_string(),
+                )
+            } else if rest == MAINTENANCE_ROUTE {
+                match handle_maintenance(
+                    method,
+                    &mut reader,
+                    headers,
+                    options.maintenance_flag.as_deref(),
+                ) {
+                    Ok(response) => response,
+                    Err(e) => (
+                        "400 Bad Request",
+                        serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                    ),
+                }
+            } else {
+                handle_admin(method, rest)
+            };
+            return write_response(
+                &mut stream,
+                status,
+                "application/json",
+                &options.security_headers,
+                body.as_bytes(),
+            );
+        }
+    }
+
+    if let Some(ref path) = options.submissions {
+        if method == "POST" && request_path == SUBMISSION_INBOX_PATH {
+            let (status, body) = if !is_authorized(headers, options.submissions_token.as_ref()) {
+                (
+                    "401 Unauthorized",
+                    r#"{"error":"Missing or invalid token"}"#.to_string(),
+                )
+            } else {
+                match accept_draft(&mut reader, headers, path) {
+                    Ok(()) => ("202 Accepted", r#"{"status":"pending"}"#.to_string()),
+                    Err(e) => (
+                        "400 Bad Request",
+                        serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                    ),
+                }
+            };
+            return write_response(
+                &mut stream,
+                status,
+                "application/json",
+                &options.security_headers,
+                body.as_bytes(),
+            );
+        }
+    }
+
+    if let Some(ref path) = options.testimonials {
+        if method == "POST" && request_path == SUBMISSION_PATH {
+            let (status, body) = match accept_testimonial(&mut reader, headers, path) {
+                Ok(()) => ("202 Accepted", r#"{"status":"pending"}"#.to_string()),
+                Err(e) => (
+                    "400 Bad Request",
+                    serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                ),
+            };
+            return write_response(
+                &mut stream,
+                status,
+                "application/json",
+                &options.security_headers,
+                body.as_bytes(),
+            );
+        }
+    }
+
+    if method == "GET" && request_path == OPENAPI_PATH {
+        return write_response(
+            &mut stream,
+            "200 OK",
+            "application/json",
+            &options.security_headers,
+            openapi::openapi_spec().to_string().as_bytes(),
+        );
+    }
+
+    if method == "GET" && request_path == API_DOCS_PATH {
+        // Swagger UI comes from a CDN the site's own policy doesn't allow
+        let security_headers = SecurityHeaders {
+            content_security_policy: Some(openapi::swagger_ui_policy(OPENAPI_PATH)),
+            ..options.security_headers.clone()
+        };
+        return write_response(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            &security_headers,
+            openapi::swagger_ui_html(OPENAPI_PATH).as_bytes(),
+        );
+    }
+
+    if live_reload && request_path == LIVE_RELOAD_PATH {
+        let key = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("sec-websocket-key"))
+            .map(|(_, value)| value.as_str())
+            .context("Missing Sec-WebSocket-Key header")?;
+
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+            websocket_accept_key(key)
+        )?;
+
+        clients
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(stream);
+        return Ok(Sent {
+            status: 101,
+            bytes: 0,
+        });
+    }
+
+    match resolve_request_path(&options.root, target) {
+        Some(path) => {
+            let body = fs::read(&path)?;
+            let content_type = content_type_for(&path);
+            let body = if live_reload && content_type.starts_with("text/html") {
+                inject_live_reload(&String::from_utf8_lossy(&body), options.port).into_bytes()
+            } else {
+                body
+            };
+            write_response(
+                &mut stream,
+                "200 OK",
+                content_type,
+                &options.security_headers,
+                &body,
+            )
+        }
+        None => write_response(
+            &mut stream,
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            &options.security_headers,
+            b"404 Not Found",
+        ),
+    }
+}
+
+/// Answer a request for the reactions to a post
+///
+/// `GET` returns the counts, `POST` adds the reaction in the body and returns
+/// the new counts. Only posts in the served root can be reacted to.
+///
+/// # Arguments
+///
+/// * `method` - Request method
+/// * `slug` - Slug of the post
+/// * `reader` - The request, positioned after the headers
+/// * `headers` - Request headers, for the body length
+/// * `address` - Address of the visitor
+/// * `root` - Served directory
+/// * `reactions` - Reactions database and rate limit
+///
+/// # Returns
+///
+/// The response status and JSON body, or an error for a malformed request
+fn handle_reaction<R: BufRead>(
+    method: &str,
+    slug: &str,
+    reader: &mut R,
+    headers: &[(String, String)],
+    address: &str,
+    root: &Path,
+    reactions: &Reactions,
+) -> Result<(&'static str, String)> {
+    let mut reactions = reactions.lock().unwrap_or_else(|e| e.into_inner());
+    let (store, limiter) = &mut *reactions;
+
+    if !limiter.allow(address, Instant::now()) {
+        return Ok((
+            "429 Too Many Requests",
+            r#"{"error":"Too many requests"}"#.to_string(),
+        ));
+    }
+    if !reactions::is_valid_slug(slug)
+        || resolve_request_path(root, &format!("/blog/{}", slug)).is_none()
+    {
+        return Ok(("404 Not Found", r#"{"error":"No such post"}"#.to_string()));
+    }
+
+    match method {
+        "GET" => {}
+        "POST" => {
+            let submission: ReactionSubmission =
+                serde_json::from_slice(&read_body(reader, headers, MAX_BODY_SIZE)?)
+                    .context("Invalid reaction JSON")?;
+            store.react(slug, submission.reaction, address)?;
+        }
+        _ => {
+            return Ok((
+                "405 Method Not Allowed",
+                r#"{"error":"Method not allowed"}"#.to_string(),
+            ))
+        }
+    }
+    Ok(("200 OK", serde_json::to_string(&store.counts(slug)?)?))
+}
+
+/// Address of the client, taken from `X-Forwarded-For` behind a trusted proxy
+///
+/// # Arguments
+///
+/// * `stream` - The connection, whose peer is the client or a proxy
+/// * `headers` - Request headers
+/// * `proxies` - Reverse proxies whose `X-Forwarded-For` is trusted
+///
+/// # Returns
+///
+/// The client's address, or `unknown` if the peer's isn't known
+fn client_address(
+    stream: &TcpStream,
+    headers: &[(String, String)],
+    proxies: &IpAllowlist,
+) -> String {
+    match stream.peer_addr() {
+        Ok(peer) => forwarded_client(peer.ip(), headers, proxies).to_string(),
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+/// The client behind a chain of trusted proxies
+///
+/// Only a trusted proxy's `X-Forwarded-For` is believed, and each proxy
+/// appends the address it was connected from, so the hops are walked from the
+/// right. The first hop that isn't a trusted proxy is the client; anything
+/// left of it was sent by the client and may be made up.
+///
+/// # Arguments
+///
+/// * `peer` - Address the connection came from
+/// * `headers` - Request headers
+/// * `proxies` - Reverse proxies whose `X-Forwarded-For` is trusted
+///
+/// # Returns
+///
+/// The client's address
+fn forwarded_client(peer: IpAddr, headers: &[(String, String)], proxies: &IpAllowlist) -> IpAddr {
+    let is_proxy = |address: IpAddr| proxies.allows(address);
+    if !is_proxy(peer) {
+        return peer;
+    }
+    let hops = headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("x-forwarded-for"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .collect::<Vec<_>>();
+
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        match hop {
+            Some(hop) if is_proxy(client) => client = hop,
+            // A hop that isn't an address can't be walked past
+            _ => break,
+        }
+    }
+    client
+}
+
+/// Admin route that reads and switches maintenance mode
+const MAINTENANCE_ROUTE: &str = "/maintenance";
+
+/// Answer a request to switch maintenance mode
+///
+/// - `GET /api/admin/maintenance`: Whether maintenance mode is on
+/// - `PUT /api/admin/maintenance`: Switch it with `{"enabled": true}`
+///
+/// # Arguments
+///
+/// * `method` - Request method
+/// * `reader` - The request, positioned after the headers
+/// * `headers` - Request headers, for the body length
+/// * `flag` - The maintenance flag file, if maintenance mode can be switched
+///
+/// # Returns
+///
+/// The response status and JSON body
+fn handle_maintenance<R: BufRead>(
+    method: &str,
+    reader: &mut R,
+    headers: &[(String, String)],
+    flag: Option<&Path>,
+) -> Result<(&'static str, String)> {
+    #[derive(serde::Deserialize)]
+    struct Switch {
+        enabled: bool,
+    }
+
+    let Some(flag) = flag else {
+        return Ok((
+            "404 Not Found",
+            r#"{"error":"Maintenance mode is not available"}"#.to_string(),
+        ));
+    };
+    match method {
+        "GET" => {}
+        "PUT" => {
+            let body = read_body(reader, headers, MAX_BODY_SIZE)?;
+            let switch: Switch =
+                serde_json::from_slice(&body).context("Invalid maintenance JSON")?;
+            admin::set_maintenance(flag, switch.enabled)?;
+            info!(
+                "Maintenance mode {}",
+                if switch.enabled { "on" } else { "off" }
+            );
+        }
+        _ => {
+            return Ok((
+                "405 Method Not Allowed",
+                r#"{"error":"Use GET or PUT /api/admin/maintenance"}"#.to_string(),
+            ))
+        }
+    }
+    Ok((
+        "200 OK",
+        serde_json::json!({ "enabled": admin::maintenance_enabled(flag) }).to_string(),
+    ))
+}
+
+/// The answer of a public API in maintenance mode
+///
+/// Browsers get the site's `maintenance.html` if it has one, or a plain page,
+/// and API clients a JSON error.
+///
+/// # Arguments
+///
+/// * `root` - The served directory
+/// * `headers` - Request headers, for `Accept`
+///
+/// # Returns
+///
+/// The content type and body
+fn maintenance_response(root: &Path, headers: &[(String, String)]) -> (&'static str, String) {
+    let wants_html = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("accept"))
+        .is_some_and(|(_, value)| value.contains("text/html"));
+    if !wants_html {
+        return (
+            "application/json",
+            r#"{"error":"Down for maintenance, please try again later"}"#.to_string(),
+        );
+    }
+    let page = fs::read_to_string(root.join(MAINTENANCE_PAGE)).unwrap_or_else(|_| {
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Down for maintenance</title>
+<link rel="stylesheet" href="/css/main.css">
+</head>
+<body>
+<main class="page-container">
+<section class="page-section">
+<h1 class="page-section-title">Down for maintenance</h1>
+<p>This part of the site is being worked on. Please try again in a few minutes.</p>
+<p><a href="/">Back to the home page</a></p>
+</section>
+</main>
+</body>
+</html>
+"#
+        .to_string()
+    });
+    ("text/html; charset=utf-8", page)
+}
+
+/// Answer a request to the admin API
+///
+/// - `GET /api/admin`: The names of the operations
+/// - `POST /api/admin/<operation>`: Run the operation and return its report
+///
+/// # Arguments
+///
+/// * `method` - Request method
+/// * `rest` - Request path after [`ADMIN_PATH`]
+///
+/// # Returns
+///
+/// The response status and JSON body
+fn handle_admin(method: &str, rest: &str) -> (&'static str, String) {
+    let operation = rest.strip_prefix('/').filter(|name| !name.is_empty());
+    match (method, operation) {
+        ("GET", None) => (
+            "200 OK",
+            serde_json::json!(AdminOperation::ALL.map(|operation| operation.as_str())).to_string(),
+        ),
+        ("POST", Some(name)) => {
+            let operation = match name.parse::<AdminOperation>() {
+                Ok(operation) => operation,
+                Err(e) => {
+                    return (
+                        "404 Not Found",
+                        serde_json::json!({ "error": e.to_string() }).to_string(),
+                    )
+                }
+            };
+            let report = admin::run(operation, &mut |step| {
+                info!("{}: {} ({} ms)", operation, step.name, step.millis)
+            });
+            match report.and_then(|report| Ok(serde_json::to_string(&report)?)) {
+                Ok(body) => ("200 OK", body),
+                Err(e) => (
+                    "500 Internal Server Error",
+                    serde_json::json!({ "error": format!("{e:#}") }).to_string(),
+                ),
+            }
+        }
+        _ => (
+            "405 Method Not Allowed",
+            r#"{"error":"Use GET /api/admin or POST /api/admin/<operation>"}"#.to_string(),
+        ),
+    }
+}
+
+/// Answer a request to the applications API
+///
+/// - `GET /api/applications`: All applications
+/// - `POST /api/applications`: Track the application in the body
+/// - `GET /api/applications/<id>`: One application
+/// - `PATCH /api/applications/<id>`: Change its status, follow-up or notes
+///
+/// # Arguments
+///
+/// * `method` - Request method
+/// * `rest` - Request path after [`APPLICATIONS_PATH`]
+/// * `reader` - The request, positioned after the headers
+/// * `headers` - Request headers, for the body length
+/// * `path` - CV database holding the applications
+///
+/// # Returns
+///
+/// The response status and JSON body, or an error for a malformed request
+fn handle_application<R: BufRead>(
+    method: &str,
+    rest: &str,
+    reader: &mut R,
+    headers: &[(String, String)],
+    path: &Path,
+) -> Result<(&'static str, String)> {
+    let not_found = ("404 Not Found", r#"{"error":"Not found"}"#.to_string());
+    let id = match rest.trim_matches('/') {
+        "" => None,
+        id => match id.parse::<i64>() {
+            Ok(id) => Some(id),
+            Err(_) => return Ok(not_found),
+        },
+    };
+    let db = Database::open(path)?;
+
+    match (method, id) {
+        ("GET", None) => Ok(("200 OK", serde_json::to_string(&db.applications()?)?)),
+        ("POST", None) => {
+            let body = read_body(reader, headers, MAX_BODY_SIZE)?;
+            let application: NewApplication =
+                serde_json::from_slice(&body).context("Invalid application JSON")?;
+            let id = db.add_application(&application)?;
+            Ok(("201 Created", serde_json::json!({ "id": id }).to_string()))
+        }
+        ("GET", Some(id)) => Ok(match db.application(id)? {
+            Some(application) => ("200 OK", serde_json::to_string(&application)?),
+            None => not_found,
+        }),
+        ("PATCH", Some(id)) => {
+            if db.application(id)?.is_none() {
+                return Ok(not_found);
+            }
+            let body = read_body(reader, headers, MAX_BODY_SIZE)?;
+            let update: ApplicationUpdate =
+                serde_json::from_slice(&body).context("Invalid application JSON")?;
+            let application = db.update_application(id, &update)?;
+            Ok(("200 OK", serde_json::to_string(&application)?))
+        }
+        _ => Ok((
+            "405 Method Not Allowed",
+            r#"{"error":"Method not allowed"}"#.to_string(),
+        )),
+    }
+}
+
+/// Read a testimonial submission from the request body and queue it
+///
+/// # Arguments
+///
+/// * `reader` - The request, positioned after the headers
+/// * `headers` - Request headers, for the body length
+/// * `path` - Testimonials file to add the submission to
+///
+/// # Returns
+///
+/// A Result indicating whether the submission was accepted
+fn accept_testimonial<R: BufRead>(
+    reader: &mut R,
+    headers: &[(String, String)],
+    path: &Path,
+) -> Result<()> {
+    let body = read_body(reader, headers, MAX_BODY_SIZE)?;
+    let submission: TestimonialSubmission =
+        serde_json::from_slice(&body).context("Invalid testimonial JSON")?;
+    let testimonial = submission.into_testimonial()?;
+
+    let _guard = TESTIMONIALS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = TestimonialStore::load(path)?;
+    store.submit(testimonial);
+    store.save(path)?;
+    println!("💬 New testimonial waiting for moderation (cv testimonials)");
+    Ok(())
+}
+
+/// Read a draft from the request body and queue it for review
+///
+/// # Arguments
+///
+/// * `reader` - The request, positioned after the headers
+/// * `headers` - Request headers, for the body length
+/// * `path` - Submissions file to add the draft to
+///
+/// # Returns
+///
+/// A Result indicating whether the draft was accepted
+fn accept_draft<R: BufRead>(
+    reader: &mut R,
+    headers: &[(String, String)],
+    path: &Path,
+) -> Result<()> {
+    let body = read_body(reader, headers, MAX_DRAFT_SIZE)?;
+    let submission: DraftSubmission =
+        serde_json::from_slice(&body).context("Invalid draft JSON")?;
+    let draft = submission.into_draft()?;
+
+    let _guard = SUBMISSIONS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut store = SubmissionStore::load(path)?;
+    store.submit(draft);
+    store.save(path)?;
+    println!("📝 New draft waiting for review (cv submissions)");
+    Ok(())
+}
+
+/// Whether the request carries the expected bearer token, if one is required
+fn is_authorized(headers: &[(String, String)], token: Option<&Secret>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let sent = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.strip_prefix("Bearer "))
+        .unwrap_or("");
+    // Compare digests so the time taken doesn't reveal a matching prefix
+    Sha1::digest(sent.trim().as_bytes()) == Sha1::digest(token.expose().as_bytes())
+}
+
+/// Read the request body, up to `limit` bytes
+fn read_body<R: BufRead>(
+    reader: &mut R,
+    headers: &[(String, String)],
+    limit: usize,
+) -> Result<Vec<u8>> {
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .context("Missing Content-Length header")?;
+    if length > limit {
+        anyhow::bail!("Request body too large");
+    }
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+/// The request line and headers were longer than the server accepts
+#[derive(Debug)]
+struct HeadTooLarge;
+
+impl std::fmt::Display for HeadTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Request line or headers too large")
+    }
+}
+
+impl std::error::Error for HeadTooLarge {}
+
+/// Read one line of the request line and headers
+///
+/// # Arguments
+///
+/// * `reader` - The request
+/// * `budget` - Bytes the rest of the request line and headers may take,
+///   reduced by the line's length
+///
+/// # Returns
+///
+/// The line, or [`HeadTooLarge`] if it's longer than [`MAX_LINE_SIZE`] or the
+/// budget
+fn read_head_line<R: BufRead>(reader: &mut R, budget: &mut usize) -> Result<String> {
+    let limit = MAX_LINE_SIZE.min(*budget);
+    let mut line = Vec::new();
+    let read = reader
+        .by_ref()
+        .take(limit as u64)
+        .read_until(b'\n', &mut line)?;
+    if read == limit && !line.ends_with(b"\n") {
+        return Err(HeadTooLarge.into());
+    }
+    *budget -= read;
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Read request headers up to the blank line
+///
+/// # Arguments
+///
+/// * `reader` - The request, positioned after the request line
+/// * `budget` - Bytes the headers may take
+///
+/// # Returns
+///
+/// The headers, or [`HeadTooLarge`] if they take more than the budget
+fn read_headers<R: BufRead>(reader: &mut R, budget: &mut usize) -> Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    loop {
+        let line = read_head_line(reader, budget)?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(headers)
+}
+
+/// Write a complete HTTP response with caching disabled
+///
+/// # Returns
+///
+/// The status code and body length, for the request log
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    security_headers: &SecurityHeaders,
+    body: &[u8],
+) -> Result<Sent> {
+    let security = security_headers
+        .headers()
+        .iter()
+        .map(|(name, value)| format!("{}: {}\r\n", name, value))
+        .collect::<String>();
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-cache, no-store, must-revalidate\r\n{}Connection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len(),
+        security
+    )?;
+    stream.write_all(body)?;
+    Ok(Sent {
+        status: status
+            .split_whitespace()
+            .next()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or_default(),
+        bytes: body.len(),
+    })
+}
+
+/// Map a request target to a file inside the served root
+///
+/// Query strings and fragments are ignored, directories resolve to their
+/// `index.html`, and extensionless paths fall back to `<path>.html`. Targets
+/// containing `..` are rejected.
+///
+/// # Returns
+///
+/// The file path, or None if the file doesn't exist or is outside the root
+pub fn resolve_request_path(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next().unwrap_or("/");
+    let relative = Path::new(path.trim_start_matches('/'));
+
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return None;
+    }
+
+    let candidate = root.join(relative);
+    if candidate.is_dir() {
+        Some(candidate.join("index.html")).filter(|p| p.is_file())
+    } else if candidate.is_file() {
+        Some(candidate)
+    } else if candidate.extension().is_none() {
+        Some(candidate.with_extension("html")).filter(|p| p.is_file())
+    } else {
+        None
+    }
+}
+
+/// Guess the Content-Type header from a file extension
+pub fn content_type_for(path: &Path) -> &'static str {
+    match path.extension().and_then(|e| e.to_str()).unwrap_or("") {
+        "html" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "application/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "ics" => "text/calendar; charset=utf-8",
+        "vcf" => "text/vcard; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "txt" => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Insert the livereload client script before `</body>`
+///
+/// If the document has no closing body tag the snippet is appended.
+pub fn inject_live_reload(html: &str, port: u16) -> String {
+    let snippet = format!(
+        r#"<script>(function(){{var ws=new WebSocket("ws://localhost:{port}{LIVE_RELOAD_PATH}");ws.onmessage=function(e){{if(e.data==="reload"){{location.reload();}}}};}})();</script>"#
+    );
+
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}{}", &html[..pos], snippet, &html[pos..]),
+        None => format!("{}{}", html, snippet),
+    }
+}
+
+/// Compute the Sec-WebSocket-Accept value for a client key
+pub fn websocket_accept_key(key: &str) -> String {
+    let digest = Sha1::new()
+        .chain_update(key.trim().as_bytes())
+        .chain_update(WEBSOCKET_GUID.as_bytes())
+        .finalize();
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+/// Build an unmasked websocket text frame (server to client)
+fn websocket_text_frame(text: &str) -> Vec<u8> {
+    let payload = text.as_bytes();
+    let mut frame = vec![0x81];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Find the most recent modification time below the given paths
+pub fn latest_modification(paths: &Vector<PathBuf>) -> Option<SystemTime> {
+    paths.iter().filter_map(|p| latest_in(p)).max()
+}
+
+fn latest_in(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    if metadata.is_dir() {
+        fs::read_dir(path)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| latest_in(&entry.path()))
+            .chain(metadata.modified().ok())
+            .max()
+    } else {
+        metadata.modified().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_websocket_accept_key() {
+        // Example from RFC 6455, section 1.3
+        assert_eq!(
+            websocket_accept_key("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn test_inject_live_reload() {
+        let html = "<html><body><p>Hi</p></body></html>";
+        let injected = inject_live_reload(html, 3000);
+        assert!(injected.contains("ws://localhost:3000/__livereload"));
+        assert!(injected.ends_with("</script></body></html>"));
+
+        let fragment = inject_live_reload("<p>No body</p>", 3000);
+        assert!(fragment.starts_with("<p>No body</p><script>"));
+    }
+
+    #[test]
+    fn test_accept_testimonial() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("testimonials.json");
+        let body = r#"{"quote": "Great colleague", "author": "Grace"}"#;
+        let headers = vec![("Content-Length".to_string(), body.len().to_string())];
+
+        accept_testimonial(&mut body.as_bytes(), &headers, &path).unwrap();
+        let missing_author = r#"{"quote": "Great colleague", "author": ""}"#;
+        let headers = vec![(
+            "Content-Length".to_string(),
+            missing_author.len().to_string(),
+        )];
+        assert!(accept_testimonial(&mut missing_author.as_bytes(), &headers, &path).is_err());
+
+        let store = TestimonialStore::load(&path).unwrap();
+        assert_eq!(store.testimonials.len(), 1);
+        assert!(store.approved().is_empty());
+    }
+
+    #[test]
+    fn test_handle_reaction() {
+        let dir = tempdir().unwrap();
+        fs::create_dir(dir.path().join("blog")).unwrap();
+        fs::write(dir.path().join("blog/hello.html"), "post").unwrap();
+        let store = ReactionStore::open(dir.path().join("reactions.db")).unwrap();
+        let reactions: Reactions = Arc::new(Mutex::new((store, RateLimiter::default())));
+
+        let body = r#"{"reaction": "useful"}"#;
+        let headers = vec![("Content-Length".to_string(), body.len().to_string())];
+        let react = |method: &str, slug: &str| {
+            handle_reaction(
+                method,
+                slug,
+                &mut body.as_bytes(),
+                &headers,
+                "192.0.2.1",
+                dir.path(),
+                &reactions,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(react("POST", "hello").0, "200 OK");
+        let (status, counts) = react("POST", "hello");
+        assert_eq!(status, "200 OK");
+        assert_eq!(counts, r#"{"like":0,"useful":1,"insightful":0}"#);
+        assert_eq!(react("GET", "missing").0, "404 Not Found");
+        assert_eq!(react("DELETE", "hello").0, "405 Method Not Allowed");
+    }
+
+    #[test]
+    fn test_accept_draft() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("submissions.json");
+        let body = r#"{"title": "Guest post", "body": "Hello", "author": "Ada"}"#;
+        let headers = vec![("Content-Length".to_string(), body.len().to_string())];
+        accept_draft(&mut body.as_bytes(), &headers, &path).unwrap();
+
+        let store = SubmissionStore::load(&path).unwrap();
+        assert_eq!(store.drafts.len(), 1);
+        assert_eq!(store.drafts[0].slug, "guest-post");
+
+        let token = Secret::new("s3cret");
+        let bearer = |value: &str| vec![("Authorization".to_string(), value.to_string())];
+        assert!(is_authorized(&[], None));
+        assert!(!is_authorized(&[], Some(&token)));
+        assert!(!is_authorized(&bearer("Bearer nope"), Some(&token)));
+        assert!(is_authorized(&bearer("Bearer s3cret"), Some(&token)));
+    }
+
+    #[test]
+    fn test_submission_routes_ignore_query() {
+        let dir = tempdir().unwrap();
+        let options = DevServerOptions::new(dir.path(), 0)
+            .with_testimonials(dir.path().join("testimonials.json"))
+            .with_submissions(dir.path().join("submissions.json"), None);
+        let post = |target: &str, body: &str| {
+            respond(
+                &options,
+                &format!(
+                    "POST {target} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{body}",
+                    body.len()
+                ),
+            )
+        };
+
+        let testimonial = post(
+            &format!("{SUBMISSION_PATH}?source=footer"),
+            r#"{"quote": "Great colleague", "author": "Grace"}"#,
+        );
+        assert!(
+            testimonial.starts_with("HTTP/1.1 202 Accepted"),
+            "{testimonial}"
+        );
+        let draft = post(
+            &format!("{SUBMISSION_INBOX_PATH}?x=1"),
+            r#"{"title": "Guest post", "body": "Hello", "author": "Ada"}"#,
+        );
+        assert!(draft.starts_with("HTTP/1.1 202 Accepted"), "{draft}");
+
+        let store = SubmissionStore::load(dir.path().join("submissions.json")).unwrap();
+        assert_eq!(store.drafts.len(), 1);
+    }
+
+    #[test]
+    fn test_handle_application() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("cv.db");
+        let request = |method: &str, rest: &str, body: &str| {
+            let headers = vec![("Content-Length".to_string(), body.len().to_string())];
+            handle_application(method, rest, &mut body.as_bytes(), &headers, &path).unwrap()
+        };
+
+        let (status, body) = request("POST", "", r#"{"company": "Acme", "role": "Engineer"}"#);
+        assert_eq!((status, body.as_str()), ("201 Created", r#"{"id":1}"#));
+        let (status, body) = request("PATCH", "/1", r#"{"status": "offer"}"#);
+        assert_eq!(status, "200 OK");
+        assert!(body.contains(r#""status":"offer""#));
+        assert_eq!(request("GET", "", "").0, "200 OK");
+        assert_eq!(request("GET", "/2", "").0, "404 Not Found");
+        assert_eq!(request("GET", "/x", "").0, "404 Not Found");
+        assert_eq!(request("DELETE", "/1", "").0, "405 Method Not Allowed");
+    }
+
+    #[test]
+    fn test_handle_admin() {
+        assert_eq!(
+            handle_admin("GET", ""),
+            ("200 OK", r#"["feeds","warm-cache"]"#.to_string())
+        );
+        assert_eq!(handle_admin("POST", "/reindex").0, "404 Not Found");
+        assert_eq!(handle_admin("GET", "/feeds").0, "405 Method Not Allowed");
+    }
+
+    #[test]
+    fn test_forwarded_client() {
+        let peer: IpAddr = "127.0.0.1".parse().unwrap();
+        let client = |forwarded: &[&str], proxies: &[&str]| {
+            let headers = forwarded
+                .iter()
+                .map(|value| ("X-Forwarded-For".to_string(), value.to_string()))
+                .collect::<Vec<_>>();
+            forwarded_client(peer, &headers, &IpAllowlist::parse(proxies).unwrap()).to_string()
+        };
+
+        // Without a trusted proxy the header is the client's own claim
+        assert_eq!(client(&["203.0.113.7"], &[]), "127.0.0.1");
+        // The proxy appends the real client, whatever the client sent before it
+        assert_eq!(
+            client(&["127.0.0.1, 203.0.113.7"], &["127.0.0.1"]),
+            "203.0.113.7"
+        );
+        assert_eq!(
+            client(
+                &["198.51.100.1", "203.0.113.7, 10.0.0.2"],
+                &["127.0.0.1", "10.0.0.0/8"]
+            ),
+            "203.0.113.7"
+        );
+        assert_eq!(client(&["nonsense"], &["127.0.0.1"]), "127.0.0.1");
+        assert_eq!(client(&[], &["127.0.0.1"]), "127.0.0.1");
+        let other: IpAddr = "192.0.2.1".parse().unwrap();
+        let headers = vec![("X-Forwarded-For".to_string(), "203.0.113.7".to_string())];
+        let proxies = IpAllowlist::parse(["127.0.0.1"]).unwrap();
+        assert_eq!(forwarded_client(other, &headers, &proxies), other);
+    }
+
+    /// Answer one raw request with `options`, returning the raw response
+    fn respond(options: &DevServerOptions, request: &str) -> String {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        let clients: Clients = Arc::new(Mutex::new(Vec::new()));
+        handle_connection(stream, options, &clients, None).unwrap();
+        let mut response = String::new();
+        std::io::Read::read_to_string(&mut client, &mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_request_head_limits() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "home").unwrap();
+        let options = DevServerOptions::new(dir.path(), 0);
+
+        let ok = respond(&options, "GET / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(ok.starts_with("HTTP/1.1 200 OK"), "{ok}");
+
+        let long_line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_SIZE));
+        assert!(respond(&options, &long_line).starts_with("HTTP/1.1 431 "));
+
+        let many_headers = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X-Padding: aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\r\n".repeat(1000)
+        );
+        assert!(respond(&options, &many_headers).starts_with("HTTP/1.1 431 "));
+    }
+
+    #[test]
+    fn test_admin_allowlist() {
+        let dir = tempdir().unwrap();
+        let options = |allowlist: &[&str]| {
+            DevServerOptions::new(dir.path(), 0)
+                .with_admin(Secret::new("admin"))
+                .with_admin_allowlist(IpAllowlist::parse(allowlist).unwrap())
+        };
+        let request = |forwarded: &str| {
+            format!(
+                "GET {ADMIN_PATH} HTTP/1.1\r\nAuthorization: Bearer admin\r\nX-Forwarded-For: {forwarded}\r\n\r\n"
+            )
+        };
+
+        let allowed = respond(&options(&["127.0.0.1"]), &request("203.0.113.7"));
+        assert!(allowed.starts_with("HTTP/1.1 200 OK"), "{allowed}");
+        // A made-up header doesn't get an outside client in
+        let spoofed = respond(&options(&["10.0.0.0/8"]), &request("10.0.0.1"));
+        assert!(spoofed.starts_with("HTTP/1.1 403 Forbidden"), "{spoofed}");
+        // Without an allowlist, nobody is let in
+        let empty = respond(&options(&[]), &request("127.0.0.1"));
+        assert!(empty.starts_with("HTTP/1.1 403 Forbidden"), "{empty}");
+    }
+
+    #[test]
+    fn test_maintenance() {
+        let dir = tempdir().unwrap();
+        let flag = dir.path().join(admin::MAINTENANCE_FILE);
+        let request = |method: &str, body: &str| {
+            let headers = vec![("Content-Length".to_string(), body.len().to_string())];
+            handle_maintenance(method, &mut body.as_bytes(), &headers, Some(&flag)).unwrap()
+        };
+
+        assert_eq!(
+            request("GET", ""),
+            ("200 OK", r#"{"enabled":false}"#.to_string())
+        );
+        assert_eq!(
+            request("PUT", r#"{"enabled": true}"#),
+            ("200 OK", r#"{"enabled":true}"#.to_string())
+        );
+        assert!(admin::maintenance_enabled(&flag));
+        assert_eq!(request("DELETE", "").0, "405 Method Not Allowed");
+        assert_eq!(
+            handle_maintenance("GET", &mut "".as_bytes(), &[], None)
+                .unwrap()
+                .0,
+            "404 Not Found"
+        );
+
+        let json = maintenance_response(dir.path(), &[]);
+        assert_eq!(json.0, "application/json");
+        let browser = vec![("Accept".to_string(), "text/html,*/*".to_string())];
+        assert!(maintenance_response(dir.path(), &browser)
+            .1
+            .contains("Down for maintenance"));
+        fs::write(dir.path().join(MAINTENANCE_PAGE), "back soon").unwrap();
+        assert_eq!(maintenance_response(dir.path(), &browser).1, "back soon");
+    }
+
+    #[test]
+    fn test_resolve_request_path() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("index.html"), "home").unwrap();
+        fs::write(dir.path().join("cv.html"), "cv").unwrap();
+        fs::create_dir(dir.path().join("blog")).unwrap();
+        fs::write(dir.path().join("blog/index.html"), "blog").unwrap();
+
+        let root = dir.path();
+        assert_eq!(
+            resolve_request_path(root, "/"),
+            Some(root.join("index.html"))
+        );
+        assert_eq!(
+            resolve_request_path(root, "/cv.html?v=1"),
+            Some(root.join("cv.html"))
+        );
+        assert_eq!(
+            resolve_request_path(root, "/cv"),
+            Some(root.join("cv.html"))
+        );
+        assert_eq!(
+            resolve_request_path(root, "/blog/"),
+            Some(root.join("blog/index.html"))
+        );
+        assert_eq!(resolve_request_path(root, "/missing.css"), None);
+        assert_eq!(resolve_request_path(root, "/../etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_websocket_text_frame() {
+        assert_eq!(websocket_text_frame("reload"), b"\x81\x06reload".to_vec());
+    }
+}
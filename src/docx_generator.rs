@@ -0,0 +1,376 @@
+//! Word (.docx) export
+//!
+//! Renders the CV into a plain, single-column Word document for applicant
+//! tracking systems and recruiters that ask for one. The layout deliberately
+//! avoids tables, text boxes and images, which ATS parsers tend to mangle:
+//! sections are real `Heading 1`/`Heading 2` paragraphs and achievements are
+//! real bullet lists.
+
+use anyhow::{Context, Result};
+use docx_rs::{
+    AbstractNumbering, Docx, IndentLevel, Level, LevelJc, LevelText, NumberFormat, Numbering,
+    NumberingId, Paragraph, Run, RunFonts, SpecialIndentType, Start, Style, StyleType,
+};
+use std::fs::{self, File};
+use std::path::Path;
+
+use crate::cv_data::{Cv, Education, Experience, Project};
+
+/// Numbering id of the bullet list definition
+const BULLET_NUMBERING: usize = 1;
+
+/// Body text size in half-points (10.5pt)
+const BODY_SIZE: usize = 21;
+
+/// Generate a .docx file from CV data
+///
+/// # Arguments
+///
+/// * `cv` - The CV data to render
+/// * `output_path` - Path where the document will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_docx(cv: &Cv, output_path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory for {}", output_path))?;
+    }
+
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create DOCX file: {}", output_path))?;
+    build_docx(cv)
+        .build()
+        .pack(file)
+        .with_context(|| format!("Failed to write DOCX file: {}", output_path))?;
+
+    println!("📄 Generated DOCX: {}", output_path);
+    Ok(())
+}
+
+/// Build the Word document for a CV
+///
+/// # Arguments
+///
+/// * `cv` - The CV data to render
+///
+/// # Returns
+///
+/// The document, ready to be packed
+pub fn build_docx(cv: &Cv) -> Docx {
+    let info = &cv.personal_info;
+    let docx = base_document()
+        .add_paragraph(text_paragraph(&info.name).style("Title"))
+        .add_paragraph(text_paragraph(&info.title).bold());
+
+    let contact = [
+        Some(info.email.as_str()),
+        info.phone.as_deref(),
+        info.location.as_deref(),
+        info.website.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|value| !value.is_empty())
+    .collect::<Vec<_>>();
+    let docx = if contact.is_empty() {
+        docx
+    } else {
+        docx.add_paragraph(text_paragraph(&contact.join(" | ")))
+    };
+
+    // Sorted so the document is the same on every build
+    let mut social_links = info.social_links.iter().collect::<Vec<_>>();
+    social_links.sort();
+    let docx = social_links.into_iter().fold(docx, |docx, (name, url)| {
+        docx.add_paragraph(text_paragraph(&format!("{}: {}", name, url)))
+    });
+
+    let docx = if info.summary.is_empty() {
+        docx
+    } else {
+        docx.add_paragraph(heading("Summary"))
+            .add_paragraph(text_paragraph(&info.summary))
+    };
+
+    let docx = section(docx, "Experience", cv.experiences.iter(), add_experience);
+    let docx = section(docx, "Education", cv.education.iter(), add_education);
+
+    let docx = section(
+        docx,
+        "Skills",
+        cv.skill_categories
+            .iter()
+            .filter(|category| !category.skills.is_empty()),
+        |docx, category| {
+            let skills = category.skill_names().into_iter().collect::<Vec<_>>();
+            docx.add_paragraph(labelled_paragraph(&category.name, &skills.join(", ")))
+        },
+    );
+
+    let docx = section(docx, "Projects", cv.projects.iter(), add_project);
+
+    let mut languages = cv.languages.iter().collect::<Vec<_>>();
+    languages.sort();
+    let docx = section(
+        docx,
+        "Languages",
+        languages.into_iter(),
+        |docx, (language, level)| docx.add_paragraph(labelled_paragraph(language, level)),
+    );
+
+    section(
+        docx,
+        "Certifications",
+        cv.certifications.iter(),
+        |docx, certification| docx.add_paragraph(bullet(certification)),
+    )
+}
+
+/// Document with the styles and bullet list definition used by the CV
+fn base_document() -> Docx {
+    Docx::new()
+        .default_fonts(RunFonts::new().ascii("Calibri").hi_ansi("Calibri"))
+        .default_size(BODY_SIZE)
+        .add_style(
+            Style::new("Title", StyleType::Paragraph)
+                .name("Title")
+                .size(36)
+                .bold(),
+        )
+        .add_style(
+            Style::new("Heading1", StyleType::Paragraph)
+                .name("Heading 1")
+                .based_on("Normal")
+                .next("Normal")
+                .outline_lvl(0)
+                .size(28)
+                .bold(),
+        )
+        .add_style(
+            Style::new("Heading2", StyleType::Paragraph)
+                .name("Heading 2")
+                .based_on("Normal")
+                .next("Normal")
+                .outline_lvl(1)
+                .size(BODY_SIZE + 2)
+                .bold(),
+        )
+        .add_abstract_numbering(
+            AbstractNumbering::new(BULLET_NUMBERING).add_level(
+                Level::new(
+                    0,
+                    Start::new(1),
+                    NumberFormat::new("bullet"),
+                    LevelText::new("•"),
+                    LevelJc::new("left"),
+                )
+                .indent(
+                    Some(720),
+                    Some(SpecialIndentType::Hanging(360)),
+                    None,
+                    None,
+                ),
+            ),
+        )
+        .add_numbering(Numbering::new(BULLET_NUMBERING, BULLET_NUMBERING))
+}
+
+/// Add a titled section, or nothing if it has no items
+fn section<T>(
+    docx: Docx,
+    title: &str,
+    items: impl Iterator<Item = T>,
+    add_item: impl Fn(Docx, T) -> Docx,
+) -> Docx {
+    let mut items = items.peekable();
+    if items.peek().is_none() {
+        return docx;
+    }
+
+    items.fold(docx.add_paragraph(heading(title)), add_item)
+}
+
+fn add_experience(docx: Docx, experience: &Experience) -> Docx {
+    let docx = docx
+        .add_paragraph(
+            text_paragraph(&format!("{}, {}", experience.position, experience.company))
+                .style("Heading2"),
+        )
+        .add_paragraph(text_paragraph(&period_line(
+            &experience.period("en"),
+            experience.location.as_deref(),
+        )));
+
+    let docx = if experience.description.is_empty() {
+        docx
+    } else {
+        docx.add_paragraph(text_paragraph(&experience.description))
+    };
+
+    let docx = experience
+        .achievements
+        .iter()
+        .fold(docx, |docx, achievement| {
+            docx.add_paragraph(bullet(achievement))
+        });
+
+    add_technologies(docx, &experience.technologies)
+}
+
+fn add_education(docx: Docx, education: &Education) -> Docx {
+    let degree = if education.field.is_empty() {
+        education.degree.clone()
+    } else {
+        format!("{} in {}", education.degree, education.field)
+    };
+
+    let docx = docx
+        .add_paragraph(
+            text_paragraph(&format!("{}, {}", degree, education.institution)).style("Heading2"),
+        )
+        .add_paragraph(text_paragraph(&period_line(
+            &education.period("en"),
+            education.location.as_deref(),
+        )));
+
+    let docx = match &education.gpa {
+        Some(gpa) => docx.add_paragraph(labelled_paragraph("GPA", gpa)),
+        None => docx,
+    };
+
+    education
+        .achievements
+        .iter()
+        .fold(docx, |docx, achievement| {
+            docx.add_paragraph(bullet(achievement))
+        })
+}
+
+fn add_project(docx: Docx, project: &Project) -> Docx {
+    let name = project.display_name.as_ref().unwrap_or(&project.name);
+    let docx = docx.add_paragraph(text_paragraph(name).style("Heading2"));
+
+    let docx = if project.description.is_empty() {
+        docx
+    } else {
+        docx.add_paragraph(text_paragraph(&project.description))
+    };
+
+    let docx = project.highlights.iter().fold(docx, |docx, highlight| {
+        docx.add_paragraph(bullet(highlight))
+    });
+    let docx = add_technologies(docx, &project.technologies);
+
+    match project.url.as_ref().or(project.repository.as_ref()) {
+        Some(url) => docx.add_paragraph(text_paragraph(url)),
+        None => docx,
+    }
+}
+
+fn add_technologies(docx: Docx, technologies: &im::Vector<String>) -> Docx {
+    if technologies.is_empty() {
+        return docx;
+    }
+
+    let technologies = technologies.iter().cloned().collect::<Vec<_>>();
+    docx.add_paragraph(labelled_paragraph("Technologies", &technologies.join(", ")))
+}
+
+/// "Mar 2021 – Present | Copenhagen"
+pub(crate) fn period_line(period: &str, location: Option<&str>) -> String {
+    match location.filter(|location| !location.is_empty()) {
+        Some(location) => format!("{} | {}", period, location),
+        None => period.to_string(),
+    }
+}
+
+fn heading(text: &str) -> Paragraph {
+    text_paragraph(text).style("Heading1")
+}
+
+fn text_paragraph(text: &str) -> Paragraph {
+    Paragraph::new().add_run(Run::new().add_text(text))
+}
+
+fn labelled_paragraph(label: &str, text: &str) -> Paragraph {
+    Paragraph::new()
+        .add_run(Run::new().add_text(format!("{}: ", label)).bold())
+        .add_run(Run::new().add_text(text))
+}
+
+fn bullet(text: &str) -> Paragraph {
+    text_paragraph(text).numbering(NumberingId::new(BULLET_NUMBERING), IndentLevel::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::PersonalInfo;
+    use crate::cv_date::CvDate;
+    use im::{vector, HashMap};
+
+    fn sample_cv() -> Cv {
+        Cv {
+            personal_info: PersonalInfo {
+                name: "Ada Lovelace".to_string(),
+                title: "Analyst".to_string(),
+                email: "ada@example.com".to_string(),
+                phone: None,
+                website: None,
+                location: Some("London".to_string()),
+                summary: String::new(),
+                social_links: HashMap::new(),
+                profile_image: None,
+                github_avatar_url: None,
+                summaries: Default::default(),
+            },
+            experiences: vector![Experience {
+                company: "Analytical Engines Ltd".to_string(),
+                position: "Programmer".to_string(),
+                start_date: CvDate::Month(1842, 9),
+                end_date: None,
+                location: None,
+                description: String::new(),
+                achievements: vector!["Wrote the first published algorithm".to_string()],
+                technologies: vector![],
+                tags: im::Vector::new(),
+            }],
+            education: vector![],
+            skill_categories: vector![],
+            projects: vector![],
+            languages: HashMap::new(),
+            certifications: vector![],
+            github_sources: vector![],
+            registry_sources: vector![],
+            testimonials: vector![],
+            github_stats: None,
+        }
+    }
+
+    #[test]
+    fn test_build_docx() {
+        let json = build_docx(&sample_cv()).json();
+
+        assert!(json.contains("Ada Lovelace"));
+        assert!(json.contains("ada@example.com | London"));
+        assert!(json.contains("Programmer, Analytical Engines Ltd"));
+        assert!(json.contains("Sep 1842 – Present"));
+        assert!(json.contains("Wrote the first published algorithm"));
+        // Empty sections are left out entirely
+        assert!(!json.contains("Summary"));
+        assert!(!json.contains("Education"));
+    }
+
+    #[test]
+    fn test_generate_docx() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out/cv.docx");
+
+        generate_docx(&sample_cv(), path.to_str().unwrap()).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"PK"), "a .docx is a zip archive");
+    }
+}
@@ -0,0 +1,561 @@
+//! Europass CV export
+//!
+//! Many EU applications ask for a Europass CV. `cv export --format europass`
+//! maps the CV data to the Europass v3.4 schema: personal information, work
+//! experience, education, skills, certifications and languages, with the
+//! proficiency of each language translated to a CEFR level (A1 to C2).
+//! The document is built in the Europass JSON layout and converted to XML,
+//! since the two only differ in how lists, dates and the locale are written.
+
+use anyhow::Result;
+use chrono::Utc;
+use im::Vector;
+use serde_json::{json, Map, Value};
+
+use crate::cv_data::Cv;
+use crate::cv_date::CvDate;
+
+/// Europass schema version the export follows
+pub const XSD_VERSION: &str = "V3.4";
+
+/// Europass XML namespace
+const NAMESPACE: &str = "http://europass.cedefop.europa.eu/Europass";
+
+/// Element order of the Europass schema, whose types are XSD sequences
+const ELEMENT_ORDER: [&str; 47] = [
+    "DocumentInfo",
+    "LearnerInfo",
+    "DocumentType",
+    "CreationDate",
+    "LastUpdateDate",
+    "XSDVersion",
+    "Generator",
+    "Identification",
+    "Headline",
+    "WorkExperience",
+    "Education",
+    "Skills",
+    "Achievement",
+    "PersonName",
+    "FirstName",
+    "Surname",
+    "Name",
+    "ContactInfo",
+    "Address",
+    "Email",
+    "Telephone",
+    "Website",
+    "Contact",
+    "Use",
+    "Type",
+    "Code",
+    "Label",
+    "Period",
+    "From",
+    "To",
+    "Current",
+    "Position",
+    "Title",
+    "Description",
+    "ProficiencyLevel",
+    "Listening",
+    "Reading",
+    "SpokenInteraction",
+    "SpokenProduction",
+    "Writing",
+    "Activities",
+    "Employer",
+    "Organisation",
+    "Linguistic",
+    "MotherTongue",
+    "ForeignLanguage",
+    "Computer",
+];
+
+/// ISO 639-1 codes of the languages Europass lists
+const LANGUAGE_CODES: [(&str, &str); 26] = [
+    ("arabic", "ar"),
+    ("chinese", "zh"),
+    ("czech", "cs"),
+    ("danish", "da"),
+    ("dutch", "nl"),
+    ("english", "en"),
+    ("estonian", "et"),
+    ("finnish", "fi"),
+    ("french", "fr"),
+    ("german", "de"),
+    ("greek", "el"),
+    ("hindi", "hi"),
+    ("hungarian", "hu"),
+    ("icelandic", "is"),
+    ("italian", "it"),
+    ("japanese", "ja"),
+    ("korean", "ko"),
+    ("norwegian", "no"),
+    ("polish", "pl"),
+    ("portuguese", "pt"),
+    ("romanian", "ro"),
+    ("russian", "ru"),
+    ("spanish", "es"),
+    ("swedish", "sv"),
+    ("turkish", "tr"),
+    ("ukrainian", "uk"),
+];
+
+/// How well a language is spoken, in Europass terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageLevel {
+    /// A mother tongue
+    Native,
+    /// A foreign language at a CEFR level
+    Cefr(&'static str),
+    /// A foreign language without a recognizable level
+    Unknown,
+}
+
+/// Map a proficiency from the CV data to a CEFR level
+///
+/// Accepts CEFR levels as they are ("B2") and common descriptions such as
+/// "Native", "Fluent", "Professional", "Intermediate" or "Basic".
+///
+/// # Arguments
+///
+/// * `proficiency` - Proficiency as written in the CV data
+///
+/// # Returns
+///
+/// The Europass language level
+pub fn cefr_level(proficiency: &str) -> LanguageLevel {
+    const LEVELS: [&str; 6] = ["A1", "A2", "B1", "B2", "C1", "C2"];
+    let normalized = proficiency.trim().to_lowercase();
+    if let Some(level) = LEVELS
+        .iter()
+        .find(|level| normalized.starts_with(&level.to_lowercase()))
+    {
+        return LanguageLevel::Cefr(level);
+    }
+
+    let mentions = |words: &[&str]| words.iter().any(|word| normalized.contains(word));
+    if mentions(&["native", "mother tongue", "bilingual"]) {
+        LanguageLevel::Native
+    } else if mentions(&["proficient", "mastery"]) {
+        LanguageLevel::Cefr("C2")
+    } else if mentions(&["fluent", "professional", "advanced"]) {
+        LanguageLevel::Cefr("C1")
+    } else if mentions(&["upper intermediate", "upper-intermediate"]) {
+        LanguageLevel::Cefr("B2")
+    } else if mentions(&["intermediate", "conversational", "working"]) {
+        LanguageLevel::Cefr("B1")
+    } else if mentions(&["elementary", "basic"]) {
+        LanguageLevel::Cefr("A2")
+    } else if mentions(&["beginner"]) {
+        LanguageLevel::Cefr("A1")
+    } else {
+        LanguageLevel::Unknown
+    }
+}
+
+/// Build the Europass document in its JSON layout
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+/// * `locale` - Language of the CV, e.g. "en"
+///
+/// # Returns
+///
+/// The `SkillsPassport` document
+pub fn europass_json(cv: &Cv, locale: &str) -> Value {
+    let info = &cv.personal_info;
+    let (first_name, surname) = match info.name.trim().rsplit_once(' ') {
+        Some((first, last)) => (first, last),
+        None => ("", info.name.trim()),
+    };
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+
+    let mut contact = Map::new();
+    if let Some(location) = info.location.as_deref().filter(|l| !l.is_empty()) {
+        contact.insert(
+            "Address".into(),
+            json!({ "Contact": { "Municipality": location } }),
+        );
+    }
+    if !info.email.is_empty() {
+        contact.insert("Email".into(), json!({ "Contact": info.email }));
+    }
+    if let Some(phone) = info.phone.as_deref().filter(|p| !p.is_empty()) {
+        contact.insert(
+            "Telephone".into(),
+            json!([{ "Contact": phone, "Use": { "Code": "mobile" } }]),
+        );
+    }
+    let mut websites = info
+        .website
+        .iter()
+        .map(|url| json!({ "Contact": url, "Use": { "Code": "personal" } }))
+        .collect::<Vec<_>>();
+    let mut social_links = info.social_links.iter().collect::<Vec<_>>();
+    social_links.sort();
+    websites.extend(
+        social_links
+            .into_iter()
+            .map(|(_, url)| json!({ "Contact": url, "Use": { "Code": "business" } })),
+    );
+    if !websites.is_empty() {
+        contact.insert("Website".into(), Value::Array(websites));
+    }
+
+    let work_experience = cv
+        .experiences
+        .iter()
+        .map(|experience| {
+            let mut employer = json!({ "Name": experience.company });
+            if let Some(location) = &experience.location {
+                employer["ContactInfo"] =
+                    json!({ "Address": { "Contact": { "Municipality": location } } });
+            }
+            json!({
+                "Period": period(&experience.start_date, experience.end_date.as_ref()),
+                "Position": { "Label": experience.position },
+                "Activities": activities(&experience.description, &experience.achievements),
+                "Employer": employer,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let education = cv
+        .education
+        .iter()
+        .map(|education| {
+            let title = if education.field.is_empty() {
+                education.degree.clone()
+            } else {
+                format!("{} in {}", education.degree, education.field)
+            };
+            let mut organisation = json!({ "Name": education.institution });
+            if let Some(location) = &education.location {
+                organisation["ContactInfo"] =
+                    json!({ "Address": { "Contact": { "Municipality": location } } });
+            }
+            let mut achievements = education.achievements.clone();
+            if let Some(gpa) = &education.gpa {
+                achievements.push_front(format!("GPA: {gpa}"));
+            }
+            json!({
+                "Period": period(&education.start_date, education.end_date.as_ref()),
+                "Title": title,
+                "Activities": activities("", &achievements),
+                "Organisation": organisation,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut languages = cv.languages.iter().collect::<Vec<_>>();
+    languages.sort();
+    let description = |language: &str| {
+        let code = LANGUAGE_CODES
+            .iter()
+            .find(|(name, _)| language.eq_ignore_ascii_case(name))
+            .map(|(_, code)| *code);
+        match code {
+            Some(code) => json!({ "Code": code, "Label": language }),
+            None => json!({ "Label": language }),
+        }
+    };
+    let mother_tongues = languages
+        .iter()
+        .filter(|(_, proficiency)| cefr_level(proficiency) == LanguageLevel::Native)
+        .map(|(language, _)| json!({ "Description": description(language) }))
+        .collect::<Vec<_>>();
+    let foreign_languages = languages
+        .iter()
+        .filter_map(|(language, proficiency)| match cefr_level(proficiency) {
+            LanguageLevel::Native => None,
+            LanguageLevel::Cefr(level) => Some(json!({
+                "Description": description(language),
+                "ProficiencyLevel": {
+                    "Listening": level,
+                    "Reading": level,
+                    "SpokenInteraction": level,
+                    "SpokenProduction": level,
+                    "Writing": level,
+                },
+            })),
+            LanguageLevel::Unknown => Some(json!({ "Description": description(language) })),
+        })
+        .collect::<Vec<_>>();
+
+    let mut linguistic = Map::new();
+    if !mother_tongues.is_empty() {
+        linguistic.insert("MotherTongue".into(), Value::Array(mother_tongues));
+    }
+    if !foreign_languages.is_empty() {
+        linguistic.insert("ForeignLanguage".into(), Value::Array(foreign_languages));
+    }
+    let mut skills = Map::new();
+    if !linguistic.is_empty() {
+        skills.insert("Linguistic".into(), Value::Object(linguistic));
+    }
+    if !cv.skill_categories.is_empty() {
+        let computer = cv
+            .skill_categories
+            .iter()
+            .map(|category| {
+                format!(
+                    "<p><strong>{}:</strong> {}</p>",
+                    category.name,
+                    category
+                        .skill_names()
+                        .into_iter()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+            .collect::<String>();
+        skills.insert("Computer".into(), json!({ "Description": computer }));
+    }
+
+    let mut learner_info = Map::new();
+    learner_info.insert(
+        "Identification".into(),
+        json!({
+            "PersonName": { "FirstName": first_name, "Surname": surname },
+            "ContactInfo": contact,
+        }),
+    );
+    if !info.title.is_empty() {
+        learner_info.insert(
+            "Headline".into(),
+            json!({
+                "Type": { "Code": "position", "Label": "Position" },
+                "Description": { "Label": info.title },
+            }),
+        );
+    }
+    if !work_experience.is_empty() {
+        learner_info.insert("WorkExperience".into(), Value::Array(work_experience));
+    }
+    if !education.is_empty() {
+        learner_info.insert("Education".into(), Value::Array(education));
+    }
+    if !skills.is_empty() {
+        learner_info.insert("Skills".into(), Value::Object(skills));
+    }
+    if !cv.certifications.is_empty() {
+        learner_info.insert(
+            "Achievement".into(),
+            json!([{
+                "Title": { "Code": "certifications", "Label": "Certifications" },
+                "Description": activities("", &cv.certifications),
+            }]),
+        );
+    }
+
+    json!({
+        "SkillsPassport": {
+            "Locale": locale,
+            "DocumentInfo": {
+                "DocumentType": "ECV",
+                "CreationDate": now,
+                "LastUpdateDate": now,
+                "XSDVersion": XSD_VERSION,
+                "Generator": "cv-generator",
+            },
+            "LearnerInfo": learner_info,
+        }
+    })
+}
+
+/// Render the Europass document as XML
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+/// * `locale` - Language of the CV, e.g. "en"
+///
+/// # Returns
+///
+/// The `SkillsPassport` XML document
+pub fn europass_xml(cv: &Cv, locale: &str) -> Result<String> {
+    let document = europass_json(cv, locale);
+    let passport = document["SkillsPassport"]
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Europass document without SkillsPassport"))?;
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SkillsPassport xmlns=\"{NAMESPACE}\" locale=\"{}\">\n",
+        escape_xml(locale)
+    );
+    for (name, value) in schema_order(passport) {
+        if name != "Locale" {
+            write_element(&mut xml, name, value, 1);
+        }
+    }
+    xml.push_str("</SkillsPassport>\n");
+    Ok(xml)
+}
+
+/// Write a JSON value as an XML element
+///
+/// Arrays become a `<NameList>` of `<Name>` elements and dates
+/// (`{"Year": 2022, "Month": 1}`) become `year` and `month` attributes,
+/// as in the Europass XML schema.
+fn write_element(xml: &mut String, name: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match value {
+        Value::Array(items) => {
+            xml.push_str(&format!("{indent}<{name}List>\n"));
+            for item in items {
+                write_element(xml, name, item, depth + 1);
+            }
+            xml.push_str(&format!("{indent}</{name}List>\n"));
+        }
+        Value::Object(fields) if fields.contains_key("Year") => {
+            let attribute = |key: &str, format: fn(u64) -> String| {
+                fields
+                    .get(key)
+                    .and_then(Value::as_u64)
+                    .map(|value| format!(" {}=\"{}\"", key.to_lowercase(), format(value)))
+                    .unwrap_or_default()
+            };
+            xml.push_str(&format!(
+                "{indent}<{name}{}{}/>\n",
+                attribute("Year", |year| year.to_string()),
+                attribute("Month", |month| format!("--{month:02}"))
+            ));
+        }
+        Value::Object(fields) => {
+            xml.push_str(&format!("{indent}<{name}>\n"));
+            for (child, value) in schema_order(fields) {
+                write_element(xml, child, value, depth + 1);
+            }
+            xml.push_str(&format!("{indent}</{name}>\n"));
+        }
+        Value::String(text) => {
+            xml.push_str(&format!("{indent}<{name}>{}</{name}>\n", escape_xml(text)))
+        }
+        other => xml.push_str(&format!("{indent}<{name}>{other}</{name}>\n")),
+    }
+}
+
+/// The fields of an object in the order the schema expects
+fn schema_order(fields: &Map<String, Value>) -> Vec<(&str, &Value)> {
+    let mut ordered = fields
+        .iter()
+        .map(|(name, value)| (name.as_str(), value))
+        .collect::<Vec<_>>();
+    ordered.sort_by_key(|(name, _)| {
+        ELEMENT_ORDER
+            .iter()
+            .position(|element| element == name)
+            .unwrap_or(ELEMENT_ORDER.len())
+    });
+    ordered
+}
+
+/// A Europass period from CV dates ("2022-01" or "2022"); no end date is current
+fn period(start: &CvDate, end: Option<&CvDate>) -> Value {
+    let mut period = json!({ "From": date(start) });
+    match end.filter(|end| **end != CvDate::Present) {
+        Some(end) => period["To"] = date(end),
+        None => period["Current"] = json!(true),
+    }
+    period
+}
+
+fn date(date: &CvDate) -> Value {
+    match (date.year(), date.month()) {
+        (Some(year), Some(month)) => json!({ "Year": year, "Month": month }),
+        (Some(year), None) => json!({ "Year": year }),
+        _ => json!({}),
+    }
+}
+
+/// Europass rich text with a description and a list of items
+fn activities(description: &str, items: &Vector<String>) -> String {
+    let paragraph = if description.is_empty() {
+        String::new()
+    } else {
+        format!("<p>{description}</p>")
+    };
+    if items.is_empty() {
+        return paragraph;
+    }
+    let list = items
+        .iter()
+        .map(|item| format!("<li>{item}</li>"))
+        .collect::<String>();
+    format!("{paragraph}<ul>{list}</ul>")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::Experience;
+
+    #[test]
+    fn test_cefr_level() {
+        assert_eq!(cefr_level("Native"), LanguageLevel::Native);
+        assert_eq!(cefr_level("b2"), LanguageLevel::Cefr("B2"));
+        assert_eq!(cefr_level("Professional"), LanguageLevel::Cefr("C1"));
+        assert_eq!(cefr_level("Intermediate"), LanguageLevel::Cefr("B1"));
+        assert_eq!(cefr_level("Some"), LanguageLevel::Unknown);
+    }
+
+    #[test]
+    fn test_europass_xml() {
+        let mut cv = Cv::create_minimal();
+        cv.personal_info.name = "Ada King Lovelace".to_string();
+        cv.experiences = im::vector![Experience {
+            company: "Analytical & Co".to_string(),
+            position: "Engineer".to_string(),
+            start_date: CvDate::Month(2022, 1),
+            end_date: None,
+            location: None,
+            description: "Built engines.".to_string(),
+            achievements: im::vector!["First program".to_string()],
+            technologies: Vector::new(),
+            tags: Vector::new(),
+        }];
+        cv.languages = im::hashmap! {
+            "English".to_string() => "Native".to_string(),
+            "Danish".to_string() => "B2".to_string(),
+        };
+
+        let json = europass_json(&cv, "en");
+        let learner = &json["SkillsPassport"]["LearnerInfo"];
+        assert_eq!(
+            learner["Identification"]["PersonName"]["Surname"],
+            "Lovelace"
+        );
+        assert_eq!(
+            learner["Skills"]["Linguistic"]["ForeignLanguage"][0]["ProficiencyLevel"]["Writing"],
+            "B2"
+        );
+
+        let xml = europass_xml(&cv, "en").unwrap();
+        assert!(xml.contains(
+            "<SkillsPassport xmlns=\"http://europass.cedefop.europa.eu/Europass\" locale=\"en\">"
+        ));
+        assert!(xml.contains("<FirstName>Ada King</FirstName>"));
+        assert!(xml.contains("<WorkExperienceList>\n      <WorkExperience>"));
+        assert!(xml.contains("<From year=\"2022\" month=\"--01\"/>"));
+        assert!(xml.contains("<Current>true</Current>"));
+        assert!(xml.contains("<Name>Analytical &amp; Co</Name>"));
+        assert!(xml.contains(
+            "<Activities>&lt;p&gt;Built engines.&lt;/p&gt;&lt;ul&gt;&lt;li&gt;First program&lt;/li&gt;&lt;/ul&gt;</Activities>"
+        ));
+        assert!(xml.contains("<Code>en</Code>"));
+        assert!(xml.contains("<MotherTongueList>"));
+        let position = |tag: &str| xml.find(tag).unwrap();
+        assert!(position("<Identification>") < position("<WorkExperienceList>"));
+        assert!(position("<WorkExperienceList>") < position("<Skills>"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Featured content selection
+//!
+//! Picks the blog posts and projects shown on the home page, according to the
+//! `featured` section of the site configuration. Posts opt in with `featured`
+//! or `pinned` in their front matter; projects are pinned by name, since most
+//! of them come from GitHub rather than the CV data.
+
+use im::Vector;
+
+use crate::blog_posts::BlogPost;
+use crate::cv_data::Project;
+use crate::site_config::{FeaturedConfig, PostOrder, ProjectOrder};
+
+/// Default number of featured posts and pinned projects
+pub const DEFAULT_FEATURED_COUNT: usize = 3;
+
+/// Select the posts to feature on the home page
+///
+/// # Arguments
+///
+/// * `posts` - All published posts
+/// * `config` - Featured content configuration
+///
+/// # Returns
+///
+/// Pinned posts first, then the other featured posts in the configured order,
+/// limited to the configured count
+pub fn featured_posts(posts: &Vector<BlogPost>, config: &FeaturedConfig) -> Vector<BlogPost> {
+    let mut featured: Vec<_> = posts
+        .iter()
+        .filter(|post| post.featured || post.pinned)
+        .cloned()
+        .collect();
+
+    featured.sort_by(|a, b| {
+        let by_date = match config.post_order {
+            PostOrder::Newest => b.date.cmp(&a.date),
+            PostOrder::Oldest => a.date.cmp(&b.date),
+        };
+        b.pinned.cmp(&a.pinned).then(by_date)
+    });
+
+    featured
+        .into_iter()
+        .take(config.posts.unwrap_or(DEFAULT_FEATURED_COUNT))
+        .collect()
+}
+
+/// Select the projects to pin on the home page
+///
+/// Projects listed in `pinned_projects` are matched by name, display name or
+/// repository name. Without a list, the most starred projects are pinned.
+///
+/// # Arguments
+///
+/// * `projects` - All projects, local and from GitHub
+/// * `config` - Featured content configuration
+///
+/// # Returns
+///
+/// The pinned projects in the configured order, limited to the configured count
+pub fn pinned_projects(projects: &Vector<Project>, config: &FeaturedConfig) -> Vector<Project> {
+    let mut pinned: Vec<Project> = if config.pinned_projects.is_empty() {
+        let mut by_stars: Vec<_> = projects.iter().cloned().collect();
+        by_stars.sort_by_key(|project| std::cmp::Reverse(project.stars.unwrap_or(0)));
+        by_stars
+    } else {
+        config
+            .pinned_projects
+            .iter()
+            .filter_map(|name| projects.iter().find(|project| matches_name(project, name)))
+            .cloned()
+            .collect()
+    };
+
+    match config.project_order {
+        ProjectOrder::Pinned => {}
+        ProjectOrder::Stars => {
+            pinned.sort_by_key(|project| std::cmp::Reverse(project.stars.unwrap_or(0)))
+        }
+        ProjectOrder::Name => pinned.sort_by_key(|project| display_name(project).to_lowercase()),
+    }
+
+    pinned
+        .into_iter()
+        .take(config.projects.unwrap_or(DEFAULT_FEATURED_COUNT))
+        .collect()
+}
+
+/// Whether a project is the one a configured name refers to
+pub(crate) fn matches_name(project: &Project, name: &str) -> bool {
+    let repository_name = project
+        .repository
+        .as_deref()
+        .and_then(|url| url.trim_end_matches('/').rsplit('/').next());
+
+    [
+        Some(project.name.as_str()),
+        project.display_name.as_deref(),
+        repository_name,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|candidate| candidate.eq_ignore_ascii_case(name))
+}
+
+fn display_name(project: &Project) -> &str {
+    project.display_name.as_deref().unwrap_or(&project.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use im::vector;
+
+    fn post(slug: &str, day: u32, featured: bool, pinned: bool) -> BlogPost {
+        BlogPost {
+            title: slug.to_string(),
+            date: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+            tags: Vector::new(),
+            excerpt: None,
+            slug: slug.to_string(),
+            content: String::new(),
+            reading_time: None,
+            published: None,
+            author: None,
+            featured,
+            pinned,
+            reading_progress: false,
+            sections: Vector::new(),
+            updated: None,
+            review_after: None,
+            stale: false,
+            fields: Default::default(),
+        }
+    }
+
+    fn project(name: &str, repository: &str, stars: u32) -> Project {
+        Project {
+            name: name.to_string(),
+            description: String::new(),
+            url: None,
+            repository: Some(repository.to_string()),
+            technologies: Vector::new(),
+            highlights: Vector::new(),
+            stars: Some(stars),
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
+            language: None,
+            language_icon: None,
+            display_name: None,
+        }
+    }
+
+    fn slugs(posts: &Vector<BlogPost>) -> Vec<&str> {
+        posts.iter().map(|post| post.slug.as_str()).collect()
+    }
+
+    fn names(projects: &Vector<Project>) -> Vec<&str> {
+        projects
+            .iter()
+            .map(|project| project.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_featured_posts() {
+        let posts = vector![
+            post("plain", 5, false, false),
+            post("new", 4, true, false),
+            post("old", 1, true, false),
+            post("pinned", 2, false, true),
+        ];
+
+        let config = FeaturedConfig::default();
+        assert_eq!(
+            slugs(&featured_posts(&posts, &config)),
+            ["pinned", "new", "old"]
+        );
+
+        let config = FeaturedConfig {
+            posts: Some(2),
+            post_order: PostOrder::Oldest,
+            ..FeaturedConfig::default()
+        };
+        assert_eq!(slugs(&featured_posts(&posts, &config)), ["pinned", "old"]);
+    }
+
+    #[test]
+    fn test_pinned_projects() {
+        let projects = vector![
+            project("alpha", "https://github.com/me/alpha", 5),
+            project("Beta - Rust", "https://github.com/me/beta-rs", 50),
+            project("gamma", "https://github.com/me/gamma", 10),
+            project("delta", "https://github.com/me/delta", 1),
+        ];
+
+        // Most starred when nothing is pinned
+        let config = FeaturedConfig::default();
+        assert_eq!(
+            names(&pinned_projects(&projects, &config)),
+            ["Beta - Rust", "gamma", "alpha"]
+        );
+
+        // Pinned by name or repository name, unknown names are skipped
+        let config = FeaturedConfig {
+            pinned_projects: vector![
+                "delta".to_string(),
+                "missing".to_string(),
+                "beta-rs".to_string()
+            ],
+            ..FeaturedConfig::default()
+        };
+        assert_eq!(
+            names(&pinned_projects(&projects, &config)),
+            ["delta", "Beta - Rust"]
+        );
+
+        let config = FeaturedConfig {
+            project_order: ProjectOrder::Stars,
+            ..config
+        };
+        assert_eq!(
+            names(&pinned_projects(&projects, &config)),
+            ["Beta - Rust", "delta"]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Front matter schemas
+//!
+//! Posts, pages and project case studies each have their own front matter
+//! fields, see [`ContentType::fields`]. Any other key is a custom field: it's
+//! kept in the content's `fields`, so templates can show it, e.g.
+//! `{% if let Some(series) = post.fields.text("series") %}`.
+//!
+//! Custom fields are declared per content type under `front_matter` in the
+//! site config, with a type and whether they're required. Every build warns
+//! about keys that are neither built in nor declared (usually typos), declared
+//! fields of the wrong type and required ones that are missing. The warnings
+//! never fail the build.
+
+use im::OrdMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+use crate::blog_posts::parse_date;
+use crate::cv_schema::closest;
+
+/// A kind of markdown content with front matter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    /// A blog post
+    Post,
+    /// A static page
+    Page,
+    /// A project case study
+    Project,
+}
+
+impl ContentType {
+    /// The built-in front matter fields
+    pub fn fields(self) -> &'static [&'static str] {
+        match self {
+            ContentType::Post => &[
+                "title",
+                "date",
+                "tags",
+                "excerpt",
+                "reading_time",
+                "published",
+                "author",
+                "featured",
+                "pinned",
+                "reading_progress",
+                "updated",
+                "review_after",
+                "references",
+                "bibliography",
+                "nocite",
+            ],
+            ContentType::Page => &[
+                "title",
+                "layout",
+                "menu_label",
+                "custom_css",
+                "custom_js",
+                "order",
+                "updated",
+                "review_after",
+                "references",
+                "bibliography",
+                "nocite",
+            ],
+            ContentType::Project => &[
+                "title",
+                "project",
+                "summary",
+                "role",
+                "screenshots",
+                "outcomes",
+            ],
+        }
+    }
+}
+
+impl fmt::Display for ContentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ContentType::Post => "post",
+            ContentType::Page => "page",
+            ContentType::Project => "project",
+        })
+    }
+}
+
+/// Front matter fields that aren't built in, by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CustomFields(OrdMap<String, Value>);
+
+impl CustomFields {
+    /// The value of a field as written in the front matter
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// A field as text: lists are joined with commas, and empty values are
+    /// left out
+    pub fn text(&self, name: &str) -> Option<String> {
+        fn text(value: &Value) -> Option<String> {
+            match value {
+                Value::Null => None,
+                Value::String(text) => Some(text.clone()),
+                Value::Array(items) => {
+                    Some(items.iter().filter_map(text).collect::<Vec<_>>().join(", "))
+                }
+                other => Some(other.to_string()),
+            }
+        }
+        self.get(name)
+            .and_then(text)
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Whether the front matter has no custom fields
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Custom front matter fields declared per content type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FrontMatterConfig {
+    /// Custom fields of blog posts
+    #[serde(default)]
+    pub posts: OrdMap<String, FieldSchema>,
+    /// Custom fields of static pages
+    #[serde(default)]
+    pub pages: OrdMap<String, FieldSchema>,
+    /// Custom fields of project case studies
+    #[serde(default)]
+    pub projects: OrdMap<String, FieldSchema>,
+}
+
+impl FrontMatterConfig {
+    /// The custom fields declared for a content type
+    pub fn schema(&self, content: ContentType) -> &OrdMap<String, FieldSchema> {
+        match content {
+            ContentType::Post => &self.posts,
+            ContentType::Page => &self.pages,
+            ContentType::Project => &self.projects,
+        }
+    }
+}
+
+/// A declared custom field
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// Type of the value (default: any)
+    #[serde(rename = "type", default)]
+    pub kind: FieldType,
+    /// Whether every post, page or case study must set it
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Type of a custom field's value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldType {
+    /// Any value
+    #[default]
+    Any,
+    /// Text
+    String,
+    /// A number
+    Number,
+    /// `true` or `false`
+    Boolean,
+    /// A list of values
+    List,
+    /// A date, RFC 3339 or `YYYY-MM-DD`
+    Date,
+}
+
+impl FieldType {
+    /// Whether a value has this type
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            FieldType::Any => true,
+            FieldType::String => value.is_string(),
+            FieldType::Number => value.is_number(),
+            FieldType::Boolean => value.is_boolean(),
+            FieldType::List => value.is_array(),
+            FieldType::Date => value.as_str().is_some_and(|date| parse_date(date).is_ok()),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldType::Any => "any",
+            FieldType::String => "string",
+            FieldType::Number => "number",
+            FieldType::Boolean => "boolean",
+            FieldType::List => "list",
+            FieldType::Date => "date",
+        })
+    }
+}
+
+/// Check the custom fields of one post, page or case study
+///
+/// # Arguments
+///
+/// * `content` - What the front matter belongs to
+/// * `slug` - Slug of the post, page or case study, for the warnings
+/// * `fields` - Its custom fields
+/// * `config` - The declared custom fields
+///
+/// # Returns
+///
+/// A warning per unknown field, field of the wrong type and missing required
+/// field
+pub fn check(
+    content: ContentType,
+    slug: &str,
+    fields: &CustomFields,
+    config: &FrontMatterConfig,
+) -> Vec<String> {
+    let schema = config.schema(content);
+    let known: Vec<String> = content
+        .fields()
+        .iter()
+        .map(|name| name.to_string())
+        .chain(schema.keys().cloned())
+        .collect();
+
+    let mut warnings = Vec::new();
+    for (name, value) in fields.0.iter() {
+        match schema.get(name) {
+            Some(field) if !field.kind.accepts(value) => warnings.push(format!(
+                "{content} '{slug}': front matter field '{name}' should be a {}",
+                field.kind
+            )),
+            Some(_) => {}
+            None => {
+                let suggestion = closest(name, known.iter())
+                    .map(|known| format!(", did you mean '{known}'?"))
+                    .unwrap_or_default();
+                warnings.push(format!(
+                    "{content} '{slug}': unknown front matter field '{name}'{suggestion}"
+                ));
+            }
+        }
+    }
+    for (name, _) in schema
+        .iter()
+        .filter(|(name, field)| field.required && fields.get(name).is_none_or(Value::is_null))
+    {
+        warnings.push(format!(
+            "{content} '{slug}': missing required front matter field '{name}'"
+        ));
+    }
+    warnings
+}
+
+/// Print front matter warnings, if there are any
+pub fn print_warnings(warnings: &[String]) {
+    if warnings.is_empty() {
+        return;
+    }
+    println!("⚠️  Front matter ({}):", warnings.len());
+    for warning in warnings {
+        println!("   {warning}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(value: Value) -> CustomFields {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_custom_field_text() {
+        let fields = fields(json!({
+            "series": "Rust in production",
+            "part": 2,
+            "stack": ["Rust", "Postgres"],
+            "draft_notes": null
+        }));
+        assert_eq!(fields.text("series").unwrap(), "Rust in production");
+        assert_eq!(fields.text("part").unwrap(), "2");
+        assert_eq!(fields.text("stack").unwrap(), "Rust, Postgres");
+        assert_eq!(fields.text("draft_notes"), None);
+        assert_eq!(fields.text("missing"), None);
+    }
+
+    #[test]
+    fn test_check_front_matter() {
+        let config: FrontMatterConfig = serde_json::from_value(json!({
+            "posts": {
+                "series": { "type": "string", "required": true },
+                "part": { "type": "number" },
+                "event_date": { "type": "date" }
+            }
+        }))
+        .unwrap();
+
+        let valid = fields(json!({ "series": "Rust", "part": 1, "event_date": "2025-05-01" }));
+        assert!(check(ContentType::Post, "hello", &valid, &config).is_empty());
+
+        let invalid = fields(json!({ "part": "one", "excerpts": "Hi", "event_date": "May" }));
+        assert_eq!(
+            check(ContentType::Post, "hello", &invalid, &config),
+            vec![
+                "post 'hello': front matter field 'event_date' should be a date",
+                "post 'hello': unknown front matter field 'excerpts', did you mean 'excerpt'?",
+                "post 'hello': front matter field 'part' should be a number",
+                "post 'hello': missing required front matter field 'series'",
+            ]
+        );
+
+        // Fields declared for posts are unknown on pages
+        assert_eq!(check(ContentType::Page, "about", &valid, &config).len(), 3);
+    }
+}
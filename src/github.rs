@@ -4,18 +4,21 @@
 //!
 //! ## Features
 //!
-//! - **🔐 Automatic Authentication**: Automatically detects and uses available authentication methods
+//! - **🔐 Token Authentication**: Uses a token from the environment or config when available
 //! - **🧠 Smart Caching**: TTL-based caching system reduces API calls by 100%
-//! - **⚡ Performance**: Direct HTTP API calls via reqwest for maximum speed
-//! - **🔄 Automatic Fallback**: Multiple fallback strategies ensure reliability
+//! - **⚡ Performance**: Native REST client via reqwest, no external binaries required
+//! - **🔄 Conditional Requests**: ETag and Last-Modified revalidation so unchanged data doesn't count against rate limits
+//! - **⏳ Stale-While-Revalidate**: Optionally serve expired data and refresh it while the site builds
+//! - **📄 Pagination**: Follows `Link` headers so accounts with more than 100 repositories work
 //!
-//! ## Authentication Strategy
+//! ## Authentication
 //!
-//! The module automatically selects the best authentication method:
-//! 1. **GITHUB_TOKEN** - Automatically provided by GitHub Actions (5,000 req/hr)
+//! [`GitHubClient::from_config`] picks the token in this order:
+//! 1. **github_token** - From `config.toml` / `CV__GITHUB_TOKEN`, the `[secrets.github_token]` source, or **GITHUB_TOKEN** (provided by GitHub Actions)
 //! 2. **GH_TOKEN** - User-provided token via environment variable (5,000 req/hr)
-//! 3. **gh CLI** - Falls back to gh CLI if installed (5,000 req/hr if authenticated)
-//! 4. **Public API** - Unauthenticated requests as last resort (60 req/hr)
+//! 3. **Public API** - Unauthenticated requests as last resort (60 req/hr)
+//!
+//! The `gh` CLI is only used as a fallback when `github_gh_cli_fallback` is enabled.
 //!
 //! ## Cache Performance
 //!
@@ -27,27 +30,35 @@
 //! ## Usage
 //!
 //! ```rust,no_run
-//! use cv_generator::{github::fetch_projects_from_sources_cached, github_cache::GitHubCache};
-//! use cv_generator::cv_data::GitHubSource;
+//! use cv_generator::{github::GitHubClient, github_cache::GitHubCache};
+//! use cv_generator::unified_config::AppConfig;
 //! use im::Vector;
 //!
 //! # fn main() -> anyhow::Result<()> {
+//! let client = GitHubClient::from_config(&AppConfig::default());
 //! let mut cache = GitHubCache::load_or_default("cache/github_cache.json");
 //! let sources = Vector::new(); // Your GitHub sources
-//! let projects = fetch_projects_from_sources_cached(&sources, &mut cache)?;
+//! let projects = client.fetch_projects_from_sources_cached(&sources, &mut cache)?;
 //! cache.save("cache/github_cache.json")?;
 //! # Ok(())
 //! # }
 //! ```
 
 use anyhow::{Context, Result};
-use im::Vector;
+use chrono::{DateTime, Utc};
+use im::{HashMap, Vector};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::process::Command;
+use std::sync::OnceLock;
+use tokio::runtime::{Handle, Runtime, RuntimeFlavor};
 
+use crate::charts::{render_chart, ChartData, ChartType, Series};
 use crate::cv_data::{GitHubSource, Project};
-use crate::github_cache::GitHubCache;
+use crate::github_cache::{CacheEvent, CacheUsage, GitHubCache};
+use crate::secrets::{redact, Secret};
+use crate::unified_config::AppConfig;
 use crate::validation::validate_github_username;
 
 /// GitHub repository information
@@ -62,6 +73,8 @@ struct GitHubRepo {
     archived: bool,
     owner: Option<GitHubOwner>,
     stargazers_count: u32,
+    #[serde(default)]
+    pushed_at: Option<DateTime<Utc>>,
 }
 
 /// GitHub repository owner information
@@ -71,271 +84,1037 @@ struct GitHubOwner {
     avatar_url: String,
 }
 
-/// Authentication strategy for GitHub API
-enum AuthStrategy {
-    Token(String),
-    GhCli,
-    Public,
+/// Default GitHub REST API endpoint
+pub const GITHUB_API_BASE: &str = "https://api.github.com";
+
+/// Maximum number of pages followed for a single listing
+const MAX_PAGES: usize = 10;
+
+/// Maximum number of repositories whose languages are summed for [`GitHubStats`]
+const MAX_STATS_REPOS: usize = 50;
+
+/// Number of languages shown in the language chart
+const CHART_LANGUAGES: usize = 8;
+
+/// Aggregate GitHub activity of a user
+///
+/// Fetched for GitHub sources with `stats` set and rendered as a summary
+/// with a chart of the languages used across the user's repositories.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GitHubStats {
+    pub username: String,
+    pub public_repos: u32,
+    pub followers: u32,
+    /// Stars across the user's own (non-fork) repositories
+    pub total_stars: u32,
+    /// Commits authored, as counted by the search API
+    pub commits: u32,
+    pub pull_requests: u32,
+    pub issues: u32,
+    /// Languages by bytes of code, largest first
+    pub languages: Vector<LanguageShare>,
+}
+
+/// Share of a language in a user's repositories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageShare {
+    pub name: String,
+    pub bytes: u64,
+    /// Percentage of all bytes, rounded to one decimal
+    pub percent: f64,
 }
 
-/// Determine the best authentication strategy
-fn get_auth_strategy() -> AuthStrategy {
-    // Priority 1: GITHUB_TOKEN (provided by GitHub Actions)
-    if let Ok(token) = env::var("GITHUB_TOKEN") {
-        if !token.is_empty() {
-            return AuthStrategy::Token(token);
+impl GitHubStats {
+    /// Render the language breakdown as a bar chart
+    ///
+    /// # Returns
+    ///
+    /// The HTML of the chart, or an empty string when no languages are known
+    pub fn language_chart(&self) -> String {
+        let top: Vec<&LanguageShare> = self.languages.iter().take(CHART_LANGUAGES).collect();
+        if top.is_empty() {
+            return String::new();
         }
+        let data = ChartData {
+            labels: top.iter().map(|language| language.name.clone()).collect(),
+            series: vec![Series {
+                name: "%".to_string(),
+                values: top.iter().map(|language| language.percent).collect(),
+            }],
+        };
+        render_chart(ChartType::Bar, Some("Languages (% of code)"), &data).unwrap_or_default()
     }
+}
+
+/// Turn byte counts per language into shares, largest first
+///
+/// # Arguments
+///
+/// * `totals` - Bytes of code per language
+///
+/// # Returns
+///
+/// The languages with their percentage of all bytes
+pub fn language_shares(totals: &HashMap<String, u64>) -> Vector<LanguageShare> {
+    let sum: u64 = totals.values().sum();
+    let mut shares: Vec<LanguageShare> = totals
+        .iter()
+        .filter(|(_, bytes)| **bytes > 0)
+        .map(|(name, bytes)| LanguageShare {
+            name: name.clone(),
+            bytes: *bytes,
+            percent: (*bytes as f64 * 1000.0 / sum as f64).round() / 10.0,
+        })
+        .collect();
+    shares.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.name.cmp(&b.name)));
+    shares.into_iter().collect()
+}
 
-    // Priority 2: GH_TOKEN (user-provided)
-    if let Ok(token) = env::var("GH_TOKEN") {
-        if !token.is_empty() {
-            return AuthStrategy::Token(token);
+/// How cached GitHub data is refreshed once its TTL has expired
+///
+/// Set with `github_cache_refresh_strategy` in `config.toml`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RefreshStrategy {
+    /// Fetch expired entries before using them
+    #[default]
+    Lazy,
+    /// Use expired entries right away and revalidate them while the site builds
+    Background,
+    /// Revalidate every entry on every build, even valid ones
+    Eager,
+}
+
+impl RefreshStrategy {
+    /// Parse a refresh strategy name
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "lazy" => Ok(Self::Lazy),
+            "background" => Ok(Self::Background),
+            "eager" => Ok(Self::Eager),
+            other => anyhow::bail!(
+                "Unknown GitHub cache refresh strategy '{}', expected lazy, background or eager",
+                other
+            ),
         }
     }
+}
 
-    // Priority 3: gh CLI (if available)
-    if Command::new("gh").arg("--version").output().is_ok() {
-        return AuthStrategy::GhCli;
-    }
+/// A cache entry served stale, to revalidate in the background
+#[derive(Debug, Clone)]
+pub enum Revalidation {
+    Projects(GitHubSource),
+    Avatar(String),
+    Stats(String),
+}
 
-    // Priority 4: Public API (unauthenticated, 60 req/hr)
-    AuthStrategy::Public
+impl std::fmt::Display for Revalidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Revalidation::Projects(source) => match (&source.username, &source.organization) {
+                (Some(username), _) => write!(f, "projects for user: {}", username),
+                (None, Some(org_name)) => write!(f, "projects for org: {}", org_name),
+                (None, None) => write!(f, "projects"),
+            },
+            Revalidation::Avatar(username) => write!(f, "avatar for user: {}", username),
+            Revalidation::Stats(username) => write!(f, "stats for user: {}", username),
+        }
+    }
 }
 
-/// Execute a GitHub API call with automatic authentication fallback
+/// Native GitHub REST API client
 ///
-/// This helper encapsulates the common pattern of trying authenticated requests
-/// first, then falling back to gh CLI, then to public API.
-///
-/// # Arguments
-/// * `with_token` - Function to call with token authentication (receives token as argument)
-/// * `with_cli` - Function to call using gh CLI
-/// * `without_token` - Function to call without authentication (public API)
-fn with_auth_fallback<T, F1, F2, F3>(with_token: F1, with_cli: F2, without_token: F3) -> Result<T>
-where
-    F1: FnOnce(&str) -> Result<T>,
-    F2: Fn() -> Result<T>,
-    F3: Fn() -> Result<T>,
-{
-    match get_auth_strategy() {
-        AuthStrategy::Token(ref token) => {
-            println!("🔐 Using GitHub API with token authentication");
-            with_token(token)
-                .or_else(|e| {
-                    eprintln!("⚠️  Token auth failed: {}. Trying gh CLI...", e);
-                    with_cli()
-                })
-                .or_else(|e| {
-                    eprintln!("⚠️  gh CLI failed: {}. Trying public API...", e);
-                    without_token()
-                })
+/// Sends authenticated requests when a token is available, revalidates
+/// previously seen responses with `If-None-Match` and `If-Modified-Since`,
+/// and follows pagination. Optionally falls back to the `gh` CLI when a
+/// native request fails.
+#[derive(Debug, Clone)]
+pub struct GitHubClient {
+    http: reqwest::Client,
+    token: Option<Secret>,
+    api_base: String,
+    gh_cli_fallback: bool,
+    refresh: RefreshStrategy,
+    offline: bool,
+}
+
+impl GitHubClient {
+    /// Create a client with an optional token and no gh CLI fallback
+    pub fn new(token: Option<String>) -> Self {
+        Self::with_token(token.map(Secret::new))
+    }
+
+    /// Create a client with an optional secret token and no gh CLI fallback
+    pub fn with_token(token: Option<Secret>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            token: token.filter(|t| !t.is_empty()),
+            api_base: GITHUB_API_BASE.to_string(),
+            gh_cli_fallback: false,
+            offline: false,
+            refresh: RefreshStrategy::Lazy,
         }
-        AuthStrategy::GhCli => {
-            println!("🔧 Using GitHub CLI");
-            with_cli().or_else(|e| {
-                eprintln!("⚠️  gh CLI failed: {}. Trying public API...", e);
-                without_token()
-            })
+    }
+
+    /// Create a client using GITHUB_TOKEN or GH_TOKEN from the environment
+    pub fn from_env() -> Self {
+        Self::new(token_from_env())
+    }
+
+    /// Create a client from the application configuration
+    ///
+    /// Uses `github_token` (which already covers GITHUB_TOKEN), then GH_TOKEN,
+    /// enables the gh CLI fallback if `github_gh_cli_fallback` is set and
+    /// refreshes the cache with `github_cache_refresh_strategy`.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let token = config
+            .github_token
+            .clone()
+            .or_else(|| token_from_env().map(Secret::new));
+        let refresh =
+            RefreshStrategy::parse(&config.github_cache_refresh_strategy).unwrap_or_else(|e| {
+                eprintln!("⚠️  {}. Using lazy.", e);
+                RefreshStrategy::Lazy
+            });
+        Self::with_token(token)
+            .with_gh_cli_fallback(config.github_gh_cli_fallback)
+            .with_refresh_strategy(refresh)
+            .with_offline(config.is_offline())
+    }
+
+    /// Serve cached data only, however old, and never access the network
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
+
+    /// Set how expired cache entries are refreshed
+    pub fn with_refresh_strategy(self, refresh: RefreshStrategy) -> Self {
+        Self { refresh, ..self }
+    }
+
+    /// How expired cache entries are refreshed
+    pub fn refresh_strategy(&self) -> RefreshStrategy {
+        self.refresh
+    }
+
+    /// Enable or disable the gh CLI fallback
+    pub fn with_gh_cli_fallback(self, enabled: bool) -> Self {
+        Self {
+            gh_cli_fallback: enabled,
+            ..self
         }
-        AuthStrategy::Public => {
-            println!("🌐 Using public GitHub API (60 req/hr limit)");
-            without_token()
+    }
+
+    /// Use a different API base URL (e.g. GitHub Enterprise)
+    pub fn with_api_base(self, api_base: &str) -> Self {
+        Self {
+            api_base: api_base.trim_end_matches('/').to_string(),
+            ..self
         }
     }
-}
 
-/// Fetch repositories using GitHub API with token (async)
-async fn fetch_repos_with_api_async(
-    username: &str,
-    token: Option<&str>,
-) -> Result<Vec<GitHubRepo>> {
-    let url = format!("https://api.github.com/users/{}/repos", username);
-    let client = reqwest::Client::new();
-
-    let mut request = client
-        .get(&url)
-        .header("User-Agent", "cv-generator")
-        .query(&[
-            ("per_page", "100"),
-            ("sort", "updated"),
-            ("direction", "desc"),
-        ]);
+    /// Whether requests are sent with a token
+    pub fn is_authenticated(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Build a request with the API headers and, if there is one, the token
+    fn api_request(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .http
+            .request(method, url)
+            .header("User-Agent", "cv-generator")
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28");
+
+        match self.token {
+            Some(ref token) => {
+                request.header("Authorization", format!("Bearer {}", token.expose()))
+            }
+            None => request,
+        }
+    }
 
-    if let Some(token) = token {
-        request = request.header("Authorization", format!("Bearer {}", token));
+    /// Send a request that must succeed, without caching
+    async fn send_checked(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let response = request
+            .send()
+            .await
+            .context("Failed to send GitHub API request")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = redact(
+                &response.text().await.unwrap_or_default(),
+                self.token.as_ref(),
+            );
+            anyhow::bail!(
+                "GitHub API request failed with status {}:\n{}",
+                status,
+                error_body
+            );
+        }
+        Ok(response)
     }
 
-    let response = request
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch repositories for user '{}'", username))?;
+    /// Fetch a single page, revalidating with the stored validators if there are any
+    ///
+    /// # Returns
+    ///
+    /// The response body and the URL of the next page, if any
+    async fn fetch_page(
+        &self,
+        url: &str,
+        cache: &mut GitHubCache,
+    ) -> Result<(String, Option<String>)> {
+        let mut request = self.api_request(reqwest::Method::GET, url);
+
+        let cached = cache.get_response(url).cloned();
+        if let Some(ref cached) = cached {
+            if let Some(ref etag) = cached.etag {
+                request = request.header("If-None-Match", etag);
+            }
+            if let Some(ref last_modified) = cached.last_modified {
+                request = request.header("If-Modified-Since", last_modified);
+            }
+        }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_body = response.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!(
-            "GitHub API request failed with status {}:\n{}\n\
-             \n\
-             Possible causes:\n\
-             - User does not exist\n\
-             - Network connectivity issues\n\
-             - Rate limit exceeded ({})",
-            status,
-            error_body,
-            if token.is_some() {
-                "authenticated: 5,000 req/hr"
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", url))?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                println!("♻️  Not modified: {}", url);
+                cache.record(CacheEvent::NotModified);
+                return Ok((cached.body, cached.next));
+            }
+        }
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = redact(
+                &response.text().await.unwrap_or_default(),
+                self.token.as_ref(),
+            );
+            return Err(anyhow::anyhow!(
+                "GitHub API request failed with status {}:\n{}\n\
+                 \n\
+                 Possible causes:\n\
+                 - User or organization does not exist\n\
+                 - Network connectivity issues\n\
+                 - Rate limit exceeded ({})",
+                status,
+                error_body,
+                if self.is_authenticated() {
+                    "authenticated: 5,000 req/hr"
+                } else {
+                    "unauthenticated: 60 req/hr - set GITHUB_TOKEN for higher limits"
+                }
+            ));
+        }
+
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(String::from)
+        };
+        let etag = header("etag");
+        let last_modified = header("last-modified");
+        let next = header("link").as_deref().and_then(parse_next_link);
+
+        let body = response
+            .text()
+            .await
+            .context("Failed to read GitHub API response")?;
+
+        cache.record(CacheEvent::Modified);
+        if etag.is_some() || last_modified.is_some() {
+            cache.cache_response(url, etag, last_modified, body.clone(), next.clone());
+        }
+
+        Ok((body, next))
+    }
+
+    /// Fetch a JSON document from an API path
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        cache: &mut GitHubCache,
+    ) -> Result<T> {
+        let url = format!("{}{}", self.api_base, path);
+        let (body, _) = self.fetch_page(&url, cache).await?;
+        serde_json::from_str(&body).context("Failed to parse GitHub API response")
+    }
+
+    /// Fetch every page of a JSON array listing
+    async fn get_paginated<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        cache: &mut GitHubCache,
+    ) -> Result<Vec<T>> {
+        let mut next = Some(format!("{}{}", self.api_base, path));
+        let mut items = Vec::new();
+        let mut pages = 0;
+
+        while let Some(url) = next {
+            if pages == MAX_PAGES {
+                println!("⚠️  Stopping after {} pages for {}", MAX_PAGES, path);
+                break;
+            }
+
+            let (body, next_url) = self.fetch_page(&url, cache).await?;
+            let page: Vec<T> =
+                serde_json::from_str(&body).context("Failed to parse GitHub API response")?;
+
+            items.extend(page);
+            next = next_url;
+            pages += 1;
+        }
+
+        Ok(items)
+    }
+
+    /// Fetch repositories for a user or organization listing path
+    async fn repos(&self, path: &str, cache: &mut GitHubCache) -> Result<Vec<GitHubRepo>> {
+        let listing = format!("{}?per_page=100&sort=updated&direction=desc", path);
+        self.get_paginated::<GitHubRepo>(&listing, cache)
+            .await
+            .map(select_top_repos)
+    }
+
+    /// Fetch repositories for a listing path, falling back to the gh CLI if enabled
+    fn fetch_repos(&self, path: &str, cache: &mut GitHubCache) -> Result<Vec<GitHubRepo>> {
+        block_on(self.repos(path, cache)).or_else(|e| {
+            if self.gh_cli_fallback {
+                eprintln!("⚠️  GitHub API failed: {}. Trying gh CLI...", e);
+                fetch_repos_with_gh_cli(path).map(select_top_repos)
             } else {
-                "unauthenticated: 60 req/hr - set GITHUB_TOKEN for higher limits"
+                Err(e)
             }
-        ));
+        })
     }
 
-    let mut repos: Vec<GitHubRepo> = response
-        .json()
-        .await
-        .context("Failed to parse GitHub API response")?;
+    /// Fetch the projects of a source, without the gh CLI fallback
+    async fn source_projects(
+        &self,
+        source: &GitHubSource,
+        cache: &mut GitHubCache,
+    ) -> Result<Vector<Project>> {
+        let path = match (&source.username, &source.organization) {
+            (Some(username), _) => {
+                validate_github_username(username)
+                    .with_context(|| format!("Invalid GitHub username: {}", username))?;
+                format!("/users/{}/repos", username)
+            }
+            (None, Some(org_name)) => format!("/orgs/{}/repos", org_name),
+            (None, None) => return Ok(Vector::new()),
+        };
+        self.repos(&path, cache)
+            .await
+            .map(convert_repos_to_projects)
+    }
 
-    // Filter and sort
-    repos.retain(|repo| !repo.fork && !repo.archived && repo.description.is_some());
-    repos.sort_by(|a, b| b.stargazers_count.cmp(&a.stargazers_count));
-    repos.truncate(10);
+    /// Fetch the public projects of a user
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - GitHub username
+    /// * `cache` - Cache holding ETags of earlier responses
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a Vector of Project structs
+    pub fn fetch_user_projects(
+        &self,
+        username: &str,
+        cache: &mut GitHubCache,
+    ) -> Result<Vector<Project>> {
+        validate_github_username(username)
+            .with_context(|| format!("Invalid GitHub username: {}", username))?;
+
+        self.fetch_repos(&format!("/users/{}/repos", username), cache)
+            .map(convert_repos_to_projects)
+    }
 
-    Ok(repos)
-}
+    /// Fetch the public projects of an organization
+    ///
+    /// # Arguments
+    ///
+    /// * `org_name` - GitHub organization name
+    /// * `cache` - Cache holding ETags of earlier responses
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a Vector of Project structs
+    pub fn fetch_org_projects(
+        &self,
+        org_name: &str,
+        cache: &mut GitHubCache,
+    ) -> Result<Vector<Project>> {
+        self.fetch_repos(&format!("/orgs/{}/repos", org_name), cache)
+            .map(convert_repos_to_projects)
+    }
 
-/// Fetch repositories using GitHub API with token (blocking wrapper)
-fn fetch_repos_with_api(username: &str, token: Option<&str>) -> Result<Vec<GitHubRepo>> {
-    tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(fetch_repos_with_api_async(username, token))
-    })
-}
+    /// Fetch a user's avatar URL
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - GitHub username
+    /// * `cache` - Cache holding ETags of earlier responses
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the avatar URL string
+    pub fn fetch_avatar(&self, username: &str, cache: &mut GitHubCache) -> Result<String> {
+        validate_github_username(username)
+            .with_context(|| format!("Invalid GitHub username: {}", username))?;
+
+        block_on(self.avatar(username, cache)).or_else(|e| {
+            if self.gh_cli_fallback {
+                eprintln!("⚠️  GitHub API failed: {}. Trying gh CLI...", e);
+                fetch_avatar_with_gh_cli(username)
+            } else {
+                Err(e)
+            }
+        })
+    }
 
-/// Fetch repositories using gh CLI (fallback)
-fn fetch_repos_with_gh_cli(username: &str) -> Result<Vec<GitHubRepo>> {
-    let output = Command::new("gh")
-        .args([
-            "api",
-            &format!("/users/{}/repos", username),
-            "--jq",
-            "map(select(.private == false and .fork == false)) | sort_by(.updated_at) | reverse | .[0:10]",
-        ])
-        .output()
-        .context("Failed to execute 'gh' command")?;
+    /// Fetch a user's avatar URL, without the gh CLI fallback
+    async fn avatar(&self, username: &str, cache: &mut GitHubCache) -> Result<String> {
+        #[derive(Deserialize)]
+        struct UserResponse {
+            avatar_url: String,
+        }
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("GitHub CLI request failed: {}", error));
+        self.get_json::<UserResponse>(&format!("/users/{}", username), cache)
+            .await
+            .map(|user| user.avatar_url)
     }
 
-    let json_str =
-        String::from_utf8(output.stdout).context("Invalid UTF-8 in gh command output")?;
+    /// Use a cache entry or fetch it, according to the refresh strategy
+    ///
+    /// Offline clients use any cached entry and fail when there is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - The cache
+    /// * `entry` - The cached data and whether it is still valid, if cached
+    /// * `revalidation` - What the entry holds, revalidated later if served stale
+    /// * `fetch` - Fetches the data and stores it in the cache
+    ///
+    /// # Returns
+    ///
+    /// The cached or fetched data
+    fn cached<T>(
+        &self,
+        cache: &mut GitHubCache,
+        entry: Option<(T, bool)>,
+        revalidation: Revalidation,
+        fetch: impl FnOnce(&mut GitHubCache) -> Result<T>,
+    ) -> Result<T> {
+        if self.offline {
+            let (data, valid) = entry
+                .with_context(|| format!("No cached {} for an offline build", revalidation))?;
+            println!("📦 Using cached {} (offline)", revalidation);
+            cache.record(if valid {
+                CacheEvent::Hit
+            } else {
+                CacheEvent::Stale
+            });
+            return Ok(data);
+        }
 
-    serde_json::from_str(&json_str).context("Failed to parse GitHub API response")
-}
+        match (entry, self.refresh) {
+            (Some((data, true)), RefreshStrategy::Lazy | RefreshStrategy::Background) => {
+                println!("✅ Using cached {}", revalidation);
+                cache.record(CacheEvent::Hit);
+                Ok(data)
+            }
+            (Some((data, false)), RefreshStrategy::Background) => {
+                println!("⏳ Using stale {}, revalidating", revalidation);
+                cache.record(CacheEvent::Stale);
+                cache.queue_revalidation(revalidation);
+                Ok(data)
+            }
+            _ => {
+                println!("🌐 Fetching fresh {}", revalidation);
+                cache.record(CacheEvent::Miss);
+                fetch(cache)
+            }
+        }
+    }
 
-/// Fetches public GitHub repositories for a user
-///
-/// # Arguments
-///
-/// * `username` - GitHub username
-///
-/// # Returns
-///
-/// A Result containing a Vector of Project structs
-///
-/// # Authentication Strategy
-///
-/// Uses automatic fallback in this order:
-/// 1. GITHUB_TOKEN environment variable (GitHub Actions provides this)
-/// 2. GH_TOKEN environment variable (user-provided)
-/// 3. gh CLI (if installed and authenticated)
-/// 4. Public API (unauthenticated, 60 req/hr limit)
-///
-/// # Rate Limits
-///
-/// - Authenticated: 5,000 requests per hour
-/// - Unauthenticated: 60 requests per hour
-pub fn fetch_github_projects(username: &str) -> Result<Vector<Project>> {
-    // Validate username format before making API call
-    validate_github_username(username)
-        .with_context(|| format!("Invalid GitHub username: {}", username))?;
+    /// Cache-aware fetch of projects from a list of sources
+    ///
+    /// Valid TTL cache entries are used as-is; expired or missing entries are
+    /// fetched (and revalidated with ETags where possible), or served stale
+    /// with the `background` refresh strategy.
+    pub fn fetch_projects_from_sources_cached(
+        &self,
+        sources: &Vector<GitHubSource>,
+        cache: &mut GitHubCache,
+    ) -> Result<Vector<Project>> {
+        let mut all_projects = Vector::new();
+
+        for source in sources.iter() {
+            let Some(key) = projects_cache_key(source) else {
+                continue;
+            };
+            let entry = cache
+                .projects
+                .get(&key)
+                .map(|entry| (entry.data.clone(), entry.is_valid()));
+
+            let projects = self.cached(
+                cache,
+                entry,
+                Revalidation::Projects(source.clone()),
+                |cache| {
+                    let projects = match (&source.username, &source.organization) {
+                        (Some(username), _) => self.fetch_user_projects(username, cache)?,
+                        (None, Some(org_name)) => self.fetch_org_projects(org_name, cache)?,
+                        (None, None) => Vector::new(),
+                    };
+                    cache.cache_projects(&key, projects.clone());
+                    Ok(projects)
+                },
+            )?;
+
+            all_projects.extend(projects);
+        }
 
-    let repos = with_auth_fallback(
-        |token| fetch_repos_with_api(username, Some(token)),
-        || fetch_repos_with_gh_cli(username),
-        || fetch_repos_with_api(username, None),
-    )?;
+        Ok(all_projects)
+    }
 
-    Ok(convert_repos_to_projects(repos))
-}
+    /// Count search results for a query, without fetching them
+    async fn search_count(&self, kind: &str, query: &str, cache: &mut GitHubCache) -> Result<u32> {
+        #[derive(Deserialize)]
+        struct SearchResponse {
+            total_count: u32,
+        }
 
-/// Fetch organization repositories using GitHub API with token (async)
-async fn fetch_org_repos_with_api_async(
-    org_name: &str,
-    token: Option<&str>,
-) -> Result<Vec<GitHubRepo>> {
-    let url = format!("https://api.github.com/orgs/{}/repos", org_name);
-    let client = reqwest::Client::new();
-
-    let mut request = client
-        .get(&url)
-        .header("User-Agent", "cv-generator")
-        .query(&[
-            ("per_page", "100"),
-            ("sort", "updated"),
-            ("direction", "desc"),
-        ]);
+        let path = format!("/search/{}?q={}&per_page=1", kind, query);
+        self.get_json::<SearchResponse>(&path, cache)
+            .await
+            .map(|response| response.total_count)
+    }
+
+    /// Fetch aggregate stats of a user
+    ///
+    /// Sums stars over the user's own repositories and their languages over
+    /// the [`MAX_STATS_REPOS`] most recently updated ones, and counts
+    /// authored commits, pull requests and issues with the search API.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - GitHub username
+    /// * `cache` - Cache holding ETags of earlier responses
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the user's stats
+    pub fn fetch_stats(&self, username: &str, cache: &mut GitHubCache) -> Result<GitHubStats> {
+        validate_github_username(username)
+            .with_context(|| format!("Invalid GitHub username: {}", username))?;
+
+        block_on(self.stats(username, cache))
+    }
+
+    /// Fetch aggregate stats of a validated username
+    async fn stats(&self, username: &str, cache: &mut GitHubCache) -> Result<GitHubStats> {
+        #[derive(Deserialize)]
+        struct UserResponse {
+            public_repos: u32,
+            followers: u32,
+        }
+
+        let user: UserResponse = self
+            .get_json(&format!("/users/{}", username), cache)
+            .await?;
+        let repos: Vec<GitHubRepo> = self
+            .get_paginated(
+                &format!(
+                    "/users/{}/repos?per_page=100&sort=updated&direction=desc",
+                    username
+                ),
+                cache,
+            )
+            .await?;
+        let own_repos: Vec<&GitHubRepo> = repos.iter().filter(|repo| !repo.fork).collect();
+
+        let mut totals = HashMap::new();
+        for repo in own_repos.iter().take(MAX_STATS_REPOS) {
+            let languages: HashMap<String, u64> = self
+                .get_json(
+                    &format!("/repos/{}/{}/languages", username, repo.name),
+                    cache,
+                )
+                .await?;
+            for (language, bytes) in languages {
+                *totals.entry(language).or_insert(0) += bytes;
+            }
+        }
 
-    if let Some(token) = token {
-        request = request.header("Authorization", format!("Bearer {}", token));
+        Ok(GitHubStats {
+            username: username.to_string(),
+            public_repos: user.public_repos,
+            followers: user.followers,
+            total_stars: own_repos.iter().map(|repo| repo.stargazers_count).sum(),
+            commits: self
+                .search_count("commits", &format!("author:{}", username), cache)
+                .await?,
+            pull_requests: self
+                .search_count("issues", &format!("author:{}+type:pr", username), cache)
+                .await?,
+            issues: self
+                .search_count("issues", &format!("author:{}+type:issue", username), cache)
+                .await?,
+            languages: language_shares(&totals),
+        })
     }
 
-    let response = request.send().await.with_context(|| {
-        format!(
-            "Failed to fetch repositories for organization '{}'",
-            org_name
+    /// Cache-aware fetch of the stats of the first source with `stats` set
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - GitHub sources from the CV data
+    /// * `cache` - Cache holding stats and ETags of earlier responses
+    ///
+    /// # Returns
+    ///
+    /// The stats, or `None` when no user source asks for them
+    pub fn fetch_stats_from_sources_cached(
+        &self,
+        sources: &Vector<GitHubSource>,
+        cache: &mut GitHubCache,
+    ) -> Result<Option<GitHubStats>> {
+        let Some(username) = sources
+            .iter()
+            .filter(|source| source.stats)
+            .find_map(|source| source.username.as_ref())
+        else {
+            return Ok(None);
+        };
+
+        let entry = cache
+            .stats
+            .get(username)
+            .map(|entry| (entry.data.clone(), entry.is_valid()));
+        self.cached(
+            cache,
+            entry,
+            Revalidation::Stats(username.clone()),
+            |cache| {
+                let stats = self.fetch_stats(username, cache)?;
+                cache.cache_stats(username, stats.clone());
+                Ok(stats)
+            },
         )
-    })?;
+        .map(Some)
+    }
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_body = response.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!(
-            "GitHub API request failed with status {}:\n{}",
-            status,
-            error_body
-        ));
+    /// Cache-aware fetch of a user's avatar URL
+    pub fn fetch_avatar_cached(&self, username: &str, cache: &mut GitHubCache) -> Result<String> {
+        let entry = cache
+            .avatars
+            .get(username)
+            .map(|entry| (entry.data.clone(), entry.is_valid()));
+        self.cached(
+            cache,
+            entry,
+            Revalidation::Avatar(username.to_string()),
+            |cache| {
+                let avatar = self.fetch_avatar(username, cache)?;
+                cache.cache_avatar(username, avatar.clone());
+                Ok(avatar)
+            },
+        )
+    }
+
+    /// Revalidate the entries served stale while the site is being built
+    ///
+    /// Works on a copy of the cache in a background task. Merge the copy it
+    /// returns with [`GitHubCache::merge`] before saving, so the next build
+    /// uses the fresh data. Entries that fail to revalidate stay stale.
+    ///
+    /// # Arguments
+    ///
+    /// * `cache` - Cache the stale entries were served from
+    ///
+    /// # Returns
+    ///
+    /// The task, or `None` if no entry was served stale
+    pub fn spawn_revalidation(
+        &self,
+        cache: &mut GitHubCache,
+    ) -> Option<tokio::task::JoinHandle<GitHubCache>> {
+        let pending = cache.take_pending();
+        if pending.is_empty() {
+            return None;
+        }
+
+        println!(
+            "🔄 Revalidating {} stale cache entries in the background",
+            pending.len()
+        );
+        let client = self.clone();
+        let mut background = GitHubCache {
+            usage: CacheUsage::default(),
+            ..cache.clone()
+        };
+        Some(tokio::spawn(async move {
+            for revalidation in pending {
+                if let Err(e) = client.revalidate(&revalidation, &mut background).await {
+                    eprintln!("⚠️  Failed to revalidate {}: {}", revalidation, e);
+                }
+            }
+            background
+        }))
+    }
+
+    /// Fetch a stale entry again and store it in the cache
+    async fn revalidate(&self, revalidation: &Revalidation, cache: &mut GitHubCache) -> Result<()> {
+        match revalidation {
+            Revalidation::Projects(source) => {
+                let projects = self.source_projects(source, cache).await?;
+                if let Some(key) = projects_cache_key(source) {
+                    cache.cache_projects(&key, projects);
+                }
+            }
+            Revalidation::Avatar(username) => {
+                let avatar = self.avatar(username, cache).await?;
+                cache.cache_avatar(username, avatar);
+            }
+            Revalidation::Stats(username) => {
+                let stats = self.stats(username, cache).await?;
+                cache.cache_stats(username, stats);
+            }
+        }
+        Ok(())
     }
+}
 
-    let mut repos: Vec<GitHubRepo> = response
-        .json()
-        .await
-        .context("Failed to parse GitHub API response")?;
+/// Cache key of the projects of a source: the username, or `org:` and the
+/// organization name
+fn projects_cache_key(source: &GitHubSource) -> Option<String> {
+    match (&source.username, &source.organization) {
+        (Some(username), _) => Some(username.clone()),
+        (None, Some(org_name)) => Some(format!("org:{}", org_name)),
+        (None, None) => None,
+    }
+}
 
-    // Filter and sort
-    repos.retain(|repo| !repo.fork && !repo.archived && repo.description.is_some());
-    repos.sort_by(|a, b| b.stargazers_count.cmp(&a.stargazers_count));
-    repos.truncate(10);
+/// A release created with [`GitHubClient::create_release`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct GitHubRelease {
+    /// Release id
+    pub id: u64,
+    /// Tag the release was created for
+    pub tag_name: String,
+    /// Page of the release on GitHub
+    pub html_url: String,
+    /// Asset upload URL template, e.g. `https://uploads.github.com/.../assets{?name,label}`
+    pub upload_url: String,
+}
 
-    Ok(repos)
+impl GitHubClient {
+    /// Whether a release exists for a tag
+    ///
+    /// # Arguments
+    ///
+    /// * `repo` - Repository as `owner/name`
+    /// * `tag` - Tag name
+    ///
+    /// # Returns
+    ///
+    /// Whether the repository has a release for the tag
+    pub async fn release_exists(&self, repo: &str, tag: &str) -> Result<bool> {
+        let url = format!("{}/repos/{}/releases/tags/{}", self.api_base, repo, tag);
+        let response = self
+            .api_request(reqwest::Method::GET, &url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", url))?;
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status if status.is_success() => Ok(true),
+            status => anyhow::bail!("GitHub API request failed with status {}", status),
+        }
+    }
+
+    /// Create a release, and its tag if the tag doesn't exist yet
+    ///
+    /// # Arguments
+    ///
+    /// * `repo` - Repository as `owner/name`
+    /// * `tag` - Tag name
+    /// * `target` - Commit the tag is created at
+    /// * `body` - Release notes (markdown)
+    /// * `draft` - Whether to create an unpublished draft
+    ///
+    /// # Returns
+    ///
+    /// The created release
+    pub async fn create_release(
+        &self,
+        repo: &str,
+        tag: &str,
+        target: &str,
+        body: &str,
+        draft: bool,
+    ) -> Result<GitHubRelease> {
+        if !self.is_authenticated() {
+            anyhow::bail!("Creating a release needs a token; set GITHUB_TOKEN");
+        }
+
+        let url = format!("{}/repos/{}/releases", self.api_base, repo);
+        let request = self
+            .api_request(reqwest::Method::POST, &url)
+            .json(&serde_json::json!({
+                "tag_name": tag,
+                "target_commitish": target,
+                "name": tag,
+                "body": body,
+                "draft": draft,
+            }));
+        self.send_checked(request)
+            .await
+            .with_context(|| format!("Failed to create release {} in {}", tag, repo))?
+            .json()
+            .await
+            .context("Failed to parse GitHub release")
+    }
+
+    /// Upload a file to a release
+    ///
+    /// # Arguments
+    ///
+    /// * `release` - Release to attach the file to
+    /// * `name` - Asset file name
+    /// * `content_type` - MIME type of the file
+    /// * `content` - File content
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    pub async fn upload_release_asset(
+        &self,
+        release: &GitHubRelease,
+        name: &str,
+        content_type: &str,
+        content: Vec<u8>,
+    ) -> Result<()> {
+        let upload_url = release
+            .upload_url
+            .split('{')
+            .next()
+            .unwrap_or(&release.upload_url);
+        let request = self
+            .api_request(reqwest::Method::POST, upload_url)
+            .query(&[("name", name)])
+            .header("Content-Type", content_type)
+            .body(content);
+        self.send_checked(request)
+            .await
+            .with_context(|| format!("Failed to upload {}", name))?;
+        Ok(())
+    }
+}
+
+/// Read a token from GITHUB_TOKEN or GH_TOKEN
+fn token_from_env() -> Option<String> {
+    ["GITHUB_TOKEN", "GH_TOKEN"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|token| !token.is_empty())
+}
+
+/// Run a future to completion from synchronous code
+///
+/// Inside a multi-threaded tokio runtime, the current worker blocks in place.
+/// Outside a runtime, and inside a current-thread runtime, which can't block
+/// in place, the future runs on a private runtime instead, on its own thread
+/// in the latter case so the caller's runtime isn't re-entered.
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: std::future::Future + Send,
+    F::Output: Send,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(future))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| fallback_runtime().block_on(future))
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+        }),
+        Err(_) => fallback_runtime().block_on(future),
+    }
+}
+
+/// Runtime for [`block_on`] calls made outside a multi-threaded runtime
+///
+/// It's kept for the whole process, so pooled connections stay usable.
+fn fallback_runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("Failed to start the tokio runtime")
+    })
 }
 
-/// Fetch organization repositories using GitHub API with token (blocking wrapper)
-fn fetch_org_repos_with_api(org_name: &str, token: Option<&str>) -> Result<Vec<GitHubRepo>> {
-    tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(fetch_org_repos_with_api_async(org_name, token))
+/// Extract the `rel="next"` URL from a GitHub `Link` header
+///
+/// # Arguments
+///
+/// * `header` - Value of the Link header
+///
+/// # Returns
+///
+/// The URL of the next page, if present
+pub fn parse_next_link(header: &str) -> Option<String> {
+    header.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == r#"rel="next""#)
+            .then(|| {
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
     })
 }
 
-/// Fetch organization repositories using gh CLI (fallback)
-fn fetch_org_repos_with_gh_cli(org_name: &str) -> Result<Vec<GitHubRepo>> {
+/// Drop forks, archived and undescribed repos, most starred first
+///
+/// Which of them are shown is up to [`crate::project_curation`].
+fn select_top_repos(mut repos: Vec<GitHubRepo>) -> Vec<GitHubRepo> {
+    repos.retain(|repo| !repo.fork && !repo.archived && repo.description.is_some());
+    repos.sort_by_key(|b| std::cmp::Reverse(b.stargazers_count));
+    repos
+}
+
+/// Fetch repositories using gh CLI (fallback)
+///
+/// # Arguments
+///
+/// * `path` - API path such as `/users/<name>/repos` or `/orgs/<name>/repos`
+fn fetch_repos_with_gh_cli(path: &str) -> Result<Vec<GitHubRepo>> {
     let output = Command::new("gh")
         .args([
             "api",
-            &format!("/orgs/{}/repos", org_name),
+            "--paginate",
+            path,
             "--jq",
-            "map(select(.private == false and .fork == false)) | sort_by(.updated_at) | reverse | .[0:10]",
+            ".[] | select(.private == false)",
         ])
         .output()
         .context("Failed to execute 'gh' command")?;
@@ -348,7 +1127,32 @@ fn fetch_org_repos_with_gh_cli(org_name: &str) -> Result<Vec<GitHubRepo>> {
     let json_str =
         String::from_utf8(output.stdout).context("Invalid UTF-8 in gh command output")?;
 
-    serde_json::from_str(&json_str).context("Failed to parse GitHub API response")
+    // --jq emits one repository object per line
+    json_str
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).context("Failed to parse GitHub API response"))
+        .collect()
+}
+
+/// Fetches public GitHub repositories for a user
+///
+/// # Arguments
+///
+/// * `username` - GitHub username
+///
+/// # Returns
+///
+/// A Result containing a Vector of Project structs
+///
+/// Uses a [`GitHubClient`] authenticated from GITHUB_TOKEN or GH_TOKEN.
+///
+/// # Rate Limits
+///
+/// - Authenticated: 5,000 requests per hour
+/// - Unauthenticated: 60 requests per hour
+pub fn fetch_github_projects(username: &str) -> Result<Vector<Project>> {
+    GitHubClient::from_env().fetch_user_projects(username, &mut GitHubCache::default())
 }
 
 /// Fetches public GitHub repositories for an organization
@@ -361,15 +1165,9 @@ fn fetch_org_repos_with_gh_cli(org_name: &str) -> Result<Vec<GitHubRepo>> {
 ///
 /// A Result containing a Vector of Project structs
 ///
-/// Uses the same authentication strategy as fetch_github_projects
+/// Uses the same authentication as fetch_github_projects
 pub fn fetch_github_org_projects(org_name: &str) -> Result<Vector<Project>> {
-    let repos = with_auth_fallback(
-        |token| fetch_org_repos_with_api(org_name, Some(token)),
-        || fetch_org_repos_with_gh_cli(org_name),
-        || fetch_org_repos_with_api(org_name, None),
-    )?;
-
-    Ok(convert_repos_to_projects(repos))
+    GitHubClient::from_env().fetch_org_projects(org_name, &mut GitHubCache::default())
 }
 
 /// Fetches public GitHub repositories for both a user and an organization
@@ -395,10 +1193,12 @@ pub fn fetch_all_github_projects(username: &str, org_name: &str) -> Result<Vecto
         GitHubSource {
             username: Some(username.to_string()),
             organization: None,
+            stats: false,
         },
         GitHubSource {
             username: None,
             organization: Some(org_name.to_string()),
+            stats: false,
         },
     ]);
 
@@ -490,6 +1290,13 @@ fn convert_repos_to_projects(repos: Vec<GitHubRepo>) -> Vector<Project> {
                 stars: Some(repo.stargazers_count),
                 owner_username,
                 owner_avatar,
+                screenshot: None,
+                registry: None,
+                package_url: None,
+                version: None,
+                downloads: None,
+                updated_at: repo.pushed_at,
+                case_study: None,
                 language: None,
                 language_icon: None,
                 display_name: None,
@@ -561,52 +1368,6 @@ pub fn fetch_projects_from_sources(sources: &Vector<GitHubSource>) -> Result<Vec
     Ok(all_projects)
 }
 
-/// Fetch avatar using GitHub API with token (async)
-async fn fetch_avatar_with_api_async(username: &str, token: Option<&str>) -> Result<String> {
-    let url = format!("https://api.github.com/users/{}", username);
-    let client = reqwest::Client::new();
-
-    let mut request = client.get(&url).header("User-Agent", "cv-generator");
-
-    if let Some(token) = token {
-        request = request.header("Authorization", format!("Bearer {}", token));
-    }
-
-    let response = request
-        .send()
-        .await
-        .with_context(|| format!("Failed to fetch avatar for user '{}'", username))?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_body = response.text().await.unwrap_or_default();
-        return Err(anyhow::anyhow!(
-            "GitHub API request failed with status {}:\n{}",
-            status,
-            error_body
-        ));
-    }
-
-    #[derive(Deserialize)]
-    struct UserResponse {
-        avatar_url: String,
-    }
-
-    let user: UserResponse = response
-        .json()
-        .await
-        .context("Failed to parse GitHub user response")?;
-
-    Ok(user.avatar_url)
-}
-
-/// Fetch avatar using GitHub API with token (blocking wrapper)
-fn fetch_avatar_with_api(username: &str, token: Option<&str>) -> Result<String> {
-    tokio::task::block_in_place(|| {
-        tokio::runtime::Handle::current().block_on(fetch_avatar_with_api_async(username, token))
-    })
-}
-
 /// Fetch avatar using gh CLI (fallback)
 fn fetch_avatar_with_gh_cli(username: &str) -> Result<String> {
     let output = Command::new("gh")
@@ -646,17 +1407,9 @@ fn fetch_avatar_with_gh_cli(username: &str) -> Result<String> {
 ///
 /// A Result containing the avatar URL string
 ///
-/// Uses the same authentication strategy as fetch_github_projects
+/// Uses the same authentication as fetch_github_projects
 pub fn fetch_github_avatar(username: &str) -> Result<String> {
-    // Validate username format before making API call
-    validate_github_username(username)
-        .with_context(|| format!("Invalid GitHub username: {}", username))?;
-
-    with_auth_fallback(
-        |token| fetch_avatar_with_api(username, Some(token)),
-        || fetch_avatar_with_gh_cli(username),
-        || fetch_avatar_with_api(username, None),
-    )
+    GitHubClient::from_env().fetch_avatar(username, &mut GitHubCache::default())
 }
 
 /// Cache-aware version of fetch_projects_from_sources
@@ -667,71 +1420,123 @@ pub fn fetch_projects_from_sources_cached(
     sources: &Vector<GitHubSource>,
     cache: &mut GitHubCache,
 ) -> Result<Vector<Project>> {
-    let mut all_projects = Vector::new();
+    GitHubClient::from_env().fetch_projects_from_sources_cached(sources, cache)
+}
 
-    for source in sources.iter() {
-        let projects = if let Some(username) = &source.username {
-            // Check cache first
-            if let Some(cached_projects) = cache.get_projects(username) {
-                println!(
-                    "✅ Using cached projects for user: {} ({} projects)",
-                    username,
-                    cached_projects.len()
-                );
-                cached_projects.clone()
-            } else {
-                // Cache miss - fetch from API
-                println!("🌐 Fetching fresh projects for user: {}", username);
-                let fresh_projects = fetch_github_projects(username)?;
+/// Cache-aware version of fetch_github_avatar
+///
+/// This function checks the cache first before making API calls.
+pub fn fetch_github_avatar_cached(username: &str, cache: &mut GitHubCache) -> Result<String> {
+    GitHubClient::from_env().fetch_avatar_cached(username, cache)
+}
 
-                // Cache the results
-                cache.cache_projects(username, fresh_projects.clone());
-                fresh_projects
-            }
-        } else if let Some(org_name) = &source.organization {
-            // For organizations, we use username as cache key for simplicity
-            let cache_key = format!("org:{}", org_name);
-
-            if let Some(cached_projects) = cache.get_projects(&cache_key) {
-                println!(
-                    "✅ Using cached projects for org: {} ({} projects)",
-                    org_name,
-                    cached_projects.len()
-                );
-                cached_projects.clone()
-            } else {
-                println!("🌐 Fetching fresh projects for org: {}", org_name);
-                let fresh_projects = fetch_github_org_projects(org_name)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github_cache::GitHubCacheEntry;
 
-                cache.cache_projects(&cache_key, fresh_projects.clone());
-                fresh_projects
-            }
-        } else {
-            Vector::new()
+    #[test]
+    fn test_block_on_without_runtime() {
+        assert_eq!(block_on(async { 42 }), 42);
+        assert_eq!(block_on(async { block_on(async { 7 }) }), 7);
+    }
+
+    #[tokio::test]
+    async fn test_block_on_in_current_thread_runtime() {
+        let value = block_on(async {
+            tokio::task::yield_now().await;
+            42
+        });
+        assert_eq!(value, 42);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_in_multi_thread_runtime() {
+        assert_eq!(block_on(async { 42 }), 42);
+    }
+
+    #[test]
+    fn test_parse_next_link() {
+        let header = r#"<https://api.github.com/user/1/repos?page=2>; rel="next", <https://api.github.com/user/1/repos?page=5>; rel="last""#;
+        assert_eq!(
+            parse_next_link(header),
+            Some("https://api.github.com/user/1/repos?page=2".to_string())
+        );
+
+        let last_page = r#"<https://api.github.com/user/1/repos?page=1>; rel="first", <https://api.github.com/user/1/repos?page=4>; rel="prev""#;
+        assert_eq!(parse_next_link(last_page), None);
+    }
+
+    #[test]
+    fn test_select_top_repos() {
+        let repo = |name: &str, stars: u32, fork: bool| GitHubRepo {
+            name: name.to_string(),
+            description: Some("A project".to_string()),
+            html_url: format!("https://github.com/user/{}", name),
+            topics: None,
+            language: None,
+            fork,
+            archived: false,
+            owner: None,
+            stargazers_count: stars,
+            pushed_at: None,
         };
 
-        all_projects.extend(projects);
+        let repos = (0..12)
+            .map(|i| repo(&format!("repo{}", i), i, false))
+            .chain([repo("forked", 100, true)])
+            .collect();
+
+        let selected = select_top_repos(repos);
+        assert_eq!(selected.len(), 12);
+        assert_eq!(selected[0].name, "repo11");
+        assert!(selected.iter().all(|r| !r.fork));
     }
 
-    Ok(all_projects)
-}
+    #[test]
+    fn test_language_shares() {
+        let totals = HashMap::from_iter([
+            ("Rust".to_string(), 7500),
+            ("Shell".to_string(), 500),
+            ("HTML".to_string(), 2000),
+            ("Empty".to_string(), 0),
+        ]);
 
-/// Cache-aware version of fetch_github_avatar
-///
-/// This function checks the cache first before making API calls.
-pub fn fetch_github_avatar_cached(username: &str, cache: &mut GitHubCache) -> Result<String> {
-    // Check cache first
-    if let Some(cached_avatar) = cache.get_avatar(username) {
-        println!("✅ Using cached avatar for user: {}", username);
-        Ok(cached_avatar.to_string())
-    } else {
-        // Cache miss - fetch from API
-        println!("🌐 Fetching fresh avatar for user: {}", username);
-        let fresh_avatar = fetch_github_avatar(username)?;
-
-        // Cache the result
-        cache.cache_avatar(username, fresh_avatar.clone());
-
-        Ok(fresh_avatar)
+        let shares = language_shares(&totals);
+        let names: Vec<&str> = shares.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Rust", "HTML", "Shell"]);
+        assert_eq!(shares[0].percent, 75.0);
+        assert_eq!(shares[2].percent, 5.0);
+
+        let stats = GitHubStats {
+            languages: shares,
+            ..GitHubStats::default()
+        };
+        assert!(stats.language_chart().contains("Rust"));
+        assert!(GitHubStats::default().language_chart().is_empty());
+    }
+
+    #[test]
+    fn test_client_token_handling() {
+        assert!(!GitHubClient::new(None).is_authenticated());
+        assert!(!GitHubClient::new(Some(String::new())).is_authenticated());
+        assert!(GitHubClient::new(Some("token".to_string())).is_authenticated());
+    }
+
+    #[test]
+    fn test_offline_uses_cache_only() {
+        let client = GitHubClient::new(None).with_offline(true);
+        let mut cache = GitHubCache::default();
+        assert!(client.fetch_avatar_cached("ada", &mut cache).is_err());
+
+        cache.avatars.insert(
+            "ada".to_string(),
+            GitHubCacheEntry::with_ttl("https://example.com/ada.png".to_string(), 0),
+        );
+        assert_eq!(
+            client.fetch_avatar_cached("ada", &mut cache).unwrap(),
+            "https://example.com/ada.png"
+        );
+        assert_eq!(cache.usage.last_build.stale, 1);
     }
 }
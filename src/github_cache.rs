@@ -2,6 +2,12 @@
 //!
 //! This module provides intelligent caching for GitHub API responses to dramatically
 //! improve build performance by avoiding redundant API calls.
+//!
+//! Entries expire after their TTL. Expired data is then revalidated with the
+//! `ETag` and `Last-Modified` validators of the response it was built from, or,
+//! with the `background` refresh strategy, served stale while it is
+//! revalidated (see [`crate::github::RefreshStrategy`]). Hits, misses and
+//! revalidations are counted per build and in total, for `cv cache stats`.
 
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
@@ -11,6 +17,10 @@ use std::fs;
 use std::path::Path;
 
 use crate::cv_data::Project;
+use crate::github::{GitHubStats, Revalidation};
+
+/// Where builds keep the GitHub cache
+pub const GITHUB_CACHE_PATH: &str = "cache/github_cache.json";
 
 /// Cache entry for GitHub API responses
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,8 +72,112 @@ pub struct GitHubCache {
     pub projects: HashMap<String, GitHubCacheEntry<Vector<Project>>>,
     /// Cached avatar URLs by username
     pub avatars: HashMap<String, GitHubCacheEntry<String>>,
+    /// Cached registry packages by `registry:name`
+    #[serde(default)]
+    pub packages: HashMap<String, GitHubCacheEntry<Project>>,
+    /// Cached aggregate stats by username
+    #[serde(default)]
+    pub stats: HashMap<String, GitHubCacheEntry<GitHubStats>>,
+    /// Raw API responses by request URL, used for conditional requests
+    #[serde(default)]
+    pub responses: HashMap<String, CachedResponse>,
+    /// Hit, miss and revalidation counts
+    #[serde(default)]
+    pub usage: CacheUsage,
     /// Cache metadata
     pub metadata: CacheMetadata,
+    /// Entries served stale this build, to revalidate in the background
+    #[serde(skip)]
+    pub(crate) pending: Vector<Revalidation>,
+}
+
+/// A raw API response kept for conditional requests
+///
+/// Its validators are sent back as `If-None-Match` and `If-Modified-Since`
+/// once the TTL entry built from it has expired; a `304 Not Modified` reply
+/// reuses the body and doesn't count against the rate limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    /// ETag header returned by the API
+    #[serde(default)]
+    pub etag: Option<String>,
+    /// Last-Modified header returned by the API
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    /// Response body
+    pub body: String,
+    /// URL of the next page, for paginated listings
+    #[serde(default)]
+    pub next: Option<String>,
+    /// When the response was last fetched or revalidated
+    pub fetched_at: DateTime<Utc>,
+}
+
+/// Something that happened to the cache
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEvent {
+    /// A valid entry was used
+    Hit,
+    /// An expired entry was used while it is revalidated in the background
+    Stale,
+    /// An entry was missing, expired or revalidated, and fetched
+    Miss,
+    /// A conditional request was answered with `304 Not Modified`
+    NotModified,
+    /// A request returned new data
+    Modified,
+    /// Expired entries were removed
+    Expired(usize),
+}
+
+/// Counts of cache events
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheCounts {
+    pub hits: u64,
+    pub stale: u64,
+    pub misses: u64,
+    pub not_modified: u64,
+    pub modified: u64,
+    pub expired: u64,
+}
+
+impl CacheCounts {
+    fn record(&mut self, event: CacheEvent) {
+        match event {
+            CacheEvent::Hit => self.hits += 1,
+            CacheEvent::Stale => self.stale += 1,
+            CacheEvent::Miss => self.misses += 1,
+            CacheEvent::NotModified => self.not_modified += 1,
+            CacheEvent::Modified => self.modified += 1,
+            CacheEvent::Expired(count) => self.expired += count as u64,
+        }
+    }
+
+    fn add(&mut self, other: &CacheCounts) {
+        self.hits += other.hits;
+        self.stale += other.stale;
+        self.misses += other.misses;
+        self.not_modified += other.not_modified;
+        self.modified += other.modified;
+        self.expired += other.expired;
+    }
+
+    /// Share of lookups answered without waiting for the API, in percent
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.stale + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            (self.hits + self.stale) as f64 * 100.0 / lookups as f64
+        }
+    }
+}
+
+/// Cache event counts of the last build and since the cache was created
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheUsage {
+    pub last_build: CacheCounts,
+    pub total: CacheCounts,
 }
 
 /// Metadata about the cache file
@@ -151,6 +265,67 @@ impl GitHubCache {
         Ok(())
     }
 
+    /// Start counting the events of a new build
+    pub fn begin_build(&mut self) {
+        self.usage.last_build = CacheCounts::default();
+    }
+
+    /// Count a cache event
+    pub fn record(&mut self, event: CacheEvent) {
+        self.usage.last_build.record(event);
+        self.usage.total.record(event);
+    }
+
+    /// Queue an entry served stale for revalidation
+    pub(crate) fn queue_revalidation(&mut self, revalidation: Revalidation) {
+        self.pending.push_back(revalidation);
+    }
+
+    /// Take the entries queued for revalidation
+    pub(crate) fn take_pending(&mut self) -> Vector<Revalidation> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Merge in entries refreshed by a background revalidation
+    ///
+    /// Entries and responses are taken when they are newer than the ones
+    /// here; the event counts of `other` are added to these.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - Cache the revalidation worked on, counting from zero
+    pub fn merge(&mut self, other: GitHubCache) {
+        fn newer<T: Clone>(
+            mine: &mut HashMap<String, GitHubCacheEntry<T>>,
+            theirs: HashMap<String, GitHubCacheEntry<T>>,
+        ) {
+            for (key, entry) in theirs {
+                if mine
+                    .get(&key)
+                    .is_none_or(|current| current.cached_at < entry.cached_at)
+                {
+                    mine.insert(key, entry);
+                }
+            }
+        }
+
+        newer(&mut self.projects, other.projects);
+        newer(&mut self.avatars, other.avatars);
+        newer(&mut self.packages, other.packages);
+        newer(&mut self.stats, other.stats);
+        for (url, response) in other.responses {
+            if self
+                .responses
+                .get(&url)
+                .is_none_or(|current| current.fetched_at < response.fetched_at)
+            {
+                self.responses.insert(url, response);
+            }
+        }
+        self.usage.last_build.add(&other.usage.last_build);
+        self.usage.total.add(&other.usage.total);
+    }
+
     /// Get cached projects for a username, if valid
     pub fn get_projects(&self, username: &str) -> Option<&Vector<Project>> {
         self.projects
@@ -187,10 +362,70 @@ impl GitHubCache {
         );
     }
 
+    /// Get a cached registry package, if valid
+    pub fn get_package(&self, key: &str) -> Option<&Project> {
+        self.packages
+            .get(key)
+            .filter(|entry| entry.is_valid())
+            .map(|entry| &entry.data)
+    }
+
+    /// Cache a registry package under `registry:name`
+    pub fn cache_package(&mut self, key: &str, package: Project) {
+        println!("📦 Caching package: {}", key);
+        self.packages
+            .insert(key.to_string(), GitHubCacheEntry::new(package));
+    }
+
+    /// Get cached stats for a username, if valid
+    pub fn get_stats(&self, username: &str) -> Option<&GitHubStats> {
+        self.stats
+            .get(username)
+            .filter(|entry| entry.is_valid())
+            .map(|entry| &entry.data)
+    }
+
+    /// Cache stats for a username
+    pub fn cache_stats(&mut self, username: &str, stats: GitHubStats) {
+        println!("📊 Caching stats for user: {}", username);
+        self.stats.insert(
+            username.to_string(),
+            GitHubCacheEntry::with_ttl(stats, 1440), // 24 hours TTL, stats change slowly
+        );
+    }
+
+    /// Get the stored response for a request URL, if any
+    pub fn get_response(&self, url: &str) -> Option<&CachedResponse> {
+        self.responses.get(url)
+    }
+
+    /// Store a response and its validators for a request URL
+    pub fn cache_response(
+        &mut self,
+        url: &str,
+        etag: Option<String>,
+        last_modified: Option<String>,
+        body: String,
+        next: Option<String>,
+    ) {
+        self.responses.insert(
+            url.to_string(),
+            CachedResponse {
+                etag,
+                last_modified,
+                body,
+                next,
+                fetched_at: Utc::now(),
+            },
+        );
+    }
+
     /// Clean up expired entries from cache (returns new cache with expired entries removed)
     pub fn cleanup_expired(&mut self) -> usize {
         let initial_projects = self.projects.len();
         let initial_avatars = self.avatars.len();
+        let initial_packages = self.packages.len();
+        let initial_stats = self.stats.len();
 
         // Filter to keep only valid entries (functional approach with im::HashMap)
         self.projects = self
@@ -207,19 +442,35 @@ impl GitHubCache {
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
 
+        self.packages = self
+            .packages
+            .iter()
+            .filter(|(_, entry)| entry.is_valid())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
+        self.stats = self
+            .stats
+            .iter()
+            .filter(|(_, entry)| entry.is_valid())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+
         let cleaned_projects = initial_projects - self.projects.len();
         let cleaned_avatars = initial_avatars - self.avatars.len();
-        let total_cleaned = cleaned_projects + cleaned_avatars;
+        let cleaned_packages = initial_packages - self.packages.len();
+        let cleaned_stats = initial_stats - self.stats.len();
+        let total_cleaned = cleaned_projects + cleaned_avatars + cleaned_packages + cleaned_stats;
 
         if total_cleaned > 0 {
             println!("🧹 Cleaned {} expired cache entries", total_cleaned);
+            self.record(CacheEvent::Expired(total_cleaned));
         }
 
         total_cleaned
     }
 
     /// Print cache statistics
-    #[allow(dead_code)]
     pub fn print_stats(&self) {
         let valid_projects: usize = self
             .projects
@@ -244,6 +495,19 @@ impl GitHubCache {
             valid_avatars,
             self.avatars.len()
         );
+        println!(
+            "  Packages: {} valid, {} total",
+            self.packages
+                .values()
+                .filter(|entry| entry.is_valid())
+                .count(),
+            self.packages.len()
+        );
+        println!(
+            "  Stats:    {} valid, {} total",
+            self.stats.values().filter(|entry| entry.is_valid()).count(),
+            self.stats.len()
+        );
 
         if !self.projects.is_empty() {
             let oldest_project = self
@@ -254,6 +518,37 @@ impl GitHubCache {
                 .unwrap_or(0);
             println!("  Oldest entry: {} minutes ago", oldest_project);
         }
+
+        println!(
+            "  Responses: {} ({} with ETag, {} with Last-Modified)",
+            self.responses.len(),
+            self.responses
+                .values()
+                .filter(|response| response.etag.is_some())
+                .count(),
+            self.responses
+                .values()
+                .filter(|response| response.last_modified.is_some())
+                .count()
+        );
+
+        for (label, counts) in [
+            ("Last build", &self.usage.last_build),
+            ("Total", &self.usage.total),
+        ] {
+            println!("  {}:", label);
+            println!(
+                "    {} hits, {} stale, {} misses ({:.0}% hit rate)",
+                counts.hits,
+                counts.stale,
+                counts.misses,
+                counts.hit_rate()
+            );
+            println!(
+                "    {} not modified, {} modified, {} expired",
+                counts.not_modified, counts.modified, counts.expired
+            );
+        }
     }
 }
 
@@ -305,4 +600,63 @@ mod tests {
         assert!(cache.get_avatar("expired_user").is_none());
         assert!(cache.get_avatar("valid_user").is_some());
     }
+
+    #[test]
+    fn test_cached_response_roundtrip() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("test_cache.json");
+
+        let mut cache = GitHubCache::default();
+        let url = "https://api.github.com/users/testuser/repos";
+        cache.cache_response(
+            url,
+            Some("\"abc\"".to_string()),
+            Some("Wed, 01 May 2024 10:00:00 GMT".to_string()),
+            "[]".to_string(),
+            None,
+        );
+        cache.save(&cache_path).unwrap();
+
+        let loaded = GitHubCache::load(&cache_path).unwrap();
+        let response = loaded.get_response(url).unwrap();
+        assert_eq!(response.etag.as_deref(), Some("\"abc\""));
+        assert_eq!(
+            response.last_modified.as_deref(),
+            Some("Wed, 01 May 2024 10:00:00 GMT")
+        );
+        assert_eq!(response.body, "[]");
+
+        // Cache files written before responses were stored still load
+        let legacy = r#"{"projects":{},"avatars":{},"metadata":{"version":"1.0.0","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}}"#;
+        let legacy: GitHubCache = serde_json::from_str(legacy).unwrap();
+        assert!(legacy.responses.is_empty());
+
+        // Responses stored before Last-Modified was kept still load
+        let legacy = r#"{"etag":"\"abc\"","body":"[]","fetched_at":"2024-01-01T00:00:00Z"}"#;
+        let legacy: CachedResponse = serde_json::from_str(legacy).unwrap();
+        assert_eq!(legacy.last_modified, None);
+    }
+
+    #[test]
+    fn test_merge_background_revalidation() {
+        let mut cache = GitHubCache::default();
+        cache.avatars.insert(
+            "ada".to_string(),
+            GitHubCacheEntry::with_ttl("old".to_string(), 0),
+        );
+        cache.record(CacheEvent::Stale);
+
+        let mut background = GitHubCache {
+            usage: CacheUsage::default(),
+            ..cache.clone()
+        };
+        background.cache_avatar("ada", "new".to_string());
+        background.record(CacheEvent::NotModified);
+
+        cache.merge(background);
+        assert_eq!(cache.get_avatar("ada"), Some("new"));
+        assert_eq!(cache.usage.total.stale, 1);
+        assert_eq!(cache.usage.total.not_modified, 1);
+        assert_eq!(cache.usage.last_build.hit_rate(), 100.0);
+    }
 }
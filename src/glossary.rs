@@ -0,0 +1,252 @@
+//! Glossary and abbreviation expansion
+//!
+//! `data/glossary.json` lists the abbreviations and jargon used in the CV,
+//! posts and pages, each with its expansion and an optional description:
+//!
+//! ```json
+//! [{ "term": "SRE", "title": "Site Reliability Engineering" }]
+//! ```
+//!
+//! After the site is generated, the first mention of each term in the main
+//! content of every page is wrapped in `<abbr title="...">`, so readers can
+//! hover it instead of looking it up. Terms are matched case-sensitively on
+//! word boundaries, like cross-links; text in code, links and headings is left
+//! alone. With `glossary_page` set, the terms and their descriptions are also
+//! listed on `glossary.html`.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::cross_links::{find_word, map_text};
+use crate::html_generator::filters::slug_str;
+use crate::html_generator::fingerprint::html_files;
+use crate::markdown_pages::Page;
+
+/// File name of the glossary in the data directory
+pub const GLOSSARY_FILE: &str = "glossary.json";
+
+/// Slug of the generated glossary page
+pub const GLOSSARY_SLUG: &str = "glossary";
+
+/// A term with its expansion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlossaryEntry {
+    /// The term as it appears in the text, e.g. `SRE`
+    pub term: String,
+    /// Expansion shown when hovering the term, e.g. `Site Reliability Engineering`
+    pub title: String,
+    /// Longer explanation for the glossary page
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// The known terms
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Glossary {
+    /// Known terms
+    pub entries: Vector<GlossaryEntry>,
+}
+
+impl Glossary {
+    /// Load the glossary from a JSON file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the JSON file
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the glossary or an error
+    pub fn from_json(path: &str) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read glossary from {path}"))?;
+        serde_json::from_str(&data).with_context(|| format!("Failed to parse glossary from {path}"))
+    }
+
+    /// Whether the glossary has no terms
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The glossary page, listing the terms alphabetically
+    pub fn page(&self) -> Result<Page> {
+        let mut entries: Vec<&GlossaryEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| entry.term.to_lowercase());
+
+        let items: String = entries
+            .iter()
+            .map(|entry| {
+                let description = entry
+                    .description
+                    .as_deref()
+                    .map(|description| format!(" {}", escape_html(description)))
+                    .unwrap_or_default();
+                format!(
+                    "<dt id=\"term-{}\"><dfn>{}</dfn></dt>\n<dd><strong>{}</strong>.{}</dd>\n",
+                    slug_str(&entry.term),
+                    escape_html(&entry.term),
+                    escape_html(&entry.title),
+                    description
+                )
+            })
+            .collect();
+
+        Ok(Page {
+            content: format!("<dl class=\"glossary\">\n{}</dl>\n", items),
+            ..Page::from_markdown("---\ntitle: Glossary\n---\n", GLOSSARY_SLUG.to_string())?
+        })
+    }
+}
+
+/// Wrap the first mention of each term in the main content of a page
+///
+/// Only the `<main>` element is rewritten, so the page title, navigation and
+/// footer are left alone. Pages without one are returned unchanged.
+///
+/// # Arguments
+///
+/// * `html` - A generated page
+/// * `glossary` - Terms to expand
+///
+/// # Returns
+///
+/// The page, and the number of terms expanded in it
+pub fn expand_abbreviations(html: &str, glossary: &Glossary) -> (String, usize) {
+    let Some(start) = html.find("<main") else {
+        return (html.to_string(), 0);
+    };
+    let end = html[start..]
+        .find("</main>")
+        .map_or(html.len(), |end| start + end);
+
+    // Longest terms first, so "AWS IAM" wins over "AWS"
+    let mut entries: Vec<&GlossaryEntry> = glossary
+        .entries
+        .iter()
+        .filter(|entry| !entry.term.trim().is_empty())
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.term.len()));
+
+    let mut expanded: Vec<&str> = Vec::new();
+    let main = map_text(&html[start..end], |text| {
+        expand_text(text, &entries, &mut expanded)
+    });
+
+    let count = expanded.len();
+    (format!("{}{}{}", &html[..start], main, &html[end..]), count)
+}
+
+/// Expand the terms of every page in the site
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+/// * `glossary` - Terms to expand
+///
+/// # Returns
+///
+/// The number of terms expanded across all pages
+pub fn add_abbreviations(output_dir: &Path, glossary: &Glossary) -> Result<usize> {
+    let mut total = 0;
+    for page in html_files(output_dir, output_dir)?.iter() {
+        let file = output_dir.join(page);
+        let html = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let (expanded, count) = expand_abbreviations(&html, glossary);
+        if count > 0 {
+            fs::write(&file, expanded)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            total += count;
+        }
+    }
+
+    Ok(total)
+}
+
+/// Expand mentions in a run of text outside tags
+fn expand_text<'a>(
+    text: &str,
+    entries: &[&'a GlossaryEntry],
+    expanded: &mut Vec<&'a str>,
+) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+
+    loop {
+        let next = entries
+            .iter()
+            .filter(|entry| !expanded.contains(&entry.term.as_str()))
+            .filter_map(|entry| {
+                find_word(rest, &escape_html(&entry.term)).map(|(start, end)| (start, end, *entry))
+            })
+            .min_by_key(|(start, _, _)| *start);
+
+        let Some((start, end, entry)) = next else {
+            output.push_str(rest);
+            return output;
+        };
+
+        output.push_str(&rest[..start]);
+        output.push_str(&format!(
+            r#"<abbr title="{}">{}</abbr>"#,
+            escape_html(&entry.title),
+            &rest[start..end]
+        ));
+        expanded.push(&entry.term);
+        rest = &rest[end..];
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use im::vector;
+
+    #[test]
+    fn test_expand_abbreviations() {
+        let entry = |term: &str, title: &str| GlossaryEntry {
+            term: term.to_string(),
+            title: title.to_string(),
+            description: None,
+        };
+        let glossary = Glossary {
+            entries: vector![
+                entry("AWS", "Amazon Web Services"),
+                entry("AWS IAM", "AWS Identity and Access Management"),
+                entry("SRE", "Site Reliability Engineering"),
+                entry("R&D", "Research & Development"),
+            ],
+        };
+        let html = concat!(
+            "<title>SRE</title><main><h2>SRE</h2>",
+            "<p>Ran <code>SRE</code> for AWS IAM and AWS, then SRE and SREs in R&amp;D.</p>",
+            "</main><footer>AWS</footer>"
+        );
+
+        let (html, count) = expand_abbreviations(html, &glossary);
+        assert_eq!(count, 4);
+        assert_eq!(
+            html,
+            concat!(
+                "<title>SRE</title><main><h2>SRE</h2>",
+                "<p>Ran <code>SRE</code> for ",
+                r#"<abbr title="AWS Identity and Access Management">AWS IAM</abbr> and "#,
+                r#"<abbr title="Amazon Web Services">AWS</abbr>, then "#,
+                r#"<abbr title="Site Reliability Engineering">SRE</abbr> and SREs in "#,
+                r#"<abbr title="Research &amp; Development">R&amp;D</abbr>.</p>"#,
+                "</main><footer>AWS</footer>"
+            )
+        );
+    }
+}
@@ -0,0 +1,202 @@
+//! Precompression of the generated site
+//!
+//! Writes `.br` and `.gz` copies of every HTML, CSS, JS, SVG and JSON file in
+//! the output directory, so servers can send them as they are instead of
+//! compressing on each request. The generated `.htaccess` serves them to
+//! browsers that accept the encoding. Copies that wouldn't be smaller than the
+//! original are skipped.
+
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use im::Vector;
+use rayon::prelude::*;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::site_config::CompressionConfig;
+
+/// Extensions of the files that are precompressed
+pub const COMPRESSED_EXTENSIONS: [&str; 5] = ["html", "css", "js", "svg", "json"];
+
+/// Brotli window size (log2), the encoder's default
+const BROTLI_WINDOW: u32 = 22;
+
+/// Precompress every eligible file below the output directory
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+/// * `config` - Compression levels
+///
+/// # Returns
+///
+/// The number of compressed copies written
+pub fn precompress_output(output_dir: &Path, config: &CompressionConfig) -> Result<usize> {
+    let files: Vec<PathBuf> = compressible_files(output_dir)?.into_iter().collect();
+
+    files
+        .par_iter()
+        .map(|path| precompress_file(path, config))
+        .try_reduce(|| 0, |a, b| Ok(a + b))
+}
+
+/// Write the `.br` and `.gz` copies of a single file
+///
+/// # Returns
+///
+/// The number of copies written (0 to 2)
+fn precompress_file(path: &Path, config: &CompressionConfig) -> Result<usize> {
+    let content = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let brotli = write_if_smaller(
+        path,
+        "br",
+        &brotli_compress(&content, config.brotli_quality())?,
+        content.len(),
+    )?;
+    let gzip = write_if_smaller(
+        path,
+        "gz",
+        &gzip_compress(&content, config.gzip_level())?,
+        content.len(),
+    )?;
+
+    Ok(usize::from(brotli) + usize::from(gzip))
+}
+
+/// Compress content with gzip
+///
+/// # Arguments
+///
+/// * `content` - The bytes to compress
+/// * `level` - Compression level, 0-9
+///
+/// # Returns
+///
+/// The compressed bytes
+pub fn gzip_compress(content: &[u8], level: u32) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+    encoder
+        .write_all(content)
+        .context("Failed to gzip content")?;
+    encoder.finish().context("Failed to finish gzip stream")
+}
+
+/// Compress content with Brotli
+///
+/// # Arguments
+///
+/// * `content` - The bytes to compress
+/// * `quality` - Compression quality, 0-11
+///
+/// # Returns
+///
+/// The compressed bytes
+pub fn brotli_compress(content: &[u8], quality: u32) -> Result<Vec<u8>> {
+    let mut compressed = Vec::new();
+    {
+        let mut writer =
+            brotli::CompressorWriter::new(&mut compressed, 4096, quality, BROTLI_WINDOW);
+        writer
+            .write_all(content)
+            .context("Failed to compress content with Brotli")?;
+    }
+    Ok(compressed)
+}
+
+/// Write `<path>.<suffix>` if the compressed copy is smaller than the original
+///
+/// A stale copy from an earlier build is removed otherwise, so it can't be
+/// served instead of the new file.
+fn write_if_smaller(
+    path: &Path,
+    suffix: &str,
+    compressed: &[u8],
+    original_len: usize,
+) -> Result<bool> {
+    let target = PathBuf::from(format!("{}.{}", path.display(), suffix));
+
+    if compressed.len() < original_len {
+        fs::write(&target, compressed)
+            .with_context(|| format!("Failed to write {}", target.display()))?;
+        Ok(true)
+    } else {
+        if target.exists() {
+            fs::remove_file(&target)
+                .with_context(|| format!("Failed to remove {}", target.display()))?;
+        }
+        Ok(false)
+    }
+}
+
+/// Recursively list the files that should be precompressed
+fn compressible_files(dir: &Path) -> Result<Vector<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vector::new());
+    }
+
+    fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        .map(|entry| {
+            let path = entry?.path();
+            if path.is_dir() {
+                compressible_files(&path)
+            } else if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| COMPRESSED_EXTENSIONS.contains(&ext))
+            {
+                Ok(Vector::unit(path))
+            } else {
+                Ok(Vector::new())
+            }
+        })
+        .try_fold(Vector::new(), |mut files, found| {
+            files.append(found?);
+            Ok(files)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_precompress_output() {
+        let dir = tempdir().unwrap();
+        let page = "<p>Hello, world!</p>\n".repeat(100);
+        fs::create_dir(dir.path().join("css")).unwrap();
+        fs::write(dir.path().join("index.html"), &page).unwrap();
+        fs::write(dir.path().join("css/tiny.css"), "a{}").unwrap();
+        fs::write(dir.path().join("css/tiny.css.gz"), "stale").unwrap();
+        fs::write(dir.path().join("photo.png"), &page).unwrap();
+
+        let written = precompress_output(dir.path(), &CompressionConfig::default()).unwrap();
+
+        assert_eq!(written, 2);
+        assert!(dir.path().join("index.html.br").exists());
+        assert!(!dir.path().join("photo.png.gz").exists());
+        // Not worth compressing, and the stale copy is gone
+        assert!(!dir.path().join("css/tiny.css.gz").exists());
+
+        let mut decompressed = String::new();
+        GzDecoder::new(&fs::read(dir.path().join("index.html.gz")).unwrap()[..])
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, page);
+
+        let mut decompressed = String::new();
+        brotli::Decompressor::new(
+            &fs::read(dir.path().join("index.html.br")).unwrap()[..],
+            4096,
+        )
+        .read_to_string(&mut decompressed)
+        .unwrap();
+        assert_eq!(decompressed, page);
+    }
+}
@@ -4,21 +4,28 @@
 //! web deployment, including server configurations, PWA manifests, and SEO files.
 
 use anyhow::{Context, Result};
+use im::Vector;
+use serde::Serialize;
 use std::fs;
+use std::path::Path;
 
 use super::utils::write_file;
-use crate::site_config::FontConfig;
+use crate::cv_data::Cv;
+use crate::dev_server::content_type_for;
+use crate::security_headers::SecurityHeaders;
+use crate::site_config::{CacheStrategy, FontConfig, PwaIcon, SiteConfig};
 
 /// Generates an .htaccess file for Apache servers with optimized settings
 ///
 /// # Arguments
 ///
 /// * `path` - Path where the .htaccess file will be written
+/// * `security_headers` - Security headers to set on every response
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_htaccess(path: &str) -> Result<()> {
+pub fn generate_htaccess(path: &str, security_headers: &SecurityHeaders) -> Result<()> {
     let htaccess_content = r#"# Enable gzip compression
 <IfModule mod_deflate.c>
   AddOutputFilterByType DEFLATE text/html text/plain text/xml text/css application/javascript application/json
@@ -28,26 +35,45 @@ pub fn generate_htaccess(path: &str) -> Result<()> {
 </IfModule>
 
 # Serve pre-compressed files if available
+<IfModule mod_rewrite.c>
+  RewriteEngine On
+
+  RewriteCond %{HTTP:Accept-Encoding} br
+  RewriteCond %{REQUEST_FILENAME}.br -f
+  RewriteRule ^(.+\.(html|css|js|svg|json))$ $1.br [L]
+
+  RewriteCond %{HTTP:Accept-Encoding} gzip
+  RewriteCond %{REQUEST_FILENAME}.gz -f
+  RewriteRule ^(.+\.(html|css|js|svg|json))$ $1.gz [L]
+
+  # Don't compress pre-compressed files a second time
+  RewriteRule \.(br|gz)$ - [E=no-gzip:1,E=no-brotli:1]
+</IfModule>
+
+# Set proper content type and encoding for compressed files
+<FilesMatch "\.html\.(br|gz)$">
+  ForceType text/html
+</FilesMatch>
+<FilesMatch "\.css\.(br|gz)$">
+  ForceType text/css
+</FilesMatch>
+<FilesMatch "\.js\.(br|gz)$">
+  ForceType application/javascript
+</FilesMatch>
+<FilesMatch "\.svg\.(br|gz)$">
+  ForceType image/svg+xml
+</FilesMatch>
+<FilesMatch "\.json\.(br|gz)$">
+  ForceType application/json
+</FilesMatch>
 <IfModule mod_headers.c>
-  <FilesMatch "\.(js|css|html)$">
-    RewriteEngine On
-    RewriteCond %{HTTP:Accept-Encoding} gzip
-    RewriteCond %{REQUEST_FILENAME}.gz -f
-    RewriteRule ^(.*)$ $1.gz [L]
-
-    # Set proper content type for compressed files
-    <Files *.js.gz>
-      ForceType application/javascript
-      Header set Content-Encoding gzip
-    </Files>
-    <Files *.css.gz>
-      ForceType text/css
-      Header set Content-Encoding gzip
-    </Files>
-    <Files *.html.gz>
-      ForceType text/html
-      Header set Content-Encoding gzip
-    </Files>
+  <FilesMatch "\.br$">
+    Header set Content-Encoding br
+    Header append Vary Accept-Encoding
+  </FilesMatch>
+  <FilesMatch "\.gz$">
+    Header set Content-Encoding gzip
+    Header append Vary Accept-Encoding
   </FilesMatch>
 </IfModule>
 
@@ -76,16 +102,20 @@ pub fn generate_htaccess(path: &str) -> Result<()> {
   ExpiresByType font/woff "access plus 1 year"
 </IfModule>
 
-# Security headers
+# Fingerprinted assets never change, their name changes instead
 <IfModule mod_headers.c>
-  Header always set X-Content-Type-Options nosniff
-  Header always set X-Frame-Options DENY
-  Header always set X-XSS-Protection "1; mode=block"
-  Header always set Strict-Transport-Security "max-age=31536000; includeSubDomains; preload"
+  <FilesMatch "\.[0-9a-f]{8}\.(css|js|woff2?|ttf|otf|eot)(\.(br|gz))?$">
+    Header set Cache-Control "public, max-age=31536000, immutable"
+  </FilesMatch>
+  <FilesMatch "^[0-9a-f]{32}(\.[a-z0-9]+)?(\.(br|gz))?$">
+    Header set Cache-Control "public, max-age=31536000, immutable"
+  </FilesMatch>
 </IfModule>
+
 "#;
+    let htaccess_content = format!("{}{}", htaccess_content, security_headers.to_htaccess());
 
-    write_file(path, htaccess_content)?;
+    write_file(path, &htaccess_content)?;
     println!("Generated .htaccess file with Apache optimization settings");
 
     Ok(())
@@ -96,11 +126,12 @@ pub fn generate_htaccess(path: &str) -> Result<()> {
 /// # Arguments
 ///
 /// * `path` - Path where the web.config file will be written
+/// * `security_headers` - Security headers to set on every response
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_web_config(path: &str) -> Result<()> {
+pub fn generate_web_config(path: &str, security_headers: &SecurityHeaders) -> Result<()> {
     let web_config_content = r##"<?xml version="1.0" encoding="UTF-8"?>
 <configuration>
   <system.webServer>
@@ -125,16 +156,15 @@ pub fn generate_web_config(path: &str) -> Result<()> {
       <!-- Font MIME types -->
       <mimeMap fileExtension=".woff2" mimeType="font/woff2" />
       <mimeMap fileExtension=".woff" mimeType="font/woff" />
+
+      <!-- Contact card -->
+      <mimeMap fileExtension=".vcf" mimeType="text/vcard" />
     </staticContent>
 
     <!-- Security headers -->
     <httpProtocol>
       <customHeaders>
-        <add name="X-Content-Type-Options" value="nosniff" />
-        <add name="X-Frame-Options" value="DENY" />
-        <add name="X-XSS-Protection" value="1; mode=block" />
-        <add name="Strict-Transport-Security" value="max-age=31536000; includeSubDomains; preload" />
-      </customHeaders>
+{security_headers}      </customHeaders>
     </httpProtocol>
 
     <!-- URL Rewrite rules -->
@@ -152,9 +182,10 @@ pub fn generate_web_config(path: &str) -> Result<()> {
     </rewrite>
   </system.webServer>
 </configuration>
-"##;
+"##
+    .replace("{security_headers}", &security_headers.to_web_config());
 
-    write_file(path, web_config_content)?;
+    write_file(path, &web_config_content)?;
     println!("Generated web.config file with IIS optimization settings");
 
     Ok(())
@@ -165,19 +196,13 @@ pub fn generate_web_config(path: &str) -> Result<()> {
 /// # Arguments
 ///
 /// * `path` - Path where the _headers file will be written
+/// * `security_headers` - Security headers to set on every response
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_netlify_headers(path: &str) -> Result<()> {
-    let headers_content = r#"# Security headers
-/*
-  X-Frame-Options: DENY
-  X-XSS-Protection: 1; mode=block
-  X-Content-Type-Options: nosniff
-  Referrer-Policy: strict-origin-when-cross-origin
-  Strict-Transport-Security: max-age=31536000; includeSubDomains; preload
-
+pub fn generate_netlify_headers(path: &str, security_headers: &SecurityHeaders) -> Result<()> {
+    let headers_content = r#"
 # Cache headers for static assets
 /css/*
   Cache-Control: public, max-age=31536000, immutable
@@ -191,6 +216,9 @@ pub fn generate_netlify_headers(path: &str) -> Result<()> {
 /fonts/*
   Cache-Control: public, max-age=31536000, immutable
 
+/assets/*
+  Cache-Control: public, max-age=31536000, immutable
+
 # Shorter cache for HTML files
 /*.html
   Cache-Control: public, max-age=86400
@@ -204,8 +232,9 @@ pub fn generate_netlify_headers(path: &str) -> Result<()> {
 /service-worker.js
   Cache-Control: no-cache
 "#;
+    let headers_content = format!("{}{}", security_headers.to_netlify(), headers_content);
 
-    write_file(path, headers_content)?;
+    write_file(path, &headers_content)?;
     println!("Generated Netlify _headers file with cache and security headers");
 
     Ok(())
@@ -234,6 +263,68 @@ pub fn generate_netlify_redirects(path: &str) -> Result<()> {
     Ok(())
 }
 
+/// Generates the GitHub Pages `CNAME` file for a custom domain
+///
+/// # Arguments
+///
+/// * `path` - Path where the CNAME file will be written
+/// * `domain` - The custom domain, e.g. "example.com"
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_cname(path: &str, domain: &str) -> Result<()> {
+    write_file(path, &format!("{domain}\n"))?;
+    println!("Generated GitHub Pages CNAME file for {domain}");
+
+    Ok(())
+}
+
+/// Generates the `.nojekyll` marker, so GitHub Pages serves the site as it is
+///
+/// Without it, Jekyll processes the site and drops files starting with an
+/// underscore, such as `_headers`.
+///
+/// # Arguments
+///
+/// * `path` - Path where the .nojekyll file will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_nojekyll(path: &str) -> Result<()> {
+    write_file(path, "")?;
+    println!("Generated .nojekyll file for GitHub Pages");
+
+    Ok(())
+}
+
+/// Generates the Cloudflare Pages `_routes.json`
+///
+/// The site is static, so the static asset directories are excluded from
+/// Pages Functions: requests for them are served directly and don't count
+/// against the Functions quota.
+///
+/// # Arguments
+///
+/// * `path` - Path where the _routes.json file will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_cloudflare_routes(path: &str) -> Result<()> {
+    let routes = serde_json::json!({
+        "version": 1,
+        "include": ["/*"],
+        "exclude": ["/css/*", "/js/*", "/img/*", "/fonts/*", "/assets/*"]
+    });
+
+    write_file(path, &serde_json::to_string_pretty(&routes)?)?;
+    println!("Generated Cloudflare Pages _routes.json file");
+
+    Ok(())
+}
+
 /// Generates a robots.txt file for SEO
 ///
 /// # Arguments
@@ -253,6 +344,7 @@ Allow: /
 # Disallow crawling of certain paths
 Disallow: /service-worker.js
 Disallow: /*.gz$
+Disallow: /*.br$
 "#;
 
     write_file(path, robots_content)?;
@@ -261,39 +353,141 @@ Disallow: /*.gz$
     Ok(())
 }
 
+/// Theme color of the manifest when neither the config nor a colorscheme sets one
+pub const DEFAULT_THEME_COLOR: &str = "#286983";
+
+/// Background color of the manifest when neither the config nor a colorscheme sets one
+pub const DEFAULT_BACKGROUND_COLOR: &str = "#faf4ed";
+
+/// Contents of a web app manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebAppManifest {
+    pub name: String,
+    pub short_name: String,
+    pub description: String,
+    pub start_url: String,
+    pub display: String,
+    pub background_color: String,
+    pub theme_color: String,
+    pub icons: Vector<PwaIcon>,
+    /// Colors for dark mode, when they differ from the default ones
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_preferences: Option<ManifestPreferences>,
+}
+
+/// Manifest members that apply to a user preference
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestPreferences {
+    /// Colors while the system is in dark mode
+    pub color_scheme_dark: ManifestColors,
+}
+
+/// Colors of the manifest for a color scheme
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestColors {
+    pub background_color: String,
+    pub theme_color: String,
+}
+
+impl WebAppManifest {
+    /// Build the manifest from the site configuration
+    ///
+    /// Colors come from the `pwa` config, then the colorscheme's custom
+    /// colors, then the generated colorscheme CSS, and finally the defaults.
+    /// Dark mode colors come from the `pwa` config, then the `.theme-dark`
+    /// rule of the CSS, and are left out when they match the default ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `cv` - CV data, for the default names and description
+    /// * `site_config` - Site configuration
+    /// * `colorscheme_css` - The generated colorscheme CSS, if any
+    ///
+    /// # Returns
+    ///
+    /// The manifest
+    pub fn new(cv: &Cv, site_config: &SiteConfig, colorscheme_css: Option<&str>) -> Self {
+        let pwa = site_config.pwa.clone().unwrap_or_default();
+        let overrides = site_config
+            .colorscheme
+            .as_ref()
+            .and_then(|colorscheme| colorscheme.custom_colors.as_ref());
+        let css_color =
+            |property: &str| colorscheme_css.and_then(|css| css_variable(css, property));
+
+        let dark_color = |property: &str| {
+            colorscheme_css
+                .and_then(|css| css_rule(css, ".theme-dark"))
+                .and_then(|rule| css_variable(rule, property))
+        };
+
+        let name = &cv.personal_info.name;
+        let icons = pwa.icons();
+        let background_color = pwa
+            .background_color
+            .or_else(|| overrides.and_then(|colors| colors.background.clone()))
+            .or_else(|| css_color("--color-background"))
+            .unwrap_or_else(|| DEFAULT_BACKGROUND_COLOR.to_string());
+        let theme_color = pwa
+            .theme_color
+            .or_else(|| overrides.and_then(|colors| colors.primary.clone()))
+            .or_else(|| css_color("--color-primary"))
+            .unwrap_or_else(|| DEFAULT_THEME_COLOR.to_string());
+        let dark = ManifestColors {
+            background_color: pwa
+                .dark_background_color
+                .or_else(|| dark_color("--color-background"))
+                .unwrap_or_else(|| background_color.clone()),
+            theme_color: pwa
+                .dark_theme_color
+                .or_else(|| dark_color("--color-primary"))
+                .unwrap_or_else(|| theme_color.clone()),
+        };
+        let user_preferences = (dark.background_color != background_color
+            || dark.theme_color != theme_color)
+            .then_some(ManifestPreferences {
+                color_scheme_dark: dark,
+            });
+
+        WebAppManifest {
+            name: pwa
+                .name
+                .or_else(|| site_config.title.clone())
+                .unwrap_or_else(|| format!("{name} - CV")),
+            short_name: pwa.short_name.unwrap_or_else(|| name.clone()),
+            description: pwa.description.unwrap_or_else(|| {
+                format!("CV and Portfolio of {name} - {}", cv.personal_info.title)
+            }),
+            start_url: "/".to_string(),
+            display: "standalone".to_string(),
+            background_color,
+            theme_color,
+            icons: icons
+                .into_iter()
+                .map(|icon| PwaIcon {
+                    mime_type: icon
+                        .mime_type
+                        .or_else(|| Some(content_type_for(Path::new(&icon.src)).to_string())),
+                    ..icon
+                })
+                .collect(),
+            user_preferences,
+        }
+    }
+}
+
 /// Generates a manifest.json file for Progressive Web App (PWA) support
 ///
 /// # Arguments
 ///
 /// * `path` - Path where the manifest.json file will be written
+/// * `manifest` - Contents of the manifest
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_manifest_json(path: &str) -> Result<()> {
-    let manifest_content = r##"{
-  "name": "Professional CV & Portfolio",
-  "short_name": "CV Portfolio",
-  "description": "Professional CV and portfolio showcasing skills and projects",
-  "start_url": "/",
-  "display": "standalone",
-  "background_color": "#faf4ed",
-  "theme_color": "#286983",
-  "icons": [
-    {
-      "src": "img/icon-192.png",
-      "sizes": "192x192",
-      "type": "image/png",
-      "purpose": "any maskable"
-    },
-    {
-      "src": "img/icon-512.png",
-      "sizes": "512x512",
-      "type": "image/png",
-      "purpose": "any maskable"
-    }
-  ]
-}"##;
+pub fn generate_manifest_json(path: &str, manifest: &WebAppManifest) -> Result<()> {
+    let manifest_content = serde_json::to_string_pretty(manifest)?;
 
     // Write the manifest.json file
     fs::write(path, manifest_content)
@@ -309,21 +503,54 @@ pub fn generate_manifest_json(path: &str) -> Result<()> {
 /// # Arguments
 ///
 /// * `path` - Path where the service-worker.js file will be written
+/// * `cache_name` - Name of the cache; a new name replaces the old cache on activation
+/// * `urls` - Site-absolute URLs to cache on install
+/// * `strategy` - How requests are answered
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_service_worker(path: &str) -> Result<()> {
+pub fn generate_service_worker(
+    path: &str,
+    cache_name: &str,
+    urls: &Vector<String>,
+    strategy: CacheStrategy,
+) -> Result<()> {
+    let (strategy_comment, fetch_handler) = match strategy {
+        CacheStrategy::CacheFirst => (
+            "Fetch event - serve cached content, falling back to the network",
+            r#"    caches.match(event.request)
+      .then(response => {
+        // Return cached version or fetch from network
+        return response || fetch(event.request);
+      })"#,
+        ),
+        CacheStrategy::NetworkFirst => (
+            "Fetch event - serve fresh content, falling back to the cache when offline",
+            r#"    fetch(event.request)
+      .then(response => {
+        // Keep a copy of successful same-origin responses for offline use
+        if (response.ok && new URL(event.request.url).origin === self.location.origin) {
+          const copy = response.clone();
+          caches.open(CACHE_NAME).then(cache => cache.put(event.request, copy));
+        }
+        return response;
+      })
+      .catch(() => caches.match(event.request))"#,
+        ),
+    };
+    let url_list = urls
+        .iter()
+        .map(|url| format!("  '{}'", url.replace('\\', "\\\\").replace('\'', "\\'")))
+        .collect::<Vec<_>>()
+        .join(",\n");
+
     let sw_content = r##"// Service Worker for CV Portfolio
 // Provides basic caching for offline support
 
-const CACHE_NAME = 'cv-portfolio-v1';
+const CACHE_NAME = '{cache_name}';
 const urlsToCache = [
-  '/',
-  '/cv.html',
-  '/css/main.css',
-  '/js/scripts.js',
-  '/manifest.json'
+{url_list}
 ];
 
 // Install event - cache resources
@@ -337,15 +564,13 @@ self.addEventListener('install', event => {
   );
 });
 
-// Fetch event - serve cached content when offline
+// {strategy_comment}
 self.addEventListener('fetch', event => {
+  if (event.request.method !== 'GET') {
+    return;
+  }
   event.respondWith(
-    caches.match(event.request)
-      .then(response => {
-        // Return cached version or fetch from network
-        return response || fetch(event.request);
-      }
-    )
+{fetch_handler}
   );
 });
 
@@ -364,14 +589,41 @@ self.addEventListener('activate', event => {
     })
   );
 });
-"##;
+"##
+    .replace("{cache_name}", cache_name)
+    .replace("{url_list}", &url_list)
+    .replace("{strategy_comment}", strategy_comment)
+    .replace("{fetch_handler}", fetch_handler);
 
-    write_file(path, sw_content)?;
+    write_file(path, &sw_content)?;
     println!("Generated service worker for offline support");
 
     Ok(())
 }
 
+/// The declarations of the top-level rule for `selector`, up to its closing brace
+fn css_rule<'a>(css: &'a str, selector: &str) -> Option<&'a str> {
+    let start = css
+        .match_indices(&format!("{selector} {{"))
+        .map(|(index, _)| index)
+        .find(|&index| index == 0 || css[..index].ends_with('\n'))?;
+    let rule = &css[start + selector.len() + 2..];
+    Some(&rule[..rule.find('}')?])
+}
+
+/// Read the value of a CSS custom property, from its first declaration
+fn css_variable(css: &str, property: &str) -> Option<String> {
+    css.lines()
+        .find_map(|line| {
+            line.trim()
+                .strip_prefix(property)?
+                .trim_start()
+                .strip_prefix(':')
+        })
+        .map(|value| value.trim().trim_end_matches(';').trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
 /// Generates CSS for font configuration
 ///
 /// # Arguments
@@ -440,3 +692,68 @@ pub fn generate_font_css(font_config: &FontConfig, path: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site_config::PwaConfig;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_pwa_files_follow_config() {
+        let cv = Cv::create_minimal();
+        let css = ":root {\n  --color-background: #191724;\n  --color-primary: #31748f;\n}\n";
+
+        let manifest = WebAppManifest::new(&cv, &SiteConfig::default(), Some(css));
+        assert_eq!(manifest.theme_color, "#31748f");
+        assert_eq!(manifest.background_color, "#191724");
+        assert_eq!(manifest.icons[0].mime_type.as_deref(), Some("image/png"));
+        assert_eq!(manifest.user_preferences, None);
+
+        // A light and dark pair puts the dark colors in the user preferences
+        let pair = ":root {\n  --color-background: #faf4ed;\n  --color-primary: #286983;\n}\n\
+                    @media (prefers-color-scheme: dark) {\n  :root:not(.theme-light) {\n    --color-background: #000000;\n  }\n}\n\
+                    .theme-dark {\n  --color-background: #232136;\n  --color-primary: #3e8fb0;\n}\n";
+        let manifest = WebAppManifest::new(&cv, &SiteConfig::default(), Some(pair));
+        assert_eq!(manifest.theme_color, "#286983");
+        assert_eq!(
+            manifest
+                .user_preferences
+                .map(|preferences| preferences.color_scheme_dark),
+            Some(ManifestColors {
+                background_color: "#232136".to_string(),
+                theme_color: "#3e8fb0".to_string(),
+            })
+        );
+
+        let site_config = SiteConfig {
+            title: Some("Ada's Site".to_string()),
+            pwa: Some(PwaConfig {
+                theme_color: Some("#ff0000".to_string()),
+                ..PwaConfig::default()
+            }),
+            ..SiteConfig::default()
+        };
+        let manifest = WebAppManifest::new(&cv, &site_config, None);
+        assert_eq!(manifest.name, "Ada's Site");
+        assert_eq!(manifest.theme_color, "#ff0000");
+        assert_eq!(manifest.background_color, DEFAULT_BACKGROUND_COLOR);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("service-worker.js");
+        let path = path.to_str().unwrap();
+        let urls = im::vector!["/".to_string(), "/cv.html".to_string()];
+
+        generate_service_worker(
+            path,
+            "cv-portfolio-test",
+            &urls,
+            CacheStrategy::NetworkFirst,
+        )
+        .unwrap();
+        let script = fs::read_to_string(path).unwrap();
+        assert!(script.contains("const CACHE_NAME = 'cv-portfolio-test';"));
+        assert!(script.contains("  '/',\n  '/cv.html'\n];"));
+        assert!(script.contains("cache.put(event.request, copy)"));
+    }
+}
@@ -0,0 +1,356 @@
+//! RSS feed generation
+//!
+//! Writes `feed.xml` (RSS 2.0) for the blog when `blog.rss_feed` is enabled.
+//! Entries carry either the post summary or, with `feed_content: "full"`, the
+//! whole post. Feed readers resolve relative URLs inconsistently, so links and
+//! images in full-content entries are rewritten to absolute URLs first; code
+//! blocks are left untouched since their markup is already escaped.
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use reqwest::Url;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::filters::{join_url, truncate_words_str};
+use crate::blog_posts::BlogPost;
+use crate::cv_data::Cv;
+use crate::site_config::{FeedContent, SiteConfig};
+
+/// Length of generated summaries for posts without an excerpt
+const SUMMARY_LENGTH: usize = 280;
+
+/// Generate the RSS feed for the blog
+///
+/// # Arguments
+///
+/// * `cv` - CV data, for the feed title and author
+/// * `site_config` - Site configuration with `base_url` and the feed options
+/// * `posts` - Published posts, newest first
+/// * `output_path` - Path where the feed will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_rss_feed(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    posts: &im::Vector<BlogPost>,
+    output_path: &Path,
+) -> Result<()> {
+    let base_url = site_config
+        .base_url
+        .as_deref()
+        .context("The RSS feed needs base_url in site.json for absolute links")?;
+    let content = site_config
+        .blog
+        .as_ref()
+        .and_then(|blog| blog.feed_content)
+        .unwrap_or_default();
+
+    let feed = render_feed(cv, site_config, posts, base_url, content)?;
+    fs::write(output_path, feed)
+        .with_context(|| format!("Failed to write RSS feed: {}", output_path.display()))
+}
+
+/// Render the RSS document
+fn render_feed(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    posts: &im::Vector<BlogPost>,
+    base_url: &str,
+    content: FeedContent,
+) -> Result<String> {
+    let title = site_config
+        .title
+        .clone()
+        .unwrap_or_else(|| cv.personal_info.name.clone());
+
+    let items = posts
+        .iter()
+        .map(|post| render_item(post, base_url, content))
+        .collect::<Result<Vec<_>>>()?;
+
+    // The newest post rather than the build time, so unchanged feeds stay identical
+    let last_build_date = posts
+        .iter()
+        .map(|post| post.date)
+        .max()
+        .map(|date| format!("\n    <lastBuildDate>{}</lastBuildDate>", date.to_rfc2822()))
+        .unwrap_or_default();
+
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+  <channel>
+    <title>{title}</title>
+    <link>{link}</link>
+    <description>{description}</description>
+    <atom:link href="{feed_url}" rel="self" type="application/rss+xml"/>{last_build_date}
+{items}
+  </channel>
+</rss>
+"#,
+        title = escape_xml(&title),
+        link = escape_xml(&join_url("blog.html", base_url)),
+        description = escape_xml(&format!(
+            "Blog of {}, {}",
+            cv.personal_info.name, cv.personal_info.title
+        )),
+        feed_url = escape_xml(&join_url("feed.xml", base_url)),
+        items = items.join("\n"),
+    ))
+}
+
+/// Render a single feed entry
+fn render_item(post: &BlogPost, base_url: &str, content: FeedContent) -> Result<String> {
+    let link = join_url(&format!("blog/{}.html", post.slug), base_url);
+    let summary = post
+        .excerpt
+        .clone()
+        .unwrap_or_else(|| truncate_words_str(&strip_tags(&post.content), SUMMARY_LENGTH));
+
+    let categories: String = post
+        .tags
+        .iter()
+        .map(|tag| format!("\n      <category>{}</category>", escape_xml(tag)))
+        .collect();
+
+    let full_content = match content {
+        FeedContent::Summary => String::new(),
+        FeedContent::Full => format!(
+            "\n      <content:encoded>{}</content:encoded>",
+            cdata(&absolutize_urls(&post.content, &link)?)
+        ),
+    };
+
+    Ok(format!(
+        r#"    <item>
+      <title>{title}</title>
+      <link>{link}</link>
+      <guid isPermaLink="true">{link}</guid>
+      <pubDate>{date}</pubDate>
+      <description>{summary}</description>{categories}{full_content}
+    </item>"#,
+        title = escape_xml(&post.title),
+        link = escape_xml(&link),
+        date = post.date.to_rfc2822(),
+        summary = escape_xml(&summary),
+    ))
+}
+
+/// Rewrite relative `href`, `src`, `srcset` and `poster` URLs to absolute ones
+///
+/// # Arguments
+///
+/// * `html` - Post HTML
+/// * `page_url` - Absolute URL of the post, which relative URLs resolve against
+///
+/// # Returns
+///
+/// The HTML with absolute URLs
+pub fn absolutize_urls(html: &str, page_url: &str) -> Result<String> {
+    let base = Url::parse(page_url).with_context(|| format!("Invalid page URL: {page_url}"))?;
+    let resolve = |url: &str| {
+        base.join(url.trim())
+            .map(|resolved| resolved.to_string())
+            .unwrap_or_else(|_| url.to_string())
+    };
+
+    let rewrite_attribute = |captures: &Captures| {
+        let value = &captures["value"];
+        let value = if captures["name"].eq_ignore_ascii_case("srcset") {
+            // "a.png 1x, b.png 2x": resolve the URL of each candidate
+            value
+                .split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    match candidate.split_once(char::is_whitespace) {
+                        Some((url, descriptor)) => format!("{} {}", resolve(url), descriptor),
+                        None => resolve(candidate),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            resolve(value)
+        };
+
+        format!(
+            "{}{}=\"{}\"",
+            &captures["space"],
+            &captures["name"],
+            value.replace('"', "&quot;")
+        )
+    };
+
+    // Only attributes inside real tags: markup in code blocks is escaped, so
+    // `&lt;a href="..."&gt;` never matches
+    let rewritten = html_tag_regex().replace_all(html, |tag: &Captures| {
+        url_attribute_regex()
+            .replace_all(&tag[0], rewrite_attribute)
+            .into_owned()
+    });
+
+    Ok(rewritten.into_owned())
+}
+
+/// Wrap text in a CDATA section, splitting any `]]>` it contains
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn strip_tags(html: &str) -> String {
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = TAG.get_or_init(|| Regex::new(r"<[^>]*>").unwrap());
+    tag.replace_all(html, "")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Matches opening HTML tags
+fn html_tag_regex() -> &'static Regex {
+    static HTML_TAG: OnceLock<Regex> = OnceLock::new();
+    HTML_TAG.get_or_init(|| Regex::new(r"<[a-zA-Z][^<>]*>").unwrap())
+}
+
+/// Matches `href`, `src`, `srcset` and `poster` attributes
+fn url_attribute_regex() -> &'static Regex {
+    static URL_ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    URL_ATTRIBUTE.get_or_init(|| {
+        Regex::new(r#"(?i)(?P<space>\s)(?P<name>href|src|srcset|poster)="(?P<value>[^"]*)""#)
+            .unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::PersonalInfo;
+    use crate::site_config::BlogConfig;
+    use chrono::{TimeZone, Utc};
+    use im::{vector, HashMap, Vector};
+
+    fn post(content: &str) -> BlogPost {
+        BlogPost {
+            title: "Fish & Chips".to_string(),
+            date: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            tags: vector!["rust".to_string()],
+            excerpt: None,
+            slug: "fish".to_string(),
+            content: content.to_string(),
+            reading_time: None,
+            published: None,
+            author: None,
+            featured: false,
+            pinned: false,
+            reading_progress: false,
+            sections: Vector::new(),
+            updated: None,
+            review_after: None,
+            stale: false,
+            fields: Default::default(),
+        }
+    }
+
+    fn cv() -> Cv {
+        Cv {
+            personal_info: PersonalInfo {
+                name: "Ada".to_string(),
+                title: "Engineer".to_string(),
+                email: String::new(),
+                phone: None,
+                website: None,
+                location: None,
+                summary: String::new(),
+                social_links: HashMap::new(),
+                profile_image: None,
+                github_avatar_url: None,
+                summaries: Default::default(),
+            },
+            experiences: Vector::new(),
+            education: Vector::new(),
+            skill_categories: Vector::new(),
+            projects: Vector::new(),
+            languages: HashMap::new(),
+            certifications: Vector::new(),
+            github_sources: Vector::new(),
+            registry_sources: Vector::new(),
+            testimonials: Vector::new(),
+            github_stats: None,
+        }
+    }
+
+    #[test]
+    fn test_absolutize_urls() {
+        let html = r##"<p><a href="other.html">a</a> <a href="/cv.html">b</a> <a href="#top">c</a> <a href="https://x.dev/">d</a></p>
+<img src="../img/pic.png" srcset="../img/pic.png 1x, ../img/pic@2x.png 2x" alt="">
+<pre><code>&lt;a href="relative.html"&gt;</code></pre>"##;
+
+        let rewritten = absolutize_urls(html, "https://example.com/blog/post.html").unwrap();
+
+        assert!(rewritten.contains(r#"href="https://example.com/blog/other.html""#));
+        assert!(rewritten.contains(r#"href="https://example.com/cv.html""#));
+        assert!(rewritten.contains(r##"href="https://example.com/blog/post.html#top""##));
+        assert!(rewritten.contains(r#"href="https://x.dev/""#));
+        assert!(rewritten.contains(r#"src="https://example.com/img/pic.png""#));
+        assert!(rewritten.contains(
+            r#"srcset="https://example.com/img/pic.png 1x, https://example.com/img/pic@2x.png 2x""#
+        ));
+        // Escaped markup in code blocks is not a real attribute
+        assert!(rewritten.contains(r#"&lt;a href="relative.html"&gt;"#));
+    }
+
+    #[test]
+    fn test_feed_content_modes() {
+        let site_config = SiteConfig {
+            base_url: Some("https://example.com".to_string()),
+            blog: Some(BlogConfig {
+                directory: None,
+                posts_per_page: None,
+                rss_feed: Some(true),
+                feed_content: None,
+                archive_links: None,
+                cross_links: None,
+                tag_styles: Default::default(),
+            }),
+            ..SiteConfig::default()
+        };
+        let posts = vector![post(r#"<p>See <a href="other.html">this</a>]]></p>"#)];
+
+        let summary = render_feed(
+            &cv(),
+            &site_config,
+            &posts,
+            "https://example.com",
+            FeedContent::Summary,
+        )
+        .unwrap();
+        assert!(summary.contains("<title>Fish &amp; Chips</title>"));
+        assert!(summary.contains("<link>https://example.com/blog/fish.html</link>"));
+        assert!(summary.contains("<description>See this]]&gt;</description>"));
+        assert!(summary.contains("<category>rust</category>"));
+        assert!(!summary.contains("content:encoded>"));
+
+        let full = render_feed(
+            &cv(),
+            &site_config,
+            &posts,
+            "https://example.com",
+            FeedContent::Full,
+        )
+        .unwrap();
+        assert!(full.contains(
+            r#"<content:encoded><![CDATA[<p>See <a href="https://example.com/blog/other.html">this</a>]]]]><![CDATA[></p>]]></content:encoded>"#
+        ));
+    }
+}
@@ -0,0 +1,430 @@
+//! Template filters
+//!
+//! Helpers available to every template, both the built-in askama templates and
+//! Tera theme templates:
+//!
+//! | Filter | Example | Result |
+//! |---|---|---|
+//! | `format_date(fmt)` | `"2021-03"\|format_date("%b %Y")` | `Mar 2021` |
+//! | `truncate_words(n)` | `text\|truncate_words(120)` | Cut on a word boundary, with `…` |
+//! | `markdown_inline` | `"Uses *Rust*"\|markdown_inline` | `Uses <em>Rust</em>` (use with `safe`) |
+//! | `humanize` | `15300\|humanize` | `15.3k` |
+//! | `url(base)` | `"blog/a.html"\|url(site_config.base_url())` | `https://example.com/blog/a.html` |
+//! | `asset(version)` | `"css/main.min.css"\|asset(version)` | `css/main.min.css?v=<version>` |
+//! | `slug` | `"C++ / Rust"\|slug` | `c-plus-plus-rust` |
+//!
+//! The askama filters live in this module so templates pick them up through
+//! `use super::filters`; [`register_tera_filters`] registers the same
+//! functions with Tera.
+//!
+//! Templates also get an `icon("github")` helper, which renders an icon from
+//! the [sprite](crate::icons): a method of every askama template through
+//! [`Icons`], and a function in Tera (`icon(name="github")`).
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use pulldown_cmark::{html, Event, Parser};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Format a date string with a chrono format string
+///
+/// Accepts RFC 3339 timestamps, `YYYY-MM-DD` and `YYYY-MM`. Anything else
+/// (a bare year, "Present", ...) is returned unchanged, since it has no month
+/// to format.
+///
+/// # Arguments
+///
+/// * `value` - The date to format
+/// * `format` - chrono format string, e.g. `%B %d, %Y`
+///
+/// # Returns
+///
+/// The formatted date
+pub fn format_date_str(value: &str, format: &str) -> String {
+    let value = value.trim();
+    let date = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S UTC"))
+        .map(|dt| dt.date())
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d"))
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{value}-01"), "%Y-%m-%d"));
+
+    match date {
+        Ok(date) => date.format(format).to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// Truncate text to at most `max_chars` characters on a word boundary
+///
+/// # Arguments
+///
+/// * `text` - The text to truncate
+/// * `max_chars` - Maximum length in characters, excluding the ellipsis
+///
+/// # Returns
+///
+/// The text itself if it fits, otherwise its leading words followed by `…`
+pub fn truncate_words_str(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let head = &text[..cut];
+    // Break at the last whitespace unless the text continues with one anyway
+    let head = if text[cut..].starts_with(char::is_whitespace) {
+        head
+    } else {
+        head.rfind(char::is_whitespace)
+            .map(|i| &head[..i])
+            .unwrap_or(head)
+    };
+
+    format!(
+        "{}…",
+        head.trim_end_matches(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+    )
+}
+
+/// Render inline Markdown (emphasis, code, links) to HTML
+///
+/// Raw HTML in the input is escaped rather than passed through, so the filter
+/// is safe for third-party text such as GitHub descriptions.
+///
+/// # Arguments
+///
+/// * `text` - Markdown text
+///
+/// # Returns
+///
+/// HTML without the wrapping paragraph
+pub fn markdown_inline_str(text: &str) -> String {
+    let events = Parser::new(text).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+
+    let rendered = rendered.trim();
+    match rendered
+        .strip_prefix("<p>")
+        .and_then(|inner| inner.strip_suffix("</p>"))
+    {
+        Some(inner) if !inner.contains("<p>") => inner.to_string(),
+        _ => rendered.to_string(),
+    }
+}
+
+/// Format a number compactly (`950`, `1.2k`, `15.3k`, `2M`)
+///
+/// # Arguments
+///
+/// * `value` - The number to format
+///
+/// # Returns
+///
+/// The humanized number
+pub fn humanize_number(value: f64) -> String {
+    let (scaled, suffix) = match value.abs() {
+        v if v >= 1_000_000_000.0 => (value / 1_000_000_000.0, "B"),
+        v if v >= 1_000_000.0 => (value / 1_000_000.0, "M"),
+        v if v >= 1_000.0 => (value / 1_000.0, "k"),
+        _ => (value, ""),
+    };
+
+    let formatted = format!("{:.1}", (scaled * 10.0).trunc() / 10.0);
+    format!("{}{}", formatted.trim_end_matches(".0"), suffix)
+}
+
+/// Join a site-relative path onto the site's base URL
+///
+/// Absolute URLs are returned unchanged, and an empty base leaves the path
+/// relative.
+///
+/// # Arguments
+///
+/// * `path` - Site-relative path, e.g. `blog/post.html`
+/// * `base_url` - Base URL of the deployed site
+///
+/// # Returns
+///
+/// The joined URL
+pub fn join_url(path: &str, base_url: &str) -> String {
+    if path.contains("://") || base_url.is_empty() {
+        return path.to_string();
+    }
+
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Resolve the public URL of a static asset
+///
+/// Every asset reference in the templates goes through this function, so the
+/// cache-busting scheme can change without touching templates.
+///
+/// # Arguments
+///
+/// * `path` - Asset path relative to the output directory
+/// * `version` - Build version used for cache busting
+///
+/// # Returns
+///
+/// The URL to reference the asset with
+pub fn asset_url(path: &str, version: &str) -> String {
+    if version.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}?v={version}")
+    }
+}
+
+/// Turn a name into an anchor id
+///
+/// `+` and `#` are spelled out, so `C`, `C++` and `C#` get distinct ids.
+///
+/// # Arguments
+///
+/// * `text` - A project, skill or other name
+///
+/// # Returns
+///
+/// A lowercase id made of letters, digits and dashes
+pub fn slug_str(text: &str) -> String {
+    text.to_lowercase()
+        .replace('+', " plus ")
+        .replace('#', " sharp ")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// `format_date` filter
+pub fn format_date<T: Display>(
+    value: T,
+    _: &dyn askama::Values,
+    format: impl Display,
+) -> askama::Result<String> {
+    Ok(format_date_str(&value.to_string(), &format.to_string()))
+}
+
+/// `truncate_words` filter
+pub fn truncate_words<T: Display>(
+    value: T,
+    _: &dyn askama::Values,
+    max_chars: usize,
+) -> askama::Result<String> {
+    Ok(truncate_words_str(&value.to_string(), max_chars))
+}
+
+/// `markdown_inline` filter
+pub fn markdown_inline<T: Display>(value: T, _: &dyn askama::Values) -> askama::Result<String> {
+    Ok(markdown_inline_str(&value.to_string()))
+}
+
+/// `humanize` filter
+pub fn humanize<T: Display>(value: T, _: &dyn askama::Values) -> askama::Result<String> {
+    let text = value.to_string();
+    Ok(text.parse::<f64>().map(humanize_number).unwrap_or(text))
+}
+
+/// `url` filter
+pub fn url<T: Display>(
+    value: T,
+    _: &dyn askama::Values,
+    base_url: impl Display,
+) -> askama::Result<String> {
+    Ok(join_url(&value.to_string(), &base_url.to_string()))
+}
+
+/// `asset` filter
+pub fn asset<T: Display>(
+    value: T,
+    _: &dyn askama::Values,
+    version: impl Display,
+) -> askama::Result<String> {
+    Ok(asset_url(&value.to_string(), &version.to_string()))
+}
+
+/// `slug` filter
+pub fn slug<T: Display>(value: T, _: &dyn askama::Values) -> askama::Result<String> {
+    Ok(slug_str(&value.to_string()))
+}
+
+/// `icon` helper for askama templates
+///
+/// Implemented for every template, so templates call it as `icon("github")`.
+pub trait Icons {
+    /// Render an icon from the sprite
+    fn icon(&self, name: impl Display) -> askama::filters::Safe<String> {
+        askama::filters::Safe(crate::icons::icon(&name.to_string()))
+    }
+}
+
+impl<T: askama::Template> Icons for T {}
+
+/// Register the filters and the `icon` function with a Tera instance
+///
+/// Tera filters take named arguments: `format_date(format="%b %Y")`,
+/// `truncate_words(length=120)`, `url(base=...)` and `asset(version=...)`.
+///
+/// # Arguments
+///
+/// * `tera` - The Tera instance used for theme templates
+pub fn register_tera_filters(tera: &mut tera::Tera) {
+    fn text(value: &tera::Value) -> String {
+        match value {
+            tera::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+    fn arg(args: &HashMap<String, tera::Value>, name: &str) -> tera::Result<String> {
+        args.get(name)
+            .map(text)
+            .ok_or_else(|| tera::Error::msg(format!("Missing filter argument '{name}'")))
+    }
+
+    tera.register_filter(
+        "format_date",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+            Ok(format_date_str(&text(value), &arg(args, "format")?).into())
+        },
+    );
+    tera.register_filter(
+        "truncate_words",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+            let length = args
+                .get("length")
+                .and_then(tera::Value::as_u64)
+                .ok_or_else(|| tera::Error::msg("Missing filter argument 'length'"))?;
+            Ok(truncate_words_str(&text(value), length as usize).into())
+        },
+    );
+    tera.register_filter(
+        "markdown_inline",
+        |value: &tera::Value, _: &HashMap<String, tera::Value>| {
+            Ok(markdown_inline_str(&text(value)).into())
+        },
+    );
+    tera.register_filter(
+        "humanize",
+        |value: &tera::Value, _: &HashMap<String, tera::Value>| match value.as_f64() {
+            Some(number) => Ok(humanize_number(number).into()),
+            None => Ok(value.clone()),
+        },
+    );
+    tera.register_filter(
+        "url",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+            Ok(join_url(&text(value), &arg(args, "base")?).into())
+        },
+    );
+    tera.register_filter(
+        "asset",
+        |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+            Ok(asset_url(&text(value), &arg(args, "version")?).into())
+        },
+    );
+    tera.register_filter(
+        "slug",
+        |value: &tera::Value, _: &HashMap<String, tera::Value>| Ok(slug_str(&text(value)).into()),
+    );
+    tera.register_function("icon", |args: &HashMap<String, tera::Value>| {
+        Ok(crate::icons::icon(&arg(args, "name")?).into())
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date_str("2021-03", "%b %Y"), "Mar 2021");
+        assert_eq!(format_date_str("2021-03-09", "%B %d, %Y"), "March 09, 2021");
+        assert_eq!(format_date_str("2016", "%b %Y"), "2016");
+        assert_eq!(
+            format_date_str("2024-01-15 10:00:00 UTC", "%d.%m.%Y"),
+            "15.01.2024"
+        );
+        assert_eq!(format_date_str("Present", "%b %Y"), "Present");
+    }
+
+    #[test]
+    fn test_truncate_words() {
+        assert_eq!(truncate_words_str("Short text", 20), "Short text");
+        assert_eq!(
+            truncate_words_str("Functional programming in Rust", 15),
+            "Functional…"
+        );
+        assert_eq!(truncate_words_str("One two, three", 8), "One two…");
+    }
+
+    #[test]
+    fn test_markdown_inline() {
+        assert_eq!(
+            markdown_inline_str("Uses *Rust* and `im`"),
+            "Uses <em>Rust</em> and <code>im</code>"
+        );
+        assert_eq!(
+            markdown_inline_str("<script>alert(1)</script>"),
+            "&lt;script&gt;alert(1)&lt;/script&gt;"
+        );
+    }
+
+    #[test]
+    fn test_humanize_url_and_asset() {
+        assert_eq!(humanize_number(950.0), "950");
+        assert_eq!(humanize_number(1234.0), "1.2k");
+        assert_eq!(humanize_number(15_360.0), "15.3k");
+        assert_eq!(humanize_number(2_000_000.0), "2M");
+
+        assert_eq!(
+            join_url("/blog/a.html", "https://example.com/"),
+            "https://example.com/blog/a.html"
+        );
+        assert_eq!(join_url("blog/a.html", ""), "blog/a.html");
+        assert_eq!(
+            join_url("https://x.dev/a", "https://example.com"),
+            "https://x.dev/a"
+        );
+
+        assert_eq!(asset_url("css/main.css", "abc123"), "css/main.css?v=abc123");
+
+        assert_eq!(slug_str("C++ / Rust"), "c-plus-plus-rust");
+        assert_eq!(slug_str("C#"), "c-sharp");
+        assert_eq!(slug_str("cv-generator"), "cv-generator");
+    }
+
+    #[test]
+    fn test_tera_filters() {
+        let mut tera = tera::Tera::default();
+        register_tera_filters(&mut tera);
+        tera.add_raw_template(
+            "t",
+            r#"{{ d | format_date(format="%b %Y") }} {{ n | humanize }} {{ p | asset(version="v1") }}"#,
+        )
+        .unwrap();
+
+        let mut context = tera::Context::new();
+        context.insert("d", "2021-03");
+        context.insert("n", &1500);
+        context.insert("p", "js/scripts.js");
+        assert_eq!(
+            tera.render("t", &context).unwrap(),
+            "Mar 2021 1.5k js/scripts.js?v=v1"
+        );
+    }
+}
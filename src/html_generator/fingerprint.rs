@@ -0,0 +1,651 @@
+//! Asset fingerprinting
+//!
+//! After the assets are minified, every CSS, JS and font file in the output,
+//! and the icon sprite, gets a copy with a hash of its content in the name,
+//! e.g. `css/main.min.3f2a9c1e.css`. References in the generated HTML, in
+//! stylesheets and in the service worker's cache list are rewritten to the
+//! hashed names, so a deploy changes the URL of every asset that changed and
+//! long-lived cache headers can never serve a stale one.
+//!
+//! The originals stay in place for anything that links to them directly, and
+//! `asset-manifest.json` maps each original path to its fingerprinted copy.
+//!
+//! With `content_addressed`, the copies go to a content-addressed store
+//! instead, named only by a longer hash, e.g.
+//! `assets/3f/2a/3f2a9c1e….css`, and images are stored as well. Identical
+//! files are stored once however many pages and directories use them, and a
+//! deploy only has to upload the store entries that are new.
+
+use anyhow::{Context, Result};
+use im::{OrdMap, Vector};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::icons::SPRITE_FILE;
+
+/// File name of the asset manifest in the output directory
+pub const ASSET_MANIFEST_FILE: &str = "asset-manifest.json";
+
+/// Font extensions; fonts are fingerprinted before the stylesheets using them
+const FONT_EXTENSIONS: [&str; 5] = ["woff", "woff2", "ttf", "otf", "eot"];
+
+/// The service worker must keep its URL to be updated, so it's never fingerprinted
+const SERVICE_WORKER: &str = "service-worker.js";
+
+/// Image extensions, stored along with the other assets in the content-addressed store
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "webp", "avif", "svg"];
+
+/// Number of hex digits of the content hash in file names
+const HASH_LENGTH: usize = 8;
+
+/// Directory of the content-addressed store in the output directory
+pub const ASSET_STORE_DIR: &str = "assets";
+
+/// Number of hex digits of the content hash naming a file in the store
+const STORE_HASH_LENGTH: usize = 32;
+
+/// Maps asset paths to their fingerprinted copies, relative to the output directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AssetManifest {
+    /// Fingerprinted path by original path, e.g. `css/main.css` → `css/main.3f2a9c1e.css`,
+    /// or `assets/3f/2a/3f2a9c1e….css` in the content-addressed store
+    pub assets: OrdMap<String, String>,
+}
+
+impl AssetManifest {
+    /// The fingerprinted path of an asset, if it has one
+    pub fn fingerprinted(&self, path: &str) -> Option<&str> {
+        self.assets.get(path).map(String::as_str)
+    }
+}
+
+/// Fingerprint the CSS, JS and font files of a generated site
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+/// * `content_addressed` - Whether to put the copies, and the images, in the
+///   content-addressed store under [`ASSET_STORE_DIR`]
+///
+/// # Returns
+///
+/// The asset manifest, which is also written to [`ASSET_MANIFEST_FILE`]
+pub fn fingerprint_assets(output_dir: &Path, content_addressed: bool) -> Result<AssetManifest> {
+    let assets = asset_files(output_dir, output_dir, content_addressed)?;
+    let is_css = |path: &String| extension(path) == Some("css");
+
+    let mut manifest = AssetManifest::default();
+
+    // Fonts and images first, so stylesheets can refer to their fingerprinted names
+    for asset in assets.iter().filter(|path| !is_css(path)) {
+        fingerprint_file(output_dir, asset, content_addressed, &mut manifest)?;
+    }
+
+    for stylesheet in assets.iter().filter(|path| is_css(path)) {
+        let file = output_dir.join(stylesheet);
+        let css = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let rewritten = rewrite_css_references(&css, parent_dir(stylesheet), &manifest);
+        if rewritten != css {
+            fs::write(&file, rewritten)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+        fingerprint_file(output_dir, stylesheet, content_addressed, &mut manifest)?;
+    }
+
+    for page in html_files(output_dir, output_dir)?.iter() {
+        let file = output_dir.join(page);
+        let html = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let rewritten =
+            rewrite_html_references(&html, &base_dir(&html, parent_dir(page)), &manifest);
+        if rewritten != html {
+            fs::write(&file, rewritten)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+    }
+
+    let service_worker = output_dir.join(SERVICE_WORKER);
+    if service_worker.is_file() {
+        let script = fs::read_to_string(&service_worker)
+            .with_context(|| format!("Failed to read {}", service_worker.display()))?;
+        fs::write(
+            &service_worker,
+            rewrite_service_worker_references(&script, &manifest),
+        )
+        .with_context(|| format!("Failed to write {}", service_worker.display()))?;
+    }
+
+    let manifest_path = output_dir.join(ASSET_MANIFEST_FILE);
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+    Ok(manifest)
+}
+
+/// Copy an asset to its fingerprinted name and record it in the manifest
+fn fingerprint_file(
+    output_dir: &Path,
+    asset: &str,
+    content_addressed: bool,
+    manifest: &mut AssetManifest,
+) -> Result<()> {
+    let source = output_dir.join(asset);
+    let content =
+        fs::read(&source).with_context(|| format!("Failed to read {}", source.display()))?;
+
+    let (fingerprinted, content) = if content_addressed {
+        let stored = store_path(asset, &content);
+        let content = if extension(asset) == Some("css") {
+            relocate_css_references(
+                &String::from_utf8_lossy(&content),
+                parent_dir(asset),
+                parent_dir(&stored),
+            )
+            .into_bytes()
+        } else {
+            content
+        };
+        (stored, content)
+    } else {
+        let hash = hex_prefix(&Sha256::digest(&content), HASH_LENGTH);
+        let fingerprinted = match asset.rsplit_once('.') {
+            Some((stem, ext)) => format!("{stem}.{hash}.{ext}"),
+            None => format!("{asset}.{hash}"),
+        };
+        (fingerprinted, content)
+    };
+
+    let target = output_dir.join(&fingerprinted);
+    // Identical files share their store entry, which only has to be written once
+    if !target.exists() {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&target, content)
+            .with_context(|| format!("Failed to write fingerprinted copy of {asset}"))?;
+    }
+    manifest.assets.insert(asset.to_string(), fingerprinted);
+    Ok(())
+}
+
+/// Path of an asset in the content-addressed store, e.g. `assets/3f/2a/3f2a….css`
+fn store_path(asset: &str, content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    // Relative references in a stylesheet depend on where it was
+    if extension(asset) == Some("css") {
+        hasher.update(parent_dir(asset));
+    }
+    let hash = hex_prefix(&hasher.finalize(), STORE_HASH_LENGTH);
+    let name = match extension(asset) {
+        Some(ext) => format!("{hash}.{}", ext.to_ascii_lowercase()),
+        None => hash.clone(),
+    };
+    format!("{ASSET_STORE_DIR}/{}/{}/{name}", &hash[..2], &hash[2..4])
+}
+
+/// Rewrite `href` and `src` attributes that point to fingerprinted assets
+///
+/// # Arguments
+///
+/// * `html` - A generated page
+/// * `page_dir` - Directory of the page, relative to the output directory
+/// * `manifest` - The fingerprinted assets
+///
+/// # Returns
+///
+/// The page with references to the fingerprinted copies
+pub fn rewrite_html_references(html: &str, page_dir: &str, manifest: &AssetManifest) -> String {
+    // Only attributes inside real tags, not escaped markup in code blocks
+    html_tag_regex()
+        .replace_all(html, |tag: &Captures| {
+            reference_attribute_regex()
+                .replace_all(&tag[0], |attribute: &Captures| {
+                    format!(
+                        "{}{quote}{}{quote}",
+                        &attribute["prefix"],
+                        rewrite_reference(&attribute["url"], page_dir, manifest),
+                        quote = &attribute["quote"]
+                    )
+                })
+                .into_owned()
+        })
+        .into_owned()
+}
+
+/// Rewrite `url()` references in a stylesheet
+fn rewrite_css_references(css: &str, css_dir: &str, manifest: &AssetManifest) -> String {
+    css_url_regex()
+        .replace_all(css, |captures: &Captures| {
+            format!(
+                "url({quote}{}{quote})",
+                rewrite_reference(&captures["url"], css_dir, manifest),
+                quote = &captures["quote"]
+            )
+        })
+        .into_owned()
+}
+
+/// Rewrite the site-absolute URLs in the service worker's cache list
+fn rewrite_service_worker_references(script: &str, manifest: &AssetManifest) -> String {
+    service_worker_url_regex()
+        .replace_all(script, |captures: &Captures| {
+            format!("'{}'", rewrite_reference(&captures["url"], "", manifest))
+        })
+        .into_owned()
+}
+
+/// Point the relative `url()` references of a stylesheet copy at the same files
+/// from another directory
+fn relocate_css_references(css: &str, from_dir: &str, to_dir: &str) -> String {
+    css_url_regex()
+        .replace_all(css, |captures: &Captures| {
+            let url = &captures["url"];
+            let relocated = (!is_external(url) && !url.starts_with('/') && !url.starts_with('#'))
+                .then(|| resolve_path(from_dir, url))
+                .flatten()
+                .map(|path| relative_path(to_dir, &path));
+            format!(
+                "url({quote}{}{quote})",
+                relocated.as_deref().unwrap_or(url),
+                quote = &captures["quote"]
+            )
+        })
+        .into_owned()
+}
+
+/// Point a single URL at the fingerprinted copy of its asset
+///
+/// Root-relative URLs stay root-relative and relative ones stay relative to
+/// `base_dir`. The cache-busting query string is dropped. URLs of other files
+/// are returned as they are.
+fn rewrite_reference(url: &str, base_dir: &str, manifest: &AssetManifest) -> String {
+    if is_external(url) {
+        return url.to_string();
+    }
+
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url, None),
+    };
+    let path = path.split('?').next().unwrap_or(path);
+
+    let fingerprinted = resolve_path(base_dir, path)
+        .and_then(|key| manifest.fingerprinted(&key))
+        .map(|fingerprinted| match path.starts_with('/') {
+            true => format!("/{fingerprinted}"),
+            false => relative_path(base_dir, fingerprinted),
+        });
+
+    match (fingerprinted, fragment) {
+        (Some(fingerprinted), Some(fragment)) => format!("{fingerprinted}#{fragment}"),
+        (Some(fingerprinted), None) => fingerprinted,
+        (None, _) => url.to_string(),
+    }
+}
+
+fn is_external(url: &str) -> bool {
+    url.starts_with("//") || url.contains("://") || url.starts_with("data:")
+}
+
+/// Directory relative URLs on a page resolve against, honoring `<base href>`
+pub(crate) fn base_dir(html: &str, page_dir: &str) -> String {
+    static BASE: OnceLock<Regex> = OnceLock::new();
+    let base = BASE.get_or_init(|| {
+        Regex::new(r#"(?i)<base\s[^>]*href=["']?([^"'\s>]*)"#).expect("valid base regex")
+    });
+    match base.captures(html).map(|captures| captures[1].to_string()) {
+        // An absolute base is the site's own URL
+        Some(href) if href.contains("://") => String::new(),
+        Some(href) => resolve_path(page_dir, &href).unwrap_or_default(),
+        None => page_dir.to_string(),
+    }
+}
+
+/// The relative URL of a file from a directory, both relative to the output directory
+pub(crate) fn relative_path(from_dir: &str, path: &str) -> String {
+    let from = from_dir
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let to = path.split('/').collect::<Vec<_>>();
+    // The file name itself is never shared with the directory
+    let common = from
+        .iter()
+        .zip(&to[..to.len() - 1])
+        .take_while(|(from, to)| from == to)
+        .count();
+
+    std::iter::repeat_n("..", from.len() - common)
+        .chain(to[common..].iter().copied())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Resolve a URL path against a directory, both relative to the output directory
+///
+/// # Returns
+///
+/// The normalized path, or None if it leaves the output directory
+pub(crate) fn resolve_path(base_dir: &str, path: &str) -> Option<String> {
+    let joined = match path.strip_prefix('/') {
+        Some(rooted) => rooted.to_string(),
+        None => format!("{base_dir}/{path}"),
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in joined.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(segments.join("/"))
+}
+
+/// Recursively list the assets to fingerprint, relative to the output directory
+///
+/// Images are only listed for the content-addressed store.
+fn asset_files(output_dir: &Path, dir: &Path, with_images: bool) -> Result<Vector<String>> {
+    list_files(output_dir, dir, &|relative| {
+        relative != SERVICE_WORKER
+            && !is_fingerprinted(relative)
+            && (relative == SPRITE_FILE
+                || extension(relative).is_some_and(|ext| {
+                    ext == "css"
+                        || ext == "js"
+                        || FONT_EXTENSIONS.contains(&ext)
+                        || (with_images
+                            && IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+                }))
+    })
+}
+
+/// Recursively list the stylesheets, relative to the output directory
+pub(crate) fn css_files(output_dir: &Path, dir: &Path) -> Result<Vector<String>> {
+    list_files(output_dir, dir, &|relative| {
+        extension(relative) == Some("css")
+    })
+}
+
+/// Recursively list the HTML pages, relative to the output directory
+pub(crate) fn html_files(output_dir: &Path, dir: &Path) -> Result<Vector<String>> {
+    list_files(output_dir, dir, &|relative| {
+        extension(relative) == Some("html")
+    })
+}
+
+fn list_files(
+    output_dir: &Path,
+    dir: &Path,
+    include: &dyn Fn(&str) -> bool,
+) -> Result<Vector<String>> {
+    if !dir.is_dir() {
+        return Ok(Vector::new());
+    }
+
+    fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        .map(|entry| {
+            let path: PathBuf = entry?.path();
+            if path.is_dir() {
+                return list_files(output_dir, &path, include);
+            }
+            let relative = path
+                .strip_prefix(output_dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Ok(if include(&relative) {
+                Vector::unit(relative)
+            } else {
+                Vector::new()
+            })
+        })
+        .try_fold(Vector::new(), |mut files, found| {
+            files.append(found?);
+            Ok(files)
+        })
+}
+
+/// Whether a file name already carries a content hash, e.g. from an earlier build
+pub(crate) fn is_fingerprinted(path: &str) -> bool {
+    is_stored(path)
+        || path
+            .rsplit('/')
+            .next()
+            .and_then(|name| name.rsplit('.').nth(1))
+            .is_some_and(|segment| {
+                segment.len() == HASH_LENGTH && segment.chars().all(|c| c.is_ascii_hexdigit())
+            })
+}
+
+/// Whether a file is an entry of the content-addressed store
+pub(crate) fn is_stored(path: &str) -> bool {
+    let segments = path.split('/').collect::<Vec<_>>();
+    let [ASSET_STORE_DIR, first, second, name] = segments.as_slice() else {
+        return false;
+    };
+    let hash = name.split('.').next().unwrap_or(name);
+    hash.len() == STORE_HASH_LENGTH
+        && hash.chars().all(|c| c.is_ascii_hexdigit())
+        && hash.starts_with(&format!("{first}{second}"))
+}
+
+fn extension(path: &str) -> Option<&str> {
+    Path::new(path).extension().and_then(|ext| ext.to_str())
+}
+
+pub(crate) fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map_or("", |end| &path[..end])
+}
+
+fn hex_prefix(bytes: &[u8], length: usize) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>()
+        .chars()
+        .take(length)
+        .collect()
+}
+
+/// Matches opening HTML tags
+fn html_tag_regex() -> &'static Regex {
+    static HTML_TAG: OnceLock<Regex> = OnceLock::new();
+    HTML_TAG.get_or_init(|| Regex::new(r"<[a-zA-Z][^<>]*>").unwrap())
+}
+
+/// Matches `href` and `src` attributes, quoted or unquoted as minified HTML has them
+fn reference_attribute_regex() -> &'static Regex {
+    static REFERENCE_ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    REFERENCE_ATTRIBUTE.get_or_init(|| {
+        Regex::new(r#"(?i)(?P<prefix>\s(?:href|src)=)(?P<quote>["']?)(?P<url>[^"'\s>]*)["']?"#)
+            .unwrap()
+    })
+}
+
+/// Matches `url(...)` in stylesheets, with or without quotes
+fn css_url_regex() -> &'static Regex {
+    static CSS_URL: OnceLock<Regex> = OnceLock::new();
+    CSS_URL.get_or_init(|| {
+        Regex::new(r#"url\(\s*(?P<quote>['"]?)(?P<url>[^'")\s]+)['"]?\s*\)"#).unwrap()
+    })
+}
+
+/// Matches single-quoted site-absolute URLs, as in the service worker's cache list
+fn service_worker_url_regex() -> &'static Regex {
+    static SERVICE_WORKER_URL: OnceLock<Regex> = OnceLock::new();
+    SERVICE_WORKER_URL.get_or_init(|| Regex::new(r"'(?P<url>/[^'\s]*)'").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn manifest() -> AssetManifest {
+        AssetManifest {
+            assets: OrdMap::unit(
+                "css/main.min.css".to_string(),
+                "css/main.min.0123abcd.css".to_string(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_rewrite_html_references() {
+        let html = r#"<link rel="stylesheet" href="css/main.min.css?v=1a2b">
+<a href="css/main.min.css#top">raw</a> <a href="https://cdn.example.com/css/main.min.css">cdn</a> <img src="img/photo.png">
+<pre><code>&lt;link href="css/main.min.css"&gt;</code></pre>"#;
+
+        let rewritten = rewrite_html_references(html, "", &manifest());
+        assert!(rewritten.contains(r#"href="css/main.min.0123abcd.css">"#));
+        assert!(rewritten.contains(r#"href="css/main.min.0123abcd.css#top""#));
+        assert!(rewritten.contains(r#"href="https://cdn.example.com/css/main.min.css""#));
+        assert!(rewritten.contains(r#"src="img/photo.png""#));
+        assert!(rewritten.contains(r#"&lt;link href="css/main.min.css"&gt;"#));
+
+        // Pages in subdirectories refer to the same asset relatively or from the root
+        let nested = r#"<link href="../css/main.min.css"><link href=/css/main.min.css>"#;
+        assert_eq!(
+            rewrite_html_references(nested, "blog", &manifest()),
+            r#"<link href="../css/main.min.0123abcd.css"><link href=/css/main.min.0123abcd.css>"#
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_assets() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for subdir in ["css", "fonts", "js"] {
+            fs::create_dir(root.join(subdir)).unwrap();
+        }
+        fs::write(root.join("fonts/mono.woff2"), "font").unwrap();
+        fs::write(
+            root.join("css/main.css"),
+            r#"@font-face{src:url("../fonts/mono.woff2") format("woff2")}"#,
+        )
+        .unwrap();
+        fs::write(root.join("js/app.js"), "run()").unwrap();
+        fs::write(root.join("js/app.0badf00d.js"), "stale").unwrap();
+        fs::write(
+            root.join(SERVICE_WORKER),
+            "const urls = ['/', '/js/app.js'];",
+        )
+        .unwrap();
+        fs::write(
+            root.join("index.html"),
+            r#"<link href="css/main.css"><script src="js/app.js"></script>"#,
+        )
+        .unwrap();
+
+        let manifest = fingerprint_assets(root, false).unwrap();
+
+        assert_eq!(manifest.assets.len(), 3);
+        let font = manifest.fingerprinted("fonts/mono.woff2").unwrap();
+        let css = manifest.fingerprinted("css/main.css").unwrap();
+        let js = manifest.fingerprinted("js/app.js").unwrap();
+        assert!(is_fingerprinted(font) && is_fingerprinted(css) && is_fingerprinted(js));
+
+        // Stylesheets refer to the fingerprinted fonts, and are hashed after that
+        let font_name = font.rsplit('/').next().unwrap();
+        assert!(fs::read_to_string(root.join(css))
+            .unwrap()
+            .contains(&format!("../fonts/{font_name}")));
+
+        let html = fs::read_to_string(root.join("index.html")).unwrap();
+        assert!(html.contains(&format!(r#"href="{css}""#)));
+        assert!(html.contains(&format!(r#"src="{js}""#)));
+        assert_eq!(
+            fs::read_to_string(root.join(SERVICE_WORKER)).unwrap(),
+            format!("const urls = ['/', '/{js}'];")
+        );
+
+        let written: AssetManifest =
+            serde_json::from_str(&fs::read_to_string(root.join(ASSET_MANIFEST_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(written, manifest);
+    }
+
+    #[test]
+    fn test_content_addressed_store() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        for subdir in ["css", "img", "projects"] {
+            fs::create_dir(root.join(subdir)).unwrap();
+        }
+        fs::write(root.join("img/logo.png"), "png").unwrap();
+        fs::write(root.join("img/logo-copy.png"), "png").unwrap();
+        fs::write(
+            root.join("css/main.css"),
+            r#"body{background:url(../img/logo.png)}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("index.html"),
+            r#"<link href="css/main.css"><img src="img/logo-copy.png">"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("projects/cv.html"),
+            r#"<base href="../"><link href="css/main.css"><img src="/img/logo.png">"#,
+        )
+        .unwrap();
+
+        let manifest = fingerprint_assets(root, true).unwrap();
+
+        // Identical images share one store entry
+        let logo = manifest.fingerprinted("img/logo.png").unwrap();
+        assert_eq!(manifest.fingerprinted("img/logo-copy.png"), Some(logo));
+        assert!(is_stored(logo) && is_fingerprinted(logo));
+        assert_eq!(fs::read_to_string(root.join(logo)).unwrap(), "png");
+
+        // The stored stylesheet refers to the stored image from its own directory
+        let css = manifest.fingerprinted("css/main.css").unwrap();
+        assert!(is_stored(css));
+        let stored_css = fs::read_to_string(root.join(css)).unwrap();
+        let from_css = relative_path(parent_dir(css), logo);
+        assert!(stored_css.contains(&format!("url({from_css})")));
+        assert_eq!(
+            resolve_path(parent_dir(css), &from_css).as_deref(),
+            Some(logo)
+        );
+
+        let html = fs::read_to_string(root.join("index.html")).unwrap();
+        assert!(html.contains(&format!(r#"href="{css}""#)));
+        assert!(html.contains(&format!(r#"src="{logo}""#)));
+        let project = fs::read_to_string(root.join("projects/cv.html")).unwrap();
+        assert!(project.contains(&format!(r#"href="{css}""#)));
+        assert!(project.contains(&format!(r#"src="/{logo}""#)));
+
+        // A second build leaves the store alone
+        let again = fingerprint_assets(root, true).unwrap();
+        assert_eq!(again.fingerprinted("img/logo.png"), Some(logo));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("", "css/main.css"), "css/main.css");
+        assert_eq!(relative_path("blog", "css/main.css"), "../css/main.css");
+        assert_eq!(
+            relative_path("assets/3f/2a", "assets/0b/1c/font.woff2"),
+            "../../0b/1c/font.woff2"
+        );
+        assert_eq!(relative_path("css", "css/main.css"), "main.css");
+        assert_eq!(relative_path("css", "css"), "../css");
+    }
+}
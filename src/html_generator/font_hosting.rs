@@ -0,0 +1,731 @@
+//! Self-hosted fonts
+//!
+//! With `fonts.self_host`, the Google Fonts stylesheets the site loads are
+//! replaced by local copies once the static assets are in place. Google Fonts
+//! splits every font into woff2 files by Unicode range (`latin`,
+//! `latin-ext`, `cyrillic`, ...); only the ranges holding characters that
+//! appear on the site's pages are downloaded, into `fonts/`. A stylesheet of
+//! matching `@font-face` rules takes the place of each Google Fonts `@import`
+//! or `<link>`, written next to the stylesheet that imported it so bundling
+//! keeps its URLs valid.
+//!
+//! The pages' preconnect hints to Google Fonts are replaced by preload hints
+//! for the regular Latin files. Nothing is loaded from Google anymore, so the
+//! derived Content-Security-Policy only allows fonts from the site itself.
+//!
+//! Downloads are kept in `cache/fonts/`, so later builds don't download them
+//! again. Offline builds leave the stylesheets that aren't cached as they are.
+
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use super::filters::slug_str;
+use super::fingerprint::{base_dir, css_files, html_files, parent_dir, relative_path};
+use crate::content_security_policy::tag_attributes;
+
+/// Default directory for downloaded font stylesheets and files
+pub const DEFAULT_FONT_CACHE_DIR: &str = "cache/fonts";
+
+/// Directory in the output the font files are written to
+pub const FONTS_DIR: &str = "fonts";
+
+/// Origins of Google Fonts stylesheets and font files
+const GOOGLE_FONTS_ORIGINS: [&str; 2] =
+    ["https://fonts.googleapis.com", "https://fonts.gstatic.com"];
+
+/// A browser with woff2 support, which Google Fonts serves split by Unicode range
+const WOFF2_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+
+/// A face and the content of its font file
+type DownloadedFace = (FontFace, Vec<u8>);
+
+/// An `@font-face` rule of a Google Fonts stylesheet
+#[derive(Debug, Clone, PartialEq)]
+struct FontFace {
+    /// Name of the Unicode range, from the comment before the rule
+    subset: Option<String>,
+    /// Declarations other than `src`, in order
+    declarations: Vec<(String, String)>,
+    /// URL of the font file
+    url: String,
+}
+
+impl FontFace {
+    fn declaration(&self, name: &str) -> Option<&str> {
+        self.declarations
+            .iter()
+            .find(|(property, _)| property == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn family(&self) -> &str {
+        self.declaration("font-family")
+            .unwrap_or_default()
+            .trim_matches(['\'', '"'])
+    }
+
+    /// Whether the face's Unicode range holds one of the characters
+    fn covers(&self, characters: &BTreeSet<char>) -> bool {
+        let Some(ranges) = self.declaration("unicode-range").map(unicode_ranges) else {
+            return true;
+        };
+        characters.iter().any(|&character| {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&(character as u32)))
+        })
+    }
+
+    /// Whether the face renders regular Latin text, and is worth preloading
+    fn is_regular_latin(&self) -> bool {
+        let weights: Vec<u32> = self
+            .declaration("font-weight")
+            .unwrap_or("400")
+            .split_whitespace()
+            .filter_map(|weight| match weight {
+                "normal" => Some(400),
+                weight => weight.parse().ok(),
+            })
+            .collect();
+        let regular = match weights.as_slice() {
+            [weight] => *weight == 400,
+            [lightest, boldest] => (*lightest..=*boldest).contains(&400),
+            _ => false,
+        };
+        regular
+            && self.declaration("font-style").unwrap_or("normal") == "normal"
+            && self
+                .subset
+                .as_deref()
+                .is_none_or(|subset| subset == "latin")
+    }
+
+    /// File name of the face in the output, unique per font file
+    fn file_name(&self) -> String {
+        let name = [
+            self.family(),
+            self.declaration("font-style").unwrap_or_default(),
+            self.declaration("font-weight").unwrap_or_default(),
+            self.subset.as_deref().unwrap_or_default(),
+        ]
+        .join(" ");
+        format!("{}-{}.woff2", slug_str(&name), &url_hash(&self.url)[..8])
+    }
+
+    /// The rule, loading the font file from a URL
+    fn to_css(&self, url: &str) -> String {
+        let declarations = self
+            .declarations
+            .iter()
+            .map(|(property, value)| format!("  {property}: {value};\n"))
+            .collect::<String>();
+        let comment = self
+            .subset
+            .as_ref()
+            .map(|subset| format!("/* {subset} */\n"))
+            .unwrap_or_default();
+        format!(
+            "{comment}@font-face {{\n{declarations}  src: url(\"{url}\") format(\"woff2\");\n}}\n"
+        )
+    }
+}
+
+/// Download Google Fonts and serve them with the site
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site, with its static assets
+/// * `cache_dir` - Directory the downloads are kept in
+/// * `offline` - Only use the stylesheets and fonts found in the cache
+///
+/// # Returns
+///
+/// The number of font files written
+pub fn self_host_fonts(output_dir: &Path, cache_dir: &Path, offline: bool) -> Result<usize> {
+    let read = |file: &String| {
+        let path = output_dir.join(file);
+        fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))
+            .map(|content| (file.clone(), content))
+    };
+    let stylesheets = css_files(output_dir, output_dir)?
+        .iter()
+        .map(read)
+        .collect::<Result<Vec<_>>>()?;
+    let pages = html_files(output_dir, output_dir)?
+        .iter()
+        .map(read)
+        .collect::<Result<Vec<_>>>()?;
+
+    let urls: BTreeSet<String> = stylesheets
+        .iter()
+        .flat_map(|(_, css)| css_imports(css))
+        .chain(pages.iter().flat_map(|(_, html)| page_stylesheets(html)))
+        .collect();
+    if urls.is_empty() {
+        return Ok(0);
+    }
+
+    // Text filled in by scripts is mostly ASCII, so that is always kept
+    let mut characters: BTreeSet<char> = (' '..='~').collect();
+    for (_, html) in &pages {
+        characters.extend(used_characters(html));
+    }
+
+    let downloader = Downloader::new(cache_dir, offline)?;
+    let fonts_dir = output_dir.join(FONTS_DIR);
+    let mut hosted: BTreeMap<String, (String, Vec<FontFace>)> = BTreeMap::new();
+    let mut written = BTreeSet::new();
+    for (url, faces) in download_stylesheets(&downloader, &urls, &characters) {
+        let faces = match faces {
+            Ok(faces) => faces,
+            Err(e) => {
+                println!("⚠️  Not self-hosting {}: {:#}", url, e);
+                continue;
+            }
+        };
+        fs::create_dir_all(&fonts_dir)
+            .with_context(|| format!("Failed to create {}", fonts_dir.display()))?;
+        for (face, content) in &faces {
+            let path = fonts_dir.join(face.file_name());
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            written.insert(face.file_name());
+        }
+
+        let faces: Vec<FontFace> = faces.into_iter().map(|(face, _)| face).collect();
+        let families: BTreeSet<&str> = faces.iter().map(FontFace::family).collect();
+        let mut name = format!("fonts-{}", slug_str(&Vec::from_iter(families).join(" ")));
+        if hosted
+            .values()
+            .any(|(taken, _)| *taken == format!("{name}.css"))
+        {
+            name = format!("{name}-{}", &url_hash(&url)[..8]);
+        }
+        hosted.insert(url, (format!("{name}.css"), faces));
+    }
+    if hosted.is_empty() {
+        return Ok(0);
+    }
+
+    // Each stylesheet imports a copy next to it, so its URLs survive bundling
+    let mut copies = BTreeSet::new();
+    for (file, css) in &stylesheets {
+        let dir = parent_dir(file);
+        let rewritten = rewrite_css_imports(css, |url| {
+            let (name, _) = hosted.get(url)?;
+            copies.insert((dir.to_string(), url.to_string()));
+            Some(name.clone())
+        });
+        if rewritten != *css {
+            fs::write(output_dir.join(file), rewritten)
+                .with_context(|| format!("Failed to write {file}"))?;
+        }
+    }
+
+    let preloads: BTreeSet<String> = hosted
+        .values()
+        .flat_map(|(_, faces)| faces.iter().filter(|face| face.is_regular_latin()))
+        .map(|face| format!("{FONTS_DIR}/{}", face.file_name()))
+        .collect();
+    let all_hosted = hosted.len() == urls.len();
+    for (file, html) in &pages {
+        let rewritten = rewrite_page(html, parent_dir(file), &preloads, all_hosted, |url| {
+            let (name, _) = hosted.get(url)?;
+            copies.insert((FONTS_DIR.to_string(), url.to_string()));
+            Some(format!("{FONTS_DIR}/{name}"))
+        });
+        if rewritten != *html {
+            fs::write(output_dir.join(file), rewritten)
+                .with_context(|| format!("Failed to write {file}"))?;
+        }
+    }
+
+    for (dir, url) in copies {
+        let (name, faces) = &hosted[&url];
+        let css = faces
+            .iter()
+            .map(|face| {
+                face.to_css(&relative_path(
+                    &dir,
+                    &format!("{FONTS_DIR}/{}", face.file_name()),
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let path = output_dir.join(&dir).join(name);
+        fs::write(&path, css).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(written.len())
+}
+
+/// Download stylesheets and the font files of the faces covering the characters
+///
+/// # Returns
+///
+/// Per stylesheet URL, the faces with the content of their font files, or why
+/// they couldn't be downloaded
+fn download_stylesheets(
+    downloader: &Downloader,
+    urls: &BTreeSet<String>,
+    characters: &BTreeSet<char>,
+) -> Vec<(String, Result<Vec<DownloadedFace>>)> {
+    urls.par_iter()
+        .map(|url| {
+            let faces = downloader.get(url, "css").and_then(|css| {
+                let css = String::from_utf8(css).context("The stylesheet isn't UTF-8")?;
+                parse_font_faces(&css)
+                    .into_par_iter()
+                    .filter(|face| face.covers(characters))
+                    .map(|face| {
+                        let content = downloader.get(&face.url, "woff2")?;
+                        Ok((face, content))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            });
+            (url.clone(), faces)
+        })
+        .collect()
+}
+
+/// Downloads files once, keeping them in a cache directory
+struct Downloader<'a> {
+    cache_dir: &'a Path,
+    offline: bool,
+    client: reqwest::Client,
+    runtime: Option<tokio::runtime::Handle>,
+}
+
+impl<'a> Downloader<'a> {
+    fn new(cache_dir: &'a Path, offline: bool) -> Result<Self> {
+        Ok(Downloader {
+            cache_dir,
+            offline,
+            client: reqwest::Client::builder()
+                .user_agent(WOFF2_USER_AGENT)
+                .timeout(Duration::from_secs(30))
+                .build()?,
+            runtime: tokio::runtime::Handle::try_current().ok(),
+        })
+    }
+
+    /// The content of a URL, from the cache or downloaded
+    ///
+    /// Call this from pool threads: they block on the build's runtime, which
+    /// its own threads can't.
+    fn get(&self, url: &str, extension: &str) -> Result<Vec<u8>> {
+        let cached = self
+            .cache_dir
+            .join(format!("{}.{extension}", &url_hash(url)[..16]));
+        if let Ok(content) = fs::read(&cached) {
+            return Ok(content);
+        }
+        if self.offline {
+            anyhow::bail!("{url} isn't cached for offline builds");
+        }
+        let runtime = self
+            .runtime
+            .as_ref()
+            .context("Downloading fonts needs the async runtime")?;
+        let content = runtime
+            .block_on(async {
+                let response = self.client.get(url).send().await?.error_for_status()?;
+                response.bytes().await
+            })
+            .with_context(|| format!("Failed to download {url}"))?;
+
+        fs::create_dir_all(self.cache_dir).with_context(|| {
+            format!(
+                "Failed to create cache directory: {}",
+                self.cache_dir.display()
+            )
+        })?;
+        fs::write(&cached, &content)
+            .with_context(|| format!("Failed to write {}", cached.display()))?;
+        Ok(content.to_vec())
+    }
+}
+
+/// The `@font-face` rules of a stylesheet
+fn parse_font_faces(css: &str) -> Vec<FontFace> {
+    static FONT_FACE: OnceLock<Regex> = OnceLock::new();
+    let font_face = FONT_FACE.get_or_init(|| {
+        Regex::new(r"(?s)(?:/\*\s*([\w-]+)\s*\*/\s*)?@font-face\s*\{([^}]*)\}")
+            .expect("valid font-face regex")
+    });
+
+    font_face
+        .captures_iter(css)
+        .filter_map(|captures| {
+            let (sources, declarations): (Vec<_>, Vec<_>) = captures[2]
+                .split(';')
+                .filter_map(|declaration| declaration.split_once(':'))
+                .map(|(property, value)| {
+                    (
+                        property.trim().to_ascii_lowercase(),
+                        value.trim().to_string(),
+                    )
+                })
+                .partition(|(property, _)| property == "src");
+            let url = sources
+                .iter()
+                .find_map(|(_, src)| css_url_regex().captures(src))
+                .map(|url| url[1].to_string())?;
+            Some(FontFace {
+                subset: captures.get(1).map(|subset| subset.as_str().to_string()),
+                declarations,
+                url,
+            })
+        })
+        .collect()
+}
+
+/// The code point ranges of a `unicode-range` value, e.g. `U+0000-00FF, U+4??`
+fn unicode_ranges(value: &str) -> Vec<(u32, u32)> {
+    value
+        .split(',')
+        .filter_map(|range| {
+            let range = range.trim();
+            let range = range.strip_prefix("U+").or(range.strip_prefix("u+"))?;
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (start.to_string(), end.to_string()),
+                None => (range.replace('?', "0"), range.replace('?', "F")),
+            };
+            Some((
+                u32::from_str_radix(&start, 16).ok()?,
+                u32::from_str_radix(&end, 16).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// The characters of a page's text
+fn used_characters(html: &str) -> BTreeSet<char> {
+    static MARKUP: OnceLock<Regex> = OnceLock::new();
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let markup = MARKUP.get_or_init(|| {
+        Regex::new(r"(?is)<script\b.*?</script>|<style\b.*?</style>|<[^>]*>")
+            .expect("valid markup regex")
+    });
+    let reference = REFERENCE.get_or_init(|| {
+        Regex::new(r"&#(?:[xX]([0-9a-fA-F]+)|([0-9]+));").expect("valid reference regex")
+    });
+
+    let text = markup.replace_all(html, " ");
+    let referenced = reference.captures_iter(&text).filter_map(|captures| {
+        let code = match (captures.get(1), captures.get(2)) {
+            (Some(hex), _) => u32::from_str_radix(hex.as_str(), 16).ok()?,
+            (_, Some(decimal)) => decimal.as_str().parse().ok()?,
+            _ => return None,
+        };
+        char::from_u32(code)
+    });
+    text.chars()
+        .filter(|character| !character.is_control())
+        .chain(referenced)
+        .collect()
+}
+
+/// The Google Fonts stylesheets a stylesheet imports
+fn css_imports(css: &str) -> Vec<String> {
+    google_import_regex()
+        .captures_iter(css)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// The Google Fonts stylesheets a page links
+fn page_stylesheets(html: &str) -> Vec<String> {
+    link_regex()
+        .find_iter(html)
+        .map(|tag| tag_attributes(tag.as_str()))
+        .filter(|attributes| {
+            attributes
+                .get("rel")
+                .is_some_and(|rel| rel.to_ascii_lowercase().contains("stylesheet"))
+        })
+        .filter_map(|attributes| {
+            attributes
+                .get("href")
+                .map(|href| href.replace("&amp;", "&"))
+        })
+        .filter(|href| href.starts_with(GOOGLE_FONTS_ORIGINS[0]))
+        .collect()
+}
+
+/// Replace the Google Fonts imports of a stylesheet
+///
+/// # Arguments
+///
+/// * `css` - The stylesheet
+/// * `replacement` - The URL to import instead of a Google Fonts stylesheet
+///
+/// # Returns
+///
+/// The stylesheet, with the imports that have a replacement replaced
+fn rewrite_css_imports(css: &str, mut replacement: impl FnMut(&str) -> Option<String>) -> String {
+    google_import_regex()
+        .replace_all(css, |captures: &Captures| match replacement(&captures[1]) {
+            Some(url) => format!("@import url(\"{url}\");"),
+            None => captures[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Point a page's Google Fonts links at their copies, and update its hints
+///
+/// # Arguments
+///
+/// * `html` - The page
+/// * `page_dir` - Directory of the page, relative to the output directory
+/// * `preloads` - Font files to preload, relative to the output directory
+/// * `drop_preconnects` - Whether to remove the preconnect hints to Google Fonts
+/// * `replacement` - The copy of a Google Fonts stylesheet, relative to the output directory
+///
+/// # Returns
+///
+/// The rewritten page
+fn rewrite_page(
+    html: &str,
+    page_dir: &str,
+    preloads: &BTreeSet<String>,
+    drop_preconnects: bool,
+    mut replacement: impl FnMut(&str) -> Option<String>,
+) -> String {
+    let base = base_dir(html, page_dir);
+    let linked = link_regex().replace_all(html, |captures: &Captures| {
+        let tag = &captures[0];
+        let attributes = tag_attributes(tag);
+        let rel = attributes
+            .get("rel")
+            .map(|rel| rel.to_ascii_lowercase())
+            .unwrap_or_default();
+        let Some(href) = attributes.get("href") else {
+            return tag.to_string();
+        };
+        if rel.contains("stylesheet") {
+            return match replacement(&href.replace("&amp;", "&")) {
+                Some(copy) => tag.replacen(href.as_str(), &relative_path(&base, &copy), 1),
+                None => tag.to_string(),
+            };
+        }
+        let is_hint = rel.contains("preconnect") || rel.contains("dns-prefetch");
+        if drop_preconnects && is_hint && GOOGLE_FONTS_ORIGINS.contains(&href.trim_end_matches('/'))
+        {
+            return String::new();
+        }
+        tag.to_string()
+    });
+
+    let has_stylesheet = link_regex().find_iter(&linked).any(|tag| {
+        tag_attributes(tag.as_str())
+            .get("rel")
+            .is_some_and(|rel| rel.to_ascii_lowercase().contains("stylesheet"))
+    });
+    match linked.find("</head>") {
+        Some(head_end) if has_stylesheet && !preloads.is_empty() => {
+            let hints = preloads
+                .iter()
+                .map(|font| {
+                    format!(
+                        "<link rel=\"preload\" href=\"{}\" as=\"font\" type=\"font/woff2\" crossorigin>",
+                        relative_path(&base, font)
+                    )
+                })
+                .collect::<String>();
+            format!("{}{hints}{}", &linked[..head_end], &linked[head_end..])
+        }
+        _ => linked.into_owned(),
+    }
+}
+
+fn url_hash(url: &str) -> String {
+    Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn google_import_regex() -> &'static Regex {
+    static IMPORT: OnceLock<Regex> = OnceLock::new();
+    IMPORT.get_or_init(|| {
+        Regex::new(
+            r#"@import\s+(?:url\(\s*)?['"]?(https://fonts\.googleapis\.com/[^'")\s]+)['"]?\s*\)?[^;]*;"#,
+        )
+        .expect("valid import regex")
+    })
+}
+
+fn css_url_regex() -> &'static Regex {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    URL.get_or_init(|| {
+        Regex::new(r#"url\(\s*['"]?([^'")\s]+)['"]?\s*\)"#).expect("valid url regex")
+    })
+}
+
+fn link_regex() -> &'static Regex {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    LINK.get_or_init(|| Regex::new(r"(?i)<link\b[^>]*>").expect("valid link regex"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTER_URL: &str =
+        "https://fonts.googleapis.com/css2?family=Inter:wght@400;700&display=swap";
+
+    const INTER_CSS: &str = r#"/* cyrillic */
+@font-face {
+  font-family: 'Inter';
+  font-style: normal;
+  font-weight: 400 700;
+  font-display: swap;
+  src: url(https://fonts.gstatic.com/s/inter/v18/cyrillic.woff2) format('woff2');
+  unicode-range: U+0301, U+0400-045F, U+0490-0491;
+}
+/* latin */
+@font-face {
+  font-family: 'Inter';
+  font-style: normal;
+  font-weight: 400 700;
+  font-display: swap;
+  src: url(https://fonts.gstatic.com/s/inter/v18/latin.woff2) format('woff2');
+  unicode-range: U+0000-00FF, U+0131, U+2000-206F, U+20??;
+}
+"#;
+
+    #[test]
+    fn test_parse_font_faces() {
+        let faces = parse_font_faces(INTER_CSS);
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[1].subset.as_deref(), Some("latin"));
+        assert_eq!(faces[1].family(), "Inter");
+        assert_eq!(
+            faces[1].url,
+            "https://fonts.gstatic.com/s/inter/v18/latin.woff2"
+        );
+        assert!(faces[1].is_regular_latin());
+        assert!(!faces[0].is_regular_latin());
+        assert!(faces[1]
+            .file_name()
+            .starts_with("inter-normal-400-700-latin-"));
+
+        let latin: BTreeSet<char> = "Café — €".chars().collect();
+        assert!(faces[1].covers(&latin));
+        assert!(!faces[0].covers(&latin));
+        assert!(faces[0].covers(&"Привет".chars().collect()));
+        assert_eq!(
+            unicode_ranges("U+0000-00FF, U+20??"),
+            vec![(0, 0xFF), (0x2000, 0x20FF)]
+        );
+    }
+
+    #[test]
+    fn test_used_characters() {
+        let characters = used_characters(
+            "<html><head><style>.ж{}</style><script>let ы = 1;</script></head>\
+             <body><p title=\"Ω\">Über &#x41F;</p></body></html>",
+        );
+        assert!(characters.contains(&'Ü'));
+        assert!(characters.contains(&'П'));
+        assert!(!characters.contains(&'ж'));
+        assert!(!characters.contains(&'ы'));
+        assert!(!characters.contains(&'Ω'));
+    }
+
+    #[test]
+    fn test_rewrite_references() {
+        let css = format!("@import url('{INTER_URL}');\n@import \"base.css\";\n");
+        assert_eq!(
+            rewrite_css_imports(&css, |url| (url == INTER_URL)
+                .then(|| "fonts-inter.css".into())),
+            "@import url(\"fonts-inter.css\");\n@import \"base.css\";\n"
+        );
+
+        let html = format!(
+            "<html><head><link rel=\"preconnect\" href=\"https://fonts.gstatic.com\" crossorigin>\
+             <link rel=\"stylesheet\" href=\"{}\"></head><body></body></html>",
+            INTER_URL.replace('&', "&amp;")
+        );
+        let preloads = BTreeSet::from(["fonts/inter-latin.woff2".to_string()]);
+        let rewritten = rewrite_page(&html, "blog", &preloads, true, |url| {
+            (url == INTER_URL).then(|| "fonts/fonts-inter.css".into())
+        });
+        assert_eq!(
+            rewritten,
+            "<html><head><link rel=\"stylesheet\" href=\"../fonts/fonts-inter.css\">\
+             <link rel=\"preload\" href=\"../fonts/inter-latin.woff2\" as=\"font\" type=\"font/woff2\" crossorigin>\
+             </head><body></body></html>"
+        );
+    }
+
+    #[test]
+    fn test_self_host_cached_fonts() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("dist");
+        let cache_dir = dir.path().join("cache");
+        fs::create_dir_all(output_dir.join("css")).unwrap();
+        fs::create_dir_all(&cache_dir).unwrap();
+        fs::write(
+            output_dir.join("css/main.css"),
+            format!("@import url(\"{INTER_URL}\");\nbody {{ font-family: Inter; }}\n"),
+        )
+        .unwrap();
+        fs::write(
+            output_dir.join("index.html"),
+            "<html><head><link rel=\"preconnect\" href=\"https://fonts.googleapis.com\">\
+             <link rel=\"stylesheet\" href=\"css/main.css\"></head><body>Hello</body></html>",
+        )
+        .unwrap();
+        let cache = |url: &str, extension: &str, content: &str| {
+            let name = format!("{}.{extension}", &url_hash(url)[..16]);
+            fs::write(cache_dir.join(name), content).unwrap();
+        };
+        cache(INTER_URL, "css", INTER_CSS);
+        cache(
+            "https://fonts.gstatic.com/s/inter/v18/latin.woff2",
+            "woff2",
+            "latin",
+        );
+
+        // The Cyrillic file isn't cached, but no page needs it
+        assert_eq!(self_host_fonts(&output_dir, &cache_dir, true).unwrap(), 1);
+
+        let main = fs::read_to_string(output_dir.join("css/main.css")).unwrap();
+        assert!(main.starts_with("@import url(\"fonts-inter.css\");"));
+        let fonts = fs::read_to_string(output_dir.join("css/fonts-inter.css")).unwrap();
+        assert!(fonts.contains("font-weight: 400 700;"));
+        assert!(fonts.contains("src: url(\"../fonts/inter-normal-400-700-latin-"));
+        assert!(!fonts.contains("cyrillic"));
+
+        let page = fs::read_to_string(output_dir.join("index.html")).unwrap();
+        assert!(!page.contains("fonts.googleapis.com"));
+        assert!(page.contains("<link rel=\"preload\" href=\"fonts/inter-normal-400-700-latin-"));
+        let files: Vec<_> = fs::read_dir(output_dir.join(FONTS_DIR)).unwrap().collect();
+        assert_eq!(files.len(), 1);
+    }
+
+    #[test]
+    fn test_offline_without_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let css = format!("@import url(\"{INTER_URL}\");\n");
+        fs::write(dir.path().join("main.css"), &css).unwrap();
+
+        let hosted = self_host_fonts(dir.path(), &dir.path().join("cache"), true).unwrap();
+        assert_eq!(hosted, 0);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("main.css")).unwrap(),
+            css
+        );
+    }
+}
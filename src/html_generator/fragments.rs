@@ -0,0 +1,302 @@
+//! Template fragment cache
+//!
+//! The header, the footer and the project cards are the same on many pages:
+//! the footer on every page, the header on every page of a section, and each
+//! project card on the CV, the projects page and the home page. Templates
+//! render them through the [`Fragments`] helpers instead of including the
+//! partials, so each distinct fragment is rendered once per build and reused
+//! after that.
+//!
+//! Fragments are cached by a hash of the data they show, so a page with a
+//! different active menu item or a changed project gets its own entry. The
+//! build calls [`reset`] before generating pages and reports [`stats`] to the
+//! profiler. Theme templates render their own partials and don't use the
+//! cache.
+
+use askama::filters::Safe;
+use askama::Template;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+use super::filters::{self, Icons};
+use super::navigation::NavState;
+use crate::cv_data::{Cv, Project};
+use crate::dependencies::Dependency;
+use crate::site_config::SiteConfig;
+
+/// How the fragment cache did during a build
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FragmentStats {
+    /// Fragments rendered because they weren't cached yet
+    pub rendered: usize,
+    /// Fragments taken from the cache
+    pub reused: usize,
+    /// Time spent rendering and looking up fragments
+    pub time: Duration,
+}
+
+/// Rendered fragments by content hash, and the statistics of the build
+#[derive(Default)]
+struct FragmentCache {
+    fragments: HashMap<String, String>,
+    stats: FragmentStats,
+}
+
+fn cache() -> MutexGuard<'static, FragmentCache> {
+    static CACHE: OnceLock<Mutex<FragmentCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+}
+
+/// Empty the cache and its statistics, at the start of a build
+pub fn reset() {
+    *cache() = FragmentCache::default();
+}
+
+/// Statistics of the cache since the last [`reset`]
+pub fn stats() -> FragmentStats {
+    cache().stats
+}
+
+/// The common header with the navigation
+#[derive(Template)]
+#[template(path = "partials/header.html")]
+struct HeaderFragment<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+}
+
+/// The common footer
+#[derive(Template)]
+#[template(path = "partials/footer.html")]
+struct FooterFragment<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    dependencies: &'a [Dependency],
+}
+
+/// The card of a project
+#[derive(Template)]
+#[template(path = "partials/project-card.html")]
+struct ProjectCardFragment<'a> {
+    project: &'a Project,
+    site_config: &'a SiteConfig,
+}
+
+/// Render a fragment, or take it from the cache
+///
+/// # Arguments
+///
+/// * `name` - Name of the fragment
+/// * `data` - Everything the fragment shows, hashed for the cache key
+/// * `render` - Renders the fragment
+///
+/// # Returns
+///
+/// The fragment HTML
+fn cached<D, F>(name: &str, data: &D, render: F) -> askama::Result<Safe<String>>
+where
+    D: Serialize + ?Sized,
+    F: FnOnce() -> askama::Result<String>,
+{
+    let start = Instant::now();
+    let key = fragment_key(name, data).map_err(|e| askama::Error::Custom(e.into()))?;
+
+    let cached = cache().fragments.get(&key).cloned();
+    let html = match cached {
+        Some(html) => {
+            cache().stats.reused += 1;
+            html
+        }
+        None => {
+            let html = render()?;
+            let mut cache = cache();
+            cache.fragments.insert(key, html.clone());
+            cache.stats.rendered += 1;
+            html
+        }
+    };
+    cache().stats.time += start.elapsed();
+    Ok(Safe(html))
+}
+
+/// Cache key of a fragment: a hash of its name and data
+fn fragment_key<D: Serialize + ?Sized>(name: &str, data: &D) -> serde_json::Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(name);
+    hasher.update(serde_json::to_vec(data)?);
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The header, with the active item of `nav` marked
+///
+/// # Arguments
+///
+/// * `cv` - CV data, for the name
+/// * `site_config` - Site configuration with the menu and languages
+/// * `nav` - Navigation state of the page
+///
+/// # Returns
+///
+/// The header HTML
+pub fn header(cv: &Cv, site_config: &SiteConfig, nav: &NavState) -> askama::Result<Safe<String>> {
+    let data = (
+        &cv.personal_info.name,
+        &site_config.menu,
+        &site_config.languages,
+        site_config.html_lang(),
+        &nav.active_path,
+    );
+    cached("header", &data, || {
+        HeaderFragment {
+            cv,
+            site_config,
+            nav,
+        }
+        .render()
+    })
+}
+
+/// The footer
+///
+/// # Arguments
+///
+/// * `cv` - CV data, for the name and social links
+/// * `site_config` - Site configuration with the menu
+/// * `dependencies` - Project dependencies from Cargo.toml
+///
+/// # Returns
+///
+/// The footer HTML
+pub fn footer(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    dependencies: &[Dependency],
+) -> askama::Result<Safe<String>> {
+    let data = (
+        &cv.personal_info.name,
+        &cv.personal_info.social_links,
+        &site_config.menu,
+        dependencies,
+    );
+    cached("footer", &data, || {
+        FooterFragment {
+            cv,
+            site_config,
+            dependencies,
+        }
+        .render()
+    })
+}
+
+/// The card of a project
+///
+/// # Arguments
+///
+/// * `project` - The project
+/// * `site_config` - Site configuration with the taxonomy, for technology icons
+///
+/// # Returns
+///
+/// The card HTML
+pub fn project_card(project: &Project, site_config: &SiteConfig) -> askama::Result<Safe<String>> {
+    let technologies: Vec<_> = project
+        .technologies
+        .iter()
+        .map(|tech| site_config.taxonomy.icon_name(tech))
+        .collect();
+    cached("project-card", &(project, technologies), || {
+        ProjectCardFragment {
+            project,
+            site_config,
+        }
+        .render()
+    })
+}
+
+/// Cached fragment helpers for askama templates
+///
+/// Implemented for every template, so templates call them as
+/// `header(cv, site_config, nav)?`.
+pub trait Fragments {
+    /// See [`header`]
+    fn header(
+        &self,
+        cv: &Cv,
+        site_config: &SiteConfig,
+        nav: &NavState,
+    ) -> askama::Result<Safe<String>> {
+        header(cv, site_config, nav)
+    }
+
+    /// See [`footer`]
+    fn footer(
+        &self,
+        cv: &Cv,
+        site_config: &SiteConfig,
+        dependencies: &[Dependency],
+    ) -> askama::Result<Safe<String>> {
+        footer(cv, site_config, dependencies)
+    }
+
+    /// See [`project_card`]
+    fn project_card(
+        &self,
+        project: &Project,
+        site_config: &SiteConfig,
+    ) -> askama::Result<Safe<String>> {
+        project_card(project, site_config)
+    }
+}
+
+impl<T: Template> Fragments for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragment_key() {
+        let key = fragment_key("header", &("Jane", Some("cv.html"))).unwrap();
+        assert_eq!(
+            fragment_key("header", &("Jane", Some("cv.html"))).unwrap(),
+            key
+        );
+        assert_ne!(
+            fragment_key("header", &("Jane", Some("blog.html"))).unwrap(),
+            key
+        );
+        assert_ne!(
+            fragment_key("footer", &("Jane", Some("cv.html"))).unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn test_fragments_render_once() {
+        let data = "test_fragments_render_once";
+        let first = cached("test", data, || Ok("<p>once</p>".to_string())).unwrap();
+        let second = cached("test", data, || panic!("rendered twice")).unwrap();
+        assert_eq!(first.0, second.0);
+        assert!(stats().reused >= 1);
+    }
+
+    #[test]
+    fn test_header_marks_active_item() {
+        let cv = Cv::from_json("data/cv_data.json").unwrap();
+        let site_config = SiteConfig::default();
+        let Some(item) = site_config.menu.front() else {
+            return;
+        };
+        let nav = NavState::for_page(&site_config.menu, &item.path);
+        let html = header(&cv, &site_config, &nav).unwrap().0;
+        assert!(html.contains(r#"aria-current="page""#));
+        assert_eq!(header(&cv, &site_config, &nav).unwrap().0, html);
+    }
+}
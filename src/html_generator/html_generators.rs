@@ -6,68 +6,111 @@
 use anyhow::{Context, Result};
 use askama::Template;
 use im::{HashMap, Vector};
+use serde::Serialize;
 
-use super::utils::{ensure_parent_dir_exists, get_cache_version, write_file};
+use super::filters::{self, Icons};
+use super::fragments::Fragments;
+use super::navigation::NavState;
+use super::utils::{ensure_parent_dir_exists, get_cache_version, write_html_page};
 use crate::blog_posts::BlogPost;
-use crate::cv_data::Cv;
+use crate::case_studies::{CaseStudy, ProjectDetail};
+use crate::cv_data::{Cv, Project, SkillLevel};
 use crate::dependencies::Dependency;
+use crate::featured::{featured_posts, pinned_projects};
 use crate::markdown_pages::Page;
 use crate::site_config::SiteConfig;
+use crate::theme::Theme;
+use crate::timeline::Timeline;
 
 /// Template for the CV HTML page
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "cv.html")]
 struct CvTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    tag_groups: &'a HashMap<String, Vector<BlogPost>>,
+    timeline: Option<&'a Timeline>,
     version: &'a str,
     dependencies: &'a [Dependency],
 }
 
 /// Template for the index HTML page
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "index.html")]
 struct IndexTemplate<'a> {
     cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+}
+
+/// Template for the home page with featured content
+#[derive(Template, Serialize)]
+#[template(path = "home.html")]
+struct HomeTemplate<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    posts: &'a Vector<BlogPost>,
+    projects: &'a Vector<Project>,
+    version: &'a str,
+    dependencies: &'a [Dependency],
 }
 
 /// Template for the projects HTML page
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "projects.html")]
 struct ProjectsTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    version: &'a str,
+    dependencies: &'a [Dependency],
+}
+
+/// Template for a project case study page
+#[derive(Template, Serialize)]
+#[template(path = "project.html")]
+struct ProjectTemplate<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    project: &'a Project,
+    case_study: &'a CaseStudy,
     version: &'a str,
     dependencies: &'a [Dependency],
 }
 
 /// Template for the blog HTML page
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "blog.html")]
 struct BlogTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
     version: &'a str,
     dependencies: &'a [Dependency],
 }
 
 /// Template for static pages
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "page.html")]
 struct PageTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
     page: &'a Page,
     version: &'a str,
     dependencies: &'a [Dependency],
 }
 
 /// Template for blog list page
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "blog_list.html")]
 struct BlogListTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
     posts: &'a Vector<BlogPost>,
     tag_groups: &'a HashMap<String, Vector<BlogPost>>,
     version: &'a str,
@@ -75,22 +118,89 @@ struct BlogListTemplate<'a> {
 }
 
 /// Template for individual blog post
-#[derive(Template)]
+#[derive(Template, Serialize)]
 #[template(path = "blog_post.html")]
 struct BlogPostTemplate<'a> {
     cv: &'a Cv,
     site_config: &'a SiteConfig,
+    nav: &'a NavState,
     post: &'a BlogPost,
     version: &'a str,
     dependencies: &'a [Dependency],
 }
 
+/// Template for the page listing the posts with a tag
+#[derive(Template, Serialize)]
+#[template(path = "blog_tag.html")]
+struct BlogTagTemplate<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    tag: &'a str,
+    posts: &'a Vector<BlogPost>,
+    version: &'a str,
+    dependencies: &'a [Dependency],
+}
+
+/// Template for the build metadata page
+#[derive(Template, Serialize)]
+#[template(path = "about_this_site.html")]
+struct AboutThisSiteTemplate<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    version: &'a str,
+    dependencies: &'a [Dependency],
+}
+
+/// Template for the page served for missing pages
+#[derive(Template, Serialize)]
+#[template(path = "not_found.html")]
+struct NotFoundTemplate<'a> {
+    cv: &'a Cv,
+    site_config: &'a SiteConfig,
+    nav: &'a NavState,
+    base_href: &'a str,
+    version: &'a str,
+    dependencies: &'a [Dependency],
+}
+
+/// Render a page with the selected theme, falling back to the built-in template
+///
+/// # Arguments
+///
+/// * `template` - The built-in template with the page data
+/// * `site_config` - Site configuration selecting the theme
+/// * `name` - Template file name, shared by built-in and theme templates
+///
+/// # Returns
+///
+/// The rendered HTML
+fn render_page<T: Template + Serialize>(
+    template: &T,
+    site_config: &SiteConfig,
+    name: &str,
+) -> Result<String> {
+    match Theme::from_site_config(site_config)? {
+        Some(theme) if theme.has_template(name) => {
+            // Theme templates can't call methods, so pass computed values too
+            let mut context =
+                serde_json::to_value(template).context("Failed to serialize page data")?;
+            context["lang"] = site_config.html_lang().into();
+            theme.render(name, &context)
+        }
+        _ => Ok(template.render()?),
+    }
+}
+
 /// Generates the main CV HTML page
 ///
 /// # Arguments
 ///
 /// * `cv` - CV data
 /// * `site_config` - Site configuration
+/// * `tag_groups` - Blog posts grouped by tag, for links from the skills
+/// * `timeline` - Career timeline, if the site shows one
 /// * `dependencies` - Project dependencies from Cargo.toml
 /// * `output_path` - Path where the CV HTML will be written
 ///
@@ -100,47 +210,86 @@ struct BlogPostTemplate<'a> {
 pub fn generate_cv_html(
     cv: &Cv,
     site_config: &SiteConfig,
+    tag_groups: &HashMap<String, Vector<BlogPost>>,
+    timeline: Option<&Timeline>,
     dependencies: &[Dependency],
     output_path: &str,
 ) -> Result<()> {
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "cv.html");
     let template = CvTemplate {
         cv,
         site_config,
+        nav: &nav,
+        tag_groups,
+        timeline,
         version: &version,
         dependencies,
     };
-    let rendered = template.render().context("Failed to render CV template")?;
+    let rendered =
+        render_page(&template, site_config, "cv.html").context("Failed to render CV template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated CV HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
 
 /// Generates the index HTML page
 ///
+/// With `featured` configured this is a home page showing the featured posts
+/// and pinned projects; otherwise it redirects to the CV.
+///
 /// # Arguments
 ///
 /// * `cv` - CV data
-/// * `_site_config` - Site configuration (unused but kept for consistency)
+/// * `site_config` - Site configuration
+/// * `posts` - Published blog posts to pick featured posts from
+/// * `dependencies` - Project dependencies from Cargo.toml
 /// * `output_path` - Path where the index HTML will be written
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn generate_index_html(cv: &Cv, _site_config: &SiteConfig, output_path: &str) -> Result<()> {
+pub fn generate_index_html(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    posts: &Vector<BlogPost>,
+    dependencies: &[Dependency],
+    output_path: &str,
+) -> Result<()> {
     ensure_parent_dir_exists(output_path)?;
 
-    let template = IndexTemplate { cv };
-    let rendered = template
-        .render()
+    if let Some(featured) = &site_config.featured {
+        let nav = NavState::for_page(&site_config.menu, "index.html");
+        let version = get_cache_version();
+        let template = HomeTemplate {
+            cv,
+            site_config,
+            nav: &nav,
+            posts: &featured_posts(posts, featured),
+            projects: &pinned_projects(&cv.projects, featured),
+            version: &version,
+            dependencies,
+        };
+        let rendered = render_page(&template, site_config, "home.html")
+            .context("Failed to render home template")?;
+
+        write_html_page(output_path, &rendered)?;
+        return Ok(());
+    }
+
+    let nav = NavState::for_page(&site_config.menu, "index.html");
+    let template = IndexTemplate {
+        cv,
+        site_config,
+        nav: &nav,
+    };
+    let rendered = render_page(&template, site_config, "index.html")
         .context("Failed to render index template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated index HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
@@ -166,18 +315,59 @@ pub fn generate_projects_html(
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "projects.html");
     let template = ProjectsTemplate {
         cv,
         site_config,
+        nav: &nav,
         version: &version,
         dependencies,
     };
-    let rendered = template
-        .render()
+    let rendered = render_page(&template, site_config, "projects.html")
         .context("Failed to render projects template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated projects HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
+
+    Ok(())
+}
+
+/// Generates the detail page of a project with a case study
+///
+/// # Arguments
+///
+/// * `cv` - CV data
+/// * `site_config` - Site configuration
+/// * `detail` - The case study and the project data it was merged with
+/// * `dependencies` - Project dependencies from Cargo.toml
+/// * `output_path` - Path where the project HTML will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_project_html(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    detail: &ProjectDetail,
+    dependencies: &[Dependency],
+    output_path: &str,
+) -> Result<()> {
+    ensure_parent_dir_exists(output_path)?;
+
+    let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "projects.html");
+    let template = ProjectTemplate {
+        cv,
+        site_config,
+        nav: &nav,
+        project: &detail.project,
+        case_study: &detail.case_study,
+        version: &version,
+        dependencies,
+    };
+    let rendered = render_page(&template, site_config, "project.html")
+        .context("Failed to render project template")?;
+
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
@@ -203,18 +393,18 @@ pub fn generate_blog_html(
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "blog.html");
     let template = BlogTemplate {
         cv,
         site_config,
+        nav: &nav,
         version: &version,
         dependencies,
     };
-    let rendered = template
-        .render()
+    let rendered = render_page(&template, site_config, "blog.html")
         .context("Failed to render blog template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated blog HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
@@ -242,19 +432,19 @@ pub fn generate_page_html(
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, &format!("{}.html", page.slug));
     let template = PageTemplate {
         cv,
         site_config,
+        nav: &nav,
         page,
         version: &version,
         dependencies,
     };
-    let rendered = template
-        .render()
+    let rendered = render_page(&template, site_config, "page.html")
         .context("Failed to render page template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated page HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
@@ -284,20 +474,20 @@ pub fn generate_blog_list_html(
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "blog.html");
     let template = BlogListTemplate {
         cv,
         site_config,
+        nav: &nav,
         posts,
         tag_groups,
         version: &version,
         dependencies,
     };
-    let rendered = template
-        .render()
+    let rendered = render_page(&template, site_config, "blog_list.html")
         .context("Failed to render blog list template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated blog list HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
@@ -325,19 +515,144 @@ pub fn generate_blog_post_html(
     ensure_parent_dir_exists(output_path)?;
 
     let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, &format!("blog/{}.html", post.slug));
     let template = BlogPostTemplate {
         cv,
         site_config,
+        nav: &nav,
         post,
         version: &version,
         dependencies,
     };
-    let rendered = template
-        .render()
+    let rendered = render_page(&template, site_config, "blog_post.html")
         .context("Failed to render blog post template")?;
 
-    write_file(output_path, &rendered)?;
-    println!("Generated blog post HTML: {output_path}");
+    write_html_page(output_path, &rendered)?;
+
+    Ok(())
+}
+
+/// Generates the page listing the blog posts with a tag
+///
+/// # Arguments
+///
+/// * `cv` - CV data
+/// * `site_config` - Site configuration
+/// * `tag` - The tag, a canonical technology name where the taxonomy knows it
+/// * `posts` - Posts with the tag
+/// * `dependencies` - Project dependencies from Cargo.toml
+/// * `output_path` - Path where the tag page will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_blog_tag_html(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    tag: &str,
+    posts: &Vector<BlogPost>,
+    dependencies: &[Dependency],
+    output_path: &str,
+) -> Result<()> {
+    ensure_parent_dir_exists(output_path)?;
+
+    let version = get_cache_version();
+    let nav = NavState::for_page(
+        &site_config.menu,
+        &format!("blog/tags/{}.html", filters::slug_str(tag)),
+    );
+    let template = BlogTagTemplate {
+        cv,
+        site_config,
+        nav: &nav,
+        tag,
+        posts,
+        version: &version,
+        dependencies,
+    };
+    let rendered = render_page(&template, site_config, "blog_tag.html")
+        .context("Failed to render blog tag template")?;
+
+    write_html_page(output_path, &rendered)?;
+
+    Ok(())
+}
+
+/// Generates the about-this-site page with the build metadata
+///
+/// # Arguments
+///
+/// * `cv` - CV data
+/// * `site_config` - Site configuration with the build metadata
+/// * `dependencies` - Project dependencies from Cargo.toml
+/// * `output_path` - Path where the page will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_about_this_site_html(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    dependencies: &[Dependency],
+    output_path: &str,
+) -> Result<()> {
+    ensure_parent_dir_exists(output_path)?;
+
+    let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "about-this-site.html");
+    let template = AboutThisSiteTemplate {
+        cv,
+        site_config,
+        nav: &nav,
+        version: &version,
+        dependencies,
+    };
+    let rendered = render_page(&template, site_config, "about_this_site.html")
+        .context("Failed to render about-this-site template")?;
+
+    write_html_page(output_path, &rendered)?;
+
+    Ok(())
+}
+
+/// Generates the page served for missing pages
+///
+/// The page can be served at any path, so it sets a `<base>` URL: the
+/// site's `base_url`, or the root of the domain.
+///
+/// # Arguments
+///
+/// * `cv` - CV data
+/// * `site_config` - Site configuration
+/// * `dependencies` - Project dependencies from Cargo.toml
+/// * `output_path` - Path where the 404 HTML will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_not_found_html(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    dependencies: &[Dependency],
+    output_path: &str,
+) -> Result<()> {
+    ensure_parent_dir_exists(output_path)?;
+
+    let version = get_cache_version();
+    let nav = NavState::for_page(&site_config.menu, "404.html");
+    let base_href = format!("{}/", site_config.base_url().trim_end_matches('/'));
+    let template = NotFoundTemplate {
+        cv,
+        site_config,
+        nav: &nav,
+        base_href: &base_href,
+        version: &version,
+        dependencies,
+    };
+    let rendered = render_page(&template, site_config, "not_found.html")
+        .context("Failed to render 404 template")?;
+
+    write_html_page(output_path, &rendered)?;
 
     Ok(())
 }
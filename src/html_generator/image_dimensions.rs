@@ -0,0 +1,531 @@
+//! Image dimensions
+//!
+//! An image without `width` and `height` attributes takes no space until it
+//! has loaded, so the page jumps when it arrives. After the site is generated,
+//! every `<img>` in its pages gets the intrinsic size of its image, read from
+//! the image's header, so the browser can reserve the space up front. The
+//! stylesheet's `height: auto` keeps the aspect ratio when an image is scaled
+//! down. When a template sets only one of the attributes, the other is
+//! derived from the aspect ratio.
+//!
+//! Local images are probed on every build, which only reads their headers.
+//! Remote images are downloaded once and their sizes kept in
+//! `cache/image_dimensions.json`. Offline builds only size the remote images
+//! found there.
+//!
+//! Downloads and pages are handled on a bounded pool of threads (the
+//! `image_workers` setting), so a large gallery doesn't hold up the build one
+//! image at a time. The cache is saved as sizes come in, so an interrupted
+//! build doesn't download them again.
+
+use anyhow::{Context, Result};
+use im::OrdMap;
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use super::fingerprint::{base_dir, html_files, parent_dir, resolve_path};
+use crate::performance::Progress;
+
+/// Default path for the remote image size cache
+pub const DEFAULT_IMAGE_CACHE_PATH: &str = "cache/image_dimensions.json";
+
+/// Most bytes downloaded from a remote image to find its size
+const MAX_PROBE_BYTES: usize = 1024 * 1024;
+
+/// New remote image sizes after which the cache is saved
+const SAVE_EVERY: usize = 20;
+
+/// Intrinsic size of an image in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dimensions {
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+}
+
+/// Sizes of remote images, by URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageDimensionCache {
+    /// Image sizes by URL
+    pub images: OrdMap<String, Dimensions>,
+}
+
+impl ImageDimensionCache {
+    /// Load the cache from file, or start an empty one if the file doesn't exist
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache to file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .context("Failed to serialize image dimension cache")?;
+        fs::write(&path, content).with_context(|| {
+            format!(
+                "Failed to write image dimension cache: {}",
+                path.as_ref().display()
+            )
+        })
+    }
+}
+
+/// Add `width` and `height` to the images of every page in the site
+///
+/// Remote images are downloaded and pages rewritten on a pool of `workers`
+/// threads, with their progress logged as they go.
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+/// * `cache_path` - Path of the remote image size cache
+/// * `offline` - Whether to leave remote images that aren't cached unsized
+/// * `workers` - Size of the worker pool, 0 for one thread per core
+///
+/// # Returns
+///
+/// The number of images that were given dimensions
+pub fn add_image_dimensions(
+    output_dir: &Path,
+    cache_path: &Path,
+    offline: bool,
+    workers: usize,
+) -> Result<usize> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .build()
+        .context("Failed to start the image worker pool")?;
+    let mut cache = ImageDimensionCache::load_or_default(cache_path);
+    let pages = html_files(output_dir, output_dir)?;
+
+    let mut pending: Vec<String> = Vec::new();
+    for page in pages.iter() {
+        let file = output_dir.join(page);
+        let html = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        for source in unsized_image_sources(&html) {
+            if is_remote(&source)
+                && !cache.images.contains_key(&source)
+                && !pending.contains(&source)
+            {
+                pending.push(source);
+            }
+        }
+    }
+
+    if !pending.is_empty() && !offline {
+        pool.install(|| probe_remote_images(&pending, &mut cache, cache_path))?;
+    }
+
+    let pages: Vec<String> = pages.into_iter().collect();
+    let progress = Progress::new("Sizing images", pages.len());
+    let counts = pool.install(|| {
+        pages
+            .par_iter()
+            .map(|page| {
+                let count = size_page(output_dir, page, &cache)?;
+                progress.tick();
+                Ok(count)
+            })
+            .collect::<Result<Vec<usize>>>()
+    })?;
+
+    Ok(counts.into_iter().sum())
+}
+
+/// Download the sizes of remote images on the current pool
+///
+/// The cache is saved after every [`SAVE_EVERY`] new sizes, so a build that
+/// is interrupted halfway picks up where it left off.
+///
+/// # Arguments
+///
+/// * `urls` - The images, none of them cached
+/// * `cache` - The remote image size cache
+/// * `cache_path` - Path the cache is saved to
+///
+/// # Returns
+///
+/// A Result indicating whether the cache could be saved
+fn probe_remote_images(
+    urls: &[String],
+    cache: &mut ImageDimensionCache,
+    cache_path: &Path,
+) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("cv-generator/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    // Pool threads aren't runtime threads, they block on the build's runtime
+    let runtime = tokio::runtime::Handle::current();
+    let progress = Progress::new("Downloading remote images", urls.len());
+    // The cache and the number of sizes not saved yet
+    let state = Mutex::new((cache, 0));
+
+    urls.par_iter().try_for_each(|url| -> Result<()> {
+        let probed = runtime.block_on(probe_remote(&client, url));
+        progress.tick();
+        let dimensions = match probed {
+            Ok(dimensions) => dimensions,
+            Err(e) => {
+                println!("⚠️  Could not read the size of {}: {:#}", url, e);
+                return Ok(());
+            }
+        };
+
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let (cache, unsaved) = &mut *state;
+        cache.images.insert(url.clone(), dimensions);
+        *unsaved += 1;
+        if *unsaved >= SAVE_EVERY {
+            cache.save(cache_path)?;
+            *unsaved = 0;
+        }
+        Ok(())
+    })?;
+
+    let (cache, unsaved) = state.into_inner().unwrap_or_else(|e| e.into_inner());
+    if unsaved > 0 {
+        cache.save(cache_path)?;
+    }
+    Ok(())
+}
+
+/// Add `width` and `height` to the images of one page of the site
+///
+/// # Returns
+///
+/// The number of images that were given dimensions
+fn size_page(output_dir: &Path, page: &str, cache: &ImageDimensionCache) -> Result<usize> {
+    let file = output_dir.join(page);
+    let html =
+        fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let base_dir = base_dir(&html, parent_dir(page));
+    let (rewritten, count) = size_images(&html, |source| {
+        if is_remote(source) {
+            return cache.images.get(source).copied();
+        }
+        let path = source.split(['?', '#']).next().unwrap_or(source);
+        let file = output_dir.join(resolve_path(&base_dir, path)?);
+        probe_local(&file)
+    });
+    if count > 0 {
+        fs::write(&file, rewritten)
+            .with_context(|| format!("Failed to write {}", file.display()))?;
+    }
+    Ok(count)
+}
+
+/// Add `width` and `height` to the images of a page
+///
+/// Images that already have both attributes, or whose size is unknown, are
+/// left as they are.
+///
+/// # Arguments
+///
+/// * `html` - A generated page
+/// * `dimensions` - Looks up the size of an image by its `src`
+///
+/// # Returns
+///
+/// The page, and the number of images that were given dimensions
+pub fn size_images(html: &str, dimensions: impl Fn(&str) -> Option<Dimensions>) -> (String, usize) {
+    let mut count = 0;
+    let rewritten = img_tag_regex()
+        .replace_all(html, |tag: &Captures| {
+            let tag = &tag[0];
+            let attributes = ImgAttributes::parse(tag);
+            let size = attributes
+                .missing_size()
+                .then(|| attributes.source.as_deref().and_then(&dimensions))
+                .flatten()
+                .and_then(|intrinsic| attributes.complete(intrinsic));
+            match size {
+                Some(extra) => {
+                    count += 1;
+                    let end = if tag.ends_with("/>") {
+                        tag.len() - 2
+                    } else {
+                        tag.len() - 1
+                    };
+                    format!("{}{}{}", tag[..end].trim_end(), extra, &tag[end..])
+                }
+                None => tag.to_string(),
+            }
+        })
+        .into_owned();
+    (rewritten, count)
+}
+
+/// The `src`, `width` and `height` of an `<img>` tag
+#[derive(Debug, Default)]
+struct ImgAttributes {
+    source: Option<String>,
+    width: Option<String>,
+    height: Option<String>,
+}
+
+impl ImgAttributes {
+    fn parse(tag: &str) -> Self {
+        let mut attributes = Self::default();
+        for captures in img_attribute_regex().captures_iter(tag) {
+            let value = captures
+                .name("double")
+                .or_else(|| captures.name("single"))
+                .or_else(|| captures.name("bare"))
+                .map(|value| value.as_str().to_string());
+            match captures["name"].to_ascii_lowercase().as_str() {
+                "src" => attributes.source = value,
+                "width" => attributes.width = value,
+                "height" => attributes.height = value,
+                _ => {}
+            }
+        }
+        attributes
+    }
+
+    /// Whether the tag lacks a dimension and has a source to measure
+    fn missing_size(&self) -> bool {
+        (self.width.is_none() || self.height.is_none())
+            && self
+                .source
+                .as_deref()
+                .is_some_and(|source| !source.is_empty() && !source.starts_with("data:"))
+    }
+
+    /// The attributes to add, given the image's intrinsic size
+    fn complete(&self, intrinsic: Dimensions) -> Option<String> {
+        if intrinsic.width == 0 || intrinsic.height == 0 {
+            return None;
+        }
+        let scaled = |value: &str, from: usize, to: usize| -> Option<usize> {
+            let value: f64 = value.trim().trim_end_matches("px").parse().ok()?;
+            Some((value * to as f64 / from as f64).round() as usize)
+        };
+        match (&self.width, &self.height) {
+            (None, None) => Some(format!(
+                r#" width="{}" height="{}""#,
+                intrinsic.width, intrinsic.height
+            )),
+            (Some(width), None) => scaled(width, intrinsic.width, intrinsic.height)
+                .map(|height| format!(r#" height="{height}""#)),
+            (None, Some(height)) => scaled(height, intrinsic.height, intrinsic.width)
+                .map(|width| format!(r#" width="{width}""#)),
+            (Some(_), Some(_)) => None,
+        }
+    }
+}
+
+/// Sources of the images on a page that lack a dimension
+fn unsized_image_sources(html: &str) -> Vec<String> {
+    img_tag_regex()
+        .find_iter(html)
+        .map(|tag| ImgAttributes::parse(tag.as_str()))
+        .filter(ImgAttributes::missing_size)
+        .filter_map(|attributes| attributes.source)
+        .collect()
+}
+
+fn is_remote(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Read the size of a local image
+fn probe_local(file: &Path) -> Option<Dimensions> {
+    if file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"))
+    {
+        return svg_size(&fs::read_to_string(file).ok()?);
+    }
+    imagesize::size(file).ok().map(|size| Dimensions {
+        width: size.width,
+        height: size.height,
+    })
+}
+
+/// Download the start of a remote image until its size can be read
+async fn probe_remote(client: &reqwest::Client, url: &str) -> Result<Dimensions> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let is_svg = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("image/svg"));
+
+    let mut bytes: Vec<u8> = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if !is_svg {
+            if let Ok(size) = imagesize::blob_size(&bytes) {
+                return Ok(Dimensions {
+                    width: size.width,
+                    height: size.height,
+                });
+            }
+        }
+        if bytes.len() > MAX_PROBE_BYTES {
+            break;
+        }
+    }
+
+    let size = if is_svg {
+        svg_size(&String::from_utf8_lossy(&bytes))
+    } else {
+        imagesize::blob_size(&bytes).ok().map(|size| Dimensions {
+            width: size.width,
+            height: size.height,
+        })
+    };
+    size.context("Unknown image format")
+}
+
+/// Read the size of an SVG from its `width` and `height`, or else its `viewBox`
+fn svg_size(svg: &str) -> Option<Dimensions> {
+    static SVG_TAG: OnceLock<Regex> = OnceLock::new();
+    let tag = SVG_TAG
+        .get_or_init(|| Regex::new(r"(?is)<svg\b[^>]*>").expect("valid svg regex"))
+        .find(svg)?
+        .as_str();
+    let attributes: OrdMap<String, String> = img_attribute_regex()
+        .captures_iter(tag)
+        .filter_map(|captures| {
+            let value = captures
+                .name("double")
+                .or_else(|| captures.name("single"))
+                .or_else(|| captures.name("bare"))?;
+            Some((
+                captures["name"].to_ascii_lowercase(),
+                value.as_str().to_string(),
+            ))
+        })
+        .collect();
+    let pixels = |name: &str| -> Option<f64> {
+        attributes
+            .get(name)?
+            .trim()
+            .trim_end_matches("px")
+            .parse()
+            .ok()
+    };
+
+    let (width, height) = match (pixels("width"), pixels("height")) {
+        (Some(width), Some(height)) => (width, height),
+        _ => {
+            let view_box: Vec<f64> = attributes
+                .get("viewbox")?
+                .split([' ', ','])
+                .filter_map(|value| value.parse().ok())
+                .collect();
+            match view_box[..] {
+                [_, _, width, height] => (width, height),
+                _ => return None,
+            }
+        }
+    };
+    Some(Dimensions {
+        width: width.round() as usize,
+        height: height.round() as usize,
+    })
+}
+
+/// Matches `<img>` tags
+fn img_tag_regex() -> &'static Regex {
+    static IMG_TAG: OnceLock<Regex> = OnceLock::new();
+    IMG_TAG.get_or_init(|| Regex::new(r"(?i)<img\b[^<>]*>").expect("valid img regex"))
+}
+
+/// Matches attributes, quoted or unquoted as minified HTML has them
+fn img_attribute_regex() -> &'static Regex {
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    ATTRIBUTE.get_or_init(|| {
+        Regex::new(
+            r#"\s(?P<name>[a-zA-Z][\w:-]*)\s*=\s*(?:"(?P<double>[^"]*)"|'(?P<single>[^']*)'|(?P<bare>[^\s"'>]+))"#,
+        )
+        .expect("valid attribute regex")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_images() {
+        let lookup = |source: &str| match source {
+            "img/photo.jpg" => Some(Dimensions {
+                width: 800,
+                height: 600,
+            }),
+            _ => None,
+        };
+        let html = concat!(
+            r#"<img src="img/photo.jpg" alt="Photo">"#,
+            r#"<img src=img/photo.jpg width=400 />"#,
+            r#"<img src="img/photo.jpg" width="10" height="20">"#,
+            r#"<img src="img/missing.png">"#,
+        );
+
+        let (sized, count) = size_images(html, lookup);
+        assert_eq!(count, 2);
+        assert_eq!(
+            sized,
+            concat!(
+                r#"<img src="img/photo.jpg" alt="Photo" width="800" height="600">"#,
+                r#"<img src=img/photo.jpg width=400 height="300"/>"#,
+                r#"<img src="img/photo.jpg" width="10" height="20">"#,
+                r#"<img src="img/missing.png">"#,
+            )
+        );
+
+        assert_eq!(
+            svg_size(
+                r#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 120 40.4">"#
+            ),
+            Some(Dimensions {
+                width: 120,
+                height: 40
+            })
+        );
+        assert_eq!(base_dir(r#"<base href="../">"#, "projects"), "");
+        assert_eq!(base_dir("<p>", "blog"), "blog");
+    }
+
+    #[test]
+    fn test_add_image_dimensions_on_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="32"></svg>"#;
+        fs::create_dir_all(dir.path().join("img")).unwrap();
+        fs::write(dir.path().join("img/logo.svg"), svg).unwrap();
+        for page in 0..8 {
+            fs::write(
+                dir.path().join(format!("page-{page}.html")),
+                r#"<img src="img/logo.svg"><img src="https://example.com/remote.png">"#,
+            )
+            .unwrap();
+        }
+
+        let cache_path = dir.path().join("cache.json");
+        let sized = add_image_dimensions(dir.path(), &cache_path, true, 2).unwrap();
+        assert_eq!(sized, 8);
+        assert!(fs::read_to_string(dir.path().join("page-3.html"))
+            .unwrap()
+            .contains(r#"<img src="img/logo.svg" width="64" height="32">"#));
+        // Offline, nothing was downloaded to cache
+        assert!(!cache_path.exists());
+    }
+}
@@ -0,0 +1,330 @@
+//! Subresource Integrity
+//!
+//! After the assets are fingerprinted, every `<script src>` and stylesheet
+//! `<link>` in the generated pages gets an `integrity` attribute with the
+//! SHA-384 hash of the file it loads, so the browser refuses a file that was
+//! changed on the way or on a CDN. External files also get
+//! `crossorigin="anonymous"`, which SRI needs for cross-origin requests.
+//!
+//! Local files are hashed on every build. External files are downloaded once
+//! and their hashes kept in `cache/integrity.json`; offline builds leave the
+//! ones that aren't cached without a hash. Tags that already have an
+//! `integrity` attribute are left alone, as are files that change without
+//! their URL changing: Google Fonts stylesheets, which differ per browser,
+//! and the URLs passed in to skip, such as the analytics script.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use im::OrdMap;
+use rayon::prelude::*;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha384};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use super::fingerprint::{base_dir, html_files, parent_dir, resolve_path};
+use crate::content_security_policy::tag_attributes;
+
+/// Default path for the external file hash cache
+pub const DEFAULT_INTEGRITY_CACHE_PATH: &str = "cache/integrity.json";
+
+/// Hosts whose files change under the same URL
+const UNPINNABLE_HOSTS: [&str; 1] = ["fonts.googleapis.com"];
+
+/// Integrity hashes of external files, by URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityCache {
+    /// `sha384-…` hashes by URL
+    pub hashes: OrdMap<String, String>,
+}
+
+impl IntegrityCache {
+    /// Load the cache from file, or start an empty one if the file doesn't exist
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache to file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(self).context("Failed to serialize integrity cache")?;
+        fs::write(&path, content).with_context(|| {
+            format!(
+                "Failed to write integrity cache: {}",
+                path.as_ref().display()
+            )
+        })
+    }
+}
+
+/// Add `integrity` to the scripts and stylesheets of every page in the site
+///
+/// # Arguments
+///
+/// * `output_dir` - The generated site
+/// * `cache_path` - Path of the external file hash cache
+/// * `offline` - Whether to leave external files that aren't cached unhashed
+/// * `skip` - URLs of files that change without their URL changing
+///
+/// # Returns
+///
+/// The number of tags that were given a hash
+pub fn add_integrity(
+    output_dir: &Path,
+    cache_path: &Path,
+    offline: bool,
+    skip: &[String],
+) -> Result<usize> {
+    let mut cache = IntegrityCache::load_or_default(cache_path);
+    let pages = html_files(output_dir, output_dir)?;
+    let pinnable = |url: &str| !skip.iter().any(|skipped| skipped == url) && is_pinnable(url);
+
+    let mut pending: Vec<String> = Vec::new();
+    for page in pages.iter() {
+        let file = output_dir.join(page);
+        let html = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        for url in unhashed_sources(&html) {
+            if is_external(&url)
+                && pinnable(&url)
+                && !cache.hashes.contains_key(&url)
+                && !pending.contains(&url)
+            {
+                pending.push(url);
+            }
+        }
+    }
+
+    if !pending.is_empty() && !offline {
+        hash_external_files(&pending, &mut cache)?;
+        cache.save(cache_path)?;
+    }
+
+    let mut count = 0;
+    for page in pages.iter() {
+        let file = output_dir.join(page);
+        let html = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let base_dir = base_dir(&html, parent_dir(page));
+        let (rewritten, added) = add_page_integrity(&html, |url| {
+            if is_external(url) {
+                return pinnable(url)
+                    .then(|| cache.hashes.get(url).cloned())
+                    .flatten();
+            }
+            let path = url.split(['?', '#']).next().unwrap_or(url);
+            let content = fs::read(output_dir.join(resolve_path(&base_dir, path)?)).ok()?;
+            Some(integrity(&content))
+        });
+        if added > 0 {
+            fs::write(&file, rewritten)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+            count += added;
+        }
+    }
+    Ok(count)
+}
+
+/// Download external files and hash them
+///
+/// Files that can't be downloaded are reported and left out of the cache.
+fn hash_external_files(urls: &[String], cache: &mut IntegrityCache) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("cv-generator/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    // Pool threads aren't runtime threads, they block on the build's runtime
+    let runtime = tokio::runtime::Handle::current();
+    let cache = Mutex::new(cache);
+
+    urls.par_iter().for_each(|url| {
+        let downloaded = runtime.block_on(async {
+            let response = client.get(url).send().await?.error_for_status()?;
+            response.bytes().await
+        });
+        match downloaded {
+            Ok(content) => {
+                let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+                cache.hashes.insert(url.clone(), integrity(&content));
+            }
+            Err(e) => println!("⚠️  Could not hash {}: {}", url, e),
+        }
+    });
+    Ok(())
+}
+
+/// Add `integrity`, and `crossorigin` for external files, to the scripts and
+/// stylesheets of a page
+///
+/// # Arguments
+///
+/// * `html` - A generated page
+/// * `integrity` - Looks up the hash of a file by its URL
+///
+/// # Returns
+///
+/// The page, and the number of tags that were given a hash
+pub fn add_page_integrity(
+    html: &str,
+    integrity: impl Fn(&str) -> Option<String>,
+) -> (String, usize) {
+    let mut count = 0;
+    let rewritten = subresource_tag_regex().replace_all(html, |captures: &Captures| {
+        let tag = &captures[0];
+        let Some(url) = unhashed_source(tag) else {
+            return tag.to_string();
+        };
+        let Some(hash) = integrity(&url) else {
+            return tag.to_string();
+        };
+
+        let mut attributes = format!(r#" integrity="{hash}""#);
+        if is_external(&url) && !has_attribute(tag, "crossorigin") {
+            attributes.push_str(r#" crossorigin="anonymous""#);
+        }
+        count += 1;
+        let end = tag.len() - if tag.ends_with("/>") { 2 } else { 1 };
+        format!("{}{attributes}{}", tag[..end].trim_end(), &tag[end..])
+    });
+    (rewritten.into_owned(), count)
+}
+
+/// The `sha384-…` integrity value of a file
+fn integrity(content: &[u8]) -> String {
+    format!(
+        "sha384-{}",
+        base64::engine::general_purpose::STANDARD.encode(Sha384::digest(content))
+    )
+}
+
+/// URLs of the scripts and stylesheets of a page that have no hash yet
+fn unhashed_sources(html: &str) -> Vec<String> {
+    subresource_tag_regex()
+        .find_iter(html)
+        .filter_map(|tag| unhashed_source(tag.as_str()))
+        .collect()
+}
+
+/// The URL a `<script>` or `<link>` tag loads, if it needs a hash
+fn unhashed_source(tag: &str) -> Option<String> {
+    let attributes = tag_attributes(tag);
+    if attributes.contains_key("integrity") {
+        return None;
+    }
+    let url = if tag[1..].to_ascii_lowercase().starts_with("script") {
+        attributes.get("src")?
+    } else {
+        let rel = attributes.get("rel")?.to_ascii_lowercase();
+        rel.split_whitespace()
+            .any(|rel| rel == "stylesheet" || rel == "modulepreload")
+            .then(|| attributes.get("href"))??
+    };
+    (!url.is_empty() && !url.starts_with("data:")).then(|| url.clone())
+}
+
+fn has_attribute(tag: &str, name: &str) -> bool {
+    tag.split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .any(|part| {
+            part.split('=')
+                .next()
+                .is_some_and(|attribute| attribute.eq_ignore_ascii_case(name))
+        })
+}
+
+fn is_external(url: &str) -> bool {
+    url.starts_with("//") || url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Whether a URL always serves the same file
+fn is_pinnable(url: &str) -> bool {
+    let host = url
+        .trim_start_matches("https:")
+        .trim_start_matches("http:")
+        .trim_start_matches("//");
+    !UNPINNABLE_HOSTS
+        .iter()
+        .any(|unpinnable| host.starts_with(unpinnable))
+}
+
+/// Matches opening `<script>` and `<link>` tags
+fn subresource_tag_regex() -> &'static Regex {
+    static SUBRESOURCE_TAG: OnceLock<Regex> = OnceLock::new();
+    SUBRESOURCE_TAG.get_or_init(|| Regex::new(r"(?i)<(?:script|link)\b[^<>]*>").unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_add_page_integrity() {
+        let html = r#"<link rel="stylesheet" href="css/main.css"><link rel="icon" href="img/icon.png">
+<script src="https://cdn.example.com/katex.min.js" defer></script>
+<script src="js/app.js" integrity="sha384-pinned"></script><script>inline()</script>
+<link rel=stylesheet href="https://fonts.googleapis.com/css2?family=Inter"/>"#;
+
+        let (rewritten, count) = add_page_integrity(html, |url| Some(format!("sha384-{url}")));
+        assert_eq!(count, 3);
+        assert!(rewritten.contains(
+            r#"<link rel="stylesheet" href="css/main.css" integrity="sha384-css/main.css">"#
+        ));
+        assert!(rewritten.contains(r#"<link rel="icon" href="img/icon.png">"#));
+        assert!(rewritten.contains(
+            r#"defer integrity="sha384-https://cdn.example.com/katex.min.js" crossorigin="anonymous">"#
+        ));
+        assert!(rewritten.contains(r#"<script src="js/app.js" integrity="sha384-pinned">"#));
+        assert!(rewritten.contains("<script>inline()</script>"));
+        assert!(rewritten.contains(r#"crossorigin="anonymous"/>"#));
+    }
+
+    #[test]
+    fn test_add_integrity() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::create_dir_all(root.join("blog")).unwrap();
+        fs::write(root.join("css/main.css"), "body{}").unwrap();
+        fs::write(
+            root.join("blog/post.html"),
+            r#"<link rel="stylesheet" href="../css/main.css"><link rel="stylesheet" href="https://fonts.googleapis.com/css2?family=Inter"><script src="https://plausible.io/js/script.js"></script><script src="https://cdn.example.com/lib.js"></script>"#,
+        )
+        .unwrap();
+
+        // Cached external hashes are used offline
+        let cache_path = root.join("integrity.json");
+        let mut cache = IntegrityCache::default();
+        cache.hashes.insert(
+            "https://cdn.example.com/lib.js".to_string(),
+            "sha384-lib".to_string(),
+        );
+        cache.save(&cache_path).unwrap();
+
+        let skip = vec!["https://plausible.io/js/script.js".to_string()];
+        let added = add_integrity(root, &cache_path, true, &skip).unwrap();
+        assert_eq!(added, 2);
+
+        let html = fs::read_to_string(root.join("blog/post.html")).unwrap();
+        assert!(html.contains(&format!(
+            r#"href="../css/main.css" integrity="{}">"#,
+            integrity(b"body{}")
+        )));
+        assert!(html.contains(
+            r#"src="https://cdn.example.com/lib.js" integrity="sha384-lib" crossorigin="anonymous">"#
+        ));
+        assert!(html.contains(r#"<script src="https://plausible.io/js/script.js"></script>"#));
+        assert!(html.contains(r#"family=Inter">"#));
+    }
+}
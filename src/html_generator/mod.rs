@@ -9,6 +9,13 @@
 //!
 //! - [`html_generators`] - Core HTML page generation functions
 //! - [`config_generators`] - Server and deployment configuration files
+//! - [`feed`] - RSS feed for the blog
+//! - [`filters`] - Filters available to all templates
+//! - [`fingerprint`] - Content hashes in asset file names
+//! - [`font_hosting`] - Google Fonts served from the site itself
+//! - [`image_dimensions`] - `width` and `height` for every image
+//! - [`integrity`] - Subresource Integrity hashes for scripts and stylesheets
+//! - [`navigation`] - Active menu item and section of each page
 //! - [`asset_processor`] - Static asset copying and file operations
 //! - [`utils`] - Shared utilities for file operations and content processing
 //!
@@ -18,26 +25,53 @@
 //! HTML generation process including all pages, configurations, and assets.
 
 use anyhow::{Context, Result};
+use im::Vector;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use crate::blog_posts::{group_posts_by_tags, load_posts_from_directory};
-use crate::css_generator::generate_colorscheme_css;
+use crate::booking::{generate_availability_ics, AVAILABILITY_FILE};
+use crate::content_assets::{copy_assets, CONTENT_DIR, MEDIA_DIR};
+use crate::content_security_policy::ContentSecurityPolicy;
+use crate::content_store::ContentStore;
+use crate::css_generator::{generate_colorscheme_css, generate_tag_accent_css};
 use crate::cv_data::Cv;
 use crate::dependencies::parse_dependencies;
-use crate::markdown_pages::load_pages_from_directory;
+use crate::glossary::GLOSSARY_SLUG;
+use crate::icons::{generate_sprite, used_icons, ICON_FONT, SPRITE_FILE};
 use crate::optimization::{optimize_css_file, optimize_js_file};
-use crate::site_config::SiteConfig;
+use crate::security_headers::SecurityHeaders;
+use crate::site_config::{AssetConfig, DeploymentTarget, SiteConfig};
+use crate::theme::Theme;
+use crate::timeline::{build_timeline, generate_timeline_json, TIMELINE_FILE};
+use crate::vcard::{generate_vcard, VCARD_FILE};
 
 // Re-export public functions from submodules
 pub use asset_processor::copy_static_assets_except;
+pub use compression::precompress_output;
 pub use config_generators::*;
+pub use feed::generate_rss_feed;
+pub use fingerprint::fingerprint_assets;
+pub use font_hosting::self_host_fonts;
 pub use html_generators::*;
+pub use image_dimensions::add_image_dimensions;
+pub use integrity::add_integrity;
 
 // Submodule declarations
 pub mod asset_processor;
+pub mod compression;
 pub mod config_generators;
+pub mod feed;
+pub mod filters;
+pub mod fingerprint;
+pub mod font_hosting;
+pub mod fragments;
 pub mod html_generators;
+pub mod image_dimensions;
+pub mod integrity;
+pub mod navigation;
 pub mod utils;
 
 /// Main HTML generation function that coordinates all HTML output
@@ -69,102 +103,239 @@ pub mod utils;
 /// }
 /// ```
 pub fn generate_html(cv: &Cv, site_config: &SiteConfig, output_path: &str) -> Result<()> {
+    generate_html_from(
+        &ContentStore::load(cv, site_config)?,
+        site_config,
+        output_path,
+    )
+}
+
+/// Generate all HTML pages from loaded content
+///
+/// Like [`generate_html`], for content that is already in a [`ContentStore`].
+///
+/// # Arguments
+///
+/// * `store` - The CV, posts, pages and case studies of the site
+/// * `site_config` - Site configuration with styling and navigation settings
+/// * `output_path` - Base path for the main CV HTML file (other files derive from this)
+///
+/// # Returns
+///
+/// A Result indicating success or failure of the entire generation process
+pub fn generate_html_from(
+    store: &ContentStore,
+    site_config: &SiteConfig,
+    output_path: &str,
+) -> Result<()> {
     // Parse dependencies from Cargo.toml
     let dependencies = parse_dependencies("Cargo.toml").unwrap_or_default();
 
-    // Generate main CV HTML
-    generate_cv_html(cv, site_config, &dependencies, output_path)?;
-
     // Get parent directory for other HTML files
     let parent_dir = Path::new(output_path)
         .parent()
         .context("Failed to get parent directory")?;
 
-    // Generate index HTML
-    let index_path = parent_dir
-        .join("index.html")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-
-    generate_index_html(cv, site_config, &index_path)?;
-
-    // Generate projects HTML
-    let projects_path = parent_dir
-        .join("projects.html")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-
-    generate_projects_html(cv, site_config, &dependencies, &projects_path)?;
-
-    // Generate blog HTML
-    let blog_path = parent_dir
-        .join("blog.html")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-
-    // Generate static blog posts from markdown if configured
-    if let Some(blog_config) = &site_config.blog {
-        let blog_dir = blog_config.directory.as_deref().unwrap_or("content/blog");
-
-        let blog_path_dir = Path::new(blog_dir);
-        if blog_path_dir.exists() {
-            let posts = load_posts_from_directory(blog_path_dir)?;
-            let tag_groups = group_posts_by_tags(&posts);
+    // Report content due for review and front matter that doesn't match its schema
+    store.print_reports();
+
+    let cv = store.cv();
+    let posts = store.posts();
+    let tag_groups = store.tag_groups();
+    let details = store.projects();
+
+    // Career timeline, on the CV page and as JSON for other renderings
+    let timeline = site_config
+        .timeline
+        .as_ref()
+        .map(|config| build_timeline(cv, config, site_config.html_lang()));
+    if let Some(timeline) = &timeline {
+        let timeline_path = parent_dir.join(TIMELINE_FILE);
+        generate_timeline_json(timeline, &timeline_path)?;
+        println!("Generated timeline: {}", timeline_path.display());
+    }
 
-            // Generate blog list page
-            generate_blog_list_html(
+    let dependencies = &dependencies;
+    let mut jobs: Vec<PageJob> = vec![
+        PageJob::new("CV", output_path.to_string(), |path| {
+            generate_cv_html(
                 cv,
                 site_config,
-                &posts,
-                &tag_groups,
-                &dependencies,
-                &blog_path,
-            )?;
+                tag_groups,
+                timeline.as_ref(),
+                dependencies,
+                path,
+            )
+        }),
+        PageJob::new("index", page_path(parent_dir, "index.html")?, |path| {
+            generate_index_html(cv, site_config, posts, dependencies, path)
+        }),
+        PageJob::new(
+            "projects",
+            page_path(parent_dir, "projects.html")?,
+            move |path| generate_projects_html(cv, site_config, dependencies, path),
+        ),
+    ];
+
+    let blog_path = page_path(parent_dir, "blog.html")?;
+    if site_config.blog.is_some() {
+        // Static blog posts from markdown
+        if store.has_blog() {
+            jobs.push(PageJob::new("blog list", blog_path, |path| {
+                generate_blog_list_html(cv, site_config, posts, tag_groups, dependencies, path)
+            }));
 
             // Create blog subdirectory for individual posts
             let blog_posts_dir = parent_dir.join("blog");
             fs::create_dir_all(&blog_posts_dir)?;
 
-            // Generate individual blog post pages
             for post in posts.iter() {
-                let post_path = blog_posts_dir
-                    .join(format!("{}.html", post.slug))
-                    .to_str()
-                    .context("Failed to convert path to string")?
-                    .to_string();
+                let post_path = page_path(&blog_posts_dir, &format!("{}.html", post.slug))?;
+                jobs.push(PageJob::new("blog post", post_path, move |path| {
+                    generate_blog_post_html(cv, site_config, post, dependencies, path)
+                }));
+            }
 
-                generate_blog_post_html(cv, site_config, post, &dependencies, &post_path)?;
+            // One page per tag, linked from the posts and the CV skills
+            let tags_dir = blog_posts_dir.join("tags");
+            fs::create_dir_all(&tags_dir)?;
+
+            for (tag, tagged) in tag_groups.iter() {
+                let tag_path = page_path(&tags_dir, &format!("{}.html", filters::slug_str(tag)))?;
+                jobs.push(PageJob::new("tag", tag_path, move |path| {
+                    generate_blog_tag_html(cv, site_config, tag, tagged, dependencies, path)
+                }));
             }
         }
     } else {
         // Fallback to old blog template if not configured
-        generate_blog_html(cv, site_config, &dependencies, &blog_path)?;
+        jobs.push(PageJob::new("blog", blog_path, move |path| {
+            generate_blog_html(cv, site_config, dependencies, path)
+        }));
+    }
+
+    if !details.is_empty() {
+        let projects_dir = parent_dir.join("projects");
+        fs::create_dir_all(&projects_dir)?;
+
+        for detail in details.iter() {
+            let path = page_path(&projects_dir, &format!("{}.html", detail.case_study.slug))?;
+            jobs.push(PageJob::new("project", path, move |path| {
+                generate_project_html(cv, site_config, detail, dependencies, path)
+            }));
+        }
     }
 
-    // Generate static pages from markdown if configured
-    if let Some(pages_config) = &site_config.pages {
-        let pages_dir = pages_config.directory.as_deref().unwrap_or("content/pages");
+    for page in store.pages().iter() {
+        let path = page_path(parent_dir, &format!("{}.html", page.slug))?;
+        jobs.push(PageJob::new("page", path, move |path| {
+            generate_page_html(cv, site_config, page, dependencies, path)
+        }));
+    }
 
-        let pages_path_dir = Path::new(pages_dir);
-        if pages_path_dir.exists() {
-            let pages = load_pages_from_directory(pages_path_dir)?;
+    // The glossary page lists every term expanded in the other pages
+    if site_config.glossary_page && !site_config.glossary.is_empty() {
+        let glossary = site_config.glossary.page()?;
+        let path = page_path(parent_dir, &format!("{}.html", GLOSSARY_SLUG))?;
+        jobs.push(PageJob::new("glossary", path, move |path| {
+            generate_page_html(cv, site_config, &glossary, dependencies, path)
+        }));
+    }
 
-            // Generate each static page
-            for page in pages.iter() {
-                let page_path = parent_dir
-                    .join(format!("{}.html", page.slug))
-                    .to_str()
-                    .context("Failed to convert path to string")?
-                    .to_string();
+    // Generate the build metadata page if enabled
+    if site_config.about_this_site {
+        let about_path = page_path(parent_dir, "about-this-site.html")?;
+        jobs.push(PageJob::new("about-this-site", about_path, move |path| {
+            generate_about_this_site_html(cv, site_config, dependencies, path)
+        }));
+    }
 
-                generate_page_html(cv, site_config, page, &dependencies, &page_path)?;
-            }
+    // GitHub Pages and Cloudflare Pages serve 404.html for missing pages
+    if site_config.deploys_to(DeploymentTarget::GithubPages)
+        || site_config.deploys_to(DeploymentTarget::CloudflarePages)
+    {
+        let not_found_path = page_path(parent_dir, "404.html")?;
+        jobs.push(PageJob::new("404", not_found_path, move |path| {
+            generate_not_found_html(cv, site_config, dependencies, path)
+        }));
+    }
+
+    // Pages don't depend on each other, so render them in parallel and
+    // report them in a fixed order afterwards
+    let results: Vec<Result<()>> = jobs.par_iter().map(PageJob::run).collect();
+    for (job, result) in jobs.iter().zip(results) {
+        result.with_context(|| format!("Failed to generate {}", job.path))?;
+        println!("Generated {} HTML: {}", job.kind, job.path);
+    }
+
+    // Posts, tags and case studies removed since the last build lose their
+    // pages, so they aren't published any longer
+    let generated: HashSet<&Path> = jobs.iter().map(|job| Path::new(&job.path)).collect();
+    let generated_dirs = [
+        parent_dir.join("blog"),
+        parent_dir.join("blog").join("tags"),
+        parent_dir.join("projects"),
+    ];
+    for dir in generated_dirs.iter().filter(|dir| dir.is_dir()) {
+        for stale in remove_stale_pages(dir, &generated)? {
+            println!("Removed stale page: {}", stale.display());
         }
     }
 
+    // Files the content refers to with @/
+    let copied = copy_assets(store.assets(), Path::new(CONTENT_DIR), parent_dir)?;
+    if copied > 0 {
+        println!(
+            "Copied {} content files: {}",
+            copied,
+            parent_dir.join(MEDIA_DIR).display()
+        );
+    }
+
+    if site_config.has_rss_feed() && store.has_blog() {
+        if site_config.base_url.is_some() {
+            let feed_path = parent_dir.join("feed.xml");
+            generate_rss_feed(cv, site_config, posts, &feed_path)?;
+            println!("Generated RSS feed: {}", feed_path.display());
+        } else {
+            println!("⚠️  Skipping RSS feed: it needs base_url in site.json for absolute links");
+        }
+    }
+
+    if let Some(booking) = site_config.booking.as_ref() {
+        if !booking.availability.is_empty() {
+            let ics_path = parent_dir.join(AVAILABILITY_FILE);
+            generate_availability_ics(booking, &cv.personal_info.name, &ics_path)?;
+            println!("Generated availability calendar: {}", ics_path.display());
+        }
+    }
+
+    // Contact card, linked from the CV header
+    let vcard_path = parent_dir.join(VCARD_FILE);
+    generate_vcard(&cv.personal_info, site_config.base_url(), &vcard_path)?;
+    println!("Generated contact card: {}", vcard_path.display());
+
+    // Icon sprite with the icons of the templates, social links, projects and
+    // technologies
+    let theme_templates = Theme::from_site_config(site_config)?.map(|theme| theme.templates_dir());
+    let template_dirs: Vec<&Path> = std::iter::once(Path::new("templates"))
+        .chain(theme_templates.as_deref())
+        .collect();
+    let sprite_path = parent_dir.join(SPRITE_FILE);
+    if Path::new(ICON_FONT).exists() {
+        let count = generate_sprite(
+            &used_icons(cv, &site_config.taxonomy, &template_dirs)?,
+            &sprite_path,
+        )?;
+        println!(
+            "Generated icon sprite with {} icons: {}",
+            count,
+            sprite_path.display()
+        );
+    } else {
+        println!("⚠️  Skipping icon sprite: {ICON_FONT} not found");
+    }
+
     // Generate dynamic CSS files if configurations are present
     if let Some(fonts_config) = &site_config.fonts {
         let font_css_path = parent_dir
@@ -197,109 +368,286 @@ pub fn generate_html(cv: &Cv, site_config: &SiteConfig, output_path: &str) -> Re
             fs::create_dir_all(css_parent)?;
         }
 
-        generate_colorscheme_css(colorscheme_config, &colorscheme_css_path)?;
+        generate_colorscheme_css(
+            colorscheme_config,
+            &colorscheme_css_path,
+            site_config.offline,
+        )?;
     }
 
-    // Generate deployment and SEO configuration files
-    generate_deployment_configs(parent_dir)?;
+    // Accent colors of styled tags, keyed like the `data-tag` of their pages
+    let tag_styles = site_config
+        .blog
+        .as_ref()
+        .map(|blog| {
+            blog.tag_styles
+                .iter()
+                .map(|(tag, style)| (site_config.taxonomy.canonical(tag), style.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+    let generated_css_dir = parent_dir.join("css").join("generated");
+    fs::create_dir_all(&generated_css_dir)?;
+    generate_tag_accent_css(
+        &tag_styles,
+        &generated_css_dir.join("tags.css").to_string_lossy(),
+    )?;
+
+    // Web app manifest, in the colors of the generated colorscheme
+    let colorscheme_css = fs::read_to_string(
+        parent_dir
+            .join("css")
+            .join("generated")
+            .join("colorscheme.css"),
+    )
+    .ok();
+    let manifest = WebAppManifest::new(cv, site_config, colorscheme_css.as_deref());
+    generate_manifest_json(&page_path(parent_dir, "manifest.json")?, &manifest)?;
 
     println!("HTML generation completed successfully");
     Ok(())
 }
 
-/// Generates all deployment and SEO configuration files
+/// Renders a page and writes it to the given path
+type RenderPage<'a> = Box<dyn Fn(&str) -> Result<()> + Send + Sync + 'a>;
+
+/// A page rendered by [`generate_html`]
+struct PageJob<'a> {
+    /// Kind of page, for the log
+    kind: &'static str,
+    /// Path the page is written to
+    path: String,
+    /// Renders the page
+    render: RenderPage<'a>,
+}
+
+impl<'a> PageJob<'a> {
+    fn new(
+        kind: &'static str,
+        path: String,
+        render: impl Fn(&str) -> Result<()> + Send + Sync + 'a,
+    ) -> Self {
+        PageJob {
+            kind,
+            path,
+            render: Box::new(render),
+        }
+    }
+
+    fn run(&self) -> Result<()> {
+        (self.render)(&self.path)
+    }
+}
+
+/// Remove the pages in a directory that weren't generated by this build
+///
+/// Only `.html` files directly in the directory are removed, together with
+/// their precompressed `.br` and `.gz` copies.
+///
+/// # Arguments
+///
+/// * `dir` - Directory of generated pages, such as `blog/`
+/// * `generated` - Paths of the pages this build generated
+///
+/// # Returns
+///
+/// The removed pages
+fn remove_stale_pages(dir: &Path, generated: &HashSet<&Path>) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or_default();
+        let page = name
+            .strip_suffix(".br")
+            .or_else(|| name.strip_suffix(".gz"))
+            .unwrap_or(name);
+        if !path.is_file()
+            || !page.ends_with(".html")
+            || generated.contains(dir.join(page).as_path())
+        {
+            continue;
+        }
+        fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        if page == name {
+            removed.push(path);
+        }
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+/// Path of a page in a directory, as a string
+fn page_path(dir: &Path, file_name: &str) -> Result<String> {
+    dir.join(file_name)
+        .to_str()
+        .context("Failed to convert path to string")
+        .map(str::to_string)
+}
+
+/// Generates the deployment configuration files for the configured targets, and the SEO files
+///
+/// Runs once the site is finished, since the server configurations send the
+/// Content-Security-Policy derived from it.
 ///
 /// # Arguments
 ///
 /// * `parent_dir` - Base directory where configuration files will be written
+/// * `site_config` - Site configuration with the deployment targets and security settings
+/// * `policy` - Content-Security-Policy allowing every page of the site
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-fn generate_deployment_configs(parent_dir: &Path) -> Result<()> {
+pub fn generate_deployment_configs(
+    parent_dir: &Path,
+    site_config: &SiteConfig,
+    policy: &ContentSecurityPolicy,
+) -> Result<()> {
+    let security_headers = &SecurityHeaders::from_config(site_config.security.as_ref())
+        .with_content_security_policy(policy);
+
     // Generate .htaccess for Apache servers
-    let htaccess_path = parent_dir
-        .join(".htaccess")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_htaccess(&htaccess_path)?;
+    if site_config.deploys_to(DeploymentTarget::Apache) {
+        generate_htaccess(&page_path(parent_dir, ".htaccess")?, security_headers)?;
+    }
 
     // Generate web.config for IIS servers
-    let web_config_path = parent_dir
-        .join("web.config")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_web_config(&web_config_path)?;
-
-    // Generate Netlify configuration files
-    let netlify_headers_path = parent_dir
-        .join("_headers")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_netlify_headers(&netlify_headers_path)?;
+    if site_config.deploys_to(DeploymentTarget::Iis) {
+        generate_web_config(&page_path(parent_dir, "web.config")?, security_headers)?;
+    }
 
-    let netlify_redirects_path = parent_dir
-        .join("_redirects")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_netlify_redirects(&netlify_redirects_path)?;
+    // Netlify and Cloudflare Pages read the same _headers and _redirects format
+    if site_config.deploys_to(DeploymentTarget::Netlify)
+        || site_config.deploys_to(DeploymentTarget::CloudflarePages)
+    {
+        generate_netlify_headers(&page_path(parent_dir, "_headers")?, security_headers)?;
+        generate_netlify_redirects(&page_path(parent_dir, "_redirects")?)?;
+    }
 
-    // Generate SEO files
-    let robots_path = parent_dir
-        .join("robots.txt")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_robots_txt(&robots_path)?;
+    if site_config.deploys_to(DeploymentTarget::CloudflarePages) {
+        generate_cloudflare_routes(&page_path(parent_dir, "_routes.json")?)?;
+    }
 
-    // Generate PWA files
-    let manifest_path = parent_dir
-        .join("manifest.json")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_manifest_json(&manifest_path)?;
+    if site_config.deploys_to(DeploymentTarget::GithubPages) {
+        generate_nojekyll(&page_path(parent_dir, ".nojekyll")?)?;
+        if let Some(domain) = site_config.custom_domain() {
+            generate_cname(&page_path(parent_dir, "CNAME")?, &domain)?;
+        }
+    }
 
-    let sw_path = parent_dir
-        .join("service-worker.js")
-        .to_str()
-        .context("Failed to convert path to string")?
-        .to_string();
-    generate_service_worker(&sw_path)?;
+    // Generate SEO files
+    generate_robots_txt(&page_path(parent_dir, "robots.txt")?)?;
 
     Ok(())
 }
 
+/// Generates the service worker
+///
+/// Runs after the static assets are in place, so the service worker only
+/// precaches files that exist, and its cache name changes whenever one of
+/// them does.
+///
+/// # Arguments
+///
+/// * `site_config` - Site configuration with the `pwa` settings
+/// * `output_dir` - The generated site
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_site_service_worker(site_config: &SiteConfig, output_dir: &Path) -> Result<()> {
+    let pwa = site_config.pwa.clone().unwrap_or_default();
+
+    // The main pages and assets, the icons and anything configured, if they exist
+    let mut pages: Vec<String> = fs::read_dir(output_dir)
+        .with_context(|| format!("Failed to read directory {}", output_dir.display()))?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(".html") && name != "index.html")
+        .collect();
+    pages.sort();
+    let candidates = [
+        "css/main.min.css",
+        "js/scripts.min.js",
+        SPRITE_FILE,
+        "manifest.json",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .chain(pages)
+    .chain(pwa.icons().into_iter().map(|icon| icon.src))
+    .chain(pwa.precache.iter().cloned());
+
+    let mut hasher = Sha256::new();
+    if let Ok(index) = fs::read(output_dir.join("index.html")) {
+        hasher.update(&index);
+    }
+    let mut urls = Vector::unit("/".to_string());
+    for path in candidates {
+        let path = path.trim_start_matches('/').to_string();
+        let url = format!("/{path}");
+        if let Ok(content) = fs::read(output_dir.join(&path)) {
+            if !urls.contains(&url) {
+                hasher.update(&content);
+                urls.push_back(url);
+            }
+        }
+    }
+    let hash = hasher.finalize();
+    let cache_name = format!(
+        "cv-portfolio-{}",
+        hash.iter()
+            .take(4)
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    );
+
+    generate_service_worker(
+        &page_path(output_dir, "service-worker.js")?,
+        &cache_name,
+        &urls,
+        pwa.cache_strategy,
+    )
+}
+
 /// Optimizes CSS and JavaScript assets for production
 ///
 /// # Arguments
 ///
 /// * `parent_dir` - Base directory containing assets to optimize
+/// * `options` - Browser targets and source map settings
 ///
 /// # Returns
 ///
 /// A Result indicating success or failure
-pub fn optimize_assets(parent_dir: &Path) -> Result<()> {
+pub fn optimize_assets(parent_dir: &Path, options: &AssetConfig) -> Result<()> {
     let css_dir = parent_dir.join("css");
     let js_dir = parent_dir.join("js");
 
-    // Optimize main CSS file if it exists
+    // Minify the main CSS and JS files, if they exist, in parallel
     let main_css = css_dir.join("main.css");
-    if main_css.exists() {
-        let main_css_min = css_dir.join("main.min.css");
-        optimize_css_file(&main_css, &main_css_min)?;
+    let main_js = js_dir.join("scripts.js");
+    let (css, js) = rayon::join(
+        || {
+            main_css
+                .exists()
+                .then(|| optimize_css_file(&main_css, &css_dir.join("main.min.css"), options))
+                .transpose()
+        },
+        || {
+            main_js
+                .exists()
+                .then(|| optimize_js_file(&main_js, &js_dir.join("scripts.min.js"), options))
+                .transpose()
+        },
+    );
+
+    if css?.is_some() {
         println!("Optimized main.css → main.min.css");
     }
-
-    // Optimize main JS file if it exists
-    let main_js = js_dir.join("scripts.js");
-    if main_js.exists() {
-        let main_js_min = js_dir.join("scripts.min.js");
-        optimize_js_file(&main_js, &main_js_min)?;
+    if js?.is_some() {
         println!("Optimized scripts.js → scripts.min.js");
     }
 
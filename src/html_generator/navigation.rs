@@ -0,0 +1,143 @@
+//! Navigation state
+//!
+//! Works out, at build time, which menu item and site section a generated
+//! page belongs to. Templates receive it as `nav`, so the header can mark the
+//! active item with `aria-current="page"` on every page, including blog posts
+//! and other nested pages whose path doesn't match a menu item directly.
+
+use im::Vector;
+use serde::Serialize;
+
+use crate::site_config::MenuItem;
+
+/// Navigation state of a single generated page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NavState {
+    /// Path of the page relative to the output directory, e.g. `blog/hello.html`
+    pub current_path: String,
+    /// Section the page belongs to, e.g. `blog` for `blog/hello.html`
+    pub section: String,
+    /// `path` of the active menu item, exactly as configured
+    pub active_path: Option<String>,
+}
+
+impl NavState {
+    /// Compute the navigation state of a page
+    ///
+    /// A menu item is active if it links to the page itself. Otherwise the
+    /// item linking to the page's section is active, so `blog/hello.html`
+    /// highlights the `blog.html` item.
+    ///
+    /// # Arguments
+    ///
+    /// * `menu` - The site menu
+    /// * `page_path` - Path of the page relative to the output directory
+    ///
+    /// # Returns
+    ///
+    /// The navigation state of the page
+    pub fn for_page(menu: &Vector<MenuItem>, page_path: &str) -> Self {
+        let current_path = normalize_path(page_path);
+        let section = section_of(&current_path);
+
+        let internal = || menu.iter().filter(|item| item.external != Some(true));
+        let active_path = internal()
+            .find(|item| normalize_path(&item.path) == current_path)
+            .or_else(|| {
+                internal().find(|item| {
+                    section_of(&normalize_path(&item.path)) == section
+                        || item.item_type.as_deref() == Some(section.as_str())
+                })
+            })
+            .map(|item| item.path.clone());
+
+        NavState {
+            current_path,
+            section,
+            active_path,
+        }
+    }
+
+    /// Whether a menu item is the active one
+    pub fn is_active(&self, item: &MenuItem) -> bool {
+        self.active_path.as_deref() == Some(item.path.as_str())
+    }
+}
+
+/// Normalize a site path: no leading `/` or `./`, and `index.html` for directories
+fn normalize_path(path: &str) -> String {
+    let path = path
+        .split(['#', '?'])
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches("./")
+        .trim_start_matches('/');
+
+    if path.is_empty() || path.ends_with('/') {
+        format!("{}index.html", path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Section of a normalized path: its first directory, or the page name for
+/// top-level pages (`home` for the index)
+fn section_of(path: &str) -> String {
+    match path.split_once('/') {
+        Some((directory, _)) => directory.to_string(),
+        None => match path.trim_end_matches(".html") {
+            "index" => "home".to_string(),
+            name => name.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use im::vector;
+
+    fn item(label: &str, path: &str, item_type: &str) -> MenuItem {
+        MenuItem {
+            label: label.to_string(),
+            path: path.to_string(),
+            item_type: Some(item_type.to_string()),
+            external: None,
+        }
+    }
+
+    #[test]
+    fn test_nav_state() {
+        let menu = vector![
+            item("Home", "index.html", "home"),
+            item("CV", "/cv.html", "cv"),
+            item("Blog", "blog.html", "blog"),
+            item("About", "about.html", "page"),
+        ];
+
+        let cases = [
+            ("index.html", "home", Some("index.html")),
+            ("cv.html", "cv", Some("/cv.html")),
+            ("blog.html", "blog", Some("blog.html")),
+            ("blog/hello-world.html", "blog", Some("blog.html")),
+            ("blog/tags/rust.html", "blog", Some("blog.html")),
+            ("about.html", "about", Some("about.html")),
+            ("uses.html", "uses", None),
+        ];
+
+        for (page, section, active) in cases {
+            let nav = NavState::for_page(&menu, page);
+            assert_eq!(nav.section, section, "section of {}", page);
+            assert_eq!(
+                nav.active_path.as_deref(),
+                active,
+                "active item on {}",
+                page
+            );
+        }
+
+        let nav = NavState::for_page(&menu, "blog/hello-world.html");
+        assert!(nav.is_active(&menu[2]));
+        assert!(!nav.is_active(&menu[0]));
+    }
+}
@@ -9,6 +9,10 @@ use std::fs;
 use std::path::Path;
 use std::process::Command;
 
+use crate::content_security_policy::apply_csp_meta;
+use crate::optimization::minify_css;
+use crate::site_config::AssetConfig;
+
 /// Ensures the parent directory of a file path exists, creating it if necessary
 ///
 /// # Arguments
@@ -73,6 +77,30 @@ pub fn write_file(path: &str, content: &str) -> Result<()> {
     Ok(())
 }
 
+/// Writes an HTML page, minified in release mode, with its Content-Security-Policy
+///
+/// The policy is computed after minification so inline script hashes match
+/// the bytes that are served.
+///
+/// # Arguments
+///
+/// * `path` - Path where the page will be written
+/// * `content` - Rendered HTML
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn write_html_page(path: &str, content: &str) -> Result<()> {
+    let content = if cfg!(debug_assertions) {
+        content.to_string()
+    } else {
+        String::from_utf8(minify_html_content(content)?)
+            .context("Minified HTML is not valid UTF-8")?
+    };
+
+    fs::write(path, apply_csp_meta(&content)).with_context(|| format!("Failed to write to {path}"))
+}
+
 /// Minifies HTML content using minify-html
 ///
 /// # Arguments
@@ -85,7 +113,9 @@ pub fn write_file(path: &str, content: &str) -> Result<()> {
 pub fn minify_html_content(content: &str) -> Result<Vec<u8>> {
     let cfg = Cfg {
         minify_css: true,
-        minify_js: false, // Disabled JS minification to avoid panic in minify-js
+        // minify-html's JS minifier panics on some inputs; scripts.js is
+        // minified separately by the oxc minifier
+        minify_js: false,
         ..Cfg::default()
     };
 
@@ -93,7 +123,7 @@ pub fn minify_html_content(content: &str) -> Result<Vec<u8>> {
     Ok(minified)
 }
 
-/// Minifies CSS content (placeholder implementation)
+/// Minifies CSS content with lightningcss for the default browser targets
 ///
 /// # Arguments
 ///
@@ -103,50 +133,7 @@ pub fn minify_html_content(content: &str) -> Result<Vec<u8>> {
 ///
 /// A Result containing the minified CSS content
 pub fn minify_css_content(content: &str) -> Result<String> {
-    // Basic CSS minification: remove comments and extra whitespace
-    let mut minified = content
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty() && !line.starts_with("/*"))
-        .collect::<Vec<&str>>()
-        .join(" ");
-
-    // Remove spaces around certain characters
-    minified = minified.replace(" { ", "{");
-    minified = minified.replace(" } ", "}");
-    minified = minified.replace("; ", ";");
-    minified = minified.replace(": ", ":");
-
-    Ok(minified)
-}
-
-/// Writes gzipped content to a file (currently disabled)
-///
-/// # Arguments
-///
-/// * `_path` - Path where the gzipped content would be written
-/// * `_content` - Content to compress and write
-///
-/// # Returns
-///
-/// A Result indicating success or failure
-#[allow(dead_code)]
-pub fn write_gzipped_file(_path: &str, _content: &[u8]) -> Result<()> {
-    // Gzip compression is currently disabled
-    // Uncomment when flate2 dependency is re-enabled
-    /*
-    let file = fs::File::create(path)
-        .with_context(|| format!("Failed to create gzipped file: {path}"))?;
-
-    let mut encoder = GzEncoder::new(file, Compression::default());
-    encoder.write_all(content)
-        .with_context(|| format!("Failed to write gzipped content to {path}"))?;
-
-    encoder.finish()
-        .with_context(|| format!("Failed to finish gzipped file: {path}"))?;
-    */
-
-    Ok(())
+    Ok(minify_css(content, "style.css", &AssetConfig::default())?.code)
 }
 
 /// Gets the current git commit hash for cache busting
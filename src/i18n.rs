@@ -0,0 +1,338 @@
+//! Multi-language (i18n) support
+//!
+//! Localized CV data lives next to the default data file as
+//! `cv_data.<lang>.json`. A locale file only needs the fields that differ from
+//! the default locale: it is merged over the default data, so anything it
+//! leaves out falls back to the default language.
+//!
+//! Each language is generated into its own directory (`dist/en/`, `dist/da/`,
+//! ...). Once all languages are written, [`link_translations`] adds `hreflang`
+//! alternates to every page that exists in more than one language.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cv_data::Cv;
+use crate::html_generator::utils::write_html_page;
+
+/// Get the path of the localized data file for a language
+///
+/// # Arguments
+///
+/// * `data_path` - Path to the default CV data file (e.g. `data/cv_data.json`)
+/// * `language` - Language code (e.g. `da`)
+///
+/// # Returns
+///
+/// The locale file path (e.g. `data/cv_data.da.json`)
+pub fn localized_data_path(data_path: &Path, language: &str) -> PathBuf {
+    let stem = data_path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("cv_data");
+    let extension = data_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("json");
+
+    data_path.with_file_name(format!("{}.{}.{}", stem, language, extension))
+}
+
+/// Merge locale overrides over default data
+///
+/// Objects are merged key by key and arrays element by element, so a locale
+/// file can translate the first experience entry without repeating the rest.
+/// Any other override value replaces the default. `null` overrides are ignored.
+///
+/// # Arguments
+///
+/// * `base` - Data in the default language
+/// * `overrides` - Locale-specific values
+///
+/// # Returns
+///
+/// The merged data
+pub fn merge_overrides(base: Value, overrides: Value) -> Value {
+    match (base, overrides) {
+        (Value::Object(mut base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                let merged = match base.remove(&key) {
+                    Some(existing) => merge_overrides(existing, value),
+                    None => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (Value::Array(base), Value::Array(overrides)) => {
+            let len = base.len().max(overrides.len());
+            let mut base = base.into_iter();
+            let mut overrides = overrides.into_iter();
+            Value::Array(
+                (0..len)
+                    .filter_map(|_| match (base.next(), overrides.next()) {
+                        (Some(existing), Some(value)) => Some(merge_overrides(existing, value)),
+                        (existing, value) => existing.or(value),
+                    })
+                    .collect(),
+            )
+        }
+        (base, Value::Null) => base,
+        (_, overrides) => overrides,
+    }
+}
+
+/// Localize CV data using the locale file for a language, if present
+///
+/// Runtime data already attached to `cv` (GitHub projects, avatar, profile
+/// image) is kept, so the locale file only has to contain translations.
+///
+/// # Arguments
+///
+/// * `cv` - CV data in the default language
+/// * `data_path` - Path to the default CV data file
+/// * `language` - Language code to localize to
+///
+/// # Returns
+///
+/// The localized CV, or a copy of `cv` when no locale file exists
+pub fn localize_cv(cv: &Cv, data_path: &Path, language: &str) -> Result<Cv> {
+    let locale_path = localized_data_path(data_path, language);
+    if !locale_path.exists() {
+        return Ok(cv.clone());
+    }
+
+    let content = fs::read_to_string(&locale_path)
+        .with_context(|| format!("Failed to read locale file {}", locale_path.display()))?;
+    let overrides: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse locale file {}", locale_path.display()))?;
+
+    let base = serde_json::to_value(cv).context("Failed to serialize CV data")?;
+    let mut localized: Cv = serde_json::from_value(merge_overrides(base, overrides))
+        .with_context(|| format!("Invalid CV data in {}", locale_path.display()))?;
+
+    // Not serialized, so carry it over explicitly
+    localized.personal_info.github_avatar_url = cv.personal_info.github_avatar_url.clone();
+
+    Ok(localized)
+}
+
+/// Add `hreflang` alternate links to every generated page
+///
+/// For each HTML page under `output_dir/<lang>/`, links are added for every
+/// language that has a page at the same relative path, plus an `x-default`
+/// link to the default language. Pages that already carry alternates are
+/// left alone.
+///
+/// # Arguments
+///
+/// * `output_dir` - The site output directory containing one folder per language
+/// * `languages` - Configured languages, default language first
+///
+/// # Returns
+///
+/// The number of pages that were updated
+pub fn link_translations(output_dir: &Path, languages: &Vector<String>) -> Result<usize> {
+    let default_language = match languages.front() {
+        Some(language) => language,
+        None => return Ok(0),
+    };
+
+    let mut updated = 0;
+    for language in languages.iter() {
+        let language_dir = output_dir.join(language);
+        for page in html_pages(&language_dir)? {
+            let relative = page
+                .strip_prefix(&language_dir)
+                .context("Generated page is outside its language directory")?;
+            let relative = relative.to_string_lossy().replace('\\', "/");
+
+            // Relative prefix from the page back to the output root
+            let depth = relative.matches('/').count() + 1;
+            let to_root = "../".repeat(depth);
+
+            let alternates = languages
+                .iter()
+                .filter(|other| output_dir.join(other).join(&relative).exists())
+                .map(|other| {
+                    format!(
+                        r#"<link rel="alternate" hreflang="{}" href="{}{}/{}">"#,
+                        other, to_root, other, relative
+                    )
+                })
+                .chain(
+                    output_dir
+                        .join(default_language)
+                        .join(&relative)
+                        .exists()
+                        .then(|| {
+                            format!(
+                                r#"<link rel="alternate" hreflang="x-default" href="{}{}/{}">"#,
+                                to_root, default_language, relative
+                            )
+                        }),
+                )
+                .collect::<String>();
+
+            let html = fs::read_to_string(&page)
+                .with_context(|| format!("Failed to read {}", page.display()))?;
+            if html.contains("hreflang=") {
+                continue;
+            }
+            if let Some(pos) = html.find("</head>") {
+                let linked = format!("{}{}{}", &html[..pos], alternates, &html[pos..]);
+                fs::write(&page, linked)
+                    .with_context(|| format!("Failed to write {}", page.display()))?;
+                updated += 1;
+            }
+        }
+    }
+
+    Ok(updated)
+}
+
+/// Write a root index page that redirects to the default language
+///
+/// # Arguments
+///
+/// * `output_dir` - The site output directory
+/// * `languages` - Configured languages, default language first
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn write_language_redirect(output_dir: &Path, languages: &Vector<String>) -> Result<()> {
+    let default_language = languages
+        .front()
+        .context("No languages configured for redirect")?;
+
+    let alternates = languages
+        .iter()
+        .map(|language| {
+            format!(
+                r#"<link rel="alternate" hreflang="{0}" href="{0}/index.html">"#,
+                language
+            )
+        })
+        .collect::<String>();
+
+    let html = format!(
+        r#"<!doctype html><html lang="{0}"><head><meta charset="UTF-8"><meta http-equiv="refresh" content="0; url={0}/index.html"><link rel="canonical" href="{0}/index.html">{1}<title>Redirecting</title></head><body><a href="{0}/index.html">Continue</a></body></html>"#,
+        default_language, alternates
+    );
+
+    let index_path = output_dir.join("index.html");
+    write_html_page(&index_path.to_string_lossy(), &html)
+}
+
+/// Recursively list the HTML files under a directory
+fn html_pages(dir: &Path) -> Result<Vector<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vector::new());
+    }
+
+    fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        .map(|entry| {
+            let path = entry?.path();
+            if path.is_dir() {
+                html_pages(&path)
+            } else if path.extension().is_some_and(|ext| ext == "html") {
+                Ok(Vector::unit(path))
+            } else {
+                Ok(Vector::new())
+            }
+        })
+        .try_fold(Vector::new(), |mut pages, found| {
+            pages.append(found?);
+            Ok(pages)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_localized_data_path() {
+        assert_eq!(
+            localized_data_path(Path::new("data/cv_data.json"), "da"),
+            PathBuf::from("data/cv_data.da.json")
+        );
+    }
+
+    #[test]
+    fn test_merge_overrides_falls_back_to_default() {
+        let base = json!({
+            "personal_info": { "name": "Jane", "title": "Engineer" },
+            "experiences": [
+                { "company": "A", "description": "Built things" },
+                { "company": "B", "description": "Fixed things" }
+            ]
+        });
+        let overrides = json!({
+            "personal_info": { "title": "Ingeniør" },
+            "experiences": [{ "description": "Byggede ting" }]
+        });
+
+        let merged = merge_overrides(base, overrides);
+        assert_eq!(merged["personal_info"]["name"], "Jane");
+        assert_eq!(merged["personal_info"]["title"], "Ingeniør");
+        assert_eq!(merged["experiences"][0]["company"], "A");
+        assert_eq!(merged["experiences"][0]["description"], "Byggede ting");
+        assert_eq!(merged["experiences"][1]["description"], "Fixed things");
+    }
+
+    #[test]
+    fn test_localize_cv_from_locale_file() {
+        let dir = TempDir::new().unwrap();
+        let data_path = dir.path().join("cv_data.json");
+        fs::write(
+            dir.path().join("cv_data.da.json"),
+            r#"{ "personal_info": { "summary": "Dansk resumé" } }"#,
+        )
+        .unwrap();
+
+        let mut cv = Cv::create_minimal();
+        cv.personal_info.github_avatar_url = Some("https://example.com/a.png".to_string());
+
+        let localized = localize_cv(&cv, &data_path, "da").unwrap();
+        assert_eq!(localized.personal_info.summary, "Dansk resumé");
+        assert_eq!(localized.personal_info.name, "Test User");
+        assert!(localized.personal_info.github_avatar_url.is_some());
+
+        let untranslated = localize_cv(&cv, &data_path, "de").unwrap();
+        assert_eq!(untranslated.personal_info.summary, "Test summary");
+    }
+
+    #[test]
+    fn test_link_translations() {
+        let dir = TempDir::new().unwrap();
+        let languages = Vector::from(vec!["en".to_string(), "da".to_string()]);
+        let page = "<html><head><title>t</title></head><body></body></html>";
+
+        fs::create_dir_all(dir.path().join("en/blog")).unwrap();
+        fs::create_dir_all(dir.path().join("da/blog")).unwrap();
+        fs::write(dir.path().join("en/cv.html"), page).unwrap();
+        fs::write(dir.path().join("da/cv.html"), page).unwrap();
+        fs::write(dir.path().join("en/blog/post.html"), page).unwrap();
+
+        assert_eq!(link_translations(dir.path(), &languages).unwrap(), 3);
+
+        let cv = fs::read_to_string(dir.path().join("da/cv.html")).unwrap();
+        assert!(cv.contains(r#"hreflang="en" href="../en/cv.html""#));
+        assert!(cv.contains(r#"hreflang="x-default" href="../en/cv.html""#));
+
+        let post = fs::read_to_string(dir.path().join("en/blog/post.html")).unwrap();
+        assert!(post.contains(r#"href="../../en/blog/post.html""#));
+        assert!(!post.contains(r#"hreflang="da""#));
+
+        // Already linked pages are not touched again
+        assert_eq!(link_translations(dir.path(), &languages).unwrap(), 0);
+    }
+}
@@ -0,0 +1,411 @@
+//! SVG icon sprite
+//!
+//! Icons are drawn from the glyphs of the bundled Nerd Font, but instead of
+//! shipping the font to the browser, the glyphs a site actually uses are
+//! converted to SVG `<symbol>`s in a single `icons.svg` sprite. Templates
+//! reference them with the `icon("github")` helper, which renders
+//! `<svg><use href="icons.svg#icon-github"></use></svg>`; the few icons shown
+//! before the sprite arrives ([`CRITICAL_ICONS`]) are inlined into the page.
+//!
+//! The sprite holds every icon named in a template, plus those of the social
+//! networks, project languages and technologies, which are only known at
+//! build time.
+
+use anyhow::{Context, Result};
+use im::OrdMap;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use ttf_parser::{Face, OutlineBuilder};
+
+use crate::cv_data::Cv;
+use crate::html_generator::filters::slug_str;
+use crate::taxonomy::{Taxonomy, GENERIC_ICON};
+
+/// Font the icons are drawn from
+pub const ICON_FONT: &str = "static/fonts/0xProto/0xProtoNerdFontPropo-Regular.ttf";
+
+/// File name of the sprite in the output directory
+pub const SPRITE_FILE: &str = "icons.svg";
+
+/// Icons inlined into every page, since they are visible before the sprite loads
+pub const CRITICAL_ICONS: [&str; 2] = ["sun", "moon"];
+
+/// Icon names and their Nerd Font code points
+const ICONS: [(&str, char); 23] = [
+    // Interface
+    ("arrow-right", '\u{f0054}'),
+    ("arrow-up", '\u{f005d}'),
+    ("book", '\u{f05da}'),
+    ("box", '\u{f03d6}'),
+    ("chevron-down", '\u{f0140}'),
+    ("cube", '\u{f01a7}'),
+    ("download", '\u{f01da}'),
+    ("external-link", '\u{f03cc}'),
+    ("file-pdf", '\u{f0226}'),
+    ("grid", '\u{f0570}'),
+    ("moon", '\u{f0594}'),
+    ("refresh", '\u{f0450}'),
+    ("rust", '\u{f1617}'),
+    ("star", '\u{f04ce}'),
+    ("sun", '\u{f05a8}'),
+    // Social networks
+    ("bitbucket", '\u{f00a8}'),
+    ("facebook", '\u{f020c}'),
+    ("github", '\u{f02a4}'),
+    ("gitlab", '\u{f0ba0}'),
+    ("instagram", '\u{f02fe}'),
+    ("linkedin", '\u{f033b}'),
+    ("twitter", '\u{f0544}'),
+    ("youtube", '\u{f05c3}'),
+];
+
+/// An icon converted from font glyphs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// Bounding box of the outline, as an SVG `viewBox`
+    pub view_box: String,
+    /// The outline as SVG path data
+    pub path: String,
+}
+
+impl Symbol {
+    /// Render the symbol for the sprite
+    fn to_symbol(&self, name: &str) -> String {
+        format!(
+            r#"<symbol id="icon-{name}" viewBox="{}"><path d="{}"/></symbol>"#,
+            self.view_box, self.path
+        )
+    }
+
+    /// Render the symbol as a standalone inline icon
+    fn to_inline(&self, name: &str) -> String {
+        format!(
+            r#"<svg class="icon icon-{name}" viewBox="{}" aria-hidden="true" focusable="false"><path d="{}"/></svg>"#,
+            self.view_box, self.path
+        )
+    }
+}
+
+/// Render an icon for a template
+///
+/// # Arguments
+///
+/// * `name` - Icon name, e.g. `github`; normalized like an anchor id
+///
+/// # Returns
+///
+/// Inline SVG for critical icons, otherwise a reference into the sprite
+pub fn icon(name: &str) -> String {
+    let name = slug_str(name);
+    match critical_icons().get(&name) {
+        Some(inline) => inline.clone(),
+        None => format!(
+            r#"<svg class="icon icon-{name}" aria-hidden="true" focusable="false"><use href="{SPRITE_FILE}#icon-{name}"></use></svg>"#
+        ),
+    }
+}
+
+/// Glyphs of an icon in the built-in set
+///
+/// # Arguments
+///
+/// * `name` - Icon name
+///
+/// # Returns
+///
+/// The glyphs, or None for an unknown icon
+pub fn builtin_glyphs(name: &str) -> Option<String> {
+    ICONS
+        .iter()
+        .find(|(icon, _)| *icon == name)
+        .map(|(_, glyph)| glyph.to_string())
+}
+
+/// Collect the icons a site uses
+///
+/// Templates are searched for `icon("name")` calls, Tera's
+/// `icon(name="name")` included. Social networks, project languages and
+/// technologies use built-in icons where there is one; otherwise a project
+/// language has its `language_icon` and a technology its taxonomy icon.
+/// Technologies showing their family's icon use the family's symbol, and the
+/// taxonomy's generic icon, if any, is [`GENERIC_ICON`].
+///
+/// # Arguments
+///
+/// * `cv` - CV data with the social links and projects
+/// * `taxonomy` - Technologies with their icons
+/// * `template_dirs` - Directories with the site's templates
+///
+/// # Returns
+///
+/// The glyphs of each icon by name
+pub fn used_icons(
+    cv: &Cv,
+    taxonomy: &Taxonomy,
+    template_dirs: &[&Path],
+) -> Result<OrdMap<String, String>> {
+    static ICON_CALL: OnceLock<Regex> = OnceLock::new();
+    let icon_call = ICON_CALL.get_or_init(|| {
+        Regex::new(r#"icon\(\s*(?:name\s*=\s*)?"([^"]+)"\s*\)"#).expect("valid icon call regex")
+    });
+
+    let mut names: Vec<String> = Vec::new();
+    for dir in template_dirs.iter().filter(|dir| dir.is_dir()) {
+        for path in template_files(dir)? {
+            let template = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template {}", path.display()))?;
+            names.extend(icon_call.captures_iter(&template).map(|c| c[1].to_string()));
+        }
+    }
+    names.extend(cv.personal_info.social_links.keys().cloned());
+
+    let mut icons: OrdMap<String, String> = names
+        .iter()
+        .map(|name| slug_str(name))
+        .filter_map(|name| builtin_glyphs(&name).map(|glyphs| (name, glyphs)))
+        .collect();
+    let custom_icons = cv
+        .projects
+        .iter()
+        .filter_map(|project| Some((project.language.as_ref()?, project.language_icon.as_ref()?)))
+        .chain(
+            taxonomy
+                .technologies
+                .iter()
+                .filter_map(|technology| Some((&technology.name, technology.icon.as_ref()?))),
+        );
+    for (name, glyphs) in custom_icons {
+        let name = slug_str(name);
+        let glyphs = builtin_glyphs(&name).unwrap_or_else(|| glyphs.clone());
+        if !glyphs.trim().is_empty() {
+            icons.entry(name).or_insert(glyphs);
+        }
+    }
+    if let Some(glyphs) = taxonomy.generic_icon.as_ref() {
+        if !glyphs.trim().is_empty() {
+            icons.insert(GENERIC_ICON.to_string(), glyphs.clone());
+        }
+    }
+    Ok(icons)
+}
+
+/// Convert a run of glyphs to an SVG symbol
+///
+/// # Arguments
+///
+/// * `face` - The icon font
+/// * `glyphs` - The characters to draw, laid out left to right
+///
+/// # Returns
+///
+/// The symbol, or None if the font has none of the glyphs
+pub fn glyph_symbol(face: &Face, glyphs: &str) -> Option<Symbol> {
+    let mut builder = PathBuilder::default();
+    let mut bounds: Option<(f32, f32, f32, f32)> = None;
+
+    for glyph in glyphs.chars() {
+        let Some(id) = face.glyph_index(glyph) else {
+            continue;
+        };
+        if let Some(rect) = face.outline_glyph(id, &mut builder) {
+            let (x_min, x_max) = (
+                builder.offset + f32::from(rect.x_min),
+                builder.offset + f32::from(rect.x_max),
+            );
+            // Font units point up, SVG units point down
+            let (y_min, y_max) = (-f32::from(rect.y_max), -f32::from(rect.y_min));
+            bounds = Some(match bounds {
+                Some((left, top, right, bottom)) => (
+                    left.min(x_min),
+                    top.min(y_min),
+                    right.max(x_max),
+                    bottom.max(y_max),
+                ),
+                None => (x_min, y_min, x_max, y_max),
+            });
+        }
+        builder.offset += f32::from(face.glyph_hor_advance(id).unwrap_or(0));
+    }
+
+    bounds.map(|(left, top, right, bottom)| Symbol {
+        view_box: format!(
+            "{} {} {} {}",
+            number(left),
+            number(top),
+            number(right - left),
+            number(bottom - top)
+        ),
+        path: builder.path,
+    })
+}
+
+/// Build the sprite
+///
+/// # Arguments
+///
+/// * `font` - The icon font file's contents
+/// * `icons` - The glyphs of each icon by name
+///
+/// # Returns
+///
+/// The sprite, and the names of icons the font can't draw
+pub fn build_sprite(font: &[u8], icons: &OrdMap<String, String>) -> Result<(String, Vec<String>)> {
+    let face = Face::parse(font, 0).context("Failed to parse the icon font")?;
+
+    let mut sprite = String::from(r#"<svg xmlns="http://www.w3.org/2000/svg">"#);
+    let mut missing = Vec::new();
+    for (name, glyphs) in icons.iter() {
+        match glyph_symbol(&face, glyphs) {
+            Some(symbol) => sprite.push_str(&symbol.to_symbol(name)),
+            None => missing.push(name.clone()),
+        }
+    }
+    sprite.push_str("</svg>\n");
+
+    Ok((sprite, missing))
+}
+
+/// Write the sprite
+///
+/// # Arguments
+///
+/// * `icons` - The glyphs of each icon by name
+/// * `path` - Path of the sprite file
+///
+/// # Returns
+///
+/// The number of icons in the sprite
+pub fn generate_sprite(icons: &OrdMap<String, String>, path: &Path) -> Result<usize> {
+    let font = fs::read(ICON_FONT).with_context(|| format!("Failed to read {ICON_FONT}"))?;
+    let (sprite, missing) = build_sprite(&font, icons)?;
+    for name in missing.iter() {
+        println!("⚠️  Icon font has no glyph for icon '{name}'");
+    }
+
+    fs::write(path, sprite).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(icons.len() - missing.len())
+}
+
+/// The critical icons as inline SVG, drawn from the icon font on first use
+fn critical_icons() -> &'static HashMap<String, String> {
+    static CRITICAL: OnceLock<HashMap<String, String>> = OnceLock::new();
+    CRITICAL.get_or_init(|| {
+        let Ok(font) = fs::read(ICON_FONT) else {
+            return HashMap::new();
+        };
+        let Ok(face) = Face::parse(&font, 0) else {
+            return HashMap::new();
+        };
+        CRITICAL_ICONS
+            .iter()
+            .filter_map(|name| {
+                let symbol = glyph_symbol(&face, &builtin_glyphs(name)?)?;
+                Some((name.to_string(), symbol.to_inline(name)))
+            })
+            .collect()
+    })
+}
+
+/// Recursively list the HTML templates in a directory
+fn template_files(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ext == "html") {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Format a coordinate with at most one decimal
+fn number(value: f32) -> String {
+    let formatted = format!("{value:.1}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Collects glyph outlines as SVG path data
+#[derive(Default)]
+struct PathBuilder {
+    path: String,
+    /// Horizontal position of the current glyph
+    offset: f32,
+}
+
+impl PathBuilder {
+    fn point(&mut self, x: f32, y: f32) {
+        let _ = write!(self.path, "{} {}", number(self.offset + x), number(-y));
+    }
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.push('M');
+        self.point(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.push('L');
+        self.point(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.path.push('Q');
+        self.point(x1, y1);
+        self.path.push(' ');
+        self.point(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path.push('C');
+        self.point(x1, y1);
+        self.path.push(' ');
+        self.point(x2, y2);
+        self.path.push(' ');
+        self.point(x, y);
+    }
+
+    fn close(&mut self) {
+        self.path.push('Z');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icon_sprite() {
+        assert_eq!(
+            icon("GitHub"),
+            r#"<svg class="icon icon-github" aria-hidden="true" focusable="false"><use href="icons.svg#icon-github"></use></svg>"#
+        );
+        assert!(icon("sun").starts_with(r#"<svg class="icon icon-sun" viewBox="#));
+
+        let icons: OrdMap<String, String> = [
+            ("github", "\u{f02a4}"),
+            ("cpp", "cpp"),
+            ("none", "\u{10fffd}"),
+        ]
+        .into_iter()
+        .map(|(name, glyphs)| (name.to_string(), glyphs.to_string()))
+        .collect();
+        let font = fs::read(ICON_FONT).unwrap();
+        let (sprite, missing) = build_sprite(&font, &icons).unwrap();
+        assert!(sprite.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg"><symbol id="icon-cpp" viewBox=""#
+        ));
+        assert!(sprite.contains(r#"<symbol id="icon-github" viewBox=""#));
+        assert!(sprite.contains("Z\"/></symbol>"));
+        assert_eq!(missing, vec!["none".to_string()]);
+    }
+}
@@ -18,6 +18,7 @@
 //! - [`cv_data`] - Core data structures and JSON parsing
 //! - [`github`] - GitHub API integration with intelligent caching
 //! - [`html_generator`] - Modular HTML generation system
+//! - [`docx_generator`] - Word export for applicant tracking systems
 //! - [`performance`] - Build profiling and optimization tools
 //! - [`github_cache`] - TTL-based caching system for API responses
 //!
@@ -44,23 +45,81 @@
 //! - Built-in performance profiling
 //!
 // Core CV generation modules
+pub mod access_stats;
+pub mod admin;
+pub mod applications;
 pub mod blog_posts;
+pub mod booking;
+pub mod build_info;
+pub mod case_studies;
+pub mod charts;
+pub mod citations;
+#[cfg(feature = "client")]
+pub mod client;
 pub mod colorscheme_provider; // Now a directory module
+pub mod consistency;
+pub mod content_assets;
+pub mod content_security_policy;
+pub mod content_store;
 pub mod cover_letter;
+pub mod cross_links;
 pub mod css_generator;
+pub mod cv_analysis;
 pub mod cv_data;
+pub mod cv_date;
+pub mod cv_diff;
+pub mod cv_schema;
+pub mod data_sources;
+pub mod db;
+pub mod db_maintenance;
 pub mod dependencies;
+pub mod deploy;
+pub mod dev_server;
+pub mod docx_generator;
+pub mod europass;
+pub mod featured;
+pub mod front_matter;
 pub mod github;
 pub mod github_cache;
+pub mod glossary;
 pub mod html_generator;
-pub mod language_icons;
+pub mod i18n;
+pub mod icons;
+pub mod link_archive;
+pub mod linkedin_import;
 pub mod markdown_pages;
+pub mod offers;
+pub mod openapi;
 pub mod optimization;
+pub mod package_registry;
+pub mod page_encryption;
+pub mod paste_export;
 pub mod performance;
+pub mod prep_notes;
+pub mod probe;
+pub mod profiles;
+pub mod project_curation;
+pub mod reactions;
+pub mod release;
+pub mod request_log;
+pub mod secrets;
+pub mod security_headers;
 pub mod site_config;
+pub mod site_cv;
+pub mod staleness;
+pub mod submissions;
+pub mod taxonomy;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+pub mod testimonials;
+pub mod theme;
+pub mod theme_preview;
+pub mod timeline;
 pub mod typst_generator;
 pub mod unified_config;
 pub mod validation;
+pub mod vcard;
+pub mod webhooks;
 
 // Utility modules
 // pub mod logging; // Disabled for now
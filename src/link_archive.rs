@@ -0,0 +1,386 @@
+//! Link archiving for outbound references
+//!
+//! Links rot: pages cited in a blog post move or disappear a few years later.
+//! With `blog.archive_links` enabled, external links in posts are looked up in
+//! the Internet Archive's Wayback Machine when the site is built, and submitted
+//! to it if no snapshot exists yet. Each link then gets an "archived" fallback
+//! link next to it.
+//!
+//! Results are kept in `cache/link_archive.json`, so every link is only
+//! submitted once. Links that couldn't be archived are retried after
+//! [`RETRY_AFTER_DAYS`], and each build submits at most
+//! [`MAX_SUBMISSIONS_PER_BUILD`] links to stay within the archive's rate limits.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use im::{HashMap, Vector};
+use regex::{Captures, Regex};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::blog_posts::BlogPost;
+use crate::github::block_on;
+
+/// Default path for the link archive cache
+pub const DEFAULT_LINK_ARCHIVE_PATH: &str = "cache/link_archive.json";
+
+/// Days before a link that couldn't be archived is tried again
+pub const RETRY_AFTER_DAYS: i64 = 7;
+
+/// Maximum number of links submitted to the archive in a single build
+pub const MAX_SUBMISSIONS_PER_BUILD: usize = 10;
+
+const AVAILABILITY_API: &str = "https://archive.org/wayback/available";
+const SAVE_ENDPOINT: &str = "https://web.archive.org/save/";
+const WAYBACK_BASE: &str = "https://web.archive.org";
+
+/// Archive result for a single link
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedLink {
+    /// URL of the archived snapshot, if archiving succeeded
+    pub archive_url: Option<String>,
+    /// When the link was last looked up or submitted
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ArchivedLink {
+    /// Whether the link should be looked up again
+    pub fn needs_retry(&self) -> bool {
+        self.archive_url.is_none()
+            && Utc::now() - self.checked_at > Duration::days(RETRY_AFTER_DAYS)
+    }
+}
+
+/// Archived snapshots of outbound links, by original URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkArchive {
+    /// Archive results by original URL
+    pub links: HashMap<String, ArchivedLink>,
+}
+
+impl LinkArchive {
+    /// Load the archive from file, or start an empty one if the file doesn't exist
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Self {
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the archive to file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create cache directory: {}", parent.display())
+            })?;
+        }
+
+        let content =
+            serde_json::to_string_pretty(self).context("Failed to serialize link archive")?;
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write link archive: {}", path.as_ref().display()))
+    }
+
+    /// Get the archived snapshot of a link, if there is one
+    pub fn archive_url(&self, url: &str) -> Option<&str> {
+        self.links
+            .get(url)
+            .and_then(|link| link.archive_url.as_deref())
+    }
+
+    /// Links that haven't been archived yet, or are due for a retry
+    ///
+    /// # Arguments
+    ///
+    /// * `posts` - Blog posts whose outbound links are archived
+    /// * `base_url` - The site's own URL, whose links are not archived
+    ///
+    /// # Returns
+    ///
+    /// The pending links, in the order they appear in the posts
+    pub fn pending_links(&self, posts: &Vector<BlogPost>, base_url: Option<&str>) -> Vec<String> {
+        let mut pending: Vec<String> = Vec::new();
+        for post in posts.iter() {
+            for url in outbound_links(&post.content, base_url) {
+                let due = self.links.get(&url).is_none_or(ArchivedLink::needs_retry);
+                if due && !pending.contains(&url) {
+                    pending.push(url);
+                }
+            }
+        }
+        pending
+    }
+
+    /// Record the archive result of a link
+    pub fn record(&mut self, url: &str, archive_url: Option<String>) {
+        self.links.insert(
+            url.to_string(),
+            ArchivedLink {
+                archive_url,
+                checked_at: Utc::now(),
+            },
+        );
+    }
+}
+
+/// Client for the Wayback Machine
+#[derive(Debug, Clone)]
+pub struct LinkArchiver {
+    http: reqwest::Client,
+}
+
+impl Default for LinkArchiver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinkArchiver {
+    /// Create a client with a generous timeout, saving a page can take a while
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .user_agent("cv-generator")
+                .timeout(std::time::Duration::from_secs(60))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Archive the pending outbound links of the posts
+    ///
+    /// Failures are recorded rather than returned, so an unreachable archive
+    /// never fails the build.
+    ///
+    /// # Arguments
+    ///
+    /// * `posts` - Blog posts whose outbound links are archived
+    /// * `base_url` - The site's own URL, whose links are not archived
+    /// * `archive` - Archive results, updated in place
+    ///
+    /// # Returns
+    ///
+    /// The number of links looked up
+    pub fn archive_posts(
+        &self,
+        posts: &Vector<BlogPost>,
+        base_url: Option<&str>,
+        archive: &mut LinkArchive,
+    ) -> usize {
+        let pending = archive.pending_links(posts, base_url);
+        if pending.len() > MAX_SUBMISSIONS_PER_BUILD {
+            println!(
+                "⏳ Archiving {} of {} new links, the rest follow in later builds",
+                MAX_SUBMISSIONS_PER_BUILD,
+                pending.len()
+            );
+        }
+
+        let batch: Vec<_> = pending
+            .into_iter()
+            .take(MAX_SUBMISSIONS_PER_BUILD)
+            .collect();
+        for url in &batch {
+            match block_on(self.archive(url)) {
+                Ok(archive_url) => {
+                    println!("🗄️  Archived {} as {}", url, archive_url);
+                    archive.record(url, Some(archive_url));
+                }
+                Err(e) => {
+                    println!("⚠️  Failed to archive {}: {}", url, e);
+                    archive.record(url, None);
+                }
+            }
+        }
+        batch.len()
+    }
+
+    /// Find or create a snapshot of a URL
+    ///
+    /// # Returns
+    ///
+    /// The URL of the snapshot
+    pub async fn archive(&self, url: &str) -> Result<String> {
+        match self.latest_snapshot(url).await {
+            Ok(Some(snapshot)) => Ok(snapshot),
+            _ => self.save(url).await,
+        }
+    }
+
+    /// Look up the most recent existing snapshot of a URL
+    async fn latest_snapshot(&self, url: &str) -> Result<Option<String>> {
+        let api = Url::parse_with_params(AVAILABILITY_API, &[("url", url)])?;
+        let response: AvailabilityResponse = self
+            .http
+            .get(api)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse Wayback Machine availability response")?;
+
+        Ok(response
+            .archived_snapshots
+            .closest
+            .filter(|snapshot| snapshot.available)
+            .map(|snapshot| snapshot.url.replacen("http://", "https://", 1)))
+    }
+
+    /// Submit a URL to the Wayback Machine's Save Page Now endpoint
+    async fn save(&self, url: &str) -> Result<String> {
+        let response = self
+            .http
+            .get(format!("{SAVE_ENDPOINT}{url}"))
+            .send()
+            .await
+            .with_context(|| format!("Failed to submit {} to the Wayback Machine", url))?
+            .error_for_status()?;
+
+        // The snapshot is named in Content-Location, or is where we were redirected to
+        let location = response
+            .headers()
+            .get("content-location")
+            .and_then(|value| value.to_str().ok())
+            .map(|path| format!("{WAYBACK_BASE}{path}"));
+        let final_url = response.url().as_str();
+
+        location
+            .or_else(|| final_url.contains("/web/").then(|| final_url.to_string()))
+            .context("The Wayback Machine didn't return a snapshot URL")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AvailabilityResponse {
+    #[serde(default)]
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<Snapshot>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Snapshot {
+    available: bool,
+    url: String,
+}
+
+/// External links in post HTML
+///
+/// # Arguments
+///
+/// * `html` - Post HTML
+/// * `base_url` - The site's own URL, whose links are not external
+///
+/// # Returns
+///
+/// The `http(s)` link targets, excluding the site itself and the archive
+pub fn outbound_links(html: &str, base_url: Option<&str>) -> Vec<String> {
+    external_link_regex()
+        .captures_iter(html)
+        .map(|captures| captures["href"].to_string())
+        .filter(|url| is_archivable(url, base_url))
+        .collect()
+}
+
+/// Add an "archived" link after every external link with a snapshot
+///
+/// # Arguments
+///
+/// * `html` - Post HTML
+/// * `archive` - Archive results
+///
+/// # Returns
+///
+/// The HTML with fallback links
+pub fn add_archive_links(html: &str, archive: &LinkArchive) -> String {
+    external_link_regex()
+        .replace_all(html, |captures: &Captures| {
+            let link = &captures[0];
+            match archive.archive_url(&captures["href"]) {
+                Some(archive_url) => format!(
+                    r#"{link} <a class="archived-link" href="{}" rel="nofollow">(archived)</a>"#,
+                    archive_url.replace('"', "&quot;")
+                ),
+                None => link.to_string(),
+            }
+        })
+        .into_owned()
+}
+
+fn is_archivable(url: &str, base_url: Option<&str>) -> bool {
+    let own_site = base_url
+        .map(|base| base.trim_end_matches('/'))
+        .filter(|base| !base.is_empty())
+        .is_some_and(|base| url.starts_with(base));
+
+    !own_site && !url.starts_with(WAYBACK_BASE) && !url.starts_with("https://archive.org")
+}
+
+/// Matches complete `<a>` elements linking to an `http(s)` URL
+fn external_link_regex() -> &'static Regex {
+    static EXTERNAL_LINK: OnceLock<Regex> = OnceLock::new();
+    EXTERNAL_LINK.get_or_init(|| {
+        Regex::new(r#"(?is)<a\s[^>]*?href="(?P<href>https?://[^"]+)"[^>]*>.*?</a>"#).unwrap()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"<p>See <a href="https://example.org/paper">the <em>paper</em></a>, <a href="https://me.dev/blog/other.html">my post</a>, <a href="../cv.html">my CV</a> and <a href="https://example.org/gone">this</a>.</p>"#;
+
+    #[test]
+    fn test_outbound_links() {
+        assert_eq!(
+            outbound_links(HTML, Some("https://me.dev/")),
+            ["https://example.org/paper", "https://example.org/gone"]
+        );
+    }
+
+    #[test]
+    fn test_add_archive_links() {
+        let mut archive = LinkArchive::default();
+        archive.record(
+            "https://example.org/paper",
+            Some("https://web.archive.org/web/2024/https://example.org/paper".to_string()),
+        );
+        archive.record("https://example.org/gone", None);
+
+        let html = add_archive_links(HTML, &archive);
+        assert!(html.contains(
+            r#"<a href="https://example.org/paper">the <em>paper</em></a> <a class="archived-link" href="https://web.archive.org/web/2024/https://example.org/paper" rel="nofollow">(archived)</a>,"#
+        ));
+        assert!(html.contains(r#"<a href="https://example.org/gone">this</a>."#));
+        assert_eq!(html.matches("archived-link").count(), 1);
+
+        // Failed lookups are only retried once they are old enough
+        let post = BlogPost::from_markdown(
+            &format!("---\ntitle: Links\ndate: 2024-01-01\n---\n\n{HTML}"),
+            "links".to_string(),
+        )
+        .unwrap();
+        assert!(archive
+            .pending_links(&im::vector![post.clone()], Some("https://me.dev"))
+            .is_empty());
+
+        archive.links.insert(
+            "https://example.org/gone".to_string(),
+            ArchivedLink {
+                archive_url: None,
+                checked_at: Utc::now() - Duration::days(RETRY_AFTER_DAYS + 1),
+            },
+        );
+        assert_eq!(
+            archive.pending_links(&im::vector![post], Some("https://me.dev")),
+            ["https://example.org/gone"]
+        );
+    }
+}
@@ -0,0 +1,750 @@
+//! Import from a LinkedIn data export
+//!
+//! LinkedIn's "Get a copy of your data" download is a zip of CSV files.
+//! `cv import linkedin <export.zip>` reads the profile, positions, education,
+//! skills, languages and certifications from it and merges them into the CV
+//! data, so a CV can be bootstrapped from LinkedIn and kept in sync with it.
+//!
+//! Entries are matched on company and position, or institution and degree.
+//! Unmatched LinkedIn entries are added and nothing is ever removed; when a
+//! matched entry differs, the [`MergeStrategy`] decides which version wins.
+//! Achievements and technologies aren't in the export, so those of existing
+//! entries are always kept.
+
+use anyhow::{Context, Result};
+use im::{HashMap, OrdMap, Vector};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use tracing::warn;
+
+use crate::cv_data::{Cv, Education, Experience, PersonalInfo, Skill, SkillCategory};
+use crate::cv_date::CvDate;
+
+/// Skill category that skills only found on LinkedIn are added to
+pub const IMPORTED_SKILLS_CATEGORY: &str = "LinkedIn";
+
+/// Which version wins when an entry differs between the CV and LinkedIn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the CV's version
+    Keep,
+    /// Take LinkedIn's version
+    LinkedIn,
+    /// Ask for each difference
+    Ask,
+}
+
+impl MergeStrategy {
+    /// Parse a strategy name as given to `--strategy`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "keep" => Ok(MergeStrategy::Keep),
+            "linkedin" => Ok(MergeStrategy::LinkedIn),
+            "ask" => Ok(MergeStrategy::Ask),
+            other => anyhow::bail!(
+                "Unknown merge strategy '{}' (expected keep, linkedin or ask)",
+                other
+            ),
+        }
+    }
+}
+
+/// An entry that differs between the CV and LinkedIn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// What the entry is, e.g. "Experience: Engineer at Acme"
+    pub label: String,
+    /// The CV's version
+    pub existing: String,
+    /// LinkedIn's version
+    pub imported: String,
+}
+
+/// What a merge changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Entries added from LinkedIn
+    pub added: Vector<String>,
+    /// Entries updated from LinkedIn
+    pub updated: Vector<String>,
+    /// Differing entries where the CV's version was kept
+    pub kept: Vector<String>,
+}
+
+/// The CV data in a LinkedIn export
+#[derive(Debug, Clone, Default)]
+pub struct LinkedInExport {
+    /// First and last name
+    pub name: Option<String>,
+    /// Profile headline
+    pub headline: Option<String>,
+    /// About section
+    pub summary: Option<String>,
+    /// Profile location
+    pub location: Option<String>,
+    /// Primary email address
+    pub email: Option<String>,
+    /// First website on the profile
+    pub website: Option<String>,
+    /// Positions, most recent first
+    pub experiences: Vector<Experience>,
+    /// Education, most recent first
+    pub education: Vector<Education>,
+    /// Skill names
+    pub skills: Vector<String>,
+    /// Languages with their proficiency
+    pub languages: Vector<(String, String)>,
+    /// Certification names
+    pub certifications: Vector<String>,
+}
+
+impl LinkedInExport {
+    /// Read a LinkedIn data export
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the export zip
+    ///
+    /// # Returns
+    ///
+    /// The CV data in the export
+    pub fn from_zip(path: &Path) -> Result<Self> {
+        let file =
+            fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .with_context(|| format!("{} is not a zip file", path.display()))?;
+
+        let mut files = HashMap::new();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let name = entry
+                .name()
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string();
+            if name.ends_with(".csv") {
+                let mut content = String::new();
+                entry
+                    .read_to_string(&mut content)
+                    .with_context(|| format!("Failed to read {} from the export", name))?;
+                files.insert(name, content);
+            }
+        }
+
+        Self::from_files(&files)
+    }
+
+    /// Read the CV data from the CSV files of an export
+    ///
+    /// # Arguments
+    ///
+    /// * `files` - CSV contents by file name, e.g. "Positions.csv"
+    ///
+    /// # Returns
+    ///
+    /// The CV data; missing files leave their part empty
+    pub fn from_files(files: &HashMap<String, String>) -> Result<Self> {
+        let rows = |file: &str, key: &str| -> Result<Vector<HashMap<String, String>>> {
+            files
+                .get(file)
+                .map(|content| csv_rows(content, key))
+                .transpose()
+                .map(Option::unwrap_or_default)
+                .with_context(|| format!("Failed to parse {} from the export", file))
+        };
+
+        let profile = rows("Profile.csv", "First Name")?.head().cloned();
+        let field = |name: &str| profile.as_ref().and_then(|row| non_empty(row, name));
+        let name = [field("First Name"), field("Last Name")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let email = rows("Email Addresses.csv", "Email Address")?
+            .iter()
+            .find(|row| row.get("Primary").is_some_and(|primary| primary == "Yes"))
+            .and_then(|row| non_empty(row, "Email Address"));
+
+        let experiences = rows("Positions.csv", "Company Name")?
+            .iter()
+            .filter_map(|row| {
+                let (description, achievements) =
+                    split_bullets(&non_empty(row, "Description").unwrap_or_default());
+                let company = non_empty(row, "Company Name").unwrap_or_default();
+                let (start_date, end_date) = dates(row, "Started On", "Finished On", &company)?;
+                Some(Experience {
+                    company,
+                    position: non_empty(row, "Title").unwrap_or_default(),
+                    start_date,
+                    end_date,
+                    location: non_empty(row, "Location"),
+                    description,
+                    achievements,
+                    technologies: Vector::new(),
+                    tags: Vector::new(),
+                })
+            })
+            .collect();
+
+        let education = rows("Education.csv", "School Name")?
+            .iter()
+            .filter_map(|row| {
+                let institution = non_empty(row, "School Name").unwrap_or_default();
+                let (start_date, end_date) = dates(row, "Start Date", "End Date", &institution)?;
+                Some(Education {
+                    institution,
+                    degree: non_empty(row, "Degree Name").unwrap_or_default(),
+                    field: String::new(),
+                    start_date,
+                    end_date,
+                    location: None,
+                    gpa: None,
+                    achievements: ["Activities", "Notes"]
+                        .into_iter()
+                        .filter_map(|column| non_empty(row, column))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Ok(LinkedInExport {
+            name: Some(name).filter(|name| !name.is_empty()),
+            headline: field("Headline"),
+            summary: field("Summary"),
+            location: field("Geo Location"),
+            email,
+            website: field("Websites").and_then(|websites| first_url(&websites)),
+            experiences,
+            education,
+            skills: rows("Skills.csv", "Name")?
+                .iter()
+                .filter_map(|row| non_empty(row, "Name"))
+                .collect(),
+            languages: rows("Languages.csv", "Name")?
+                .iter()
+                .filter_map(|row| {
+                    let proficiency = non_empty(row, "Proficiency").unwrap_or_default();
+                    non_empty(row, "Name").map(|name| (name, convert_proficiency(&proficiency)))
+                })
+                .collect(),
+            certifications: rows("Certifications.csv", "Name")?
+                .iter()
+                .filter_map(|row| non_empty(row, "Name"))
+                .collect(),
+        })
+    }
+
+    /// A new CV with only the LinkedIn data
+    pub fn to_cv(&self) -> Cv {
+        Cv {
+            personal_info: PersonalInfo {
+                name: self.name.clone().unwrap_or_default(),
+                title: self.headline.clone().unwrap_or_default(),
+                email: self.email.clone().unwrap_or_default(),
+                phone: None,
+                website: self.website.clone(),
+                location: self.location.clone(),
+                summary: self.summary.clone().unwrap_or_default(),
+                summaries: OrdMap::new(),
+                social_links: HashMap::new(),
+                profile_image: None,
+                github_avatar_url: None,
+            },
+            experiences: self.experiences.clone(),
+            education: self.education.clone(),
+            skill_categories: if self.skills.is_empty() {
+                Vector::new()
+            } else {
+                Vector::unit(SkillCategory {
+                    name: "Skills".to_string(),
+                    skills: self.skills.iter().cloned().map(Skill::from).collect(),
+                    tags: Vector::new(),
+                })
+            },
+            projects: Vector::new(),
+            languages: self.languages.iter().cloned().collect(),
+            certifications: self.certifications.clone(),
+            github_sources: Vector::new(),
+            registry_sources: Vector::new(),
+            testimonials: Vector::new(),
+            github_stats: None,
+        }
+    }
+
+    /// Merge the LinkedIn data into a CV
+    ///
+    /// # Arguments
+    ///
+    /// * `cv` - The existing CV data
+    /// * `take_imported` - Decides each conflict, true to take LinkedIn's version
+    ///
+    /// # Returns
+    ///
+    /// The merged CV and what changed
+    pub fn merge_into(
+        &self,
+        cv: &Cv,
+        mut take_imported: impl FnMut(&Conflict) -> bool,
+    ) -> (Cv, MergeReport) {
+        let mut report = MergeReport::default();
+        let mut decisions = Vector::new();
+        let mut resolve = |label: String, existing: String, imported: String| {
+            let conflict = Conflict {
+                label,
+                existing,
+                imported,
+            };
+            let take = take_imported(&conflict);
+            decisions.push_back((conflict.label, take));
+            take
+        };
+
+        // Personal information: fill in what's missing, resolve what differs
+        let info = &cv.personal_info;
+        let mut merge_field =
+            |label: &str, existing: &str, imported: &Option<String>| match imported
+                .as_deref()
+                .filter(|imported| *imported != existing)
+            {
+                Some(imported) if existing.is_empty() => imported.to_string(),
+                Some(imported) => {
+                    if resolve(
+                        format!("Personal info: {label}"),
+                        existing.to_string(),
+                        imported.to_string(),
+                    ) {
+                        imported.to_string()
+                    } else {
+                        existing.to_string()
+                    }
+                }
+                None => existing.to_string(),
+            };
+        let optional = |value: String| Some(value).filter(|value| !value.is_empty());
+        let personal_info = PersonalInfo {
+            name: merge_field("name", &info.name, &self.name),
+            title: merge_field("title", &info.title, &self.headline),
+            email: merge_field("email", &info.email, &self.email),
+            summary: merge_field("summary", &info.summary, &self.summary),
+            location: optional(merge_field(
+                "location",
+                info.location.as_deref().unwrap_or_default(),
+                &self.location,
+            )),
+            website: optional(merge_field(
+                "website",
+                info.website.as_deref().unwrap_or_default(),
+                &self.website,
+            )),
+            ..info.clone()
+        };
+
+        let mut experiences = cv.experiences.clone();
+        for imported in self.experiences.iter() {
+            let label = format!("Experience: {} at {}", imported.position, imported.company);
+            let existing = experiences.iter().position(|existing| {
+                existing.company.eq_ignore_ascii_case(&imported.company)
+                    && existing.position.eq_ignore_ascii_case(&imported.position)
+            });
+            match existing {
+                None => {
+                    experiences.push_back(imported.clone());
+                    report.added.push_back(label);
+                }
+                Some(index) => {
+                    let existing = &experiences[index];
+                    let updated = Experience {
+                        start_date: imported.start_date,
+                        end_date: imported.end_date,
+                        location: imported.location.clone().or(existing.location.clone()),
+                        description: imported.description.clone(),
+                        achievements: if imported.achievements.is_empty() {
+                            existing.achievements.clone()
+                        } else {
+                            imported.achievements.clone()
+                        },
+                        ..existing.clone()
+                    };
+                    if summarize_experience(&updated) != summarize_experience(existing)
+                        && resolve(
+                            label,
+                            summarize_experience(existing),
+                            summarize_experience(&updated),
+                        )
+                    {
+                        experiences.set(index, updated);
+                    }
+                }
+            }
+        }
+
+        let mut education = cv.education.clone();
+        for imported in self.education.iter() {
+            let label = format!("Education: {} at {}", imported.degree, imported.institution);
+            let existing = education.iter().position(|existing| {
+                existing
+                    .institution
+                    .eq_ignore_ascii_case(&imported.institution)
+                    && existing.degree.eq_ignore_ascii_case(&imported.degree)
+            });
+            match existing {
+                None => {
+                    education.push_back(imported.clone());
+                    report.added.push_back(label);
+                }
+                Some(index) => {
+                    let existing = &education[index];
+                    let updated = Education {
+                        start_date: imported.start_date,
+                        end_date: imported.end_date,
+                        ..existing.clone()
+                    };
+                    if summarize_education(&updated) != summarize_education(existing)
+                        && resolve(
+                            label,
+                            summarize_education(existing),
+                            summarize_education(&updated),
+                        )
+                    {
+                        education.set(index, updated);
+                    }
+                }
+            }
+        }
+
+        // Skills only found on LinkedIn go to their own category, to be sorted by hand
+        let known = |skill: &String| {
+            cv.skill_categories
+                .iter()
+                .flat_map(|category| category.skills.iter())
+                .any(|existing| existing.name.eq_ignore_ascii_case(skill))
+        };
+        let new_skills = self
+            .skills
+            .iter()
+            .filter(|skill| !known(skill))
+            .cloned()
+            .map(Skill::from)
+            .collect::<Vector<_>>();
+        let mut skill_categories = cv.skill_categories.clone();
+        if !new_skills.is_empty() {
+            report
+                .added
+                .extend(new_skills.iter().map(|skill| format!("Skill: {skill}")));
+            match skill_categories
+                .iter()
+                .position(|category| category.name == IMPORTED_SKILLS_CATEGORY)
+            {
+                Some(index) => {
+                    let mut category = skill_categories[index].clone();
+                    category.skills.append(new_skills);
+                    skill_categories.set(index, category);
+                }
+                None => skill_categories.push_back(SkillCategory {
+                    name: IMPORTED_SKILLS_CATEGORY.to_string(),
+                    skills: new_skills,
+                    tags: Vector::new(),
+                }),
+            }
+        }
+
+        let mut languages = cv.languages.clone();
+        for (language, proficiency) in self.languages.iter() {
+            match languages.get(language) {
+                None => {
+                    languages.insert(language.clone(), proficiency.clone());
+                    report.added.push_back(format!("Language: {language}"));
+                }
+                Some(existing)
+                    if existing != proficiency
+                        && !proficiency.is_empty()
+                        && resolve(
+                            format!("Language: {language}"),
+                            existing.clone(),
+                            proficiency.clone(),
+                        ) =>
+                {
+                    languages.insert(language.clone(), proficiency.clone());
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut certifications = cv.certifications.clone();
+        for certification in self.certifications.iter() {
+            if !certifications
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(certification))
+            {
+                certifications.push_back(certification.clone());
+                report
+                    .added
+                    .push_back(format!("Certification: {certification}"));
+            }
+        }
+
+        for (label, taken) in decisions {
+            if taken {
+                report.updated.push_back(label);
+            } else {
+                report.kept.push_back(label);
+            }
+        }
+
+        let merged = Cv {
+            personal_info,
+            experiences,
+            education,
+            skill_categories,
+            languages,
+            certifications,
+            ..cv.clone()
+        };
+        (merged, report)
+    }
+}
+
+/// The rows of a CSV file, by column name
+///
+/// Some exports start with notes before the header, so the header is the
+/// first row with the `key` column.
+fn csv_rows(content: &str, key: &str) -> Result<Vector<HashMap<String, String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let mut header: Option<Vec<String>> = None;
+    let mut rows = Vector::new();
+    for record in reader.records() {
+        let record = record?;
+        match header {
+            None => {
+                if record.iter().any(|column| column.trim() == key) {
+                    header = Some(
+                        record
+                            .iter()
+                            .map(|column| column.trim().to_string())
+                            .collect(),
+                    );
+                }
+            }
+            Some(ref columns) => rows.push_back(
+                columns
+                    .iter()
+                    .cloned()
+                    .zip(record.iter().map(|value| value.trim().to_string()))
+                    .collect(),
+            ),
+        }
+    }
+    Ok(rows)
+}
+
+/// A column value, if it isn't empty
+fn non_empty(row: &HashMap<String, String>, column: &str) -> Option<String> {
+    row.get(column).filter(|value| !value.is_empty()).cloned()
+}
+
+/// Convert a LinkedIn date ("Jan 2022" or "2022") to the CV format ("2022-01")
+fn convert_date(date: &str) -> Option<CvDate> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    match date.split_once(' ') {
+        Some((month, year)) => MONTHS
+            .iter()
+            .position(|name| month.starts_with(name))
+            .and_then(|index| format!("{}-{:02}", year, index + 1).parse().ok()),
+        None => date.parse().ok(),
+    }
+}
+
+/// Start and end date of a row, from a start and an end date column
+///
+/// Without a start date the entry starts when it ended. Rows without either
+/// are skipped, since a CV entry needs a start date.
+fn dates(
+    row: &HashMap<String, String>,
+    start_column: &str,
+    end_column: &str,
+    entry: &str,
+) -> Option<(CvDate, Option<CvDate>)> {
+    let end = non_empty(row, end_column).and_then(|date| convert_date(&date));
+    match non_empty(row, start_column)
+        .and_then(|date| convert_date(&date))
+        .or(end)
+    {
+        Some(start) => Some((start, end)),
+        None => {
+            warn!("Skipping {}: it has no start or end date", entry);
+            None
+        }
+    }
+}
+
+/// Convert a LinkedIn proficiency to the wording of the CV data
+fn convert_proficiency(proficiency: &str) -> String {
+    match proficiency {
+        "Native or bilingual proficiency" => "Native",
+        "Full professional proficiency" | "Professional working proficiency" => "Professional",
+        "Limited working proficiency" => "Intermediate",
+        "Elementary proficiency" => "Basic",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Split a description into its prose and its bullet points
+fn split_bullets(text: &str) -> (String, Vector<String>) {
+    let (bullets, prose): (Vec<&str>, Vec<&str>) = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .partition(|line| line.starts_with(['•', '-', '*']));
+    (
+        prose.join(" "),
+        bullets
+            .iter()
+            .map(|line| line.trim_start_matches(['•', '-', '*']).trim().to_string())
+            .collect(),
+    )
+}
+
+/// The first URL in the profile's websites, e.g. "[PERSONAL:https://example.com]"
+fn first_url(websites: &str) -> Option<String> {
+    let start = websites.find("http")?;
+    let url = websites[start..]
+        .split([']', ',', ' '])
+        .next()
+        .unwrap_or_default();
+    Some(url.to_string())
+}
+
+fn summarize_experience(experience: &Experience) -> String {
+    format!(
+        "{} - {}, {}\n{}",
+        experience.start_date,
+        experience.end_date.unwrap_or(CvDate::Present),
+        experience.location.as_deref().unwrap_or("no location"),
+        [experience.description.clone()]
+            .into_iter()
+            .chain(
+                experience
+                    .achievements
+                    .iter()
+                    .map(|item| format!("- {item}"))
+            )
+            .collect::<Vec<_>>()
+            .join("\n")
+    )
+}
+
+fn summarize_education(education: &Education) -> String {
+    format!(
+        "{} - {}",
+        education.start_date,
+        education.end_date.unwrap_or(CvDate::Present)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> LinkedInExport {
+        let files = HashMap::from(vec![
+            (
+                "Profile.csv".to_string(),
+                "First Name,Last Name,Headline,Summary,Geo Location,Websites\nAda,Lovelace,Analyst,\"Notes on engines\",London,[PERSONAL:https://ada.dev]\n".to_string(),
+            ),
+            (
+                "Positions.csv".to_string(),
+                "Company Name,Title,Description,Location,Started On,Finished On\nAcme,Engineer,\"Built things.\n• Shipped the engine\",London,Jan 2022,\nInitech,Intern,,,2019,Dec 2020\n".to_string(),
+            ),
+            (
+                "Skills.csv".to_string(),
+                "Name\nRust\nTypst\n".to_string(),
+            ),
+            (
+                "Languages.csv".to_string(),
+                "Name,Proficiency\nEnglish,Native or bilingual proficiency\n".to_string(),
+            ),
+        ]);
+        LinkedInExport::from_files(&files).unwrap()
+    }
+
+    #[test]
+    fn test_read_export() {
+        let export = export();
+
+        assert_eq!(export.name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(export.website.as_deref(), Some("https://ada.dev"));
+        assert_eq!(export.experiences.len(), 2);
+        assert_eq!(export.experiences[0].start_date, CvDate::Month(2022, 1));
+        assert_eq!(export.experiences[0].end_date, None);
+        assert_eq!(export.experiences[0].description, "Built things.");
+        assert_eq!(
+            export.experiences[0].achievements,
+            im::vector!["Shipped the engine".to_string()]
+        );
+        assert_eq!(
+            export.experiences[1].end_date,
+            Some(CvDate::Month(2020, 12))
+        );
+        assert_eq!(
+            export.languages,
+            im::vector![("English".to_string(), "Native".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_merge_into() {
+        let mut cv = Cv::create_minimal();
+        cv.experiences = im::vector![Experience {
+            company: "ACME".to_string(),
+            position: "Engineer".to_string(),
+            start_date: CvDate::Month(2021, 6),
+            end_date: None,
+            location: None,
+            description: "Built things.".to_string(),
+            achievements: im::vector!["Shipped the engine".to_string()],
+            technologies: im::vector!["Rust".to_string()],
+            tags: Vector::new(),
+        }];
+        cv.skill_categories = im::vector![SkillCategory {
+            name: "Languages".to_string(),
+            skills: im::vector!["rust".into()],
+            tags: Vector::new(),
+        }];
+
+        let (kept, report) = export().merge_into(&cv, |_| false);
+        assert_eq!(kept.experiences.len(), 2);
+        assert_eq!(kept.experiences[0].start_date, CvDate::Month(2021, 6));
+        assert_eq!(kept.personal_info.name, "Test User");
+        assert_eq!(kept.skill_categories[1].name, IMPORTED_SKILLS_CATEGORY);
+        assert_eq!(
+            kept.skill_categories[1].skills,
+            im::vector![Skill::from("Typst")]
+        );
+        assert!(report
+            .added
+            .contains(&"Experience: Intern at Initech".to_string()));
+        assert!(report
+            .kept
+            .contains(&"Experience: Engineer at Acme".to_string()));
+
+        let (taken, report) = export().merge_into(&cv, |_| true);
+        assert_eq!(taken.experiences[0].start_date, CvDate::Month(2022, 1));
+        assert_eq!(taken.experiences[0].location.as_deref(), Some("London"));
+        assert_eq!(
+            taken.experiences[0].technologies,
+            im::vector!["Rust".to_string()]
+        );
+        assert_eq!(taken.personal_info.name, "Ada Lovelace");
+        assert!(report.updated.contains(&"Personal info: name".to_string()));
+    }
+}
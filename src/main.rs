@@ -1,19 +1,65 @@
 use anyhow::{Context, Result};
+use chrono::Utc;
 use cv_generator::{
-    cv_data::{Cv, Project},
-    github,
-    github_cache::GitHubCache,
-    html_generator,
-    language_icons::LanguageIcons,
-    performance::BuildProfiler,
-    site_config::SiteConfig,
-    typst_generator,
-    unified_config::{self, AppConfig},
+    access_stats::{self, AccessStats},
+    admin::{self, AdminOperation, IpAllowlist, ADMIN_TOKEN_KEY, MAINTENANCE_FILE},
+    applications::{
+        self, ApplicationStatus, ApplicationUpdate, NewApplication, APPLICATIONS_TOKEN_KEY,
+    },
+    build_info::BuildInfo,
+    citations,
+    colorscheme_provider::CURATED_SOURCES,
+    consistency::{self, SiteCheck},
+    content_security_policy,
+    content_store::{self, ContentStore},
+    css_generator::{self, SCHEME_SOURCES},
+    cv_analysis,
+    cv_data::Cv,
+    cv_diff::{self, Side},
+    cv_schema::{validate_json, CV_DATA_SCHEMA},
+    data_sources,
+    db::{CvSource, Database},
+    db_maintenance::{self, MaintenanceReport},
+    deploy,
+    dev_server::{self, DevServerOptions},
+    docx_generator, europass,
+    github::{GitHubClient, RefreshStrategy},
+    github_cache::{GitHubCache, GITHUB_CACHE_PATH},
+    glossary,
+    html_generator::{
+        self, font_hosting::DEFAULT_FONT_CACHE_DIR, image_dimensions::DEFAULT_IMAGE_CACHE_PATH,
+        integrity::DEFAULT_INTEGRITY_CACHE_PATH,
+    },
+    i18n,
+    linkedin_import::{self, LinkedInExport, MergeStrategy},
+    offers::{self, OfferComparison},
+    openapi,
+    package_registry::{self, RegistryClient},
+    page_encryption, paste_export,
+    performance::{self, BuildProfiler},
+    prep_notes::{self, Brief, NoteKind},
+    probe::{self, ProbeOptions},
+    profiles, project_curation,
+    reactions::REACTIONS_FILE,
+    release,
+    request_log::Redaction,
+    secrets,
+    security_headers::SecurityHeaders,
+    site_config::{PdfVariant, SchemeSource, SiteConfig},
+    site_cv::{self, data_dir},
+    submissions::{SubmissionStore, SUBMISSIONS_FILE, SUBMISSIONS_TOKEN_KEY},
+    testimonials::{TestimonialStatus, TestimonialStore, TESTIMONIALS_FILE},
+    theme::Theme,
+    theme_preview,
+    typst_generator::{self, PdfJob},
+    unified_config::{self, AppConfig, DateCheck, OfflineMissing, OutputFormat},
+    webhooks::{self, DeliveryReport},
 };
-use im::Vector;
+use im::{OrdMap, Vector};
 use std::env;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use tracing::{debug, info, warn};
 
 // Extension trait to enable method chaining with pipe
@@ -118,28 +164,1817 @@ async fn download_and_save_image(url: &str, path: &str) -> Result<String> {
 
 /// Main entry point for the CV generator application
 ///
+/// Without a subcommand, or with only build options, this builds the site
+/// (see [`build`]). `help` prints the subcommands, and an unknown subcommand
+/// or build option is an error.
+///
+/// Subcommands:
+/// - `build [--format html,pdf,docx,paste] [--offline] [--source json|db]
+///   [--date-check off|warn|error] [--keep-typst]`: Build the site (the
+///   default); `--offline` builds from cached data without network access,
+///   `--source db` reads the CV from the database, `--keep-typst` keeps the
+///   markup of each PDF next to it
+/// - `db sync --from json|db --to db|json [--db-path <path>]`: Copy the CV
+///   between the JSON file and the database
+/// - `db maintain [--db-path <path>]`: Check the database's integrity, refresh
+///   its statistics and reclaim free space
+/// - `validate [<path>] [--schema]`: Check the CV data against its JSON
+///   Schema, or print the schema
+/// - `diff <a.json> <b.json>`: Show the field-level changes between two CVs
+/// - `merge <base.json> <ours.json> <theirs.json> [--prefer ours|theirs]
+///   [--output <path>]`: Three-way merge of two edits of the CV
+/// - `serve [--dev] [--port <port>]`: Serve the output directory locally;
+///   `--dev` enables live reload and rebuilds on source changes
+/// - `deploy [--target <name>] [--dry-run]`: Upload the output directory to a
+///   deploy target from the site config
+/// - `release [<tag>] [--repo owner/name] [--draft] [--dry-run]`: Build every
+///   format and publish the artifacts as a GitHub Release
+/// - `stats ingest <access.log>...` / `stats report`: Aggregate web server
+///   access logs into a private statistics report
+/// - `import linkedin <export.zip> [--strategy keep|linkedin|ask] [--dry-run]`:
+///   Merge a LinkedIn data export into the CV data
+/// - `export --format europass|europass-json|bibtex [--output <path>]`: Export
+///   the CV data in the Europass format for EU applications, or the
+///   publications as BibTeX
+/// - `probe [<base_url>] [--page <path>]... [--commit <sha|HEAD>]`: Check the
+///   deployed site is up and current, exiting non-zero on failures
+/// - `config check-secrets`: Verify the declared secrets can be resolved
+/// - `cache stats`: Show GitHub cache entries and hit/miss/expiry counts
+/// - `theme list [--source <source>]`: Show the schemes of the curated
+///   collections, or of one source
+/// - `theme search <query> [--source <source>]`: Find color schemes by name,
+///   across the built-in sources or in one
+/// - `theme preview <name> [--source <source>] [--variant <variant>]
+///   [--output <dir>] [--open]`: Render a color scheme on a preview page
+/// - `testimonials [list|approve <n>|reject <n>]`: Moderate the testimonials
+///   sent to `cv serve`
+/// - `submissions [list|show <n>|promote <n>|reject <n>]`: Review drafts sent
+///   to `cv serve`
+/// - `applications [list|add|update|dashboard|ics]`: Track job applications
+///   and their follow-ups
+/// - `notes [list|add <experience|project> <entry> <text>|remove <id>]`:
+///   Private prep notes on CV entries
+/// - `brief <cv|portfolio> [--application <id>] [--format md|pdf]
+///   [--output <path>]`: Assemble a private prep document from a CV variant
+///   and its notes
+/// - `offers [--input <path>] [--format html|pdf] [--output <path>]`: Render
+///   the private offer comparison worksheet
+/// - `webhooks [list|deliver|retry <id>]`: Show the webhook delivery log, send
+///   due deliveries or queue a failed one again
+/// - `admin <feeds|warm-cache|maintenance <on|off>>`: Regenerate the RSS
+///   feeds or revalidate the GitHub cache without a full rebuild, or switch
+///   the maintenance mode of `cv serve`
+/// - `api spec [--output <file>]`: Write the OpenAPI document of the
+///   `cv serve` APIs
+#[tokio::main]
+async fn main() -> Result<()> {
+    // Initialize logging
+    init_logging();
+
+    // Parse command-line arguments
+    let args: Vector<String> = env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("serve") => serve(&args),
+        Some("deploy") => deploy_command(&args).await,
+        Some("release") => release_command(&args).await,
+        Some("config") => config_command(&args),
+        Some("testimonials") => testimonials_command(&args),
+        Some("submissions") => submissions_command(&args),
+        Some("applications") => applications_command(&args),
+        Some("notes") => notes_command(&args),
+        Some("brief") => brief_command(&args),
+        Some("offers") => offers_command(&args),
+        Some("webhooks") => webhooks_command(&args).await,
+        Some("admin") => admin_command(&args),
+        Some("api") => api_command(&args),
+        Some("stats") => stats_command(&args),
+        Some("import") => import_command(&args),
+        Some("export") => export_command(&args),
+        Some("probe") => probe_command(&args).await,
+        Some("cache") => cache_command(&args),
+        Some("theme") => theme_command(&args),
+        Some("db") => db_command(&args),
+        Some("validate") => validate_command(&args),
+        Some("diff") => diff_command(&args),
+        Some("merge") => merge_command(&args),
+        Some("help" | "--help" | "-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        // Build options may be given without the `build` subcommand
+        None | Some("build") => build(&args).await,
+        Some(option) if option.starts_with("--") => build(&args).await,
+        Some(other) => {
+            eprintln!("{}", USAGE);
+            anyhow::bail!("Unknown subcommand '{}'", other)
+        }
+    }
+}
+
+/// Overview of the subcommands, printed by `cv help` and on unknown ones
+const USAGE: &str = "Usage: cv [build] [options] | cv <subcommand> ...
+
+Build options:
+  --format <html,pdf,docx,paste>  --offline  --source <json|db>
+  --db-path <path>  --cache-path <path>  --public-data <config>
+  --date-check <off|warn|error>  --strict  --overwrite-data  --keep-typst
+
+Subcommands:
+  serve, deploy, release, config, testimonials, submissions, applications,
+  notes, brief, offers, webhooks, admin, api, stats, import, export, probe,
+  cache, theme, db, validate, diff, merge, help";
+
+/// Options of `cv build` that take a value
+const BUILD_OPTIONS: [&str; 6] = [
+    "--cache-path",
+    "--public-data",
+    "--format",
+    "--source",
+    "--db-path",
+    "--date-check",
+];
+
+/// Options of `cv build` that are switched on by being given
+const BUILD_SWITCHES: [&str; 4] = ["--offline", "--strict", "--overwrite-data", "--keep-typst"];
+
+/// Reject build arguments that `cv build` doesn't know, such as typos
+///
+/// # Arguments
+///
+/// * `args` - Command-line arguments, with or without the `build` subcommand
+///
+/// # Returns
+///
+/// An error naming the first unknown argument or option missing its value
+fn check_build_args(args: &Vector<String>) -> Result<()> {
+    let start = if args.get(1).map(String::as_str) == Some("build") {
+        2
+    } else {
+        1
+    };
+    let mut rest = args.iter().skip(start);
+    while let Some(arg) = rest.next() {
+        if BUILD_OPTIONS.contains(&arg.as_str()) {
+            rest.next()
+                .with_context(|| format!("{} needs a value\n\n{}", arg, USAGE))?;
+        } else if !BUILD_SWITCHES.contains(&arg.as_str()) {
+            anyhow::bail!("Unknown build argument '{}'\n\n{}", arg, USAGE);
+        }
+    }
+    Ok(())
+}
+
+/// Load `config/site.json`, falling back to the defaults with a warning
+fn load_site_config() -> SiteConfig {
+    SiteConfig::from_json("config/site.json").unwrap_or_else(|e| {
+        warn!("Failed to load site config: {}. Using defaults.", e);
+        SiteConfig::default()
+    })
+}
+
+/// Run the development server
+///
+/// Command-line arguments:
+/// - `--dev`: Inject the livereload snippet and rebuild when sources change
+/// - `--port <port>`: Port to listen on (defaults to the configured API port)
+fn serve(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+
+    let port = args
+        .iter()
+        .position(|arg| arg == "--port")
+        .and_then(|i| args.get(i + 1))
+        .map(|port| port.parse::<u16>())
+        .transpose()
+        .context("Invalid value for --port")?
+        .unwrap_or(config.api_port);
+
+    let site_config = load_site_config();
+    let applications_token = secret(&config, APPLICATIONS_TOKEN_KEY)?;
+    let options = DevServerOptions::new(&config.output_dir, port)
+        .with_live_reload(args.iter().any(|arg| arg == "--dev"))
+        .with_security_headers(SecurityHeaders::from_config(site_config.security.as_ref()))
+        .with_testimonials(testimonials_path(&config))
+        .with_submissions(
+            data_dir(&config).join(SUBMISSIONS_FILE),
+            submissions_token(&config)?,
+        )
+        .with_reactions(data_dir(&config).join(REACTIONS_FILE))
+        .with_maintenance_flag(data_dir(&config).join(MAINTENANCE_FILE))
+        .with_admin_allowlist(
+            IpAllowlist::parse(config.admin_allowlist.iter()).context("Invalid admin_allowlist")?,
+        )
+        .with_trusted_proxies(
+            IpAllowlist::parse(config.trusted_proxies.iter()).context("Invalid trusted_proxies")?,
+        )
+        .with_request_log(
+            Redaction::new(config.redact_params.iter()),
+            config.access_log.clone(),
+        );
+    let options = match applications_token {
+        Some(token) => options.with_applications(config.db_path(), token),
+        None => options,
+    };
+    let options = match secret(&config, ADMIN_TOKEN_KEY)? {
+        Some(token) => {
+            if config.admin_allowlist.is_empty() {
+                println!("⚠️  admin_allowlist is empty, so the admin API turns every address away");
+            }
+            options.with_admin(token)
+        }
+        None => options,
+    };
+
+    // Rebuild by re-running this binary without a subcommand, so a failing
+    // build never takes the server down with it. Rebuilds are development
+    // builds, so local visits aren't counted by analytics.
+    let exe = env::current_exe().context("Failed to locate the cv executable")?;
+    dev_server::serve(&options, move || {
+        let status = Command::new(&exe)
+            .env("CV__ENVIRONMENT", "development")
+            .status()
+            .context("Failed to run rebuild")?;
+        if status.success() {
+            Ok(())
+        } else {
+            anyhow::bail!("Build exited with {}", status)
+        }
+    })
+}
+
+/// Upload the built site to a deploy target
+///
+/// Command-line arguments:
+/// - `--target <name>`: Target from `deploy` in the site config; may be left
+///   out when only one is configured
+/// - `--dry-run`: Show what would be uploaded without uploading
+async fn deploy_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let site_config = SiteConfig::from_json("config/site.json")?;
+
+    let name = match args
+        .iter()
+        .position(|arg| arg == "--target")
+        .and_then(|i| args.get(i + 1))
+    {
+        Some(name) => name.clone(),
+        None if site_config.deploy.len() == 1 => site_config.deploy.keys().next().unwrap().clone(),
+        None if site_config.deploy.is_empty() => {
+            anyhow::bail!("No deploy targets configured in config/site.json")
+        }
+        None => anyhow::bail!(
+            "Usage: cv deploy --target <name> (one of: {})",
+            site_config
+                .deploy
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let backend = site_config
+        .deploy
+        .get(&name)
+        .with_context(|| format!("Unknown deploy target '{}'", name))?;
+
+    deploy::deploy(
+        backend,
+        &config.output_dir,
+        &config.secrets,
+        args.iter().any(|arg| arg == "--dry-run"),
+    )
+    .await
+    .with_context(|| format!("Failed to deploy to '{}'", name))
+}
+
+/// Build every format and publish the artifacts as a GitHub Release
+///
+/// Command-line arguments:
+/// - `<tag>`: Tag of the release (default: `cv-YYYY.MM.DD` for today)
+/// - `--repo <owner/name>`: Repository (default: `GITHUB_REPOSITORY`, then the
+///   `origin` remote)
+/// - `--draft`: Create an unpublished draft release
+/// - `--dry-run`: Build and pack the artifacts without publishing them
+async fn release_command(args: &Vector<String>) -> Result<()> {
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+
+    let repo = match option("--repo").or_else(|| env::var("GITHUB_REPOSITORY").ok()) {
+        Some(repo) => repo,
+        None => git_output(&["remote", "get-url", "origin"])
+            .ok()
+            .and_then(|url| release::repository_from_remote(&url))
+            .context("Can't tell the GitHub repository; pass --repo owner/name")?,
+    };
+    let target = git_output(&["rev-parse", "HEAD"]).context("Failed to find the current commit")?;
+
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let client = GitHubClient::from_config(&config);
+    let tag = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+        Some(tag) => tag.clone(),
+        None if dry_run => release::release_tag(chrono::Local::now().date_naive(), |_| false),
+        None => {
+            let today = chrono::Local::now().date_naive();
+            let mut taken = Vec::new();
+            loop {
+                let tag = release::release_tag(today, |tag| taken.iter().any(|t| t == tag));
+                if !client.release_exists(&repo, &tag).await? {
+                    break tag;
+                }
+                taken.push(tag);
+            }
+        }
+    };
+
+    println!("📦 Building release {} of {}", tag, repo);
+    build(&im::vector![
+        "cv".to_string(),
+        "build".to_string(),
+        "--format".to_string(),
+        "html,pdf,docx".to_string(),
+    ])
+    .await?;
+
+    let artifacts_dir = tempfile::tempdir().context("Failed to create temporary directory")?;
+    let archive = artifacts_dir.path().join(format!("cv-site-{tag}.tar.gz"));
+    release::archive_directory(&config.output_dir, &tag, &archive)?;
+    let mut assets = Vector::unit(release::ReleaseAsset {
+        name: format!("cv-site-{tag}.tar.gz"),
+        path: archive,
+    });
+    assets.append(release::document_assets(&config.output_dir)?);
+
+    if dry_run {
+        println!("🔍 Would publish release {} with:", tag);
+        for asset in assets.iter() {
+            println!("  - {}", asset.name);
+        }
+        return Ok(());
+    }
+
+    let url = release::publish_release(
+        &client,
+        &repo,
+        &tag,
+        &target,
+        &assets,
+        args.iter().any(|arg| arg == "--draft"),
+    )
+    .await?;
+    println!("✅ Published {}", url);
+    Ok(())
+}
+
+/// Run a git command in the current directory and return its trimmed output
+fn git_output(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to execute 'git'")?;
+    if !output.status.success() {
+        anyhow::bail!("git {} failed", args.join(" "));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Probe the deployed site
+///
+/// Fetches the home page and every internal menu page, and fails if any of
+/// them is down, slow, or lacks the expected `build-commit` meta tag, or if
+/// the TLS certificate is about to expire.
+///
+/// Command-line arguments:
+/// - `<base_url>`: Site to probe (default: `base_url` from the site config)
+/// - `--page <path>`: Probe this page as well; may be repeated
+/// - `--commit <sha|HEAD>`: Require the pages to be built from this commit
+/// - `--max-time <ms>`: Slowest acceptable response (default: 2000)
+/// - `--min-cert-days <days>`: Fewest days the certificate must remain valid
+///   (default: 14)
+async fn probe_command(args: &Vector<String>) -> Result<()> {
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let site_config = load_site_config();
+    let base_url = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+        Some(url) => url.clone(),
+        None if !site_config.base_url().is_empty() => site_config.base_url().to_string(),
+        None => anyhow::bail!("Usage: cv probe <base_url> (or set base_url in the site config)"),
+    };
+
+    let defaults = ProbeOptions::default();
+    let mut pages = defaults.pages.clone();
+    let menu_pages = site_config
+        .menu
+        .iter()
+        .filter(|item| !item.external.unwrap_or(false) && !item.path.contains("://"))
+        .map(|item| item.path.clone());
+    let extra_pages = args
+        .iter()
+        .zip(args.iter().skip(1))
+        .filter(|(flag, _)| *flag == "--page")
+        .map(|(_, page)| page.clone());
+    for page in menu_pages.chain(extra_pages) {
+        let normalized = format!("/{}", page.trim_start_matches('/'));
+        if normalized != "/index.html" && !pages.contains(&normalized) {
+            pages.push_back(normalized);
+        }
+    }
+
+    let expected_commit = match option("--commit").map(String::as_str) {
+        Some("HEAD") => Some(git_output(&["rev-parse", "HEAD"])?),
+        commit => commit.map(str::to_string),
+    };
+    let options = ProbeOptions {
+        pages,
+        expected_commit,
+        max_response_time: option("--max-time")
+            .map(|ms| ms.parse().context("--max-time must be milliseconds"))
+            .transpose()?
+            .map_or(defaults.max_response_time, std::time::Duration::from_millis),
+        min_certificate_days: option("--min-cert-days")
+            .map(|days| days.parse().context("--min-cert-days must be a number"))
+            .transpose()?
+            .unwrap_or(defaults.min_certificate_days),
+    };
+
+    println!("🩺 Probing {}", base_url);
+    let report = probe::probe(&base_url, &options).await?;
+    for page in report.pages.iter() {
+        let status = page
+            .status
+            .map_or_else(|| "---".to_string(), |status| status.to_string());
+        let icon = if page.problems.is_empty() {
+            "✅"
+        } else {
+            "❌"
+        };
+        println!(
+            "  {} {} {} ({} ms)",
+            icon,
+            status,
+            page.url,
+            page.response_time.as_millis()
+        );
+        for problem in page.problems.iter() {
+            println!("     {}", problem);
+        }
+    }
+    if let Some(expiry) = report.certificate_expiry {
+        println!(
+            "  🔐 TLS certificate valid until {}",
+            expiry.format("%Y-%m-%d")
+        );
+    }
+    for problem in report.problems.iter() {
+        println!("  ❌ {}", problem);
+    }
+
+    if !report.passed() {
+        anyhow::bail!("Probe of {} failed", base_url);
+    }
+    println!("✅ All checks passed");
+    Ok(())
+}
+
+/// Run a `config` subcommand
+///
+/// Subcommands:
+/// - `check-secrets`: Resolve every secret declared in `config.toml` and report
+///   where it came from, without printing any values. Fails if a required
+///   secret can't be resolved.
+fn config_command(args: &Vector<String>) -> Result<()> {
+    match args.get(2).map(String::as_str) {
+        Some("check-secrets") => {
+            let config = AppConfig::load().context("Failed to load configuration")?;
+            if config.secrets.is_empty() {
+                println!("ℹ️  No secrets declared in config.toml");
+                return Ok(());
+            }
+
+            println!("🔐 Checking {} secret(s)", config.secrets.len());
+            let checks = secrets::check_secrets(&config.secrets);
+            for check in checks.iter() {
+                match check.outcome {
+                    Ok(Some(ref origin)) => println!("  ✅ {}: {}", check.name, origin),
+                    Ok(None) if check.required => {
+                        println!("  ❌ {}: required but not set", check.name)
+                    }
+                    Ok(None) => println!("  ⚠️  {}: not set (optional)", check.name),
+                    Err(ref e) => println!("  ❌ {}: {}", check.name, e),
+                }
+            }
+
+            let failures = checks.iter().filter(|check| check.is_failure()).count();
+            if failures > 0 {
+                anyhow::bail!("{} secret(s) could not be resolved", failures);
+            }
+            println!("✅ All required secrets are resolvable");
+            Ok(())
+        }
+        Some(other) => anyhow::bail!("Unknown config subcommand '{}'", other),
+        None => anyhow::bail!("Usage: cv config check-secrets"),
+    }
+}
+
+/// Where builds keep the last downloaded GitHub avatar, without its extension
+const KEPT_AVATAR_PATH: &str = "cache/avatar";
+
+/// Report CV dates that don't add up, see [`cv_analysis::analyze`]
+///
+/// They are warnings unless `date_check` is `error`, which fails the build.
+fn check_dates(config: &AppConfig, cv: &Cv) -> Result<()> {
+    let check = config.date_check()?;
+    if check == DateCheck::Off {
+        return Ok(());
+    }
+
+    let issues = cv_analysis::analyze(cv, chrono::Local::now().date_naive());
+    for issue in issues.iter() {
+        warn!("CV dates: {}", issue);
+    }
+    if check == DateCheck::Error && !issues.is_empty() {
+        anyhow::bail!(
+            "{} date problems in the CV data (date_check = \"error\"):\n  {}",
+            issues.len(),
+            issues
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n  ")
+        );
+    }
+    Ok(())
+}
+
+/// Report data the build couldn't get
+///
+/// Offline builds fail on it unless `offline_missing` is `warn`; other builds
+/// carry on without it.
+///
+/// # Arguments
+///
+/// * `config` - Application configuration
+/// * `what` - The data, e.g. "GitHub projects"
+/// * `error` - Why it couldn't be fetched
+fn missing_data(config: &AppConfig, what: &str, error: anyhow::Error) -> Result<()> {
+    if config.is_offline() && config.offline_missing()? == OfflineMissing::Error {
+        return Err(error.context(format!("{} not available offline", what)));
+    }
+    warn!("Failed to fetch {}: {:#}", what, error);
+    Ok(())
+}
+
+/// Keep a copy of the downloaded GitHub avatar for offline builds
+fn keep_avatar(path: &str) -> Result<()> {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png");
+    if let Some(parent) = Path::new(KEPT_AVATAR_PATH).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    for old in ["png", "jpg"] {
+        fs::remove_file(format!("{}.{}", KEPT_AVATAR_PATH, old)).ok();
+    }
+    fs::copy(path, format!("{}.{}", KEPT_AVATAR_PATH, extension))?;
+    Ok(())
+}
+
+/// Copy the GitHub avatar kept by an earlier build to the output
+///
+/// # Arguments
+///
+/// * `path` - Where to copy it; the extension follows the kept image
+///
+/// # Returns
+///
+/// The path it was copied to
+fn copy_kept_avatar(path: &str) -> Result<String> {
+    let extension = ["png", "jpg"]
+        .into_iter()
+        .find(|extension| Path::new(&format!("{}.{}", KEPT_AVATAR_PATH, extension)).exists())
+        .with_context(|| format!("No GitHub avatar kept in {}.png", KEPT_AVATAR_PATH))?;
+    let actual_path = Path::new(path)
+        .with_extension(extension)
+        .to_string_lossy()
+        .into_owned();
+    fs::copy(format!("{}.{}", KEPT_AVATAR_PATH, extension), &actual_path)?;
+    Ok(actual_path)
+}
+
+/// Run an admin operation, printing each step as it completes
+///
+/// Operations:
+/// - `feeds`: Regenerate the RSS feeds in the output directory
+/// - `warm-cache`: Revalidate the GitHub cache entries the build uses
+/// - `maintenance <on|off>`: Switch the maintenance mode of `cv serve`
+fn admin_command(args: &Vector<String>) -> Result<()> {
+    const USAGE: &str = "Usage: cv admin <feeds|warm-cache|maintenance <on|off>>";
+    let operation = args.get(2).context(USAGE)?;
+    if operation == "maintenance" {
+        let config = AppConfig::load().context("Failed to load configuration")?;
+        let enabled = match args.get(3).map(String::as_str) {
+            Some("on") => true,
+            Some("off") => false,
+            _ => anyhow::bail!("Usage: cv admin maintenance <on|off>"),
+        };
+        admin::set_maintenance(&data_dir(&config).join(MAINTENANCE_FILE), enabled)?;
+        println!("🚧 Maintenance mode {}", if enabled { "on" } else { "off" });
+        return Ok(());
+    }
+    let operation = operation.parse::<AdminOperation>()?;
+    println!("🛠️  Running {}", operation);
+    let report = admin::run(operation, &mut |step| {
+        println!("  ✓ {} ({} ms): {}", step.name, step.millis, step.detail)
+    })?;
+    println!("✅ {} done in {} ms", report.operation, report.total_millis);
+    Ok(())
+}
+
+/// Describe the `cv serve` APIs
+///
+/// Command-line arguments:
+/// - `spec`: Write the OpenAPI document
+/// - `--output <file>`: File to write it to (defaults to `openapi.json`)
+fn api_command(args: &Vector<String>) -> Result<()> {
+    if args.get(2).map(String::as_str) != Some("spec") {
+        anyhow::bail!("Usage: cv api spec [--output <file>]");
+    }
+    let output = args
+        .iter()
+        .position(|arg| arg == "--output")
+        .and_then(|i| args.get(i + 1))
+        .map_or(openapi::DEFAULT_SPEC_FILE, String::as_str);
+    openapi::write_spec(Path::new(output))?;
+    println!("📜 Wrote the OpenAPI document to {}", output);
+    Ok(())
+}
+
+/// Run a `cache` subcommand
+///
+/// Subcommands:
+/// - `stats`: Show the GitHub cache entries, and the hit, miss and expiry
+///   counts of the last build and of all builds
+fn cache_command(args: &Vector<String>) -> Result<()> {
+    match args.get(2).map(String::as_str) {
+        Some("stats") => {
+            let cache = GitHubCache::load(GITHUB_CACHE_PATH).with_context(|| {
+                format!("Failed to load GitHub cache from {}", GITHUB_CACHE_PATH)
+            })?;
+            cache.print_stats();
+            Ok(())
+        }
+        Some(other) => anyhow::bail!("Unknown cache subcommand '{}'", other),
+        None => anyhow::bail!("Usage: cv cache stats"),
+    }
+}
+
+/// Number of color schemes `cv theme search` shows
+const THEME_SEARCH_LIMIT: usize = 20;
+
+/// Run a `theme` subcommand
+///
+/// Subcommands:
+/// - `list [--source <source>]`: Show the schemes of the curated collections,
+///   or of `source`
+/// - `search <query> [--source <source>]`: Find color schemes by name, in
+///   every built-in source or only in `source`
+/// - `preview <name> [--source <source>] [--variant <variant>] [--output <dir>]
+///   [--open]`: Render a scheme on a standalone page, in a temporary
+///   directory unless `--output` names one, and open it in the browser
+fn theme_command(args: &Vector<String>) -> Result<()> {
+    match args.get(2).map(String::as_str) {
+        Some("list") => {
+            let source = args
+                .iter()
+                .position(|arg| arg == "--source")
+                .and_then(|i| args.get(i + 1));
+            let sources = match source {
+                Some(source) => vec![source.as_str()],
+                None => CURATED_SOURCES.to_vec(),
+            };
+            for source in sources {
+                let names = css_generator::list_available_schemes(Some(source))?;
+                println!("🎨 {}:", source);
+                for name in names {
+                    println!("  {}", name);
+                }
+            }
+            Ok(())
+        }
+        Some("search") => {
+            let source = args
+                .iter()
+                .position(|arg| arg == "--source")
+                .and_then(|i| args.get(i + 1));
+            let query = args
+                .iter()
+                .skip(3)
+                .take_while(|arg| !arg.starts_with("--"))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if query.is_empty() {
+                anyhow::bail!("Usage: cv theme search <query> [--source <source>]");
+            }
+
+            let sources = match source {
+                Some(source) => vec![source.as_str()],
+                None => SCHEME_SOURCES.to_vec(),
+            };
+            let results = css_generator::search_schemes(&query, &sources)?;
+            if results.is_empty() {
+                println!("ℹ️  No color schemes match '{}'", query);
+                return Ok(());
+            }
+            println!("🎨 {} color schemes match '{}':", results.len(), query);
+            for result in results.iter().take(THEME_SEARCH_LIMIT) {
+                println!("  {:<40} {}", result.name, result.source);
+            }
+            if results.len() > THEME_SEARCH_LIMIT {
+                println!("  … and {} more", results.len() - THEME_SEARCH_LIMIT);
+            }
+            Ok(())
+        }
+        Some("preview") => {
+            let option = |name: &str| {
+                args.iter()
+                    .position(|arg| arg == name)
+                    .and_then(|i| args.get(i + 1))
+            };
+            let name = args
+                .iter()
+                .skip(3)
+                .take_while(|arg| !arg.starts_with("--"))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" ");
+            if name.is_empty() {
+                anyhow::bail!(
+                    "Usage: cv theme preview <name> [--source <source>] [--variant <variant>] [--output <dir>] [--open]"
+                );
+            }
+
+            let scheme = SchemeSource {
+                name,
+                source: option("--source").cloned(),
+                url: None,
+                variant: option("--variant").cloned(),
+            };
+            let (palette, provider) = css_generator::fetch_palette(&scheme, false)?;
+            let dir = option("--output")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| env::temp_dir().join(theme_preview::PREVIEW_DIR));
+            let path = theme_preview::write_preview(&dir, &scheme.name, &provider, &palette)?;
+            println!("🎨 Preview of {}: {}", scheme.name, path.display());
+            if args.iter().any(|arg| arg == "--open") {
+                theme_preview::open_in_browser(&path)?;
+            }
+            Ok(())
+        }
+        Some(other) => anyhow::bail!("Unknown theme subcommand '{}'", other),
+        None => anyhow::bail!(
+            "Usage: cv theme <list|search <query>|preview <name>> [--source <source>]"
+        ),
+    }
+}
+
+/// Path of the testimonials file, next to the CV data
+fn testimonials_path(config: &AppConfig) -> std::path::PathBuf {
+    data_dir(config).join(TESTIMONIALS_FILE)
+}
+
+/// Run a `testimonials` subcommand
+///
+/// Subcommands:
+/// - (none) or `list`: Show all testimonials with their number and status
+/// - `approve <n>`: Publish testimonial `n`
+/// - `reject <n>`: Keep testimonial `n` off the site
+fn testimonials_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let path = testimonials_path(&config);
+    let mut store = TestimonialStore::load(&path)?;
+
+    let status = match args.get(2).map(String::as_str) {
+        None | Some("list") => {
+            if store.testimonials.is_empty() {
+                println!("ℹ️  No testimonials in {}", path.display());
+            }
+            for (i, testimonial) in store.testimonials.iter().enumerate() {
+                let relation = testimonial
+                    .relation
+                    .as_deref()
+                    .map(|relation| format!(", {relation}"))
+                    .unwrap_or_default();
+                println!(
+                    "#{} [{:?}] {}{}\n    \"{}\"",
+                    i + 1,
+                    testimonial.status,
+                    testimonial.author,
+                    relation,
+                    testimonial.quote
+                );
+            }
+            return Ok(());
+        }
+        Some("approve") => TestimonialStatus::Approved,
+        Some("reject") => TestimonialStatus::Rejected,
+        Some(other) => anyhow::bail!("Unknown testimonials subcommand '{}'", other),
+    };
+
+    let number = args
+        .get(3)
+        .context("Usage: cv testimonials approve|reject <number>")?
+        .parse::<usize>()
+        .context("Invalid testimonial number")?;
+    let testimonial = store.moderate(number, status)?;
+    println!(
+        "✅ Testimonial #{} by {} is now {:?}",
+        number, testimonial.author, status
+    );
+    store.save(&path)
+}
+
+/// Run a `db` subcommand
+///
+/// Subcommands:
+/// - `sync --from json --to db`: Store the CV data file in the database
+/// - `sync --from db --to json`: Write the database's CV to the data file
+/// - `maintain`: Check integrity, analyze, vacuum and checkpoint the database
+///
+/// `--db-path <path>` overrides the configured database.
+fn db_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let config = match option("--db-path") {
+        Some(path) => config.with_option(unified_config::DB_PATH_KEY, path),
+        None => config,
+    };
+    let usage = "Usage: cv db sync --from json|db --to db|json [--db-path <path>] | cv db maintain [--db-path <path>]";
+    match args.get(2).map(String::as_str) {
+        Some("sync") => {}
+        Some("maintain") => {
+            let report = Database::open(config.db_path())?.maintain(&Default::default())?;
+            print_maintenance_report(&report);
+            if !report.is_healthy() {
+                anyhow::bail!("{} failed its integrity check", config.db_path().display());
+            }
+            return Ok(());
+        }
+        _ => anyhow::bail!(usage),
+    }
+
+    let from = option("--from").context(usage)?.parse::<CvSource>()?;
+    let to = option("--to").context(usage)?.parse::<CvSource>()?;
+    let db_path = config.db_path();
+    match (from, to) {
+        (CvSource::Json, CvSource::Db) => {
+            let cv = Cv::from_json(&config.data_path.to_string_lossy())?;
+            Database::open(&db_path)?.save_cv(&cv)?;
+            println!(
+                "✅ Copied {} to {}",
+                config.data_path.display(),
+                db_path.display()
+            );
+        }
+        (CvSource::Db, CvSource::Json) => {
+            let cv = Database::open(&db_path)?.load_cv()?;
+            fs::write(&config.data_path, serde_json::to_string_pretty(&cv)?)
+                .with_context(|| format!("Failed to write {}", config.data_path.display()))?;
+            println!(
+                "✅ Copied {} to {}",
+                db_path.display(),
+                config.data_path.display()
+            );
+        }
+        _ => anyhow::bail!("--from and --to must differ ({})", usage),
+    }
+    Ok(())
+}
+
+/// Print what a maintenance run of the CV database found and did
+fn print_maintenance_report(report: &MaintenanceReport) {
+    if report.is_healthy() {
+        println!("✅ Integrity check passed");
+    } else {
+        println!("❌ Integrity check failed:");
+        for problem in &report.integrity_problems {
+            println!("   {}", problem);
+        }
+        return;
+    }
+    if report.vacuumed {
+        println!(
+            "🧹 Reclaimed free pages ({} bytes were free)",
+            report.free_bytes
+        );
+    }
+    if report.checkpointed {
+        println!(
+            "📝 Checkpointed the write-ahead log ({} bytes)",
+            report.wal_bytes
+        );
+    }
+    println!(
+        "📊 Statistics refreshed; {} bytes → {} bytes in {} ms",
+        report.size_before, report.size_after, report.millis
+    );
+}
+
+/// Check a CV data file against the published JSON Schema
+///
+/// Command-line arguments:
+/// - `<path>`: The CV data file (default: the configured data path)
+/// - `--schema`: Print the schema instead, e.g. to save it for an editor
+fn validate_command(args: &Vector<String>) -> Result<()> {
+    if args.iter().any(|arg| arg == "--schema") {
+        print!("{}", CV_DATA_SCHEMA);
+        return Ok(());
+    }
+
+    let path = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            AppConfig::load()
+                .context("Failed to load configuration")?
+                .data_path
+        }
+    };
+    let source =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let problems = validate_json(&source)?;
+    if problems.is_empty() {
+        println!("✅ {} matches the CV data schema", path.display());
+        return Ok(());
+    }
+    for problem in problems.iter() {
+        println!("{}:{}", path.display(), problem);
+    }
+    anyhow::bail!("{} problems in {}", problems.len(), path.display())
+}
+
+/// Show the structural differences between two CV data files
+///
+/// Command-line arguments:
+/// - `<a.json> <b.json>`: The CV to compare from and the one to compare to
+fn diff_command(args: &Vector<String>) -> Result<()> {
+    let usage = "Usage: cv diff <a.json> <b.json>";
+    let (Some(old), Some(new)) = (args.get(2), args.get(3)) else {
+        anyhow::bail!(usage);
+    };
+
+    let changes = cv_diff::diff(&Cv::from_json(old)?, &Cv::from_json(new)?)?;
+    if changes.is_empty() {
+        println!("✅ No differences between {} and {}", old, new);
+        return Ok(());
+    }
+    for change in changes.iter() {
+        println!("{}", change);
+    }
+    println!("\n{} changes", changes.len());
+    Ok(())
+}
+
+/// Merge two edits of the CV data made from a common base
+///
+/// Command-line arguments:
+/// - `<base.json> <ours.json> <theirs.json>`: The common ancestor and the two
+///   edited versions
+/// - `--prefer ours|theirs`: Side that wins conflicts; without it the merge
+///   stops when there are conflicts
+/// - `--output <path>`: File to write the merged CV to (default: `ours.json`)
+fn merge_command(args: &Vector<String>) -> Result<()> {
+    let usage = "Usage: cv merge <base.json> <ours.json> <theirs.json> \
+                 [--prefer ours|theirs] [--output <path>]";
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let (Some(base), Some(ours), Some(theirs)) = (args.get(2), args.get(3), args.get(4)) else {
+        anyhow::bail!(usage);
+    };
+    let prefer = option("--prefer")
+        .map(|side| side.parse::<Side>())
+        .transpose()?;
+    let output = option("--output").unwrap_or(ours);
+
+    let (cv, conflicts) = cv_diff::merge(
+        &Cv::from_json(base)?,
+        &Cv::from_json(ours)?,
+        &Cv::from_json(theirs)?,
+        prefer.unwrap_or_default(),
+    )?;
+    for conflict in conflicts.iter() {
+        println!("{}", conflict);
+    }
+    if !conflicts.is_empty() && prefer.is_none() {
+        anyhow::bail!(
+            "{} conflicts; resolve them by hand or rerun with --prefer ours|theirs",
+            conflicts.len()
+        );
+    }
+
+    fs::write(output, serde_json::to_string_pretty(&cv)?)
+        .with_context(|| format!("Failed to write {}", output))?;
+    println!(
+        "✅ Merged {} into {} ({} conflicts)",
+        theirs,
+        output,
+        conflicts.len()
+    );
+    Ok(())
+}
+
+/// Run an `applications` subcommand
+///
+/// Subcommands:
+/// - (none) or `list`: Show all applications with their id and status
+/// - `add <company> <role> [--variant <name>] [--status <status>]
+///   [--applied <date>] [--follow-up <date>] [--notes <text>]`: Track an
+///   application; it counts as applied today unless `--applied` says otherwise
+/// - `update <id> [--status <status>] [--follow-up <date>] [--notes <text>]`:
+///   Change an application
+/// - `dashboard`: Write the overview page to `stats/applications.html`
+/// - `ics [--output <path>]`: Write the follow-up reminders as a calendar
+///
+/// `--db-path <path>` overrides the configured database.
+fn applications_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let date = |name: &str| {
+        option(name)
+            .map(|date| date.parse::<chrono::NaiveDate>())
+            .transpose()
+            .with_context(|| format!("Invalid date for {} (expected YYYY-MM-DD)", name))
+    };
+    let config = match option("--db-path") {
+        Some(path) => config.with_option(unified_config::DB_PATH_KEY, path),
+        None => config,
+    };
+    let db = Database::open(config.db_path())?;
+    let today = chrono::Local::now().date_naive();
+
+    let subcommand = args
+        .get(2)
+        .map(String::as_str)
+        .filter(|arg| !arg.starts_with("--"))
+        .unwrap_or("list");
+    match subcommand {
+        "list" => {
+            let applications = db.applications()?;
+            if applications.is_empty() {
+                println!("ℹ️  No applications tracked yet (cv applications add)");
+            }
+            for application in applications.iter() {
+                let follow_up = application
+                    .follow_up
+                    .filter(|_| application.status.is_open())
+                    .map(|date| format!(", follow up {}", date))
+                    .unwrap_or_default();
+                println!(
+                    "#{} [{}] {} at {}{}",
+                    application.id,
+                    application.status,
+                    application.role,
+                    application.company,
+                    follow_up
+                );
+            }
+        }
+        "add" => {
+            let usage = "Usage: cv applications add <company> <role> [--variant <name>] \
+                         [--status <status>] [--applied <date>] [--follow-up <date>] [--notes <text>]";
+            let (Some(company), Some(role)) = (args.get(3), args.get(4)) else {
+                anyhow::bail!(usage);
+            };
+            let status = option("--status")
+                .map(|status| status.parse::<ApplicationStatus>())
+                .transpose()?
+                .unwrap_or_default();
+            let applied_on =
+                date("--applied")?.or((status != ApplicationStatus::Planned).then_some(today));
+            let id = db.add_application(&NewApplication {
+                company: company.clone(),
+                role: role.clone(),
+                variant: option("--variant").cloned(),
+                status,
+                applied_on,
+                follow_up: date("--follow-up")?,
+                notes: option("--notes").cloned(),
+            })?;
+            println!("✅ Tracking application #{}: {} at {}", id, role, company);
+        }
+        "update" => {
+            let id = args
+                .get(3)
+                .context("Usage: cv applications update <id> [--status <status>] [--follow-up <date>] [--notes <text>]")?
+                .trim_start_matches('#')
+                .parse::<i64>()
+                .context("Invalid application id")?;
+            let application = db.update_application(
+                id,
+                &ApplicationUpdate {
+                    status: option("--status")
+                        .map(|status| status.parse::<ApplicationStatus>())
+                        .transpose()?,
+                    follow_up: date("--follow-up")?,
+                    notes: option("--notes").cloned(),
+                },
+            )?;
+            println!(
+                "✅ Application #{} ({} at {}) is now {}",
+                id, application.role, application.company, application.status
+            );
+        }
+        "dashboard" => {
+            let name = Cv::from_json(&config.data_path.to_string_lossy())
+                .map(|cv| cv.personal_info.name)
+                .unwrap_or_else(|_| "CV".to_string());
+            let html = applications::dashboard_html(&db.applications()?, &name, today);
+            write_private(Path::new(applications::DASHBOARD_FILE), &html)?;
+            println!("✅ Dashboard written to {}", applications::DASHBOARD_FILE);
+        }
+        "ics" => {
+            let output = option("--output")
+                .map(String::as_str)
+                .unwrap_or(applications::FOLLOW_UPS_FILE);
+            let calendar =
+                applications::follow_up_calendar(&db.applications()?, chrono::Utc::now());
+            write_private(Path::new(output), &calendar)?;
+            println!("✅ Follow-up reminders written to {}", output);
+        }
+        other => anyhow::bail!("Unknown applications subcommand '{}'", other),
+    }
+    Ok(())
+}
+
+/// Run a `notes` subcommand
+///
+/// Subcommands:
+/// - (none) or `list`: Show all notes with their id and entry
+/// - `add <experience|project> <entry> <text>`: Attach a note to an entry; an
+///   experience can be named by "Position at Company", company or position
+/// - `remove <id>`: Delete a note
+///
+/// `--db-path <path>` overrides the configured database.
+fn notes_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let config = match args
+        .iter()
+        .position(|arg| arg == "--db-path")
+        .and_then(|i| args.get(i + 1))
+    {
+        Some(path) => config.with_option(unified_config::DB_PATH_KEY, path),
+        None => config,
+    };
+    let db = Database::open(config.db_path())?;
+
+    let subcommand = args
+        .get(2)
+        .map(String::as_str)
+        .filter(|arg| !arg.starts_with("--"))
+        .unwrap_or("list");
+    match subcommand {
+        "list" => {
+            let notes = db.notes()?;
+            if notes.is_empty() {
+                println!("ℹ️  No notes yet (cv notes add)");
+            }
+            for note in notes.iter() {
+                println!("#{} [{}] {}", note.id, note.kind, note.entry);
+                for line in note.body.lines() {
+                    println!("    {}", line);
+                }
+            }
+        }
+        "add" => {
+            let usage = "Usage: cv notes add <experience|project> <entry> <text>";
+            let (Some(kind), Some(entry), Some(body)) = (args.get(3), args.get(4), args.get(5))
+            else {
+                anyhow::bail!(usage);
+            };
+            let kind = kind.parse::<NoteKind>()?;
+            let entry = prep_notes::resolve_entry(&load_cv(&config)?, kind, entry)?;
+            let id = db.add_note(kind, &entry, body)?;
+            println!("✅ Note #{} added to {}", id, entry);
+        }
+        "remove" => {
+            let id = args
+                .get(3)
+                .context("Usage: cv notes remove <id>")?
+                .trim_start_matches('#')
+                .parse::<i64>()
+                .context("Invalid note id")?;
+            db.remove_note(id)?;
+            println!("🗑️  Note #{} removed", id);
+        }
+        other => anyhow::bail!("Unknown notes subcommand '{}'", other),
+    }
+    Ok(())
+}
+
+/// Assemble a private prep document from a CV variant and its notes
+///
+/// Command-line arguments:
+/// - `<cv|portfolio>`: The CV variant the brief follows
+/// - `--application <id>`: Head the brief with a tracked application
+/// - `--format md|pdf`: Markdown (the default) or PDF
+/// - `--output <path>`: Where to write it (default: `private/brief-<variant>.md`
+///   or `.pdf`)
+/// - `--db-path <path>`: Override the configured database
+fn brief_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let config = match option("--db-path") {
+        Some(path) => config.with_option(unified_config::DB_PATH_KEY, path),
+        None => config,
+    };
+    let usage =
+        "Usage: cv brief <cv|portfolio> [--application <id>] [--format md|pdf] [--output <path>]";
+    let variant = args.get(2).context(usage)?.parse::<PdfVariant>()?;
+    let pdf = match option("--format").map(String::as_str) {
+        None | Some("md") => false,
+        Some("pdf") => true,
+        Some(other) => anyhow::bail!("Unknown brief format '{}' (expected md or pdf)", other),
+    };
+
+    let db = Database::open(config.db_path())?;
+    let application = option("--application")
+        .map(|id| -> Result<_> {
+            let id = id
+                .trim_start_matches('#')
+                .parse::<i64>()
+                .context("Invalid application id")?;
+            db.application(id)?
+                .with_context(|| format!("No application #{}", id))
+        })
+        .transpose()?;
+    let brief = Brief::assemble(
+        &load_cv(&config)?,
+        variant,
+        &db.notes()?,
+        application.as_ref(),
+    );
+
+    let output = option("--output").cloned().unwrap_or_else(|| {
+        format!(
+            "{}/brief-{}.{}",
+            prep_notes::BRIEFS_DIR,
+            variant,
+            if pdf { "pdf" } else { "md" }
+        )
+    });
+    if pdf {
+        let site_config = load_site_config();
+        let typst_config = site_config
+            .pdf_typst_config()
+            .context("Failed to get Typst configuration")?;
+        typst_generator::generate_brief_pdf(
+            &brief,
+            &typst_config,
+            &config.typst_temp_str()?,
+            &output,
+        )
+        .context("Failed to generate brief PDF")?;
+    } else {
+        write_private(Path::new(&output), &brief.to_markdown())?;
+    }
+    println!(
+        "🔒 Brief written to {} (private, not part of the site)",
+        output
+    );
+    Ok(())
+}
+
+/// Render the private offer comparison worksheet
+///
+/// Command-line arguments:
+/// - `--input <path>`: The offers (default: `private/offers.yaml`)
+/// - `--format html|pdf`: HTML page (the default) or PDF
+/// - `--output <path>`: Where to write it (default: `private/offers.html` or
+///   `.pdf`); it may not be inside the output directory
+fn offers_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let pdf = match option("--format").map(String::as_str) {
+        None | Some("html") => false,
+        Some("pdf") => true,
+        Some(other) => anyhow::bail!("Unknown offers format '{}' (expected html or pdf)", other),
+    };
+    let input = option("--input").map_or(offers::OFFERS_FILE, String::as_str);
+    let output = option("--output").map(PathBuf::from).unwrap_or_else(|| {
+        Path::new(offers::OFFERS_FILE).with_extension(if pdf { "pdf" } else { "html" })
+    });
+    offers::ensure_private(&output, &config.output_dir)?;
+
+    let comparison = OfferComparison::from_yaml(Path::new(input))?;
+    let name = load_cv(&config)?.personal_info.name;
+    if pdf {
+        let site_config = load_site_config();
+        let typst_config = site_config
+            .pdf_typst_config()
+            .context("Failed to get Typst configuration")?;
+        typst_generator::generate_offers_pdf(
+            &comparison,
+            &name,
+            &typst_config,
+            &config.typst_temp_str()?,
+            &output.to_string_lossy(),
+        )
+        .context("Failed to generate offers PDF")?;
+    } else {
+        write_private(&output, &offers::worksheet_html(&comparison, &name))?;
+    }
+
+    for (rank, offer) in comparison.ranked().iter().enumerate() {
+        println!(
+            "{}. {} ({}): {} expected, benefits {:.1}/{}",
+            rank + 1,
+            offer.offer.company,
+            offer.offer.role,
+            comparison.money(offer.expected),
+            offer.benefits_score,
+            offers::MAX_SCORE
+        );
+    }
+    println!(
+        "🔒 Offer worksheet written to {} (private, not part of the site)",
+        output.display()
+    );
+    Ok(())
+}
+
+/// Write a file that stays out of the output directory, creating its directory
+fn write_private(path: &Path, content: &str) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Resolve a declared secret, if it is declared
+fn secret(config: &AppConfig, key: &str) -> Result<Option<secrets::Secret>> {
+    let Some(source) = config.secrets.get(key) else {
+        return Ok(None);
+    };
+    let (token, origin) = source
+        .resolve()?
+        .with_context(|| format!("Secret '{}' is not set", key))?;
+    info!("Using secret '{}' from {}", key, origin);
+    Ok(Some(token))
+}
+
+/// Resolve the token drafts must be sent with, if the secret is declared
+fn submissions_token(config: &AppConfig) -> Result<Option<secrets::Secret>> {
+    secret(config, SUBMISSIONS_TOKEN_KEY)
+}
+
+/// Run a `submissions` subcommand
+///
+/// Subcommands:
+/// - (none) or `list`: Show all drafts with their number and status
+/// - `show <n>`: Print draft `n` as the post it would become
+/// - `promote <n>`: Write draft `n` to the blog directory as an unpublished post
+/// - `reject <n>`: Keep draft `n` off the blog
+fn submissions_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let path = data_dir(&config).join(SUBMISSIONS_FILE);
+    let mut store = SubmissionStore::load(&path)?;
+
+    let subcommand = args.get(2).map(String::as_str).unwrap_or("list");
+    if subcommand == "list" {
+        if store.drafts.is_empty() {
+            println!("ℹ️  No drafts in {}", path.display());
+        }
+        for (i, draft) in store.drafts.iter().enumerate() {
+            let contact = draft
+                .contact
+                .as_deref()
+                .map(|contact| format!(" <{contact}>"))
+                .unwrap_or_default();
+            println!(
+                "#{} [{}] \"{}\" by {}{}, {}",
+                i + 1,
+                draft.status,
+                draft.title,
+                draft.author,
+                contact,
+                draft.submitted_at.format("%Y-%m-%d")
+            );
+        }
+        return Ok(());
+    }
+
+    let number = args
+        .get(3)
+        .context("Usage: cv submissions show|promote|reject <number>")?
+        .parse::<usize>()
+        .context("Invalid draft number")?;
+    match subcommand {
+        "show" => {
+            print!("{}", store.get(number)?.to_post());
+            return Ok(());
+        }
+        "promote" => {
+            let site_config = load_site_config();
+            let blog_dir = site_config
+                .blog
+                .as_ref()
+                .and_then(|blog| blog.directory.as_deref())
+                .unwrap_or("content/blog");
+            let post = store.promote(number, Path::new(blog_dir))?;
+            println!(
+                "✅ Draft #{} is now {}; set `published: true` once it's ready",
+                number,
+                post.display()
+            );
+        }
+        "reject" => {
+            let draft = store.reject(number)?;
+            println!("✅ Draft #{} by {} is now rejected", number, draft.author);
+        }
+        other => anyhow::bail!("Unknown submissions subcommand '{}'", other),
+    }
+    store.save(&path)
+}
+
+/// Run a `webhooks` subcommand
+///
+/// Subcommands:
+/// - (none) or `list`: Show the latest deliveries and their status
+/// - `deliver`: Send the deliveries that are due
+/// - `retry <id>`: Queue failed delivery `id` again and send it
+async fn webhooks_command(args: &Vector<String>) -> Result<()> {
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let db = Database::open(config.db_path())?;
+
+    match args.get(2).map(String::as_str).unwrap_or("list") {
+        "list" => {
+            let deliveries = db.deliveries(20)?;
+            if deliveries.is_empty() {
+                println!("ℹ️  No webhook deliveries yet");
+            }
+            for delivery in &deliveries {
+                println!(
+                    "#{} [{}] {} to {}, {} attempts, queued {}",
+                    delivery.id,
+                    delivery.status,
+                    delivery.event,
+                    delivery.endpoint,
+                    delivery.attempts,
+                    delivery.created_at
+                );
+                if let Some(error) = &delivery.last_error {
+                    println!("    {}", error);
+                }
+            }
+            return Ok(());
+        }
+        "deliver" => {}
+        "retry" => {
+            let id = args
+                .get(3)
+                .context("Usage: cv webhooks retry <id>")?
+                .parse::<i64>()
+                .context("Invalid delivery id")?;
+            db.retry_delivery(id)?;
+        }
+        other => anyhow::bail!("Unknown webhooks subcommand '{}'", other),
+    }
+
+    let report = webhooks::deliver_due(
+        &db,
+        &config.webhooks,
+        &webhook_secrets(&config)?,
+        Utc::now(),
+    )
+    .await?;
+    print_delivery_report(&report);
+    Ok(())
+}
+
+/// Queue webhook events for new and changed content and send the due ones
+///
+/// Offline builds only queue the events; the next online build sends them.
+async fn notify_webhooks(config: &AppConfig, cv: &Cv, site_config: &SiteConfig) -> Result<()> {
+    let base_url = site_config.base_url();
+    let store = ContentStore::load(cv, site_config)?;
+    let items = store
+        .posts()
+        .iter()
+        .map(|post| webhooks::post_item(post, base_url))
+        .chain(webhooks::media_items(&config.static_dir, base_url)?)
+        .collect::<Vector<_>>();
+
+    let mut db = Database::open(config.db_path())?;
+    let queued = db.record_content(&items, &config.webhooks, Utc::now())?;
+    if queued > 0 {
+        println!("🪝 Queued {} webhook deliveries", queued);
+    }
+    if config.is_offline() {
+        return Ok(());
+    }
+    let report =
+        webhooks::deliver_due(&db, &config.webhooks, &webhook_secrets(config)?, Utc::now()).await?;
+    print_delivery_report(&report);
+    Ok(())
+}
+
+/// Run maintenance on the CV database when the last run is old enough
+fn maintain_database_if_due(config: &AppConfig, db_path: &Path) -> Result<()> {
+    let db = Database::open(db_path)?;
+    if !db_maintenance::is_due(
+        db.last_maintenance()?,
+        Utc::now(),
+        config.db_maintenance_days()?,
+    ) {
+        return Ok(());
+    }
+    info!("Running maintenance on {}", db_path.display());
+    let report = db.maintain(&Default::default())?;
+    if !report.is_healthy() {
+        warn!(
+            "{} failed its integrity check: {}",
+            db_path.display(),
+            report.integrity_problems.join("; ")
+        );
+    }
+    Ok(())
+}
+
+/// Resolve the secrets the webhook bodies are signed with, by endpoint name
+fn webhook_secrets(config: &AppConfig) -> Result<OrdMap<String, secrets::Secret>> {
+    config
+        .webhooks
+        .iter()
+        .filter_map(|(name, endpoint)| endpoint.secret.as_ref().map(|key| (name, key)))
+        .map(|(name, key)| {
+            let value = secret(config, key)?.with_context(|| {
+                format!(
+                    "Webhook '{}' is signed with secret '{}', which is not declared",
+                    name, key
+                )
+            })?;
+            Ok((name.clone(), value))
+        })
+        .collect()
+}
+
+fn print_delivery_report(report: &DeliveryReport) {
+    if *report == DeliveryReport::default() {
+        return;
+    }
+    println!(
+        "🪝 Webhooks: {} delivered, {} to retry, {} failed",
+        report.delivered, report.retrying, report.failed
+    );
+}
+
+/// Run a `stats` subcommand
+///
+/// Subcommands:
+/// - `ingest <access.log>...`: Add the new entries of access logs (plain or
+///   `.gz`) to the statistics and rewrite the report
+/// - `report`: Rewrite the report from the stored statistics
+fn stats_command(args: &Vector<String>) -> Result<()> {
+    let mut stats = AccessStats::load(access_stats::STATS_FILE)?;
+    let site_config = load_site_config();
+
+    match args.get(2).map(String::as_str) {
+        Some("ingest") if args.len() > 3 => {
+            let own_host = access_stats::url_host(site_config.base_url());
+            for log_path in args.iter().skip(3) {
+                let log = access_stats::read_log(Path::new(log_path))?;
+                let summary = stats.ingest(&log, own_host);
+                println!(
+                    "📊 {}: {} requests, {} page views ({} already ingested, {} unparsed lines)",
+                    log_path,
+                    summary.requests,
+                    summary.page_views,
+                    summary.already_ingested,
+                    summary.unparsed
+                );
+            }
+            stats.save(access_stats::STATS_FILE)?;
+        }
+        Some("report") => {}
+        _ => anyhow::bail!("Usage: cv stats ingest <access.log>... | cv stats report"),
+    }
+
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let site_name = Cv::from_json(&config.data_path.to_string_lossy())
+        .map(|cv| cv.personal_info.name)
+        .unwrap_or_else(|_| "CV".to_string());
+    fs::write(access_stats::REPORT_FILE, stats.report_html(&site_name))
+        .with_context(|| format!("Failed to write {}", access_stats::REPORT_FILE))?;
+    println!("✅ Report written to {}", access_stats::REPORT_FILE);
+    Ok(())
+}
+
+/// Import CV data from another source
+///
+/// Subcommands:
+/// - `linkedin <export.zip>`: Merge a LinkedIn data export into the CV data,
+///   or create the CV data from it if there is none yet
+///
+/// Command-line arguments:
+/// - `--strategy <keep|linkedin|ask>`: Which version wins when an entry
+///   differs (default: keep)
+/// - `--dry-run`: Show the changes without writing them
+fn import_command(args: &Vector<String>) -> Result<()> {
+    let export_path = match (args.get(2).map(String::as_str), args.get(3)) {
+        (Some("linkedin"), Some(path)) => Path::new(path),
+        _ => anyhow::bail!(
+            "Usage: cv import linkedin <export.zip> [--strategy keep|linkedin|ask] [--dry-run]"
+        ),
+    };
+    let strategy = args
+        .iter()
+        .position(|arg| arg == "--strategy")
+        .and_then(|i| args.get(i + 1))
+        .map(|name| MergeStrategy::parse(name))
+        .transpose()?
+        .unwrap_or(MergeStrategy::Keep);
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let export = LinkedInExport::from_zip(export_path)?;
+    println!(
+        "📥 LinkedIn export: {} positions, {} education entries, {} skills",
+        export.experiences.len(),
+        export.education.len(),
+        export.skills.len()
+    );
+
+    let cv = if config.data_path.exists() {
+        let existing = Cv::from_json(&config.data_path.to_string_lossy())?;
+        let (merged, report) = export.merge_into(&existing, |conflict| match strategy {
+            MergeStrategy::Keep => false,
+            MergeStrategy::LinkedIn => true,
+            MergeStrategy::Ask => ask_take_imported(conflict),
+        });
+        for label in report.added.iter() {
+            println!("  + {}", label);
+        }
+        for label in report.updated.iter() {
+            println!("  ~ {}", label);
+        }
+        for label in report.kept.iter() {
+            println!("  = {} (kept)", label);
+        }
+        merged
+    } else {
+        println!("  Creating {} from the export", config.data_path.display());
+        export.to_cv()
+    };
+
+    if dry_run {
+        println!("🔍 Dry run, {} is unchanged", config.data_path.display());
+        return Ok(());
+    }
+    fs::write(&config.data_path, serde_json::to_string_pretty(&cv)?)
+        .with_context(|| format!("Failed to write {}", config.data_path.display()))?;
+    println!("✅ Updated {}", config.data_path.display());
+    Ok(())
+}
+
+/// Export the CV data in another format
+///
+/// Command-line arguments:
+/// - `--format <europass|europass-json|bibtex>`: Europass XML or JSON, or a
+///   BibTeX file of the works on the publications pages
+/// - `--output <path>`: Where to write the export (default: `europass.xml`,
+///   `europass.json` or `publications.bib` in the current directory, outside
+///   the published site)
+fn export_command(args: &Vector<String>) -> Result<()> {
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|i| args.get(i + 1))
+    };
+    let config = AppConfig::load().context("Failed to load configuration")?;
+    let cv = Cv::from_json(&config.data_path.to_string_lossy())?;
+    let site_config = load_site_config();
+    let locale = site_config.default_language().to_string();
+
+    let (content, default_output, description) = match option("--format").map(String::as_str) {
+        Some("europass") => (
+            europass::europass_xml(&cv, &locale)?,
+            "europass.xml",
+            "🇪🇺 Europass CV",
+        ),
+        Some("europass-json") => (
+            serde_json::to_string_pretty(&europass::europass_json(&cv, &locale))?,
+            "europass.json",
+            "🇪🇺 Europass CV",
+        ),
+        Some("bibtex") => {
+            let publications = ContentStore::load(&cv, &site_config)?.publications();
+            if publications.is_empty() {
+                anyhow::bail!(
+                    "No publications found: list them on a page in {} with a bibliography and nocite: \"@*\"",
+                    content_store::pages_directory(&site_config).display()
+                );
+            }
+            (
+                citations::to_bibtex(&publications),
+                "publications.bib",
+                "📚 Publications",
+            )
+        }
+        _ => anyhow::bail!(
+            "Usage: cv export --format europass|europass-json|bibtex [--output <path>]"
+        ),
+    };
+    let output = option("--output").map_or(default_output, String::as_str);
+    fs::write(output, content).with_context(|| format!("Failed to write {}", output))?;
+    println!("{} written to {}", description, output);
+    Ok(())
+}
+
+/// Ask on the terminal whether to take LinkedIn's version of an entry
+fn ask_take_imported(conflict: &linkedin_import::Conflict) -> bool {
+    println!("\n{}", conflict.label);
+    println!(
+        "  CV:       {}",
+        conflict.existing.replace('\n', "\n            ")
+    );
+    println!(
+        "  LinkedIn: {}",
+        conflict.imported.replace('\n', "\n            ")
+    );
+    loop {
+        print!("Keep the CV's version or take LinkedIn's? [k/l] ");
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+            return false;
+        }
+        match answer.trim() {
+            "k" | "K" | "" => return false,
+            "l" | "L" => return true,
+            _ => {}
+        }
+    }
+}
+
+/// Build the site
+///
 /// This function initializes the configuration, loads the CV data,
 /// fetches GitHub projects, and generates both HTML and PDF versions of the CV.
 ///
 /// Command-line arguments:
 /// - `--cache-path <path>`: Set a custom path for the GitHub cache file
 /// - `--public-data <config>`: Set public data configuration
-#[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    init_logging();
+/// - `--format <formats>`: Comma-separated output formats (`html`, `pdf`, `docx`, `paste`)
+/// - `--offline`: Build from cached data only, without network access
+/// - `--source <json|db>`: Read the CV from the JSON file or the database
+/// - `--db-path <path>`: Set a custom path for the CV database
+/// - `--strict`: Fail when a build phase takes longer than its budget, or a
+///   budget names an unknown phase
+/// - `--overwrite-data`: Replace files from data sources that were changed
+///   locally since they were fetched
+/// - `--keep-typst`: Keep the Typst markup of each PDF next to it
+async fn build(args: &Vector<String>) -> Result<()> {
+    check_build_args(args)?;
 
     // Initialize performance profiler
     let mut profiler = BuildProfiler::new();
-
-    // Parse command-line arguments
-    let args: Vector<String> = env::args().collect();
+    html_generator::fragments::reset();
 
     // Load GitHub cache
-    let cache_path = "cache/github_cache.json";
+    let cache_path = GITHUB_CACHE_PATH;
     let mut github_cache = profiler.time_operation("Load GitHub cache", || {
         GitHubCache::load_or_default(cache_path)
     });
+    github_cache.begin_build();
 
     // Load configuration from all available sources
     let base_config = AppConfig::load().context("Failed to load configuration")?;
@@ -157,6 +1992,13 @@ async fn main() -> Result<()> {
                     info!("Using custom public data settings: {}", args[i + 1]);
                     cfg.with_option(unified_config::PUBLIC_DATA_KEY, &args[i + 1])
                 }
+                "--format" => {
+                    info!("Building output formats: {}", args[i + 1]);
+                    cfg.with_option(unified_config::OUTPUT_FORMATS_KEY, &args[i + 1])
+                }
+                "--source" => cfg.with_option(unified_config::CV_SOURCE_KEY, &args[i + 1]),
+                "--db-path" => cfg.with_option(unified_config::DB_PATH_KEY, &args[i + 1]),
+                "--date-check" => cfg.with_option(unified_config::DATE_CHECK_KEY, &args[i + 1]),
                 _ => cfg,
             }
         } else {
@@ -164,46 +2006,92 @@ async fn main() -> Result<()> {
         }
     });
 
-    // Load CV data - prioritize local file (which may contain real data from content branch in CI)
-    let mut cv = profiler.time_operation("Load CV data", || {
-        info!(
-            "Loading CV data from local file: {}",
-            config.data_path.display()
+    let config = if args.iter().any(|arg| arg == "--offline") {
+        config.with_option(unified_config::OFFLINE_KEY, "true")
+    } else {
+        config
+    };
+    let config = if args.iter().any(|arg| arg == "--keep-typst") {
+        config.with_option(unified_config::KEEP_TYPST_KEY, "true")
+    } else {
+        config
+    };
+    if config.is_offline() {
+        // Fail early on a typo rather than when the first cache miss happens
+        config.offline_missing()?;
+        println!("📴 Building offline, from cached data only");
+    }
+
+    let formats = config.output_formats()?;
+    let strict = args.iter().any(|arg| arg == "--strict");
+    let unknown = performance::unknown_phases(config.build_budgets.keys());
+    for phase in &unknown {
+        println!("⚠️  build_budgets names an unknown phase: '{}'", phase);
+    }
+    if strict && !unknown.is_empty() {
+        anyhow::bail!(
+            "build_budgets names {} unknown phase(s): {}",
+            unknown.len(),
+            unknown.join(", ")
         );
-        Cv::from_json(&config.data_path.to_string_lossy()).context("Failed to load CV data")
-    })?;
+    }
+    let mut profiler = profiler.with_budgets(
+        config
+            .build_budgets
+            .iter()
+            .map(|(phase, millis)| (phase.clone(), std::time::Duration::from_millis(*millis)))
+            .collect(),
+    );
+
+    // CV data, site configuration and content declared in data_sources
+    if !config.data_sources.is_empty() {
+        profiler.time_operation("Load data sources", || {
+            data_sources::load_data_sources(
+                &config.data_sources,
+                Path::new(data_sources::DATA_SOURCES_MANIFEST),
+                config.is_offline(),
+                args.iter().any(|arg| arg == "--overwrite-data"),
+            )
+            .context("Failed to load data sources")
+        })?;
+    }
+
+    // Load CV data from the JSON file (which may be loaded from a data source)
+    // or the database
+    let mut cv = profiler.time_operation("Load CV data", || load_cv(&config))?;
+    check_dates(&config, &cv)?;
 
-    // We're using GitHub CLI (gh) which handles authentication automatically
-    info!("Fetching GitHub projects from sources defined in CV data using GitHub CLI");
+    // Native GitHub REST client, token from config or environment
+    let github_client = GitHubClient::from_config(&config);
+    info!("Fetching GitHub projects from sources defined in CV data");
     match profiler.time_operation("Fetch GitHub projects", || {
-        github::fetch_projects_from_sources_cached(&cv.github_sources, &mut github_cache)
+        github_client.fetch_projects_from_sources_cached(&cv.github_sources, &mut github_cache)
     }) {
         Ok(github_projects) => {
             info!("Found {} GitHub projects", github_projects.len());
 
-            // Keep any existing projects that are not from GitHub (identified by not having a repository URL)
-            let local_projects = cv
-                .projects
-                .iter()
-                .filter(|p| p.repository.is_none())
-                .cloned()
-                .collect::<Vector<_>>();
-
-            // Combine local projects with GitHub projects
-            cv.projects = local_projects
-                .iter()
-                .chain(github_projects.iter())
-                .cloned()
-                .collect();
+            // Projects with a repository URL are replaced by the GitHub ones
+            cv.projects = site_cv::merge_github_projects(&cv, &github_projects);
 
             info!("Updated CV with {} total projects", cv.projects.len());
         }
         Err(e) => {
-            warn!("Failed to fetch GitHub projects: {}", e);
+            missing_data(&config, "GitHub projects", e)?;
             info!("Continuing with existing projects data");
         }
     }
 
+    // Published packages, merged into the projects they were built from
+    if !cv.registry_sources.is_empty() {
+        let packages = profiler.time_operation("Fetch registry packages", || {
+            RegistryClient::new()
+                .with_offline(config.is_offline())
+                .fetch_packages_cached(&cv.registry_sources, &mut github_cache)
+        });
+        info!("Found {} registry packages", packages.len());
+        cv.projects = package_registry::merge_packages(&cv.projects, &packages);
+    }
+
     // Always fetch GitHub avatar URL as fallback
     let github_username = cv
         .github_sources
@@ -213,18 +2101,27 @@ async fn main() -> Result<()> {
     if let Some(username) = github_username {
         info!("Fetching GitHub avatar for user: {}", username);
         match profiler.time_operation("Fetch GitHub avatar", || {
-            github::fetch_github_avatar_cached(username, &mut github_cache)
+            github_client.fetch_avatar_cached(username, &mut github_cache)
         }) {
             Ok(avatar_url) => {
                 cv.personal_info.github_avatar_url = Some(avatar_url.clone());
                 info!("Successfully fetched GitHub avatar URL (fallback)");
             }
-            Err(e) => {
-                warn!("Failed to fetch GitHub avatar: {}", e);
-            }
+            Err(e) => missing_data(&config, "GitHub avatar", e)?,
         }
     }
 
+    // Aggregate stats, only for sources that ask for them
+    match profiler.time_operation("Fetch GitHub stats", || {
+        github_client.fetch_stats_from_sources_cached(&cv.github_sources, &mut github_cache)
+    }) {
+        Ok(stats) => cv.github_stats = stats,
+        Err(e) => missing_data(&config, "GitHub stats", e)?,
+    }
+
+    // Entries served stale are refreshed while the rest of the site builds
+    let revalidation = github_client.spawn_revalidation(&mut github_cache);
+
     // Ensure output img directory exists
     let output_img_dir = format!("{}/img", config.output_dir.display());
     fs::create_dir_all(&output_img_dir).ok();
@@ -280,98 +2177,484 @@ async fn main() -> Result<()> {
     if !custom_profile_found {
         cv.personal_info.profile_image = None;
 
-        if let Some(ref avatar_url) = cv.personal_info.github_avatar_url {
-            let avatar_path = format!("{}/profile.png", output_img_dir);
-            match download_and_save_image(avatar_url, &avatar_path).await {
-                Ok(actual_path) => {
-                    // Extract just the relative path for the template
-                    let relative_path = actual_path
-                        .strip_prefix(&format!("{}/", config.output_dir.display()))
-                        .unwrap_or(&actual_path);
-                    cv.personal_info.profile_image = Some(relative_path.to_string());
-                    info!("Downloaded GitHub avatar to: {}", actual_path);
-                }
-                Err(e) => {
-                    warn!("Failed to download GitHub avatar: {}", e);
-                    info!("Will use default placeholder image");
+        let avatar_path = format!("{}/profile.png", output_img_dir);
+        let avatar = match cv.personal_info.github_avatar_url {
+            Some(_) if config.is_offline() => Some(copy_kept_avatar(&avatar_path)),
+            Some(ref avatar_url) => {
+                let downloaded = download_and_save_image(avatar_url, &avatar_path).await;
+                if let Ok(ref actual_path) = downloaded {
+                    if let Err(e) = keep_avatar(actual_path) {
+                        warn!("Failed to keep the GitHub avatar for offline builds: {}", e);
+                    }
                 }
+                Some(downloaded)
             }
-        } else {
-            info!("No GitHub avatar available, using default placeholder image");
+            None => None,
+        };
+
+        match avatar {
+            Some(Ok(actual_path)) => {
+                // Extract just the relative path for the template
+                let relative_path = actual_path
+                    .strip_prefix(&format!("{}/", config.output_dir.display()))
+                    .unwrap_or(&actual_path);
+                cv.personal_info.profile_image = Some(relative_path.to_string());
+                info!("Saved GitHub avatar to: {}", actual_path);
+            }
+            Some(Err(e)) => {
+                missing_data(&config, "GitHub avatar image", e)?;
+                info!("Will use default placeholder image");
+            }
+            None => info!("No GitHub avatar available, using default placeholder image"),
         }
     }
 
-    // Load language icons and associate them with projects
-    info!("Loading language icons");
-    let icons_path = config
-        .data_path
-        .parent()
-        .unwrap()
-        .join("language_icons.json");
+    // Moderated testimonials; pending and rejected ones are dropped by `Cv::filtered`
+    let approved = site_cv::add_testimonials(&mut cv, &config)?;
+    info!("Found {} approved testimonials", approved);
+
+    // Fields that may be published; the CV is filtered per language in generate_site
+    let public_data = config.public_data_config()?;
+    debug!("Public data fields: {:?}", config.public_data());
+
+    // Load site configuration (menu, navigation, etc.)
+    info!("Loading site configuration");
+    let site_config = load_site_config();
+
+    // Taxonomy and glossary next to the CV data
+    let site_config = site_cv::with_site_data(site_config, &config)?;
+
+    // Pinned projects first, then the ones passing the project rules
+    let uncurated_projects = cv.projects.clone();
+    cv.projects = site_cv::curate_projects(&cv.projects, &site_config);
+    info!("Listing {} projects", cv.projects.len());
+
+    // Make the build traceable from the generated pages
+    let build_info = profiler.time_operation("Collect build info", BuildInfo::collect);
+    info!(
+        "Build info: cv-generator {}, commit {}",
+        build_info.generator_version,
+        build_info.short_commit().unwrap_or("unknown")
+    );
+    // Analytics only counts visits in the environments it is enabled for
+    let analytics = site_config
+        .analytics
+        .clone()
+        .filter(|analytics| analytics.is_enabled_in(&config.environment));
+    let site_config = SiteConfig {
+        build_info: Some(build_info),
+        offline: config.is_offline(),
+        analytics,
+        ..site_config
+    };
+
+    let mut pdf_jobs = Vec::new();
+    let mut site_checks = Vec::new();
+    if site_config.is_multilingual() {
+        // One output directory per language, e.g. dist/en/ and dist/da/
+        for language in site_config.languages.iter() {
+            info!("Generating site for language: {}", language);
+            let localized_cv =
+                site_cv::publishable_cv(&cv, &config, &site_config, Some(language), &public_data)
+                    .with_context(|| format!("Failed to localize CV data for {}", language))?;
+
+            let output_dir = config.output_dir.join(language);
+            let localized_config = AppConfig {
+                html_output: output_dir.join("cv.html"),
+                pdf_output: output_dir.join("cv.pdf"),
+                docx_output: output_dir.join("cv.docx"),
+                paste_output: output_dir.join(unified_config::PASTE_OUTPUT_FILE),
+                portfolio_output: output_dir.join(unified_config::PORTFOLIO_OUTPUT_FILE),
+                output_dir,
+                ..config.clone()
+            };
 
-    let icons = LanguageIcons::from_json(icons_path.to_str().unwrap()).ok();
+            generate_site(
+                &localized_cv,
+                &site_config.for_language(language),
+                &localized_config,
+                &formats,
+                &mut profiler,
+                &mut pdf_jobs,
+                &mut site_checks,
+            )?;
+        }
 
-    if let Some(ref icons) = icons {
-        info!("Found {} language icons", icons.0.len());
+        // Root files (CNAME, manifest, ...) plus a redirect to the default language
+        if formats.contains(&OutputFormat::Html) {
+            profiler.time_operation("Link translations", || {
+                html_generator::copy_static_assets_except(
+                    &config.static_dir_str()?,
+                    &config.output_dir_str()?,
+                    &["index.html", "cv.html", "projects.html", "blog.html"],
+                )
+                .context("Failed to copy static assets")?;
+                i18n::write_language_redirect(&config.output_dir, &site_config.languages)?;
+                let linked = i18n::link_translations(&config.output_dir, &site_config.languages)?;
+                info!("Added hreflang links to {} pages", linked);
+                Ok::<_, anyhow::Error>(())
+            })?;
+        }
     } else {
-        warn!("Failed to load language icons, continuing without them");
+        generate_site(
+            &site_cv::publishable_cv(&cv, &config, &site_config, None, &public_data)?,
+            &site_config,
+            &config,
+            &formats,
+            &mut profiler,
+            &mut pdf_jobs,
+            &mut site_checks,
+        )?;
     }
 
-    // Transform projects with display names and language icons (functional style)
-    cv.projects = cv
-        .projects
+    // Tailored profiles, each built like the site into profiles/<name>/
+    let profile_formats = formats
         .iter()
-        .map(|project| {
-            // Extract display name (without language suffix)
-            let display_name = project
-                .name
-                .find(" - ")
-                .map(|pos| project.name[..pos].to_string())
-                .unwrap_or_else(|| project.name.clone());
-
-            // Detect language and icon if icons are available
-            let (language, language_icon) = icons
-                .as_ref()
-                .and_then(|icons| {
-                    icons
-                        .detect_language_vector(&project.name, &project.technologies)
-                        .map(|lang| {
-                            let icon = icons.get_icon(&lang).to_string();
-                            debug!("Detected language for project {}: {}", display_name, lang);
-                            (Some(lang), Some(icon))
-                        })
-                })
-                .unwrap_or((None, None));
-
-            Project {
-                display_name: Some(display_name),
-                language,
-                language_icon,
-                ..project.clone()
+        .filter(|format| matches!(format, OutputFormat::Html | OutputFormat::Pdf))
+        .cloned()
+        .collect::<Vector<_>>();
+    for (name, profile) in site_config.profiles.iter() {
+        for tag in profiles::unused_tags(&cv, profile) {
+            warn!("Profile '{}': no CV entry is tagged '{}'", name, tag);
+        }
+        info!("Generating profile: {}", name);
+
+        // Profiles are built in the default language
+        let (profile_cv, profile_site_config) = match site_config.languages.front() {
+            Some(language) if site_config.is_multilingual() => (
+                i18n::localize_cv(&cv, &config.data_path, language)
+                    .with_context(|| format!("Failed to localize CV data for {}", language))?,
+                site_config.for_language(language),
+            ),
+            _ => (cv.clone(), site_config.clone()),
+        };
+        let projects = match &profile.project_rules {
+            Some(rules) => project_curation::curate_projects(&uncurated_projects, rules),
+            None => profile_cv.projects.clone(),
+        };
+        let profile_cv = Cv {
+            projects: site_cv::decorate_projects(&projects, &site_config.taxonomy),
+            ..profiles::resolve(&profile_cv, profile)
+        }
+        .filtered(&public_data);
+
+        let output_dir = config.output_dir.join(profiles::PROFILES_DIR).join(name);
+        let profile_config = AppConfig {
+            html_output: output_dir.join("cv.html"),
+            pdf_output: output_dir.join("cv.pdf"),
+            docx_output: output_dir.join("cv.docx"),
+            paste_output: output_dir.join(unified_config::PASTE_OUTPUT_FILE),
+            portfolio_output: output_dir.join(unified_config::PORTFOLIO_OUTPUT_FILE),
+            output_dir,
+            ..config.clone()
+        };
+        generate_site(
+            &profile_cv,
+            &profile_site_config,
+            &profile_config,
+            &profile_formats,
+            &mut profiler,
+            &mut pdf_jobs,
+            &mut site_checks,
+        )?;
+    }
+
+    // Shared fragments are rendered during page generation, across all pages
+    let fragments = html_generator::fragments::stats();
+    if fragments.rendered > 0 {
+        profiler.record("Render template fragments", fragments.time);
+        info!(
+            "Rendered {} template fragments, reused {} times",
+            fragments.rendered, fragments.reused
+        );
+    }
+
+    // All PDF variants, languages and profiles compile at once
+    if !pdf_jobs.is_empty() {
+        info!("Compiling {} PDFs", pdf_jobs.len());
+        let report = profiler.time_operation("Compile PDFs", || {
+            typst_generator::compile_pdfs(
+                &pdf_jobs,
+                &config.typst_temp_str()?,
+                Path::new(typst_generator::PDF_CACHE_DIR),
+                config.keeps_typst(),
+            )
+        })?;
+        info!(
+            "Compiled {} PDFs, {} unchanged from the cache",
+            report.compiled, report.cached
+        );
+    }
+
+    // Warns when the pages, feeds, menus and PDFs of a site disagree
+    if !site_checks.is_empty() {
+        let discrepancies = profiler.time_operation("Check outputs", || {
+            site_checks
+                .iter()
+                .flat_map(SiteCheck::run)
+                .collect::<Vec<_>>()
+        });
+        consistency::print_warnings(&discrepancies);
+    }
+
+    // Precompress the finished site, after every page and asset is written
+    let compression = site_config.compression.clone().unwrap_or_default();
+    if formats.contains(&OutputFormat::Html) && compression.is_enabled() {
+        let compressed = profiler.time_operation("Precompress output", || {
+            html_generator::precompress_output(&config.output_dir, &compression)
+                .context("Failed to precompress output")
+        })?;
+        info!("Wrote {} precompressed files", compressed);
+    }
+
+    // A failing endpoint is logged and retried later, it never fails the build
+    if formats.contains(&OutputFormat::Html) && !config.webhooks.is_empty() {
+        if let Err(e) = notify_webhooks(&config, &cv, &site_config).await {
+            warn!("Failed to notify webhooks: {:#}", e);
+        }
+    }
+
+    // Only a database that is in use is maintained, the build never creates one
+    let db_path = config.db_path();
+    if db_path.exists() {
+        if let Err(e) = maintain_database_if_due(&config, &db_path) {
+            warn!("Failed to maintain {}: {:#}", db_path.display(), e);
+        }
+    }
+
+    // Process and bundle assets (disabled for now)
+    info!("Skipping asset processing for now");
+    // bundler::process_assets("bundle.toml", &config.static_dir_str()?)
+    //     .context("Failed to process and bundle assets")?;
+
+    // Wait for the background revalidation so the next build gets fresh data
+    if let Some(revalidation) = revalidation {
+        match revalidation.await {
+            Ok(refreshed) => github_cache.merge(refreshed),
+            Err(e) => warn!("Background cache revalidation failed: {}", e),
+        }
+    }
+
+    // Save GitHub cache
+    profiler.time_operation("Save GitHub cache", || {
+        // Stale entries are still served with background refresh and offline,
+        // so keep them
+        if github_client.refresh_strategy() != RefreshStrategy::Background && !config.is_offline() {
+            github_cache.cleanup_expired();
+        }
+        github_cache.save(cache_path)
+    })?;
+
+    // Print performance summary
+    profiler.print_summary();
+    let overruns = profiler.overruns();
+    if strict && !overruns.is_empty() {
+        anyhow::bail!(
+            "{} build phase(s) took longer than their budget: {}",
+            overruns.len(),
+            overruns
+                .iter()
+                .map(|overrun| overrun.phase.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    // Print output summary
+    info!("Done! Output files:");
+    if formats.contains(&OutputFormat::Pdf) {
+        let variants = site_config.pdf_variants();
+        if variants.contains(&PdfVariant::Cv) {
+            info!("  - PDF CV: {}", config.pdf_output.display());
+        }
+        if variants.contains(&PdfVariant::Portfolio) {
+            info!("  - Portfolio PDF: {}", config.portfolio_output.display());
+        }
+    }
+    if formats.contains(&OutputFormat::Docx) {
+        info!("  - DOCX CV: {}", config.docx_output.display());
+    }
+    if formats.contains(&OutputFormat::Paste) {
+        info!("  - Paste-friendly CV: {}", config.paste_output.display());
+    }
+    if formats.contains(&OutputFormat::Html) {
+        info!("  - HTML CV: {}", config.html_output.display());
+        info!("  - Static assets: {}", config.output_dir.display());
+        info!(
+            "  - Bundled assets: {}/[bundle_name].bundle.[css|js]",
+            config.output_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Load the CV from the configured source, the JSON file or the database
+fn load_cv(config: &AppConfig) -> Result<Cv> {
+    match config.cv_source()? {
+        CvSource::Json => {
+            info!(
+                "Loading CV data from local file: {}",
+                config.data_path.display()
+            );
+            let path = config.data_path.to_string_lossy();
+            let source = fs::read_to_string(&config.data_path)
+                .with_context(|| format!("Failed to read CV data file: {}", path))?;
+            let problems = validate_json(&source)?;
+            if !problems.is_empty() {
+                let listed = problems
+                    .iter()
+                    .map(|problem| format!("  {}:{}", path, problem))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                anyhow::bail!(
+                    "The CV data doesn't match the schema:\n{}\n\nRun `cv validate` after fixing it",
+                    listed
+                );
             }
-        })
-        .collect();
+            Cv::from_json_str(&source, &path).context("Failed to load CV data")
+        }
+        CvSource::Db => {
+            info!(
+                "Loading CV data from database: {}",
+                config.db_path().display()
+            );
+            Database::open(config.db_path())
+                .and_then(|db| db.load_cv())
+                .context("Failed to load CV data")
+        }
+    }
+}
+
+/// Generate the requested output formats into the configured output
+///
+/// # Arguments
+///
+/// * `cv` - CV data to render
+/// * `site_config` - Site configuration (menu, theme, language)
+/// * `config` - Application configuration with the output paths
+/// * `formats` - Output formats to generate
+/// * `profiler` - Build profiler recording each step
+/// * `pdf_jobs` - Where the PDFs to compile are added
+/// * `site_checks` - Where the site is added to be checked once it's built
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+fn generate_site(
+    cv: &Cv,
+    site_config: &SiteConfig,
+    config: &AppConfig,
+    formats: &Vector<OutputFormat>,
+    profiler: &mut BuildProfiler,
+    pdf_jobs: &mut Vec<PdfJob>,
+    site_checks: &mut Vec<SiteCheck>,
+) -> Result<()> {
+    // Sorted here, after localization, since locale files override by position
+    let cv = &cv_analysis::sort_chronologically(&site_config.taxonomy.canonicalize_cv(cv));
+    let first_pdf = pdf_jobs.len();
 
-    // Filter CV data based on public_data configuration
-    info!("Filtering CV data based on public_data configuration");
-    let public_data_fields = config.public_data();
-    debug!("Public data fields: {:?}", public_data_fields);
+    let store = if formats.contains(&OutputFormat::Html) {
+        let store = profiler.time_operation("Load content", || {
+            ContentStore::load(cv, site_config).context("Failed to load content")
+        })?;
+        generate_html_site(&store, site_config, config, profiler)?;
+        Some(store)
+    } else {
+        None
+    };
 
-    // Note: In a real implementation, we would create a filtered copy of the CV data
-    // based on the public_data configuration. For now, we'll just log the fields
-    // that would be included.
+    if formats.contains(&OutputFormat::Pdf) {
+        // Compiled together with the PDFs of the other languages and profiles
+        let variants = site_config.pdf_variants();
+        let typst_config = site_config
+            .pdf_typst_config()
+            .context("Failed to get Typst configuration")?;
 
-    // Load site configuration (menu, navigation, etc.)
-    info!("Loading site configuration");
-    let site_config = SiteConfig::from_json("config/site.json").unwrap_or_else(|e| {
-        warn!("Failed to load site config: {}. Using defaults.", e);
-        SiteConfig::default()
-    });
+        if variants.contains(&PdfVariant::Cv) {
+            let markup = profiler.time_operation("Generate PDF markup", || {
+                typst_generator::generate_cv_markup(cv, &typst_config)
+                    .context("Failed to generate PDF CV")
+            })?;
+            pdf_jobs.push(PdfJob {
+                label: config.pdf_output.display().to_string(),
+                markup,
+                typst_config: typst_config.clone(),
+                output_path: config.pdf_output.clone(),
+            });
+        }
+
+        if variants.contains(&PdfVariant::Portfolio) {
+            let markup = profiler.time_operation("Generate PDF markup", || {
+                typst_generator::generate_portfolio_markup(cv, &typst_config)
+            });
+            pdf_jobs.push(PdfJob {
+                label: config.portfolio_output.display().to_string(),
+                markup,
+                typst_config,
+                output_path: config.portfolio_output.clone(),
+            });
+        }
+    }
+
+    if formats.contains(&OutputFormat::Docx) {
+        info!("Generating DOCX CV");
+        profiler.time_operation("Generate DOCX CV", || {
+            docx_generator::generate_docx(cv, &config.docx_output_str()?)
+                .context("Failed to generate DOCX CV")
+        })?;
+    }
+
+    if formats.contains(&OutputFormat::Paste) {
+        info!("Generating paste-friendly HTML CV");
+        profiler.time_operation("Generate paste-friendly CV", || {
+            paste_export::generate_paste_html(cv, &config.paste_output_str()?)
+                .context("Failed to generate paste-friendly CV")
+        })?;
+    }
+
+    // Checked after the PDFs of all sites are compiled
+    if let Some(store) = store {
+        site_checks.push(SiteCheck {
+            output_dir: config
+                .html_output
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            site_config: site_config.clone(),
+            store,
+            pdfs: pdf_jobs[first_pdf..]
+                .iter()
+                .map(|job| job.output_path.clone())
+                .collect(),
+        });
+    }
+
+    Ok(())
+}
 
+/// Generate the HTML pages and static assets into the configured output
+///
+/// # Arguments
+///
+/// * `store` - The CV and content to render
+/// * `site_config` - Site configuration (menu, theme, language)
+/// * `config` - Application configuration with the output paths
+/// * `profiler` - Build profiler recording each step
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+fn generate_html_site(
+    store: &ContentStore,
+    site_config: &SiteConfig,
+    config: &AppConfig,
+    profiler: &mut BuildProfiler,
+) -> Result<()> {
     // Generate HTML CV and index
     info!("Generating HTML files");
     profiler.time_operation("Generate HTML files", || {
-        html_generator::generate_html(&cv, &site_config, &config.html_output_str()?)
+        html_generator::generate_html_from(store, site_config, &config.html_output_str()?)
             .context("Failed to generate HTML files")
     })?;
 
@@ -386,51 +2669,124 @@ async fn main() -> Result<()> {
         .context("Failed to copy static assets")
     })?;
 
+    // Theme assets go on top of the static assets so themes can replace them
+    if let Some(theme) = Theme::from_site_config(site_config)? {
+        let assets_dir = theme.assets_dir();
+        if assets_dir.is_dir() {
+            info!("Copying assets for theme: {}", theme.manifest.name);
+            html_generator::copy_static_assets_except(
+                &assets_dir.to_string_lossy(),
+                &config.output_dir_str()?,
+                &[],
+            )
+            .context("Failed to copy theme assets")?;
+        }
+    }
+
+    let output_dir = config.output_dir_str()?;
+
+    // Self-host the web fonts before the stylesheets are bundled
+    if site_config.self_hosts_fonts() {
+        let hosted = profiler.time_operation("Self-host fonts", || {
+            html_generator::self_host_fonts(
+                Path::new(&output_dir),
+                Path::new(DEFAULT_FONT_CACHE_DIR),
+                site_config.offline,
+            )
+            .context("Failed to self-host fonts")
+        })?;
+        if hosted > 0 {
+            println!("🔤 Self-hosted {} font files", hosted);
+        }
+    }
+
     // Optimize CSS and JS files after copying static assets
     info!("Optimizing CSS and JS assets");
+    let assets = site_config.assets.clone().unwrap_or_default();
     profiler.time_operation("Optimize assets", || {
-        html_generator::optimize_assets(Path::new(&config.output_dir_str()?))
+        html_generator::optimize_assets(Path::new(&output_dir), &assets)
             .context("Failed to optimize assets")
     })?;
 
-    // Generate PDF CV
-    info!("Generating PDF CV");
-    profiler.time_operation("Generate PDF CV", || {
-        let typst_config = site_config
-            .get_typst_config()
-            .context("Failed to get Typst configuration")?;
-        typst_generator::generate_pdf(
-            &cv,
-            &typst_config,
-            &config.typst_temp_str()?,
-            &config.pdf_output_str()?,
-        )
-        .context("Failed to generate PDF CV")
+    // Derive the Content-Security-Policy now that every page and stylesheet is
+    // in place, and send it from the server configurations
+    let policy = profiler.time_operation("Derive Content-Security-Policy", || {
+        content_security_policy::apply_site_csp(Path::new(&output_dir))
+            .context("Failed to derive the Content-Security-Policy")
     })?;
+    html_generator::generate_deployment_configs(Path::new(&output_dir), site_config, &policy)
+        .context("Failed to generate deployment configurations")?;
 
-    // Process and bundle assets (disabled for now)
-    info!("Skipping asset processing for now");
-    // bundler::process_assets("bundle.toml", &config.static_dir_str()?)
-    //     .context("Failed to process and bundle assets")?;
+    // Expand abbreviations before the service worker hashes the pages
+    if !site_config.glossary.is_empty() {
+        let expanded = profiler.time_operation("Expand abbreviations", || {
+            glossary::add_abbreviations(Path::new(&output_dir), &site_config.glossary)
+                .context("Failed to expand abbreviations")
+        })?;
+        println!("📖 Expanded {} abbreviations", expanded);
+    }
 
-    // Save GitHub cache
-    profiler.time_operation("Save GitHub cache", || {
-        github_cache.cleanup_expired();
-        github_cache.save(cache_path)
+    profiler.time_operation("Generate service worker", || {
+        html_generator::generate_site_service_worker(site_config, Path::new(&output_dir))
+            .context("Failed to generate service worker")
     })?;
 
-    // Print performance summary
-    profiler.print_summary();
+    // Size the images once every page and image is in place
+    let sized = profiler.time_operation("Add image dimensions", || {
+        html_generator::add_image_dimensions(
+            Path::new(&output_dir),
+            Path::new(DEFAULT_IMAGE_CACHE_PATH),
+            site_config.offline,
+            config.image_workers,
+        )
+        .context("Failed to add image dimensions")
+    })?;
+    if sized > 0 {
+        println!("📐 Added dimensions to {} images", sized);
+    }
 
-    // Print output summary
-    info!("Done! Output files:");
-    info!("  - HTML CV: {}", config.html_output.display());
-    info!("  - PDF CV: {}", config.pdf_output.display());
-    info!("  - Static assets: {}", config.output_dir.display());
-    info!(
-        "  - Bundled assets: {}/[bundle_name].bundle.[css|js]",
-        config.output_dir.display()
-    );
+    // Fingerprint after minifying, so the hashes cover the files that are served
+    if assets.fingerprint() {
+        let manifest = profiler.time_operation("Fingerprint assets", || {
+            html_generator::fingerprint_assets(Path::new(&output_dir), assets.content_addressed())
+                .context("Failed to fingerprint assets")
+        })?;
+        println!("🔖 Fingerprinted {} assets", manifest.assets.len());
+    }
+
+    // Hash the files as they are served, after fingerprinting rewrote them
+    if assets.integrity() {
+        let skip = site_config
+            .analytics
+            .iter()
+            .map(|analytics| analytics.script_url().to_string())
+            .collect::<Vec<_>>();
+        let hashed = profiler.time_operation("Add integrity hashes", || {
+            html_generator::add_integrity(
+                Path::new(&output_dir),
+                Path::new(DEFAULT_INTEGRITY_CACHE_PATH),
+                site_config.offline,
+                &skip,
+            )
+            .context("Failed to add integrity hashes")
+        })?;
+        if hashed > 0 {
+            println!("🔏 Added integrity hashes to {} tags", hashed);
+        }
+    }
+
+    // Encrypt last, so the encrypted pages refer to the fingerprinted assets
+    if !site_config.encrypted_pages.is_empty() {
+        let encrypted = profiler.time_operation("Encrypt pages", || {
+            page_encryption::encrypt_pages(
+                Path::new(&output_dir),
+                &site_config.encrypted_pages,
+                &config.secrets,
+            )
+            .context("Failed to encrypt pages")
+        })?;
+        println!("🔒 Encrypted {} pages", encrypted);
+    }
 
     Ok(())
 }
@@ -1,11 +1,17 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use gray_matter::Matter;
 use im::Vector;
-use pulldown_cmark::{html, Options, Parser};
+use pulldown_cmark::{html, Event, Options, Parser, TextMergeStream};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::Path;
 
+use crate::blog_posts::parse_date;
+use crate::charts::render_charts;
+use crate::citations::{cite, load_references, render_bibliography, Reference};
+use crate::front_matter::CustomFields;
+
 /// Represents a static page with front matter metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
@@ -25,6 +31,18 @@ pub struct Page {
     pub custom_js: Option<String>,
     /// Sort order for menu (lower numbers appear first)
     pub order: Option<i32>,
+    /// Date of the last revision, from the front matter
+    #[serde(default)]
+    pub updated: Option<DateTime<Utc>>,
+    /// Date after which the page should be reviewed
+    #[serde(default)]
+    pub review_after: Option<DateTime<Utc>>,
+    /// Works listed with `nocite`, when the page is a publications list
+    #[serde(skip)]
+    pub publications: Vector<Reference>,
+    /// Front matter fields that aren't built in, for templates
+    #[serde(default)]
+    pub fields: CustomFields,
 }
 
 /// Front matter structure for markdown pages
@@ -37,6 +55,14 @@ struct PageFrontMatter {
     custom_css: Option<String>,
     custom_js: Option<String>,
     order: Option<i32>,
+    updated: Option<String>,
+    review_after: Option<String>,
+    #[serde(default)]
+    references: Vector<Reference>,
+    bibliography: Option<String>,
+    nocite: Option<String>,
+    #[serde(flatten)]
+    fields: CustomFields,
 }
 
 fn default_layout() -> String {
@@ -63,7 +89,7 @@ impl Page {
             .unwrap_or("page")
             .to_string();
 
-        Self::from_markdown(&content, slug)
+        Self::parse(&content, slug, path.parent())
     }
 
     /// Parse markdown content with front matter
@@ -77,6 +103,11 @@ impl Page {
     ///
     /// Result containing the parsed Page or an error
     pub fn from_markdown(content: &str, slug: String) -> Result<Self> {
+        Self::parse(content, slug, None)
+    }
+
+    /// Parse markdown content, resolving the bibliography file against `base_dir`
+    fn parse(content: &str, slug: String, base_dir: Option<&Path>) -> Result<Self> {
         let matter = Matter::<gray_matter::engine::YAML>::new();
         let parsed = matter
             .parse::<Option<PageFrontMatter>>(content)
@@ -90,8 +121,27 @@ impl Page {
             custom_css: None,
             custom_js: None,
             order: None,
+            updated: None,
+            review_after: None,
+            references: Vector::new(),
+            bibliography: None,
+            nocite: None,
+            fields: CustomFields::default(),
         });
 
+        let updated = front_matter
+            .updated
+            .as_deref()
+            .map(parse_date)
+            .transpose()
+            .context("Failed to parse page updated date")?;
+        let review_after = front_matter
+            .review_after
+            .as_deref()
+            .map(parse_date)
+            .transpose()
+            .context("Failed to parse page review_after date")?;
+
         // Convert markdown to HTML
         let mut options = Options::empty();
         options.insert(Options::ENABLE_STRIKETHROUGH);
@@ -99,9 +149,26 @@ impl Page {
         options.insert(Options::ENABLE_FOOTNOTES);
         options.insert(Options::ENABLE_SMART_PUNCTUATION);
 
-        let parser = Parser::new_ext(&parsed.content, options);
+        let references = load_references(
+            front_matter.references,
+            front_matter.bibliography.as_deref(),
+            base_dir,
+        )?;
+
+        let events: Vec<Event> =
+            TextMergeStream::new(Parser::new_ext(&parsed.content, options)).collect();
+        let (events, cited) = if references.is_empty() {
+            (events, Vector::new())
+        } else {
+            cite(events, &references, front_matter.nocite.as_deref())
+        };
+        let events = render_charts(events)?;
+
         let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
+        html::push_html(&mut html_output, events.into_iter());
+        // Pages listing works with `nocite` are publication lists
+        let is_publication_list = front_matter.nocite.is_some();
+        html_output.push_str(&render_bibliography(&cited, is_publication_list));
 
         Ok(Page {
             title: front_matter.title,
@@ -112,6 +179,14 @@ impl Page {
             custom_css: front_matter.custom_css,
             custom_js: front_matter.custom_js,
             order: front_matter.order,
+            updated,
+            review_after,
+            publications: if is_publication_list {
+                cited
+            } else {
+                Vector::new()
+            },
+            fields: front_matter.fields,
         })
     }
 }
@@ -161,6 +236,28 @@ pub fn load_pages_from_directory(dir_path: &Path) -> Result<Vector<Page>> {
     Ok(pages_vec.into_iter().collect())
 }
 
+/// Collect the publications listed on the pages of a site
+///
+/// # Arguments
+///
+/// * `pages` - Loaded pages
+///
+/// # Returns
+///
+/// The works of every publications list, in page order, without duplicate keys
+pub fn publications(pages: &Vector<Page>) -> Vector<Reference> {
+    let mut publications = Vector::new();
+    for reference in pages.iter().flat_map(|page| page.publications.iter()) {
+        if publications
+            .iter()
+            .all(|listed: &Reference| listed.key != reference.key)
+        {
+            publications.push_back(reference.clone());
+        }
+    }
+    publications
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -199,4 +296,38 @@ I'm a data engineer passionate about functional programming."#;
         assert_eq!(page.menu_label, None);
         assert!(page.content.contains("<h1>Simple Page</h1>"));
     }
+
+    #[test]
+    fn test_publications_page() {
+        let dir = std::env::temp_dir().join("cv_publications_page_test");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("papers.bib"),
+            "@inproceedings{me23, author = {Doe, Jane}, title = {A Paper}, booktitle = {RustConf}, year = 2023}",
+        )
+        .unwrap();
+        let path = dir.join("publications.md");
+        fs::write(
+            &path,
+            "---\ntitle: Publications\nbibliography: papers.bib\nnocite: \"@*\"\n---\nMy papers.",
+        )
+        .unwrap();
+
+        let page = Page::from_markdown_file(&path).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert!(page
+            .content
+            .contains(r#"<li id="ref-me23">Doe, J. (2023). A Paper. <em>RustConf</em>."#));
+        assert!(page.content.contains(
+            r#"<details class="cite"><summary>Cite</summary><pre><code class="language-bibtex">@inproceedings{me23,"#
+        ));
+
+        let publications = publications(&Vector::from(vec![page.clone(), page]));
+        assert_eq!(publications.len(), 1);
+        assert_eq!(publications[0].key, "me23");
+        assert!(publications[0]
+            .to_bibtex()
+            .contains("booktitle = {RustConf}"));
+    }
 }
@@ -0,0 +1,521 @@
+//! Offer comparison worksheet
+//!
+//! Job offers are compared in `private/offers.yaml`: the compensation of each
+//! offer, broken into components, and a score from 0 to 5 for every benefit
+//! criterion, weighted by how much it matters:
+//!
+//! ```yaml
+//! currency: EUR
+//! criteria:
+//!   - name: Remote work
+//!     weight: 3
+//!   - name: Learning budget
+//! offers:
+//!   - company: Acme
+//!     role: Senior Engineer
+//!     application: 3
+//!     compensation:
+//!       - name: Base salary
+//!         amount: 85000
+//!       - name: Bonus
+//!         amount: 10000
+//!         certainty: 0.5
+//!     benefits:
+//!       Remote work: 4
+//!       Learning budget: 2
+//! ```
+//!
+//! Amounts are yearly. A component with a `certainty` below 1, like a bonus
+//! or equity, counts for that share in the expected compensation. `cv offers`
+//! renders the worksheet as an HTML page or, through the Typst pipeline, a PDF
+//! (see [`crate::typst_generator::generate_offers_pdf`]).
+//!
+//! Offers never reach the site: the build doesn't read the file, `private/`
+//! is git-ignored, and [`ensure_private`] refuses to write the worksheet into
+//! the output directory.
+
+use anyhow::{bail, Context, Result};
+use im::Vector;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Default location of the offers
+pub const OFFERS_FILE: &str = "private/offers.yaml";
+
+/// Highest score of a benefit
+pub const MAX_SCORE: f64 = 5.0;
+
+/// The offers to compare and what they are compared on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfferComparison {
+    /// Currency of every amount, e.g. `EUR`
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Benefits to score each offer on
+    #[serde(default)]
+    pub criteria: Vector<Criterion>,
+    pub offers: Vector<Offer>,
+}
+
+/// A benefit offers are scored on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Criterion {
+    pub name: String,
+    /// How much the criterion counts relative to the others
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+/// An offer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Offer {
+    pub company: String,
+    pub role: String,
+    /// Id of the application in the tracker, if it is tracked
+    #[serde(default)]
+    pub application: Option<i64>,
+    #[serde(default)]
+    pub compensation: Vector<Component>,
+    /// Score from 0 to 5 by criterion name; unscored criteria count as 0
+    #[serde(default)]
+    pub benefits: im::OrdMap<String, f64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// A part of the yearly compensation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Component {
+    pub name: String,
+    /// Yearly amount
+    pub amount: f64,
+    /// Chance the amount is paid out, from 0 to 1
+    #[serde(default = "default_certainty")]
+    pub certainty: f64,
+}
+
+fn default_currency() -> String {
+    "EUR".to_string()
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_certainty() -> f64 {
+    1.0
+}
+
+impl OfferComparison {
+    /// Load offers from a YAML file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the YAML file
+    ///
+    /// # Returns
+    ///
+    /// The validated offers, or an error naming the problem
+    pub fn from_yaml(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read offers from {}", path.display()))?;
+        Self::from_yaml_str(&content)
+            .with_context(|| format!("Invalid offers in {}", path.display()))
+    }
+
+    /// Load offers from YAML text
+    pub fn from_yaml_str(yaml: &str) -> Result<Self> {
+        let comparison: Self = serde_yaml::from_str(yaml).context("Failed to parse offers")?;
+        comparison.validate()?;
+        Ok(comparison)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(criterion) = self.criteria.iter().find(|c| c.weight <= 0.0) {
+            bail!("Criterion '{}' needs a positive weight", criterion.name);
+        }
+        for offer in self.offers.iter() {
+            if let Some(component) = offer
+                .compensation
+                .iter()
+                .find(|c| !(0.0..=1.0).contains(&c.certainty))
+            {
+                bail!(
+                    "{}: the certainty of '{}' must be between 0 and 1",
+                    offer.company,
+                    component.name
+                );
+            }
+            for (name, score) in offer.benefits.iter() {
+                if !self.criteria.iter().any(|c| &c.name == name) {
+                    bail!(
+                        "{}: '{}' is not one of the criteria ({})",
+                        offer.company,
+                        name,
+                        self.criteria
+                            .iter()
+                            .map(|c| c.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    );
+                }
+                if !(0.0..=MAX_SCORE).contains(score) {
+                    bail!(
+                        "{}: the score for '{}' must be between 0 and {}",
+                        offer.company,
+                        name,
+                        MAX_SCORE
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Offers with their totals, best expected compensation first
+    pub fn ranked(&self) -> Vector<RankedOffer> {
+        let mut ranked = self
+            .offers
+            .iter()
+            .map(|offer| RankedOffer {
+                guaranteed: offer.guaranteed_compensation(),
+                expected: offer.expected_compensation(),
+                benefits_score: offer.benefits_score(&self.criteria),
+                offer: offer.clone(),
+            })
+            .collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.expected.total_cmp(&a.expected));
+        ranked.into_iter().collect()
+    }
+
+    /// Every component name, in the order they first appear
+    pub fn component_names(&self) -> Vector<String> {
+        self.offers
+            .iter()
+            .flat_map(|offer| offer.compensation.iter())
+            .fold(Vector::new(), |mut names, component| {
+                if !names.contains(&component.name) {
+                    names.push_back(component.name.clone());
+                }
+                names
+            })
+    }
+
+    /// Format an amount in the currency, e.g. `EUR 85,000`
+    pub fn money(&self, amount: f64) -> String {
+        let digits = format!("{:.0}", amount.abs());
+        let grouped = digits
+            .as_bytes()
+            .rchunks(3)
+            .rev()
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(",");
+        let sign = if amount < -0.5 { "-" } else { "" };
+        format!("{} {}{}", self.currency, sign, grouped)
+    }
+}
+
+impl Offer {
+    /// Compensation paid out for certain
+    pub fn guaranteed_compensation(&self) -> f64 {
+        self.compensation
+            .iter()
+            .filter(|c| c.certainty >= 1.0)
+            .map(|c| c.amount)
+            .sum()
+    }
+
+    /// Compensation weighted by how likely each component is paid out
+    pub fn expected_compensation(&self) -> f64 {
+        self.compensation
+            .iter()
+            .map(|c| c.amount * c.certainty)
+            .sum()
+    }
+
+    /// The amount of a component, if the offer has it
+    pub fn component(&self, name: &str) -> Option<&Component> {
+        self.compensation.iter().find(|c| c.name == name)
+    }
+
+    /// Weighted average of the benefit scores, from 0 to 5
+    ///
+    /// # Arguments
+    ///
+    /// * `criteria` - The criteria to score on, with their weights
+    pub fn benefits_score(&self, criteria: &Vector<Criterion>) -> f64 {
+        let total_weight = criteria.iter().map(|c| c.weight).sum::<f64>();
+        if total_weight == 0.0 {
+            return 0.0;
+        }
+        criteria
+            .iter()
+            .map(|c| c.weight * self.benefits.get(&c.name).copied().unwrap_or(0.0))
+            .sum::<f64>()
+            / total_weight
+    }
+}
+
+/// An offer with its computed totals
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankedOffer {
+    pub offer: Offer,
+    pub guaranteed: f64,
+    pub expected: f64,
+    pub benefits_score: f64,
+}
+
+/// Refuse to write a private document into the output directory
+///
+/// # Arguments
+///
+/// * `path` - Where the document would be written
+/// * `output_dir` - The directory the site is built into
+///
+/// # Returns
+///
+/// An error if `path` is inside `output_dir`
+pub fn ensure_private(path: &Path, output_dir: &Path) -> Result<()> {
+    let absolute = |path: &Path| {
+        std::path::absolute(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .components()
+            .collect::<std::path::PathBuf>()
+    };
+    if absolute(path).starts_with(absolute(output_dir)) {
+        bail!(
+            "{} is inside the output directory {}; offers are private and must not be published",
+            path.display(),
+            output_dir.display()
+        );
+    }
+    Ok(())
+}
+
+/// Render the worksheet as a standalone HTML page
+///
+/// # Arguments
+///
+/// * `comparison` - The offers
+/// * `name` - Whose offers they are, for the title
+///
+/// # Returns
+///
+/// The page, marked `noindex`
+pub fn worksheet_html(comparison: &OfferComparison, name: &str) -> String {
+    let ranked = comparison.ranked();
+    let header = ranked
+        .iter()
+        .map(|r| {
+            format!(
+                "<th>{}<br><small>{}</small></th>",
+                escape_html(&r.offer.company),
+                escape_html(&r.offer.role)
+            )
+        })
+        .collect::<String>();
+    let row = |label: &str, cells: Vec<String>, class: &str| {
+        format!(
+            "<tr{}><th>{}</th>{}</tr>",
+            class,
+            escape_html(label),
+            cells
+                .into_iter()
+                .map(|cell| format!("<td>{cell}</td>"))
+                .collect::<String>()
+        )
+    };
+
+    let component_rows = comparison
+        .component_names()
+        .iter()
+        .map(|name| {
+            let cells = ranked
+                .iter()
+                .map(|r| match r.offer.component(name) {
+                    Some(c) if c.certainty < 1.0 => format!(
+                        "{} <small>({:.0}%)</small>",
+                        comparison.money(c.amount),
+                        c.certainty * 100.0
+                    ),
+                    Some(c) => comparison.money(c.amount),
+                    None => "–".to_string(),
+                })
+                .collect();
+            row(name, cells, "")
+        })
+        .collect::<String>();
+    let totals = [
+        row(
+            "Guaranteed",
+            ranked
+                .iter()
+                .map(|r| comparison.money(r.guaranteed))
+                .collect(),
+            " class=\"total\"",
+        ),
+        row(
+            "Expected",
+            ranked
+                .iter()
+                .map(|r| comparison.money(r.expected))
+                .collect(),
+            " class=\"total\"",
+        ),
+    ]
+    .concat();
+
+    let benefit_rows = comparison
+        .criteria
+        .iter()
+        .map(|criterion| {
+            let cells = ranked
+                .iter()
+                .map(|r| {
+                    r.offer
+                        .benefits
+                        .get(&criterion.name)
+                        .map(|score| format!("{score}"))
+                        .unwrap_or_else(|| "–".to_string())
+                })
+                .collect();
+            row(
+                &format!("{} (×{})", criterion.name, criterion.weight),
+                cells,
+                "",
+            )
+        })
+        .collect::<String>();
+    let score_row = row(
+        "Score",
+        ranked
+            .iter()
+            .map(|r| format!("{:.1} / {}", r.benefits_score, MAX_SCORE))
+            .collect(),
+        " class=\"total\"",
+    );
+    let notes_row = row(
+        "Notes",
+        ranked
+            .iter()
+            .map(|r| escape_html(r.offer.notes.as_deref().unwrap_or("")))
+            .collect(),
+        "",
+    );
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<title>Offers - {name}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 64rem; margin: 2rem auto; padding: 0 1rem; color: #222; }}
+table {{ border-collapse: collapse; width: 100%; margin-bottom: 2rem; }}
+td, th {{ padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; vertical-align: top; }}
+.total td, .total th {{ font-weight: 600; border-top: 2px solid #222; }}
+</style>
+</head>
+<body>
+<h1>Offers - {name}</h1>
+<p>{count} {offers}, by expected yearly compensation. Amounts in {currency}; percentages are the chance a component is paid out.</p>
+<h2>Compensation</h2>
+<table><tr><th></th>{header}</tr>{component_rows}{totals}</table>
+<h2>Benefits</h2>
+<table><tr><th></th>{header}</tr>{benefit_rows}{score_row}{notes_row}</table>
+</body>
+</html>
+"#,
+        name = escape_html(name),
+        count = ranked.len(),
+        offers = if ranked.len() == 1 { "offer" } else { "offers" },
+        currency = escape_html(&comparison.currency),
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OFFERS: &str = r#"
+currency: EUR
+criteria:
+  - name: Remote work
+    weight: 3
+  - name: Learning budget
+offers:
+  - company: Acme
+    role: Senior Engineer
+    compensation:
+      - name: Base salary
+        amount: 85000
+      - name: Bonus
+        amount: 10000
+        certainty: 0.5
+    benefits:
+      Remote work: 4
+      Learning budget: 2
+  - company: Globex
+    role: Staff Engineer
+    compensation:
+      - name: Base salary
+        amount: 92000
+    benefits:
+      Remote work: 1
+"#;
+
+    #[test]
+    fn test_ranked_offers() {
+        let comparison = OfferComparison::from_yaml_str(OFFERS).unwrap();
+        let ranked = comparison.ranked();
+
+        assert_eq!(ranked[0].offer.company, "Globex");
+        assert_eq!(ranked[0].expected, 92000.0);
+        assert_eq!(ranked[0].benefits_score, 0.75);
+        assert_eq!(ranked[1].guaranteed, 85000.0);
+        assert_eq!(ranked[1].expected, 90000.0);
+        assert_eq!(ranked[1].benefits_score, 3.5);
+        assert_eq!(
+            comparison.component_names(),
+            im::vector!["Base salary".to_string(), "Bonus".to_string()]
+        );
+        assert_eq!(comparison.money(1234567.4), "EUR 1,234,567");
+    }
+
+    #[test]
+    fn test_invalid_offers() {
+        let unknown = OFFERS.replace("Learning budget: 2", "Free lunch: 2");
+        let error = OfferComparison::from_yaml_str(&unknown).unwrap_err();
+        assert!(format!("{error:#}").contains("'Free lunch' is not one of the criteria"));
+
+        let out_of_range = OFFERS.replace("Remote work: 4", "Remote work: 7");
+        assert!(OfferComparison::from_yaml_str(&out_of_range).is_err());
+    }
+
+    #[test]
+    fn test_worksheet_html() {
+        let comparison = OfferComparison::from_yaml_str(OFFERS).unwrap();
+        let html = worksheet_html(&comparison, "Ada");
+
+        assert!(html.contains(r#"<meta name="robots" content="noindex">"#));
+        assert!(html.contains("<td>EUR 10,000 <small>(50%)</small></td>"));
+        assert!(html.contains("<th>Remote work (×3)</th><td>1</td><td>4</td>"));
+    }
+
+    #[test]
+    fn test_ensure_private() {
+        assert!(ensure_private(Path::new("private/offers.pdf"), Path::new("dist")).is_ok());
+        assert!(ensure_private(Path::new("dist/offers.pdf"), Path::new("dist")).is_err());
+        assert!(ensure_private(Path::new("./dist/a/../offers.html"), Path::new("dist")).is_err());
+    }
+}
@@ -0,0 +1,520 @@
+//! OpenAPI description of the dev server's APIs
+//!
+//! `cv serve` answers JSON requests for post reactions, drafts, testimonials,
+//! the job application tracker and the admin operations. This module
+//! describes them as an OpenAPI 3.1 document, served at [`OPENAPI_PATH`] with
+//! a Swagger UI at [`API_DOCS_PATH`], and written to disk by `cv api spec`.
+//! The enums in the schemas come from the types the server parses, so the
+//! document can't drift from the values it accepts.
+
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::admin::{AdminOperation, ADMIN_PATH};
+use crate::applications::{ApplicationStatus, APPLICATIONS_PATH};
+use crate::content_security_policy::script_hash;
+use crate::reactions::{Reaction, REACTIONS_PATH};
+use crate::submissions::{MAX_DRAFT_SIZE, SUBMISSION_INBOX_PATH};
+use crate::testimonials::{MAX_QUOTE_LENGTH, SUBMISSION_PATH};
+
+/// Path the OpenAPI document is served on
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Path the Swagger UI is served on
+pub const API_DOCS_PATH: &str = "/api/docs";
+
+/// File `cv api spec` writes to by default
+pub const DEFAULT_SPEC_FILE: &str = "openapi.json";
+
+/// Where the Swagger UI page loads its script and stylesheet from
+const SWAGGER_UI_CDN: &str = "https://cdn.jsdelivr.net/npm/swagger-ui-dist@5";
+
+/// The OpenAPI 3.1 document of the dev server's APIs
+///
+/// # Returns
+///
+/// The document as JSON
+pub fn openapi_spec() -> Value {
+    let reactions = Reaction::ALL.map(|reaction| reaction.as_str());
+    let statuses = ApplicationStatus::ALL.map(|status| status.as_str());
+    let operations = AdminOperation::ALL.map(|operation| operation.as_str());
+    let bearer = json!([{ "bearerAuth": [] }]);
+
+    let mut paths = serde_json::Map::new();
+    paths.insert(
+        format!("{REACTIONS_PATH}/{{slug}}"),
+        json!({
+            "parameters": [path_parameter("slug", "Slug of the post", json!({ "type": "string" }))],
+            "get": {
+                "tags": ["reactions"],
+                "summary": "Reactions to a post",
+                "responses": {
+                    "200": json_response("The counts", schema_ref("ReactionCounts")),
+                    "404": error_response("No such post"),
+                    "429": error_response("Too many requests from this address"),
+                    "503": error_response("Down for maintenance"),
+                }
+            },
+            "post": {
+                "tags": ["reactions"],
+                "summary": "React to a post",
+                "requestBody": json_body(schema_ref("ReactionSubmission")),
+                "responses": {
+                    "200": json_response("The new counts", schema_ref("ReactionCounts")),
+                    "400": error_response("Invalid reaction"),
+                    "404": error_response("No such post"),
+                    "429": error_response("Too many requests from this address"),
+                    "503": error_response("Down for maintenance"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        SUBMISSION_INBOX_PATH.to_string(),
+        json!({
+            "post": {
+                "tags": ["submissions"],
+                "summary": "Submit a draft post for review",
+                "description": format!(
+                    "Queued for `cv submissions`. Needs the submissions token if one is configured. Bodies are limited to {} KiB.",
+                    MAX_DRAFT_SIZE / 1024
+                ),
+                "security": [{}, { "bearerAuth": [] }],
+                "requestBody": json_body(schema_ref("DraftSubmission")),
+                "responses": {
+                    "202": json_response("Queued for review", schema_ref("Pending")),
+                    "400": error_response("Invalid draft"),
+                    "401": error_response("Missing or invalid token"),
+                    "503": error_response("Down for maintenance"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        SUBMISSION_PATH.to_string(),
+        json!({
+            "post": {
+                "tags": ["testimonials"],
+                "summary": "Submit a testimonial",
+                "description": "Queued for moderation with `cv testimonials`.",
+                "requestBody": json_body(schema_ref("TestimonialSubmission")),
+                "responses": {
+                    "202": json_response("Queued for moderation", schema_ref("Pending")),
+                    "400": error_response("Invalid testimonial"),
+                    "503": error_response("Down for maintenance"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        APPLICATIONS_PATH.to_string(),
+        json!({
+            "get": {
+                "tags": ["applications"],
+                "summary": "All tracked applications",
+                "security": bearer,
+                "responses": {
+                    "200": json_response(
+                        "The applications",
+                        json!({ "type": "array", "items": schema_ref("Application") })
+                    ),
+                    "401": error_response("Missing or invalid token"),
+                    "503": error_response("Down for maintenance"),
+                }
+            },
+            "post": {
+                "tags": ["applications"],
+                "summary": "Track an application",
+                "security": bearer,
+                "requestBody": json_body(schema_ref("NewApplication")),
+                "responses": {
+                    "201": json_response("Tracked", schema_ref("Created")),
+                    "400": error_response("Invalid application"),
+                    "401": error_response("Missing or invalid token"),
+                    "503": error_response("Down for maintenance"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        format!("{APPLICATIONS_PATH}/{{id}}"),
+        json!({
+            "parameters": [path_parameter(
+                "id",
+                "Id of the application",
+                json!({ "type": "integer", "format": "int64" })
+            )],
+            "get": {
+                "tags": ["applications"],
+                "summary": "One application",
+                "security": bearer,
+                "responses": {
+                    "200": json_response("The application", schema_ref("Application")),
+                    "401": error_response("Missing or invalid token"),
+                    "404": error_response("No such application"),
+                    "503": error_response("Down for maintenance"),
+                }
+            },
+            "patch": {
+                "tags": ["applications"],
+                "summary": "Change the status, follow-up or notes of an application",
+                "security": bearer,
+                "requestBody": json_body(schema_ref("ApplicationUpdate")),
+                "responses": {
+                    "200": json_response("The changed application", schema_ref("Application")),
+                    "400": error_response("Invalid update"),
+                    "401": error_response("Missing or invalid token"),
+                    "404": error_response("No such application"),
+                    "503": error_response("Down for maintenance"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        ADMIN_PATH.to_string(),
+        json!({
+            "get": {
+                "tags": ["admin"],
+                "summary": "Operations that can be run",
+                "security": bearer,
+                "responses": {
+                    "200": json_response(
+                        "Names of the operations",
+                        json!({ "type": "array", "items": { "type": "string", "enum": operations } })
+                    ),
+                    "401": error_response("Missing or invalid token"),
+                    "403": error_response("Address not in the admin allowlist"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        format!("{ADMIN_PATH}/maintenance"),
+        json!({
+            "get": {
+                "tags": ["admin"],
+                "summary": "Whether maintenance mode is on",
+                "security": bearer,
+                "responses": {
+                    "200": json_response("The mode", schema_ref("Maintenance")),
+                    "401": error_response("Missing or invalid token"),
+                    "403": error_response("Address not in the admin allowlist"),
+                    "404": error_response("Maintenance mode is not available"),
+                }
+            },
+            "put": {
+                "tags": ["admin"],
+                "summary": "Switch maintenance mode",
+                "description": "While it is on, the public APIs answer 503.",
+                "security": bearer,
+                "requestBody": json_body(schema_ref("Maintenance")),
+                "responses": {
+                    "200": json_response("The new mode", schema_ref("Maintenance")),
+                    "400": error_response("Invalid switch"),
+                    "401": error_response("Missing or invalid token"),
+                    "403": error_response("Address not in the admin allowlist"),
+                    "404": error_response("Maintenance mode is not available"),
+                }
+            }
+        }),
+    );
+    paths.insert(
+        format!("{ADMIN_PATH}/{{operation}}"),
+        json!({
+            "parameters": [path_parameter(
+                "operation",
+                "The operation to run",
+                json!({ "type": "string", "enum": operations })
+            )],
+            "post": {
+                "tags": ["admin"],
+                "summary": "Run an operation",
+                "security": bearer,
+                "responses": {
+                    "200": json_response("What the operation did", schema_ref("OperationReport")),
+                    "401": error_response("Missing or invalid token"),
+                    "403": error_response("Address not in the admin allowlist"),
+                    "404": error_response("Unknown operation"),
+                    "500": error_response("The operation failed"),
+                }
+            }
+        }),
+    );
+
+    let date = json!({ "type": ["string", "null"], "format": "date" });
+    let optional_string = json!({ "type": ["string", "null"] });
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "cv dev server",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "APIs served by `cv serve` next to the generated site.",
+        },
+        "servers": [{ "url": "/" }],
+        "paths": paths,
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The applications, admin or submissions token from the secret store",
+                }
+            },
+            "schemas": {
+                "Error": object(&["error"], json!({ "error": { "type": "string" } })),
+                "Pending": object(&["status"], json!({ "status": { "const": "pending" } })),
+                "Maintenance": object(&["enabled"], json!({ "enabled": { "type": "boolean" } })),
+                "Created": object(&["id"], json!({ "id": { "type": "integer", "format": "int64" } })),
+                "Reaction": { "type": "string", "enum": reactions },
+                "ReactionSubmission": object(&["reaction"], json!({ "reaction": schema_ref("Reaction") })),
+                "ReactionCounts": object(
+                    &reactions,
+                    Value::Object(
+                        reactions
+                            .iter()
+                            .map(|reaction| {
+                                (reaction.to_string(), json!({ "type": "integer", "minimum": 0 }))
+                            })
+                            .collect()
+                    )
+                ),
+                "DraftSubmission": object(&["title", "body", "author"], json!({
+                    "title": { "type": "string" },
+                    "body": { "type": "string", "description": "The post, in markdown" },
+                    "author": { "type": "string" },
+                    "contact": optional_string,
+                    "excerpt": optional_string,
+                    "tags": { "type": "array", "items": { "type": "string" } },
+                })),
+                "TestimonialSubmission": object(&["quote", "author"], json!({
+                    "quote": { "type": "string", "maxLength": MAX_QUOTE_LENGTH },
+                    "author": { "type": "string" },
+                    "relation": optional_string,
+                    "link": { "type": ["string", "null"], "format": "uri" },
+                })),
+                "ApplicationStatus": { "type": "string", "enum": statuses },
+                "Application": object(
+                    &["id", "company", "role", "variant", "status", "applied_on", "follow_up", "notes", "updated_at"],
+                    json!({
+                        "id": { "type": "integer", "format": "int64" },
+                        "company": { "type": "string" },
+                        "role": { "type": "string" },
+                        "variant": optional_string,
+                        "status": schema_ref("ApplicationStatus"),
+                        "applied_on": date,
+                        "follow_up": date,
+                        "notes": optional_string,
+                        "updated_at": { "type": "string", "format": "date-time" },
+                    })
+                ),
+                "NewApplication": object(&["company", "role"], json!({
+                    "company": { "type": "string" },
+                    "role": { "type": "string" },
+                    "variant": optional_string,
+                    "status": schema_ref("ApplicationStatus"),
+                    "applied_on": date,
+                    "follow_up": date,
+                    "notes": optional_string,
+                })),
+                "ApplicationUpdate": object(&[], json!({
+                    "status": { "oneOf": [schema_ref("ApplicationStatus"), { "type": "null" }] },
+                    "follow_up": date,
+                    "notes": optional_string,
+                })),
+                "StepReport": object(&["name", "millis", "detail"], json!({
+                    "name": { "type": "string" },
+                    "millis": { "type": "integer", "minimum": 0 },
+                    "detail": { "type": "string" },
+                })),
+                "OperationReport": object(&["operation", "steps", "total_millis"], json!({
+                    "operation": { "type": "string", "enum": operations },
+                    "steps": { "type": "array", "items": schema_ref("StepReport") },
+                    "total_millis": { "type": "integer", "minimum": 0 },
+                })),
+            }
+        }
+    })
+}
+
+/// Write the OpenAPI document to a file
+///
+/// # Arguments
+///
+/// * `path` - File to write, its directory is created if needed
+///
+/// # Returns
+///
+/// A Result indicating whether the document was written
+pub fn write_spec(path: &Path) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    let spec = serde_json::to_string_pretty(&openapi_spec())?;
+    fs::write(path, spec + "\n").with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The Swagger UI page for the OpenAPI document
+///
+/// The page loads Swagger UI from a CDN, so it needs its own
+/// Content-Security-Policy, see [`swagger_ui_policy`].
+///
+/// # Arguments
+///
+/// * `spec_url` - URL of the OpenAPI document
+///
+/// # Returns
+///
+/// A standalone HTML page
+pub fn swagger_ui_html(spec_url: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>cv dev server API</title>
+<link rel="stylesheet" href="{SWAGGER_UI_CDN}/swagger-ui.css">
+</head>
+<body>
+<div id="swagger-ui"></div>
+<script src="{SWAGGER_UI_CDN}/swagger-ui-bundle.js"></script>
+<script>{script}</script>
+</body>
+</html>
+"#,
+        script = swagger_ui_script(spec_url),
+    )
+}
+
+/// Content-Security-Policy of the Swagger UI page
+///
+/// # Arguments
+///
+/// * `spec_url` - URL of the OpenAPI document the page was rendered with
+///
+/// # Returns
+///
+/// The header value, allowing the CDN and the page's inline script
+pub fn swagger_ui_policy(spec_url: &str) -> String {
+    format!(
+        "default-src 'none'; script-src https://cdn.jsdelivr.net {}; style-src https://cdn.jsdelivr.net 'unsafe-inline'; img-src 'self' data: https://cdn.jsdelivr.net; connect-src 'self'; base-uri 'none'; form-action 'none'",
+        script_hash(&swagger_ui_script(spec_url))
+    )
+}
+
+/// The inline script starting Swagger UI
+fn swagger_ui_script(spec_url: &str) -> String {
+    format!(
+        "window.ui = SwaggerUIBundle({{ url: {}, dom_id: '#swagger-ui' }});",
+        Value::from(spec_url)
+    )
+}
+
+/// A reference to a schema in the components
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+/// An object schema
+fn object(required: &[&str], properties: Value) -> Value {
+    json!({ "type": "object", "required": required, "properties": properties })
+}
+
+/// A required path parameter
+fn path_parameter(name: &str, description: &str, schema: Value) -> Value {
+    json!({ "name": name, "in": "path", "required": true, "description": description, "schema": schema })
+}
+
+/// A required JSON request body
+fn json_body(schema: Value) -> Value {
+    json!({ "required": true, "content": { "application/json": { "schema": schema } } })
+}
+
+/// A JSON response
+fn json_response(description: &str, schema: Value) -> Value {
+    json!({ "description": description, "content": { "application/json": { "schema": schema } } })
+}
+
+/// A JSON error response
+fn error_response(description: &str) -> Value {
+    json_response(description, schema_ref("Error"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` in a JSON value
+    fn refs(value: &Value, found: &mut Vec<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    found.push(reference.clone());
+                }
+                map.values().for_each(|value| refs(value, found));
+            }
+            Value::Array(values) => values.iter().for_each(|value| refs(value, found)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_openapi_spec() {
+        let spec = openapi_spec();
+        assert_eq!(spec["openapi"], "3.1.0");
+
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/api/reactions/{slug}",
+            "/api/submissions",
+            "/api/testimonials",
+            "/api/applications",
+            "/api/applications/{id}",
+            "/api/admin",
+            "/api/admin/maintenance",
+            "/api/admin/{operation}",
+        ] {
+            assert!(paths.contains_key(path), "missing {path}");
+        }
+        assert_eq!(
+            paths["/api/applications"]["get"]["security"][0]["bearerAuth"],
+            json!([])
+        );
+        assert_eq!(
+            spec["components"]["schemas"]["ApplicationStatus"]["enum"]
+                .as_array()
+                .unwrap()
+                .len(),
+            ApplicationStatus::ALL.len()
+        );
+
+        let mut found = Vec::new();
+        refs(&spec, &mut found);
+        assert!(!found.is_empty());
+        for reference in found {
+            let pointer = reference.strip_prefix('#').unwrap();
+            assert!(spec.pointer(pointer).is_some(), "unresolved {reference}");
+        }
+    }
+
+    #[test]
+    fn test_swagger_ui() {
+        let html = swagger_ui_html(OPENAPI_PATH);
+        assert!(html.contains("SwaggerUIBundle({ url: \"/api/openapi.json\""));
+        let policy = swagger_ui_policy(OPENAPI_PATH);
+        assert!(policy.contains(&script_hash(&swagger_ui_script(OPENAPI_PATH))));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("docs/openapi.json");
+        write_spec(&path).unwrap();
+        let written: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(written, openapi_spec());
+    }
+}
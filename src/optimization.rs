@@ -1,99 +1,206 @@
 //! Build optimization module for CSS and JS minification
+//!
+//! CSS is minified with lightningcss, which also adds the vendor prefixes and
+//! fallbacks needed by the browsers in `assets.browserslist`. JavaScript is
+//! minified with the oxc minifier. With `assets.source_maps` enabled, a
+//! `.map` file is written next to each minified file.
 
 use anyhow::{Context, Result};
+use im::Vector;
+use lightningcss::stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet};
+use lightningcss::targets::{Browsers, Targets};
+use oxc_allocator::Allocator;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_minifier::{Minifier, MinifierOptions};
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use parcel_sourcemap::SourceMap;
 use std::fs;
-use std::path::Path;
-
-/// Simple CSS minification - removes comments, whitespace, and line breaks
-pub fn minify_css(css_content: &str) -> String {
-    css_content
-        // Remove CSS comments
-        .split("/*")
-        .enumerate()
-        .map(|(i, part)| {
-            if i == 0 {
-                part.to_string()
-            } else {
-                part.split("*/").skip(1).collect::<Vec<_>>().join("")
-            }
-        })
-        .collect::<String>()
-        // Remove extra whitespace and line breaks
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join(" ")
-        // Remove spaces around CSS operators
-        .replace(" { ", "{")
-        .replace(" } ", "}")
-        .replace(" { ", "{")
-        .replace("; ", ";")
-        .replace(": ", ":")
-        .replace(", ", ",")
+use std::path::{Path, PathBuf};
+
+use crate::site_config::AssetConfig;
+
+/// Browserslist query used when none is configured
+const DEFAULT_BROWSERSLIST: &str = "defaults";
+
+/// Minified code with its optional source map
+#[derive(Debug, Clone)]
+pub struct Minified {
+    /// The minified code
+    pub code: String,
+    /// Source map as JSON, if source maps are enabled
+    pub source_map: Option<String>,
 }
 
-/// Simple JavaScript minification - removes comments and unnecessary whitespace
-pub fn minify_js(js_content: &str) -> String {
-    js_content
-        // Remove single-line comments (basic implementation)
-        .lines()
-        .map(|line| {
-            if let Some(comment_pos) = line.find("//") {
-                // Check if // is inside a string
-                let before_comment = &line[..comment_pos];
-                let single_quotes = before_comment.matches('\'').count();
-                let double_quotes = before_comment.matches('"').count();
-
-                // Simple heuristic: if odd number of quotes, // is likely in a string
-                if single_quotes % 2 == 1 || double_quotes % 2 == 1 {
-                    line.to_string()
-                } else {
-                    before_comment.to_string()
-                }
-            } else {
-                line.to_string()
-            }
+/// Minify CSS with lightningcss
+///
+/// # Arguments
+///
+/// * `css` - The stylesheet
+/// * `filename` - Name of the stylesheet, used in errors and the source map
+/// * `options` - Browser targets and source map settings
+///
+/// # Returns
+///
+/// The minified CSS, or an error if it can't be parsed
+pub fn minify_css(css: &str, filename: &str, options: &AssetConfig) -> Result<Minified> {
+    let targets = css_targets(&options.browserslist)?;
+
+    let mut stylesheet = StyleSheet::parse(
+        css,
+        ParserOptions {
+            filename: filename.to_string(),
+            ..ParserOptions::default()
+        },
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", filename, e))?;
+
+    stylesheet
+        .minify(MinifyOptions {
+            targets,
+            ..MinifyOptions::default()
         })
-        .collect::<Vec<_>>()
-        .join("\n")
-        // Remove multi-line comments (basic)
-        .split("/*")
-        .enumerate()
-        .map(|(i, part)| {
-            if i == 0 {
-                part.to_string()
-            } else {
-                part.split("*/").skip(1).collect::<Vec<_>>().join("")
-            }
+        .map_err(|e| anyhow::anyhow!("Failed to minify {}: {}", filename, e))?;
+
+    let mut source_map = if options.source_maps() {
+        let mut map = SourceMap::new("/");
+        let source = map.add_source(filename);
+        map.set_source_content(source as usize, css)
+            .map_err(|e| anyhow::anyhow!("Failed to build source map for {}: {}", filename, e))?;
+        Some(map)
+    } else {
+        None
+    };
+
+    let output = stylesheet
+        .to_css(PrinterOptions {
+            minify: true,
+            targets,
+            source_map: source_map.as_mut(),
+            ..PrinterOptions::default()
         })
-        .collect::<String>()
-        // Remove extra whitespace
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n")
+        .map_err(|e| anyhow::anyhow!("Failed to print {}: {}", filename, e))?;
+
+    let source_map = source_map
+        .map(|mut map| map.to_json(None))
+        .transpose()
+        .map_err(|e| anyhow::anyhow!("Failed to write source map for {}: {}", filename, e))?;
+
+    Ok(Minified {
+        code: output.code,
+        source_map,
+    })
 }
 
-/// Bundle and optimize a CSS file by resolving @imports and minifying
-pub fn optimize_css_file(input_path: &Path, output_path: &Path) -> Result<()> {
-    let bundled_css = bundle_css_imports(input_path)?;
-    let minified = minify_css(&bundled_css);
+/// Minify JavaScript with the oxc minifier
+///
+/// The code is treated as a classic script, so top-level names that pages
+/// may call are never renamed.
+///
+/// # Arguments
+///
+/// * `js` - The script
+/// * `filename` - Name of the script, used in errors and the source map
+/// * `options` - Source map settings
+///
+/// # Returns
+///
+/// The minified JavaScript, or an error if it can't be parsed
+pub fn minify_js(js: &str, filename: &str, options: &AssetConfig) -> Result<Minified> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, js, SourceType::script()).parse();
+    if let Some(error) = parsed.diagnostics.iter().next() {
+        anyhow::bail!("Failed to parse {}: {}", filename, error);
+    }
 
+    let mut program = parsed.program;
+    let minified = Minifier::new(MinifierOptions::default()).minify(&allocator, &mut program);
+    let output = Codegen::new()
+        .with_options(CodegenOptions {
+            source_map_path: options.source_maps().then(|| PathBuf::from(filename)),
+            ..CodegenOptions::minify()
+        })
+        .with_scoping(minified.scoping)
+        .build(&program);
+
+    Ok(Minified {
+        code: output.code,
+        source_map: output.map.map(|map| map.to_json_string()),
+    })
+}
+
+/// Resolve browserslist queries to lightningcss targets
+fn css_targets(browserslist: &Vector<String>) -> Result<Targets> {
+    let queries = if browserslist.is_empty() {
+        vec![DEFAULT_BROWSERSLIST.to_string()]
+    } else {
+        browserslist.iter().cloned().collect()
+    };
+
+    let browsers = Browsers::from_browserslist(&queries)
+        .with_context(|| format!("Invalid browserslist queries: {}", queries.join(", ")))?;
+
+    Ok(Targets {
+        browsers,
+        ..Targets::default()
+    })
+}
+
+/// Write minified code, and its source map next to it if there is one
+///
+/// # Arguments
+///
+/// * `output_path` - Path of the minified file
+/// * `minified` - The minified code
+/// * `map_comment` - Builds the `sourceMappingURL` comment for the map's file name
+fn write_minified(
+    output_path: &Path,
+    minified: Minified,
+    map_comment: fn(&str) -> String,
+) -> Result<()> {
     // Ensure output directory exists
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
 
-    fs::write(output_path, minified).with_context(|| {
-        format!(
-            "Failed to write bundled and minified CSS: {:?}",
-            output_path
-        )
-    })?;
+    let code = match minified.source_map {
+        Some(source_map) => {
+            let map_path = PathBuf::from(format!("{}.map", output_path.display()));
+            fs::write(&map_path, source_map)
+                .with_context(|| format!("Failed to write source map: {:?}", map_path))?;
+            let map_name = map_path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!("{}\n{}", minified.code.trim_end(), map_comment(&map_name))
+        }
+        None => minified.code,
+    };
+
+    fs::write(output_path, code)
+        .with_context(|| format!("Failed to write minified file: {:?}", output_path))
+}
+
+/// File name of a path, for error messages and source maps
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Bundle and optimize a CSS file by resolving @imports and minifying
+pub fn optimize_css_file(
+    input_path: &Path,
+    output_path: &Path,
+    options: &AssetConfig,
+) -> Result<()> {
+    let bundled_css = hoist_imports(&bundle_css_imports(input_path)?);
+    let minified = minify_css(&bundled_css, &file_name(input_path), options)?;
 
-    Ok(())
+    write_minified(output_path, minified, |map| {
+        format!("/*# sourceMappingURL={map} */")
+    })
+    .context("Failed to write bundled and minified CSS")
 }
 
 /// Bundle CSS by resolving @import statements into a single file
@@ -129,9 +236,9 @@ pub fn bundle_css_imports(css_path: &Path) -> Result<String> {
                         }
                     }
                 } else {
-                    // Keep external imports (like Google Fonts)
-                    bundled_content.push_str(line);
-                    bundled_content.push('\n');
+                    // A missing local file would 404 in the browser too, and
+                    // an @import left between rules is invalid CSS
+                    eprintln!("Warning: Skipping missing CSS import {}", import_path);
                 }
             } else {
                 // Keep the original line if we can't parse it
@@ -148,6 +255,22 @@ pub fn bundle_css_imports(css_path: &Path) -> Result<String> {
     Ok(bundled_content)
 }
 
+/// Move the remaining (external) @import rules to the top of a bundled stylesheet
+///
+/// Imports are only valid before all other rules, which an import from a
+/// bundled file no longer is.
+fn hoist_imports(css: &str) -> String {
+    let (imports, rules): (Vec<&str>, Vec<&str>) = css
+        .lines()
+        .partition(|line| line.trim_start().starts_with("@import"));
+
+    imports
+        .into_iter()
+        .chain(rules)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Extract file path from @import statement
 fn extract_import_path(import_line: &str) -> Option<String> {
     // Handle: @import "path/file.css";
@@ -168,21 +291,20 @@ fn extract_import_path(import_line: &str) -> Option<String> {
 }
 
 /// Optimize a JavaScript file by minifying it
-pub fn optimize_js_file(input_path: &Path, output_path: &Path) -> Result<()> {
+pub fn optimize_js_file(
+    input_path: &Path,
+    output_path: &Path,
+    options: &AssetConfig,
+) -> Result<()> {
     let js_content = fs::read_to_string(input_path)
         .with_context(|| format!("Failed to read JS file: {:?}", input_path))?;
 
-    let minified = minify_js(&js_content);
-
-    // Ensure output directory exists
-    if let Some(parent) = output_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
+    let minified = minify_js(&js_content, &file_name(input_path), options)?;
 
-    fs::write(output_path, minified)
-        .with_context(|| format!("Failed to write minified JS: {:?}", output_path))?;
-
-    Ok(())
+    write_minified(output_path, minified, |map| {
+        format!("//# sourceMappingURL={map}")
+    })
+    .context("Failed to write minified JS")
 }
 
 #[cfg(test)]
@@ -196,6 +318,7 @@ mod tests {
 .class {
     color: red;
     background: blue;
+    user-select: none;
 }
 
 .another-class {
@@ -203,11 +326,25 @@ mod tests {
 }
         "#;
 
-        let minified = minify_css(css);
-        assert!(!minified.contains("/*"));
-        assert!(!minified.contains("This is a comment"));
-        assert!(minified.contains(".class{"));
-        assert!(minified.contains("color:red"));
+        let options = AssetConfig {
+            browserslist: im::vector!["safari 12".to_string()],
+            source_maps: Some(true),
+            ..AssetConfig::default()
+        };
+        let minified = minify_css(css, "main.css", &options).unwrap();
+        assert!(!minified.code.contains("/*"));
+        assert!(!minified.code.contains("This is a comment"));
+        assert!(minified.code.contains(".class{"));
+        assert!(minified.code.contains("color:red"));
+        // Prefixed for the configured browsers
+        assert!(minified.code.contains("-webkit-user-select:none"));
+        assert!(minified.source_map.unwrap().contains("main.css"));
+
+        let invalid_targets = AssetConfig {
+            browserslist: im::vector!["not a query".to_string()],
+            ..AssetConfig::default()
+        };
+        assert!(minify_css("a{}", "main.css", &invalid_targets).is_err());
     }
 
     #[test]
@@ -215,7 +352,8 @@ mod tests {
         let js = r#"
 // This is a comment
 function test() {
-    console.log("Hello"); // End comment
+    const url = "https://example.com"; // End comment
+    console.log("Hello", url);
     return true;
 }
 
@@ -224,10 +362,14 @@ function test() {
 const x = 5;
         "#;
 
-        let minified = minify_js(js);
-        assert!(!minified.contains("// This is a comment"));
-        assert!(!minified.contains("/* Multi-line"));
-        assert!(minified.contains("function test()"));
-        assert!(minified.contains("console.log(\"Hello\");"));
+        let minified = minify_js(js, "scripts.js", &AssetConfig::default()).unwrap();
+        assert!(!minified.code.contains("This is a comment"));
+        assert!(!minified.code.contains("Multi-line"));
+        // Top-level names stay callable from pages
+        assert!(minified.code.contains("function test()"));
+        assert!(minified.code.contains("https://example.com"));
+        assert!(minified.source_map.is_none());
+
+        assert!(minify_js("function (", "scripts.js", &AssetConfig::default()).is_err());
     }
 }
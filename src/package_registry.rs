@@ -0,0 +1,472 @@
+//! Published packages from crates.io, npm and PyPI
+//!
+//! `registry_sources` in the CV data lists packages to show next to the
+//! GitHub projects. Each package is fetched from its registry's JSON API with
+//! its description, latest version and download count, and cached in the
+//! GitHub cache like the repositories. A package whose repository is one of
+//! the fetched GitHub projects adds its version and downloads to that
+//! project's card instead of getting a card of its own.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::cv_data::{Project, Registry, RegistrySource};
+use crate::github::block_on;
+use crate::github_cache::GitHubCache;
+
+const CRATES_IO_API: &str = "https://crates.io/api/v1/crates";
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const NPM_DOWNLOADS_API: &str = "https://api.npmjs.org/downloads/point/last-month";
+const PYPI_API: &str = "https://pypi.org/pypi";
+const PYPI_STATS_API: &str = "https://pypistats.org/api/packages";
+
+#[derive(Debug, Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrateInfo {
+    name: String,
+    description: Option<String>,
+    max_stable_version: Option<String>,
+    newest_version: String,
+    downloads: u64,
+    homepage: Option<String>,
+    repository: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmPackage {
+    name: String,
+    version: String,
+    description: Option<String>,
+    homepage: Option<String>,
+    repository: Option<Value>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NpmDownloads {
+    downloads: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyPiResponse {
+    info: PyPiInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyPiInfo {
+    name: String,
+    version: String,
+    summary: Option<String>,
+    home_page: Option<String>,
+    #[serde(default)]
+    project_urls: Option<std::collections::BTreeMap<String, String>>,
+    keywords: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyPiStats {
+    data: PyPiRecentDownloads,
+}
+
+#[derive(Debug, Deserialize)]
+struct PyPiRecentDownloads {
+    last_month: u64,
+}
+
+/// Client for the package registry APIs
+#[derive(Debug, Clone)]
+pub struct RegistryClient {
+    http: reqwest::Client,
+    offline: bool,
+}
+
+impl Default for RegistryClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegistryClient {
+    /// Create a client; crates.io rejects requests without a user agent
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .user_agent(concat!(
+                    "cv-generator/",
+                    env!("CARGO_PKG_VERSION"),
+                    " (https://github.com/hakimjonas/cv)"
+                ))
+                .build()
+                .unwrap_or_default(),
+            offline: false,
+        }
+    }
+
+    /// Serve cached packages only, however old, and never access the network
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
+
+    /// Fetch a package from its registry
+    ///
+    /// # Arguments
+    ///
+    /// * `registry` - The registry the package is published to
+    /// * `name` - Package name
+    ///
+    /// # Returns
+    ///
+    /// The package as a project
+    pub async fn fetch_package(&self, registry: Registry, name: &str) -> Result<Project> {
+        match registry {
+            Registry::CratesIo => self.fetch_crate(name).await,
+            Registry::Npm => self.fetch_npm_package(name).await,
+            Registry::PyPi => self.fetch_pypi_package(name).await,
+        }
+    }
+
+    /// Fetch the packages of all sources, from the cache where it is fresh
+    ///
+    /// A package that can't be fetched is skipped with a warning, so one
+    /// unreachable registry doesn't fail the build.
+    /// Offline clients use expired packages and skip the ones not cached.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Registry sources from the CV data
+    /// * `cache` - The GitHub cache, which also holds the packages
+    ///
+    /// # Returns
+    ///
+    /// The packages as projects
+    pub fn fetch_packages_cached(
+        &self,
+        sources: &Vector<RegistrySource>,
+        cache: &mut GitHubCache,
+    ) -> Vector<Project> {
+        let mut packages = Vector::new();
+        for source in sources.iter() {
+            for name in source.packages.iter() {
+                let key = format!("{}:{}", source.registry.name(), name);
+                if let Some(package) = cache.get_package(&key) {
+                    println!("✅ Using cached package: {}", key);
+                    packages.push_back(package.clone());
+                    continue;
+                }
+
+                if self.offline {
+                    match cache.packages.get(&key) {
+                        Some(entry) => {
+                            println!("📦 Using cached package: {} (offline)", key);
+                            packages.push_back(entry.data.clone());
+                        }
+                        None => warn!("Package {} is not cached, skipping it offline", key),
+                    }
+                    continue;
+                }
+
+                println!("🌐 Fetching package: {}", key);
+                match block_on(self.fetch_package(source.registry, name)) {
+                    Ok(package) => {
+                        cache.cache_package(&key, package.clone());
+                        packages.push_back(package);
+                    }
+                    Err(e) => warn!("Failed to fetch package {}: {:#}", key, e),
+                }
+            }
+        }
+        packages
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<T> {
+        self.http
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to request {}", url))?
+            .error_for_status()
+            .with_context(|| format!("Request to {} failed", url))?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", url))
+    }
+
+    async fn fetch_crate(&self, name: &str) -> Result<Project> {
+        let info = self
+            .get_json::<CrateResponse>(&format!("{}/{}", CRATES_IO_API, name))
+            .await?
+            .krate;
+        Ok(package_project(
+            Registry::CratesIo,
+            &info.name,
+            info.description,
+            info.max_stable_version.unwrap_or(info.newest_version),
+            Some(info.downloads),
+            info.repository.or(info.homepage),
+            info.keywords,
+        ))
+    }
+
+    async fn fetch_npm_package(&self, name: &str) -> Result<Project> {
+        // Scoped packages keep their slash encoded in registry URLs
+        let encoded = name.replace('/', "%2F");
+        let package = self
+            .get_json::<NpmPackage>(&format!("{}/{}/latest", NPM_REGISTRY, encoded))
+            .await?;
+        let downloads = self
+            .get_json::<NpmDownloads>(&format!("{}/{}", NPM_DOWNLOADS_API, name))
+            .await
+            .map(|stats| stats.downloads)
+            .inspect_err(|e| warn!("No download count for npm:{}: {:#}", name, e))
+            .ok();
+        let repository = match package.repository {
+            Some(Value::String(url)) => Some(url),
+            Some(Value::Object(repository)) => repository
+                .get("url")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            _ => None,
+        };
+        Ok(package_project(
+            Registry::Npm,
+            &package.name,
+            package.description,
+            package.version,
+            downloads,
+            repository.or(package.homepage),
+            package.keywords,
+        ))
+    }
+
+    async fn fetch_pypi_package(&self, name: &str) -> Result<Project> {
+        let info = self
+            .get_json::<PyPiResponse>(&format!("{}/{}/json", PYPI_API, name))
+            .await?
+            .info;
+        let downloads = self
+            .get_json::<PyPiStats>(&format!("{}/{}/recent", PYPI_STATS_API, name))
+            .await
+            .map(|stats| stats.data.last_month)
+            .inspect_err(|e| warn!("No download count for pypi:{}: {:#}", name, e))
+            .ok();
+        let repository = info
+            .project_urls
+            .iter()
+            .flat_map(|urls| urls.values())
+            .find(|url| url.contains("github.com") || url.contains("gitlab.com"))
+            .cloned()
+            .or(info.home_page);
+        let keywords = info
+            .keywords
+            .unwrap_or_default()
+            .split([',', ' '])
+            .filter(|keyword| !keyword.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(package_project(
+            Registry::PyPi,
+            &info.name,
+            info.summary,
+            info.version,
+            downloads,
+            repository,
+            keywords,
+        ))
+    }
+}
+
+/// Build the project for a package
+fn package_project(
+    registry: Registry,
+    name: &str,
+    description: Option<String>,
+    version: String,
+    downloads: Option<u64>,
+    repository: Option<String>,
+    keywords: Vec<String>,
+) -> Project {
+    let (language, package_url) = match registry {
+        Registry::CratesIo => ("Rust", format!("https://crates.io/crates/{}", name)),
+        Registry::Npm => (
+            "JavaScript",
+            format!("https://www.npmjs.com/package/{}", name),
+        ),
+        Registry::PyPi => ("Python", format!("https://pypi.org/project/{}/", name)),
+    };
+    let technologies = std::iter::once(language.to_string())
+        .chain(keywords.into_iter().map(|keyword| keyword.to_lowercase()))
+        .fold(Vector::new(), |mut technologies, keyword| {
+            if !technologies
+                .iter()
+                .any(|t: &String| t.eq_ignore_ascii_case(&keyword))
+            {
+                technologies.push_back(keyword);
+            }
+            technologies
+        });
+
+    Project {
+        name: name.to_string(),
+        description: description
+            .filter(|description| !description.trim().is_empty())
+            .unwrap_or_else(|| "No description provided.".to_string()),
+        url: None,
+        repository: repository.map(|url| normalize_repository(&url)),
+        technologies,
+        highlights: Vector::new(),
+        stars: None,
+        owner_username: None,
+        owner_avatar: None,
+        screenshot: None,
+        registry: Some(registry.name().to_string()),
+        package_url: Some(package_url),
+        version: Some(version),
+        downloads,
+        updated_at: None,
+        case_study: None,
+        language: None,
+        language_icon: None,
+        display_name: None,
+    }
+}
+
+/// Merge packages into the projects
+///
+/// A package whose repository matches a project adds its registry, version
+/// and downloads to that project; the other packages are appended.
+///
+/// # Arguments
+///
+/// * `projects` - Projects from GitHub and the CV data
+/// * `packages` - Packages from the registries
+///
+/// # Returns
+///
+/// The merged projects
+pub fn merge_packages(projects: &Vector<Project>, packages: &Vector<Project>) -> Vector<Project> {
+    let same_repository =
+        |project: &Project, package: &Project| match (&project.repository, &package.repository) {
+            (Some(a), Some(b)) => normalize_repository(a).eq_ignore_ascii_case(b),
+            _ => false,
+        };
+
+    let merged = projects
+        .iter()
+        .map(|project| {
+            match packages
+                .iter()
+                .find(|package| same_repository(project, package))
+            {
+                Some(package) => Project {
+                    registry: package.registry.clone(),
+                    package_url: package.package_url.clone(),
+                    version: package.version.clone(),
+                    downloads: package.downloads,
+                    ..project.clone()
+                },
+                None => project.clone(),
+            }
+        })
+        .collect::<Vector<_>>();
+
+    let unmatched = packages
+        .iter()
+        .filter(|package| !projects.iter().any(|p| same_repository(p, package)))
+        .cloned();
+    merged.into_iter().chain(unmatched).collect()
+}
+
+/// Normalize a repository URL from package metadata to a web URL
+///
+/// Handles `git+https://…`, `git://…`, `git@github.com:…`, `github:owner/repo`,
+/// a trailing `.git` and trailing slashes.
+pub fn normalize_repository(url: &str) -> String {
+    let url = url.trim().trim_start_matches("git+");
+    let url = if let Some(path) = url.strip_prefix("github:") {
+        format!("https://github.com/{}", path)
+    } else if let Some(path) = url.strip_prefix("git@github.com:") {
+        format!("https://github.com/{}", path)
+    } else {
+        url.replacen("ssh://git@", "https://", 1)
+            .replacen("git://", "https://", 1)
+            .replacen("http://", "https://", 1)
+    };
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_packages() {
+        assert_eq!(
+            normalize_repository("git+https://github.com/ada/engine.git"),
+            "https://github.com/ada/engine"
+        );
+        assert_eq!(
+            normalize_repository("github:ada/engine"),
+            "https://github.com/ada/engine"
+        );
+
+        let crate_package = package_project(
+            Registry::CratesIo,
+            "engine",
+            Some("Difference engine".to_string()),
+            "1.2.0".to_string(),
+            Some(15_300),
+            Some("https://github.com/Ada/engine/".to_string()),
+            vec!["math".to_string(), "Rust".to_string()],
+        );
+        assert_eq!(
+            crate_package.technologies,
+            im::vector!["Rust".to_string(), "math".to_string()]
+        );
+        let npm_package = package_project(
+            Registry::Npm,
+            "@ada/notes",
+            None,
+            "0.3.1".to_string(),
+            None,
+            None,
+            Vec::new(),
+        );
+        assert_eq!(
+            npm_package.package_url.as_deref(),
+            Some("https://www.npmjs.com/package/@ada/notes")
+        );
+
+        let mut repository_project = crate_package.clone();
+        repository_project.name = "engine-repo".to_string();
+        repository_project.repository = Some("https://github.com/ada/engine".to_string());
+        repository_project.registry = None;
+        repository_project.version = None;
+        repository_project.downloads = None;
+        repository_project.stars = Some(42);
+
+        let merged = merge_packages(
+            &im::vector![repository_project],
+            &im::vector![crate_package, npm_package],
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].name, "engine-repo");
+        assert_eq!(merged[0].stars, Some(42));
+        assert_eq!(merged[0].version.as_deref(), Some("1.2.0"));
+        assert_eq!(merged[0].downloads, Some(15_300));
+        assert_eq!(merged[1].name, "@ada/notes");
+    }
+}
@@ -0,0 +1,339 @@
+//! Password-protected pages on static hosting
+//!
+//! Pages listed in `encrypted_pages` are encrypted after the site is built,
+//! with AES-256-GCM and a key derived from the page's password with
+//! PBKDF2-SHA256. Each page is replaced by a password form carrying the
+//! ciphertext, and `js/page-decrypt.js` decrypts it in the browser with the
+//! Web Crypto API, so no server is involved. The passwords are
+//! [secrets](crate::secrets), never stored in the site or the configuration.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::Aes256Gcm;
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use im::{HashMap, OrdMap};
+use regex::Regex;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::secrets::SecretSource;
+
+/// Path of the decryption script in the output directory
+pub const DECRYPT_SCRIPT: &str = "js/page-decrypt.js";
+
+/// PBKDF2 iterations for deriving the key from a password
+pub const PBKDF2_ITERATIONS: u32 = 600_000;
+
+/// An encrypted page, as embedded in its password form
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptedPayload {
+    /// Base64 PBKDF2 salt
+    pub salt: String,
+    /// Base64 AES-GCM nonce
+    pub iv: String,
+    /// PBKDF2 iterations
+    pub iterations: u32,
+    /// Base64 ciphertext followed by the authentication tag
+    pub ciphertext: String,
+}
+
+/// Encrypt a page with a password
+///
+/// # Arguments
+///
+/// * `html` - The rendered page
+/// * `password` - Password the page is unlocked with
+/// * `iterations` - PBKDF2 iterations
+///
+/// # Returns
+///
+/// The encrypted page with a fresh salt and nonce
+pub fn encrypt(html: &str, password: &str, iterations: u32) -> Result<EncryptedPayload> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(&derive_key(password, &salt, iterations).into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, html.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt page"))?;
+
+    Ok(EncryptedPayload {
+        salt: STANDARD.encode(salt),
+        iv: STANDARD.encode(nonce),
+        iterations,
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+/// The password form replacing an encrypted page
+///
+/// The form keeps the page's Content Security Policy, `<base>` and
+/// stylesheets: the decrypted page is written into the same document, so it
+/// runs under the form's policy.
+///
+/// # Arguments
+///
+/// * `html` - The rendered page, for its head
+/// * `payload` - The encrypted page
+/// * `depth` - Number of directories between the page and the site root
+///
+/// # Returns
+///
+/// The HTML of the password form
+pub fn protected_page(html: &str, payload: &EncryptedPayload, depth: usize) -> Result<String> {
+    let head_tags = head_tag_regex()
+        .find_iter(html)
+        .map(|tag| tag.as_str())
+        .collect::<Vec<_>>();
+    // Relative URLs resolve against <base> if the page has one
+    let root = if head_tags.iter().any(|tag| tag.starts_with("<base")) {
+        String::new()
+    } else {
+        "../".repeat(depth)
+    };
+
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+{}
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>Protected page</title>
+<script src="{root}{DECRYPT_SCRIPT}" defer></script>
+</head>
+<body>
+<main class="page-container">
+<form id="page-unlock" class="page-section protected-page">
+<h2 class="page-section-title">Protected page</h2>
+<p>Enter the password to view this page.</p>
+<input type="password" id="page-password" aria-label="Password" autocomplete="current-password" required autofocus>
+<button type="submit" class="btn btn-primary">Unlock</button>
+<p id="page-unlock-error" role="alert" hidden>Wrong password, please try again.</p>
+</form>
+</main>
+<script type="application/json" id="encrypted-page">{}</script>
+</body>
+</html>
+"#,
+        head_tags.join("\n"),
+        serde_json::to_string(payload)?
+    ))
+}
+
+/// Check that the configured pages can be encrypted without leaking
+///
+/// Blog posts and tag pages are rejected: their text also goes into the RSS
+/// feed, the blog listings, the other tag pages and webhook payloads, none of
+/// which are encrypted.
+///
+/// # Arguments
+///
+/// * `pages` - Page paths with the name of their password secret
+///
+/// # Returns
+///
+/// An error naming the first page that can't be encrypted
+pub fn validate_encrypted_pages(pages: &OrdMap<String, String>) -> Result<()> {
+    for page in pages.keys() {
+        let segments: Vec<&str> = page.trim_start_matches('/').split('/').collect();
+        if segments[..segments.len() - 1].contains(&"blog") {
+            anyhow::bail!(
+                "Encrypted page {page} is a blog page: posts also appear unencrypted in the feed, listings, tag pages and webhooks. Keep private content in a static page instead"
+            );
+        }
+        if !page.ends_with(".html") {
+            anyhow::bail!("Encrypted page {page} is not an HTML page");
+        }
+    }
+    Ok(())
+}
+
+/// Encrypt the configured pages of a built site
+///
+/// Every page must exist, be allowed by [`validate_encrypted_pages`] and
+/// have a password that resolves: a private page is never published
+/// unencrypted.
+///
+/// # Arguments
+///
+/// * `output_dir` - The built site
+/// * `pages` - Page paths with the name of their password secret
+/// * `secrets` - Declared secrets
+///
+/// # Returns
+///
+/// The number of encrypted pages
+pub fn encrypt_pages(
+    output_dir: &Path,
+    pages: &OrdMap<String, String>,
+    secrets: &HashMap<String, SecretSource>,
+) -> Result<usize> {
+    if pages.is_empty() {
+        return Ok(0);
+    }
+    validate_encrypted_pages(pages)?;
+
+    let passwords = pages
+        .iter()
+        .map(|(page, secret_name)| {
+            secrets
+                .get(secret_name)
+                .with_context(|| {
+                    format!("Secret '{secret_name}' for {page} is not declared in config.toml")
+                })?
+                .resolve()?
+                .map(|(password, _)| (page, password))
+                .with_context(|| format!("Secret '{secret_name}' for {page} could not be resolved"))
+        })
+        .collect::<Result<Vec<_>>>();
+    // Don't leave the pages unencrypted for a later deploy
+    let passwords = passwords.inspect_err(|_| {
+        for page in pages.keys() {
+            let _ = fs::remove_file(output_dir.join(page.trim_start_matches('/')));
+        }
+    })?;
+
+    for (page, password) in passwords {
+        let relative = page.trim_start_matches('/');
+        let path = output_dir.join(relative);
+        let html = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read encrypted page {}", path.display()))?;
+        let payload = encrypt(&html, password.expose(), PBKDF2_ITERATIONS)?;
+        let depth = relative.matches('/').count();
+        fs::write(&path, protected_page(&html, &payload, depth)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    let script_path = output_dir.join(DECRYPT_SCRIPT);
+    if let Some(parent) = script_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&script_path, DECRYPT_SCRIPT_SOURCE)
+        .with_context(|| format!("Failed to write {}", script_path.display()))?;
+
+    Ok(pages.len())
+}
+
+/// Derive the AES key from a password
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key);
+    key
+}
+
+/// The head tags an encrypted page shares with its password form
+fn head_tag_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| {
+        Regex::new(
+            r#"(?i)<meta\s+http-equiv="?Content-Security-Policy"?[^>]*>|<base\b[^>]*>|<link\b[^>]*\brel="?stylesheet"?[^>]*>"#,
+        )
+        .expect("valid head tag regex")
+    })
+}
+
+/// Decrypts the page in the browser and remembers the key for the session
+const DECRYPT_SCRIPT_SOURCE: &str = r#"// Decrypts password-protected pages, see page_encryption.rs
+(() => {
+  const payload = JSON.parse(document.getElementById('encrypted-page').textContent);
+  const form = document.getElementById('page-unlock');
+  const input = document.getElementById('page-password');
+  const error = document.getElementById('page-unlock-error');
+  const storageKey = `cv-page-key:${payload.salt}`;
+  const bytes = base64 => Uint8Array.from(atob(base64), c => c.charCodeAt(0));
+
+  async function deriveKey(password) {
+    const material = await crypto.subtle.importKey(
+      'raw', new TextEncoder().encode(password), 'PBKDF2', false, ['deriveKey']);
+    return crypto.subtle.deriveKey(
+      { name: 'PBKDF2', hash: 'SHA-256', salt: bytes(payload.salt), iterations: payload.iterations },
+      material, { name: 'AES-GCM', length: 256 }, true, ['decrypt']);
+  }
+
+  async function unlock(key) {
+    const html = new TextDecoder().decode(await crypto.subtle.decrypt(
+      { name: 'AES-GCM', iv: bytes(payload.iv) }, key, bytes(payload.ciphertext)));
+    const raw = new Uint8Array(await crypto.subtle.exportKey('raw', key));
+    sessionStorage.setItem(storageKey, btoa(String.fromCharCode(...raw)));
+    document.open();
+    document.write(html);
+    document.close();
+  }
+
+  form.addEventListener('submit', async event => {
+    event.preventDefault();
+    error.hidden = true;
+    try {
+      await unlock(await deriveKey(input.value));
+    } catch {
+      error.hidden = false;
+      input.select();
+    }
+  });
+
+  // Unlock without asking again when the password was entered this session
+  const stored = sessionStorage.getItem(storageKey);
+  if (stored) {
+    crypto.subtle.importKey('raw', bytes(stored), 'AES-GCM', true, ['decrypt'])
+      .then(unlock)
+      .catch(() => sessionStorage.removeItem(storageKey));
+  }
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_gcm::Nonce;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let html = "<html><head><base href=\"../\"><link rel=\"stylesheet\" href=\"css/main.min.css\"></head><body>Members only</body></html>";
+        let payload = encrypt(html, "hunter2", 1_000).unwrap();
+
+        let salt = STANDARD.decode(&payload.salt).unwrap();
+        let key = derive_key("hunter2", &salt, payload.iterations);
+        let cipher = Aes256Gcm::new(&key.into());
+        let iv = STANDARD.decode(&payload.iv).unwrap();
+        let ciphertext = STANDARD.decode(&payload.ciphertext).unwrap();
+        let decrypted = cipher
+            .decrypt(Nonce::from_slice(&iv), ciphertext.as_slice())
+            .unwrap();
+        assert_eq!(String::from_utf8(decrypted).unwrap(), html);
+
+        let wrong_key = derive_key("hunter3", &salt, payload.iterations);
+        assert!(Aes256Gcm::new(&wrong_key.into())
+            .decrypt(Nonce::from_slice(&iv), ciphertext.as_slice())
+            .is_err());
+
+        let page = protected_page(html, &payload, 1).unwrap();
+        assert!(!page.contains("Members only"));
+        assert!(page.contains("<base href=\"../\">"));
+        assert!(page.contains("<link rel=\"stylesheet\" href=\"css/main.min.css\">"));
+        assert!(page.contains("<script src=\"js/page-decrypt.js\" defer>"));
+        assert!(page.contains(&payload.ciphertext));
+    }
+
+    #[test]
+    fn test_validate_encrypted_pages() {
+        let pages = |path: &str| OrdMap::unit(path.to_string(), "password".to_string());
+        assert!(validate_encrypted_pages(&pages("members.html")).is_ok());
+        assert!(validate_encrypted_pages(&pages("/de/members.html")).is_ok());
+        // The listing only has excerpts of public posts
+        assert!(validate_encrypted_pages(&pages("blog.html")).is_ok());
+
+        let error = validate_encrypted_pages(&pages("blog/private.html"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("is a blog page"));
+        assert!(validate_encrypted_pages(&pages("/de/blog/tags/private.html")).is_err());
+        assert!(validate_encrypted_pages(&pages("feed.xml")).is_err());
+    }
+}
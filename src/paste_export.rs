@@ -0,0 +1,307 @@
+//! Paste-friendly HTML export
+//!
+//! Renders the CV into a single HTML file meant to be opened in a browser,
+//! selected and pasted into Google Docs, Notion or Word online. Those editors
+//! drop stylesheets, classes and scripts on paste and keep only the markup and
+//! inline styles, so the document uses nothing else: real headings, paragraphs
+//! and lists, with every style written on the element it applies to. Like the
+//! DOCX export, it avoids tables and images, which paste unpredictably.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::cv_data::{Cv, Education, Experience, Project};
+use crate::docx_generator::period_line;
+use crate::html_generator::filters::markdown_inline_str;
+
+/// Font stack available in all the target editors
+const FONT_FAMILY: &str = "Arial, Helvetica, sans-serif";
+
+const TITLE_STYLE: &str = "font-size: 24pt; font-weight: bold; margin: 0 0 4pt 0;";
+const SUBTITLE_STYLE: &str = "font-size: 13pt; font-weight: bold; margin: 0 0 6pt 0;";
+const HEADING_STYLE: &str =
+    "font-size: 15pt; font-weight: bold; margin: 16pt 0 6pt 0; border-bottom: 1px solid #999999;";
+const SUBHEADING_STYLE: &str = "font-size: 11.5pt; font-weight: bold; margin: 10pt 0 2pt 0;";
+const TEXT_STYLE: &str = "font-size: 10.5pt; margin: 0 0 4pt 0;";
+const MUTED_STYLE: &str = "font-size: 10pt; color: #555555; margin: 0 0 4pt 0;";
+const LIST_STYLE: &str = "font-size: 10.5pt; margin: 0 0 4pt 0; padding-left: 18pt;";
+
+/// Generate the paste-friendly HTML file from CV data
+///
+/// # Arguments
+///
+/// * `cv` - The CV data to render
+/// * `output_path` - Path where the document will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_paste_html(cv: &Cv, output_path: &str) -> Result<()> {
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory for {}", output_path))?;
+    }
+
+    fs::write(output_path, build_paste_html(cv))
+        .with_context(|| format!("Failed to write paste-friendly CV: {}", output_path))?;
+
+    println!("📋 Generated paste-friendly CV: {}", output_path);
+    Ok(())
+}
+
+/// Build the paste-friendly HTML document for a CV
+///
+/// # Arguments
+///
+/// * `cv` - The CV data to render
+///
+/// # Returns
+///
+/// A complete HTML document
+pub fn build_paste_html(cv: &Cv) -> String {
+    let info = &cv.personal_info;
+    let mut body = vec![
+        element("h1", TITLE_STYLE, &escape_html(&info.name)),
+        element("p", SUBTITLE_STYLE, &escape_html(&info.title)),
+    ];
+
+    let contact = [
+        Some(info.email.as_str()),
+        info.phone.as_deref(),
+        info.location.as_deref(),
+        info.website.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|value| !value.is_empty())
+    .map(escape_html)
+    .collect::<Vec<_>>();
+    if !contact.is_empty() {
+        body.push(element("p", MUTED_STYLE, &contact.join(" | ")));
+    }
+
+    // Sorted so the document is the same on every build
+    let mut social_links = info.social_links.iter().collect::<Vec<_>>();
+    social_links.sort();
+    if !social_links.is_empty() {
+        let links = social_links
+            .into_iter()
+            .map(|(name, url)| format!("{}: {}", escape_html(name), link(url)))
+            .collect::<Vec<_>>();
+        body.push(element("p", MUTED_STYLE, &links.join(" | ")));
+    }
+
+    if !info.summary.is_empty() {
+        body.push(element("h2", HEADING_STYLE, "Summary"));
+        body.push(element(
+            "p",
+            TEXT_STYLE,
+            &markdown_inline_str(&info.summary),
+        ));
+    }
+
+    section(&mut body, "Experience", cv.experiences.iter(), experience);
+    section(&mut body, "Education", cv.education.iter(), education);
+
+    section(
+        &mut body,
+        "Skills",
+        cv.skill_categories
+            .iter()
+            .filter(|category| !category.skills.is_empty()),
+        |category| {
+            let skills = category.skill_names().into_iter().collect::<Vec<_>>();
+            labelled(&category.name, &skills.join(", "))
+        },
+    );
+
+    section(&mut body, "Projects", cv.projects.iter(), project);
+
+    let mut languages = cv.languages.iter().collect::<Vec<_>>();
+    languages.sort();
+    section(
+        &mut body,
+        "Languages",
+        languages.into_iter(),
+        |(language, level)| labelled(language, level),
+    );
+
+    if !cv.certifications.is_empty() {
+        body.push(element("h2", HEADING_STYLE, "Certifications"));
+        body.push(bullets(cv.certifications.iter()));
+    }
+
+    format!(
+        "<!doctype html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{} - CV</title>\n</head>\n<body style=\"font-family: {}; color: #000000; max-width: 720px; margin: 24px auto;\">\n{}\n</body>\n</html>\n",
+        escape_html(&info.name),
+        FONT_FAMILY,
+        body.join("\n")
+    )
+}
+
+/// Add a titled section, or nothing if it has no items
+fn section<T>(
+    body: &mut Vec<String>,
+    title: &str,
+    items: impl Iterator<Item = T>,
+    render_item: impl Fn(T) -> String,
+) {
+    let mut items = items.peekable();
+    if items.peek().is_none() {
+        return;
+    }
+
+    body.push(element("h2", HEADING_STYLE, title));
+    body.extend(items.map(render_item));
+}
+
+fn experience(experience: &Experience) -> String {
+    let mut parts = vec![
+        element(
+            "h3",
+            SUBHEADING_STYLE,
+            &escape_html(&format!("{}, {}", experience.position, experience.company)),
+        ),
+        element(
+            "p",
+            MUTED_STYLE,
+            &escape_html(&period_line(
+                &experience.period("en"),
+                experience.location.as_deref(),
+            )),
+        ),
+    ];
+    if !experience.description.is_empty() {
+        parts.push(element(
+            "p",
+            TEXT_STYLE,
+            &markdown_inline_str(&experience.description),
+        ));
+    }
+    if !experience.achievements.is_empty() {
+        parts.push(bullets(experience.achievements.iter()));
+    }
+    if !experience.technologies.is_empty() {
+        parts.push(technologies(&experience.technologies));
+    }
+    parts.join("\n")
+}
+
+fn education(education: &Education) -> String {
+    let degree = if education.field.is_empty() {
+        education.degree.clone()
+    } else {
+        format!("{} in {}", education.degree, education.field)
+    };
+
+    let mut parts = vec![
+        element(
+            "h3",
+            SUBHEADING_STYLE,
+            &escape_html(&format!("{}, {}", degree, education.institution)),
+        ),
+        element(
+            "p",
+            MUTED_STYLE,
+            &escape_html(&period_line(
+                &education.period("en"),
+                education.location.as_deref(),
+            )),
+        ),
+    ];
+    if let Some(gpa) = &education.gpa {
+        parts.push(labelled("GPA", gpa));
+    }
+    if !education.achievements.is_empty() {
+        parts.push(bullets(education.achievements.iter()));
+    }
+    parts.join("\n")
+}
+
+fn project(project: &Project) -> String {
+    let name = project.display_name.as_ref().unwrap_or(&project.name);
+    let mut parts = vec![element("h3", SUBHEADING_STYLE, &escape_html(name))];
+    if !project.description.is_empty() {
+        parts.push(element(
+            "p",
+            TEXT_STYLE,
+            &markdown_inline_str(&project.description),
+        ));
+    }
+    if !project.highlights.is_empty() {
+        parts.push(bullets(project.highlights.iter()));
+    }
+    if !project.technologies.is_empty() {
+        parts.push(technologies(&project.technologies));
+    }
+    if let Some(url) = project.url.as_ref().or(project.repository.as_ref()) {
+        parts.push(element("p", MUTED_STYLE, &link(url)));
+    }
+    parts.join("\n")
+}
+
+fn technologies(technologies: &im::Vector<String>) -> String {
+    let technologies = technologies.iter().cloned().collect::<Vec<_>>();
+    labelled("Technologies", &technologies.join(", "))
+}
+
+fn element(tag: &str, style: &str, content: &str) -> String {
+    format!("<{tag} style=\"{style}\">{content}</{tag}>")
+}
+
+fn labelled(label: &str, text: &str) -> String {
+    element(
+        "p",
+        TEXT_STYLE,
+        &format!(
+            "<strong>{}:</strong> {}",
+            escape_html(label),
+            escape_html(text)
+        ),
+    )
+}
+
+fn bullets<'a>(items: impl Iterator<Item = &'a String>) -> String {
+    let items = items
+        .map(|item| format!("<li>{}</li>", markdown_inline_str(item)))
+        .collect::<Vec<_>>();
+    element("ul", LIST_STYLE, &items.join(""))
+}
+
+/// A link showing its URL, so it survives editors that drop hyperlinks
+fn link(url: &str) -> String {
+    let url = escape_html(url);
+    format!("<a href=\"{url}\" style=\"color: #1a5fb4;\">{url}</a>")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paste_html_is_self_contained() {
+        let mut cv = Cv::create_minimal();
+        cv.personal_info.name = "Ada <Lovelace>".to_string();
+        cv.certifications = im::vector!["**Certified** Analyst".to_string()];
+
+        let html = build_paste_html(&cv);
+
+        assert!(html.contains("Ada &lt;Lovelace&gt;"));
+        assert!(html.contains(
+            "<h2 style=\"font-size: 15pt; font-weight: bold; margin: 16pt 0 6pt 0; border-bottom: 1px solid #999999;\">Certifications</h2>"
+        ));
+        assert!(html.contains("<li><strong>Certified</strong> Analyst</li>"));
+        // Nothing the editors would drop on paste
+        for dropped in ["<style", "<link", "<script", "class=", "<table", "<img"] {
+            assert!(!html.contains(dropped), "unexpected {dropped}");
+        }
+    }
+}
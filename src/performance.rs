@@ -47,6 +47,26 @@
 //! profiler.print_summary(); // Shows detailed performance breakdown
 //! ```
 //!
+//! ### Duration Budgets
+//!
+//! Phases can be given a budget, so a slow phase is flagged in the summary and
+//! automation can fail on it:
+//!
+//! ```rust
+//! use cv_generator::performance::BuildProfiler;
+//! use std::collections::HashMap;
+//! use std::time::Duration;
+//!
+//! let budgets = HashMap::from([("github_fetch".to_string(), Duration::from_secs(10))]);
+//! let mut profiler = BuildProfiler::new().with_budgets(budgets);
+//!
+//! profiler.time_operation("github_fetch", || {
+//!     // GitHub API operations
+//! });
+//!
+//! assert!(profiler.overruns().is_empty());
+//! ```
+//!
 //! ### Using Convenience Macros
 //!
 //! ```rust
@@ -58,6 +78,7 @@
 //! ```
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 /// Performance timer for measuring operation durations
@@ -89,9 +110,76 @@ impl PerfTimer {
     }
 }
 
+/// Name of the whole build in the summary, which can also have a budget
+pub const TOTAL_PHASE: &str = "Total Build Time";
+
+/// Phases of a build, by their name in the summary, which budgets can name
+///
+/// Phases that run more than once, such as one per language, add up.
+pub const BUILD_PHASES: [&str; 29] = [
+    "Load GitHub cache",
+    "Load data sources",
+    "Load CV data",
+    "Fetch GitHub projects",
+    "Fetch registry packages",
+    "Fetch GitHub avatar",
+    "Fetch GitHub stats",
+    "Collect build info",
+    "Link translations",
+    "Load content",
+    "Generate HTML files",
+    "Render template fragments",
+    "Copy static assets",
+    "Self-host fonts",
+    "Optimize assets",
+    "Derive Content-Security-Policy",
+    "Expand abbreviations",
+    "Generate service worker",
+    "Add image dimensions",
+    "Fingerprint assets",
+    "Add integrity hashes",
+    "Encrypt pages",
+    "Generate PDF markup",
+    "Compile PDFs",
+    "Generate DOCX CV",
+    "Generate paste-friendly CV",
+    "Check outputs",
+    "Precompress output",
+    "Save GitHub cache",
+];
+
+/// Budgeted phase names that no build has, usually typos
+///
+/// # Arguments
+///
+/// * `phases` - Names of the budgeted phases
+///
+/// # Returns
+///
+/// The names that are neither in [`BUILD_PHASES`] nor [`TOTAL_PHASE`]
+pub fn unknown_phases<S: AsRef<str>>(phases: impl IntoIterator<Item = S>) -> Vec<String> {
+    phases
+        .into_iter()
+        .map(|phase| phase.as_ref().to_string())
+        .filter(|phase| phase != TOTAL_PHASE && !BUILD_PHASES.contains(&phase.as_str()))
+        .collect()
+}
+
+/// A phase that took longer than its budget
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetOverrun {
+    /// Name of the phase
+    pub phase: String,
+    /// The phase's budget
+    pub budget: Duration,
+    /// How long the phase took
+    pub actual: Duration,
+}
+
 /// Global performance tracker for the entire build process
 pub struct BuildProfiler {
     timers: HashMap<String, Duration>,
+    budgets: HashMap<String, Duration>,
     total_start: Instant,
 }
 
@@ -100,22 +188,69 @@ impl BuildProfiler {
     pub fn new() -> Self {
         Self {
             timers: HashMap::new(),
+            budgets: HashMap::new(),
             total_start: Instant::now(),
         }
     }
 
+    /// Set how long phases may take
+    ///
+    /// # Arguments
+    ///
+    /// * `budgets` - Budgets by phase name, as shown in the summary;
+    ///   [`TOTAL_PHASE`] is the whole build
+    pub fn with_budgets(self, budgets: HashMap<String, Duration>) -> Self {
+        Self { budgets, ..self }
+    }
+
+    /// Phases that took longer than their budget so far
+    ///
+    /// # Returns
+    ///
+    /// The overruns, the one furthest over its budget first
+    pub fn overruns(&self) -> Vec<BudgetOverrun> {
+        let total = (TOTAL_PHASE.to_string(), self.total_start.elapsed());
+        let mut overruns = self
+            .timers
+            .iter()
+            .map(|(phase, actual)| (phase.clone(), *actual))
+            .chain(std::iter::once(total))
+            .filter_map(|(phase, actual)| {
+                let budget = *self.budgets.get(&phase)?;
+                (actual > budget).then_some(BudgetOverrun {
+                    phase,
+                    budget,
+                    actual,
+                })
+            })
+            .collect::<Vec<_>>();
+        overruns.sort_by_key(|overrun| std::cmp::Reverse(overrun.actual - overrun.budget));
+        overruns
+    }
+
     /// Time an operation and add it to the profile
+    ///
+    /// Operations timed under the same name add up, like [`Self::record`].
     pub fn time_operation<F, R>(&mut self, name: &str, operation: F) -> R
     where
         F: FnOnce() -> R,
     {
         let timer = PerfTimer::new(name);
         let result = operation();
-        let duration = timer.finish_silent();
-        self.timers.insert(name.to_string(), duration);
+        self.record(name, timer.finish_silent());
         result
     }
 
+    /// Add a phase measured elsewhere, e.g. time spent across many calls
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the phase
+    /// * `duration` - How long it took
+    pub fn record(&mut self, name: &str, duration: Duration) {
+        *self.timers.entry(name.to_string()).or_default() += duration;
+    }
+
     /// Print a summary of all timed operations
     pub fn print_summary(&self) {
         let total_duration = self.total_start.elapsed();
@@ -139,13 +274,22 @@ impl BuildProfiler {
         }
 
         println!("───────────────────────────────");
-        println!(
-            "  {:<25} {:>6}ms",
-            "Total Build Time",
-            total_duration.as_millis()
-        );
+        println!("  {:<25} {:>6}ms", TOTAL_PHASE, total_duration.as_millis());
         println!();
 
+        let overruns = self.overruns();
+        if !overruns.is_empty() {
+            for overrun in &overruns {
+                println!(
+                    "⚠️  {} took {}ms, over its budget of {}ms",
+                    overrun.phase,
+                    overrun.actual.as_millis(),
+                    overrun.budget.as_millis()
+                );
+            }
+            println!();
+        }
+
         // Performance assessment
         if total_duration.as_millis() < 3000 {
             println!("🚀 Excellent performance! Build time under 3 seconds.");
@@ -163,6 +307,49 @@ impl Default for BuildProfiler {
     }
 }
 
+/// Progress of a step working through many items, possibly on several threads
+///
+/// Once the step has run for a second, a line is printed each time another
+/// tenth of the items is done, so long steps show they're moving while quick
+/// ones stay quiet.
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: AtomicUsize,
+    start: Instant,
+}
+
+impl Progress {
+    /// Start tracking a step
+    ///
+    /// # Arguments
+    ///
+    /// * `label` - What the step does, e.g. "Sizing remote images"
+    /// * `total` - Number of items
+    pub fn new(label: &str, total: usize) -> Self {
+        Self {
+            label: label.to_string(),
+            total,
+            done: AtomicUsize::new(0),
+            start: Instant::now(),
+        }
+    }
+
+    /// Count a finished item
+    ///
+    /// # Returns
+    ///
+    /// The number of items done so far
+    pub fn tick(&self) -> usize {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let next_tenth = self.total > 0 && done * 10 / self.total > (done - 1) * 10 / self.total;
+        if next_tenth && self.start.elapsed() >= Duration::from_secs(1) {
+            println!("⏳ {}: {}/{}", self.label, done, self.total);
+        }
+        done
+    }
+}
+
 /// Macro for easily timing code blocks
 #[macro_export]
 macro_rules! time_block {
@@ -215,5 +402,45 @@ mod tests {
         assert_eq!(profiler.timers.len(), 2);
         assert!(profiler.timers["operation 1"].as_millis() >= 10);
         assert!(profiler.timers["operation 2"].as_millis() >= 5);
+
+        profiler.time_operation("operation 2", || {
+            thread::sleep(Duration::from_millis(5));
+        });
+        assert!(profiler.timers["operation 2"].as_millis() >= 10);
+
+        profiler.record("fragments", Duration::from_millis(3));
+        profiler.record("fragments", Duration::from_millis(4));
+        assert_eq!(profiler.timers["fragments"], Duration::from_millis(7));
+    }
+
+    #[test]
+    fn test_budget_overruns() {
+        let budgets = HashMap::from([
+            ("slow".to_string(), Duration::from_millis(1)),
+            ("fast".to_string(), Duration::from_secs(10)),
+            (TOTAL_PHASE.to_string(), Duration::ZERO),
+        ]);
+        let mut profiler = BuildProfiler::new().with_budgets(budgets);
+
+        profiler.time_operation("slow", || thread::sleep(Duration::from_millis(5)));
+        profiler.time_operation("fast", || {});
+        profiler.time_operation("unbudgeted", || thread::sleep(Duration::from_millis(5)));
+
+        let overruns = profiler.overruns();
+        let phases = overruns
+            .iter()
+            .map(|overrun| overrun.phase.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(phases, vec![TOTAL_PHASE, "slow"]);
+        assert_eq!(overruns[1].budget, Duration::from_millis(1));
+        assert!(overruns[1].actual >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_unknown_phases() {
+        assert_eq!(
+            unknown_phases(["Generate HTML files", TOTAL_PHASE, "Generate HTML"]),
+            vec!["Generate HTML"]
+        );
     }
 }
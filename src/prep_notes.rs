@@ -0,0 +1,535 @@
+//! Private interview prep notes and briefs
+//!
+//! Notes are attached to experiences and projects of the CV: the story behind
+//! a migration, the numbers to remember, the questions to expect. They are
+//! stored in the `notes` table of the CV database (`data/cv.db`) and never
+//! read by the build, so they can't end up in the published site.
+//!
+//! `cv brief <variant>` assembles a prep document from the entries of a CV
+//! variant and their notes, as markdown or PDF in the `private/` directory,
+//! optionally headed by a tracked application.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use im::Vector;
+use rusqlite::params;
+use serde::Serialize;
+use std::fmt;
+
+use crate::applications::Application;
+use crate::cv_data::{Cv, Experience, Project};
+use crate::db::Database;
+use crate::site_config::PdfVariant;
+
+/// Directory briefs are written to
+pub const BRIEFS_DIR: &str = "private";
+
+/// Kind of CV entry a note is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteKind {
+    Experience,
+    Project,
+}
+
+impl NoteKind {
+    /// Name of the kind in the CLI and the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NoteKind::Experience => "experience",
+            NoteKind::Project => "project",
+        }
+    }
+}
+
+impl fmt::Display for NoteKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for NoteKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "experience" => Ok(NoteKind::Experience),
+            "project" => Ok(NoteKind::Project),
+            other => anyhow::bail!(
+                "Unknown entry kind '{}' (expected experience or project)",
+                other
+            ),
+        }
+    }
+}
+
+/// A private note on a CV entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Note {
+    pub id: i64,
+    pub kind: NoteKind,
+    /// The entry: "Position at Company" for experiences, the name for projects
+    pub entry: String,
+    pub body: String,
+    /// When the note was written, RFC 3339
+    pub created_at: String,
+}
+
+impl Database {
+    /// Attach a note to a CV entry
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of the entry
+    /// * `entry` - The entry, as returned by [`resolve_entry`]
+    /// * `body` - The note
+    ///
+    /// # Returns
+    ///
+    /// The id of the note
+    pub fn add_note(&self, kind: NoteKind, entry: &str, body: &str) -> Result<i64> {
+        if body.trim().is_empty() {
+            anyhow::bail!("The note is empty");
+        }
+        self.connection
+            .execute(
+                "INSERT INTO notes (kind, entry, body, created_at) VALUES (?1, ?2, ?3, ?4)",
+                params![kind.as_str(), entry, body.trim(), Utc::now().to_rfc3339()],
+            )
+            .context("Failed to store note")?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    /// All notes, oldest first
+    pub fn notes(&self) -> Result<Vector<Note>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT id, kind, entry, body, created_at FROM notes ORDER BY id")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read notes")?;
+        rows.into_iter()
+            .map(|(id, kind, entry, body, created_at)| {
+                Ok(Note {
+                    id,
+                    kind: kind.parse()?,
+                    entry,
+                    body,
+                    created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Delete a note
+    pub fn remove_note(&self, id: i64) -> Result<()> {
+        let removed = self
+            .connection
+            .execute("DELETE FROM notes WHERE id = ?1", params![id])
+            .context("Failed to remove note")?;
+        if removed == 0 {
+            anyhow::bail!("No note #{}", id);
+        }
+        Ok(())
+    }
+}
+
+/// How a note refers to an experience
+pub fn experience_entry(experience: &Experience) -> String {
+    format!("{} at {}", experience.position, experience.company)
+}
+
+/// Find the CV entry a note is meant for
+///
+/// Experiences match on "Position at Company", the company or the position,
+/// projects on their name, all ignoring case.
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+/// * `kind` - Kind of the entry
+/// * `query` - What the user typed
+///
+/// # Returns
+///
+/// The entry as notes refer to it, or an error if no entry or more than one
+/// matches
+pub fn resolve_entry(cv: &Cv, kind: NoteKind, query: &str) -> Result<String> {
+    let query = query.trim().to_lowercase();
+    let candidates = match kind {
+        NoteKind::Experience => cv
+            .experiences
+            .iter()
+            .filter(|experience| {
+                [
+                    experience_entry(experience),
+                    experience.company.clone(),
+                    experience.position.clone(),
+                ]
+                .iter()
+                .any(|name| name.to_lowercase() == query)
+            })
+            .map(experience_entry)
+            .collect::<Vector<_>>(),
+        NoteKind::Project => cv
+            .projects
+            .iter()
+            .filter(|project| project.name.to_lowercase() == query)
+            .map(|project| project.name.clone())
+            .collect(),
+    };
+
+    match candidates.len() {
+        1 => Ok(candidates[0].clone()),
+        0 => anyhow::bail!("No {} in the CV data matches '{}'", kind, query),
+        _ => anyhow::bail!(
+            "'{}' matches more than one {}: {}",
+            query,
+            kind,
+            candidates.iter().cloned().collect::<Vec<_>>().join("; ")
+        ),
+    }
+}
+
+/// A private prep document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Brief {
+    /// Name of the CV owner
+    pub name: String,
+    /// Variant of the CV the brief follows
+    pub variant: PdfVariant,
+    /// The application the brief is for, e.g. "Rust Engineer at Acme"
+    pub application: Option<String>,
+    /// Details of the application: status, variant sent and its notes
+    pub application_details: Vector<String>,
+    pub sections: Vector<BriefSection>,
+}
+
+/// A section of the brief, e.g. the experience
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BriefSection {
+    pub heading: String,
+    pub entries: Vector<BriefEntry>,
+}
+
+/// A CV entry with its notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BriefEntry {
+    pub title: String,
+    /// When, for experiences
+    pub period: Option<String>,
+    pub description: String,
+    /// Achievements or highlights
+    pub points: Vector<String>,
+    pub technologies: Vector<String>,
+    pub notes: Vector<String>,
+}
+
+impl Brief {
+    /// Assemble the brief for a CV variant
+    ///
+    /// The CV variant has the experiences and projects, the portfolio only
+    /// the projects. Notes on entries that are no longer in the CV data are
+    /// listed at the end, so they aren't lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `cv` - The CV data
+    /// * `variant` - The CV variant
+    /// * `notes` - All notes
+    /// * `application` - The application the brief is for, if any
+    ///
+    /// # Returns
+    ///
+    /// The brief
+    pub fn assemble(
+        cv: &Cv,
+        variant: PdfVariant,
+        notes: &Vector<Note>,
+        application: Option<&Application>,
+    ) -> Self {
+        let notes_on = |kind: NoteKind, entry: &str| {
+            notes
+                .iter()
+                .filter(|note| note.kind == kind && note.entry == entry)
+                .map(|note| note.body.clone())
+                .collect::<Vector<_>>()
+        };
+
+        let experience = BriefSection {
+            heading: "Experience".to_string(),
+            entries: cv
+                .experiences
+                .iter()
+                .map(|experience| {
+                    experience_brief(
+                        experience,
+                        notes_on(NoteKind::Experience, &experience_entry(experience)),
+                    )
+                })
+                .collect(),
+        };
+        let projects = BriefSection {
+            heading: "Projects".to_string(),
+            entries: cv
+                .projects
+                .iter()
+                .map(|project| project_brief(project, notes_on(NoteKind::Project, &project.name)))
+                .collect(),
+        };
+
+        let listed = |note: &Note| match note.kind {
+            NoteKind::Experience => {
+                variant == PdfVariant::Cv
+                    && cv
+                        .experiences
+                        .iter()
+                        .any(|experience| experience_entry(experience) == note.entry)
+            }
+            NoteKind::Project => cv.projects.iter().any(|project| project.name == note.entry),
+        };
+        let unattached = notes
+            .iter()
+            .filter(|note| !listed(note))
+            .filter(|note| variant == PdfVariant::Cv || note.kind == NoteKind::Project)
+            .map(|note| BriefEntry {
+                title: format!("{} ({})", note.entry, note.kind),
+                period: None,
+                description: String::new(),
+                points: Vector::new(),
+                technologies: Vector::new(),
+                notes: Vector::unit(note.body.clone()),
+            })
+            .collect::<Vector<_>>();
+
+        let sections = match variant {
+            PdfVariant::Cv => Vector::from(vec![experience, projects]),
+            PdfVariant::Portfolio => Vector::unit(projects),
+        }
+        .into_iter()
+        .chain((!unattached.is_empty()).then(|| BriefSection {
+            heading: "Other notes".to_string(),
+            entries: unattached,
+        }))
+        .filter(|section| !section.entries.is_empty())
+        .collect();
+
+        Brief {
+            name: cv.personal_info.name.clone(),
+            variant,
+            application: application
+                .map(|application| format!("{} at {}", application.role, application.company)),
+            application_details: application
+                .map(|application| {
+                    [
+                        Some(format!("Status: {}", application.status)),
+                        application
+                            .applied_on
+                            .map(|date| format!("Applied: {}", date)),
+                        application
+                            .variant
+                            .as_ref()
+                            .map(|variant| format!("Sent: {}", variant)),
+                        application.notes.clone(),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+                })
+                .unwrap_or_default(),
+            sections,
+        }
+    }
+
+    /// Title of the brief
+    pub fn title(&self) -> String {
+        match self.application {
+            Some(ref application) => format!("Interview brief: {}", application),
+            None => format!("Interview brief: {}", self.name),
+        }
+    }
+
+    /// Render the brief as markdown
+    pub fn to_markdown(&self) -> String {
+        let header = [
+            format!("# {}\n", self.title()),
+            format!(
+                "_Private: {} ({} variant). Not part of the published site._\n",
+                self.name, self.variant
+            ),
+        ]
+        .into_iter()
+        .chain(
+            self.application_details
+                .iter()
+                .map(|detail| format!("- {}", detail)),
+        )
+        .collect::<Vec<_>>()
+        .join("\n");
+
+        self.sections.iter().fold(header, |markdown, section| {
+            section.entries.iter().fold(
+                markdown + &format!("\n\n## {}", section.heading),
+                |markdown, entry| {
+                    let title = match entry.period {
+                        Some(ref period) => format!("{} ({})", entry.title, period),
+                        None => entry.title.clone(),
+                    };
+                    let body = [
+                        Some(entry.description.clone()).filter(|text| !text.is_empty()),
+                        bullets(&entry.points),
+                        (!entry.technologies.is_empty()).then(|| {
+                            format!(
+                                "Technologies: {}",
+                                entry
+                                    .technologies
+                                    .iter()
+                                    .cloned()
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        }),
+                        bullets(&entry.notes).map(|notes| format!("**Notes**\n\n{}", notes)),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>();
+                    markdown + &format!("\n\n### {}\n\n{}", title, body.join("\n\n"))
+                },
+            )
+        }) + "\n"
+    }
+}
+
+fn experience_brief(experience: &Experience, notes: Vector<String>) -> BriefEntry {
+    BriefEntry {
+        title: experience_entry(experience),
+        period: Some(format!(
+            "{}, {}",
+            experience.period("en"),
+            experience.duration()
+        )),
+        description: experience.description.clone(),
+        points: experience.achievements.clone(),
+        technologies: experience.technologies.clone(),
+        notes,
+    }
+}
+
+fn project_brief(project: &Project, notes: Vector<String>) -> BriefEntry {
+    BriefEntry {
+        title: project.name.clone(),
+        period: None,
+        description: project.description.clone(),
+        points: project.highlights.clone(),
+        technologies: project.technologies.clone(),
+        notes,
+    }
+}
+
+fn bullets(items: &Vector<String>) -> Option<String> {
+    (!items.is_empty()).then(|| {
+        items
+            .iter()
+            .map(|item| format!("- {}", item.replace('\n', "\n  ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applications::ApplicationStatus;
+    use crate::cv_date::CvDate;
+
+    fn cv() -> Cv {
+        let mut cv = Cv::create_minimal();
+        cv.experiences = im::vector![Experience {
+            company: "Acme".to_string(),
+            position: "Engineer".to_string(),
+            start_date: CvDate::Month(2020, 1),
+            end_date: Some(CvDate::Month(2023, 3)),
+            location: None,
+            description: "Built the billing system".to_string(),
+            achievements: im::vector!["Cut costs by 30%".to_string()],
+            technologies: im::vector!["Rust".to_string()],
+            tags: Vector::new(),
+        }];
+        cv
+    }
+
+    #[test]
+    fn test_notes() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("cv.db")).unwrap();
+        let cv = cv();
+
+        let entry = resolve_entry(&cv, NoteKind::Experience, "acme").unwrap();
+        assert_eq!(entry, "Engineer at Acme");
+        assert!(resolve_entry(&cv, NoteKind::Project, "acme").is_err());
+
+        let id = db
+            .add_note(NoteKind::Experience, &entry, "Migrated 2M invoices")
+            .unwrap();
+        assert!(db.add_note(NoteKind::Project, "x", " ").is_err());
+        assert_eq!(db.notes().unwrap()[0].body, "Migrated 2M invoices");
+        db.remove_note(id).unwrap();
+        assert!(db.notes().unwrap().is_empty());
+        assert!(db.remove_note(id).is_err());
+    }
+
+    #[test]
+    fn test_brief() {
+        let note = |id, kind, entry: &str, body: &str| Note {
+            id,
+            kind,
+            entry: entry.to_string(),
+            body: body.to_string(),
+            created_at: String::new(),
+        };
+        let notes = im::vector![
+            note(
+                1,
+                NoteKind::Experience,
+                "Engineer at Acme",
+                "Ask about the team"
+            ),
+            note(2, NoteKind::Experience, "Intern at Initech", "Old story"),
+        ];
+        let application = Application {
+            id: 1,
+            company: "Globex".to_string(),
+            role: "Lead".to_string(),
+            variant: Some("cv".to_string()),
+            status: ApplicationStatus::Interviewing,
+            applied_on: None,
+            follow_up: None,
+            notes: None,
+            updated_at: String::new(),
+        };
+
+        let brief = Brief::assemble(&cv(), PdfVariant::Cv, &notes, Some(&application));
+        let markdown = brief.to_markdown();
+        assert!(markdown.starts_with("# Interview brief: Lead at Globex\n"));
+        assert!(markdown.contains("- Status: interviewing"));
+        assert!(markdown.contains(
+            "### Engineer at Acme (Jan 2020 – Mar 2023, 3 yrs 3 mos)\n\nBuilt the billing system\n\n- Cut costs by 30%"
+        ));
+        assert!(markdown.contains("**Notes**\n\n- Ask about the team"));
+        assert!(markdown.contains("## Other notes\n\n### Intern at Initech (experience)"));
+
+        // The portfolio has no experience, and the CV data no projects
+        let brief = Brief::assemble(&cv(), PdfVariant::Portfolio, &notes, None);
+        assert!(brief.sections.is_empty());
+    }
+}
@@ -0,0 +1,270 @@
+//! Uptime probe for the deployed site
+//!
+//! `cv probe <base_url>` fetches the key pages of the deployed site and checks
+//! that each one answers with a success status, fast enough, and carries the
+//! `build-commit` meta tag written by [`BuildInfo`](crate::build_info), so a
+//! stale or broken deploy is caught as well as an unreachable one. For HTTPS
+//! sites it also checks how long the TLS certificate remains valid. The
+//! command exits non-zero when any check fails, so it can drive alerting from
+//! cron or a scheduled workflow.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use im::Vector;
+use regex::Regex;
+use reqwest::tls::TlsInfo;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// What to check on the deployed site
+#[derive(Debug, Clone)]
+pub struct ProbeOptions {
+    /// Page paths relative to the base URL
+    pub pages: Vector<String>,
+    /// Commit the pages must have been built from (a prefix is enough)
+    pub expected_commit: Option<String>,
+    /// Slowest acceptable response
+    pub max_response_time: Duration,
+    /// Fewest days the TLS certificate must remain valid
+    pub min_certificate_days: i64,
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        Self {
+            pages: im::vector!["/".to_string()],
+            expected_commit: None,
+            max_response_time: Duration::from_secs(2),
+            min_certificate_days: 14,
+        }
+    }
+}
+
+/// Result of probing a single page
+#[derive(Debug, Clone)]
+pub struct PageCheck {
+    /// The fetched URL
+    pub url: String,
+    /// HTTP status, if the page answered
+    pub status: Option<u16>,
+    /// Time until the full response was received
+    pub response_time: Duration,
+    /// Commit from the page's `build-commit` meta tag
+    pub build_commit: Option<String>,
+    /// What is wrong with the page; empty if it passed
+    pub problems: Vector<String>,
+}
+
+/// Result of probing the site
+#[derive(Debug, Clone)]
+pub struct ProbeReport {
+    /// Every probed page
+    pub pages: Vector<PageCheck>,
+    /// Expiry of the TLS certificate, for HTTPS sites
+    pub certificate_expiry: Option<DateTime<Utc>>,
+    /// Problems with the site as a whole, such as the certificate
+    pub problems: Vector<String>,
+}
+
+impl ProbeReport {
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty() && self.pages.iter().all(|page| page.problems.is_empty())
+    }
+}
+
+/// Probe the deployed site
+///
+/// # Arguments
+///
+/// * `base_url` - Root URL of the deployed site
+/// * `options` - What to check
+///
+/// # Returns
+///
+/// The report of every check
+pub async fn probe(base_url: &str, options: &ProbeOptions) -> Result<ProbeReport> {
+    let client = reqwest::Client::builder()
+        .user_agent("cv-generator probe")
+        .timeout(options.max_response_time.max(Duration::from_secs(10)))
+        .tls_info(true)
+        .build()?;
+    let base_url = base_url.trim_end_matches('/');
+
+    let mut report = ProbeReport {
+        pages: Vector::new(),
+        certificate_expiry: None,
+        problems: Vector::new(),
+    };
+
+    for page in options.pages.iter() {
+        let url = format!("{}/{}", base_url, page.trim_start_matches('/'));
+        let started = Instant::now();
+        let check = match client.get(&url).send().await {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                if report.certificate_expiry.is_none() {
+                    if let Some(certificate) = response
+                        .extensions()
+                        .get::<TlsInfo>()
+                        .and_then(TlsInfo::peer_certificate)
+                    {
+                        report.certificate_expiry = Some(certificate_expiry(certificate)?);
+                    }
+                }
+                let is_html = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| value.starts_with("text/html"));
+                let body = response.text().await.unwrap_or_default();
+                let response_time = started.elapsed();
+                let build_commit = if is_html { build_commit(&body) } else { None };
+                PageCheck {
+                    problems: check_page(status, response_time, is_html, &build_commit, options),
+                    url,
+                    status: Some(status),
+                    response_time,
+                    build_commit,
+                }
+            }
+            Err(e) => PageCheck {
+                url,
+                status: None,
+                response_time: started.elapsed(),
+                build_commit: None,
+                problems: im::vector![format!("request failed: {:#}", anyhow::Error::from(e))],
+            },
+        };
+        report.pages.push_back(check);
+    }
+
+    if let Some(expiry) = report.certificate_expiry {
+        let days_left = (expiry - Utc::now()).num_days();
+        if days_left < options.min_certificate_days {
+            report.problems.push_back(format!(
+                "TLS certificate expires in {days_left} days ({})",
+                expiry.format("%Y-%m-%d")
+            ));
+        }
+    } else if base_url.starts_with("https://") && report.pages.iter().any(|p| p.status.is_some()) {
+        report
+            .problems
+            .push_back("TLS certificate could not be read".to_string());
+    }
+
+    Ok(report)
+}
+
+/// Check a fetched page against the options
+///
+/// # Arguments
+///
+/// * `status` - HTTP status of the response
+/// * `response_time` - Time until the full response was received
+/// * `is_html` - Whether the response is an HTML page
+/// * `build_commit` - Commit from the page's `build-commit` meta tag
+/// * `options` - What to check
+///
+/// # Returns
+///
+/// What is wrong with the page; empty if it passed
+pub fn check_page(
+    status: u16,
+    response_time: Duration,
+    is_html: bool,
+    build_commit: &Option<String>,
+    options: &ProbeOptions,
+) -> Vector<String> {
+    let mut problems = Vector::new();
+    let success = (200..300).contains(&status);
+    if !success {
+        problems.push_back(format!("HTTP {status}"));
+    }
+    if response_time > options.max_response_time {
+        problems.push_back(format!(
+            "slow response: {} ms (limit {} ms)",
+            response_time.as_millis(),
+            options.max_response_time.as_millis()
+        ));
+    }
+    if success && is_html {
+        match (build_commit, &options.expected_commit) {
+            (None, _) => problems.push_back("no build-commit meta tag".to_string()),
+            (Some(commit), Some(expected)) if !commit.starts_with(expected.as_str()) => problems
+                .push_back(format!(
+                    "built from {}, expected {}",
+                    &commit[..commit.len().min(8)],
+                    expected
+                )),
+            _ => {}
+        }
+    }
+    problems
+}
+
+/// Get the commit from a page's `build-commit` meta tag
+///
+/// # Arguments
+///
+/// * `html` - The page
+///
+/// # Returns
+///
+/// The commit hash, if the page has the tag
+pub fn build_commit(html: &str) -> Option<String> {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX
+        .get_or_init(|| {
+            Regex::new(r#"<meta\s+name="?build-commit"?\s+content="?([0-9a-f]+)"#)
+                .expect("valid build commit regex")
+        })
+        .captures(html)
+        .map(|captures| captures[1].to_string())
+}
+
+/// Read the expiry of a DER-encoded certificate
+fn certificate_expiry(der: &[u8]) -> Result<DateTime<Utc>> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| anyhow::anyhow!("Failed to parse TLS certificate: {e}"))?;
+    DateTime::from_timestamp(certificate.validity().not_after.timestamp(), 0)
+        .context("TLS certificate expiry out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_page() {
+        let html = r#"<head><meta name="build-commit" content="0123456789abcdef"><meta name="build-date" content="2025-01-01"></head>"#;
+        let commit = build_commit(html);
+        assert_eq!(commit.as_deref(), Some("0123456789abcdef"));
+        // Minified pages drop the attribute quotes
+        assert_eq!(
+            build_commit("<meta name=build-commit content=0123456789abcdef>"),
+            commit
+        );
+
+        let options = ProbeOptions {
+            expected_commit: Some("01234567".to_string()),
+            ..ProbeOptions::default()
+        };
+        let fast = Duration::from_millis(150);
+        assert!(check_page(200, fast, true, &commit, &options).is_empty());
+        assert!(check_page(200, fast, false, &None, &options).is_empty());
+
+        let problems = check_page(503, Duration::from_secs(5), true, &None, &options);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0], "HTTP 503");
+
+        let stale = ProbeOptions {
+            expected_commit: Some("fedcba98".to_string()),
+            ..ProbeOptions::default()
+        };
+        assert_eq!(
+            check_page(200, fast, true, &commit, &stale),
+            im::vector!["built from 01234567, expected fedcba98".to_string()]
+        );
+    }
+}
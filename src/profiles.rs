@@ -0,0 +1,214 @@
+//! Tailored CV profiles
+//!
+//! A profile (e.g. `backend` or `management`) is a CV made from the entries of
+//! the CV data that carry its tags. Profiles are declared in
+//! `config/site.json` and built like the site into `profiles/<name>/`:
+//!
+//! ```json
+//! "profiles": {
+//!   "backend": { "tags": ["backend", "rust"], "title": "Backend Engineer" }
+//! }
+//! ```
+//!
+//! [`resolve`] keeps the experiences and skill categories tagged with one of
+//! the profile's tags, plus the untagged ones unless `only_tagged` is set;
+//! entries tagged for other profiles only are left out. Skill categories are
+//! ordered by their most relevant tag, the profile's tags being listed most
+//! relevant first, while experiences stay newest first. The summary is taken
+//! from `personal_info.summaries` for the first of the profile's tags that has
+//! one. A profile may list its own projects with `project_rules`.
+
+use im::Vector;
+use std::collections::BTreeSet;
+
+use crate::cv_data::{Cv, PersonalInfo};
+use crate::site_config::ProfileConfig;
+
+/// Directory of the output the profiles are built into
+pub const PROFILES_DIR: &str = "profiles";
+
+/// How relevant an entry is to a profile, lower is more relevant
+///
+/// # Returns
+///
+/// The position of the first of the profile's tags the entry has, `tags.len()`
+/// for an untagged entry, or None if the entry belongs to other profiles only
+fn relevance(entry_tags: &Vector<String>, profile: &ProfileConfig) -> Option<usize> {
+    if entry_tags.is_empty() {
+        return (!profile.only_tagged).then_some(profile.tags.len());
+    }
+    profile
+        .tags
+        .iter()
+        .position(|tag| entry_tags.iter().any(|entry_tag| entry_tag == tag))
+}
+
+/// Make the CV of a profile
+///
+/// # Arguments
+///
+/// * `cv` - The full CV
+/// * `profile` - The profile
+///
+/// # Returns
+///
+/// The CV with the profile's title, summary, experiences and skill categories
+pub fn resolve(cv: &Cv, profile: &ProfileConfig) -> Cv {
+    let info = &cv.personal_info;
+    let summary = profile
+        .tags
+        .iter()
+        .find_map(|tag| info.summaries.get(tag))
+        .unwrap_or(&info.summary);
+
+    let experiences = cv
+        .experiences
+        .iter()
+        .filter(|experience| relevance(&experience.tags, profile).is_some())
+        .cloned()
+        .collect();
+
+    let mut skill_categories = cv
+        .skill_categories
+        .iter()
+        .filter_map(|category| relevance(&category.tags, profile).map(|rank| (rank, category)))
+        .collect::<Vec<_>>();
+    skill_categories.sort_by_key(|(rank, _)| *rank);
+
+    Cv {
+        personal_info: PersonalInfo {
+            title: profile.title.clone().unwrap_or_else(|| info.title.clone()),
+            summary: summary.clone(),
+            ..info.clone()
+        },
+        experiences,
+        skill_categories: skill_categories
+            .into_iter()
+            .map(|(_, category)| category.clone())
+            .collect(),
+        ..cv.clone()
+    }
+}
+
+/// Tags of a profile that no entry of the CV carries, likely typos
+///
+/// # Arguments
+///
+/// * `cv` - The full CV
+/// * `profile` - The profile
+pub fn unused_tags(cv: &Cv, profile: &ProfileConfig) -> Vector<String> {
+    let used = cv
+        .experiences
+        .iter()
+        .flat_map(|experience| experience.tags.iter())
+        .chain(
+            cv.skill_categories
+                .iter()
+                .flat_map(|category| category.tags.iter()),
+        )
+        .chain(cv.personal_info.summaries.keys())
+        .collect::<BTreeSet<_>>();
+    profile
+        .tags
+        .iter()
+        .filter(|tag| !used.contains(tag))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::{Experience, SkillCategory};
+
+    fn experience(position: &str, tags: &[&str]) -> Experience {
+        Experience {
+            company: "Acme".to_string(),
+            position: position.to_string(),
+            start_date: "2020".parse().unwrap(),
+            end_date: None,
+            location: None,
+            description: String::new(),
+            achievements: Vector::new(),
+            technologies: Vector::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn category(name: &str, tags: &[&str]) -> SkillCategory {
+        SkillCategory {
+            name: name.to_string(),
+            skills: Vector::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn cv() -> Cv {
+        let mut cv = Cv::create_minimal();
+        cv.personal_info.summary = "Engineer and manager".to_string();
+        cv.personal_info
+            .summaries
+            .insert("backend".to_string(), "Backend engineer".to_string());
+        cv.experiences = im::vector![
+            experience("Team Lead", &["management"]),
+            experience("Rust Developer", &["backend", "rust"]),
+            experience("Consultant", &[]),
+        ];
+        cv.skill_categories = im::vector![
+            category("Soft skills", &[]),
+            category("Leadership", &["management"]),
+            category("Databases", &["data"]),
+            category("Languages", &["rust"]),
+        ];
+        cv
+    }
+
+    fn profile(tags: &[&str]) -> ProfileConfig {
+        ProfileConfig {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            title: Some("Backend Engineer".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let resolved = resolve(&cv(), &profile(&["rust", "data"]));
+
+        assert_eq!(resolved.personal_info.title, "Backend Engineer");
+        assert_eq!(resolved.personal_info.summary, "Engineer and manager");
+        let positions = resolved
+            .experiences
+            .iter()
+            .map(|e| e.position.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec!["Rust Developer", "Consultant"]);
+        let categories = resolved
+            .skill_categories
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(categories, vec!["Languages", "Databases", "Soft skills"]);
+    }
+
+    #[test]
+    fn test_resolve_only_tagged() {
+        let profile = ProfileConfig {
+            only_tagged: true,
+            ..profile(&["backend"])
+        };
+        let resolved = resolve(&cv(), &profile);
+
+        assert_eq!(resolved.personal_info.summary, "Backend engineer");
+        assert_eq!(resolved.experiences.len(), 1);
+        assert!(resolved.skill_categories.is_empty());
+    }
+
+    #[test]
+    fn test_unused_tags() {
+        assert_eq!(
+            unused_tags(&cv(), &profile(&["backend", "bakcend"])),
+            im::vector!["bakcend".to_string()]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Selection and ordering of the listed projects
+//!
+//! Projects come from the CV data, GitHub and package registries, so their
+//! number grows with every repository pushed. The `project_rules` section of
+//! the site configuration decides which of them are listed: pinned projects
+//! first, then the others that pass the filters in the configured order, up to
+//! a maximum count.
+//!
+//! Projects written in the CV data have no star count or push date; they stay
+//! ahead of fetched projects when ordering by stars or recent activity.
+
+use im::Vector;
+use std::cmp::Reverse;
+
+use crate::cv_data::Project;
+use crate::featured::matches_name;
+use crate::site_config::{ProjectRules, ProjectSort};
+
+/// Number of projects listed unless `max_count` is set
+pub const DEFAULT_MAX_PROJECTS: usize = 10;
+
+/// Apply the project rules to the merged project list
+///
+/// Excluded projects are dropped, even when pinned. Pinned projects are
+/// listed first regardless of the other filters.
+///
+/// # Arguments
+///
+/// * `projects` - Projects from the CV data, GitHub and package registries
+/// * `rules` - Project rules from the site configuration
+///
+/// # Returns
+///
+/// The projects to list, in order
+pub fn curate_projects(projects: &Vector<Project>, rules: &ProjectRules) -> Vector<Project> {
+    let candidates: Vec<&Project> = projects
+        .iter()
+        .filter(|project| !rules.exclude.iter().any(|name| matches_name(project, name)))
+        .collect();
+
+    let pinned: Vec<&Project> = rules
+        .pin
+        .iter()
+        .filter_map(|name| {
+            candidates
+                .iter()
+                .find(|project| matches_name(project, name))
+                .copied()
+        })
+        .fold(Vec::new(), |mut pinned, project| {
+            if !pinned.iter().any(|p| std::ptr::eq(*p, project)) {
+                pinned.push(project);
+            }
+            pinned
+        });
+
+    let mut others: Vec<&Project> = candidates
+        .into_iter()
+        .filter(|project| !pinned.iter().any(|p| std::ptr::eq(*p, *project)))
+        .filter(|project| passes_filters(project, rules))
+        .collect();
+
+    match rules.sort {
+        ProjectSort::Stars => others.sort_by_key(|project| project.stars.map(Reverse)),
+        ProjectSort::Recent => others.sort_by_key(|project| project.updated_at.map(Reverse)),
+        ProjectSort::Name => others.sort_by_key(|project| {
+            project
+                .display_name
+                .as_deref()
+                .unwrap_or(&project.name)
+                .to_lowercase()
+        }),
+    }
+
+    pinned
+        .into_iter()
+        .chain(others)
+        .take(rules.max_count.unwrap_or(DEFAULT_MAX_PROJECTS))
+        .cloned()
+        .collect()
+}
+
+/// Whether a project passes the star and topic filters
+fn passes_filters(project: &Project, rules: &ProjectRules) -> bool {
+    let has_topic = |topics: &Vector<String>| {
+        project
+            .technologies
+            .iter()
+            .any(|technology| topics.iter().any(|t| t.eq_ignore_ascii_case(technology)))
+    };
+
+    let enough_stars = match (project.stars, rules.min_stars) {
+        (Some(stars), Some(min_stars)) => stars >= min_stars,
+        _ => true,
+    };
+
+    enough_stars
+        && (rules.topics.is_empty() || has_topic(&rules.topics))
+        && !has_topic(&rules.exclude_topics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use im::vector;
+
+    fn project(name: &str, stars: Option<u32>, day: u32, topics: &[&str]) -> Project {
+        Project {
+            name: name.to_string(),
+            description: String::new(),
+            url: None,
+            repository: stars.map(|_| format!("https://github.com/ada/{}", name)),
+            technologies: topics.iter().map(|t| t.to_string()).collect(),
+            highlights: Vector::new(),
+            stars,
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: None,
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: stars.map(|_| Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()),
+            case_study: None,
+            language: None,
+            language_icon: None,
+            display_name: None,
+        }
+    }
+
+    fn names(projects: &Vector<Project>) -> Vec<&str> {
+        projects.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_curate_projects() {
+        let projects = vector![
+            project("popular", Some(50), 1, &["Rust"]),
+            project("fresh", Some(5), 20, &["Rust", "cli"]),
+            project("tiny", Some(1), 25, &["Rust"]),
+            project("dotfiles", Some(30), 10, &["Shell"]),
+            project("thesis", None, 1, &["Python"]),
+            project("toy", Some(0), 5, &["Rust", "experiment"]),
+        ];
+
+        let defaults = curate_projects(&projects, &ProjectRules::default());
+        assert_eq!(
+            names(&defaults),
+            ["thesis", "popular", "dotfiles", "fresh", "tiny", "toy"]
+        );
+
+        let rules = ProjectRules {
+            pin: vector!["toy".to_string(), "popular".to_string()],
+            exclude: vector!["dotfiles".to_string()],
+            min_stars: Some(2),
+            topics: vector!["rust".to_string()],
+            exclude_topics: vector!["cli".to_string()],
+            sort: ProjectSort::Recent,
+            max_count: Some(3),
+        };
+        // Pinned projects skip the filters; thesis isn't written in Rust
+        assert_eq!(
+            names(&curate_projects(&projects, &rules)),
+            ["toy", "popular"]
+        );
+
+        let rules = ProjectRules {
+            sort: ProjectSort::Recent,
+            max_count: Some(3),
+            ..ProjectRules::default()
+        };
+        assert_eq!(
+            names(&curate_projects(&projects, &rules)),
+            ["thesis", "tiny", "fresh"]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! Anonymous reactions to blog posts
+//!
+//! Readers can mark a post as liked, useful or insightful. `cv serve` counts
+//! them on `POST /api/reactions/<slug>` and answers the counts on `GET`, both
+//! kept in `data/reactions.db`, an SQLite database next to the CV data. Each
+//! visitor counts once per post and reaction: only a salted hash of their
+//! address is stored, with the salt in the same database, and requests are
+//! rate limited per address.
+//!
+//! Blog posts ask for the counts when they load and show the buttons only when
+//! that works, so a static deployment without the server has no reactions.
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use anyhow::{Context, Result};
+use im::{HashMap, Vector};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// File name of the reactions database in the data directory
+pub const REACTIONS_FILE: &str = "reactions.db";
+
+/// Path the dev server serves reactions on, followed by `/<slug>`
+pub const REACTIONS_PATH: &str = "/api/reactions";
+
+/// Requests an address may make per [`RATE_LIMIT_WINDOW`]
+pub const RATE_LIMIT: usize = 20;
+
+/// Window the rate limit applies to
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Longest accepted post slug, in characters
+const MAX_SLUG_LENGTH: usize = 200;
+
+/// A reaction to a post
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Like,
+    Useful,
+    Insightful,
+}
+
+impl Reaction {
+    /// Every reaction, in display order
+    pub const ALL: [Reaction; 3] = [Reaction::Like, Reaction::Useful, Reaction::Insightful];
+
+    /// Name of the reaction in requests and the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Reaction::Like => "like",
+            Reaction::Useful => "useful",
+            Reaction::Insightful => "insightful",
+        }
+    }
+}
+
+impl fmt::Display for Reaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Reaction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Reaction::ALL
+            .into_iter()
+            .find(|reaction| reaction.as_str() == s.trim().to_lowercase())
+            .with_context(|| {
+                format!(
+                    "Unknown reaction '{}' (expected like, useful or insightful)",
+                    s
+                )
+            })
+    }
+}
+
+/// A reaction as posted by the blog post page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReactionSubmission {
+    pub reaction: Reaction,
+}
+
+/// Number of each reaction to a post
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionCounts {
+    pub like: u64,
+    pub useful: u64,
+    pub insightful: u64,
+}
+
+/// Reactions stored in SQLite
+#[derive(Debug)]
+pub struct ReactionStore {
+    connection: Connection,
+    salt: String,
+}
+
+impl ReactionStore {
+    /// Open the database, creating it and its salt on first use
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the database file
+    ///
+    /// # Returns
+    ///
+    /// The store, or an error if the database can't be opened
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open reactions database {}", path.display()))?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS reactions (
+                    slug TEXT NOT NULL,
+                    reaction TEXT NOT NULL,
+                    address_hash TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (slug, reaction, address_hash)
+                );
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL
+                );",
+            )
+            .context("Failed to create reactions tables")?;
+
+        let salt = match connection
+            .query_row("SELECT value FROM settings WHERE key = 'salt'", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .optional()?
+        {
+            Some(salt) => salt,
+            None => {
+                let mut bytes = [0u8; 32];
+                OsRng.fill_bytes(&mut bytes);
+                let salt = format!("{:x}", Sha256::digest(bytes));
+                connection.execute(
+                    "INSERT INTO settings (key, value) VALUES ('salt', ?1)",
+                    params![salt],
+                )?;
+                salt
+            }
+        };
+
+        Ok(Self { connection, salt })
+    }
+
+    /// Record a reaction, unless the address already gave it to the post
+    ///
+    /// # Arguments
+    ///
+    /// * `slug` - Slug of the post
+    /// * `reaction` - The reaction
+    /// * `address` - Address of the visitor, hashed before it's stored
+    ///
+    /// # Returns
+    ///
+    /// Whether the reaction was counted
+    pub fn react(&self, slug: &str, reaction: Reaction, address: &str) -> Result<bool> {
+        let inserted = self
+            .connection
+            .execute(
+                "INSERT OR IGNORE INTO reactions (slug, reaction, address_hash, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    slug,
+                    reaction.as_str(),
+                    self.address_hash(address),
+                    chrono::Utc::now().to_rfc3339()
+                ],
+            )
+            .context("Failed to store reaction")?;
+        Ok(inserted > 0)
+    }
+
+    /// Count the reactions to a post
+    pub fn counts(&self, slug: &str) -> Result<ReactionCounts> {
+        let mut statement = self.connection.prepare(
+            "SELECT reaction, COUNT(*) FROM reactions WHERE slug = ?1 GROUP BY reaction",
+        )?;
+        let rows = statement
+            .query_map(params![slug], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to count reactions")?;
+
+        Ok(rows
+            .into_iter()
+            .fold(
+                ReactionCounts::default(),
+                |counts, (reaction, count)| match reaction.parse::<Reaction>() {
+                    Ok(Reaction::Like) => ReactionCounts {
+                        like: count,
+                        ..counts
+                    },
+                    Ok(Reaction::Useful) => ReactionCounts {
+                        useful: count,
+                        ..counts
+                    },
+                    Ok(Reaction::Insightful) => ReactionCounts {
+                        insightful: count,
+                        ..counts
+                    },
+                    Err(_) => counts,
+                },
+            ))
+    }
+
+    fn address_hash(&self, address: &str) -> String {
+        format!(
+            "{:x}",
+            Sha256::new()
+                .chain_update(self.salt.as_bytes())
+                .chain_update(address.as_bytes())
+                .finalize()
+        )
+    }
+}
+
+/// Whether a slug can belong to a post
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LENGTH
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Sliding window rate limit per address
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    limit: usize,
+    window: Duration,
+    requests: HashMap<String, Vector<Instant>>,
+}
+
+impl RateLimiter {
+    /// Allow `limit` requests per address in every `window`
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            requests: HashMap::new(),
+        }
+    }
+
+    /// Count a request, if the address is still within its limit
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - Address of the visitor
+    /// * `now` - Time of the request
+    ///
+    /// # Returns
+    ///
+    /// Whether the request is allowed
+    pub fn allow(&mut self, address: &str, now: Instant) -> bool {
+        let window = self.window;
+        // Forget addresses that have been quiet for a whole window
+        self.requests.retain(|_, times| {
+            times
+                .last()
+                .is_some_and(|last| now.duration_since(*last) < window)
+        });
+
+        let recent = self
+            .requests
+            .get(address)
+            .map(|times| {
+                times
+                    .iter()
+                    .filter(|time| now.duration_since(**time) < window)
+                    .copied()
+                    .collect::<Vector<_>>()
+            })
+            .unwrap_or_default();
+        if recent.len() >= self.limit {
+            return false;
+        }
+
+        let mut recent = recent;
+        recent.push_back(now);
+        self.requests.insert(address.to_string(), recent);
+        true
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RATE_LIMIT, RATE_LIMIT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reactions_are_counted_once_per_address() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(REACTIONS_FILE);
+        let store = ReactionStore::open(&path).unwrap();
+
+        assert!(store.react("hello", Reaction::Like, "192.0.2.1").unwrap());
+        assert!(!store.react("hello", Reaction::Like, "192.0.2.1").unwrap());
+        assert!(store.react("hello", Reaction::Useful, "192.0.2.1").unwrap());
+        assert!(store.react("hello", Reaction::Like, "192.0.2.2").unwrap());
+        assert!(store.react("other", Reaction::Like, "192.0.2.1").unwrap());
+
+        // Reopening keeps the salt, so the same address is still recognized
+        let store = ReactionStore::open(&path).unwrap();
+        assert!(!store.react("hello", Reaction::Useful, "192.0.2.1").unwrap());
+        assert_eq!(
+            store.counts("hello").unwrap(),
+            ReactionCounts {
+                like: 2,
+                useful: 1,
+                insightful: 0
+            }
+        );
+        assert_eq!(store.counts("missing").unwrap(), ReactionCounts::default());
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(60));
+        assert!(limiter.allow("a", start));
+        assert!(limiter.allow("a", start + Duration::from_secs(1)));
+        assert!(!limiter.allow("a", start + Duration::from_secs(2)));
+        assert!(limiter.allow("b", start + Duration::from_secs(2)));
+        assert!(limiter.allow("a", start + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_parse_reaction() {
+        assert_eq!("Useful".parse::<Reaction>().unwrap(), Reaction::Useful);
+        assert!("love".parse::<Reaction>().is_err());
+        assert!(is_valid_slug("rust-2024_notes"));
+        assert!(!is_valid_slug("../cv"));
+        assert!(!is_valid_slug(""));
+    }
+}
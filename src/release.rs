@@ -0,0 +1,301 @@
+//! Versioned CV releases on GitHub
+//!
+//! `cv release` builds every format, packs the website into a `.tar.gz` and
+//! publishes it together with the PDFs and Word documents as a GitHub Release.
+//! Each release gets a date-based tag (`cv-2026.10.17`, then
+//! `cv-2026.10.17.2` for a second release that day), so earlier versions of
+//! the CV stay downloadable.
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use im::Vector;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::dev_server::content_type_for;
+use crate::github::GitHubClient;
+
+/// Extensions of the documents attached to a release besides the site archive
+pub const DOCUMENT_EXTENSIONS: [&str; 2] = ["pdf", "docx"];
+
+/// A file attached to a release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseAsset {
+    /// File name of the asset on GitHub
+    pub name: String,
+    /// Local file
+    pub path: PathBuf,
+}
+
+/// The tag of a content version
+///
+/// # Arguments
+///
+/// * `date` - Day of the release
+/// * `taken` - Whether a tag is already used
+///
+/// # Returns
+///
+/// `cv-YYYY.MM.DD`, with a `.2`, `.3`, ... suffix if that is taken
+pub fn release_tag(date: NaiveDate, taken: impl Fn(&str) -> bool) -> String {
+    let base = format!("cv-{}", date.format("%Y.%m.%d"));
+    std::iter::once(base.clone())
+        .chain((2..).map(|n| format!("{base}.{n}")))
+        .find(|tag| !taken(tag))
+        .expect("an unused tag")
+}
+
+/// Pack a directory into a gzipped tar archive
+///
+/// The files are stored under a top-level directory named `root`.
+///
+/// # Arguments
+///
+/// * `dir` - Directory to pack
+/// * `root` - Name of the directory inside the archive
+/// * `path` - Path of the `.tar.gz` file
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn archive_directory(dir: &Path, root: &str, path: &Path) -> Result<()> {
+    let mut archive = GzEncoder::new(Vec::new(), Compression::best());
+    for file in files(dir)?.iter() {
+        let content =
+            fs::read(dir.join(file)).with_context(|| format!("Failed to read {}", file))?;
+        archive.write_all(&tar_header(&format!("{root}/{file}"), content.len())?)?;
+        archive.write_all(&content)?;
+        // Pad the content to a whole block
+        archive.write_all(&vec![0; (512 - content.len() % 512) % 512])?;
+    }
+    // Two empty blocks end the archive
+    archive.write_all(&[0; 1024])?;
+
+    fs::write(path, archive.finish()?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The documents in the output directory, e.g. `cv.pdf` and `da/cv.docx`
+///
+/// # Arguments
+///
+/// * `output_dir` - The built site
+///
+/// # Returns
+///
+/// The assets, named after their path with `/` replaced by `-`
+pub fn document_assets(output_dir: &Path) -> Result<Vector<ReleaseAsset>> {
+    Ok(files(output_dir)?
+        .iter()
+        .filter(|file| {
+            Path::new(file.as_str())
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| DOCUMENT_EXTENSIONS.contains(&ext))
+        })
+        .map(|file| ReleaseAsset {
+            name: file.replace('/', "-"),
+            path: output_dir.join(file),
+        })
+        .collect())
+}
+
+/// Create a release and upload its assets
+///
+/// # Arguments
+///
+/// * `client` - Authenticated GitHub client
+/// * `repo` - Repository as `owner/name`
+/// * `tag` - Tag of the release
+/// * `target` - Commit the tag is created at
+/// * `assets` - Files to attach
+/// * `draft` - Whether to create an unpublished draft
+///
+/// # Returns
+///
+/// The URL of the release page
+pub async fn publish_release(
+    client: &GitHubClient,
+    repo: &str,
+    tag: &str,
+    target: &str,
+    assets: &Vector<ReleaseAsset>,
+    draft: bool,
+) -> Result<String> {
+    let notes = assets
+        .iter()
+        .map(|asset| format!("- `{}`", asset.name))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let release = client
+        .create_release(
+            repo,
+            tag,
+            target,
+            &format!("CV content version {tag}.\n\n{notes}"),
+            draft,
+        )
+        .await?;
+    println!("🏷️  Created release {}", release.tag_name);
+
+    for asset in assets.iter() {
+        let content = fs::read(&asset.path)
+            .with_context(|| format!("Failed to read {}", asset.path.display()))?;
+        let content_type = if asset.name.ends_with(".tar.gz") {
+            "application/gzip"
+        } else if asset.name.ends_with(".docx") {
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+        } else {
+            content_type_for(&asset.path)
+        };
+        client
+            .upload_release_asset(&release, &asset.name, content_type, content)
+            .await?;
+        println!("  ⬆️  {}", asset.name);
+    }
+
+    Ok(release.html_url)
+}
+
+/// The `owner/name` of a GitHub repository from a git remote URL
+///
+/// # Arguments
+///
+/// * `url` - An HTTPS or SSH remote URL
+///
+/// # Returns
+///
+/// The repository, or None if the remote isn't on github.com
+pub fn repository_from_remote(url: &str) -> Option<String> {
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.split_once("github.com/").map(|(_, path)| path))?;
+    let repo = path.trim_end_matches('/').trim_end_matches(".git");
+    (repo.split('/').count() == 2).then(|| repo.to_string())
+}
+
+/// The files of a directory, relative and with `/` separators, sorted
+fn files(dir: &Path) -> Result<Vector<String>> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) -> Result<()> {
+        for entry in
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.is_dir() {
+                walk(root, &path, files)?;
+            } else {
+                files.push(
+                    path.strip_prefix(root)?
+                        .components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/"),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files)?;
+    files.sort();
+    Ok(files.into_iter().collect())
+}
+
+/// A ustar header for a regular file
+fn tar_header(name: &str, size: usize) -> Result<[u8; 512]> {
+    // Names over 100 bytes are split into a prefix and a name at a `/`
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .with_context(|| format!("Path too long for a tar archive: {}", name))?
+    };
+
+    let mut header = [0u8; 512];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, name.as_bytes());
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{size:011o}\0").as_bytes());
+    field(136, b"00000000000\0");
+    field(156, b"0");
+    field(257, b"ustar\0");
+    field(263, b"00");
+    field(345, prefix.as_bytes());
+
+    // The checksum is computed with its own field set to spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&byte| u32::from(byte)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_release_tag() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 17).unwrap();
+        assert_eq!(release_tag(date, |_| false), "cv-2026.10.17");
+        assert_eq!(
+            release_tag(date, |tag| tag == "cv-2026.10.17"
+                || tag == "cv-2026.10.17.2"),
+            "cv-2026.10.17.3"
+        );
+    }
+
+    #[test]
+    fn test_repository_from_remote() {
+        assert_eq!(
+            repository_from_remote("git@github.com:hakimjonas/cv.git").as_deref(),
+            Some("hakimjonas/cv")
+        );
+        assert_eq!(
+            repository_from_remote("https://github.com/hakimjonas/cv").as_deref(),
+            Some("hakimjonas/cv")
+        );
+        assert_eq!(repository_from_remote("https://gitlab.com/a/b.git"), None);
+    }
+
+    #[test]
+    fn test_archive_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("site/da")).unwrap();
+        fs::write(dir.path().join("site/index.html"), "<h1>Hi</h1>").unwrap();
+        fs::write(dir.path().join("site/da/cv.pdf"), "%PDF").unwrap();
+        let archive_path = dir.path().join("site.tar.gz");
+
+        archive_directory(&dir.path().join("site"), "cv-2026.10.17", &archive_path).unwrap();
+
+        let mut tar = Vec::new();
+        GzDecoder::new(fs::File::open(&archive_path).unwrap())
+            .read_to_end(&mut tar)
+            .unwrap();
+        // Two files of one block each, plus their headers and the end marker
+        assert_eq!(tar.len(), 512 * 6);
+        assert!(tar.starts_with(b"cv-2026.10.17/da/cv.pdf\0"));
+        assert_eq!(&tar[257..262], b"ustar");
+        assert_eq!(&tar[512..516], b"%PDF");
+        assert_eq!(
+            document_assets(&dir.path().join("site")).unwrap(),
+            im::vector![ReleaseAsset {
+                name: "da-cv.pdf".to_string(),
+                path: dir.path().join("site").join("da/cv.pdf"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Request logging for `cv serve`
+//!
+//! Every request the dev server answers is logged through `tracing` with its
+//! method, path, status and latency, and can also be appended to an access log
+//! in the Combined Log Format, which `cv stats ingest` reads back. Secrets
+//! never reach either: query parameters named like a token, password or email
+//! address are replaced with [`REDACTED`], as are values holding an email
+//! address, in the request target and the referrer alike. Request headers
+//! other than the referrer and user agent, `Authorization` among them, are
+//! never logged.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, FixedOffset};
+use im::Vector;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+/// What a redacted query value is replaced with
+pub const REDACTED: &str = "REDACTED";
+
+/// Query parameters whose values are always redacted
+pub const DEFAULT_REDACTED_PARAMS: [&str; 8] = [
+    "access_token",
+    "api_key",
+    "code",
+    "email",
+    "key",
+    "password",
+    "secret",
+    "token",
+];
+
+/// Which query parameters to redact before a request is logged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redaction {
+    /// Lowercased names of the redacted parameters
+    params: Vector<String>,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self::new(std::iter::empty::<&str>())
+    }
+}
+
+impl Redaction {
+    /// Redact the [default parameters](DEFAULT_REDACTED_PARAMS) and `extra`
+    ///
+    /// # Arguments
+    ///
+    /// * `extra` - Further parameter names, matched case-insensitively
+    ///
+    /// # Returns
+    ///
+    /// The redaction rules
+    pub fn new<S: AsRef<str>>(extra: impl IntoIterator<Item = S>) -> Self {
+        let params = DEFAULT_REDACTED_PARAMS
+            .iter()
+            .map(|param| param.to_string())
+            .chain(extra.into_iter().map(|param| param.as_ref().to_lowercase()))
+            .collect();
+        Self { params }
+    }
+
+    /// A request target or URL with its sensitive query values replaced
+    ///
+    /// The fragment is dropped, it never reaches a server anyway.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Request target, or a full URL such as a referrer
+    ///
+    /// # Returns
+    ///
+    /// The target as it may be logged
+    pub fn target(&self, target: &str) -> String {
+        let target = target.split('#').next().unwrap_or_default();
+        let Some((path, query)) = target.split_once('?') else {
+            return target.to_string();
+        };
+        let query = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, value)) if self.is_sensitive(name, value) => {
+                    format!("{name}={REDACTED}")
+                }
+                _ => pair.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{path}?{query}")
+    }
+
+    /// Whether a query value must not be logged
+    fn is_sensitive(&self, name: &str, value: &str) -> bool {
+        let name = name.to_lowercase();
+        self.params.iter().any(|param| *param == name)
+            || value.contains('@')
+            || value.to_lowercase().contains("%40")
+    }
+}
+
+/// One answered request
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestLog {
+    /// Address of the client
+    pub address: String,
+    /// When the request was received
+    pub time: DateTime<FixedOffset>,
+    /// Request method
+    pub method: String,
+    /// Redacted request target
+    pub target: String,
+    /// Protocol of the request line, such as `HTTP/1.1`
+    pub protocol: String,
+    /// Response status code
+    pub status: u16,
+    /// Length of the response body
+    pub bytes: usize,
+    /// Time from reading the request to writing the response
+    pub latency: Duration,
+    /// Redacted `Referer` header, if sent
+    pub referrer: Option<String>,
+    /// `User-Agent` header, if sent
+    pub user_agent: Option<String>,
+}
+
+impl RequestLog {
+    /// The request as a Combined Log Format line
+    ///
+    /// The dev server has no user accounts, so the identity and user fields
+    /// are always `-`.
+    pub fn combined(&self) -> String {
+        format!(
+            r#"{} - - [{}] "{} {} {}" {} {} "{}" "{}""#,
+            self.address,
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            self.target,
+            self.protocol,
+            self.status,
+            self.bytes,
+            quoted(self.referrer.as_deref()),
+            quoted(self.user_agent.as_deref()),
+        )
+    }
+
+    /// Append the request to an access log
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The access log, created with its directory if missing
+    ///
+    /// # Returns
+    ///
+    /// An error if the log can't be written
+    pub fn append(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open access log {}", path.display()))?;
+        // One write per line, so concurrent requests don't interleave
+        file.write_all(format!("{}\n", self.combined()).as_bytes())
+            .with_context(|| format!("Failed to write access log {}", path.display()))
+    }
+}
+
+/// A header value escaped for a quoted log field, or `-` if missing
+fn quoted(value: Option<&str>) -> String {
+    match value {
+        Some(value) if !value.is_empty() => value.replace('\\', "\\\\").replace('"', "\\\""),
+        _ => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_stats::LogEntry;
+
+    #[test]
+    fn test_redact_target() {
+        let redaction = Redaction::new(["Session"]);
+        assert_eq!(redaction.target("/blog/post.html"), "/blog/post.html");
+        assert_eq!(
+            redaction.target("/api/admin?token=s3cret&page=2#top"),
+            "/api/admin?token=REDACTED&page=2"
+        );
+        assert_eq!(
+            redaction.target("/contact?from=ada%40example.com&session=abc&flag"),
+            "/contact?from=REDACTED&session=REDACTED&flag"
+        );
+        assert_eq!(
+            redaction.target("https://example.com/?Email=ada@example.com"),
+            "https://example.com/?Email=REDACTED"
+        );
+    }
+
+    #[test]
+    fn test_combined_line() {
+        let log = RequestLog {
+            address: "203.0.113.7".to_string(),
+            time: DateTime::parse_from_rfc3339("2026-10-17T09:30:00+02:00").unwrap(),
+            method: "GET".to_string(),
+            target: "/blog/post.html".to_string(),
+            protocol: "HTTP/1.1".to_string(),
+            status: 200,
+            bytes: 512,
+            latency: Duration::from_millis(3),
+            referrer: Some("https://search.example/?q=\"cv\"".to_string()),
+            user_agent: None,
+        };
+        let line = log.combined();
+        assert_eq!(
+            line,
+            r#"203.0.113.7 - - [17/Oct/2026:09:30:00 +0200] "GET /blog/post.html HTTP/1.1" 200 512 "https://search.example/?q=\"cv\"" "-""#
+        );
+
+        // cv stats ingest reads the access log back
+        let entry = LogEntry::parse(&line).unwrap();
+        assert_eq!(
+            (entry.path.as_str(), entry.status),
+            ("/blog/post.html", 200)
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("access.log");
+        log.append(&path).unwrap();
+        log.append(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+}
@@ -0,0 +1,307 @@
+//! Secrets management
+//!
+//! Tokens and passwords (GitHub, SMTP, S3, ...) are declared by name in
+//! `config.toml` together with where to find them:
+//!
+//! ```toml
+//! [secrets.github_token]
+//! env = "GITHUB_TOKEN"
+//! command = ["pass", "show", "cv/github"]
+//! required = true
+//!
+//! [secrets.smtp_password]
+//! file = "~/.config/cv/smtp_password"
+//! ```
+//!
+//! Sources are tried in the order environment, file, command, so a CI
+//! environment variable wins over a local password store. Resolved values are
+//! wrapped in [`Secret`], which never prints its value in logs or debug output.
+
+use anyhow::{Context, Result};
+use im::{HashMap, Vector};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Placeholder printed instead of a secret value
+pub const REDACTED: &str = "[redacted]";
+
+/// A secret value that is redacted when formatted or serialized
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap a secret value
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    /// Get the actual value, e.g. to send it in a request header
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the secret is an empty string
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+/// Where a named secret can be read from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecretSource {
+    /// Environment variable holding the secret
+    #[serde(default)]
+    pub env: Option<String>,
+
+    /// File containing the secret (`~/` is expanded)
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
+    /// Command printing the secret, e.g. `["pass", "show", "cv/github"]`
+    #[serde(default)]
+    pub command: Vector<String>,
+
+    /// Whether `cv config check-secrets` fails when the secret can't be resolved
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl SecretSource {
+    /// Resolve the secret from the first source that provides a value
+    ///
+    /// An unset environment variable or missing file falls through to the
+    /// next source; an unreadable file or failing command is an error.
+    ///
+    /// # Returns
+    ///
+    /// The secret and a description of where it came from, or None if no
+    /// source provided a value
+    pub fn resolve(&self) -> Result<Option<(Secret, String)>> {
+        if let Some(ref var) = self.env {
+            if let Some(value) = std::env::var(var).ok().filter(|v| !v.is_empty()) {
+                return Ok(Some((Secret(value), format!("env {}", var))));
+            }
+        }
+
+        if let Some(ref file) = self.file {
+            let path = expand_home(file);
+            match fs::read_to_string(&path) {
+                Ok(content) if !content.trim().is_empty() => {
+                    let value = content.trim().to_string();
+                    return Ok(Some((Secret(value), format!("file {}", file.display()))));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to read secret file {}", path.display()))
+                }
+            }
+        }
+
+        if let Some(program) = self.command.front() {
+            let command_line = self.command.iter().cloned().collect::<Vec<_>>().join(" ");
+            let output = Command::new(program)
+                .args(self.command.iter().skip(1))
+                .output()
+                .with_context(|| format!("Failed to run secret command `{}`", command_line))?;
+
+            if !output.status.success() {
+                anyhow::bail!(
+                    "Secret command `{}` exited with {}: {}",
+                    command_line,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+
+            let value = String::from_utf8(output.stdout)
+                .with_context(|| {
+                    format!("Secret command `{}` printed invalid UTF-8", command_line)
+                })?
+                .trim()
+                .to_string();
+            if !value.is_empty() {
+                return Ok(Some((Secret(value), format!("command `{}`", command_line))));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Result of checking one declared secret
+#[derive(Debug, Clone)]
+pub struct SecretCheck {
+    /// Secret name
+    pub name: String,
+    /// Whether the secret is required
+    pub required: bool,
+    /// Where the secret was resolved from, or why it couldn't be
+    pub outcome: Result<Option<String>, String>,
+}
+
+impl SecretCheck {
+    /// Whether this check should fail the command
+    pub fn is_failure(&self) -> bool {
+        match self.outcome {
+            Ok(Some(_)) => false,
+            Ok(None) => self.required,
+            Err(_) => true,
+        }
+    }
+}
+
+/// Check that declared secrets can be resolved, without keeping their values
+///
+/// # Arguments
+///
+/// * `secrets` - Declared secrets by name
+///
+/// # Returns
+///
+/// One check per secret, sorted by name
+pub fn check_secrets(secrets: &HashMap<String, SecretSource>) -> Vector<SecretCheck> {
+    let mut checks = secrets
+        .iter()
+        .map(|(name, source)| SecretCheck {
+            name: name.clone(),
+            required: source.required,
+            outcome: source
+                .resolve()
+                .map(|resolved| resolved.map(|(_, origin)| origin))
+                .map_err(|e| format!("{:#}", e)),
+        })
+        .collect::<Vector<_>>();
+    checks.sort_by(|a, b| a.name.cmp(&b.name));
+    checks
+}
+
+/// Replace every occurrence of the given secrets in a message
+///
+/// # Arguments
+///
+/// * `text` - Text that may contain secret values, e.g. command output
+/// * `secrets` - Secrets to hide
+///
+/// # Returns
+///
+/// The text with secret values replaced by a placeholder
+pub fn redact<'a>(text: &str, secrets: impl IntoIterator<Item = &'a Secret>) -> String {
+    secrets
+        .into_iter()
+        .filter(|secret| !secret.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.expose(), REDACTED)
+        })
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &std::path::Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_secret_is_redacted() {
+        let secret = Secret::new("ghp_abc123");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some([redacted])");
+        assert_eq!(secret.to_string(), REDACTED);
+        assert_eq!(serde_json::to_string(&secret).unwrap(), "\"[redacted]\"");
+        assert_eq!(secret.expose(), "ghp_abc123");
+
+        assert_eq!(
+            redact("Bearer ghp_abc123 rejected", [&secret]),
+            "Bearer [redacted] rejected"
+        );
+    }
+
+    #[test]
+    fn test_resolve_from_file_and_command() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("token");
+        fs::write(&file, "from-file\n").unwrap();
+
+        let source = SecretSource {
+            env: Some("CV_TEST_SECRET_THAT_IS_NOT_SET".to_string()),
+            file: Some(file),
+            ..Default::default()
+        };
+        let (secret, origin) = source.resolve().unwrap().unwrap();
+        assert_eq!(secret.expose(), "from-file");
+        assert!(origin.starts_with("file "));
+
+        let source = SecretSource {
+            file: Some(dir.path().join("missing")),
+            command: Vector::from(vec!["echo".to_string(), "from-command".to_string()]),
+            ..Default::default()
+        };
+        let (secret, origin) = source.resolve().unwrap().unwrap();
+        assert_eq!(secret.expose(), "from-command");
+        assert_eq!(origin, "command `echo from-command`");
+
+        assert!(SecretSource::default().resolve().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_check_secrets() {
+        let secrets = HashMap::new()
+            .update(
+                "smtp_password".to_string(),
+                SecretSource {
+                    command: Vector::from(vec!["false".to_string()]),
+                    ..Default::default()
+                },
+            )
+            .update(
+                "s3_secret_access_key".to_string(),
+                SecretSource {
+                    required: true,
+                    ..Default::default()
+                },
+            )
+            .update("optional".to_string(), SecretSource::default());
+
+        let checks = check_secrets(&secrets);
+        let names = checks.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["optional", "s3_secret_access_key", "smtp_password"]);
+        assert!(!checks[0].is_failure());
+        assert!(checks[1].is_failure());
+        assert!(checks[2].is_failure());
+    }
+}
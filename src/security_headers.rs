@@ -0,0 +1,239 @@
+//! Security response headers
+//!
+//! One definition of the security headers the site is served with. The
+//! deployment configs (`.htaccess`, `web.config`, Netlify `_headers`) and the
+//! local dev server all render from [`SecurityHeaders`], so the deployment
+//! paths can't drift apart. The deployment configs also carry the site's
+//! Content-Security-Policy, derived from the generated output by
+//! [`crate::content_security_policy::apply_site_csp`].
+
+use im::Vector;
+
+use crate::content_security_policy::ContentSecurityPolicy;
+use crate::site_config::SecurityConfig;
+
+/// HSTS max-age of one year, the minimum accepted by the preload list
+pub const HSTS_MAX_AGE: u64 = 31_536_000;
+
+/// Default Permissions-Policy: the site needs none of these features
+pub const DEFAULT_PERMISSIONS_POLICY: &str =
+    "accelerometer=(), camera=(), geolocation=(), gyroscope=(), microphone=(), payment=(), usb=(), interest-cohort=()";
+
+/// Security headers for every response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecurityHeaders {
+    /// Send Strict-Transport-Security (disabled for plain-HTTP local serving)
+    pub hsts: bool,
+    /// Add `preload` to Strict-Transport-Security
+    pub hsts_preload: bool,
+    /// Value of X-Frame-Options
+    pub frame_options: String,
+    /// Value of Referrer-Policy
+    pub referrer_policy: String,
+    /// Value of Permissions-Policy
+    pub permissions_policy: String,
+    /// Value of Cross-Origin-Opener-Policy
+    pub cross_origin_opener_policy: String,
+    /// Value of Cross-Origin-Embedder-Policy, if cross-origin isolation is enabled
+    pub cross_origin_embedder_policy: Option<String>,
+    /// Value of Content-Security-Policy, if derived from the site
+    pub content_security_policy: Option<String>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        SecurityHeaders {
+            hsts: true,
+            hsts_preload: true,
+            frame_options: "DENY".to_string(),
+            referrer_policy: "strict-origin-when-cross-origin".to_string(),
+            permissions_policy: DEFAULT_PERMISSIONS_POLICY.to_string(),
+            cross_origin_opener_policy: "same-origin".to_string(),
+            cross_origin_embedder_policy: None,
+            content_security_policy: None,
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// Build the headers from the site's security configuration
+    ///
+    /// Cross-origin isolation (COEP `require-corp`) is off unless enabled,
+    /// since it blocks third-party fonts and images that don't send CORP headers.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Optional security section of the site config
+    ///
+    /// # Returns
+    ///
+    /// The security headers to serve
+    pub fn from_config(config: Option<&SecurityConfig>) -> Self {
+        let defaults = SecurityHeaders::default();
+        match config {
+            Some(config) => SecurityHeaders {
+                hsts_preload: config.hsts_preload.unwrap_or(defaults.hsts_preload),
+                permissions_policy: config
+                    .permissions_policy
+                    .clone()
+                    .unwrap_or(defaults.permissions_policy),
+                cross_origin_embedder_policy: config
+                    .cross_origin_isolation
+                    .unwrap_or(false)
+                    .then(|| "require-corp".to_string()),
+                ..defaults
+            },
+            None => defaults,
+        }
+    }
+
+    /// Headers for serving over plain HTTP, e.g. the local dev server
+    ///
+    /// # Returns
+    ///
+    /// The same headers without Strict-Transport-Security
+    pub fn without_hsts(self) -> Self {
+        SecurityHeaders {
+            hsts: false,
+            ..self
+        }
+    }
+
+    /// Headers that also send a site's Content-Security-Policy
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Policy allowing every page of the site
+    ///
+    /// # Returns
+    ///
+    /// The same headers with Content-Security-Policy
+    pub fn with_content_security_policy(self, policy: &ContentSecurityPolicy) -> Self {
+        SecurityHeaders {
+            content_security_policy: Some(policy.to_header_value()),
+            ..self
+        }
+    }
+
+    /// Get the header name/value pairs in a stable order
+    pub fn headers(&self) -> Vector<(&'static str, String)> {
+        let mut headers = Vector::from(vec![
+            ("X-Content-Type-Options", "nosniff".to_string()),
+            ("X-Frame-Options", self.frame_options.clone()),
+            ("X-XSS-Protection", "1; mode=block".to_string()),
+            ("Referrer-Policy", self.referrer_policy.clone()),
+            ("Permissions-Policy", self.permissions_policy.clone()),
+            (
+                "Cross-Origin-Opener-Policy",
+                self.cross_origin_opener_policy.clone(),
+            ),
+        ]);
+
+        if let Some(ref coep) = self.cross_origin_embedder_policy {
+            headers.push_back(("Cross-Origin-Embedder-Policy", coep.clone()));
+        }
+
+        if let Some(ref csp) = self.content_security_policy {
+            headers.push_back(("Content-Security-Policy", csp.clone()));
+        }
+
+        if self.hsts {
+            let preload = if self.hsts_preload { "; preload" } else { "" };
+            headers.push_back((
+                "Strict-Transport-Security",
+                format!("max-age={}; includeSubDomains{}", HSTS_MAX_AGE, preload),
+            ));
+        }
+
+        headers
+    }
+
+    /// Render the headers as Apache `mod_headers` directives
+    pub fn to_htaccess(&self) -> String {
+        let directives = self
+            .headers()
+            .iter()
+            .map(|(name, value)| format!("  Header always set {} \"{}\"\n", name, value))
+            .collect::<String>();
+
+        format!(
+            "# Security headers\n<IfModule mod_headers.c>\n{}</IfModule>\n",
+            directives
+        )
+    }
+
+    /// Render the headers as IIS `customHeaders` entries
+    pub fn to_web_config(&self) -> String {
+        self.headers()
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "        <add name=\"{}\" value=\"{}\" />\n",
+                    name,
+                    value.replace('"', "&quot;")
+                )
+            })
+            .collect()
+    }
+
+    /// Render the headers as a Netlify `_headers` block for all paths
+    pub fn to_netlify(&self) -> String {
+        let lines = self
+            .headers()
+            .iter()
+            .map(|(name, value)| format!("  {}: {}\n", name, value))
+            .collect::<String>();
+
+        format!("# Security headers\n/*\n{}", lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hsts_preload_option() {
+        let headers = SecurityHeaders::default().headers();
+        let hsts = headers
+            .iter()
+            .find(|(name, _)| *name == "Strict-Transport-Security")
+            .unwrap();
+        assert_eq!(hsts.1, "max-age=31536000; includeSubDomains; preload");
+
+        let config = SecurityConfig {
+            hsts_preload: Some(false),
+            cross_origin_isolation: Some(true),
+            permissions_policy: None,
+        };
+        let headers = SecurityHeaders::from_config(Some(&config)).headers();
+        assert!(headers
+            .iter()
+            .any(|(name, value)| *name == "Strict-Transport-Security"
+                && value == "max-age=31536000; includeSubDomains"));
+        assert!(headers.iter().any(
+            |(name, value)| *name == "Cross-Origin-Embedder-Policy" && value == "require-corp"
+        ));
+
+        let local = SecurityHeaders::default().without_hsts().headers();
+        assert!(!local
+            .iter()
+            .any(|(name, _)| *name == "Strict-Transport-Security"));
+    }
+
+    #[test]
+    fn test_renderings_share_headers() {
+        let policy = ContentSecurityPolicy::for_page(&Default::default());
+        let headers = SecurityHeaders::default().with_content_security_policy(&policy);
+        let htaccess = headers.to_htaccess();
+        let web_config = headers.to_web_config();
+        let netlify = headers.to_netlify();
+
+        for (name, value) in headers.headers().iter() {
+            assert!(htaccess.contains(&format!("Header always set {} \"{}\"", name, value)));
+            assert!(web_config.contains(&format!("<add name=\"{}\" value=\"{}\" />", name, value)));
+            assert!(netlify.contains(&format!("  {}: {}", name, value)));
+        }
+        assert!(netlify.contains("  Content-Security-Policy: default-src 'self';"));
+    }
+}
@@ -1,15 +1,26 @@
 use anyhow::{Context, Result};
-use im::Vector;
+use chrono::{NaiveTime, Weekday};
+use im::{OrdMap, Vector};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
-use crate::validation::validate_paper_size;
+use crate::build_info::BuildInfo;
+use crate::css_generator;
+use crate::front_matter::FrontMatterConfig;
+use crate::glossary::Glossary;
+use crate::page_encryption::validate_encrypted_pages;
+use crate::reactions::REACTIONS_PATH;
+use crate::taxonomy::Taxonomy;
+use crate::validation::{validate_language_code, validate_paper_size};
 
 /// Site configuration including menu and navigation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SiteConfig {
     /// Site title (defaults to personal name)
     pub title: Option<String>,
+    /// Public URL of the deployed site (e.g. "https://example.com")
+    #[serde(default)]
+    pub base_url: Option<String>,
     /// Main navigation menu items
     pub menu: Vector<MenuItem>,
     /// Typst PDF generation configuration
@@ -22,6 +33,662 @@ pub struct SiteConfig {
     pub fonts: Option<FontConfig>,
     /// Colorscheme configuration
     pub colorscheme: Option<ColorschemeConfig>,
+    /// Security header configuration
+    #[serde(default)]
+    pub security: Option<SecurityConfig>,
+    /// Runtime theme replacing the built-in templates
+    #[serde(default)]
+    pub theme: Option<ThemeConfig>,
+    /// Languages to generate the site in (e.g. ["en", "da"]); the first is the default
+    #[serde(default)]
+    pub languages: Vector<String>,
+    /// Language currently being generated (set per build, not configured)
+    #[serde(skip)]
+    pub current_language: Option<String>,
+    /// Generate `about-this-site.html` with the build metadata
+    #[serde(default)]
+    pub about_this_site: bool,
+    /// Generate `glossary.html` listing the terms of the glossary
+    #[serde(default)]
+    pub glossary_page: bool,
+    /// Featured posts and pinned projects; turns `index.html` into a home page
+    #[serde(default)]
+    pub featured: Option<FeaturedConfig>,
+    /// CSS and JS minification settings
+    #[serde(default)]
+    pub assets: Option<AssetConfig>,
+    /// Precompression of the generated site
+    #[serde(default)]
+    pub compression: Option<CompressionConfig>,
+    /// "Book a call" section on the CV page
+    #[serde(default)]
+    pub booking: Option<BookingConfig>,
+    /// Career timeline on the CV page and in `timeline.json`
+    #[serde(default)]
+    pub timeline: Option<TimelineConfig>,
+    /// Privacy-friendly analytics script on every page
+    #[serde(default)]
+    pub analytics: Option<AnalyticsConfig>,
+    /// Web app manifest and service worker
+    #[serde(default)]
+    pub pwa: Option<PwaConfig>,
+    /// Hosting platforms to write configuration files for
+    #[serde(default)]
+    pub deployment: Option<DeploymentConfig>,
+    /// PDF documents to generate alongside the CV
+    #[serde(default)]
+    pub pdf: Option<PdfConfig>,
+    /// Named targets for `cv deploy`, e.g. "production"
+    #[serde(default)]
+    pub deploy: OrdMap<String, DeployBackend>,
+    /// Pages to encrypt, by path in the output directory (e.g.
+    /// "members.html"), with the name of the secret holding the password
+    #[serde(default)]
+    pub encrypted_pages: OrdMap<String, String>,
+    /// Which projects are listed and in what order
+    #[serde(default)]
+    pub project_rules: Option<ProjectRules>,
+    /// Tailored CVs built to `profiles/<name>/`, see [`crate::profiles`]
+    #[serde(default)]
+    pub profiles: OrdMap<String, ProfileConfig>,
+    /// Review reminders for posts and pages that haven't been updated in a while
+    #[serde(default)]
+    pub staleness: Option<StalenessConfig>,
+    /// Reaction buttons on blog posts, served by `cv serve`
+    #[serde(default)]
+    pub reactions: Option<ReactionsConfig>,
+    /// Custom front matter fields of posts, pages and case studies
+    #[serde(default)]
+    pub front_matter: FrontMatterConfig,
+    /// Metadata about the current build (set per build, not configured)
+    #[serde(default, skip_deserializing)]
+    pub build_info: Option<BuildInfo>,
+    /// Technologies from `data/technologies.toml` (set per build, not configured)
+    #[serde(default, skip_deserializing)]
+    pub taxonomy: Taxonomy,
+    /// Terms from `data/glossary.json` (set per build, not configured)
+    #[serde(default, skip_deserializing)]
+    pub glossary: Glossary,
+    /// Whether the build must not access the network (set per build, not configured)
+    #[serde(default, skip_deserializing)]
+    pub offline: bool,
+}
+
+/// Featured content on the home page
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeaturedConfig {
+    /// Number of featured posts to show (default: 3)
+    pub posts: Option<usize>,
+    /// Number of pinned projects to show (default: 3)
+    pub projects: Option<usize>,
+    /// Projects to pin by name, in display order (default: the most starred)
+    #[serde(default)]
+    pub pinned_projects: Vector<String>,
+    /// Order of the featured posts
+    #[serde(default)]
+    pub post_order: PostOrder,
+    /// Order of the pinned projects
+    #[serde(default)]
+    pub project_order: ProjectOrder,
+}
+
+/// Order of featured posts; pinned posts always come first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostOrder {
+    /// Newest first
+    #[default]
+    Newest,
+    /// Oldest first
+    Oldest,
+}
+
+/// Order of pinned projects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectOrder {
+    /// The order of `pinned_projects`
+    #[default]
+    Pinned,
+    /// Most stars first
+    Stars,
+    /// Alphabetical
+    Name,
+}
+
+/// Which projects are listed, see [`crate::project_curation`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectRules {
+    /// Projects listed first, by name or repository name, in this order
+    #[serde(default)]
+    pub pin: Vector<String>,
+    /// Projects never listed, by name or repository name
+    #[serde(default)]
+    pub exclude: Vector<String>,
+    /// Minimum stars of projects with a star count
+    pub min_stars: Option<u32>,
+    /// Only list projects with at least one of these topics or languages
+    #[serde(default)]
+    pub topics: Vector<String>,
+    /// Never list projects with any of these topics or languages
+    #[serde(default)]
+    pub exclude_topics: Vector<String>,
+    /// Order of the projects after the pinned ones
+    #[serde(default)]
+    pub sort: ProjectSort,
+    /// Maximum number of projects listed (default: 10)
+    pub max_count: Option<usize>,
+}
+
+/// A tailored CV, selected from the CV data by tags
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    /// Tags of the entries that belong to the profile, most relevant first
+    pub tags: Vector<String>,
+    /// Job title shown instead of the one in the CV data
+    pub title: Option<String>,
+    /// Leave out experiences and skill categories without tags too
+    #[serde(default)]
+    pub only_tagged: bool,
+    /// Which projects are listed (default: the ones the site lists)
+    pub project_rules: Option<ProjectRules>,
+}
+
+/// Order of the listed projects
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectSort {
+    /// Most stars first
+    #[default]
+    Stars,
+    /// Most recently pushed first
+    Recent,
+    /// Alphabetical
+    Name,
+}
+
+/// CSS and JS minification settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetConfig {
+    /// Browserslist queries for the browsers the CSS must support (default: "defaults")
+    #[serde(default)]
+    pub browserslist: Vector<String>,
+    /// Whether to write source maps next to the minified files (default: false)
+    #[serde(default)]
+    pub source_maps: Option<bool>,
+    /// Whether to add content hashes to CSS, JS and font file names (default: true)
+    #[serde(default)]
+    pub fingerprint: Option<bool>,
+    /// Whether fingerprinted copies, and images, go to the content-addressed
+    /// store under `assets/` (default: false)
+    #[serde(default)]
+    pub content_addressed: Option<bool>,
+    /// Whether to add Subresource Integrity hashes to scripts and stylesheets
+    /// (default: true)
+    #[serde(default)]
+    pub integrity: Option<bool>,
+}
+
+impl AssetConfig {
+    /// Whether source maps should be written
+    pub fn source_maps(&self) -> bool {
+        self.source_maps.unwrap_or(false)
+    }
+
+    /// Whether assets should be fingerprinted
+    pub fn fingerprint(&self) -> bool {
+        self.fingerprint.unwrap_or(true)
+    }
+
+    /// Whether fingerprinted assets should go to the content-addressed store
+    pub fn content_addressed(&self) -> bool {
+        self.content_addressed.unwrap_or(false)
+    }
+
+    /// Whether scripts and stylesheets should get integrity hashes
+    pub fn integrity(&self) -> bool {
+        self.integrity.unwrap_or(true)
+    }
+}
+
+/// Precompression of the generated site into `.br` and `.gz` files
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    /// Whether to write precompressed files (default: only in release builds)
+    pub enabled: Option<bool>,
+    /// gzip level, 0-9 (default: 9)
+    pub gzip_level: Option<u32>,
+    /// Brotli quality, 0-11 (default: 11)
+    pub brotli_quality: Option<u32>,
+}
+
+impl CompressionConfig {
+    /// Whether precompressed files should be written
+    ///
+    /// Like minification, precompression is skipped in debug builds unless
+    /// enabled explicitly, to keep `cv serve --dev` rebuilds fast.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(!cfg!(debug_assertions))
+    }
+
+    /// gzip level, capped at the maximum of 9
+    pub fn gzip_level(&self) -> u32 {
+        self.gzip_level.unwrap_or(9).min(9)
+    }
+
+    /// Brotli quality, capped at the maximum of 11
+    pub fn brotli_quality(&self) -> u32 {
+        self.brotli_quality.unwrap_or(11).min(11)
+    }
+}
+
+/// "Book a call" section, embedding a scheduling provider and/or linking an availability calendar
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BookingConfig {
+    /// Section heading (default: "Book a call")
+    pub title: Option<String>,
+    /// Text shown above the scheduler
+    pub description: Option<String>,
+    /// Scheduling provider (default: detected from `url`)
+    pub provider: Option<BookingProvider>,
+    /// Public scheduling page, e.g. "https://cal.com/you/30min"
+    pub url: Option<String>,
+    /// IANA time zone of the availability (default: "UTC")
+    pub timezone: Option<String>,
+    /// Weekly availability, published as `availability.ics`
+    #[serde(default)]
+    pub availability: Vector<AvailabilitySlot>,
+}
+
+/// Career timeline merging experiences, education, certifications and projects
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TimelineConfig {
+    /// Section heading (default: "Timeline")
+    pub title: Option<String>,
+    /// Projects on the timeline by name (default: those with a case study)
+    #[serde(default)]
+    pub projects: Vector<String>,
+}
+
+impl TimelineConfig {
+    /// Section heading
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Timeline")
+    }
+}
+
+/// Privacy-friendly analytics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Analytics service
+    pub provider: AnalyticsProvider,
+    /// Domain of the site for Plausible and umami (e.g. "example.com"), or of
+    /// the GoatCounter site (e.g. "example.goatcounter.com")
+    pub domain: String,
+    /// Website ID, required by umami
+    #[serde(default)]
+    pub website_id: Option<String>,
+    /// Script of a self-hosted instance (default: the provider's hosted script)
+    #[serde(default)]
+    pub script_url: Option<String>,
+    /// Environments the script is included in (default: ["production"])
+    #[serde(default = "default_analytics_environments")]
+    pub environments: Vector<String>,
+}
+
+fn default_analytics_environments() -> Vector<String> {
+    im::vector!["production".to_string()]
+}
+
+/// Analytics services that don't need a cookie banner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsProvider {
+    /// Plausible, hosted or self-hosted
+    Plausible,
+    /// GoatCounter, hosted or self-hosted
+    GoatCounter,
+    /// umami, cloud or self-hosted
+    Umami,
+}
+
+impl AnalyticsConfig {
+    /// URL of the tracking script
+    ///
+    /// The page's Content-Security-Policy picks up the script's origin and the
+    /// origin it reports to from the script tag, so there's nothing to allow
+    /// by hand.
+    pub fn script_url(&self) -> &str {
+        self.script_url.as_deref().unwrap_or(match self.provider {
+            AnalyticsProvider::Plausible => "https://plausible.io/js/script.js",
+            AnalyticsProvider::GoatCounter => "https://gc.zgo.at/count.js",
+            AnalyticsProvider::Umami => "https://cloud.umami.is/script.js",
+        })
+    }
+
+    /// The `data-` attributes of the script tag, as name and value
+    pub fn script_attributes(&self) -> Vector<(&'static str, String)> {
+        match self.provider {
+            AnalyticsProvider::Plausible => im::vector![("data-domain", self.domain.clone())],
+            AnalyticsProvider::GoatCounter => {
+                im::vector![("data-goatcounter", format!("https://{}/count", self.domain))]
+            }
+            AnalyticsProvider::Umami => im::vector![
+                (
+                    "data-website-id",
+                    self.website_id.clone().unwrap_or_default()
+                ),
+                ("data-domains", self.domain.clone()),
+            ],
+        }
+    }
+
+    /// Whether the script is included in builds for `environment`
+    pub fn is_enabled_in(&self, environment: &str) -> bool {
+        self.environments.iter().any(|e| e == environment)
+    }
+
+    /// Check the domain, the script URL and the umami website ID
+    pub fn validate(&self) -> Result<()> {
+        if self.domain.is_empty() || self.domain.contains(['/', ':', ' ']) {
+            anyhow::bail!(
+                "Analytics domain must be a bare domain like example.com: '{}'",
+                self.domain
+            );
+        }
+        if let Some(url) = &self.script_url {
+            if !url.starts_with("https://") {
+                anyhow::bail!("Analytics script URL must be an https URL: {}", url);
+            }
+        }
+        if self.provider == AnalyticsProvider::Umami && self.website_id.is_none() {
+            anyhow::bail!("umami analytics needs a website_id");
+        }
+        Ok(())
+    }
+}
+
+/// Scheduling services that can be embedded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookingProvider {
+    /// Cal.com, hosted or self-hosted
+    Cal,
+    /// Calendly
+    Calendly,
+}
+
+/// A weekly recurring time slot, e.g. Mondays from 09:00 to 12:00
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AvailabilitySlot {
+    /// Day of the week, e.g. "monday"
+    pub day: Weekday,
+    /// Start time, e.g. "09:00"
+    pub start: NaiveTime,
+    /// End time, e.g. "12:00"
+    pub end: NaiveTime,
+}
+
+impl BookingConfig {
+    /// Section heading
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Book a call")
+    }
+
+    /// Time zone of the availability
+    pub fn timezone(&self) -> &str {
+        self.timezone.as_deref().unwrap_or("UTC")
+    }
+
+    /// The scheduling provider, configured or detected from the URL
+    pub fn provider(&self) -> Option<BookingProvider> {
+        let url = self.url.as_deref()?;
+        Some(self.provider.unwrap_or(if url.contains("calendly.com") {
+            BookingProvider::Calendly
+        } else {
+            BookingProvider::Cal
+        }))
+    }
+
+    /// URL of the provider's inline scheduler, for the booking iframe
+    ///
+    /// The page's Content-Security-Policy picks up the provider's origin from
+    /// the iframe, so there's nothing to allow by hand.
+    pub fn embed_url(&self) -> Option<String> {
+        let url = self.url.as_deref()?;
+        let (page, query) = match url.split_once('?') {
+            Some((page, query)) => (page.trim_end_matches('/'), format!("{query}&")),
+            None => (url.trim_end_matches('/'), String::new()),
+        };
+        Some(match self.provider()? {
+            BookingProvider::Cal => format!("{page}/embed?{query}layout=month_view"),
+            BookingProvider::Calendly => {
+                format!("{page}?{query}embed_type=Inline&hide_gdpr_banner=1")
+            }
+        })
+    }
+
+    /// Check the scheduling URL and the availability slots
+    pub fn validate(&self) -> Result<()> {
+        if let Some(url) = &self.url {
+            if !url.starts_with("https://") {
+                anyhow::bail!("Booking URL must be an https URL: {}", url);
+            }
+        }
+        if let Some(slot) = self.availability.iter().find(|slot| slot.end <= slot.start) {
+            anyhow::bail!(
+                "Availability on {} ends before it starts ({}-{})",
+                slot.day,
+                slot.start.format("%H:%M"),
+                slot.end.format("%H:%M")
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Web app manifest and service worker settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PwaConfig {
+    /// App name (default: the site title)
+    pub name: Option<String>,
+    /// Name shown under the home screen icon (default: the CV owner's name)
+    pub short_name: Option<String>,
+    /// App description
+    pub description: Option<String>,
+    /// Theme color (default: the colorscheme's primary color)
+    pub theme_color: Option<String>,
+    /// Splash screen background color (default: the colorscheme's background)
+    pub background_color: Option<String>,
+    /// Theme color in dark mode (default: the dark colorscheme's primary color)
+    pub dark_theme_color: Option<String>,
+    /// Splash screen background color in dark mode (default: the dark
+    /// colorscheme's background)
+    pub dark_background_color: Option<String>,
+    /// App icons (default: `img/icon-192.png` and `img/icon-512.png`)
+    #[serde(default)]
+    pub icons: Vector<PwaIcon>,
+    /// How the service worker answers requests
+    #[serde(default)]
+    pub cache_strategy: CacheStrategy,
+    /// Extra paths to cache on install, in addition to the main pages and assets
+    #[serde(default)]
+    pub precache: Vector<String>,
+}
+
+impl PwaConfig {
+    /// The configured icons, or the default 192 and 512 pixel icons
+    pub fn icons(&self) -> Vector<PwaIcon> {
+        if !self.icons.is_empty() {
+            return self.icons.clone();
+        }
+        [192, 512]
+            .into_iter()
+            .map(|size| PwaIcon {
+                src: format!("img/icon-{size}.png"),
+                sizes: format!("{size}x{size}"),
+                mime_type: None,
+                purpose: Some("any maskable".to_string()),
+            })
+            .collect()
+    }
+}
+
+/// An icon of the web app manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PwaIcon {
+    /// Path of the image, relative to the site root
+    pub src: String,
+    /// Sizes, e.g. "192x192"
+    pub sizes: String,
+    /// MIME type (default: guessed from the extension)
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Purpose, e.g. "any maskable"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+}
+
+/// Caching strategy of the service worker
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheStrategy {
+    /// Answer from the cache, falling back to the network
+    #[default]
+    CacheFirst,
+    /// Answer from the network and update the cache, falling back to the cache offline
+    NetworkFirst,
+}
+
+/// Hosting platforms to write configuration files for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeploymentConfig {
+    /// Platforms the site is deployed to (default: apache, iis and netlify)
+    #[serde(default)]
+    pub targets: Vector<DeploymentTarget>,
+    /// Custom domain for the GitHub Pages `CNAME` file (default: the host of `base_url`)
+    pub custom_domain: Option<String>,
+}
+
+/// A hosting platform with its own configuration files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DeploymentTarget {
+    /// `.htaccess`
+    Apache,
+    /// `web.config`
+    Iis,
+    /// `_headers` and `_redirects`
+    Netlify,
+    /// `CNAME`, `.nojekyll` and `404.html`
+    GithubPages,
+    /// `_headers`, `_redirects`, `_routes.json` and `404.html`
+    CloudflarePages,
+}
+
+impl DeploymentConfig {
+    /// Targets written when none are configured
+    pub const DEFAULT_TARGETS: [DeploymentTarget; 3] = [
+        DeploymentTarget::Apache,
+        DeploymentTarget::Iis,
+        DeploymentTarget::Netlify,
+    ];
+}
+
+/// Where `cv deploy` uploads the output directory to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "kebab-case")]
+pub enum DeployBackend {
+    /// `rsync` over SSH
+    Rsync {
+        /// Remote directory, e.g. "deploy@example.com:/var/www/cv"
+        destination: String,
+        /// SSH port (default: 22)
+        port: Option<u16>,
+        /// Whether to delete remote files missing from the output (default: true)
+        delete: Option<bool>,
+    },
+    /// An S3-compatible bucket (AWS, Cloudflare R2, MinIO, ...)
+    S3 {
+        /// Bucket name
+        bucket: String,
+        /// Bucket region (default: "us-east-1")
+        region: Option<String>,
+        /// Endpoint URL (default: the AWS endpoint of the region)
+        endpoint: Option<String>,
+        /// Key prefix the site is uploaded under
+        prefix: Option<String>,
+        /// Secret holding the access key id (default: "s3_access_key_id")
+        access_key_secret: Option<String>,
+        /// Secret holding the secret access key (default: "s3_secret_access_key")
+        secret_key_secret: Option<String>,
+    },
+    /// A force-pushed branch served by GitHub Pages
+    GithubPages {
+        /// Git remote to push to (default: "origin")
+        remote: Option<String>,
+        /// Branch to replace (default: "gh-pages")
+        branch: Option<String>,
+    },
+}
+
+/// PDF documents to generate
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PdfConfig {
+    /// Documents built for the `pdf` output format (default: cv)
+    #[serde(default)]
+    pub variants: Vector<PdfVariant>,
+}
+
+/// A PDF document built from the CV data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PdfVariant {
+    /// The CV itself, `cv.pdf`
+    Cv,
+    /// One page per project with screenshots and footnoted links, `portfolio.pdf`
+    Portfolio,
+}
+
+impl std::fmt::Display for PdfVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PdfVariant::Cv => "cv",
+            PdfVariant::Portfolio => "portfolio",
+        })
+    }
+}
+
+impl std::str::FromStr for PdfVariant {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cv" => Ok(PdfVariant::Cv),
+            "portfolio" => Ok(PdfVariant::Portfolio),
+            other => anyhow::bail!("Unknown variant '{}' (expected cv or portfolio)", other),
+        }
+    }
+}
+
+/// Theme selection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeConfig {
+    /// Theme name, the directory name under the themes directory
+    pub name: String,
+    /// Directory containing installed themes (default: "themes")
+    pub directory: Option<String>,
+}
+
+/// Security header configuration for deployment configs and the dev server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// Whether to add `preload` to Strict-Transport-Security (default: true)
+    pub hsts_preload: Option<bool>,
+    /// Whether to send COEP `require-corp` for cross-origin isolation (default: false)
+    pub cross_origin_isolation: Option<bool>,
+    /// Custom Permissions-Policy value
+    pub permissions_policy: Option<String>,
 }
 
 /// Configuration for blog posts
@@ -33,6 +700,137 @@ pub struct BlogConfig {
     pub posts_per_page: Option<usize>,
     /// Whether to generate RSS feed
     pub rss_feed: Option<bool>,
+    /// What feed entries contain (default: summary)
+    #[serde(default)]
+    pub feed_content: Option<FeedContent>,
+    /// Whether to archive outbound links with the Internet Archive
+    #[serde(default)]
+    pub archive_links: Option<bool>,
+    /// Link mentions of projects and skills to the CV, if set
+    #[serde(default)]
+    pub cross_links: Option<CrossLinkConfig>,
+    /// Accent colors and social card images of tags, by tag name
+    #[serde(default)]
+    pub tag_styles: OrdMap<String, TagStyle>,
+}
+
+/// Months without an update after which content is stale, unless configured
+pub const DEFAULT_STALE_MONTHS: u32 = 24;
+
+/// Review reminders for old content
+///
+/// Posts and pages are stale once their `review_after` date has passed or
+/// they haven't been updated for the configured number of months.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StalenessConfig {
+    /// Months without an update after which content is stale (default: 24)
+    pub months: Option<u32>,
+    /// Months per blog tag; the shortest of a post's tags applies
+    #[serde(default)]
+    pub tags: OrdMap<String, u32>,
+    /// Months per page type: `post` for blog posts, the layout for pages
+    #[serde(default)]
+    pub page_types: OrdMap<String, u32>,
+    /// Show an "out of date" notice on stale blog posts (default: false)
+    pub banner: Option<bool>,
+}
+
+/// Reaction buttons on blog posts, see [`crate::reactions`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionsConfig {
+    /// Where the reactions API is served (default: "/api/reactions" on the
+    /// same host); an absolute URL points at a server deployed elsewhere
+    pub endpoint: Option<String>,
+}
+
+impl ReactionsConfig {
+    /// URL of the reactions API, without a trailing slash
+    pub fn endpoint(&self) -> &str {
+        self.endpoint
+            .as_deref()
+            .map(|endpoint| endpoint.trim_end_matches('/'))
+            .unwrap_or(REACTIONS_PATH)
+    }
+}
+
+impl StalenessConfig {
+    /// Months without an update after which content is stale
+    ///
+    /// # Arguments
+    ///
+    /// * `page_type` - `post`, or the layout of a page
+    /// * `tags` - Tags of the post, if any
+    ///
+    /// # Returns
+    ///
+    /// The shortest matching tag threshold, else the page type's, else the default
+    pub fn threshold_months(&self, page_type: &str, tags: &Vector<String>) -> u32 {
+        self.tags
+            .iter()
+            .filter(|(tag, _)| tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .map(|(_, months)| *months)
+            .min()
+            .or_else(|| self.page_types.get(page_type).copied())
+            .or(self.months)
+            .unwrap_or(DEFAULT_STALE_MONTHS)
+    }
+
+    /// Whether stale blog posts show a notice
+    pub fn shows_banner(&self) -> bool {
+        self.banner.unwrap_or(false)
+    }
+}
+
+/// Look of a tag's page and of the posts tagged with it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagStyle {
+    /// Accent color: a CSS color, or a colorscheme role such as `secondary`
+    #[serde(default)]
+    pub accent: Option<String>,
+    /// Social card image, with `{tag}` and `{slug}` replaced by the tag and
+    /// the page slug, e.g. `img/og/{tag}.png`
+    #[serde(default)]
+    pub og_image: Option<String>,
+}
+
+impl TagStyle {
+    /// The social card image of a page
+    ///
+    /// # Arguments
+    ///
+    /// * `tag` - The styled tag
+    /// * `slug` - Slug of the post; the tag's own page uses the tag's slug
+    ///
+    /// # Returns
+    ///
+    /// The image path, if the tag has one
+    pub fn og_image_path(&self, tag: &str, slug: Option<&str>) -> Option<String> {
+        let tag_slug = crate::html_generator::filters::slug_str(tag);
+        self.og_image.as_ref().map(|image| {
+            image
+                .replace("{tag}", &tag_slug)
+                .replace("{slug}", slug.unwrap_or(&tag_slug))
+        })
+    }
+}
+
+/// Cross-linking of CV projects and skills mentioned in blog posts
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrossLinkConfig {
+    /// Project and skill names that are never linked (case-insensitive)
+    #[serde(default)]
+    pub exclude: Vector<String>,
+}
+
+/// Content of RSS feed entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedContent {
+    /// The excerpt, or the start of the post
+    #[default]
+    Summary,
+    /// The whole post, with URLs made absolute
+    Full,
 }
 
 /// Configuration for static markdown pages
@@ -72,6 +870,9 @@ pub struct FontConfig {
     pub weight_regular: Option<u16>,
     /// Font weight for bold text
     pub weight_bold: Option<u16>,
+    /// Serve the Google Fonts the site uses from `fonts/` (default: false)
+    #[serde(default)]
+    pub self_host: Option<bool>,
 }
 
 /// Colorscheme configuration for website theming
@@ -87,6 +888,73 @@ pub struct ColorschemeConfig {
     pub variant: Option<String>,
     /// Custom color overrides
     pub custom_colors: Option<ColorOverrides>,
+    /// Scheme for the light theme (default: this scheme, if it is light)
+    #[serde(default)]
+    pub light: Option<SchemeSource>,
+    /// Scheme for the dark theme (default: this scheme, if it is dark)
+    #[serde(default)]
+    pub dark: Option<SchemeSource>,
+}
+
+impl ColorschemeConfig {
+    /// This scheme as one side of a pair
+    pub fn scheme(&self) -> SchemeSource {
+        SchemeSource {
+            name: self.name.clone(),
+            source: self.source.clone(),
+            url: self.url.clone(),
+            variant: self.variant.clone(),
+        }
+    }
+
+    /// The light and dark schemes, if the site has a pair
+    ///
+    /// A side that isn't configured is this scheme, and a side without a
+    /// source comes from this scheme's source. Rose Pine is paired with
+    /// its Dawn and Moon variants when neither side is configured.
+    ///
+    /// # Returns
+    ///
+    /// The light and the dark scheme, or `None` for a single scheme
+    pub fn pair(&self) -> Option<(SchemeSource, SchemeSource)> {
+        let inherit = |side: &SchemeSource| match side.source {
+            Some(_) => side.clone(),
+            None => SchemeSource {
+                source: self.source.clone(),
+                url: side.url.clone().or_else(|| self.url.clone()),
+                ..side.clone()
+            },
+        };
+        let named = |name: &str| SchemeSource {
+            name: name.to_string(),
+            variant: None,
+            ..self.scheme()
+        };
+
+        match (&self.light, &self.dark) {
+            (None, None) if self.name.to_lowercase().contains("rose pine") => {
+                Some((named("Rose Pine Dawn"), named("Rose Pine Moon")))
+            }
+            (None, None) => None,
+            (light, dark) => Some((
+                light.as_ref().map_or_else(|| self.scheme(), inherit),
+                dark.as_ref().map_or_else(|| self.scheme(), inherit),
+            )),
+        }
+    }
+}
+
+/// One side of a light and dark colorscheme pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemeSource {
+    /// Name of the colorscheme
+    pub name: String,
+    /// Source of the colorscheme (default: the source of the main scheme)
+    pub source: Option<String>,
+    /// Optional URL for the colorscheme source
+    pub url: Option<String>,
+    /// Variant of the colorscheme
+    pub variant: Option<String>,
 }
 
 /// Custom color overrides for fine-tuning
@@ -115,6 +983,15 @@ pub struct TypstConfig {
     pub theme: TypstTheme,
     /// Customization options for the theme
     pub customization: TypstCustomization,
+    /// Custom Typst template file replacing the built-in layout
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Extra directories searched for fonts
+    #[serde(default)]
+    pub font_paths: Vector<String>,
+    /// Only use fonts from `font_paths` and those bundled with Typst
+    #[serde(default)]
+    pub ignore_system_fonts: bool,
 }
 
 /// Typst theme configuration
@@ -135,6 +1012,23 @@ pub struct TypstCustomization {
     pub colors: TypstColors,
     /// Layout customization
     pub layout: TypstLayout,
+    /// Where the PDF colors come from (default: the website's colorscheme)
+    #[serde(default)]
+    pub palette: PdfPalette,
+}
+
+/// Where the colors of the PDFs come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PdfPalette {
+    /// The light side of the website's colorscheme, or `colors` when the
+    /// site has no colorscheme or only a dark one
+    #[default]
+    Site,
+    /// The configured `colors`
+    Config,
+    /// Black on white, for printing
+    Monochrome,
 }
 
 /// Typst color scheme
@@ -169,6 +1063,7 @@ impl Default for SiteConfig {
     fn default() -> Self {
         SiteConfig {
             title: None,
+            base_url: None,
             menu: Vector::from(vec![
                 MenuItem {
                     label: "CV".to_string(),
@@ -194,6 +1089,32 @@ impl Default for SiteConfig {
             blog: None,
             fonts: None,
             colorscheme: None,
+            security: None,
+            theme: None,
+            languages: Vector::new(),
+            current_language: None,
+            featured: None,
+            assets: None,
+            compression: None,
+            booking: None,
+            timeline: None,
+            analytics: None,
+            pwa: None,
+            deployment: None,
+            pdf: None,
+            deploy: OrdMap::new(),
+            encrypted_pages: OrdMap::new(),
+            project_rules: None,
+            profiles: OrdMap::new(),
+            staleness: None,
+            reactions: None,
+            front_matter: FrontMatterConfig::default(),
+            about_this_site: false,
+            glossary_page: false,
+            build_info: None,
+            taxonomy: Taxonomy::default(),
+            glossary: Glossary::default(),
+            offline: false,
         }
     }
 }
@@ -207,6 +1128,7 @@ impl Default for FontConfig {
             base_size: Some("16px".to_string()),
             weight_regular: Some(400),
             weight_bold: Some(700),
+            self_host: None,
         }
     }
 }
@@ -268,6 +1190,25 @@ impl SiteConfig {
                 .with_context(|| "Invalid Typst configuration in site config")?;
         }
 
+        if let Some(ref booking) = config.booking {
+            booking
+                .validate()
+                .with_context(|| "Invalid booking configuration in site config")?;
+        }
+
+        if let Some(ref analytics) = config.analytics {
+            analytics
+                .validate()
+                .with_context(|| "Invalid analytics configuration in site config")?;
+        }
+
+        for language in config.languages.iter() {
+            validate_language_code(language).with_context(|| "Invalid language in site config")?;
+        }
+
+        validate_encrypted_pages(&config.encrypted_pages)
+            .with_context(|| "Invalid encrypted_pages in site config")?;
+
         Ok(config)
     }
 
@@ -288,6 +1229,34 @@ impl SiteConfig {
         Ok(config)
     }
 
+    /// Get Typst configuration with the colors the PDFs are printed in
+    ///
+    /// Resolves `customization.palette`: the colors come from the website's
+    /// colorscheme, from `customization.colors`, or are monochrome. A
+    /// colorscheme that can't be fetched is reported and the configured
+    /// colors are used.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the validated TypstConfig or an error
+    pub fn pdf_typst_config(&self) -> Result<TypstConfig> {
+        let mut config = self.get_typst_config()?;
+        let colors = match config.customization.palette {
+            PdfPalette::Config => None,
+            PdfPalette::Monochrome => Some(TypstColors::monochrome()),
+            PdfPalette::Site => self.colorscheme.as_ref().and_then(|colorscheme| {
+                css_generator::print_colors(colorscheme, self.offline).unwrap_or_else(|e| {
+                    println!("⚠️  {e:#}; the PDFs use the configured colors");
+                    None
+                })
+            }),
+        };
+        if let Some(colors) = colors {
+            config.customization.colors = colors;
+        }
+        Ok(config)
+    }
+
     /// Get the site title, falling back to a default if not configured
     ///
     /// # Arguments
@@ -301,6 +1270,134 @@ impl SiteConfig {
     pub fn get_title(&self, fallback: &str) -> String {
         self.title.as_deref().unwrap_or(fallback).to_string()
     }
+
+    /// Get the default language (the first configured language, or "en")
+    pub fn default_language(&self) -> &str {
+        self.languages.front().map(String::as_str).unwrap_or("en")
+    }
+
+    /// Whether the site is generated in more than one language
+    pub fn is_multilingual(&self) -> bool {
+        self.languages.len() > 1
+    }
+
+    /// Get the base URL of the deployed site, or "" when it isn't configured
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_deref().unwrap_or("")
+    }
+
+    /// Whether the blog RSS feed is enabled
+    pub fn has_rss_feed(&self) -> bool {
+        self.blog
+            .as_ref()
+            .and_then(|blog| blog.rss_feed)
+            .unwrap_or(false)
+    }
+
+    /// Whether web fonts are downloaded at build time and served with the site
+    pub fn self_hosts_fonts(&self) -> bool {
+        self.fonts
+            .as_ref()
+            .and_then(|fonts| fonts.self_host)
+            .unwrap_or(false)
+    }
+
+    /// Whether outbound links in blog posts are archived
+    pub fn archives_links(&self) -> bool {
+        self.blog
+            .as_ref()
+            .and_then(|blog| blog.archive_links)
+            .unwrap_or(false)
+    }
+
+    /// The style of a tag, matching the configured names like tags are matched
+    pub fn tag_style(&self, tag: &str) -> Option<&TagStyle> {
+        self.blog.as_ref().and_then(|blog| {
+            blog.tag_styles
+                .iter()
+                .find(|(name, _)| self.taxonomy.canonical(name).eq_ignore_ascii_case(tag))
+                .map(|(_, style)| style)
+        })
+    }
+
+    /// The style of a post: that of its first tag with one
+    ///
+    /// # Returns
+    ///
+    /// The styled tag and its style, if any of the tags has one
+    pub fn post_tag_style<'a>(
+        &'a self,
+        tags: &'a Vector<String>,
+    ) -> Option<(&'a String, &'a TagStyle)> {
+        tags.iter()
+            .find_map(|tag| self.tag_style(tag).map(|style| (tag, style)))
+    }
+
+    /// Get the language of the pages being generated, for the `lang` attribute
+    pub fn html_lang(&self) -> &str {
+        self.current_language
+            .as_deref()
+            .unwrap_or_else(|| self.default_language())
+    }
+
+    /// Platforms to write deployment configuration files for
+    pub fn deployment_targets(&self) -> Vector<DeploymentTarget> {
+        match &self.deployment {
+            Some(deployment) if !deployment.targets.is_empty() => deployment.targets.clone(),
+            _ => DeploymentConfig::DEFAULT_TARGETS.into_iter().collect(),
+        }
+    }
+
+    /// Whether configuration files for a platform are written
+    pub fn deploys_to(&self, target: DeploymentTarget) -> bool {
+        self.deployment_targets().contains(&target)
+    }
+
+    /// PDF documents built for the `pdf` output format
+    pub fn pdf_variants(&self) -> Vector<PdfVariant> {
+        match &self.pdf {
+            Some(pdf) if !pdf.variants.is_empty() => pdf.variants.clone(),
+            _ => Vector::unit(PdfVariant::Cv),
+        }
+    }
+
+    /// Domain for the GitHub Pages `CNAME` file
+    ///
+    /// The configured custom domain, or the host of `base_url` unless that is
+    /// a `github.io` address, which needs no CNAME.
+    pub fn custom_domain(&self) -> Option<String> {
+        if let Some(domain) = self
+            .deployment
+            .as_ref()
+            .and_then(|deployment| deployment.custom_domain.clone())
+        {
+            return Some(domain);
+        }
+        let host = self
+            .base_url
+            .as_deref()?
+            .split_once("://")?
+            .1
+            .split(['/', ':'])
+            .next()?;
+        (!host.is_empty() && !host.ends_with(".github.io")).then(|| host.to_string())
+    }
+
+    /// Create a copy of this configuration for generating one language
+    ///
+    /// # Arguments
+    ///
+    /// * `language` - The language code to generate
+    ///
+    /// # Returns
+    ///
+    /// A SiteConfig with `current_language` set
+    pub fn for_language(&self, language: &str) -> Self {
+        SiteConfig {
+            current_language: Some(language.to_string()),
+            ..self.clone()
+        }
+    }
 }
 
 impl TypstConfig {
@@ -316,6 +1413,16 @@ impl TypstConfig {
         // Validate customization
         self.customization.validate()?;
 
+        // Validate custom template path
+        if let Some(ref template) = self.template {
+            if !template.ends_with(".typ") {
+                return Err(anyhow::anyhow!(
+                    "Typst template '{}' must be a .typ file",
+                    template
+                ));
+            }
+        }
+
         Ok(())
     }
 }
@@ -371,6 +1478,17 @@ impl TypstCustomization {
 }
 
 impl TypstColors {
+    /// Print-safe colors: black text and accents on white
+    pub fn monochrome() -> Self {
+        TypstColors {
+            fill: "#ffffff".to_string(),
+            accent: "#000000".to_string(),
+            text_light: "#ffffff".to_string(),
+            text_medium: "#555555".to_string(),
+            text_dark: "#000000".to_string(),
+        }
+    }
+
     /// Validate color configuration
     ///
     /// # Returns
@@ -462,12 +1580,39 @@ mod tests {
 
         let config_with_title = SiteConfig {
             title: Some("My Portfolio".to_string()),
+            base_url: None,
             menu: Vector::new(),
             typst: None,
             pages: None,
             blog: None,
             fonts: None,
             colorscheme: None,
+            security: None,
+            theme: None,
+            languages: Vector::new(),
+            current_language: None,
+            featured: None,
+            assets: None,
+            compression: None,
+            booking: None,
+            timeline: None,
+            analytics: None,
+            pwa: None,
+            deployment: None,
+            pdf: None,
+            deploy: OrdMap::new(),
+            encrypted_pages: OrdMap::new(),
+            project_rules: None,
+            profiles: OrdMap::new(),
+            staleness: None,
+            reactions: None,
+            front_matter: FrontMatterConfig::default(),
+            about_this_site: false,
+            glossary_page: false,
+            build_info: None,
+            taxonomy: Taxonomy::default(),
+            glossary: Glossary::default(),
+            offline: false,
         };
         assert_eq!(config_with_title.get_title("John Doe"), "My Portfolio");
     }
@@ -630,6 +1775,22 @@ mod tests {
             .contains("Typst font size must be in points"));
     }
 
+    #[test]
+    fn test_site_config_languages() {
+        let config = SiteConfig::default();
+        assert_eq!(config.default_language(), "en");
+        assert_eq!(config.html_lang(), "en");
+        assert!(!config.is_multilingual());
+
+        let config = SiteConfig {
+            languages: Vector::from(vec!["da".to_string(), "en".to_string()]),
+            ..SiteConfig::default()
+        };
+        assert!(config.is_multilingual());
+        assert_eq!(config.html_lang(), "da");
+        assert_eq!(config.for_language("en").html_lang(), "en");
+    }
+
     #[test]
     fn test_site_config_get_typst_config_success() {
         let config = SiteConfig::default();
@@ -639,16 +1800,155 @@ mod tests {
         assert_eq!(typst_config.theme.name, "grotesk-cv");
     }
 
+    #[test]
+    fn test_site_config_pdf_palette() {
+        let with_palette = |palette: PdfPalette| {
+            let mut config = SiteConfig {
+                colorscheme: None,
+                ..SiteConfig::default()
+            };
+            if let Some(typst) = config.typst.as_mut() {
+                typst.customization.palette = palette;
+            }
+            config.pdf_typst_config().unwrap().customization.colors
+        };
+        let configured = SiteConfig::default()
+            .get_typst_config()
+            .unwrap()
+            .customization
+            .colors;
+
+        assert_eq!(with_palette(PdfPalette::Config).fill, configured.fill);
+        // Without a colorscheme the configured colors are used
+        assert_eq!(with_palette(PdfPalette::Site).accent, configured.accent);
+        let monochrome = with_palette(PdfPalette::Monochrome);
+        assert_eq!(monochrome.fill, "#ffffff");
+        assert_eq!(monochrome.text_dark, "#000000");
+
+        assert_eq!(
+            serde_json::from_str::<PdfPalette>(r#""monochrome""#).unwrap(),
+            PdfPalette::Monochrome
+        );
+    }
+
+    #[test]
+    fn test_booking_config() {
+        let booking = |json: &str| serde_json::from_str::<BookingConfig>(json).unwrap();
+
+        let calendly = booking(r#"{"url": "https://calendly.com/ada/30min/"}"#);
+        assert_eq!(calendly.provider(), Some(BookingProvider::Calendly));
+        assert_eq!(
+            calendly.embed_url().unwrap(),
+            "https://calendly.com/ada/30min?embed_type=Inline&hide_gdpr_banner=1"
+        );
+        let cal = booking(r#"{"url": "https://cal.example.com/ada?duration=15"}"#);
+        assert_eq!(
+            cal.embed_url().unwrap(),
+            "https://cal.example.com/ada/embed?duration=15&layout=month_view"
+        );
+        assert_eq!(booking("{}").embed_url(), None);
+
+        assert!(booking(r#"{"url": "http://cal.com/ada"}"#)
+            .validate()
+            .is_err());
+        assert!(
+            booking(r#"{"availability": [{"day": "fri", "start": "12:00", "end": "09:00"}]}"#)
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_analytics_config() {
+        let analytics = |json: &str| serde_json::from_str::<AnalyticsConfig>(json).unwrap();
+
+        let plausible = analytics(r#"{"provider": "plausible", "domain": "example.com"}"#);
+        assert_eq!(plausible.script_url(), "https://plausible.io/js/script.js");
+        assert!(plausible.is_enabled_in("production"));
+        assert!(!plausible.is_enabled_in("development"));
+        assert!(plausible.validate().is_ok());
+
+        let goatcounter = analytics(
+            r#"{"provider": "goatcounter", "domain": "ada.goatcounter.com", "environments": ["staging"]}"#,
+        );
+        assert_eq!(
+            goatcounter.script_attributes(),
+            im::vector![(
+                "data-goatcounter",
+                "https://ada.goatcounter.com/count".to_string()
+            )]
+        );
+        assert!(goatcounter.is_enabled_in("staging"));
+
+        let umami = analytics(r#"{"provider": "umami", "domain": "example.com"}"#);
+        assert!(umami.validate().is_err());
+        assert!(
+            analytics(r#"{"provider": "plausible", "domain": "https://example.com"}"#)
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_deployment_targets() {
+        let config = SiteConfig {
+            base_url: Some("https://cv.example.com/".to_string()),
+            ..SiteConfig::default()
+        };
+        assert!(config.deploys_to(DeploymentTarget::Netlify));
+        assert!(!config.deploys_to(DeploymentTarget::GithubPages));
+        assert_eq!(config.custom_domain().as_deref(), Some("cv.example.com"));
+
+        let config: SiteConfig = serde_json::from_str(
+            r#"{
+                "menu": [],
+                "base_url": "https://ada.github.io",
+                "deployment": {"targets": ["github-pages", "cloudflare-pages"]}
+            }"#,
+        )
+        .unwrap();
+        assert!(config.deploys_to(DeploymentTarget::CloudflarePages));
+        assert!(!config.deploys_to(DeploymentTarget::Apache));
+        assert_eq!(config.custom_domain(), None);
+    }
+
     #[test]
     fn test_site_config_get_typst_config_missing() {
         let config = SiteConfig {
             title: None,
+            base_url: None,
             menu: Vector::new(),
             typst: None,
             pages: None,
             blog: None,
             fonts: None,
             colorscheme: None,
+            security: None,
+            theme: None,
+            languages: Vector::new(),
+            current_language: None,
+            featured: None,
+            assets: None,
+            compression: None,
+            booking: None,
+            timeline: None,
+            analytics: None,
+            pwa: None,
+            deployment: None,
+            pdf: None,
+            deploy: OrdMap::new(),
+            encrypted_pages: OrdMap::new(),
+            project_rules: None,
+            profiles: OrdMap::new(),
+            staleness: None,
+            reactions: None,
+            front_matter: FrontMatterConfig::default(),
+            about_this_site: false,
+            glossary_page: false,
+            build_info: None,
+            taxonomy: Taxonomy::default(),
+            glossary: Glossary::default(),
+            offline: false,
         };
         let result = config.get_typst_config();
         assert!(result.is_err());
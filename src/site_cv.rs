@@ -0,0 +1,245 @@
+//! The CV as a site publishes it
+//!
+//! The build and the [admin operations](crate::admin) prepare the CV the same
+//! way before anything is rendered from it:
+//!
+//! - GitHub projects replace the ones in the CV data that have a repository,
+//!   and published packages are merged into them
+//! - approved testimonials are added, for [`Cv::filtered`] to publish
+//! - the site config gets the taxonomy and glossary next to the CV data
+//! - projects are curated by the site's project rules
+//! - each site (language) localizes the CV, decorates its projects with their
+//!   language and icon, and drops the fields `public_data` doesn't allow
+//!
+//! Pages and feeds regenerated outside the build therefore show the same CV
+//! the build publishes, and never more of it.
+
+use anyhow::Result;
+use im::Vector;
+use std::path::Path;
+use tracing::{debug, info};
+
+use crate::cv_data::{Cv, Project, PublicDataConfig};
+use crate::glossary::{Glossary, GLOSSARY_FILE};
+use crate::i18n;
+use crate::project_curation;
+use crate::site_config::SiteConfig;
+use crate::taxonomy::Taxonomy;
+use crate::testimonials::{TestimonialStore, TESTIMONIALS_FILE};
+use crate::unified_config::AppConfig;
+
+/// Directory of the CV data, which also holds testimonials and reactions
+pub fn data_dir(config: &AppConfig) -> &Path {
+    config.data_path.parent().unwrap_or_else(|| Path::new("."))
+}
+
+/// Replace the CV's GitHub projects with the fetched ones
+///
+/// # Arguments
+///
+/// * `cv` - The CV as loaded
+/// * `github_projects` - Projects fetched from the CV's GitHub sources
+///
+/// # Returns
+///
+/// The projects without a repository, followed by the GitHub projects
+pub fn merge_github_projects(cv: &Cv, github_projects: &Vector<Project>) -> Vector<Project> {
+    cv.projects
+        .iter()
+        .filter(|project| project.repository.is_none())
+        .chain(github_projects.iter())
+        .cloned()
+        .collect()
+}
+
+/// Add the moderated testimonials next to the CV data
+///
+/// # Arguments
+///
+/// * `cv` - The CV to add them to
+/// * `config` - Application configuration, for the data directory
+///
+/// # Returns
+///
+/// The number of approved testimonials; pending and rejected ones are added
+/// too, and dropped by [`Cv::filtered`]
+pub fn add_testimonials(cv: &mut Cv, config: &AppConfig) -> Result<usize> {
+    let testimonials = TestimonialStore::load(data_dir(config).join(TESTIMONIALS_FILE))?;
+    let approved = testimonials.approved().len();
+    cv.testimonials.append(testimonials.testimonials);
+    Ok(approved)
+}
+
+/// Add the taxonomy and glossary next to the CV data to the site config
+///
+/// # Arguments
+///
+/// * `site_config` - The site config as loaded
+/// * `config` - Application configuration, for the data directory
+///
+/// # Returns
+///
+/// The site config with the canonical technology names, icons and
+/// abbreviations
+pub fn with_site_data(site_config: SiteConfig, config: &AppConfig) -> Result<SiteConfig> {
+    // Canonical technology names and icons, shared by CV skills, projects and blog tags
+    let taxonomy = Taxonomy::load(data_dir(config))?;
+    info!("Found {} technologies", taxonomy.technologies.len());
+
+    // Abbreviations expanded on every page, optionally listed on a glossary page
+    let glossary_path = data_dir(config).join(GLOSSARY_FILE);
+    let glossary = if glossary_path.exists() {
+        Glossary::from_json(&glossary_path.to_string_lossy())?
+    } else {
+        Glossary::default()
+    };
+    info!("Found {} glossary terms", glossary.entries.len());
+
+    Ok(SiteConfig {
+        taxonomy,
+        glossary,
+        ..site_config
+    })
+}
+
+/// Pinned projects first, then the ones passing the site's project rules
+pub fn curate_projects(projects: &Vector<Project>, site_config: &SiteConfig) -> Vector<Project> {
+    project_curation::curate_projects(
+        projects,
+        &site_config.project_rules.clone().unwrap_or_default(),
+    )
+}
+
+/// The CV one site publishes
+///
+/// # Arguments
+///
+/// * `cv` - The prepared CV, in the default language
+/// * `config` - Application configuration, for the translations
+/// * `site_config` - Site config with the taxonomy
+/// * `language` - Language of the site, if the site is multilingual
+/// * `public_data` - Fields that may be published
+///
+/// # Returns
+///
+/// The localized CV with decorated projects and only the public fields
+pub fn publishable_cv(
+    cv: &Cv,
+    config: &AppConfig,
+    site_config: &SiteConfig,
+    language: Option<&str>,
+    public_data: &PublicDataConfig,
+) -> Result<Cv> {
+    let cv = match language {
+        Some(language) => i18n::localize_cv(cv, &config.data_path, language)?,
+        None => cv.clone(),
+    };
+    Ok(Cv {
+        projects: decorate_projects(&cv.projects, &site_config.taxonomy),
+        ..cv
+    }
+    .filtered(public_data))
+}
+
+/// Add the display name, language and language icon to each project
+///
+/// # Arguments
+///
+/// * `projects` - The projects
+/// * `taxonomy` - Technologies with their icons
+///
+/// # Returns
+///
+/// The decorated projects
+pub fn decorate_projects(projects: &Vector<Project>, taxonomy: &Taxonomy) -> Vector<Project> {
+    projects
+        .iter()
+        .map(|project| {
+            // Extract display name (without language suffix)
+            let display_name = project
+                .name
+                .find(" - ")
+                .map(|pos| project.name[..pos].to_string())
+                .unwrap_or_else(|| project.name.clone());
+
+            let language = taxonomy.detect_language(&project.name, &project.technologies);
+            if let Some(language) = &language {
+                debug!(
+                    "Detected language for project {}: {}",
+                    display_name, language
+                );
+            }
+            let language_icon = language
+                .as_deref()
+                .and_then(|language| taxonomy.icon(language))
+                .map(str::to_string);
+
+            Project {
+                display_name: Some(display_name),
+                language,
+                language_icon,
+                ..project.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cv_data::PersonalInfo;
+
+    #[test]
+    fn test_merge_github_projects() {
+        let project = |name: &str, repository: Option<&str>| -> Project {
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "description": "",
+                "repository": repository,
+                "technologies": [],
+                "highlights": []
+            }))
+            .unwrap()
+        };
+        let cv = Cv {
+            projects: Vector::from(vec![
+                project("Talk", None),
+                project("old-repo", Some("https://github.com/ada/old-repo")),
+            ]),
+            ..Cv::create_minimal()
+        };
+        let github = Vector::unit(project("engine", Some("https://github.com/ada/engine")));
+        let names: Vec<String> = merge_github_projects(&cv, &github)
+            .iter()
+            .map(|project| project.name.clone())
+            .collect();
+        assert_eq!(names, vec!["Talk", "engine"]);
+    }
+
+    #[test]
+    fn test_publishable_cv_is_filtered() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = AppConfig {
+            data_path: dir.path().join("cv_data.json"),
+            ..AppConfig::default()
+        };
+        let cv = Cv {
+            personal_info: PersonalInfo {
+                phone: Some("+44 20 7946 0000".to_string()),
+                ..Cv::create_minimal().personal_info
+            },
+            ..Cv::create_minimal()
+        };
+        let public_data = PublicDataConfig::from_fields(&Vector::unit("name".to_string())).unwrap();
+        let published = publishable_cv(
+            &cv,
+            &config,
+            &SiteConfig::default(),
+            Some("da"),
+            &public_data,
+        )
+        .unwrap();
+        assert_eq!(published.personal_info.phone, None);
+        assert_eq!(published.personal_info.name, cv.personal_info.name);
+    }
+}
@@ -0,0 +1,268 @@
+//! Review reminders for old content
+//!
+//! Technical posts age: APIs change, links break and advice goes out of date.
+//! With `staleness` configured, every build lists the posts and pages whose
+//! `review_after` date has passed or that haven't been updated for longer than
+//! their threshold, and stale posts can show an "out of date" notice.
+//!
+//! A post or page was last updated on its `updated` front matter date, else on
+//! the last commit that touched its file, else (for posts) on its publication
+//! date. Thresholds are set per blog tag or page type, see
+//! [`StalenessConfig::threshold_months`].
+
+use chrono::{DateTime, Months, Utc};
+use im::Vector;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+use crate::blog_posts::BlogPost;
+use crate::markdown_pages::Page;
+use crate::site_config::StalenessConfig;
+
+/// Page type of blog posts in [`StalenessConfig::page_types`]
+pub const POST_PAGE_TYPE: &str = "post";
+
+/// A post or page due for review
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaleContent {
+    /// Title of the post or page
+    pub title: String,
+    /// Path of the generated page, e.g. `blog/hello.html`
+    pub path: String,
+    /// When it was last updated, if known
+    pub last_updated: Option<DateTime<Utc>>,
+    /// Why it's due for review
+    pub reason: StaleReason,
+}
+
+/// Why content is due for review
+#[derive(Debug, Clone, PartialEq)]
+pub enum StaleReason {
+    /// The `review_after` date has passed
+    ReviewDue(DateTime<Utc>),
+    /// Not updated for longer than the threshold
+    Untouched { months: u32 },
+}
+
+impl fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaleReason::ReviewDue(date) => {
+                write!(f, "review was due on {}", date.format("%Y-%m-%d"))
+            }
+            StaleReason::Untouched { months } => {
+                write!(f, "not updated in over {} months", months)
+            }
+        }
+    }
+}
+
+/// Decide whether content is due for review
+///
+/// # Arguments
+///
+/// * `last_updated` - When the content was last updated, if known
+/// * `review_after` - Explicit review date, if any
+/// * `threshold_months` - Months without an update after which it is stale
+/// * `now` - The current time
+///
+/// # Returns
+///
+/// The reason it is stale, or `None` if it is current
+pub fn stale_reason(
+    last_updated: Option<DateTime<Utc>>,
+    review_after: Option<DateTime<Utc>>,
+    threshold_months: u32,
+    now: DateTime<Utc>,
+) -> Option<StaleReason> {
+    let cutoff = now.checked_sub_months(Months::new(threshold_months))?;
+
+    match (review_after, last_updated) {
+        (Some(review_after), _) if review_after <= now => {
+            Some(StaleReason::ReviewDue(review_after))
+        }
+        (_, Some(updated)) if updated < cutoff => Some(StaleReason::Untouched {
+            months: threshold_months,
+        }),
+        _ => None,
+    }
+}
+
+/// Check blog posts for staleness
+///
+/// Stale posts get their last update date and, when the notice is enabled,
+/// `stale` set, so the template can say how old they are.
+///
+/// # Arguments
+///
+/// * `posts` - Posts loaded from `blog_dir`
+/// * `blog_dir` - Directory of the post sources
+/// * `config` - Staleness settings
+/// * `now` - The current time
+///
+/// # Returns
+///
+/// The posts, and the ones due for review
+pub fn check_posts(
+    posts: &Vector<BlogPost>,
+    blog_dir: &Path,
+    config: &StalenessConfig,
+    now: DateTime<Utc>,
+) -> (Vector<BlogPost>, Vec<StaleContent>) {
+    let mut stale = Vec::new();
+    let posts = posts
+        .iter()
+        .map(|post| {
+            let source = blog_dir.join(format!("{}.md", post.slug));
+            let last_updated = post
+                .updated
+                .or_else(|| git_last_commit(&source))
+                .or(Some(post.date));
+            let threshold = config.threshold_months(POST_PAGE_TYPE, &post.tags);
+
+            match stale_reason(last_updated, post.review_after, threshold, now) {
+                Some(reason) => {
+                    stale.push(StaleContent {
+                        title: post.title.clone(),
+                        path: format!("blog/{}.html", post.slug),
+                        last_updated,
+                        reason,
+                    });
+                    BlogPost {
+                        updated: last_updated,
+                        stale: config.shows_banner(),
+                        ..post.clone()
+                    }
+                }
+                None => post.clone(),
+            }
+        })
+        .collect();
+
+    (posts, stale)
+}
+
+/// Check static pages for staleness
+///
+/// # Arguments
+///
+/// * `pages` - Pages loaded from `pages_dir`
+/// * `pages_dir` - Directory of the page sources
+/// * `config` - Staleness settings
+/// * `now` - The current time
+///
+/// # Returns
+///
+/// The pages due for review
+pub fn check_pages(
+    pages: &Vector<Page>,
+    pages_dir: &Path,
+    config: &StalenessConfig,
+    now: DateTime<Utc>,
+) -> Vec<StaleContent> {
+    pages
+        .iter()
+        .filter_map(|page| {
+            let source = pages_dir.join(format!("{}.md", page.slug));
+            let last_updated = page.updated.or_else(|| git_last_commit(&source));
+            let threshold = config.threshold_months(&page.layout, &Vector::new());
+
+            stale_reason(last_updated, page.review_after, threshold, now).map(|reason| {
+                StaleContent {
+                    title: page.title.clone(),
+                    path: format!("{}.html", page.slug),
+                    last_updated,
+                    reason,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Print the content due for review
+///
+/// # Arguments
+///
+/// * `stale` - Posts and pages due for review
+pub fn print_report(stale: &[StaleContent]) {
+    if stale.is_empty() {
+        return;
+    }
+
+    println!("🕰️  Content due for review ({}):", stale.len());
+    for content in stale {
+        let last_updated = content
+            .last_updated
+            .map(|date| format!("last updated {}, ", date.format("%Y-%m-%d")))
+            .unwrap_or_default();
+        println!(
+            "   {} ({}): {}{}",
+            content.title, content.path, last_updated, content.reason
+        );
+    }
+}
+
+/// Get the time of the last commit that touched a file
+fn git_last_commit(path: &Path) -> Option<DateTime<Utc>> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%cI", "--"])
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let date = String::from_utf8(output.stdout).ok()?;
+    DateTime::parse_from_rfc3339(date.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use im::{vector, OrdMap};
+
+    #[test]
+    fn test_stale_posts() {
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let post = |slug: &str, year: i32, tags: Vector<String>| BlogPost {
+            date: Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap(),
+            tags,
+            ..BlogPost::from_markdown(
+                "---\ntitle: Post\ndate: 2020-01-01\n---\n",
+                slug.to_string(),
+            )
+            .unwrap()
+        };
+        let posts = vector![
+            post("recent", 2026, Vector::new()),
+            post("old", 2023, Vector::new()),
+            post("rust", 2025, vector!["Rust".to_string()]),
+            BlogPost {
+                review_after: Some(Utc.with_ymd_and_hms(2026, 5, 1, 0, 0, 0).unwrap()),
+                ..post("review", 2026, Vector::new())
+            },
+        ];
+        let config = StalenessConfig {
+            tags: OrdMap::unit("rust".to_string(), 12),
+            banner: Some(true),
+            ..StalenessConfig::default()
+        };
+
+        let (posts, stale) = check_posts(&posts, Path::new("/nonexistent"), &config, now);
+        let stale_paths: Vec<&str> = stale.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(
+            stale_paths,
+            ["blog/old.html", "blog/rust.html", "blog/review.html"]
+        );
+        assert_eq!(stale[0].reason, StaleReason::Untouched { months: 24 });
+        assert_eq!(stale[1].reason, StaleReason::Untouched { months: 12 });
+        assert!(matches!(stale[2].reason, StaleReason::ReviewDue(_)));
+
+        assert!(!posts[0].stale);
+        assert!(posts[1].stale);
+        assert_eq!(posts[1].updated, Some(posts[1].date));
+    }
+}
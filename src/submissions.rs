@@ -0,0 +1,433 @@
+//! Submitted drafts: guest posts and suggestions
+//!
+//! Anyone can send a draft to `POST /api/submissions` on `cv serve`, or only
+//! those holding the `submissions_token` secret when it is declared. Drafts
+//! are checked, stripped of raw HTML and unsafe links, and queued in
+//! `data/submissions.json`. Nothing reaches the blog until
+//! `cv submissions promote <n>` turns a draft into an unpublished post in the
+//! blog directory, to be edited and published like any other.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use im::Vector;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::html_generator::filters::slug_str;
+
+/// File name of the submitted drafts in the data directory
+pub const SUBMISSIONS_FILE: &str = "submissions.json";
+
+/// Path the dev server accepts drafts on
+pub const SUBMISSION_INBOX_PATH: &str = "/api/submissions";
+
+/// Secret that, when declared, submitters must send as a bearer token
+pub const SUBMISSIONS_TOKEN_KEY: &str = "submissions_token";
+
+/// Largest accepted draft, in bytes of JSON
+pub const MAX_DRAFT_SIZE: usize = 128 * 1024;
+
+/// Longest accepted title, author or excerpt, in characters
+const MAX_FIELD_LENGTH: usize = 300;
+
+/// Most tags a draft may have
+const MAX_TAGS: usize = 10;
+
+/// Review state of a draft
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DraftStatus {
+    /// Submitted, waiting for review
+    #[default]
+    Pending,
+    /// Turned into a draft post
+    Promoted,
+    /// Rejected, kept so the same draft isn't reviewed twice
+    Rejected,
+}
+
+impl std::fmt::Display for DraftStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DraftStatus::Pending => "pending",
+            DraftStatus::Promoted => "promoted",
+            DraftStatus::Rejected => "rejected",
+        })
+    }
+}
+
+/// A draft post sent in by a guest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Draft {
+    /// Post title
+    pub title: String,
+    /// Slug of the post it becomes
+    pub slug: String,
+    /// Markdown body, sanitized
+    pub body: String,
+    /// Who wrote it
+    pub author: String,
+    /// How to reach the author; never published
+    #[serde(default)]
+    pub contact: Option<String>,
+    /// Short summary
+    #[serde(default)]
+    pub excerpt: Option<String>,
+    /// Suggested tags
+    #[serde(default)]
+    pub tags: Vector<String>,
+    /// Review state
+    #[serde(default)]
+    pub status: DraftStatus,
+    /// When the draft was submitted
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// A draft as submitted to the dev server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftSubmission {
+    pub title: String,
+    pub body: String,
+    pub author: String,
+    #[serde(default)]
+    pub contact: Option<String>,
+    #[serde(default)]
+    pub excerpt: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl DraftSubmission {
+    /// Check and sanitize the submission and turn it into a pending draft
+    ///
+    /// # Returns
+    ///
+    /// The draft, or an error describing what's wrong with the submission
+    pub fn into_draft(self) -> Result<Draft> {
+        let single_line = |value: &str| value.split_whitespace().collect::<Vec<_>>().join(" ");
+        let optional = |value: Option<String>| {
+            value
+                .map(|value| single_line(&value))
+                .filter(|value| !value.is_empty())
+        };
+        let title = single_line(&self.title);
+        let author = single_line(&self.author);
+        let contact = optional(self.contact);
+        let excerpt = optional(self.excerpt);
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| single_line(tag))
+            .filter(|tag| !tag.is_empty())
+            .collect::<Vector<_>>();
+        let body = sanitize_markdown(&self.body);
+
+        if title.is_empty() || author.is_empty() || body.trim().is_empty() {
+            anyhow::bail!("A draft needs a title, a body and an author");
+        }
+        if [
+            Some(&title),
+            Some(&author),
+            contact.as_ref(),
+            excerpt.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .chain(tags.iter())
+        .any(|field| field.chars().count() > MAX_FIELD_LENGTH)
+        {
+            anyhow::bail!("Fields are limited to {} characters", MAX_FIELD_LENGTH);
+        }
+        if tags.len() > MAX_TAGS {
+            anyhow::bail!("Drafts are limited to {} tags", MAX_TAGS);
+        }
+
+        let slug = slug_str(&title);
+        if slug.is_empty() {
+            anyhow::bail!("The title needs at least one letter or digit");
+        }
+
+        Ok(Draft {
+            title,
+            slug,
+            body,
+            author,
+            contact,
+            excerpt,
+            tags,
+            status: DraftStatus::Pending,
+            submitted_at: Utc::now(),
+        })
+    }
+}
+
+/// Remove raw HTML and links with unsafe schemes from markdown
+///
+/// Posts may contain HTML written by the site owner, so a guest's HTML is
+/// dropped before it gets there. Links and images keep their text but lose
+/// destinations other than http(s), mailto and relative ones.
+///
+/// # Arguments
+///
+/// * `markdown` - The submitted markdown
+///
+/// # Returns
+///
+/// The markdown without them
+pub fn sanitize_markdown(markdown: &str) -> String {
+    let removed = Parser::new_ext(markdown, Options::all())
+        .into_offset_iter()
+        .filter_map(|(event, range)| match event {
+            Event::Html(_) | Event::InlineHtml(_) => Some(range),
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. })
+                if !is_safe_url(&dest_url) =>
+            {
+                destination_range(markdown, range, &dest_url)
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let (mut sanitized, end) =
+        removed
+            .iter()
+            .fold((String::new(), 0), |(mut sanitized, end), range| {
+                if range.start >= end {
+                    sanitized.push_str(&markdown[end..range.start]);
+                    (sanitized, range.end)
+                } else {
+                    (sanitized, end.max(range.end))
+                }
+            });
+    sanitized.push_str(&markdown[end.min(markdown.len())..]);
+    sanitized
+}
+
+/// Whether a link destination is safe to publish
+fn is_safe_url(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            matches!(scheme, "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+/// Where a link's destination is in the markdown, to remove it
+///
+/// Reference links have their destination elsewhere, so the whole link goes.
+fn destination_range(markdown: &str, link: Range<usize>, url: &str) -> Option<Range<usize>> {
+    match markdown[link.clone()].rfind(url) {
+        Some(offset) => Some(link.start + offset..link.start + offset + url.len()),
+        None => Some(link),
+    }
+}
+
+/// All submitted drafts, in submission order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SubmissionStore {
+    /// Drafts, whatever their status
+    pub drafts: Vector<Draft>,
+}
+
+impl SubmissionStore {
+    /// Load the drafts, or start an empty store if the file doesn't exist
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the JSON file
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the store or an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read submissions from {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse submissions from {}", path.display()))
+    }
+
+    /// Save the drafts
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write submissions to {}", path.display()))
+    }
+
+    /// Add a draft to the review queue
+    pub fn submit(&mut self, draft: Draft) {
+        self.drafts.push_back(Draft {
+            status: DraftStatus::Pending,
+            ..draft
+        });
+    }
+
+    /// Get a draft by its number
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - 1-based number of the draft, as shown by `cv submissions`
+    ///
+    /// # Returns
+    ///
+    /// The draft, or an error if there's no such draft
+    pub fn get(&self, number: usize) -> Result<&Draft> {
+        number
+            .checked_sub(1)
+            .and_then(|index| self.drafts.get(index))
+            .with_context(|| format!("No draft #{number} ({} in total)", self.drafts.len()))
+    }
+
+    /// Reject a pending draft
+    pub fn reject(&mut self, number: usize) -> Result<&Draft> {
+        self.set_status(number, DraftStatus::Rejected)
+    }
+
+    /// Turn a pending draft into an unpublished post
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - 1-based number of the draft
+    /// * `blog_dir` - Directory of the blog posts
+    ///
+    /// # Returns
+    ///
+    /// The path of the new post; an existing post is never overwritten
+    pub fn promote(&mut self, number: usize, blog_dir: &Path) -> Result<PathBuf> {
+        let draft = self.get(number)?;
+        if draft.status != DraftStatus::Pending {
+            anyhow::bail!("Draft #{} is already {}", number, draft.status);
+        }
+
+        let path = blog_dir.join(format!("{}.md", draft.slug));
+        if path.exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        fs::create_dir_all(blog_dir)
+            .with_context(|| format!("Failed to create {}", blog_dir.display()))?;
+        fs::write(&path, draft.to_post())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        self.set_status(number, DraftStatus::Promoted)?;
+        Ok(path)
+    }
+
+    fn set_status(&mut self, number: usize, status: DraftStatus) -> Result<&Draft> {
+        self.get(number)?;
+        let draft = &mut self.drafts[number - 1];
+        draft.status = status;
+        Ok(draft)
+    }
+}
+
+impl Draft {
+    /// The draft as an unpublished blog post with front matter
+    pub fn to_post(&self) -> String {
+        #[derive(Serialize)]
+        struct FrontMatter<'a> {
+            title: &'a str,
+            date: String,
+            author: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            excerpt: Option<&'a str>,
+            #[serde(skip_serializing_if = "Vector::is_empty")]
+            tags: &'a Vector<String>,
+            published: bool,
+        }
+
+        let front_matter = FrontMatter {
+            title: &self.title,
+            date: self.submitted_at.format("%Y-%m-%d").to_string(),
+            author: &self.author,
+            excerpt: self.excerpt.as_deref(),
+            tags: &self.tags,
+            published: false,
+        };
+        let yaml = serde_yaml::to_string(&front_matter).unwrap_or_default();
+        format!("---\n{}---\n\n{}\n", yaml, self.body.trim())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blog_posts::BlogPost;
+
+    fn submission(title: &str, body: &str) -> DraftSubmission {
+        DraftSubmission {
+            title: title.to_string(),
+            body: body.to_string(),
+            author: "Ada".to_string(),
+            contact: Some("ada@example.com".to_string()),
+            excerpt: None,
+            tags: vec!["Rust".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_sanitize_markdown() {
+        let markdown = "Hi <script>alert(1)</script> there\n\n<div onclick=\"x()\">block</div>\n\n[ok](https://example.com) [bad](javascript:alert(1)) ![img](data:image/png;base64,AA) [ref][x]\n\n[x]: vbscript:run\n\n```html\n<b>code stays</b>\n```\n";
+        let sanitized = sanitize_markdown(markdown);
+
+        assert!(!sanitized.contains("<script>"));
+        assert!(!sanitized.contains("onclick"));
+        assert!(!sanitized.contains("javascript:"));
+        assert!(!sanitized.contains("data:image"));
+        assert!(!sanitized.contains("[ref][x]"));
+        assert!(sanitized.contains("[ok](https://example.com)"));
+        assert!(sanitized.contains("[bad]()"));
+        assert!(sanitized.contains("<b>code stays</b>"));
+    }
+
+    #[test]
+    fn test_submission_validation() {
+        let draft = submission("  Why Rust?  ", "Because.")
+            .into_draft()
+            .unwrap();
+        assert_eq!(draft.title, "Why Rust?");
+        assert_eq!(draft.slug, "why-rust");
+        assert_eq!(draft.status, DraftStatus::Pending);
+
+        assert!(submission("", "Body").into_draft().is_err());
+        assert!(submission("Title", "<p>only html</p>")
+            .into_draft()
+            .is_err());
+        assert!(submission(&"a".repeat(MAX_FIELD_LENGTH + 1), "Body")
+            .into_draft()
+            .is_err());
+    }
+
+    #[test]
+    fn test_promote_writes_unpublished_post() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = SubmissionStore::default();
+        store.submit(
+            submission("Guest post", "Hello **world**")
+                .into_draft()
+                .unwrap(),
+        );
+        store.submit(submission("Spam", "Buy now").into_draft().unwrap());
+
+        let path = store.promote(1, dir.path()).unwrap();
+        assert_eq!(path, dir.path().join("guest-post.md"));
+        let post = BlogPost::from_markdown_file(&path).unwrap();
+        assert_eq!(post.title, "Guest post");
+        assert_eq!(post.author.as_deref(), Some("Ada"));
+        assert!(!post.is_published());
+        assert!(post.content.contains("<strong>world</strong>"));
+
+        assert!(store.promote(1, dir.path()).is_err());
+        assert_eq!(store.reject(2).unwrap().status, DraftStatus::Rejected);
+        assert!(store.reject(3).is_err());
+    }
+}
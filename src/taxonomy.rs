@@ -0,0 +1,472 @@
+//! Technology taxonomy
+//!
+//! `data/technologies.toml` lists technologies with a canonical name, aliases,
+//! an optional icon and an optional family:
+//!
+//! ```toml
+//! [[technology]]
+//! name = "Rust"
+//! aliases = ["rust-lang"]
+//! icon = ""
+//! language = true
+//!
+//! [[technology]]
+//! name = "Tokio"
+//! family = "Rust"
+//! ```
+//!
+//! CV skills, project technologies and blog post tags are all mapped to the
+//! canonical names, so "rust-lang" in a post and "Rust" in the CV are the same
+//! topic. That gives every technology one icon and one tag page, and lets the
+//! CV skills section link to the posts about each skill.
+//!
+//! A technology without an icon of its own shows its family's, and anything
+//! else the `generic_icon`, if there is one. Projects are labelled with the
+//! language they are written in, detected from their name and technologies.
+//!
+//! The list is read from `technologies.json` (the older format, a plain array
+//! in JSON), `technologies.toml` and `technologies.local.toml`, in that
+//! order, so a later file can add technologies or extend those of an earlier
+//! one without editing it.
+
+use anyhow::{Context, Result};
+use im::Vector;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::blog_posts::BlogPost;
+use crate::cv_data::{Cv, Project, Skill, SkillCategory};
+
+/// File names of the taxonomy in the data directory, in the order they're merged
+pub const TAXONOMY_FILES: [&str; 3] = [
+    "technologies.json",
+    "technologies.toml",
+    "technologies.local.toml",
+];
+
+/// Name of the generic icon in the sprite
+pub const GENERIC_ICON: &str = "technology";
+
+/// A technology with its canonical name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Technology {
+    /// Canonical name, e.g. `Rust`
+    pub name: String,
+    /// Other spellings that mean the same technology, e.g. `rust-lang`
+    #[serde(default)]
+    pub aliases: Vector<String>,
+    /// Icon shown next to the technology
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Technology whose icon is shown when this one has none, e.g. `Rust` for `Tokio`
+    #[serde(default)]
+    pub family: Option<String>,
+    /// Whether this is a programming language, which projects are labelled with
+    #[serde(default)]
+    pub language: bool,
+}
+
+/// The known technologies
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "TaxonomyFile")]
+pub struct Taxonomy {
+    /// Known technologies
+    pub technologies: Vector<Technology>,
+    /// Icon of the technologies that have none, not even through their family
+    pub generic_icon: Option<String>,
+}
+
+/// A taxonomy file: a list of technologies, or a table with the generic icon
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TaxonomyFile {
+    List(Vector<Technology>),
+    Table {
+        #[serde(default, alias = "technology")]
+        technologies: Vector<Technology>,
+        #[serde(default)]
+        generic_icon: Option<String>,
+    },
+}
+
+impl From<TaxonomyFile> for Taxonomy {
+    fn from(file: TaxonomyFile) -> Self {
+        match file {
+            TaxonomyFile::List(technologies) => Taxonomy {
+                technologies,
+                generic_icon: None,
+            },
+            TaxonomyFile::Table {
+                technologies,
+                generic_icon,
+            } => Taxonomy {
+                technologies,
+                generic_icon,
+            },
+        }
+    }
+}
+
+impl Taxonomy {
+    /// Load and merge the taxonomy files of a data directory
+    ///
+    /// # Arguments
+    ///
+    /// * `data_dir` - Directory with the [`TAXONOMY_FILES`]; missing ones are skipped
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the merged taxonomy, empty if there are no files
+    pub fn load(data_dir: &Path) -> Result<Self> {
+        let mut taxonomy = Taxonomy::default();
+        for path in TAXONOMY_FILES
+            .iter()
+            .map(|file| data_dir.join(file))
+            .filter(|path| path.exists())
+        {
+            taxonomy.extend(Self::from_file(&path)?);
+        }
+        Ok(taxonomy)
+    }
+
+    /// Load the taxonomy from a JSON or TOML file, by its extension
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the file
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the taxonomy or an error
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read technologies from {}", path.display()))?;
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&data).map_err(anyhow::Error::from),
+            _ => serde_json::from_str(&data).map_err(anyhow::Error::from),
+        };
+        parsed.with_context(|| format!("Failed to parse technologies from {}", path.display()))
+    }
+
+    /// Add the technologies of another taxonomy
+    ///
+    /// A technology that is already known, by its name or an alias, gets the
+    /// new aliases, and the icon, family and language flag where they are set.
+    pub fn extend(&mut self, other: Taxonomy) {
+        if other.generic_icon.is_some() {
+            self.generic_icon = other.generic_icon;
+        }
+        for technology in other.technologies {
+            let known = self.technologies.iter().position(|existing| {
+                existing.name.eq_ignore_ascii_case(&technology.name)
+                    || existing
+                        .aliases
+                        .iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(&technology.name))
+            });
+            let Some(index) = known else {
+                self.technologies.push_back(technology);
+                continue;
+            };
+            let existing = &mut self.technologies[index];
+            for alias in technology.aliases {
+                if !existing
+                    .aliases
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(&alias))
+                {
+                    existing.aliases.push_back(alias);
+                }
+            }
+            existing.icon = technology.icon.or(existing.icon.take());
+            existing.family = technology.family.or(existing.family.take());
+            existing.language |= technology.language;
+        }
+    }
+
+    /// Find a technology by its name or one of its aliases, ignoring case
+    pub fn find(&self, term: &str) -> Option<&Technology> {
+        let term = term.trim();
+        self.technologies
+            .iter()
+            .find(|technology| technology.name.eq_ignore_ascii_case(term))
+            .or_else(|| {
+                self.technologies.iter().find(|technology| {
+                    technology
+                        .aliases
+                        .iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(term))
+                })
+            })
+    }
+
+    /// The canonical name of a term, or the term itself if it isn't known
+    pub fn canonical(&self, term: &str) -> String {
+        self.find(term)
+            .map(|technology| technology.name.clone())
+            .unwrap_or_else(|| term.trim().to_string())
+    }
+
+    /// The technology a term, or its family, takes its icon from
+    fn icon_source(&self, term: &str) -> Option<&Technology> {
+        let mut technology = self.find(term);
+        // Bounded, so a family that loops back on itself ends
+        for _ in 0..=self.technologies.len() {
+            let current = technology?;
+            if current.icon.as_deref().is_some_and(|icon| !icon.is_empty()) {
+                return Some(current);
+            }
+            technology = current
+                .family
+                .as_deref()
+                .and_then(|family| self.find(family));
+        }
+        None
+    }
+
+    /// The icon of a technology: its own, its family's, or the generic one
+    pub fn icon(&self, term: &str) -> Option<&str> {
+        self.icon_source(term)
+            .and_then(|technology| technology.icon.as_deref())
+            .or(self.generic_icon.as_deref())
+            .filter(|icon| !icon.is_empty())
+    }
+
+    /// Name of a technology's icon in the sprite, if it has one
+    ///
+    /// That is the name of the technology the icon comes from, or
+    /// [`GENERIC_ICON`].
+    pub fn icon_name(&self, term: &str) -> Option<String> {
+        match self.icon_source(term) {
+            Some(technology) => Some(technology.name.clone()),
+            None => self.icon(term).map(|_| GENERIC_ICON.to_string()),
+        }
+    }
+
+    /// Detect the language a project is written in
+    ///
+    /// A language named in the project name, at word boundaries, comes first,
+    /// e.g. "Rust" for `cv - rust`. After that, the first of the project's
+    /// technologies that is a language, or belongs to a language's family.
+    ///
+    /// # Arguments
+    ///
+    /// * `project_name` - The project name, which may contain language information
+    /// * `technologies` - Technologies used in the project
+    ///
+    /// # Returns
+    ///
+    /// The canonical name of the language, or None if no language could be detected
+    pub fn detect_language(
+        &self,
+        project_name: &str,
+        technologies: &Vector<String>,
+    ) -> Option<String> {
+        // Longer spellings first, so `javascript` isn't taken for `java`
+        let mut spellings = self
+            .technologies
+            .iter()
+            .filter(|technology| technology.language)
+            .flat_map(|technology| {
+                std::iter::once(&technology.name)
+                    .chain(technology.aliases.iter())
+                    .map(move |spelling| (spelling.to_lowercase(), &technology.name))
+            })
+            .collect::<Vec<_>>();
+        spellings.sort_by_key(|(spelling, _)| std::cmp::Reverse(spelling.len()));
+
+        let name = project_name.to_lowercase();
+        let named = spellings.iter().find(|(spelling, _)| {
+            Regex::new(&format!(
+                r"(?:^|[\s_\-]){}(?:$|[\s_\-])",
+                regex::escape(spelling)
+            ))
+            .is_ok_and(|word| word.is_match(&name))
+        });
+        if let Some((_, language)) = named {
+            return Some(language.to_string());
+        }
+
+        technologies.iter().find_map(|term| self.language_of(term))
+    }
+
+    /// The language a technology is, or belongs to through its family
+    fn language_of(&self, term: &str) -> Option<String> {
+        let mut technology = self.find(term);
+        for _ in 0..=self.technologies.len() {
+            let current = technology?;
+            if current.language {
+                return Some(current.name.clone());
+            }
+            technology = current
+                .family
+                .as_deref()
+                .and_then(|family| self.find(family));
+        }
+        None
+    }
+
+    /// Map a list of terms to canonical names, dropping duplicates
+    pub fn canonicalize(&self, terms: &Vector<String>) -> Vector<String> {
+        let mut canonical = Vector::new();
+        for term in terms.iter().map(|term| self.canonical(term)) {
+            if !canonical.contains(&term) {
+                canonical.push_back(term);
+            }
+        }
+        canonical
+    }
+
+    /// Map skills to canonical names, dropping duplicates
+    ///
+    /// The first of the skills that share a canonical name keeps its details.
+    pub fn canonicalize_skills(&self, skills: &Vector<Skill>) -> Vector<Skill> {
+        let mut canonical: Vector<Skill> = Vector::new();
+        for skill in skills.iter() {
+            let name = self.canonical(&skill.name);
+            if !canonical.iter().any(|existing| existing.name == name) {
+                canonical.push_back(Skill {
+                    name,
+                    ..skill.clone()
+                });
+            }
+        }
+        canonical
+    }
+
+    /// Use canonical names for the skills and project technologies of a CV
+    pub fn canonicalize_cv(&self, cv: &Cv) -> Cv {
+        if self.technologies.is_empty() {
+            return cv.clone();
+        }
+
+        Cv {
+            skill_categories: cv
+                .skill_categories
+                .iter()
+                .map(|category| SkillCategory {
+                    skills: self.canonicalize_skills(&category.skills),
+                    ..category.clone()
+                })
+                .collect(),
+            projects: cv
+                .projects
+                .iter()
+                .map(|project| Project {
+                    technologies: self.canonicalize(&project.technologies),
+                    ..project.clone()
+                })
+                .collect(),
+            ..cv.clone()
+        }
+    }
+
+    /// Use canonical names for the tags of blog posts
+    pub fn canonicalize_tags(&self, posts: &Vector<BlogPost>) -> Vector<BlogPost> {
+        posts
+            .iter()
+            .map(|post| BlogPost {
+                tags: self.canonicalize(&post.tags),
+                ..post.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use im::vector;
+
+    #[test]
+    fn test_canonical_names() {
+        let taxonomy: Taxonomy = serde_json::from_str(
+            r#"[
+                { "name": "Rust", "aliases": ["rust-lang"], "icon": "R" },
+                { "name": "C++", "aliases": ["cpp"] }
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(taxonomy.canonical("rust"), "Rust");
+        assert_eq!(taxonomy.canonical("Rust-Lang"), "Rust");
+        assert_eq!(taxonomy.canonical("cpp"), "C++");
+        assert_eq!(taxonomy.canonical("Zig"), "Zig");
+        assert_eq!(taxonomy.icon("rust-lang"), Some("R"));
+        assert_eq!(taxonomy.icon("cpp"), None);
+        assert_eq!(
+            taxonomy.canonicalize(&vector![
+                "rust".to_string(),
+                "Rust".to_string(),
+                "cpp".to_string()
+            ]),
+            vector!["Rust".to_string(), "C++".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_icon_fallbacks() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("technologies.json"),
+            r#"[{ "name": "Rust", "aliases": ["rust-lang"], "icon": "R", "language": true }]"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("technologies.toml"),
+            r#"
+generic_icon = "*"
+
+[[technology]]
+name = "Tokio"
+aliases = ["tokio-rs"]
+family = "Rust"
+
+[[technology]]
+name = "rust-lang"
+aliases = ["rustlang"]
+"#,
+        )
+        .unwrap();
+        let taxonomy = Taxonomy::load(dir.path()).unwrap();
+
+        // The later file extends the technology it names by an alias
+        assert_eq!(taxonomy.technologies.len(), 2);
+        assert_eq!(taxonomy.canonical("rustlang"), "Rust");
+
+        // Exact name, alias, family, generic
+        assert_eq!(taxonomy.icon("Rust"), Some("R"));
+        assert_eq!(taxonomy.icon_name("rust-lang").as_deref(), Some("Rust"));
+        assert_eq!(taxonomy.icon("tokio-rs"), Some("R"));
+        assert_eq!(taxonomy.icon_name("Tokio").as_deref(), Some("Rust"));
+        assert_eq!(taxonomy.icon("Zig"), Some("*"));
+        assert_eq!(taxonomy.icon_name("Zig").as_deref(), Some(GENERIC_ICON));
+
+        // Languages come from the project name, then from technologies and their families
+        assert_eq!(
+            taxonomy.detect_language("server - rust", &Vector::new()),
+            Some("Rust".to_string())
+        );
+        assert_eq!(
+            taxonomy.detect_language("server", &vector!["Tokio".to_string()]),
+            Some("Rust".to_string())
+        );
+        assert_eq!(taxonomy.detect_language("trusty", &Vector::new()), None);
+    }
+
+    #[test]
+    fn test_shipped_taxonomy() {
+        let taxonomy = Taxonomy::load(Path::new("data")).unwrap();
+        assert_eq!(taxonomy.canonical("cpp"), "C++");
+        assert_eq!(
+            taxonomy.detect_language("cv - scala3", &Vector::new()),
+            Some("Scala".to_string())
+        );
+        assert_eq!(
+            taxonomy.detect_language("widgets", &vector!["javascript".to_string()]),
+            Some("JavaScript".to_string())
+        );
+    }
+}
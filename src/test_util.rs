@@ -0,0 +1,325 @@
+//! End-to-end test harness
+//!
+//! Behind the `test-util` feature, so this crate's integration tests and
+//! downstream tools can test against the real server instead of mocking it.
+//! [`TestServer::start`] serves a temporary directory with the
+//! [dev server](crate::dev_server) on a free port, with every API enabled:
+//!
+//! - the CV database is created and migrated, and seeded with a CV
+//! - the served site has a home page and the post [`SEED_POST`], so
+//!   reactions have something to react to
+//! - testimonials, drafts and reactions are stored in the directory
+//! - the applications API and the submission inbox require [`TEST_TOKEN`]
+//!
+//! The admin API isn't served, its operations act on the working directory.
+//! There is no image API on the server, so there is none to test either.
+//!
+//! Requests are made with blocking `reqwest` builders, so tests stay
+//! synchronous; with the `client` feature, [`TestServer::client`] returns an
+//! [`ApiClient`](crate::client::ApiClient) for async tests. The server is
+//! stopped and its directory removed when the [`TestServer`] is dropped.
+
+use anyhow::{anyhow, Context, Result};
+use reqwest::blocking::{Client, RequestBuilder};
+use reqwest::Method;
+use std::fs;
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use tempfile::TempDir;
+
+use crate::cv_data::Cv;
+use crate::db::Database;
+use crate::dev_server::{serve_listener, DevServerOptions};
+use crate::reactions::REACTIONS_FILE;
+use crate::secrets::Secret;
+use crate::submissions::{SubmissionStore, SUBMISSIONS_FILE};
+use crate::testimonials::{TestimonialStore, TESTIMONIALS_FILE};
+
+/// Bearer token of the applications API and the submission inbox
+pub const TEST_TOKEN: &str = "test-token";
+
+/// Slug of the blog post the served site has
+pub const SEED_POST: &str = "hello-world";
+
+/// File name of the CV database in the server's directory
+const DB_FILE: &str = "cv.db";
+
+/// A running server over a temporary site and database
+pub struct TestServer {
+    dir: TempDir,
+    port: u16,
+    http: Client,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<Result<()>>>,
+}
+
+impl TestServer {
+    /// Start a server seeded with a minimal CV
+    ///
+    /// # Returns
+    ///
+    /// The running server
+    pub fn start() -> Result<Self> {
+        Self::start_with(&Cv::create_minimal())
+    }
+
+    /// Start a server seeded with `cv`
+    ///
+    /// # Arguments
+    ///
+    /// * `cv` - The CV in the database
+    ///
+    /// # Returns
+    ///
+    /// The running server, or an error if the site or database can't be set up
+    pub fn start_with(cv: &Cv) -> Result<Self> {
+        let dir = tempfile::tempdir().context("Failed to create test directory")?;
+        let root = dir.path().join("dist");
+        fs::create_dir_all(root.join("blog"))?;
+        fs::write(root.join("index.html"), "<!doctype html><h1>Home</h1>")?;
+        fs::write(
+            root.join("blog").join(format!("{SEED_POST}.html")),
+            "<!doctype html><h1>Hello, world</h1>",
+        )?;
+
+        let db_path = dir.path().join(DB_FILE);
+        Database::open(&db_path)?.save_cv(cv)?;
+
+        let listener = TcpListener::bind(("127.0.0.1", 0)).context("Failed to bind test server")?;
+        let port = listener.local_addr()?.port();
+        let token = Secret::new(TEST_TOKEN);
+        let options = DevServerOptions::new(&root, port)
+            .with_testimonials(dir.path().join(TESTIMONIALS_FILE))
+            .with_submissions(dir.path().join(SUBMISSIONS_FILE), Some(token.clone()))
+            .with_reactions(dir.path().join(REACTIONS_FILE))
+            .with_applications(&db_path, token);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || serve_listener(listener, &options, || Ok(()), &stop)
+        });
+
+        Ok(TestServer {
+            dir,
+            port,
+            http: Client::new(),
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    /// Where the server is, e.g. `http://127.0.0.1:40123`
+    pub fn base_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.port)
+    }
+
+    /// URL of a path on the server
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url(), path)
+    }
+
+    /// The served directory
+    pub fn root(&self) -> PathBuf {
+        self.dir.path().join("dist")
+    }
+
+    /// Add a page to the served site
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the page relative to the site root, e.g. `blog/post.html`
+    /// * `html` - Contents of the page
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure
+    pub fn write_page(&self, path: &str, html: &str) -> Result<()> {
+        let file = self.root().join(path);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&file, html).with_context(|| format!("Failed to write {}", file.display()))
+    }
+
+    /// Open the server's CV database
+    pub fn database(&self) -> Result<Database> {
+        Database::open(self.dir.path().join(DB_FILE))
+    }
+
+    /// The testimonials submitted to the server
+    pub fn testimonials(&self) -> Result<TestimonialStore> {
+        TestimonialStore::load(self.path(TESTIMONIALS_FILE))
+    }
+
+    /// The drafts submitted to the server
+    pub fn submissions(&self) -> Result<SubmissionStore> {
+        SubmissionStore::load(self.path(SUBMISSIONS_FILE))
+    }
+
+    /// A request without a token
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - Request method
+    /// * `path` - Path on the server, e.g. `/api/reactions/hello-world`
+    ///
+    /// # Returns
+    ///
+    /// The request, ready for a body and `send()`
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, self.url(path))
+    }
+
+    /// A request sending [`TEST_TOKEN`] as `Authorization: Bearer`
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - Request method
+    /// * `path` - Path on the server, e.g. `/api/applications`
+    ///
+    /// # Returns
+    ///
+    /// The request, ready for a body and `send()`
+    pub fn authorized(&self, method: Method, path: &str) -> RequestBuilder {
+        self.request(method, path).bearer_auth(TEST_TOKEN)
+    }
+
+    /// A typed client for the server, with [`TEST_TOKEN`]
+    #[cfg(feature = "client")]
+    pub fn client(&self) -> Result<crate::client::ApiClient> {
+        Ok(crate::client::ApiClient::new(&self.base_url())?.with_token(TEST_TOKEN))
+    }
+
+    /// Stop the server
+    ///
+    /// # Returns
+    ///
+    /// The error the server stopped with, if any
+    pub fn stop(mut self) -> Result<()> {
+        self.shut_down()
+    }
+
+    fn shut_down(&mut self) -> Result<()> {
+        let Some(thread) = self.thread.take() else {
+            return Ok(());
+        };
+        self.stop.store(true, Ordering::SeqCst);
+        // The server checks the flag once the next connection comes in
+        let _ = TcpStream::connect(("127.0.0.1", self.port));
+        thread
+            .join()
+            .map_err(|_| anyhow!("The test server panicked"))?
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.path().join(file)
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.shut_down();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::applications::{Application, NewApplication, APPLICATIONS_PATH};
+    use crate::reactions::{Reaction, ReactionCounts, ReactionSubmission, REACTIONS_PATH};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_server_serves_seeded_site() {
+        let server = TestServer::start().unwrap();
+        let response = server.request(Method::GET, "/").send().unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.text().unwrap().contains("Home"));
+
+        let reactions = format!("{REACTIONS_PATH}/{SEED_POST}");
+        let counts: ReactionCounts = server
+            .request(Method::POST, &reactions)
+            .json(&ReactionSubmission {
+                reaction: Reaction::Like,
+            })
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(counts.like, 1);
+        let response = server
+            .request(Method::GET, &format!("{REACTIONS_PATH}/missing"))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        assert_eq!(
+            server
+                .database()
+                .unwrap()
+                .load_cv()
+                .unwrap()
+                .personal_info
+                .name,
+            Cv::create_minimal().personal_info.name
+        );
+        server.stop().unwrap();
+    }
+
+    #[test]
+    fn test_spoofed_forwarded_for_is_ignored() {
+        let server = TestServer::start().unwrap();
+        let reactions = format!("{REACTIONS_PATH}/{SEED_POST}");
+        let react = |address: usize| {
+            server
+                .request(Method::POST, &reactions)
+                .header("X-Forwarded-For", format!("203.0.113.{address}"))
+                .json(&ReactionSubmission {
+                    reaction: Reaction::Like,
+                })
+                .send()
+                .unwrap()
+        };
+
+        // Every request comes from the same address, whatever the header says
+        for address in 1..=20 {
+            let counts: ReactionCounts = react(address).json().unwrap();
+            assert_eq!(counts.like, 1);
+        }
+        assert_eq!(react(21).status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[test]
+    fn test_server_requires_token() {
+        let server = TestServer::start().unwrap();
+        let response = server
+            .request(Method::GET, APPLICATIONS_PATH)
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = server
+            .authorized(Method::POST, APPLICATIONS_PATH)
+            .json(&NewApplication {
+                company: "Acme".to_string(),
+                role: "Engineer".to_string(),
+                ..Default::default()
+            })
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let applications: Vec<Application> = server
+            .authorized(Method::GET, APPLICATIONS_PATH)
+            .send()
+            .unwrap()
+            .json()
+            .unwrap();
+        assert_eq!(applications.len(), 1);
+        assert_eq!(server.database().unwrap().applications().unwrap().len(), 1);
+    }
+}
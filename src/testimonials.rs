@@ -0,0 +1,262 @@
+//! Testimonials and their moderation
+//!
+//! Testimonials live in `data/testimonials.json`, next to the CV data. New
+//! ones arrive through `POST /api/testimonials` on `cv serve` and are stored as
+//! `pending`; nothing is published until it has been approved with
+//! `cv testimonials approve <n>`. Only approved testimonials reach the CV page
+//! and the PDF, and the whole section can be left out of a build with the
+//! `testimonials` public data field.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use im::Vector;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// File name of the testimonials in the data directory
+pub const TESTIMONIALS_FILE: &str = "testimonials.json";
+
+/// Path the dev server accepts submissions on
+pub const SUBMISSION_PATH: &str = "/api/testimonials";
+
+/// Longest accepted quote, in characters
+pub const MAX_QUOTE_LENGTH: usize = 1000;
+
+/// Longest accepted author name or relation, in characters
+const MAX_FIELD_LENGTH: usize = 120;
+
+/// Moderation state of a testimonial
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestimonialStatus {
+    /// Submitted, waiting for moderation
+    #[default]
+    Pending,
+    /// Approved for publishing
+    Approved,
+    /// Rejected, kept so the same submission isn't reviewed twice
+    Rejected,
+}
+
+/// An endorsement from someone the CV owner worked with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Testimonial {
+    /// What they said
+    pub quote: String,
+    /// Who said it
+    pub author: String,
+    /// How they know the CV owner, e.g. "Manager at Acme"
+    #[serde(default)]
+    pub relation: Option<String>,
+    /// Link to the author, e.g. their LinkedIn profile
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Moderation state
+    #[serde(default)]
+    pub status: TestimonialStatus,
+    /// When the testimonial was submitted
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+impl Testimonial {
+    /// Whether the testimonial may be published
+    pub fn is_approved(&self) -> bool {
+        self.status == TestimonialStatus::Approved
+    }
+}
+
+/// A testimonial as submitted to the dev server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestimonialSubmission {
+    pub quote: String,
+    pub author: String,
+    #[serde(default)]
+    pub relation: Option<String>,
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+impl TestimonialSubmission {
+    /// Check the submission and turn it into a pending testimonial
+    ///
+    /// # Returns
+    ///
+    /// The testimonial, or an error describing what's wrong with the submission
+    pub fn into_testimonial(self) -> Result<Testimonial> {
+        let trimmed = |value: Option<String>| {
+            value
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        let quote = self.quote.trim().to_string();
+        let author = self.author.trim().to_string();
+        let relation = trimmed(self.relation);
+        let link = trimmed(self.link);
+
+        if quote.is_empty() || author.is_empty() {
+            anyhow::bail!("A testimonial needs a quote and an author");
+        }
+        if quote.chars().count() > MAX_QUOTE_LENGTH {
+            anyhow::bail!("Quotes are limited to {} characters", MAX_QUOTE_LENGTH);
+        }
+        if [Some(&author), relation.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|field| field.chars().count() > MAX_FIELD_LENGTH)
+        {
+            anyhow::bail!(
+                "Author and relation are limited to {} characters",
+                MAX_FIELD_LENGTH
+            );
+        }
+        if let Some(ref link) = link {
+            if !link.starts_with("https://") && !link.starts_with("http://") {
+                anyhow::bail!("Links must be http or https URLs");
+            }
+        }
+
+        Ok(Testimonial {
+            quote,
+            author,
+            relation,
+            link,
+            status: TestimonialStatus::Pending,
+            submitted_at: Some(Utc::now()),
+        })
+    }
+}
+
+/// All testimonials, in submission order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TestimonialStore {
+    /// Testimonials, whatever their status
+    pub testimonials: Vector<Testimonial>,
+}
+
+impl TestimonialStore {
+    /// Load the testimonials, or start an empty store if the file doesn't exist
+    ///
+    /// Unlike the caches, a malformed file is an error: saving over it would
+    /// lose the testimonials it holds.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to the JSON file
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the store or an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read testimonials from {}", path.display()))?;
+        serde_json::from_str(&data)
+            .with_context(|| format!("Failed to parse testimonials from {}", path.display()))
+    }
+
+    /// Save the testimonials
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write testimonials to {}", path.display()))
+    }
+
+    /// Add a testimonial to the moderation queue
+    pub fn submit(&mut self, testimonial: Testimonial) {
+        self.testimonials.push_back(Testimonial {
+            status: TestimonialStatus::Pending,
+            ..testimonial
+        });
+    }
+
+    /// Change the status of a testimonial
+    ///
+    /// # Arguments
+    ///
+    /// * `number` - 1-based number of the testimonial, as shown by `cv testimonials`
+    /// * `status` - The new status
+    ///
+    /// # Returns
+    ///
+    /// The updated testimonial, or an error if there's no such testimonial
+    pub fn moderate(&mut self, number: usize, status: TestimonialStatus) -> Result<&Testimonial> {
+        let count = self.testimonials.len();
+        let testimonial = number
+            .checked_sub(1)
+            .and_then(|index| self.testimonials.get_mut(index))
+            .with_context(|| format!("No testimonial #{number} ({count} in total)"))?;
+        testimonial.status = status;
+        Ok(testimonial)
+    }
+
+    /// Approved testimonials, in submission order
+    pub fn approved(&self) -> Vector<Testimonial> {
+        self.testimonials
+            .iter()
+            .filter(|testimonial| testimonial.is_approved())
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn submission(quote: &str, link: Option<&str>) -> TestimonialSubmission {
+        TestimonialSubmission {
+            quote: quote.to_string(),
+            author: " Grace ".to_string(),
+            relation: Some("".to_string()),
+            link: link.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_submission_validation() {
+        let testimonial = submission("Great to work with.", Some("https://example.com"))
+            .into_testimonial()
+            .unwrap();
+        assert_eq!(testimonial.author, "Grace");
+        assert_eq!(testimonial.relation, None);
+        assert_eq!(testimonial.status, TestimonialStatus::Pending);
+
+        assert!(submission("  ", None).into_testimonial().is_err());
+        assert!(submission("Hi", Some("javascript:alert(1)"))
+            .into_testimonial()
+            .is_err());
+        assert!(submission(&"a".repeat(MAX_QUOTE_LENGTH + 1), None)
+            .into_testimonial()
+            .is_err());
+    }
+
+    #[test]
+    fn test_moderation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(TESTIMONIALS_FILE);
+
+        let mut store = TestimonialStore::load(&path).unwrap();
+        for quote in ["First", "Second"] {
+            store.submit(submission(quote, None).into_testimonial().unwrap());
+        }
+        assert!(store.approved().is_empty());
+
+        store.moderate(2, TestimonialStatus::Approved).unwrap();
+        store.moderate(1, TestimonialStatus::Rejected).unwrap();
+        assert!(store.moderate(3, TestimonialStatus::Approved).is_err());
+        assert!(store.moderate(0, TestimonialStatus::Approved).is_err());
+        store.save(&path).unwrap();
+
+        let approved = TestimonialStore::load(&path).unwrap().approved();
+        assert_eq!(approved.len(), 1);
+        assert_eq!(approved[0].quote, "Second");
+    }
+}
@@ -0,0 +1,238 @@
+//! Runtime themes
+//!
+//! The built-in templates are compiled into the binary with askama. A theme
+//! is a directory that replaces some or all of them at runtime, so themes can
+//! be switched or installed without recompiling:
+//!
+//! ```text
+//! themes/minimal/
+//! ├── theme.toml       # manifest
+//! ├── templates/       # Tera templates named like the built-ins (cv.html, blog_post.html, ...)
+//! └── assets/          # copied over the static assets (css/, js/, img/, ...)
+//! ```
+//!
+//! Pages the theme has no template for fall back to the built-in template.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tera::Tera;
+
+use crate::html_generator::filters::register_tera_filters;
+use crate::site_config::SiteConfig;
+
+/// Name of the theme manifest file
+pub const THEME_MANIFEST: &str = "theme.toml";
+
+/// Directory themes are looked up in when no explicit directory is configured
+pub const DEFAULT_THEMES_DIR: &str = "themes";
+
+/// Theme manifest (`theme.toml`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeManifest {
+    /// Theme name
+    pub name: String,
+    /// Theme version
+    pub version: String,
+    /// Short description
+    pub description: Option<String>,
+    /// Theme author
+    pub author: Option<String>,
+    /// Template directory relative to the theme root (default: "templates")
+    pub templates_dir: Option<String>,
+    /// Asset directory relative to the theme root (default: "assets")
+    pub assets_dir: Option<String>,
+}
+
+/// A loaded theme with its parsed templates
+#[derive(Debug)]
+pub struct Theme {
+    /// Theme root directory
+    pub root: PathBuf,
+    /// Parsed manifest
+    pub manifest: ThemeManifest,
+    tera: Tera,
+}
+
+impl Theme {
+    /// Load a theme from its directory
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Theme directory containing `theme.toml`
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the loaded theme or an error
+    pub fn load<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let manifest_path = root.join(THEME_MANIFEST);
+
+        let content = fs::read_to_string(&manifest_path).with_context(|| {
+            format!("Failed to read theme manifest {}", manifest_path.display())
+        })?;
+        let manifest: ThemeManifest = toml::from_str(&content).with_context(|| {
+            format!("Failed to parse theme manifest {}", manifest_path.display())
+        })?;
+
+        let templates_dir = root.join(manifest.templates_dir.as_deref().unwrap_or("templates"));
+        let mut tera = if templates_dir.is_dir() {
+            let glob = format!("{}/**/*.html", templates_dir.display());
+            Tera::new(&glob).with_context(|| {
+                format!("Failed to load templates for theme '{}'", manifest.name)
+            })?
+        } else {
+            Tera::default()
+        };
+        register_tera_filters(&mut tera);
+
+        Ok(Theme {
+            root,
+            manifest,
+            tera,
+        })
+    }
+
+    /// Load the theme selected in the site configuration, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `site_config` - Site configuration
+    ///
+    /// # Returns
+    ///
+    /// The selected theme, or None when the built-in templates are used
+    pub fn from_site_config(site_config: &SiteConfig) -> Result<Option<Self>> {
+        let theme_config = match site_config.theme {
+            Some(ref theme_config) => theme_config,
+            None => return Ok(None),
+        };
+
+        let mut name = Path::new(&theme_config.name).components();
+        if !matches!(
+            (name.next(), name.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            anyhow::bail!(
+                "Invalid theme name '{}': must be a single directory name",
+                theme_config.name
+            );
+        }
+
+        let themes_dir = theme_config
+            .directory
+            .as_deref()
+            .unwrap_or(DEFAULT_THEMES_DIR);
+
+        Theme::load(Path::new(themes_dir).join(&theme_config.name))
+            .with_context(|| format!("Failed to load theme '{}'", theme_config.name))
+            .map(Some)
+    }
+
+    /// Whether the theme overrides the given template
+    pub fn has_template(&self, name: &str) -> bool {
+        self.tera.get_template_names().any(|t| t == name)
+    }
+
+    /// Render a theme template
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Template name (e.g. "cv.html")
+    /// * `context` - Values made available to the template
+    ///
+    /// # Returns
+    ///
+    /// The rendered HTML
+    pub fn render<T: Serialize>(&self, name: &str, context: &T) -> Result<String> {
+        let context = tera::Context::from_serialize(context)
+            .context("Failed to build theme template context")?;
+
+        self.tera.render(name, &context).with_context(|| {
+            format!(
+                "Failed to render {} from theme '{}'",
+                name, self.manifest.name
+            )
+        })
+    }
+
+    /// Directory with the theme's templates
+    pub fn templates_dir(&self) -> PathBuf {
+        self.root.join(
+            self.manifest
+                .templates_dir
+                .as_deref()
+                .unwrap_or("templates"),
+        )
+    }
+
+    /// Directory with the theme's static assets
+    pub fn assets_dir(&self) -> PathBuf {
+        self.root
+            .join(self.manifest.assets_dir.as_deref().unwrap_or("assets"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::site_config::ThemeConfig;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn write_theme(dir: &Path) {
+        fs::create_dir_all(dir.join("templates")).unwrap();
+        fs::write(
+            dir.join(THEME_MANIFEST),
+            "name = \"minimal\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("templates/cv.html"),
+            "<h1>{{ cv.name }}</h1><p>{{ lang }}</p>{{ stars | humanize }}",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_theme_renders_overridden_templates() {
+        let dir = TempDir::new().unwrap();
+        write_theme(dir.path());
+
+        let theme = Theme::load(dir.path()).unwrap();
+        assert_eq!(theme.manifest.name, "minimal");
+        assert!(theme.has_template("cv.html"));
+        assert!(!theme.has_template("blog.html"));
+        assert_eq!(theme.assets_dir(), dir.path().join("assets"));
+
+        let html = theme
+            .render(
+                "cv.html",
+                &json!({ "cv": { "name": "Jane" }, "lang": "da", "stars": 2500 }),
+            )
+            .unwrap();
+        assert_eq!(html, "<h1>Jane</h1><p>da</p>2.5k");
+    }
+
+    #[test]
+    fn test_theme_from_site_config() {
+        let dir = TempDir::new().unwrap();
+        write_theme(&dir.path().join("minimal"));
+
+        let mut site_config = SiteConfig::default();
+        assert!(Theme::from_site_config(&site_config).unwrap().is_none());
+
+        site_config.theme = Some(ThemeConfig {
+            name: "minimal".to_string(),
+            directory: Some(dir.path().to_string_lossy().to_string()),
+        });
+        assert!(Theme::from_site_config(&site_config).unwrap().is_some());
+
+        site_config.theme = Some(ThemeConfig {
+            name: "../minimal".to_string(),
+            directory: Some(dir.path().to_string_lossy().to_string()),
+        });
+        assert!(Theme::from_site_config(&site_config).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Color scheme previews
+//!
+//! `cv theme preview` fetches a scheme like the build does and renders it on
+//! a standalone page: the terminal palette as swatches, the site's color
+//! roles, and a sample CV section styled with the same CSS variables the
+//! generated `colorscheme.css` sets. Schemes can be auditioned without
+//! changing the site config or rebuilding the site.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::colorscheme_provider::{ColorPalette, ToCss};
+use crate::html_generator::filters::slug_str;
+
+/// Directory in the system's temporary directory previews are written to
+pub const PREVIEW_DIR: &str = "cv-theme-preview";
+
+/// The preview page of a color scheme
+///
+/// # Arguments
+///
+/// * `name` - Name of the scheme, for the title
+/// * `provider` - Provider the scheme came from
+/// * `palette` - The scheme's colors
+///
+/// # Returns
+///
+/// A standalone HTML page
+pub fn preview_html(name: &str, provider: &str, palette: &ColorPalette) -> String {
+    let ansi = [
+        ("black", &palette.black),
+        ("red", &palette.red),
+        ("green", &palette.green),
+        ("yellow", &palette.yellow),
+        ("blue", &palette.blue),
+        ("magenta", &palette.magenta),
+        ("cyan", &palette.cyan),
+        ("white", &palette.white),
+        ("bright black", &palette.bright_black),
+        ("bright red", &palette.bright_red),
+        ("bright green", &palette.bright_green),
+        ("bright yellow", &palette.bright_yellow),
+        ("bright blue", &palette.bright_blue),
+        ("bright magenta", &palette.bright_magenta),
+        ("bright cyan", &palette.bright_cyan),
+        ("bright white", &palette.bright_white),
+    ];
+    let special = [
+        ("background", Some(&palette.background)),
+        ("foreground", Some(&palette.foreground)),
+        ("cursor", palette.cursor.as_ref()),
+        ("selection", palette.selection.as_ref()),
+    ];
+    let swatches = |colors: Vec<(&str, &String)>| {
+        colors
+            .into_iter()
+            .map(|(label, color)| {
+                let color = escape_html(color);
+                format!(
+                    "<li><span class=\"swatch\" style=\"background: {color}\"></span>{label}<code>{color}</code></li>"
+                )
+            })
+            .collect::<String>()
+    };
+    let ansi = swatches(ansi.to_vec());
+    let special = swatches(
+        special
+            .into_iter()
+            .filter_map(|(label, color)| color.map(|color| (label, color)))
+            .collect(),
+    );
+    let roles = [
+        "primary",
+        "secondary",
+        "accent",
+        "muted",
+        "success",
+        "warning",
+        "error",
+    ]
+    .iter()
+    .map(|role| {
+        format!("<li><span class=\"swatch\" style=\"background: var(--color-{role})\"></span>{role}</li>")
+    })
+    .collect::<String>();
+
+    format!(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="robots" content="noindex">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{name} - theme preview</title>
+<style>
+{variables}
+body {{ font-family: system-ui, sans-serif; max-width: 56rem; margin: 2rem auto; padding: 0 1rem; background: var(--color-background); color: var(--color-text); }}
+h1, h2, h3 {{ color: var(--color-primary); }}
+a {{ color: var(--color-secondary); }}
+ul.swatches {{ list-style: none; padding: 0; display: grid; grid-template-columns: repeat(auto-fill, minmax(11rem, 1fr)); gap: 0.5rem; }}
+ul.swatches li {{ display: flex; align-items: center; gap: 0.5rem; }}
+ul.swatches code {{ margin-left: auto; color: var(--color-muted); }}
+.swatch {{ display: inline-block; width: 1.5rem; height: 1.5rem; border: 1px solid var(--color-border); border-radius: 0.25rem; }}
+.sample {{ background: var(--color-card-background); border: 1px solid var(--color-border); border-radius: 0.5rem; padding: 1rem 1.5rem; }}
+.subtitle, .dates {{ color: var(--color-muted); }}
+.tag {{ display: inline-block; padding: 0.1rem 0.6rem; margin: 0 0.3rem 0.3rem 0; border-radius: 1rem; border: 1px solid var(--color-accent); color: var(--color-accent); }}
+.button {{ display: inline-block; padding: 0.4rem 1rem; border-radius: 0.3rem; background: var(--color-primary); color: var(--color-background); text-decoration: none; }}
+::selection {{ background: var(--color-selection, var(--color-surface)); }}
+</style>
+</head>
+<body>
+<h1>{name}</h1>
+<p class="subtitle">From {provider}</p>
+<h2>Palette</h2>
+<ul class="swatches">{ansi}</ul>
+<ul class="swatches">{special}</ul>
+<h2>Site colors</h2>
+<ul class="swatches">{roles}</ul>
+<h2>Sample</h2>
+<section class="sample">
+<h2>Ada Lovelace</h2>
+<p class="subtitle">Software Engineer · London</p>
+<p>Engineer with a taste for <a href="#">analytical engines</a> and clear documentation.</p>
+<h3>Lead Engineer, Analytical Engines Ltd</h3>
+<p class="dates">2021 – present</p>
+<ul>
+<li>Designed the first published algorithm for the engine</li>
+<li>Cut build times from hours to minutes</li>
+</ul>
+<p><span class="tag">Rust</span><span class="tag">Typst</span><span class="tag">SQLite</span></p>
+<p><a class="button" href="#">Download CV</a></p>
+</section>
+</body>
+</html>
+"##,
+        name = escape_html(name),
+        provider = escape_html(provider),
+        variables = palette.to_css_variables(),
+    )
+}
+
+/// Write the preview page of a color scheme
+///
+/// # Arguments
+///
+/// * `dir` - Directory to write the page to, created if needed
+/// * `name` - Name of the scheme
+/// * `provider` - Provider the scheme came from
+/// * `palette` - The scheme's colors
+///
+/// # Returns
+///
+/// The path of the page
+pub fn write_preview(
+    dir: &Path,
+    name: &str,
+    provider: &str,
+    palette: &ColorPalette,
+) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(format!("{}.html", slug_str(name)));
+    fs::write(&path, preview_html(name, provider, palette))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Open a file in the default browser
+///
+/// # Arguments
+///
+/// * `path` - The file
+///
+/// # Returns
+///
+/// A Result indicating whether the browser could be started
+pub fn open_in_browser(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = command
+        .arg(path)
+        .status()
+        .context("Failed to start the browser")?;
+    if !status.success() {
+        anyhow::bail!("The browser couldn't open {}", path.display());
+    }
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> ColorPalette {
+        let color = |hex: &str| hex.to_string();
+        ColorPalette {
+            black: color("#191724"),
+            red: color("#eb6f92"),
+            green: color("#31748f"),
+            yellow: color("#f6c177"),
+            blue: color("#9ccfd8"),
+            magenta: color("#c4a7e7"),
+            cyan: color("#ebbcba"),
+            white: color("#e0def4"),
+            bright_black: color("#6e6a86"),
+            bright_red: color("#eb6f92"),
+            bright_green: color("#31748f"),
+            bright_yellow: color("#f6c177"),
+            bright_blue: color("#9ccfd8"),
+            bright_magenta: color("#c4a7e7"),
+            bright_cyan: color("#ebbcba"),
+            bright_white: color("#e0def4"),
+            background: color("#191724"),
+            foreground: color("#e0def4"),
+            cursor: None,
+            selection: Some(color("#403d52")),
+        }
+    }
+
+    #[test]
+    fn test_preview_html() {
+        let html = preview_html("Rosé <Pine>", "GitHub", &palette());
+        assert!(html.contains("<title>Rosé &lt;Pine&gt; - theme preview</title>"));
+        assert!(html.contains("--color-background: #191724;"));
+        assert!(html.contains("style=\"background: #eb6f92\"></span>red<code>#eb6f92</code>"));
+        assert!(html.contains("selection<code>#403d52</code>"));
+        assert!(!html.contains("cursor<code>"));
+        assert!(html.contains("var(--color-accent)\"></span>accent"));
+    }
+
+    #[test]
+    fn test_write_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_preview(dir.path(), "Rose Pine Moon", "GitHub", &palette()).unwrap();
+        assert_eq!(path, dir.path().join("rose-pine-moon.html"));
+        assert!(fs::read_to_string(path).unwrap().contains("Rose Pine Moon"));
+    }
+}
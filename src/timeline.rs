@@ -0,0 +1,337 @@
+//! Career timeline
+//!
+//! With `timeline` in the site config, experiences, education, certifications
+//! and major projects are merged into one chronological list. It is published
+//! as `timeline.json`, so the site (or anything else) can render it without
+//! keeping the data twice, and shown as a filterable section on the CV page.
+//!
+//! Certifications are plain text in the CV data, so they are dated by the last
+//! year they mention, e.g. "AWS Solutions Architect (2021)"; undated ones are
+//! left out. Projects are the ones listed in `timeline.projects`, or those with
+//! a case study, dated by their last activity.
+
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use im::Vector;
+use regex::Regex;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::cv_data::{Cv, Project};
+use crate::cv_date::{format_range, CvDate};
+use crate::featured::matches_name;
+use crate::site_config::TimelineConfig;
+
+/// File name of the timeline in the output directory
+pub const TIMELINE_FILE: &str = "timeline.json";
+
+/// What a timeline entry is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineKind {
+    /// A job
+    Experience,
+    /// A degree
+    Education,
+    /// A certification, at the year it was earned
+    Certification,
+    /// A project, at its last activity
+    Project,
+}
+
+impl TimelineKind {
+    /// All kinds, in the order they are offered as filters
+    pub const ALL: [TimelineKind; 4] = [
+        TimelineKind::Experience,
+        TimelineKind::Education,
+        TimelineKind::Certification,
+        TimelineKind::Project,
+    ];
+
+    /// Label of the kind's filter
+    pub fn label(&self) -> &'static str {
+        match self {
+            TimelineKind::Experience => "Experience",
+            TimelineKind::Education => "Education",
+            TimelineKind::Certification => "Certifications",
+            TimelineKind::Project => "Projects",
+        }
+    }
+}
+
+impl fmt::Display for TimelineKind {
+    /// The kind as in `timeline.json`, e.g. `experience`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TimelineKind::Experience => "experience",
+            TimelineKind::Education => "education",
+            TimelineKind::Certification => "certification",
+            TimelineKind::Project => "project",
+        })
+    }
+}
+
+/// An entry of the timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineEntry {
+    /// What the entry is
+    pub kind: TimelineKind,
+    /// Position, degree, certification or project name
+    pub title: String,
+    /// Company or institution
+    pub organization: Option<String>,
+    /// When it started
+    pub start: CvDate,
+    /// When it ended: `None` while ongoing, the start for certifications
+    /// and projects
+    pub end: Option<CvDate>,
+    /// The dates for people, e.g. `Jan 2020 – Present`
+    pub period: String,
+    /// Link to more about the entry
+    pub url: Option<String>,
+    /// Technologies used
+    pub technologies: Vector<String>,
+}
+
+/// The published timeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Timeline {
+    /// Language of the periods
+    pub language: String,
+    /// Entries, most recent first
+    pub entries: Vector<TimelineEntry>,
+}
+
+impl Timeline {
+    /// The kinds of the entries, in the order of [`TimelineKind::ALL`]
+    pub fn kinds(&self) -> Vec<TimelineKind> {
+        TimelineKind::ALL
+            .into_iter()
+            .filter(|kind| self.entries.iter().any(|entry| entry.kind == *kind))
+            .collect()
+    }
+}
+
+/// Merge the dated parts of the CV into a timeline
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+/// * `config` - Timeline configuration choosing the projects
+/// * `language` - Language of the periods
+///
+/// # Returns
+///
+/// The timeline, most recent entries first; entries that started at the same
+/// time are ordered by end date, ongoing ones first
+pub fn build_timeline(cv: &Cv, config: &TimelineConfig, language: &str) -> Timeline {
+    let range = |start: &CvDate, end: Option<&CvDate>| format_range(start, end, language);
+
+    let experiences = cv.experiences.iter().map(|experience| TimelineEntry {
+        kind: TimelineKind::Experience,
+        title: experience.position.clone(),
+        organization: Some(experience.company.clone()),
+        start: experience.start_date,
+        end: experience.end_date,
+        period: range(&experience.start_date, experience.end_date.as_ref()),
+        url: None,
+        technologies: experience.technologies.clone(),
+    });
+
+    let education = cv.education.iter().map(|education| TimelineEntry {
+        kind: TimelineKind::Education,
+        title: if education.field.is_empty() {
+            education.degree.clone()
+        } else {
+            format!("{} in {}", education.degree, education.field)
+        },
+        organization: Some(education.institution.clone()),
+        start: education.start_date,
+        end: education.end_date,
+        period: range(&education.start_date, education.end_date.as_ref()),
+        url: None,
+        technologies: Vector::new(),
+    });
+
+    let certifications = cv.certifications.iter().filter_map(|certification| {
+        let year = certification_year(certification)?;
+        Some(TimelineEntry {
+            kind: TimelineKind::Certification,
+            title: certification.clone(),
+            organization: None,
+            start: year,
+            end: Some(year),
+            period: range(&year, Some(&year)),
+            url: None,
+            technologies: Vector::new(),
+        })
+    });
+
+    let projects = timeline_projects(&cv.projects, config)
+        .into_iter()
+        .filter_map(|project| {
+            let updated = project.updated_at?.date_naive();
+            let date = CvDate::Month(updated.year(), updated.month());
+            Some(TimelineEntry {
+                kind: TimelineKind::Project,
+                title: project
+                    .display_name
+                    .clone()
+                    .unwrap_or_else(|| project.name.clone()),
+                organization: None,
+                start: date,
+                end: Some(date),
+                period: range(&date, Some(&date)),
+                url: project
+                    .case_study
+                    .as_ref()
+                    .map(|slug| format!("projects/{}.html", slug))
+                    .or_else(|| project.url.clone())
+                    .or_else(|| project.repository.clone()),
+                technologies: project.technologies.clone(),
+            })
+        });
+
+    let mut entries = experiences
+        .chain(education)
+        .chain(certifications)
+        .chain(projects)
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|entry| Reverse((entry.start, entry.end.unwrap_or(CvDate::Present))));
+
+    Timeline {
+        language: language.to_string(),
+        entries: entries.into_iter().collect(),
+    }
+}
+
+/// The projects on the timeline: the configured ones, or those with a case study
+fn timeline_projects(projects: &Vector<Project>, config: &TimelineConfig) -> Vec<Project> {
+    if config.projects.is_empty() {
+        projects
+            .iter()
+            .filter(|project| project.case_study.is_some())
+            .cloned()
+            .collect()
+    } else {
+        config
+            .projects
+            .iter()
+            .filter_map(|name| projects.iter().find(|project| matches_name(project, name)))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The last year a certification mentions, e.g. 2021 for "AWS (2019, renewed 2021)"
+fn certification_year(certification: &str) -> Option<CvDate> {
+    static YEAR: OnceLock<Regex> = OnceLock::new();
+    YEAR.get_or_init(|| Regex::new(r"\b(?:19|20)\d{2}\b").unwrap())
+        .find_iter(certification)
+        .last()
+        .and_then(|year| year.as_str().parse().ok())
+        .map(CvDate::Year)
+}
+
+/// Write the timeline as JSON
+///
+/// # Arguments
+///
+/// * `timeline` - The timeline
+/// * `path` - Path where the JSON will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_timeline_json(timeline: &Timeline, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(timeline).context("Failed to serialize timeline")?;
+    fs::write(path, json).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_build_timeline() {
+        let mut cv = Cv::from_json("data/cv_data.json").unwrap();
+        cv.certifications = im::vector![
+            "AWS Solutions Architect (2019, renewed 2024)".to_string(),
+            "First Aid".to_string(),
+        ];
+        let mut project = cv.projects[0].clone();
+        project.case_study = Some("cv".to_string());
+        project.updated_at = Some(Utc.with_ymd_and_hms(2023, 6, 15, 12, 0, 0).unwrap());
+        cv.projects = im::vector![project.clone(), cv.projects[1].clone()];
+
+        let timeline = build_timeline(&cv, &TimelineConfig::default(), "en");
+        let count = |kind| {
+            timeline
+                .entries
+                .iter()
+                .filter(|entry| entry.kind == kind)
+                .count()
+        };
+        assert_eq!(count(TimelineKind::Experience), cv.experiences.len());
+        assert_eq!(count(TimelineKind::Education), cv.education.len());
+        assert_eq!(count(TimelineKind::Certification), 1);
+        assert_eq!(count(TimelineKind::Project), 1);
+        assert_eq!(timeline.kinds(), TimelineKind::ALL);
+
+        let certification = timeline
+            .entries
+            .iter()
+            .find(|entry| entry.kind == TimelineKind::Certification)
+            .unwrap();
+        assert_eq!(certification.start, CvDate::Year(2024));
+        assert_eq!(certification.period, "2024");
+
+        let project = timeline
+            .entries
+            .iter()
+            .find(|entry| entry.kind == TimelineKind::Project)
+            .unwrap();
+        assert_eq!(project.start, CvDate::Month(2023, 6));
+        assert_eq!(project.url.as_deref(), Some("projects/cv.html"));
+
+        // Most recent first
+        let starts = timeline
+            .entries
+            .iter()
+            .map(|entry| entry.start)
+            .collect::<Vec<_>>();
+        assert!(starts.windows(2).all(|pair| pair[0] >= pair[1]));
+    }
+
+    #[test]
+    fn test_configured_projects() {
+        let mut cv = Cv::from_json("data/cv_data.json").unwrap();
+        let updated = Some(Utc.with_ymd_and_hms(2022, 1, 1, 12, 0, 0).unwrap());
+        cv.projects = cv
+            .projects
+            .iter()
+            .map(|project| Project {
+                updated_at: updated,
+                ..project.clone()
+            })
+            .collect();
+        let config = TimelineConfig {
+            projects: im::vector![cv.projects[1].name.clone()],
+            ..Default::default()
+        };
+
+        let timeline = build_timeline(&cv, &config, "en");
+        let projects = timeline
+            .entries
+            .iter()
+            .filter(|entry| entry.kind == TimelineKind::Project)
+            .map(|entry| entry.title.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(projects, vec![cv.projects[1].name.as_str()]);
+    }
+}
@@ -0,0 +1,307 @@
+//! Concurrent, cached compilation of the PDFs of a build
+//!
+//! A build can produce several PDFs: the CV and the portfolio, for every
+//! language and profile. Their markup is generated first and collected as
+//! [`PdfJob`]s, then [`compile_pdfs`] compiles them at the same time, one job
+//! per core.
+//!
+//! Compiled PDFs are kept in [`PDF_CACHE_DIR`], named by a hash of everything
+//! that goes into them: the markup, the Typst settings, the compiler version
+//! and the images the markup embeds. A job whose hash is in the cache is
+//! copied from there instead of compiled, so a variant is only recompiled when
+//! the CV content it shows has changed. Entries no job of the build used are
+//! removed afterwards.
+//!
+//! The temporary markup of a job is removed once it compiles, and kept when it
+//! fails. To look at the markup of every PDF, keep it next to the PDFs, e.g.
+//! `dist/cv.typ` for `dist/cv.pdf`; the CV markup names the CV field each
+//! block was generated from.
+
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+use crate::site_config::TypstConfig;
+
+/// Directory of the compiled PDF cache
+pub const PDF_CACHE_DIR: &str = "cache/pdf";
+
+/// A PDF to compile
+#[derive(Debug, Clone)]
+pub struct PdfJob {
+    /// What the PDF is, for errors, e.g. its output path
+    pub label: String,
+    /// Typst markup of the document
+    pub markup: String,
+    /// Typst configuration with font discovery settings
+    pub typst_config: TypstConfig,
+    /// Path where the PDF will be written
+    pub output_path: PathBuf,
+}
+
+/// What a batch of jobs did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PdfBatchReport {
+    /// PDFs compiled by Typst
+    pub compiled: usize,
+    /// PDFs copied from the cache
+    pub cached: usize,
+}
+
+/// Compile PDFs concurrently, reusing cached results
+///
+/// # Arguments
+///
+/// * `jobs` - The PDFs to compile
+/// * `temp_path` - Path of the temporary Typst file; each job writes its
+///   markup next to it, with its index appended to the name
+/// * `cache_dir` - Directory of the compiled PDF cache
+/// * `keep_markup` - Whether to write each job's markup next to its PDF, with
+///   the `.typ` extension
+///
+/// # Returns
+///
+/// How many PDFs were compiled and how many came from the cache, or the error
+/// of the first job that failed
+pub fn compile_pdfs(
+    jobs: &[PdfJob],
+    temp_path: &str,
+    cache_dir: &Path,
+    keep_markup: bool,
+) -> Result<PdfBatchReport> {
+    let compiler = super::typst_version().unwrap_or_default();
+    let base_dir = Path::new(temp_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let keys = jobs
+        .iter()
+        .map(|job| cache_key(job, &compiler, base_dir))
+        .collect::<Result<Vec<_>>>()?;
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(jobs.iter().map(|_| None).collect::<Vec<_>>());
+    let workers = thread::available_parallelism()
+        .map_or(1, |cores| cores.get())
+        .min(jobs.len());
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+                let temp_path = temp_file(temp_path, index);
+                let result = run_job(job, &keys[index], &temp_path, cache_dir, keep_markup)
+                    .with_context(|| format!("Failed to generate {}", job.label));
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    let mut report = PdfBatchReport::default();
+    for result in results.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        match result.ok_or_else(|| anyhow!("A PDF job did not run"))?? {
+            true => report.cached += 1,
+            false => report.compiled += 1,
+        }
+    }
+
+    prune_cache(cache_dir, &keys.into_iter().collect())?;
+    Ok(report)
+}
+
+/// Write one PDF, from the cache if it's there
+///
+/// # Returns
+///
+/// Whether the PDF came from the cache
+fn run_job(
+    job: &PdfJob,
+    key: &str,
+    temp_path: &str,
+    cache_dir: &Path,
+    keep_markup: bool,
+) -> Result<bool> {
+    if let Some(parent) = job.output_path.parent() {
+        fs::create_dir_all(parent).context("Failed to create output directory")?;
+    }
+    if keep_markup {
+        let kept = job.output_path.with_extension("typ");
+        fs::write(&kept, &job.markup)
+            .with_context(|| format!("Failed to keep Typst markup in {}", kept.display()))?;
+    }
+
+    let cached = cache_dir.join(format!("{key}.pdf"));
+    if cached.exists() {
+        fs::copy(&cached, &job.output_path)
+            .with_context(|| format!("Failed to copy {}", cached.display()))?;
+        return Ok(true);
+    }
+
+    let output_path = job
+        .output_path
+        .to_str()
+        .with_context(|| format!("Invalid output path: {}", job.output_path.display()))?;
+    fs::write(temp_path, &job.markup)
+        .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
+    super::compile(temp_path, output_path, &job.typst_config)?;
+    fs::remove_file(temp_path)
+        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
+
+    fs::create_dir_all(cache_dir)
+        .with_context(|| format!("Failed to create {}", cache_dir.display()))?;
+    fs::copy(&job.output_path, &cached)
+        .with_context(|| format!("Failed to cache {}", job.output_path.display()))?;
+    Ok(false)
+}
+
+/// Hash of everything that goes into the PDF of a job
+///
+/// # Arguments
+///
+/// * `job` - The job
+/// * `compiler` - Version of the Typst compiler
+/// * `base_dir` - Directory the markup is compiled in, for image paths
+fn cache_key(job: &PdfJob, compiler: &str, base_dir: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(compiler);
+    hasher.update(serde_json::to_string(&job.typst_config)?);
+    hasher.update(&job.markup);
+    for path in image_paths(&job.markup) {
+        // A missing image fails the compilation, not the lookup
+        if let Ok(image) = fs::read(base_dir.join(path)) {
+            hasher.update(path);
+            hasher.update(image);
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Local files embedded with `image("...")` in Typst markup
+fn image_paths(markup: &str) -> impl Iterator<Item = &str> {
+    static IMAGE: OnceLock<Regex> = OnceLock::new();
+    IMAGE
+        .get_or_init(|| Regex::new(r#"image\(\s*"([^"]+)""#).unwrap())
+        .captures_iter(markup)
+        .filter_map(|captures| captures.get(1))
+        .map(|path| path.as_str())
+}
+
+/// Temporary Typst file of the job at `index`, e.g. `temp_cv-1.typ`
+fn temp_file(temp_path: &str, index: usize) -> String {
+    let path = Path::new(temp_path);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("temp");
+    let name = match path.extension().and_then(|s| s.to_str()) {
+        Some(extension) => format!("{stem}-{index}.{extension}"),
+        None => format!("{stem}-{index}"),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Remove cached PDFs none of the jobs used
+fn prune_cache(cache_dir: &Path, keys: &HashSet<String>) -> Result<()> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return Ok(());
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let used = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|stem| keys.contains(stem));
+        if !used && path.extension().is_some_and(|extension| extension == "pdf") {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(markup: &str, output_path: PathBuf) -> PdfJob {
+        PdfJob {
+            label: "CV".to_string(),
+            markup: markup.to_string(),
+            typst_config: TypstConfig::default(),
+            output_path,
+        }
+    }
+
+    #[test]
+    fn test_cache_key_covers_images() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("me.png"), "one").unwrap();
+        let markup = r#"#let photo = image("me.png")"#;
+        let output = dir.path().join("cv.pdf");
+
+        let first = cache_key(&job(markup, output.clone()), "typst 0.14", dir.path()).unwrap();
+        assert_eq!(
+            cache_key(&job(markup, output.clone()), "typst 0.14", dir.path()).unwrap(),
+            first
+        );
+        assert_ne!(
+            cache_key(&job(markup, output.clone()), "typst 0.15", dir.path()).unwrap(),
+            first
+        );
+
+        fs::write(dir.path().join("me.png"), "two").unwrap();
+        assert_ne!(
+            cache_key(&job(markup, output), "typst 0.14", dir.path()).unwrap(),
+            first
+        );
+    }
+
+    #[test]
+    fn test_cached_jobs_skip_compilation() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_dir = dir.path().join("cache");
+        let temp_path = dir.path().join("temp_cv.typ");
+        let jobs = [
+            job("= CV", dir.path().join("en/cv.pdf")),
+            job("= CV (da)", dir.path().join("da/cv.pdf")),
+        ];
+
+        // Seed the cache, nothing is left to compile
+        fs::create_dir_all(&cache_dir).unwrap();
+        let compiler = crate::typst_generator::typst_version().unwrap_or_default();
+        for job in &jobs {
+            let key = cache_key(job, &compiler, dir.path()).unwrap();
+            fs::write(cache_dir.join(format!("{key}.pdf")), &job.markup).unwrap();
+        }
+        fs::write(cache_dir.join("stale.pdf"), "old").unwrap();
+
+        let report = compile_pdfs(&jobs, temp_path.to_str().unwrap(), &cache_dir, true).unwrap();
+        assert_eq!(
+            report,
+            PdfBatchReport {
+                compiled: 0,
+                cached: 2
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(dir.path().join("da/cv.pdf")).unwrap(),
+            "= CV (da)"
+        );
+        assert!(!cache_dir.join("stale.pdf").exists());
+        assert_eq!(
+            fs::read_to_string(dir.path().join("da/cv.typ")).unwrap(),
+            "= CV (da)"
+        );
+    }
+
+    #[test]
+    fn test_temp_file() {
+        assert_eq!(temp_file("temp_cv.typ", 1), "temp_cv-1.typ");
+        assert_eq!(temp_file("build/cv", 0), "build/cv-0");
+    }
+}
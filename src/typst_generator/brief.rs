@@ -0,0 +1,185 @@
+//! Interview brief Typst markup generation
+//!
+//! Renders a private prep document (see [`crate::prep_notes`]) with the fonts
+//! and colors of the CV theme. All text is escaped, since notes are free-form.
+
+use super::utils::{append_line, append_lines, escape_typst_markup, string_literal};
+use crate::prep_notes::{Brief, BriefEntry};
+use crate::site_config::TypstConfig;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Generate the brief PDF
+///
+/// # Arguments
+///
+/// * `brief` - The assembled brief
+/// * `typst_config` - Typst configuration for colors, fonts and paper size
+/// * `temp_path` - Path to a temporary file for Typst markup
+/// * `output_path` - Path where the PDF will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_brief_pdf(
+    brief: &Brief,
+    typst_config: &TypstConfig,
+    temp_path: &str,
+    output_path: &str,
+) -> Result<()> {
+    let typst_markup = generate_brief_markup(brief, typst_config);
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).context("Failed to create output directory")?;
+    }
+
+    fs::write(temp_path, &typst_markup)
+        .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
+
+    super::compile(temp_path, output_path, typst_config)?;
+
+    fs::remove_file(temp_path)
+        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
+
+    Ok(())
+}
+
+/// Generate Typst markup for the brief
+fn generate_brief_markup(brief: &Brief, typst_config: &TypstConfig) -> String {
+    let colors = &typst_config.customization.colors;
+    let layout = &typst_config.customization.layout;
+    let title = escape_typst_markup(&brief.title());
+
+    let setup = [
+        format!(
+            "#set document(title: \"{}\", author: \"{}\")",
+            string_literal(&brief.title()),
+            string_literal(&brief.name)
+        ),
+        format!(
+            "#set page(paper: \"{}\", margin: (x: 2cm, y: 2.2cm), header: text(size: 8pt, fill: rgb(\"{}\"))[Private #h(1fr) {}], footer: context align(right, text(size: 8pt)[#counter(page).display()]))",
+            layout.paper_size, colors.text_medium, title
+        ),
+        format!(
+            "#set text(font: \"{}\", size: 10.5pt, fill: rgb(\"{}\"))",
+            layout.font, colors.text_dark
+        ),
+        format!(
+            "#show heading.where(level: 2): it => block(width: 100%, stroke: (bottom: 1pt + rgb(\"{}\")), inset: (bottom: 4pt))[#it.body]",
+            colors.accent
+        ),
+    ]
+    .join("\n");
+
+    let header = brief.application_details.iter().fold(
+        append_lines(append_lines(setup, ""), &format!("= {}", title)),
+        |markup, detail| append_line(markup, &format!("- {}", escape_typst_markup(detail))),
+    );
+
+    brief
+        .sections
+        .iter()
+        .fold(append_line(header, ""), |markup, section| {
+            section.entries.iter().fold(
+                append_lines(
+                    markup,
+                    &format!("== {}", escape_typst_markup(&section.heading)),
+                ),
+                generate_entry,
+            )
+        })
+}
+
+/// Generate one entry with its notes
+fn generate_entry(markup: String, entry: &BriefEntry) -> String {
+    let title = match entry.period {
+        Some(ref period) => format!(
+            "=== {} #h(1fr) #text(size: 9pt, weight: \"regular\")[{}]",
+            escape_typst_markup(&entry.title),
+            escape_typst_markup(period)
+        ),
+        None => format!("=== {}", escape_typst_markup(&entry.title)),
+    };
+    let with_description = if entry.description.is_empty() {
+        append_lines(markup, &title)
+    } else {
+        append_lines(
+            append_lines(markup, &title),
+            &escape_typst_markup(&entry.description),
+        )
+    };
+
+    let with_points = entry.points.iter().fold(with_description, |s, point| {
+        append_line(s, &format!("- {}", escape_typst_markup(point)))
+    });
+    let with_technologies = if entry.technologies.is_empty() {
+        with_points
+    } else {
+        append_lines(
+            append_line(with_points, ""),
+            &format!(
+                "_Technologies:_ {}",
+                escape_typst_markup(
+                    &entry
+                        .technologies
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            ),
+        )
+    };
+
+    if entry.notes.is_empty() {
+        append_line(with_technologies, "")
+    } else {
+        let notes = entry
+            .notes
+            .iter()
+            .map(|note| format!("- {}", escape_typst_markup(note)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        append_lines(
+            with_technologies,
+            &format!(
+                "#block(fill: luma(245), inset: 8pt, radius: 3pt, width: 100%)[*Notes*\n\n{}\n]",
+                notes
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prep_notes::BriefSection;
+    use crate::site_config::PdfVariant;
+
+    #[test]
+    fn test_brief_markup_escapes_notes() {
+        let brief = Brief {
+            name: "Ada".to_string(),
+            variant: PdfVariant::Cv,
+            application: None,
+            application_details: im::Vector::new(),
+            sections: im::vector![BriefSection {
+                heading: "Projects".to_string(),
+                entries: im::vector![BriefEntry {
+                    title: "cv".to_string(),
+                    period: None,
+                    description: "A CV generator".to_string(),
+                    points: im::Vector::new(),
+                    technologies: im::vector!["Rust".to_string()],
+                    notes: im::vector!["#import \"secrets\" = 42".to_string()],
+                }],
+            }],
+        };
+
+        let markup = generate_brief_markup(&brief, &TypstConfig::default());
+        assert!(markup.contains("= Interview brief: Ada\n"));
+        assert!(markup.contains("== Projects\n\n=== cv\n\nA CV generator\n"));
+        assert!(markup.contains("- \\#import \"secrets\" \\= 42"));
+    }
+}
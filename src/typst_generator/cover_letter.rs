@@ -3,6 +3,8 @@
 //! Generates Typst markup for cover letters using the grotesk-cv template.
 //! Reuses the same metadata structure as the CV for consistent styling.
 
+#[cfg(not(feature = "embedded-typst"))]
+use super::utils::font_args;
 use super::utils::{append_line, append_lines, split_name};
 use crate::cover_letter::CoverLetter;
 use crate::cv_data::PersonalInfo;
@@ -10,6 +12,7 @@ use crate::site_config::TypstConfig;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
+#[cfg(not(feature = "embedded-typst"))]
 use std::process::Command;
 
 // Extension trait to enable method chaining with pipe (same as markup.rs)
@@ -44,26 +47,33 @@ pub fn generate_cover_letter_pdf(
         .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
 
     // Compile Typst to PDF
-    let output = Command::new("typst")
-        .arg("compile")
-        .arg(temp_path)
-        .arg(output_path)
-        .output()
-        .context(
-            "Failed to execute 'typst' command.\n\
+    #[cfg(feature = "embedded-typst")]
+    super::embedded::compile_pdf(temp_path, output_path, typst_config)?;
+
+    #[cfg(not(feature = "embedded-typst"))]
+    {
+        let output = Command::new("typst")
+            .arg("compile")
+            .args(font_args(typst_config))
+            .arg(temp_path)
+            .arg(output_path)
+            .output()
+            .context(
+                "Failed to execute 'typst' command.\n\
              \n\
              Is Typst installed?\n\
              - Install from: https://typst.app/\n\
              - Or run: cargo install typst-cli",
-        )?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!(
-            "Typst compilation failed:\n{}\n\nTemp file preserved at: {}",
-            stderr,
-            temp_path
-        ));
+            )?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow::anyhow!(
+                "Typst compilation failed:\n{}\n\nTemp file preserved at: {}",
+                stderr,
+                temp_path
+            ));
+        }
     }
 
     // Clean up temp file
@@ -398,6 +408,7 @@ mod tests {
             social_links: im::HashMap::new(),
             profile_image: None,
             github_avatar_url: None,
+            summaries: Default::default(),
         }
     }
 
@@ -423,7 +434,11 @@ mod tests {
                     font: "Hanken Grotesk".to_string(),
                     font_size: "9pt".to_string(),
                 },
+                palette: Default::default(),
             },
+            template: None,
+            font_paths: im::Vector::new(),
+            ignore_system_fonts: false,
         }
     }
 
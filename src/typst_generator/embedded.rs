@@ -0,0 +1,175 @@
+/// In-process Typst compilation
+///
+/// Used instead of the `typst` CLI when the `embedded-typst` feature is
+/// enabled. Fonts come from the configured font paths, the system (unless
+/// disabled) and the fonts bundled with Typst; `@preview` packages are
+/// downloaded into the usual Typst package cache.
+use anyhow::{Context, Result};
+use chrono::Datelike;
+use std::fs;
+use std::path::{Path, PathBuf};
+use typst::diag::{FileError, FileResult, SourceDiagnostic};
+use typst::foundations::{Bytes, Datetime};
+use typst::layout::PagedDocument;
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::utils::LazyHash;
+use typst::{Library, LibraryExt, World};
+use typst_kit::download::{Downloader, ProgressSink};
+use typst_kit::fonts::{FontSlot, Fonts};
+use typst_kit::package::PackageStorage;
+
+use crate::site_config::TypstConfig;
+
+/// The world a single document is compiled in
+struct CvWorld {
+    root: PathBuf,
+    main: FileId,
+    library: LazyHash<Library>,
+    book: LazyHash<FontBook>,
+    fonts: Vec<FontSlot>,
+    packages: PackageStorage,
+}
+
+impl CvWorld {
+    fn new(main_path: &Path, typst_config: &TypstConfig) -> Result<Self> {
+        let root = main_path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        let file_name = main_path
+            .file_name()
+            .context("Typst source path has no file name")?;
+
+        let fonts = Fonts::searcher()
+            .include_system_fonts(!typst_config.ignore_system_fonts)
+            .search_with(typst_config.font_paths.iter());
+
+        Ok(CvWorld {
+            root,
+            main: FileId::new(None, VirtualPath::new(file_name)),
+            library: LazyHash::new(Library::default()),
+            book: LazyHash::new(fonts.book),
+            fonts: fonts.fonts,
+            packages: PackageStorage::new(None, None, Downloader::new("cv-generator")),
+        })
+    }
+
+    /// Resolve a file id to a path on disk, downloading its package if needed
+    fn path_for(&self, id: FileId) -> FileResult<PathBuf> {
+        let root = match id.package() {
+            Some(spec) => self.packages.prepare_package(spec, &mut ProgressSink)?,
+            None => self.root.clone(),
+        };
+        id.vpath().resolve(&root).ok_or(FileError::AccessDenied)
+    }
+
+    fn read(&self, id: FileId) -> FileResult<Vec<u8>> {
+        let path = self.path_for(id)?;
+        fs::read(&path).map_err(|e| FileError::from_io(e, &path))
+    }
+}
+
+impl World for CvWorld {
+    fn library(&self) -> &LazyHash<Library> {
+        &self.library
+    }
+
+    fn book(&self) -> &LazyHash<FontBook> {
+        &self.book
+    }
+
+    fn main(&self) -> FileId {
+        self.main
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        let text = String::from_utf8(self.read(id)?).map_err(|_| FileError::InvalidUtf8)?;
+        Ok(Source::new(id, text))
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.read(id).map(Bytes::new)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.fonts.get(index).and_then(FontSlot::get)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        let now = chrono::Utc::now();
+        let today = match offset {
+            Some(hours) => (now + chrono::Duration::hours(hours)).date_naive(),
+            None => now.with_timezone(&chrono::Local).date_naive(),
+        };
+        Datetime::from_ymd(today.year(), today.month() as u8, today.day() as u8)
+    }
+}
+
+/// Version of the embedded Typst compiler
+pub fn compiler_version() -> String {
+    typst::syntax::package::PackageVersion::compiler().to_string()
+}
+
+/// Format compiler diagnostics for an error message
+fn format_diagnostics(diagnostics: &[SourceDiagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| {
+            let hints = d
+                .hints
+                .iter()
+                .map(|hint| format!("\n  hint: {hint}"))
+                .collect::<String>();
+            format!("- {}{}", d.message, hints)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lines of the main file the diagnostics point at, starting at 1
+fn error_lines(world: &CvWorld, diagnostics: &[SourceDiagnostic]) -> Vec<usize> {
+    let Ok(source) = world.source(world.main) else {
+        return Vec::new();
+    };
+    let mut lines: Vec<usize> = diagnostics
+        .iter()
+        .filter(|d| d.span.id() == Some(world.main))
+        .filter_map(|d| source.range(d.span))
+        .filter_map(|range| source.lines().byte_to_line(range.start))
+        .map(|line| line + 1)
+        .collect();
+    lines.dedup();
+    lines
+}
+
+/// Compile a Typst file to PDF in-process
+///
+/// # Arguments
+///
+/// * `source_path` - Path to the Typst markup
+/// * `output_path` - Path where the PDF will be written
+/// * `typst_config` - Typst configuration with font discovery settings
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn compile_pdf(source_path: &str, output_path: &str, typst_config: &TypstConfig) -> Result<()> {
+    let world = CvWorld::new(Path::new(source_path), typst_config)?;
+
+    let document = typst::compile::<PagedDocument>(&world)
+        .output
+        .map_err(|errors| {
+            super::compilation_error(
+                source_path,
+                &format_diagnostics(&errors),
+                &error_lines(&world, &errors),
+            )
+        })?;
+
+    let pdf = typst_pdf::pdf(&document, &typst_pdf::PdfOptions::default())
+        .map_err(|errors| anyhow::anyhow!("PDF export failed:\n{}", format_diagnostics(&errors)))?;
+
+    fs::write(output_path, pdf).with_context(|| format!("Failed to write PDF to {output_path}"))
+}
@@ -1,7 +1,9 @@
 use super::sections::{
     generate_education_section, generate_experience_section, generate_languages_section,
     generate_projects_section, generate_skills_section, generate_summary_section,
+    generate_testimonials_section,
 };
+use super::source_map::append_source;
 use super::utils::{append_line, append_lines, append_markup, format_email_for_typst, split_name};
 /// Functions for generating complete Typst markup from CV data
 use crate::cv_data::Cv;
@@ -217,15 +219,20 @@ fn generate_personal_info(cv: &Cv) -> String {
     // Name
     let (first_name, last_name) = split_name(&cv.personal_info.name);
 
-    // Email
-    let email_display = format_email_for_typst(&cv.personal_info.email);
-
     // Start building the markup
-    let base = String::new()
+    let with_name = String::new()
+        .pipe(|s| append_source(s, "personal_info"))
         .pipe(|s| append_line(s, "#let personal_info = ("))
         .pipe(|s| append_markup(s, &format!("first_name: \"{first_name}\", ")))
-        .pipe(|s| append_markup(s, &format!("last_name: \"{last_name}\", ")))
-        .pipe(|s| append_markup(s, &format!("email: \"{email_display}\", ")));
+        .pipe(|s| append_markup(s, &format!("last_name: \"{last_name}\", ")));
+
+    // Email (empty when it isn't public)
+    let base = if cv.personal_info.email.is_empty() {
+        with_name
+    } else {
+        let email_display = format_email_for_typst(&cv.personal_info.email);
+        with_name.pipe(|s| append_markup(s, &format!("email: \"{email_display}\", ")))
+    };
 
     // Phone
     let with_phone = if let Some(phone) = &cv.personal_info.phone {
@@ -265,6 +272,8 @@ fn generate_left_pane(cv: &Cv) -> String {
         .pipe(|s| append_markup(s, &generate_experience_section(cv)))
         // Projects section
         .pipe(|s| append_markup(s, &generate_projects_section(cv)))
+        // Testimonials section
+        .pipe(|s| append_markup(s, &generate_testimonials_section(cv)))
         // End of left pane
         .pipe(|s| append_lines(s, "]"))
 }
@@ -309,6 +318,7 @@ fn generate_final_template(cv: &Cv, typst_config: &TypstConfig) -> String {
     );
 
     String::new()
+        .pipe(|s| append_source(s, "personal_info"))
         // Photo and document settings
         .pipe(|s| {
             // Use dynamic profile image: custom image > none (GitHub avatars are URLs, not supported in Typst)
@@ -2,21 +2,37 @@
 ///
 /// This module provides functionality to generate Typst markup from CV data
 /// and compile it to PDF using the Typst CLI.
+mod batch;
+mod brief;
 mod cover_letter;
+#[cfg(feature = "embedded-typst")]
+mod embedded;
 mod markup;
+mod offers;
+mod portfolio;
 mod sections;
+mod source_map;
+mod template;
 mod utils;
 
+pub use batch::{compile_pdfs, PdfBatchReport, PdfJob, PDF_CACHE_DIR};
+pub use brief::generate_brief_pdf;
 pub use cover_letter::generate_cover_letter_pdf;
+pub use offers::generate_offers_pdf;
+pub use portfolio::{generate_portfolio_markup, generate_portfolio_pdf};
 
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
+#[cfg(not(feature = "embedded-typst"))]
 use std::process::Command;
 
 use crate::cv_data::Cv;
 use crate::site_config::TypstConfig;
 use markup::generate_typst_markup;
+use template::generate_custom_markup;
+#[cfg(not(feature = "embedded-typst"))]
+use utils::font_args;
 
 /// Generate a PDF from CV data using Typst
 ///
@@ -36,8 +52,7 @@ pub fn generate_pdf(
     temp_path: &str,
     output_path: &str,
 ) -> Result<()> {
-    // Generate Typst markup with configuration
-    let typst_markup = generate_typst_markup(cv, typst_config);
+    let typst_markup = generate_cv_markup(cv, typst_config)?;
 
     // Ensure the output directory exists
     if let Some(parent) = Path::new(output_path).parent() {
@@ -48,52 +63,134 @@ pub fn generate_pdf(
     fs::write(temp_path, &typst_markup)
         .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
 
-    // Debug: Print template structure
-    if cfg!(debug_assertions) {
-        let lines: Vec<&str> = typst_markup.lines().collect();
-        println!("Generated Typst template (first 20 lines):");
-        for (i, line) in lines.iter().take(20).enumerate() {
-            println!("{:2}: {}", i + 1, line);
-        }
-        println!("\n... (template continues) ...\n");
-        println!("Generated Typst template (last 10 lines):");
-        for (i, line) in lines.iter().rev().take(10).rev().enumerate() {
-            println!("{:2}: {}", lines.len() - 9 + i, line);
+    // Compile Typst to PDF
+    compile(temp_path, output_path, typst_config)?;
+
+    // Optionally, clean up the temporary file
+    fs::remove_file(temp_path)
+        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
+
+    Ok(())
+}
+
+/// Generate the Typst markup of the CV
+///
+/// # Arguments
+///
+/// * `cv` - The CV data to render
+/// * `typst_config` - Typst configuration for theme and customization
+///
+/// # Returns
+///
+/// The markup from the custom template if one is configured, otherwise from
+/// the theme
+pub fn generate_cv_markup(cv: &Cv, typst_config: &TypstConfig) -> Result<String> {
+    match typst_config.template {
+        Some(ref template_path) => {
+            let template = fs::read_to_string(template_path)
+                .with_context(|| format!("Failed to read Typst template {template_path}"))?;
+            generate_custom_markup(cv, typst_config, &template)
         }
+        None => Ok(generate_typst_markup(cv, typst_config)),
     }
+}
 
-    // Compile Typst to PDF
-    let status = Command::new("typst")
-        .arg("compile")
-        .arg(temp_path)
-        .arg(output_path)
-        .status()
-        .context(
-            "Failed to execute 'typst' command.\n\
+/// Compile a Typst file to PDF with the embedded compiler or the `typst` CLI
+///
+/// # Arguments
+///
+/// * `temp_path` - Path of the Typst markup to compile
+/// * `output_path` - Path where the PDF will be written
+/// * `typst_config` - Typst configuration with font discovery settings
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+fn compile(temp_path: &str, output_path: &str, typst_config: &TypstConfig) -> Result<()> {
+    #[cfg(feature = "embedded-typst")]
+    embedded::compile_pdf(temp_path, output_path, typst_config)?;
+
+    #[cfg(not(feature = "embedded-typst"))]
+    {
+        let output = Command::new("typst")
+            .arg("compile")
+            .args(font_args(typst_config))
+            .arg(temp_path)
+            .arg(output_path)
+            .output()
+            .context(
+                "Failed to execute 'typst' command.\n\
                   \n\
                   Is Typst installed?\n\
                   - Install from: https://typst.app/\n\
                   - Or run: cargo install typst-cli\n\
                   - Or run: brew install typst (macOS) / sudo snap install typst (Ubuntu)",
-        )?;
+            )?;
 
-    if !status.success() {
-        return Err(anyhow::anyhow!(
-            "Typst compilation failed with status: {}\n\
-             \n\
-             Check the following:\n\
-             - Verify paper size is valid (a4, letter, legal, a3, a5)\n\
-             - Ensure font '{}' is available on your system\n\
-             - Check temporary file for syntax errors: {}",
-            status,
-            "HK Grotesk (or configured font)",
-            temp_path
-        ));
+        let diagnostics = String::from_utf8_lossy(&output.stderr);
+        if !output.status.success() {
+            let file_name = Path::new(temp_path)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(temp_path);
+            let lines = source_map::error_lines(&diagnostics, file_name);
+            return Err(compilation_error(temp_path, diagnostics.trim_end(), &lines));
+        }
+        // Warnings
+        if !diagnostics.trim().is_empty() {
+            eprint!("{diagnostics}");
+        }
     }
 
-    // Optionally, clean up the temporary file
-    fs::remove_file(temp_path)
-        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
-
     Ok(())
 }
+
+/// The error of a failed compilation, showing the offending markup
+///
+/// # Arguments
+///
+/// * `temp_path` - Path of the Typst markup that failed, which is kept
+/// * `diagnostics` - What the compiler reported
+/// * `lines` - Lines of the markup the errors are at, starting at 1
+///
+/// # Returns
+///
+/// The error, with the lines and the CV fields they came from
+fn compilation_error(temp_path: &str, diagnostics: &str, lines: &[usize]) -> anyhow::Error {
+    let markup = fs::read_to_string(temp_path).unwrap_or_default();
+    let excerpts = source_map::excerpts(&markup, lines);
+    let details = if excerpts.is_empty() {
+        format!(
+            "Check the following:\n\
+             - Verify paper size is valid (a4, letter, legal, a3, a5)\n\
+             - Ensure font '{}' is available on your system",
+            "HK Grotesk (or configured font)"
+        )
+    } else {
+        excerpts
+    };
+    anyhow::anyhow!(
+        "Typst compilation failed:\n{diagnostics}\n\n{details}\n\nThe markup is kept at {temp_path}"
+    )
+}
+
+/// Get the version of the Typst compiler used for PDFs
+///
+/// # Returns
+///
+/// The version string (e.g. "typst 0.14.2"), or None if the `typst` CLI
+/// isn't installed
+pub fn typst_version() -> Option<String> {
+    #[cfg(feature = "embedded-typst")]
+    return Some(format!("typst {} (embedded)", embedded::compiler_version()));
+
+    #[cfg(not(feature = "embedded-typst"))]
+    Command::new("typst")
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .filter(|version| !version.is_empty())
+}
@@ -0,0 +1,245 @@
+//! Offer comparison Typst markup generation
+//!
+//! Renders the private worksheet of [`crate::offers`] as tables with the fonts
+//! and colors of the CV theme, in landscape so several offers fit side by side.
+
+use super::utils::{append_line, append_lines, escape_typst_markup, string_literal};
+use crate::offers::{OfferComparison, RankedOffer, MAX_SCORE};
+use crate::site_config::TypstConfig;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+/// Generate the offer comparison PDF
+///
+/// # Arguments
+///
+/// * `comparison` - The offers
+/// * `name` - Whose offers they are, for the title
+/// * `typst_config` - Typst configuration for colors, fonts and paper size
+/// * `temp_path` - Path to a temporary file for Typst markup
+/// * `output_path` - Path where the PDF will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_offers_pdf(
+    comparison: &OfferComparison,
+    name: &str,
+    typst_config: &TypstConfig,
+    temp_path: &str,
+    output_path: &str,
+) -> Result<()> {
+    let typst_markup = generate_offers_markup(comparison, name, typst_config);
+
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).context("Failed to create output directory")?;
+    }
+
+    fs::write(temp_path, &typst_markup)
+        .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
+
+    super::compile(temp_path, output_path, typst_config)?;
+
+    fs::remove_file(temp_path)
+        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
+
+    Ok(())
+}
+
+/// Generate Typst markup for the worksheet
+fn generate_offers_markup(
+    comparison: &OfferComparison,
+    name: &str,
+    typst_config: &TypstConfig,
+) -> String {
+    let colors = &typst_config.customization.colors;
+    let layout = &typst_config.customization.layout;
+    let title = format!("Offers: {}", name);
+    let ranked = comparison.ranked();
+
+    let setup = [
+        format!(
+            "#set document(title: \"{}\", author: \"{}\")",
+            string_literal(&title),
+            string_literal(name)
+        ),
+        format!(
+            "#set page(paper: \"{}\", flipped: true, margin: (x: 1.8cm, y: 2cm), header: text(size: 8pt, fill: rgb(\"{}\"))[Private #h(1fr) {}])",
+            layout.paper_size,
+            colors.text_medium,
+            escape_typst_markup(&title)
+        ),
+        format!(
+            "#set text(font: \"{}\", size: 10pt, fill: rgb(\"{}\"))",
+            layout.font, colors.text_dark
+        ),
+        format!(
+            "#set table(stroke: (x, y) => if y == 0 {{ (bottom: 1pt + rgb(\"{}\")) }} else {{ (bottom: 0.5pt + luma(220)) }}, inset: 6pt)",
+            colors.accent
+        ),
+    ]
+    .join("\n");
+
+    let intro = format!(
+        "{} {}, by expected yearly compensation. Amounts in {}; percentages are the chance a component is paid out.",
+        ranked.len(),
+        if ranked.len() == 1 { "offer" } else { "offers" },
+        escape_typst_markup(&comparison.currency)
+    );
+    let header = append_lines(
+        append_lines(
+            append_lines(setup, ""),
+            &format!("= {}", escape_typst_markup(&title)),
+        ),
+        &intro,
+    );
+
+    let component_rows = comparison
+        .component_names()
+        .iter()
+        .map(|component| {
+            let cells = ranked
+                .iter()
+                .map(|r| match r.offer.component(component) {
+                    Some(c) if c.certainty < 1.0 => format!(
+                        "{} ({:.0}%)",
+                        comparison.money(c.amount),
+                        c.certainty * 100.0
+                    ),
+                    Some(c) => comparison.money(c.amount),
+                    None => "–".to_string(),
+                })
+                .collect::<Vec<_>>();
+            row(component, &cells, false)
+        })
+        .chain([
+            row(
+                "Guaranteed",
+                &ranked
+                    .iter()
+                    .map(|r| comparison.money(r.guaranteed))
+                    .collect::<Vec<_>>(),
+                true,
+            ),
+            row(
+                "Expected",
+                &ranked
+                    .iter()
+                    .map(|r| comparison.money(r.expected))
+                    .collect::<Vec<_>>(),
+                true,
+            ),
+        ])
+        .collect::<Vec<_>>();
+
+    let benefit_rows = comparison
+        .criteria
+        .iter()
+        .map(|criterion| {
+            let cells = ranked
+                .iter()
+                .map(|r| {
+                    r.offer
+                        .benefits
+                        .get(&criterion.name)
+                        .map(|score| format!("{score}"))
+                        .unwrap_or_else(|| "–".to_string())
+                })
+                .collect::<Vec<_>>();
+            row(
+                &format!("{} (×{})", criterion.name, criterion.weight),
+                &cells,
+                false,
+            )
+        })
+        .chain([row(
+            "Score",
+            &ranked
+                .iter()
+                .map(|r| format!("{:.1} / {}", r.benefits_score, MAX_SCORE))
+                .collect::<Vec<_>>(),
+            true,
+        )])
+        .collect::<Vec<_>>();
+
+    let with_compensation = append_lines(
+        append_lines(header, "== Compensation"),
+        &table(&ranked, &component_rows),
+    );
+    let with_benefits = append_lines(
+        append_lines(with_compensation, "== Benefits"),
+        &table(&ranked, &benefit_rows),
+    );
+
+    ranked
+        .iter()
+        .filter_map(|r| r.offer.notes.as_ref().map(|notes| (r, notes)))
+        .fold(append_line(with_benefits, ""), |markup, (r, notes)| {
+            append_lines(
+                markup,
+                &format!(
+                    "*{}:* {}",
+                    escape_typst_markup(&r.offer.company),
+                    escape_typst_markup(notes)
+                ),
+            )
+        })
+}
+
+/// A table with a column per offer
+fn table(ranked: &im::Vector<RankedOffer>, rows: &[String]) -> String {
+    let header = ranked
+        .iter()
+        .map(|r| {
+            format!(
+                "[*{}* \\ #text(size: 8pt)[{}]]",
+                escape_typst_markup(&r.offer.company),
+                escape_typst_markup(&r.offer.role)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "#table(columns: {}, [], {},\n{})",
+        ranked.len() + 1,
+        header,
+        rows.join(",\n")
+    )
+}
+
+/// A table row: a label and a cell per offer
+fn row(label: &str, cells: &[String], bold: bool) -> String {
+    let cell = |text: &str| {
+        if bold {
+            format!("[*{}*]", escape_typst_markup(text))
+        } else {
+            format!("[{}]", escape_typst_markup(text))
+        }
+    };
+    std::iter::once(cell(label))
+        .chain(cells.iter().map(|text| cell(text)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offers_markup() {
+        let comparison = OfferComparison::from_yaml_str(
+            "criteria:\n  - name: Remote work\noffers:\n  - company: \"Acme #1\"\n    role: Engineer\n    compensation:\n      - name: Base salary\n        amount: 85000\n    benefits:\n      Remote work: 4\n",
+        )
+        .unwrap();
+
+        let markup = generate_offers_markup(&comparison, "Ada", &TypstConfig::default());
+        assert!(markup.contains("= Offers: Ada\n"));
+        assert!(
+            markup.contains("#table(columns: 2, [], [*Acme \\#1* \\ #text(size: 8pt)[Engineer]],")
+        );
+        assert!(markup.contains("[Base salary], [EUR 85,000]"));
+        assert!(markup.contains("[*Score*], [*4.0 \\/ 5*]"));
+    }
+}
@@ -0,0 +1,301 @@
+//! Project portfolio Typst markup generation
+//!
+//! Generates a print-ready portfolio, separate from the CV: a title page
+//! followed by one page per project with its screenshot, highlights and
+//! technologies. Links become footnotes showing the full URL, so they still
+//! work on paper. Uses plain Typst with the colors and fonts of the CV theme,
+//! since the CV templates have no layout for full-page project entries.
+
+use super::utils::{append_line, append_lines, join_strings, string_literal};
+use crate::cv_data::{Cv, Project};
+use crate::site_config::TypstConfig;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+// Extension trait to enable method chaining with pipe (same as markup.rs)
+trait Pipe: Sized {
+    fn pipe<F, R>(self, f: F) -> R
+    where
+        F: FnOnce(Self) -> R,
+    {
+        f(self)
+    }
+}
+
+impl Pipe for String {}
+
+/// Generate the project portfolio PDF
+///
+/// # Arguments
+///
+/// * `cv` - The CV data with the projects
+/// * `typst_config` - Typst configuration for colors, fonts and paper size
+/// * `temp_path` - Path to a temporary file for Typst markup
+/// * `output_path` - Path where the PDF will be written
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_portfolio_pdf(
+    cv: &Cv,
+    typst_config: &TypstConfig,
+    temp_path: &str,
+    output_path: &str,
+) -> Result<()> {
+    let typst_markup = generate_portfolio_markup(cv, typst_config);
+
+    // Ensure output directory exists
+    if let Some(parent) = Path::new(output_path).parent() {
+        fs::create_dir_all(parent).context("Failed to create output directory")?;
+    }
+
+    // Write Typst markup to temporary file
+    fs::write(temp_path, &typst_markup)
+        .with_context(|| format!("Failed to write Typst markup to {temp_path}"))?;
+
+    super::compile(temp_path, output_path, typst_config)?;
+
+    // Clean up temp file
+    fs::remove_file(temp_path)
+        .with_context(|| format!("Failed to remove temporary file: {temp_path}"))?;
+
+    Ok(())
+}
+
+/// Generate Typst markup for the project portfolio
+///
+/// # Arguments
+///
+/// * `cv` - The CV data with the projects
+/// * `typst_config` - Typst configuration for colors, fonts and paper size
+///
+/// # Returns
+///
+/// The Typst markup, one page per project after the title page
+pub fn generate_portfolio_markup(cv: &Cv, typst_config: &TypstConfig) -> String {
+    cv.projects.iter().fold(
+        String::new()
+            .pipe(|s| generate_document_setup(s, cv, typst_config))
+            .pipe(|s| generate_title_page(s, cv)),
+        |markup, project| {
+            markup
+                .pipe(|s| append_lines(s, "#pagebreak()"))
+                .pipe(|s| generate_project_page(s, project))
+        },
+    )
+}
+
+/// Generate the document, page and text settings
+fn generate_document_setup(markup: String, cv: &Cv, typst_config: &TypstConfig) -> String {
+    let colors = &typst_config.customization.colors;
+    let layout = &typst_config.customization.layout;
+    let name = string_literal(&cv.personal_info.name);
+
+    markup
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!("#set document(title: \"{name} - Portfolio\", author: \"{name}\")"),
+            )
+        })
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!(
+                    "#set page(paper: \"{}\", fill: rgb(\"{}\"), margin: (x: 2cm, y: 2.2cm), footer: context text(size: 8pt, fill: rgb(\"{}\"))[{} #h(1fr) #counter(page).display()])",
+                    layout.paper_size,
+                    colors.fill,
+                    colors.text_medium,
+                    cv.personal_info.name
+                ),
+            )
+        })
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!(
+                    "#set text(font: \"{}\", size: 11pt, fill: rgb(\"{}\"))",
+                    layout.font, colors.text_dark
+                ),
+            )
+        })
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!(
+                    "#show heading.where(level: 1): it => block(width: 100%, stroke: (bottom: 1pt + rgb(\"{}\")), inset: (bottom: 6pt))[#text(size: 20pt, it.body)]",
+                    colors.accent
+                ),
+            )
+        })
+        .pipe(|s| {
+            append_lines(
+                s,
+                &format!(
+                    "#show link: set text(fill: rgb(\"{}\"))",
+                    colors.text_medium
+                ),
+            )
+        })
+}
+
+/// Generate the title page with the name, title and contact details
+fn generate_title_page(markup: String, cv: &Cv) -> String {
+    let info = &cv.personal_info;
+    let contact = [
+        Some(info.email.as_str()),
+        info.website.as_deref(),
+        info.location.as_deref(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|value| !value.is_empty())
+    .map(|value| value.to_string())
+    .collect();
+
+    markup
+        .pipe(|s| append_line(s, "#align(center + horizon)["))
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!("  #text(size: 28pt, weight: \"bold\")[{}]", info.name),
+            )
+        })
+        .pipe(|s| append_line(s, "  #v(6pt)"))
+        .pipe(|s| append_line(s, &format!("  #text(size: 14pt)[{}]", info.title)))
+        .pipe(|s| append_line(s, "  #v(18pt)"))
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!(
+                    "  #text(size: 16pt, weight: \"bold\")[Project Portfolio] \\ {} projects",
+                    cv.projects.len()
+                ),
+            )
+        })
+        .pipe(|s| append_line(s, "  #v(18pt)"))
+        .pipe(|s| {
+            append_line(
+                s,
+                &format!(
+                    "  #text(size: 9pt)[{}]",
+                    join_strings(&contact, " #sym.dot.c ")
+                ),
+            )
+        })
+        .pipe(|s| append_lines(s, "]"))
+}
+
+/// Generate a page for one project
+fn generate_project_page(markup: String, project: &Project) -> String {
+    let name = project.display_name.as_ref().unwrap_or(&project.name);
+
+    let with_description = markup
+        .pipe(|s| append_lines(s, &format!("= {name}")))
+        .pipe(|s| append_lines(s, &project.description));
+
+    // Typst can only read local files; the path is relative to the project root
+    let with_screenshot = match project
+        .screenshot
+        .as_deref()
+        .filter(|path| !path.contains("://"))
+    {
+        Some(path) => append_lines(
+            with_description,
+            &format!(
+                "#figure(image(\"/{}\", width: 100%))",
+                string_literal(path.trim_start_matches("./").trim_start_matches('/'))
+            ),
+        ),
+        None => with_description,
+    };
+
+    let with_highlights = if project.highlights.is_empty() {
+        with_screenshot
+    } else {
+        project
+            .highlights
+            .iter()
+            .fold(append_line(with_screenshot, "== Highlights"), |s, item| {
+                append_line(s, &format!("- {item}"))
+            })
+            .pipe(|s| append_line(s, ""))
+    };
+
+    let with_technologies = if project.technologies.is_empty() {
+        with_highlights
+    } else {
+        append_lines(
+            with_highlights,
+            &format!(
+                "*Technologies:* {}",
+                join_strings(&project.technologies, ", ")
+            ),
+        )
+    };
+
+    let links = [
+        ("Website", &project.url),
+        ("Repository", &project.repository),
+    ]
+    .into_iter()
+    .filter_map(|(label, url)| url.as_ref().map(|url| footnoted_link(label, url)))
+    .collect::<im::Vector<_>>();
+    if links.is_empty() {
+        with_technologies
+    } else {
+        append_lines(
+            with_technologies,
+            &format!("*Links:* {}", join_strings(&links, ", ")),
+        )
+    }
+}
+
+/// A link whose full URL is printed as a footnote
+fn footnoted_link(label: &str, url: &str) -> String {
+    let url = string_literal(url);
+    format!("#link(\"{url}\")[{label}]#footnote[#link(\"{url}\")]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_portfolio_markup() {
+        let mut cv = Cv::create_minimal();
+        cv.projects = im::vector![Project {
+            name: "cv - Rust".to_string(),
+            display_name: Some("cv".to_string()),
+            description: "Static site and PDF generator".to_string(),
+            url: None,
+            repository: Some("https://github.com/ada/cv".to_string()),
+            technologies: im::vector!["Rust".to_string(), "Typst".to_string()],
+            highlights: im::vector!["One source, three formats".to_string()],
+            stars: None,
+            owner_username: None,
+            owner_avatar: None,
+            screenshot: Some("./static/img/cv.png".to_string()),
+            registry: None,
+            package_url: None,
+            version: None,
+            downloads: None,
+            updated_at: None,
+            case_study: None,
+            language: None,
+            language_icon: None,
+        }];
+
+        let markup = generate_portfolio_markup(&cv, &TypstConfig::default());
+
+        assert_eq!(markup.matches("#pagebreak()").count(), 1);
+        assert!(markup.contains("= cv\n"));
+        assert!(markup.contains("#figure(image(\"/static/img/cv.png\", width: 100%))"));
+        assert!(markup.contains("- One source, three formats\n"));
+        assert!(markup.contains("*Technologies:* Rust, Typst"));
+        assert!(markup.contains(
+            "*Links:* #link(\"https://github.com/ada/cv\")[Repository]#footnote[#link(\"https://github.com/ada/cv\")]"
+        ));
+    }
+}
@@ -1,6 +1,8 @@
-use super::utils::{append_line, append_lines, join_strings};
+use super::source_map::append_source;
+use super::utils::{append_line, append_lines, escape_typst_markup, join_strings};
 /// Functions for generating Typst markup for different CV sections
-use crate::cv_data::{Cv, Education, Experience, Project, SkillCategory};
+use crate::cv_data::{Cv, Education, Experience, Project, Skill, SkillCategory, SkillLevel};
+use crate::testimonials::Testimonial;
 use im::Vector;
 
 /// Generates Typst markup for the summary section
@@ -13,7 +15,12 @@ use im::Vector;
 ///
 /// The Typst markup for the summary section
 pub fn generate_summary_section(cv: &Cv) -> String {
+    if cv.personal_info.summary.is_empty() {
+        return String::new();
+    }
+
     String::new()
+        .pipe(|s| append_source(s, "personal_info.summary"))
         .pipe(|s| append_line(s, "= #fa-icon(section_icons.profile) #h(5pt) Summary"))
         .pipe(|s| append_line(s, "#v(5pt)"))
         .pipe(|s| append_lines(s, &cv.personal_info.summary))
@@ -71,12 +78,21 @@ where
 ///
 /// The Typst markup for the experience section
 pub fn generate_experience_section(cv: &Cv) -> String {
+    if cv.experiences.is_empty() {
+        return String::new();
+    }
+
     let base = String::new()
         .pipe(|s| append_line(s, "= #fa-icon(section_icons.experience) #h(5pt) Experience"))
         .pipe(|s| append_lines(s, "== Professional Experience"));
 
     // Use fold to accumulate experience entries
-    cv.experiences.iter().fold(base, append_experience_entry)
+    cv.experiences
+        .iter()
+        .enumerate()
+        .fold(base, |acc, (i, exp)| {
+            append_experience_entry(append_source(acc, &format!("experiences[{i}]")), exp)
+        })
 }
 
 /// Generates Typst markup for a single experience entry
@@ -95,10 +111,10 @@ fn append_experience_entry(markup: String, exp: &Experience) -> String {
 
     // Company and date range
     let company_line = format!(
-        "*{}* | {} – {}",
+        "*{}* | {} #text(fill: gray)[({})]",
         exp.company,
-        exp.start_date,
-        exp.end_date.as_deref().unwrap_or("Present")
+        exp.period("en"),
+        exp.duration()
     );
     let with_company = with_position.pipe(|s| append_lines(s, &company_line));
 
@@ -143,7 +159,12 @@ pub fn generate_projects_section(cv: &Cv) -> String {
         let base = String::new().pipe(|s| append_lines(s, "= Projects"));
 
         // Use fold to accumulate project entries
-        cv.projects.iter().fold(base, append_project_entry)
+        cv.projects
+            .iter()
+            .enumerate()
+            .fold(base, |acc, (i, project)| {
+                append_project_entry(append_source(acc, &format!("projects[{i}]")), project)
+            })
     } else {
         String::new()
     }
@@ -206,6 +227,72 @@ fn append_project_entry(markup: String, project: &Project) -> String {
     }
 }
 
+/// Generates Typst markup for the testimonials section
+///
+/// Testimonials are submitted by others, so all of their text is escaped.
+///
+/// # Arguments
+///
+/// * `cv` - The CV data
+///
+/// # Returns
+///
+/// The Typst markup for the testimonials section
+pub fn generate_testimonials_section(cv: &Cv) -> String {
+    if !cv.testimonials.is_empty() {
+        // Start with the section header
+        let base = String::new().pipe(|s| append_lines(s, "= Testimonials"));
+
+        // Use fold to accumulate testimonials
+        cv.testimonials
+            .iter()
+            .enumerate()
+            .fold(base, |acc, (i, testimonial)| {
+                append_testimonial_entry(
+                    append_source(acc, &format!("testimonials[{i}]")),
+                    testimonial,
+                )
+            })
+    } else {
+        String::new()
+    }
+}
+
+/// Generates Typst markup for a single testimonial
+///
+/// # Arguments
+///
+/// * `markup` - The current Typst markup
+/// * `testimonial` - The testimonial
+///
+/// # Returns
+///
+/// The updated Typst markup
+fn append_testimonial_entry(markup: String, testimonial: &Testimonial) -> String {
+    let author = escape_typst_markup(&testimonial.author);
+    let author = match testimonial.link {
+        Some(ref link) => format!(
+            "#link(\"{}\")[{author}]",
+            link.replace('\\', "\\\\").replace('"', "\\\"")
+        ),
+        None => author,
+    };
+    let attribution = match testimonial.relation {
+        Some(ref relation) => format!("{author}, {}", escape_typst_markup(relation)),
+        None => author,
+    };
+
+    markup.pipe(|s| {
+        append_lines(
+            s,
+            &format!(
+                "#quote(block: true, attribution: [{attribution}])[{}]",
+                escape_typst_markup(&testimonial.quote)
+            ),
+        )
+    })
+}
+
 /// Generates Typst markup for the skills section
 ///
 /// # Arguments
@@ -223,7 +310,15 @@ pub fn generate_skills_section(cv: &Cv) -> String {
             .pipe(|s| append_lines(s, "== Key Skills"));
 
         // Use fold to accumulate skill categories
-        cv.skill_categories.iter().fold(base, append_skill_category)
+        cv.skill_categories
+            .iter()
+            .enumerate()
+            .fold(base, |acc, (i, category)| {
+                append_skill_category(
+                    append_source(acc, &format!("skill_categories[{i}]")),
+                    category,
+                )
+            })
     } else {
         String::new()
     }
@@ -244,7 +339,28 @@ fn append_skill_category(markup: String, category: &SkillCategory) -> String {
     let with_name = markup.pipe(|s| append_lines(s, &format!("=== {}", category.name)));
 
     // Add skills
-    append_bullet_list(with_name, &category.skills, |skill| skill.clone())
+    append_bullet_list(with_name, &category.skills, skill_markup)
+}
+
+/// Generates Typst markup for a skill, with its level as filled dots
+///
+/// # Arguments
+///
+/// * `skill` - The skill
+///
+/// # Returns
+///
+/// E.g. `Rust #h(1fr) #text(size: 7pt, fill: luma(120))[●●●○]`
+fn skill_markup(skill: &Skill) -> String {
+    match skill.level {
+        Some(level) => format!(
+            "{} #h(1fr) #text(size: 7pt, fill: luma(120))[{}{}]",
+            skill.name,
+            "●".repeat(level.rank()),
+            "○".repeat(SkillLevel::MAX_RANK - level.rank())
+        ),
+        None => skill.name.clone(),
+    }
 }
 
 /// Generates Typst markup for the languages section
@@ -260,6 +376,7 @@ pub fn generate_languages_section(cv: &Cv) -> String {
     if !cv.languages.is_empty() {
         // Start with the section header
         let base = String::new()
+            .pipe(|s| append_source(s, "languages"))
             .pipe(|s| append_line(s, "= #fa-icon(section_icons.languages) #h(5pt) Languages"))
             .pipe(|s| append_lines(s, "#v(5pt)"));
 
@@ -295,7 +412,12 @@ pub fn generate_education_section(cv: &Cv) -> String {
             .pipe(|s| append_lines(s, "== Education"));
 
         // Use fold to accumulate education entries
-        cv.education.iter().fold(header, append_education_entry)
+        cv.education
+            .iter()
+            .enumerate()
+            .fold(header, |acc, (i, edu)| {
+                append_education_entry(append_source(acc, &format!("education[{i}]")), edu)
+            })
     } else {
         base
     };
@@ -303,7 +425,9 @@ pub fn generate_education_section(cv: &Cv) -> String {
     // Add certifications section if not empty
     if !cv.certifications.is_empty() {
         // Add section header
-        let with_cert_header = with_education.pipe(|s| append_lines(s, "== Certificates"));
+        let with_cert_header = with_education
+            .pipe(|s| append_source(s, "certifications"))
+            .pipe(|s| append_lines(s, "== Certificates"));
 
         // Use fold to accumulate certifications
         let with_certs = cv
@@ -336,14 +460,7 @@ fn append_education_entry(markup: String, edu: &Education) -> String {
         markup.pipe(|s| append_line(s, &format!("=== {} in {}", edu.degree, edu.field)));
 
     // Institution and date range
-    let institution_line = {
-        let base = format!("{} {}", edu.institution, edu.start_date);
-        if let Some(end_date) = &edu.end_date {
-            format!("{base}-{end_date}")
-        } else {
-            base
-        }
-    };
+    let institution_line = format!("{} | {}", edu.institution, edu.period("en"));
 
     let with_institution = with_degree.pipe(|s| append_lines(s, &institution_line));
 
@@ -0,0 +1,155 @@
+/// Mapping generated Typst markup back to the CV data
+///
+/// Every block of the CV markup starts with a `// cv: <field>` comment naming
+/// the CV field it was generated from, e.g. `// cv: experiences[2]`. When
+/// Typst rejects the markup, the lines it points at are shown with the field
+/// they came from, so the fix can be made in the CV data instead of in
+/// generated markup.
+use regex::Regex;
+use std::sync::OnceLock;
+
+use super::utils::append_line;
+
+/// Start of the comment naming the CV field a block was generated from
+const SOURCE_PREFIX: &str = "// cv: ";
+
+/// Lines shown before and after an offending line
+const CONTEXT_LINES: usize = 2;
+
+/// Mark the markup that follows as generated from a CV field
+///
+/// # Arguments
+///
+/// * `markup` - The current Typst markup
+/// * `field` - Path of the field in the CV data, e.g. `experiences[0]`
+///
+/// # Returns
+///
+/// The markup with the source comment appended
+pub fn append_source(markup: String, field: &str) -> String {
+    append_line(markup, &format!("{SOURCE_PREFIX}{field}"))
+}
+
+/// The CV field the markup at a line was generated from
+///
+/// # Arguments
+///
+/// * `markup` - The generated Typst markup
+/// * `line` - A line number, starting at 1
+///
+/// # Returns
+///
+/// The field of the nearest source comment at or above the line
+pub fn source_of(markup: &str, line: usize) -> Option<&str> {
+    markup
+        .lines()
+        .take(line)
+        .filter_map(|text| text.trim_start().strip_prefix(SOURCE_PREFIX))
+        .last()
+}
+
+/// Line numbers of the errors the `typst` CLI reported in a file
+///
+/// # Arguments
+///
+/// * `diagnostics` - What `typst compile` wrote to stderr
+/// * `file_name` - Name of the compiled file, e.g. `temp_cv-0.typ`
+///
+/// # Returns
+///
+/// The lines, starting at 1, in the order they were reported
+#[cfg_attr(feature = "embedded-typst", allow(dead_code))]
+pub fn error_lines(diagnostics: &str, file_name: &str) -> Vec<usize> {
+    static LOCATION: OnceLock<Regex> = OnceLock::new();
+    let location = LOCATION.get_or_init(|| Regex::new(r"┌─ (\S+?):(\d+):\d+").unwrap());
+    let mut lines: Vec<usize> = location
+        .captures_iter(diagnostics)
+        .filter(|captures| captures[1].ends_with(file_name))
+        .filter_map(|captures| captures[2].parse().ok())
+        .collect();
+    lines.dedup();
+    lines
+}
+
+/// The offending lines of the markup, with the CV fields they came from
+///
+/// # Arguments
+///
+/// * `markup` - The generated Typst markup
+/// * `lines` - Lines Typst reported errors at, starting at 1
+///
+/// # Returns
+///
+/// One excerpt per line, the line marked with `>`, or an empty string when
+/// there are no lines
+pub fn excerpts(markup: &str, lines: &[usize]) -> String {
+    let text: Vec<&str> = markup.lines().collect();
+    lines
+        .iter()
+        .filter(|&&line| line >= 1 && line <= text.len())
+        .map(|&line| {
+            let heading = match source_of(markup, line) {
+                Some(field) => format!("Line {line}, generated from cv.{field}:"),
+                None => format!("Line {line}:"),
+            };
+            let first = line.saturating_sub(CONTEXT_LINES).max(1);
+            let last = (line + CONTEXT_LINES).min(text.len());
+            let shown = (first..=last)
+                .map(|number| {
+                    let marker = if number == line { '>' } else { ' ' };
+                    format!("{marker} {number:4} | {}", text[number - 1])
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{heading}\n{shown}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markup() -> String {
+        let markup = append_source(String::new(), "personal_info");
+        let markup = append_line(markup, "#let personal_info = (first_name: \"Ada\")");
+        let markup = append_source(markup, "experiences[0]");
+        let markup = append_line(markup, "=== Engineer");
+        append_line(markup, "*Acme* | #broken(")
+    }
+
+    #[test]
+    fn test_source_of() {
+        let markup = markup();
+        assert_eq!(source_of(&markup, 2), Some("personal_info"));
+        assert_eq!(source_of(&markup, 3), Some("experiences[0]"));
+        assert_eq!(source_of(&markup, 5), Some("experiences[0]"));
+        assert_eq!(source_of("= CV", 1), None);
+    }
+
+    #[test]
+    fn test_error_lines() {
+        let stderr = concat!(
+            "error: unclosed delimiter\n",
+            "  ┌─ temp_cv-0.typ:5:14\n",
+            "  │\n",
+            "5 │ *Acme* | #broken(\n",
+            "\n",
+            "error: unknown variable\n",
+            "  ┌─ @preview/grotesk-cv:1.0.5/lib.typ:3:1\n",
+        );
+        assert_eq!(error_lines(stderr, "temp_cv-0.typ"), vec![5]);
+        assert!(error_lines("error: file not found", "temp_cv-0.typ").is_empty());
+    }
+
+    #[test]
+    fn test_excerpts() {
+        let excerpt = excerpts(&markup(), &[5]);
+        assert!(excerpt.starts_with("Line 5, generated from cv.experiences[0]:\n"));
+        assert!(excerpt.contains(">    5 | *Acme* | #broken("));
+        assert!(excerpt.contains("     3 | // cv: experiences[0]"));
+        assert!(!excerpt.contains("     2 |"));
+        assert_eq!(excerpts(&markup(), &[42]), "");
+    }
+}
@@ -0,0 +1,119 @@
+/// Custom Typst templates
+///
+/// A custom template is a regular Typst file. The CV data and PDF settings are
+/// injected ahead of it as Typst dictionaries, so the template can use
+/// `#cv.personal_info.name`, `#for exp in cv.experiences [...]` or
+/// `#config.customization.colors.accent` directly.
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::cv_data::Cv;
+use crate::site_config::TypstConfig;
+
+/// Converts a JSON value into an equivalent Typst literal
+///
+/// Objects become dictionaries, arrays become arrays and `null` becomes `none`.
+///
+/// # Arguments
+///
+/// * `value` - The JSON value to convert
+///
+/// # Returns
+///
+/// The Typst literal
+pub fn typst_value(value: &Value) -> String {
+    match value {
+        Value::Null => "none".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => typst_string(s),
+        Value::Array(items) => match items.len() {
+            0 => "()".to_string(),
+            // A single element needs a trailing comma to be an array
+            1 => format!("({},)", typst_value(&items[0])),
+            _ => format!(
+                "({})",
+                items.iter().map(typst_value).collect::<Vec<_>>().join(", ")
+            ),
+        },
+        Value::Object(fields) if fields.is_empty() => "(:)".to_string(),
+        Value::Object(fields) => format!(
+            "({})",
+            fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}", typst_string(key), typst_value(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Quotes and escapes a string for Typst
+fn typst_string(s: &str) -> String {
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+/// Generates Typst markup from a custom template
+///
+/// # Arguments
+///
+/// * `cv` - The CV data, available to the template as `cv`
+/// * `typst_config` - Typst configuration, available to the template as `config`
+/// * `template` - The template source
+///
+/// # Returns
+///
+/// The template with the data definitions prepended
+pub fn generate_custom_markup(
+    cv: &Cv,
+    typst_config: &TypstConfig,
+    template: &str,
+) -> Result<String> {
+    let cv_value = serde_json::to_value(cv).context("Failed to serialize CV data for Typst")?;
+    let config_value =
+        serde_json::to_value(typst_config).context("Failed to serialize Typst configuration")?;
+
+    Ok(format!(
+        "// Data injected by cv-generator\n#let cv = {}\n#let config = {}\n\n{}",
+        typst_value(&cv_value),
+        typst_value(&config_value),
+        template
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_typst_value() {
+        assert_eq!(typst_value(&json!(null)), "none");
+        assert_eq!(typst_value(&json!([])), "()");
+        assert_eq!(typst_value(&json!(["Rust"])), "(\"Rust\",)");
+        assert_eq!(typst_value(&json!({})), "(:)");
+        assert_eq!(
+            typst_value(&json!({ "name": "Jane \"JD\" Doe", "years": 5 })),
+            "(\"name\": \"Jane \\\"JD\\\" Doe\", \"years\": 5)"
+        );
+    }
+
+    #[test]
+    fn test_generate_custom_markup() {
+        let cv = Cv::create_minimal();
+        let markup =
+            generate_custom_markup(&cv, &TypstConfig::default(), "= #cv.personal_info.name")
+                .unwrap();
+
+        assert!(markup.starts_with("// Data injected by cv-generator\n#let cv = ("));
+        assert!(markup.contains("\"name\": \"Test User\""));
+        assert!(markup.contains("#let config = ("));
+        assert!(markup.ends_with("\n\n= #cv.personal_info.name"));
+    }
+}
@@ -1,6 +1,8 @@
 /// Utility functions for Typst markup generation
 use im::Vector;
 
+use crate::site_config::TypstConfig;
+
 /// Splits a full name into first name and last name
 ///
 /// # Arguments
@@ -38,6 +40,29 @@ pub fn split_name(full_name: &str) -> (String, String) {
     (first_name, last_name)
 }
 
+/// Builds the font discovery arguments for the `typst` CLI
+///
+/// # Arguments
+///
+/// * `typst_config` - Typst configuration with the font settings
+///
+/// # Returns
+///
+/// The `--font-path` and `--ignore-system-fonts` arguments
+#[cfg_attr(feature = "embedded-typst", allow(dead_code))]
+pub fn font_args(typst_config: &TypstConfig) -> Vector<String> {
+    typst_config
+        .font_paths
+        .iter()
+        .flat_map(|path| ["--font-path".to_string(), path.clone()])
+        .chain(
+            typst_config
+                .ignore_system_fonts
+                .then(|| "--ignore-system-fonts".to_string()),
+        )
+        .collect()
+}
+
 /// Formats an email address for Typst (replacing @ with " at ")
 ///
 /// # Arguments
@@ -51,6 +76,48 @@ pub fn format_email_for_typst(email: &str) -> String {
     email.replace('@', " at ")
 }
 
+/// Escapes text for Typst markup, so it can't start code, math or markup
+///
+/// # Arguments
+///
+/// * `text` - Untrusted text, e.g. a submitted testimonial
+///
+/// # Returns
+///
+/// The text with every markup character escaped
+pub fn escape_typst_markup(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            if matches!(
+                c,
+                '\\' | '#'
+                    | '$'
+                    | '*'
+                    | '_'
+                    | '`'
+                    | '<'
+                    | '>'
+                    | '@'
+                    | '['
+                    | ']'
+                    | '~'
+                    | '='
+                    | '-'
+                    | '+'
+                    | '/'
+            ) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+}
+
+/// Escape text for use inside a Typst string literal
+pub fn string_literal(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
 /// Joins a vector of strings with a separator
 ///
 /// # Arguments
@@ -5,13 +5,18 @@
 /// from multiple sources (files, environment variables, command-line arguments).
 use anyhow::{Context, Result};
 use config::{Config, Environment, File};
-use im::{HashMap, Vector};
+use im::{HashMap, OrdMap, Vector};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+use crate::cv_data::PublicDataConfig;
+use crate::data_sources::DataSource;
+use crate::db::{CvSource, DEFAULT_DB_PATH};
+use crate::secrets::{Secret, SecretSource};
+use crate::webhooks::WebhookEndpoint;
 
 /// Configuration key for the GitHub API token
-#[allow(dead_code)]
 pub const GITHUB_TOKEN_KEY: &str = "github_token";
 
 /// Configuration key for the GitHub cache file
@@ -20,6 +25,10 @@ pub const GITHUB_CACHE_KEY: &str = "github_cache";
 /// Default path for the GitHub cache file
 pub const DEFAULT_GITHUB_CACHE_PATH: &str = "data/github_cache.json";
 
+/// Configuration key for enabling the gh CLI fallback
+#[allow(dead_code)]
+pub const GITHUB_GH_CLI_FALLBACK_KEY: &str = "github_gh_cli_fallback";
+
 /// Configuration key for the GitHub cache TTL (Time To Live) in seconds
 #[allow(dead_code)]
 pub const GITHUB_CACHE_TTL_KEY: &str = "github_cache_ttl";
@@ -47,8 +56,54 @@ pub const DEFAULT_GITHUB_RATE_LIMIT_STRATEGY: &str = "backoff";
 pub const PUBLIC_DATA_KEY: &str = "public_data";
 
 /// Default public data settings (comma-separated list of fields)
-pub const DEFAULT_PUBLIC_DATA: &str =
-    "name,title,summary,experiences,education,skill_categories,projects,languages,certifications";
+///
+/// Everything except the phone number is published unless configured otherwise.
+pub const DEFAULT_PUBLIC_DATA: &str = "name,title,email,website,location,summary,social_links,profile_image,experiences,education,skill_categories,projects,languages,certifications,testimonials";
+
+/// Configuration key for the output formats to build
+pub const OUTPUT_FORMATS_KEY: &str = "output_formats";
+
+/// Default output formats (comma-separated list)
+pub const DEFAULT_OUTPUT_FORMATS: &str = "html,pdf";
+
+/// Configuration key for where the build reads the CV from
+pub const CV_SOURCE_KEY: &str = "cv_source";
+
+/// Default CV source
+pub const DEFAULT_CV_SOURCE: &str = "json";
+
+/// Configuration key for the CV database
+pub const DB_PATH_KEY: &str = "db_path";
+
+/// Configuration key for the days between maintenance runs of the CV database
+pub const DB_MAINTENANCE_DAYS_KEY: &str = "db_maintenance_days";
+
+/// Default days between maintenance runs of the CV database
+pub const DEFAULT_DB_MAINTENANCE_DAYS: u32 = 7;
+
+/// Configuration key for building without network access
+pub const OFFLINE_KEY: &str = "offline";
+
+/// Configuration key for what an offline build does with data that isn't cached
+pub const OFFLINE_MISSING_KEY: &str = "offline_missing";
+
+/// Default handling of data an offline build can't find in the cache
+pub const DEFAULT_OFFLINE_MISSING: &str = "error";
+
+/// Configuration key for keeping the generated Typst markup next to the PDFs
+pub const KEEP_TYPST_KEY: &str = "keep_typst";
+
+/// Configuration key for what the build does with inconsistent CV dates
+pub const DATE_CHECK_KEY: &str = "date_check";
+
+/// Default handling of inconsistent CV dates
+pub const DEFAULT_DATE_CHECK: &str = "warn";
+
+/// File name of the paste-friendly HTML CV in the output directory
+pub const PASTE_OUTPUT_FILE: &str = "cv-paste.html";
+
+/// File name of the project portfolio PDF in the output directory
+pub const PORTFOLIO_OUTPUT_FILE: &str = "portfolio.pdf";
 
 /// Default port for the blog API server
 pub const DEFAULT_API_PORT: u16 = 3000;
@@ -56,6 +111,88 @@ pub const DEFAULT_API_PORT: u16 = 3000;
 /// Default maximum port for the blog API server
 pub const DEFAULT_API_MAX_PORT: u16 = 3010;
 
+/// An output format the build can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// The website, including `cv.html`
+    Html,
+    /// The PDF CV, compiled with Typst
+    Pdf,
+    /// A Word document for applicant tracking systems
+    Docx,
+    /// A single inline-styled HTML file for pasting into Google Docs, Notion or Word online
+    Paste,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "html" => Ok(OutputFormat::Html),
+            "pdf" => Ok(OutputFormat::Pdf),
+            "docx" => Ok(OutputFormat::Docx),
+            "paste" => Ok(OutputFormat::Paste),
+            other => anyhow::bail!(
+                "Unknown output format '{}' (expected html, pdf, docx or paste)",
+                other
+            ),
+        }
+    }
+}
+
+/// What an offline build does when data it needs isn't cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineMissing {
+    /// Fail the build
+    Error,
+    /// Warn and build without the data
+    Warn,
+}
+
+impl std::str::FromStr for OfflineMissing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "error" => Ok(OfflineMissing::Error),
+            "warn" => Ok(OfflineMissing::Warn),
+            other => anyhow::bail!(
+                "Unknown offline_missing value '{}' (expected error or warn)",
+                other
+            ),
+        }
+    }
+}
+
+/// What the build does with CV dates that don't add up (see
+/// [`crate::cv_analysis`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateCheck {
+    /// Don't check the dates
+    Off,
+    /// Warn and build anyway
+    Warn,
+    /// Fail the build
+    Error,
+}
+
+impl std::str::FromStr for DateCheck {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(DateCheck::Off),
+            "warn" => Ok(DateCheck::Warn),
+            "error" => Ok(DateCheck::Error),
+            other => anyhow::bail!(
+                "Unknown date_check value '{}' (expected off, warn or error)",
+                other
+            ),
+        }
+    }
+}
+
 /// Configuration for the site owner
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OwnerConfig {
@@ -93,6 +230,22 @@ pub struct AppConfig {
     #[serde(default = "default_data_path")]
     pub data_path: PathBuf,
 
+    /// Where the build reads the CV from ("json" or "db")
+    #[serde(default = "default_cv_source")]
+    pub cv_source: String,
+
+    /// Path to the CV database, used with the "db" source
+    #[serde(default = "default_db_path")]
+    pub db_path: PathBuf,
+
+    /// Days between the maintenance runs builds do on the CV database, 0 for never
+    #[serde(default = "default_db_maintenance_days")]
+    pub db_maintenance_days: u32,
+
+    /// Threads for image processing, 0 for one per core
+    #[serde(default)]
+    pub image_workers: usize,
+
     /// Directory containing static assets
     #[serde(default = "default_static_dir")]
     pub static_dir: PathBuf,
@@ -113,13 +266,70 @@ pub struct AppConfig {
     #[serde(skip)]
     pub pdf_output: PathBuf,
 
+    /// Path where the Word CV will be written
+    #[serde(skip)]
+    pub docx_output: PathBuf,
+
+    /// Path where the paste-friendly HTML CV will be written
+    #[serde(skip)]
+    pub paste_output: PathBuf,
+
+    /// Path where the project portfolio PDF will be written
+    #[serde(skip)]
+    pub portfolio_output: PathBuf,
+
+    /// Output formats to build (comma-separated: html, pdf, docx, paste)
+    #[serde(default = "default_output_formats")]
+    pub output_formats: String,
+
     /// Path to the GitHub cache file
     #[serde(default = "default_github_cache_path")]
     pub github_cache_path: PathBuf,
 
     /// GitHub API token
     #[serde(default)]
-    pub github_token: Option<String>,
+    pub github_token: Option<Secret>,
+
+    /// Named secrets and where to read them from (see [`crate::secrets`])
+    #[serde(default)]
+    pub secrets: HashMap<String, SecretSource>,
+
+    /// How long build phases may take in milliseconds, by their name in the
+    /// performance summary (see [`crate::performance::BuildProfiler`])
+    #[serde(default)]
+    pub build_budgets: OrdMap<String, u64>,
+
+    /// Data loaded into the working tree before building, by path (see
+    /// [`crate::data_sources`])
+    #[serde(default)]
+    pub data_sources: OrdMap<String, DataSource>,
+
+    /// Addresses and CIDR ranges allowed to reach the admin API of `cv serve`
+    /// (see [`crate::admin::IpAllowlist`]); empty allows none
+    #[serde(default)]
+    pub admin_allowlist: Vector<String>,
+
+    /// Addresses and CIDR ranges of the reverse proxies in front of
+    /// `cv serve`, whose `X-Forwarded-For` header is trusted; empty trusts none
+    #[serde(default)]
+    pub trusted_proxies: Vector<String>,
+
+    /// Combined Log Format file `cv serve` appends every request to, if set
+    #[serde(default)]
+    pub access_log: Option<PathBuf>,
+
+    /// Query parameters hidden from the `cv serve` request log, besides
+    /// [the default ones](crate::request_log::DEFAULT_REDACTED_PARAMS)
+    #[serde(default)]
+    pub redact_params: Vector<String>,
+
+    /// Endpoints told about content events, by name (see [`crate::webhooks`])
+    #[serde(default)]
+    pub webhooks: OrdMap<String, WebhookEndpoint>,
+
+    /// Fall back to the `gh` CLI when native GitHub API requests fail
+    #[serde(default)]
+    pub github_gh_cli_fallback: bool,
 
     /// Time To Live for GitHub cache in seconds
     #[serde(default = "default_github_cache_ttl")]
@@ -134,6 +344,23 @@ pub struct AppConfig {
     pub github_rate_limit_strategy: String,
 
     // OAuth fields removed - they were deprecated and unused
+    /// Build without network access, from cached data only
+    #[serde(default)]
+    pub offline: bool,
+
+    /// What an offline build does with data that isn't cached ("error", "warn")
+    #[serde(default = "default_offline_missing")]
+    pub offline_missing: String,
+
+    /// What the build does with CV dates that don't add up ("off", "warn",
+    /// "error")
+    #[serde(default = "default_date_check")]
+    pub date_check: String,
+
+    /// Environment the site is built for, e.g. "production" or "development"
+    #[serde(default = "default_environment")]
+    pub environment: String,
+
     /// Fields that should be publicly visible (comma-separated)
     #[serde(default = "default_public_data")]
     pub public_data: String,
@@ -164,6 +391,18 @@ fn default_data_path() -> PathBuf {
     PathBuf::from("data/cv_data.json")
 }
 
+fn default_cv_source() -> String {
+    DEFAULT_CV_SOURCE.to_string()
+}
+
+fn default_db_path() -> PathBuf {
+    PathBuf::from(DEFAULT_DB_PATH)
+}
+
+fn default_db_maintenance_days() -> u32 {
+    DEFAULT_DB_MAINTENANCE_DAYS
+}
+
 fn default_static_dir() -> PathBuf {
     PathBuf::from("static")
 }
@@ -184,6 +423,10 @@ fn default_public_data() -> String {
     DEFAULT_PUBLIC_DATA.to_string()
 }
 
+fn default_output_formats() -> String {
+    DEFAULT_OUTPUT_FORMATS.to_string()
+}
+
 fn default_api_port() -> u16 {
     DEFAULT_API_PORT
 }
@@ -204,6 +447,18 @@ fn default_github_rate_limit_strategy() -> String {
     DEFAULT_GITHUB_RATE_LIMIT_STRATEGY.to_string()
 }
 
+fn default_offline_missing() -> String {
+    DEFAULT_OFFLINE_MISSING.to_string()
+}
+
+fn default_date_check() -> String {
+    DEFAULT_DATE_CHECK.to_string()
+}
+
+fn default_environment() -> String {
+    "production".to_string()
+}
+
 // OAuth default function removed
 
 impl Default for AppConfig {
@@ -211,19 +466,43 @@ impl Default for AppConfig {
         let output_dir = PathBuf::from("dist");
         let html_output = output_dir.join("cv.html");
         let pdf_output = output_dir.join("cv.pdf");
+        let docx_output = output_dir.join("cv.docx");
+        let paste_output = output_dir.join(PASTE_OUTPUT_FILE);
+        let portfolio_output = output_dir.join(PORTFOLIO_OUTPUT_FILE);
 
         Self {
             data_path: default_data_path(),
+            cv_source: default_cv_source(),
+            db_path: default_db_path(),
+            db_maintenance_days: default_db_maintenance_days(),
+            image_workers: 0,
             static_dir: default_static_dir(),
             output_dir,
             html_output,
             typst_temp: default_typst_temp(),
             pdf_output,
+            docx_output,
+            paste_output,
+            portfolio_output,
+            output_formats: default_output_formats(),
             github_cache_path: default_github_cache_path(),
             github_token: None,
+            secrets: HashMap::new(),
+            build_budgets: OrdMap::new(),
+            data_sources: OrdMap::new(),
+            admin_allowlist: Vector::new(),
+            trusted_proxies: Vector::new(),
+            access_log: None,
+            redact_params: Vector::new(),
+            webhooks: OrdMap::new(),
+            github_gh_cli_fallback: false,
             github_cache_ttl: default_github_cache_ttl(),
             github_cache_refresh_strategy: default_github_cache_refresh_strategy(),
             github_rate_limit_strategy: default_github_rate_limit_strategy(),
+            offline: false,
+            offline_missing: default_offline_missing(),
+            date_check: default_date_check(),
+            environment: default_environment(),
             // OAuth fields removed
             public_data: default_public_data(),
             api_port: default_api_port(),
@@ -306,16 +585,33 @@ impl AppConfig {
         // Set derived paths
         app_config.html_output = app_config.output_dir.join("cv.html");
         app_config.pdf_output = app_config.output_dir.join("cv.pdf");
+        app_config.docx_output = app_config.output_dir.join("cv.docx");
+        app_config.paste_output = app_config.output_dir.join(PASTE_OUTPUT_FILE);
+        app_config.portfolio_output = app_config.output_dir.join(PORTFOLIO_OUTPUT_FILE);
         info!("Set derived output paths");
         debug!("HTML output path: {}", app_config.html_output.display());
         debug!("PDF output path: {}", app_config.pdf_output.display());
 
+        // Resolve the GitHub token from its declared secret source, if any
+        if app_config.github_token.is_none() {
+            if let Some(source) = app_config.secrets.get(GITHUB_TOKEN_KEY) {
+                match source.resolve() {
+                    Ok(Some((token, origin))) => {
+                        info!("Using GitHub API token from {}", origin);
+                        app_config.github_token = Some(token);
+                    }
+                    Ok(None) => debug!("Declared GitHub token secret is not set"),
+                    Err(e) => warn!("Failed to resolve GitHub token secret: {:#}", e),
+                }
+            }
+        }
+
         // Try to get GitHub token from environment variable if not set
         if app_config.github_token.is_none() {
             if let Ok(token) = std::env::var("GITHUB_TOKEN") {
                 if !token.is_empty() {
                     info!("Using GitHub API token from environment variable");
-                    app_config.github_token = Some(token);
+                    app_config.github_token = Some(Secret::new(token));
                     debug!("GitHub token successfully retrieved from environment");
                 } else {
                     debug!("Empty GitHub API token found in environment variable");
@@ -378,6 +674,37 @@ impl AppConfig {
         })
     }
 
+    /// Gets the DOCX output path as a string
+    pub fn docx_output_str(&self) -> Result<String> {
+        self.path_to_string(&self.docx_output).with_context(|| {
+            format!(
+                "Failed to get DOCX output path as string: {}",
+                self.docx_output.display()
+            )
+        })
+    }
+
+    /// Gets the paste-friendly HTML output path as a string
+    pub fn paste_output_str(&self) -> Result<String> {
+        self.path_to_string(&self.paste_output).with_context(|| {
+            format!(
+                "Failed to get paste HTML output path as string: {}",
+                self.paste_output.display()
+            )
+        })
+    }
+
+    /// Gets the project portfolio PDF output path as a string
+    pub fn portfolio_output_str(&self) -> Result<String> {
+        self.path_to_string(&self.portfolio_output)
+            .with_context(|| {
+                format!(
+                    "Failed to get portfolio PDF output path as string: {}",
+                    self.portfolio_output.display()
+                )
+            })
+    }
+
     /// Gets the Typst temp path as a string
     pub fn typst_temp_str(&self) -> Result<String> {
         debug!(
@@ -439,6 +766,124 @@ impl AppConfig {
         fields
     }
 
+    /// Gets the public data configuration used to filter the CV
+    ///
+    /// # Returns
+    ///
+    /// The configuration, or an error if an unknown field is listed
+    pub fn public_data_config(&self) -> Result<PublicDataConfig> {
+        PublicDataConfig::from_fields(&self.public_data())
+            .context("Invalid public_data configuration")
+    }
+
+    /// Gets the output formats to build
+    ///
+    /// The `output_formats` option (set by `--format`) takes precedence over
+    /// the configured value.
+    ///
+    /// # Returns
+    ///
+    /// The formats in the order given, or an error if one is unknown
+    pub fn output_formats(&self) -> Result<Vector<OutputFormat>> {
+        let formats = self
+            .options
+            .get(OUTPUT_FORMATS_KEY)
+            .unwrap_or(&self.output_formats);
+
+        formats
+            .split(',')
+            .filter(|format| !format.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vector<_>>>()
+            .context("Invalid output_formats configuration")
+    }
+
+    /// Gets where the build reads the CV from
+    ///
+    /// The `cv_source` option (set by `--source`) takes precedence over the
+    /// configured value.
+    ///
+    /// # Returns
+    ///
+    /// The source, or an error if it is unknown
+    pub fn cv_source(&self) -> Result<CvSource> {
+        self.options
+            .get(CV_SOURCE_KEY)
+            .unwrap_or(&self.cv_source)
+            .parse()
+            .context("Invalid cv_source configuration")
+    }
+
+    /// Gets the path of the CV database
+    ///
+    /// The `db_path` option (set by `--db-path`) takes precedence over the
+    /// configured value.
+    pub fn db_path(&self) -> PathBuf {
+        self.options
+            .get(DB_PATH_KEY)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.db_path.clone())
+    }
+
+    /// Gets the days between the maintenance runs builds do on the CV database
+    ///
+    /// The `db_maintenance_days` option takes precedence over the configured
+    /// value.
+    ///
+    /// # Returns
+    ///
+    /// The interval, 0 if builds never run maintenance, or an error if the
+    /// option isn't a number
+    pub fn db_maintenance_days(&self) -> Result<u32> {
+        match self.options.get(DB_MAINTENANCE_DAYS_KEY) {
+            Some(days) => days
+                .parse()
+                .with_context(|| format!("Invalid {}: {}", DB_MAINTENANCE_DAYS_KEY, days)),
+            None => Ok(self.db_maintenance_days),
+        }
+    }
+
+    /// Whether the build must not access the network
+    ///
+    /// The `offline` option (set by `--offline`) turns it on regardless of the
+    /// configured value.
+    pub fn is_offline(&self) -> bool {
+        self.offline || self.options.contains_key(OFFLINE_KEY)
+    }
+
+    /// Whether the build keeps the Typst markup of each PDF next to it
+    ///
+    /// Set by `--keep-typst`, for debugging the PDF output.
+    pub fn keeps_typst(&self) -> bool {
+        self.options.contains_key(KEEP_TYPST_KEY)
+    }
+
+    /// Gets what an offline build does with data that isn't cached
+    ///
+    /// # Returns
+    ///
+    /// The configured handling, or an error if it is unknown
+    pub fn offline_missing(&self) -> Result<OfflineMissing> {
+        self.options
+            .get(OFFLINE_MISSING_KEY)
+            .unwrap_or(&self.offline_missing)
+            .parse()
+            .context("Invalid offline_missing configuration")
+    }
+
+    /// Gets what the build does with CV dates that don't add up
+    ///
+    /// # Returns
+    ///
+    /// The configured handling, or an error if it is unknown
+    pub fn date_check(&self) -> Result<DateCheck> {
+        self.options
+            .get(DATE_CHECK_KEY)
+            .unwrap_or(&self.date_check)
+            .parse()
+            .context("Invalid date_check configuration")
+    }
+
     /// Checks if a field should be publicly visible
     #[allow(dead_code)]
     pub fn is_public(&self, field: &str) -> bool {
@@ -482,6 +927,7 @@ mod tests {
         assert_eq!(config.output_dir, PathBuf::from("dist"));
         assert_eq!(config.html_output, PathBuf::from("dist/cv.html"));
         assert_eq!(config.pdf_output, PathBuf::from("dist/cv.pdf"));
+        assert_eq!(config.docx_output, PathBuf::from("dist/cv.docx"));
     }
 
     #[test]
@@ -505,5 +951,108 @@ mod tests {
         assert!(config.is_public("name"));
         assert!(config.is_public("title"));
         assert!(config.is_public("experiences"));
+        assert!(!config.is_public("phone"));
+    }
+
+    #[test]
+    fn test_public_data_config() {
+        let config = AppConfig::default();
+        let public = config.public_data_config().unwrap();
+        assert!(public.is_public("email"));
+        assert!(!public.is_public("phone"));
+
+        let config = config.with_option(PUBLIC_DATA_KEY, "name, *");
+        assert!(config.public_data_config().unwrap().is_public("phone"));
+
+        let config = AppConfig::default().with_option(PUBLIC_DATA_KEY, "name,telephone");
+        assert!(config.public_data_config().is_err());
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let config = AppConfig::default();
+        assert_eq!(
+            config.output_formats().unwrap(),
+            im::vector![OutputFormat::Html, OutputFormat::Pdf]
+        );
+
+        let config = config.with_option(OUTPUT_FORMATS_KEY, "docx, PDF");
+        assert_eq!(
+            config.output_formats().unwrap(),
+            im::vector![OutputFormat::Docx, OutputFormat::Pdf]
+        );
+
+        let config = AppConfig::default().with_option(OUTPUT_FORMATS_KEY, "html,odt");
+        assert!(config.output_formats().is_err());
+    }
+
+    #[test]
+    fn test_offline() {
+        let config = AppConfig::default();
+        assert!(!config.is_offline());
+        assert_eq!(config.offline_missing().unwrap(), OfflineMissing::Error);
+
+        let config = config
+            .with_option(OFFLINE_KEY, "true")
+            .with_option(OFFLINE_MISSING_KEY, "Warn");
+        assert!(config.is_offline());
+        assert_eq!(config.offline_missing().unwrap(), OfflineMissing::Warn);
+
+        let config = AppConfig::default().with_option(OFFLINE_MISSING_KEY, "ignore");
+        assert!(config.offline_missing().is_err());
+    }
+
+    #[test]
+    fn test_date_check() {
+        assert_eq!(AppConfig::default().date_check().unwrap(), DateCheck::Warn);
+
+        let config = AppConfig::default().with_option(DATE_CHECK_KEY, "Error");
+        assert_eq!(config.date_check().unwrap(), DateCheck::Error);
+
+        let config = AppConfig::default().with_option(DATE_CHECK_KEY, "strict");
+        assert!(config.date_check().is_err());
+    }
+
+    #[test]
+    fn test_data_sources() {
+        let config: AppConfig = Config::builder()
+            .add_source(File::from_str(
+                r#"
+                [data_sources."data/cv_data.json"]
+                git = "origin/content"
+                sha256 = "00"
+
+                [data_sources.content]
+                local = "../cv-private/content"
+                required = true
+                "#,
+                config::FileFormat::Toml,
+            ))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        let cv_data = &config.data_sources["data/cv_data.json"];
+        assert_eq!(cv_data.git.as_deref(), Some("origin/content"));
+        assert_eq!(cv_data.sha256.as_deref(), Some("00"));
+        assert!(!cv_data.required);
+        assert!(config.data_sources["content"].required);
+    }
+
+    #[test]
+    fn test_cv_source() {
+        let config = AppConfig::default();
+        assert_eq!(config.cv_source().unwrap(), CvSource::Json);
+        assert_eq!(config.db_path(), PathBuf::from(DEFAULT_DB_PATH));
+
+        let config = config
+            .with_option(CV_SOURCE_KEY, "db")
+            .with_option(DB_PATH_KEY, "cv.db");
+        assert_eq!(config.cv_source().unwrap(), CvSource::Db);
+        assert_eq!(config.db_path(), PathBuf::from("cv.db"));
+
+        let config = AppConfig::default().with_option(CV_SOURCE_KEY, "yaml");
+        assert!(config.cv_source().is_err());
     }
 }
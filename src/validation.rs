@@ -86,6 +86,38 @@ pub fn validate_paper_size(size: &str) -> Result<()> {
     Ok(())
 }
 
+/// Validate a BCP 47 style language code (e.g. "en", "da", "pt-BR")
+///
+/// Language codes become output directory names, so only a primary subtag of
+/// 2-3 letters followed by optional alphanumeric subtags is accepted.
+///
+/// # Examples
+/// ```
+/// use cv_generator::validation::validate_language_code;
+///
+/// assert!(validate_language_code("en").is_ok());
+/// assert!(validate_language_code("pt-BR").is_ok());
+/// assert!(validate_language_code("../en").is_err());
+/// ```
+pub fn validate_language_code(code: &str) -> Result<()> {
+    let mut subtags = code.split('-');
+    let primary = subtags.next().unwrap_or_default();
+
+    let primary_valid =
+        (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic());
+    let rest_valid = subtags
+        .all(|tag| (1..=8).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric()));
+
+    if !primary_valid || !rest_valid {
+        return Err(anyhow!(
+            "Invalid language code '{}': expected a code like 'en', 'da' or 'pt-BR'",
+            code
+        ));
+    }
+
+    Ok(())
+}
+
 /// Check if a path is safe (doesn't escape base directory)
 ///
 /// This prevents path traversal attacks by ensuring the resolved path
@@ -235,6 +267,19 @@ mod tests {
         assert!(validate_github_username(&"a".repeat(40)).is_err()); // Too long
     }
 
+    #[test]
+    fn test_validate_language_code() {
+        assert!(validate_language_code("en").is_ok());
+        assert!(validate_language_code("fil").is_ok());
+        assert!(validate_language_code("zh-Hant-TW").is_ok());
+
+        assert!(validate_language_code("").is_err());
+        assert!(validate_language_code("e").is_err());
+        assert!(validate_language_code("en/").is_err());
+        assert!(validate_language_code("en-").is_err());
+        assert!(validate_language_code("..").is_err());
+    }
+
     #[test]
     fn test_validate_paper_size() {
         // Valid sizes
@@ -0,0 +1,179 @@
+//! Contact card for the address book
+//!
+//! The personal info from the CV data is published as a vCard 3.0 file, which
+//! every common address book imports, and the CV page marks up the same
+//! details as an [h-card](https://microformats.org/wiki/h-card) for tools that
+//! read microformats.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::cv_data::PersonalInfo;
+
+/// File name of the contact card in the output directory
+pub const VCARD_FILE: &str = "contact.vcf";
+
+/// Longest line in octets before it is folded (RFC 6350, section 3.2)
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Render the personal info as a vCard
+///
+/// # Arguments
+///
+/// * `info` - Personal info from the CV data
+/// * `base_url` - Site URL, to make a relative profile image absolute
+///
+/// # Returns
+///
+/// The vCard, with CRLF line endings and folded long lines
+pub fn vcard(info: &PersonalInfo, base_url: &str) -> String {
+    let name = info.name.trim();
+    let (given, family) = name.rsplit_once(' ').unwrap_or(("", name));
+
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:3.0".to_string(),
+        "PRODID:-//cv-generator//contact//EN".to_string(),
+        format!("FN:{}", escape_text(name)),
+        format!("N:{};{};;;", escape_text(family), escape_text(given)),
+    ];
+    if !info.title.is_empty() {
+        lines.push(format!("TITLE:{}", escape_text(&info.title)));
+    }
+    if !info.email.is_empty() {
+        lines.push(format!("EMAIL;TYPE=INTERNET:{}", info.email));
+    }
+    if let Some(phone) = info.phone.as_deref().filter(|p| !p.is_empty()) {
+        lines.push(format!("TEL;TYPE=CELL:{}", phone));
+    }
+    if let Some(location) = info.location.as_deref().filter(|l| !l.is_empty()) {
+        lines.push(format!("ADR;TYPE=WORK:;;;{};;;", escape_text(location)));
+    }
+    if let Some(website) = info.website.as_deref().filter(|w| !w.is_empty()) {
+        lines.push(format!("URL:{}", website));
+    }
+
+    let mut social_links = info.social_links.iter().collect::<Vec<_>>();
+    social_links.sort();
+    for (network, url) in social_links {
+        lines.push(format!(
+            "X-SOCIALPROFILE;TYPE={}:{}",
+            network.to_lowercase(),
+            url
+        ));
+    }
+
+    let photo = info
+        .profile_image
+        .as_deref()
+        .map(|image| image.trim_start_matches("dist/"))
+        .and_then(|image| {
+            if image.starts_with("http://") || image.starts_with("https://") {
+                Some(image.to_string())
+            } else if !base_url.is_empty() {
+                Some(format!(
+                    "{}/{}",
+                    base_url.trim_end_matches('/'),
+                    image.trim_start_matches('/')
+                ))
+            } else {
+                None
+            }
+        })
+        .or_else(|| info.github_avatar_url.clone());
+    if let Some(photo) = photo {
+        lines.push(format!("PHOTO;VALUE=URI:{}", photo));
+    }
+    lines.push("END:VCARD".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+/// Write the contact card
+///
+/// # Arguments
+///
+/// * `info` - Personal info from the CV data
+/// * `base_url` - Site URL, to make a relative profile image absolute
+/// * `path` - Path of the `.vcf` file
+///
+/// # Returns
+///
+/// A Result indicating success or failure
+pub fn generate_vcard(info: &PersonalInfo, base_url: &str, path: &Path) -> Result<()> {
+    fs::write(path, vcard(info, base_url))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Fold a line longer than 75 octets, continuing it after CRLF and a space
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut octets = 0;
+    for c in line.chars() {
+        // Continuation lines start with a space, which counts towards the limit
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+/// Escape a TEXT value (RFC 6350, section 3.4)
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vcard() {
+        let info = PersonalInfo {
+            name: "Ada King Lovelace".to_string(),
+            title: "Analyst, Programmer".to_string(),
+            email: "ada@example.com".to_string(),
+            phone: Some("+44 20 1234 5678".to_string()),
+            website: Some("https://ada.example.com".to_string()),
+            location: Some("London; UK".to_string()),
+            summary: String::new(),
+            social_links: im::hashmap! {
+                "GitHub".to_string() => "https://github.com/ada".to_string(),
+            },
+            profile_image: Some("dist/img/ada.jpg".to_string()),
+            github_avatar_url: None,
+            summaries: Default::default(),
+        };
+
+        let card = vcard(&info, "https://ada.example.com/");
+        let lines: Vec<&str> = card.split("\r\n").collect();
+        assert_eq!(lines[0], "BEGIN:VCARD");
+        assert!(lines.contains(&"FN:Ada King Lovelace"));
+        assert!(lines.contains(&"N:Lovelace;Ada King;;;"));
+        assert!(lines.contains(&"TITLE:Analyst\\, Programmer"));
+        assert!(lines.contains(&"TEL;TYPE=CELL:+44 20 1234 5678"));
+        assert!(lines.contains(&"ADR;TYPE=WORK:;;;London\\; UK;;;"));
+        assert!(lines.contains(&"X-SOCIALPROFILE;TYPE=github:https://github.com/ada"));
+        assert!(lines.contains(&"PHOTO;VALUE=URI:https://ada.example.com/img/ada.jpg"));
+        assert!(card.ends_with("END:VCARD\r\n"));
+
+        let long = fold_line(&format!("NOTE:{}", "é".repeat(60)));
+        assert!(long.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+        assert_eq!(
+            long.replace("\r\n ", ""),
+            format!("NOTE:{}", "é".repeat(60))
+        );
+    }
+}
@@ -0,0 +1,817 @@
+//! Webhooks for content events
+//!
+//! Endpoints declared in `config.toml` are told when a post is published or
+//! updated and when new media appears in the static directory, so automations
+//! such as cross-posting or reindexing a search can react to content changes:
+//!
+//! ```toml
+//! [webhooks.crosspost]
+//! url = "https://automation.example.com/hooks/cv"
+//! events = ["post.published"]
+//! secret = "crosspost_webhook_secret"
+//! ```
+//!
+//! Each build compares the published posts and the media files with what the
+//! CV database (`data/cv.db`) recorded last time and queues an event per
+//! subscribed endpoint in the `webhook_deliveries` table. The first build only
+//! records what exists, so enabling webhooks doesn't announce every old post.
+//!
+//! Queued events are sent as JSON `POST`s. With a `secret` (the name of a
+//! declared [secret](crate::secrets)) the body is signed: `X-Webhook-Signature`
+//! is `sha256=` and the hex HMAC-SHA256 of `<X-Webhook-Timestamp>.<body>`.
+//! Failed deliveries are retried with a growing delay on later builds or with
+//! `cv webhooks deliver`, up to [`MAX_ATTEMPTS`] times; every attempt is
+//! logged in the table.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use im::{OrdMap, Vector};
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::blog_posts::BlogPost;
+use crate::db::Database;
+use crate::deploy::{hex, hmac_sha256};
+use crate::html_generator::filters::join_url;
+use crate::secrets::Secret;
+
+/// Attempts after which a delivery is given up
+pub const MAX_ATTEMPTS: u32 = 6;
+
+/// Delay before the first retry; each further retry waits four times as long
+pub const RETRY_DELAY_MINUTES: i64 = 5;
+
+/// Seconds to wait for an endpoint to answer
+const TIMEOUT_SECONDS: u64 = 10;
+
+/// File extensions of media files
+const MEDIA_EXTENSIONS: [&str; 12] = [
+    "png", "jpg", "jpeg", "gif", "webp", "avif", "svg", "mp4", "webm", "mp3", "ogg", "pdf",
+];
+
+/// Columns of the `webhook_deliveries` table, in the order [`from_row`] reads them
+const COLUMNS: &str = "id, endpoint, event, payload, status, attempts, next_attempt_at, \
+     response_status, last_error, created_at, delivered_at";
+
+/// A content event endpoints can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ContentEvent {
+    /// A post was published, or a new post appeared
+    #[serde(rename = "post.published")]
+    PostPublished,
+    /// A published post changed
+    #[serde(rename = "post.updated")]
+    PostUpdated,
+    /// A new media file appeared in the static directory
+    #[serde(rename = "media.uploaded")]
+    MediaUploaded,
+}
+
+impl ContentEvent {
+    /// Name of the event, as sent in `X-Webhook-Event`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEvent::PostPublished => "post.published",
+            ContentEvent::PostUpdated => "post.updated",
+            ContentEvent::MediaUploaded => "media.uploaded",
+        }
+    }
+}
+
+impl fmt::Display for ContentEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ContentEvent {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "post.published" => Ok(ContentEvent::PostPublished),
+            "post.updated" => Ok(ContentEvent::PostUpdated),
+            "media.uploaded" => Ok(ContentEvent::MediaUploaded),
+            other => anyhow::bail!(
+                "Unknown event '{}' (expected post.published, post.updated or media.uploaded)",
+                other
+            ),
+        }
+    }
+}
+
+/// An endpoint from `[webhooks.<name>]` in `config.toml`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    /// Where events are posted
+    pub url: String,
+    /// Events to send; all of them if empty
+    #[serde(default)]
+    pub events: Vector<ContentEvent>,
+    /// Name of the declared secret the body is signed with
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl WebhookEndpoint {
+    /// Whether the endpoint wants an event
+    pub fn subscribes(&self, event: ContentEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+}
+
+/// A post or media file as the webhooks see it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentItem {
+    /// `post:<slug>` or `media:<path>`
+    pub key: String,
+    /// Hash of the content, to notice changes
+    pub fingerprint: String,
+    /// What is sent as `data` in the event
+    pub data: serde_json::Value,
+}
+
+impl ContentItem {
+    fn is_post(&self) -> bool {
+        self.key.starts_with("post:")
+    }
+}
+
+/// A published post
+///
+/// # Arguments
+///
+/// * `post` - The post
+/// * `base_url` - Base URL of the site, for the post's URL
+pub fn post_item(post: &BlogPost, base_url: &str) -> ContentItem {
+    let fingerprint = sha256(
+        serde_json::json!([
+            post.title,
+            post.excerpt,
+            post.tags,
+            post.content,
+            post.updated
+        ])
+        .to_string()
+        .as_bytes(),
+    );
+    ContentItem {
+        key: format!("post:{}", post.slug),
+        fingerprint,
+        data: serde_json::json!({
+            "slug": post.slug,
+            "title": post.title,
+            "url": join_url(&format!("blog/{}.html", post.slug), base_url),
+            "date": post.date.to_rfc3339(),
+            "updated": post.updated.map(|updated| updated.to_rfc3339()),
+            "tags": post.tags,
+            "excerpt": post.excerpt,
+        }),
+    }
+}
+
+/// The media files of a directory
+///
+/// # Arguments
+///
+/// * `dir` - The static directory
+/// * `base_url` - Base URL of the site, for the files' URLs
+///
+/// # Returns
+///
+/// A media item per file, by path; none if the directory doesn't exist
+pub fn media_items(dir: &Path, base_url: &str) -> Result<Vector<ContentItem>> {
+    fn walk(root: &Path, dir: &Path, base_url: &str, items: &mut Vec<ContentItem>) -> Result<()> {
+        for entry in
+            fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?
+        {
+            let path = entry?.path();
+            let is_media = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+            if path.is_dir() {
+                walk(root, &path, base_url, items)?;
+            } else if is_media {
+                let content = fs::read(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let relative = path
+                    .strip_prefix(root)?
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                items.push(ContentItem {
+                    key: format!("media:{}", relative),
+                    fingerprint: sha256(&content),
+                    data: serde_json::json!({
+                        "path": relative,
+                        "url": join_url(&relative, base_url),
+                        "size": content.len(),
+                    }),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    let mut items = Vec::new();
+    if dir.is_dir() {
+        walk(dir, dir, base_url, &mut items)?;
+    }
+    items.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(items.into_iter().collect())
+}
+
+/// Events for the content that is new or changed since the last build
+///
+/// # Arguments
+///
+/// * `previous` - Fingerprints recorded by the last build, by key
+/// * `items` - The posts and media files now
+///
+/// # Returns
+///
+/// `post.published` for new posts, `post.updated` for changed ones and
+/// `media.uploaded` for new media files, with the item they are about
+pub fn detect_events<'a>(
+    previous: &OrdMap<String, String>,
+    items: &'a Vector<ContentItem>,
+) -> Vector<(ContentEvent, &'a ContentItem)> {
+    items
+        .iter()
+        .filter_map(|item| match (previous.get(&item.key), item.is_post()) {
+            (None, true) => Some((ContentEvent::PostPublished, item)),
+            (None, false) => Some((ContentEvent::MediaUploaded, item)),
+            (Some(fingerprint), true) if *fingerprint != item.fingerprint => {
+                Some((ContentEvent::PostUpdated, item))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Signature of a webhook body, as sent in `X-Webhook-Signature`
+///
+/// # Arguments
+///
+/// * `secret` - The endpoint's secret
+/// * `timestamp` - Unix time sent in `X-Webhook-Timestamp`
+/// * `body` - The JSON body
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let message = format!("{}.{}", timestamp, body);
+    format!(
+        "sha256={}",
+        hex(&hmac_sha256(secret.as_bytes(), message.as_bytes()))
+    )
+}
+
+/// Where a delivery stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Waiting for its first or next attempt
+    Pending,
+    /// Accepted by the endpoint
+    Delivered,
+    /// Given up after [`MAX_ATTEMPTS`] attempts
+    Failed,
+}
+
+impl DeliveryStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DeliveryStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "pending" => Ok(DeliveryStatus::Pending),
+            "delivered" => Ok(DeliveryStatus::Delivered),
+            "failed" => Ok(DeliveryStatus::Failed),
+            other => anyhow::bail!("Unknown delivery status '{}'", other),
+        }
+    }
+}
+
+/// An event queued for an endpoint, with its delivery log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub id: i64,
+    /// Name of the endpoint in `config.toml`
+    pub endpoint: String,
+    pub event: ContentEvent,
+    /// The JSON body
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    /// When the next attempt is due, RFC 3339
+    pub next_attempt_at: String,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub response_status: Option<u16>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    /// When the event was queued, RFC 3339
+    pub created_at: String,
+    /// When the endpoint accepted it, RFC 3339
+    pub delivered_at: Option<String>,
+}
+
+/// Deliveries made by [`deliver_due`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub delivered: usize,
+    /// Failed, but will be retried
+    pub retrying: usize,
+    /// Failed for the last time
+    pub failed: usize,
+}
+
+/// Delay before the attempt after `attempts` failed ones
+fn retry_delay(attempts: u32) -> Duration {
+    Duration::minutes(RETRY_DELAY_MINUTES * 4_i64.pow(attempts.saturating_sub(1)))
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn sha256(content: &[u8]) -> String {
+    hex(&Sha256::digest(content))
+}
+
+/// A row as stored, before parsing the event and status
+type RawRow = (
+    i64,
+    String,
+    String,
+    String,
+    String,
+    u32,
+    String,
+    Option<u16>,
+    Option<String>,
+    String,
+    Option<String>,
+);
+
+fn raw_row(row: &Row) -> rusqlite::Result<RawRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+        row.get(9)?,
+        row.get(10)?,
+    ))
+}
+
+fn from_row(row: RawRow) -> Result<Delivery> {
+    let (
+        id,
+        endpoint,
+        event,
+        payload,
+        status,
+        attempts,
+        next_attempt_at,
+        response_status,
+        last_error,
+        created_at,
+        delivered_at,
+    ) = row;
+    Ok(Delivery {
+        id,
+        endpoint,
+        event: event.parse()?,
+        payload,
+        status: status.parse()?,
+        attempts,
+        next_attempt_at,
+        response_status,
+        last_error,
+        created_at,
+        delivered_at,
+    })
+}
+
+impl Database {
+    /// Fingerprints of the content recorded by the last build, by key
+    pub fn content_fingerprints(&self) -> Result<OrdMap<String, String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT key, fingerprint FROM content_fingerprints")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read content fingerprints")?;
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Record the content of a build and queue its events for the endpoints
+    ///
+    /// Nothing is queued the first time, when no content was recorded yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The posts and media files now
+    /// * `endpoints` - Configured endpoints, by name
+    /// * `now` - When the build ran
+    ///
+    /// # Returns
+    ///
+    /// The number of deliveries queued
+    pub fn record_content(
+        &mut self,
+        items: &Vector<ContentItem>,
+        endpoints: &OrdMap<String, WebhookEndpoint>,
+        now: DateTime<Utc>,
+    ) -> Result<usize> {
+        let previous = self.content_fingerprints()?;
+        let events = if previous.is_empty() {
+            Vector::new()
+        } else {
+            detect_events(&previous, items)
+        };
+
+        let transaction = self.connection.transaction()?;
+        let mut queued = 0;
+        for (event, item) in &events {
+            let payload = serde_json::json!({
+                "event": event,
+                "occurred_at": timestamp(now),
+                "data": item.data,
+            })
+            .to_string();
+            for (name, _) in endpoints.iter().filter(|(_, e)| e.subscribes(*event)) {
+                transaction.execute(
+                    "INSERT INTO webhook_deliveries
+                     (endpoint, event, payload, status, attempts, next_attempt_at, created_at)
+                     VALUES (?1, ?2, ?3, ?4, 0, ?5, ?5)",
+                    params![
+                        name,
+                        event.as_str(),
+                        payload,
+                        DeliveryStatus::Pending.as_str(),
+                        timestamp(now)
+                    ],
+                )?;
+                queued += 1;
+            }
+        }
+        transaction.execute("DELETE FROM content_fingerprints", [])?;
+        for item in items {
+            transaction.execute(
+                "INSERT INTO content_fingerprints (key, fingerprint) VALUES (?1, ?2)",
+                params![item.key, item.fingerprint],
+            )?;
+        }
+        transaction
+            .commit()
+            .context("Failed to record content for webhooks")?;
+        Ok(queued)
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first
+    pub fn due_deliveries(&self, now: DateTime<Utc>) -> Result<Vector<Delivery>> {
+        self.query_deliveries(
+            &format!(
+                "SELECT {} FROM webhook_deliveries
+                 WHERE status = 'pending' AND next_attempt_at <= ?1 ORDER BY id",
+                COLUMNS
+            ),
+            params![timestamp(now)],
+        )
+    }
+
+    /// The latest deliveries, newest first
+    pub fn deliveries(&self, limit: usize) -> Result<Vector<Delivery>> {
+        self.query_deliveries(
+            &format!(
+                "SELECT {} FROM webhook_deliveries ORDER BY id DESC LIMIT ?1",
+                COLUMNS
+            ),
+            params![limit as i64],
+        )
+    }
+
+    fn query_deliveries(
+        &self,
+        sql: &str,
+        parameters: impl rusqlite::Params,
+    ) -> Result<Vector<Delivery>> {
+        let mut statement = self.connection.prepare(sql)?;
+        let rows = statement
+            .query_map(parameters, raw_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("Failed to read webhook deliveries")?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /// Log an attempt to deliver an event
+    ///
+    /// # Arguments
+    ///
+    /// * `delivery` - The delivery as it was before the attempt
+    /// * `response_status` - HTTP status the endpoint answered with, if any
+    /// * `error` - Why the attempt failed, None if it succeeded
+    /// * `now` - When the attempt was made
+    ///
+    /// # Returns
+    ///
+    /// The status of the delivery after the attempt
+    pub fn record_attempt(
+        &self,
+        delivery: &Delivery,
+        response_status: Option<u16>,
+        error: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<DeliveryStatus> {
+        let attempts = delivery.attempts + 1;
+        let (status, next_attempt_at) = match error {
+            None => (DeliveryStatus::Delivered, now),
+            Some(_) if attempts >= MAX_ATTEMPTS => (DeliveryStatus::Failed, now),
+            Some(_) => (DeliveryStatus::Pending, now + retry_delay(attempts)),
+        };
+        self.connection
+            .execute(
+                "UPDATE webhook_deliveries SET status = ?1, attempts = ?2, next_attempt_at = ?3,
+                 response_status = ?4, last_error = ?5, delivered_at = ?6 WHERE id = ?7",
+                params![
+                    status.as_str(),
+                    attempts,
+                    timestamp(next_attempt_at),
+                    response_status,
+                    error,
+                    (status == DeliveryStatus::Delivered).then(|| timestamp(now)),
+                    delivery.id
+                ],
+            )
+            .context("Failed to log webhook delivery")?;
+        Ok(status)
+    }
+
+    /// Queue a failed delivery again, for an attempt on the next delivery run
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Id of the delivery
+    ///
+    /// # Returns
+    ///
+    /// An error if there is no failed delivery with the id
+    pub fn retry_delivery(&self, id: i64) -> Result<()> {
+        let changed = self
+            .connection
+            .execute(
+                "UPDATE webhook_deliveries SET status = 'pending', attempts = 0,
+                 next_attempt_at = ?1 WHERE id = ?2 AND status = 'failed'",
+                params![timestamp(Utc::now()), id],
+            )
+            .context("Failed to queue webhook delivery")?;
+        if changed == 0 {
+            anyhow::bail!("No failed delivery #{}", id);
+        }
+        Ok(())
+    }
+}
+
+/// Send the deliveries that are due
+///
+/// # Arguments
+///
+/// * `db` - CV database holding the deliveries
+/// * `endpoints` - Configured endpoints, by name
+/// * `secrets` - Resolved signing secrets, by endpoint name
+/// * `now` - The current time
+///
+/// # Returns
+///
+/// How many deliveries succeeded, will be retried or were given up
+pub async fn deliver_due(
+    db: &Database,
+    endpoints: &OrdMap<String, WebhookEndpoint>,
+    secrets: &OrdMap<String, Secret>,
+    now: DateTime<Utc>,
+) -> Result<DeliveryReport> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(TIMEOUT_SECONDS))
+        .user_agent(concat!("cv-generator/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .context("Failed to create HTTP client")?;
+
+    let mut report = DeliveryReport::default();
+    for delivery in db.due_deliveries(now)? {
+        let Some(endpoint) = endpoints.get(&delivery.endpoint) else {
+            // Nobody can receive it, so this is its last attempt
+            let last = Delivery {
+                attempts: MAX_ATTEMPTS - 1,
+                ..delivery.clone()
+            };
+            db.record_attempt(&last, None, Some("Endpoint is no longer configured"), now)?;
+            report.failed += 1;
+            continue;
+        };
+
+        let time = Utc::now();
+        let request = client
+            .post(&endpoint.url)
+            .header("Content-Type", "application/json")
+            .header("X-Webhook-Event", delivery.event.as_str())
+            .header("X-Webhook-Delivery", delivery.id.to_string())
+            .header("X-Webhook-Timestamp", time.timestamp().to_string());
+        let request = match secrets.get(&delivery.endpoint) {
+            Some(secret) => request.header(
+                "X-Webhook-Signature",
+                signature(secret.expose(), time.timestamp(), &delivery.payload),
+            ),
+            None => request,
+        };
+        let (response_status, error) = match request.body(delivery.payload.clone()).send().await {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("Endpoint answered {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let status = db.record_attempt(&delivery, response_status, error.as_deref(), now)?;
+        match status {
+            DeliveryStatus::Delivered => report.delivered += 1,
+            DeliveryStatus::Pending => report.retrying += 1,
+            DeliveryStatus::Failed => report.failed += 1,
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap()
+    }
+
+    fn item(key: &str, fingerprint: &str) -> ContentItem {
+        ContentItem {
+            key: key.to_string(),
+            fingerprint: fingerprint.to_string(),
+            data: serde_json::json!({ "key": key }),
+        }
+    }
+
+    fn endpoints() -> OrdMap<String, WebhookEndpoint> {
+        OrdMap::unit(
+            "search".to_string(),
+            WebhookEndpoint {
+                url: "https://example.com/hook".to_string(),
+                events: im::vector![ContentEvent::PostPublished, ContentEvent::PostUpdated],
+                secret: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_detect_events() {
+        let previous = OrdMap::from(vec![
+            ("post:old".to_string(), "a".to_string()),
+            ("post:edited".to_string(), "b".to_string()),
+            ("media:img/old.png".to_string(), "c".to_string()),
+        ]);
+        let items = im::vector![
+            item("post:old", "a"),
+            item("post:edited", "B"),
+            item("post:new", "d"),
+            item("media:img/old.png", "C"),
+            item("media:img/new.png", "e"),
+        ];
+
+        let events = detect_events(&previous, &items)
+            .iter()
+            .map(|(event, item)| format!("{} {}", event, item.key))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                "post.updated post:edited",
+                "post.published post:new",
+                "media.uploaded media:img/new.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        assert_eq!(
+            signature("s3cret", 1_760_000_000, r#"{"event":"post.published"}"#),
+            "sha256=026eeffee86483569466893949ba07b9db210dfa96165606ce058edd87b1e47c"
+        );
+    }
+
+    #[test]
+    fn test_record_content_queues_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Database::open(dir.path().join("cv.db")).unwrap();
+
+        // The first build only records the content
+        let first = im::vector![item("post:hello", "a")];
+        assert_eq!(db.record_content(&first, &endpoints(), now()).unwrap(), 0);
+
+        let second = im::vector![
+            item("post:hello", "b"),
+            item("post:new", "c"),
+            item("media:img/new.png", "d"),
+        ];
+        assert_eq!(db.record_content(&second, &endpoints(), now()).unwrap(), 2);
+        assert_eq!(db.record_content(&second, &endpoints(), now()).unwrap(), 0);
+
+        let due = db.due_deliveries(now()).unwrap();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[0].event, ContentEvent::PostUpdated);
+        assert_eq!(due[0].endpoint, "search");
+        let payload: serde_json::Value = serde_json::from_str(&due[1].payload).unwrap();
+        assert_eq!(payload["event"], "post.published");
+        assert_eq!(payload["data"]["key"], "post:new");
+    }
+
+    #[test]
+    fn test_record_attempt_retries_then_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Database::open(dir.path().join("cv.db")).unwrap();
+        db.record_content(&im::vector![item("post:a", "a")], &endpoints(), now())
+            .unwrap();
+        db.record_content(&im::vector![item("post:a", "b")], &endpoints(), now())
+            .unwrap();
+
+        let delivery = db.due_deliveries(now()).unwrap()[0].clone();
+        let status = db
+            .record_attempt(&delivery, Some(502), Some("Endpoint answered 502"), now())
+            .unwrap();
+        assert_eq!(status, DeliveryStatus::Pending);
+        assert!(db.due_deliveries(now()).unwrap().is_empty());
+        let later = now() + Duration::minutes(RETRY_DELAY_MINUTES);
+        let delivery = db.due_deliveries(later).unwrap()[0].clone();
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.response_status, Some(502));
+
+        let last = Delivery {
+            attempts: MAX_ATTEMPTS - 1,
+            ..delivery
+        };
+        let status = db
+            .record_attempt(&last, None, Some("timed out"), later)
+            .unwrap();
+        assert_eq!(status, DeliveryStatus::Failed);
+        assert!(db.retry_delivery(last.id + 1).is_err());
+        db.retry_delivery(last.id).unwrap();
+        assert_eq!(
+            db.deliveries(10).unwrap()[0].status,
+            DeliveryStatus::Pending
+        );
+    }
+
+    #[test]
+    fn test_endpoint_config() {
+        let endpoint: WebhookEndpoint = toml::from_str(
+            r#"
+            url = "https://example.com/hook"
+            events = ["media.uploaded"]
+            "#,
+        )
+        .unwrap();
+        assert!(endpoint.subscribes(ContentEvent::MediaUploaded));
+        assert!(!endpoint.subscribes(ContentEvent::PostPublished));
+        assert_eq!(
+            "post.updated".parse::<ContentEvent>().unwrap(),
+            ContentEvent::PostUpdated
+        );
+    }
+}
@@ -2,73 +2,29 @@
  * Main JavaScript file for the CV/Portfolio site
  */
 
-// Menu Module - Persistent menu with configurable items
-const MenuModule = (function() {
-    // Store active menu item in localStorage
-    const storeActiveMenuItem = (item) => {
-        localStorage.setItem('activeMenuItem', item);
-    };
-
-    // Get active menu item from localStorage
-    const getActiveMenuItem = () => {
-        return localStorage.getItem('activeMenuItem') || 'home';
-    };
-
-    // Initialize menu with active item
-    const initMenu = (config = {}) => {
-        const menuItems = document.querySelectorAll('nav ul li a');
-        const activeItem = config.activeItem || getActiveMenuItem();
-
-        // Set active class on the current page's menu item
-        menuItems.forEach(item => {
-            const href = item.getAttribute('href');
-            const itemId = href.includes('.html') ? href.split('.')[0] : href.replace('#', '');
-
-            if (itemId === activeItem) {
-                item.classList.add('active');
-                storeActiveMenuItem(activeItem);
-            }
-
-            // Add click handler to store the clicked item
-            item.addEventListener('click', function() {
-                const clickedItemId = href.includes('.html') ? href.split('.')[0] : href.replace('#', '');
-                storeActiveMenuItem(clickedItemId);
-            });
-        });
-
-        // Apply custom menu configuration if provided
-        if (config.visibleItems) {
-            menuItems.forEach(item => {
-                const href = item.getAttribute('href');
-                const itemId = href.includes('.html') ? href.split('.')[0] : href.replace('#', '');
-
-                if (!config.visibleItems.includes(itemId)) {
-                    item.parentElement.style.display = 'none';
-                } else {
-                    item.parentElement.style.display = '';
-                }
-            });
-        }
-    };
-
-    // Public API
-    return {
-        init: initMenu
-    };
-})();
-
 // Theme Module - Light/Dark mode switcher with localStorage persistence
 const ThemeModule = (function() {
-    // Function to set a theme
-    function setTheme(theme) {
-        document.documentElement.className = theme;
+    const systemDark = window.matchMedia("(prefers-color-scheme: dark)");
 
-        // Safe localStorage access with error handling
+    // The theme saved by the toggle, if any
+    function savedTheme() {
         try {
-            localStorage.setItem("theme", theme);
+            return localStorage.getItem("theme");
         } catch (error) {
             console.warn('localStorage not available:', error);
+            return null;
         }
+    }
+
+    // The theme following the system preference
+    function systemTheme() {
+        return systemDark.matches ? "theme-dark" : "theme-light";
+    }
+
+    // Function to show a theme without saving it
+    function applyTheme(theme) {
+        document.documentElement.classList.remove("theme-dark", "theme-light");
+        document.documentElement.classList.add(theme);
 
         const themeToggle = document.querySelector('.theme-switch');
         if (themeToggle) {
@@ -92,26 +48,34 @@ const ThemeModule = (function() {
         }
     }
 
-    // Initialize theme system
-    function initTheme() {
-        const themeToggle = document.querySelector('.theme-switch');
-        if (!themeToggle) {
-            return;
-        }
+    // Function to set a theme and remember it
+    function setTheme(theme) {
+        applyTheme(theme);
 
-        // Check for saved theme preference or use system preference
-        let savedTheme = null;
+        // Safe localStorage access with error handling
         try {
-            savedTheme = localStorage.getItem("theme");
+            localStorage.setItem("theme", theme);
         } catch (error) {
             console.warn('localStorage not available:', error);
         }
+    }
 
-        const currentTheme = savedTheme ||
-            (window.matchMedia("(prefers-color-scheme: dark)").matches ? "theme-dark" : "theme-light");
+    // Initialize theme system
+    function initTheme() {
+        // Use the saved theme preference or follow the system preference
+        applyTheme(savedTheme() || systemTheme());
 
-        // Apply the theme
-        setTheme(currentTheme);
+        // Keep following the system until a theme is chosen
+        systemDark.addEventListener("change", function () {
+            if (!savedTheme()) {
+                applyTheme(systemTheme());
+            }
+        });
+
+        const themeToggle = document.querySelector('.theme-switch');
+        if (!themeToggle) {
+            return;
+        }
 
         // Listen for theme toggle
         themeToggle.addEventListener("click", function () {
@@ -139,68 +103,8 @@ const ThemeModule = (function() {
     };
 })();
 
-// Load language icons from JSON file
-let languageIcons = {};
-
-// Fetch language icons from JSON file
-fetch('data/language_icons.json')
-    .then(response => response.json())
-    .then(data => {
-        languageIcons = data;
-        // Language icons loaded successfully
-
-        // Initialize language icons for GitHub cards after icons are loaded
-        initializeLanguageIcons();
-    })
-    .catch(error => {
-        console.error('Error loading language icons:', error);
-        // Fallback to hardcoded icons
-        languageIcons = {
-            "scala": "",
-            "rust": ""
-        };
-        initializeLanguageIcons();
-    });
-
-// Function to get language icon
-function getLanguageIcon(language) {
-    const normalizedLang = language.toLowerCase();
-
-    // Direct match
-    if (languageIcons[normalizedLang]) {
-        return languageIcons[normalizedLang];
-    }
-
-    // Check for partial matches (e.g., "scala3" should match "scala")
-    for (const [key, value] of Object.entries(languageIcons)) {
-        if (normalizedLang.includes(key)) {
-            return value;
-        }
-    }
-
-    // Default icon for unknown languages
-    return '';
-}
-
 // Main initialization function
 document.addEventListener('DOMContentLoaded', function() {
-    // Initialize the menu module
-    const currentPath = window.location.pathname;
-    const pageName = currentPath.split('/').pop().split('.')[0] || 'index';
-
-    // Configure menu based on current page
-    let menuConfig = { activeItem: pageName };
-
-    // Example of page-specific menu configuration
-    if (pageName === 'cv') {
-        menuConfig.visibleItems = ['cv', 'projects', 'blog', 'index'];
-    } else if (pageName === 'projects') {
-        menuConfig.visibleItems = ['cv', 'projects', 'blog', 'index'];
-    }
-
-    // Initialize the menu with the configuration
-    MenuModule.init(menuConfig);
-
     // Initialize the theme switcher
     ThemeModule.init();
 
@@ -240,6 +144,16 @@ document.addEventListener('DOMContentLoaded', function() {
         });
     }
 
+    // Timeline filters show and hide the entries of a kind
+    document.querySelectorAll('.timeline-filter').forEach(filter => {
+        filter.addEventListener('click', function() {
+            const shown = this.getAttribute('aria-pressed') !== 'true';
+            this.setAttribute('aria-pressed', shown ? 'true' : 'false');
+            document.querySelectorAll(`.timeline-entry[data-kind="${this.dataset.kind}"]`)
+                .forEach(entry => { entry.hidden = !shown; });
+        });
+    });
+
     // Mobile menu toggle
     const mobileMenuToggle = document.querySelector('.mobile-menu-toggle');
     const navigation = document.querySelector('#main-navigation');
@@ -254,55 +168,6 @@ document.addEventListener('DOMContentLoaded', function() {
     }
 });
 
-// Function to initialize language icons for GitHub cards
-function initializeLanguageIcons() {
-    const githubCards = document.querySelectorAll('.github-card');
-    githubCards.forEach(card => {
-        const header = card.querySelector('.card-header');
-        const title = card.querySelector('.project-title').textContent.toLowerCase();
-        let languageDetected = false;
-
-        // Function to add language icon
-        function addLanguageIcon(language) {
-            const titleElement = card.querySelector('.project-title');
-            const iconClass = `${language}-icon`;
-
-            if (titleElement && !titleElement.querySelector('i') && !titleElement.querySelector(`span.${iconClass}`)) {
-                const icon = document.createElement('span');
-                icon.textContent = getLanguageIcon(language);
-                icon.className = iconClass;
-                titleElement.prepend(icon);
-                titleElement.insertBefore(document.createTextNode(' '), titleElement.childNodes[1]);
-            }
-
-            header.classList.add(language);
-            languageDetected = true;
-        }
-
-        // Check title for language hints
-        for (const lang in languageIcons) {
-            if (title.includes(lang)) {
-                addLanguageIcon(lang);
-                break;
-            }
-        }
-
-        // If no language detected from title, check technologies
-        if (!languageDetected) {
-            const techTags = card.querySelectorAll('.tech-tag');
-            techTags.forEach(tag => {
-                const tagText = tag.textContent.toLowerCase();
-                for (const lang in languageIcons) {
-                    if (tagText === lang || tagText.includes(lang)) {
-                        addLanguageIcon(lang);
-                        return;
-                    }
-                }
-            });
-        }
-    });
-}
-
 // Service Worker Registration
 if ('serviceWorker' in navigator) {
     window.addEventListener('load', () => {
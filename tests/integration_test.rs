@@ -1,5 +1,5 @@
 use anyhow::Result;
-use cv_generator::{cv_data::Cv, html_generator, site_config::SiteConfig};
+use cv_generator::{content_security_policy, cv_data::Cv, html_generator, site_config::SiteConfig};
 use std::fs;
 use std::path::Path;
 use tempfile::tempdir;
@@ -51,6 +51,10 @@ fn test_full_cv_generation_workflow() -> Result<()> {
         "HTML should link to CSS"
     );
 
+    // Deployment configs are written once the site is finished
+    let policy = content_security_policy::apply_site_csp(output_dir)?;
+    html_generator::generate_deployment_configs(output_dir, &site_config, &policy)?;
+
     // Verify deployment configs
     assert!(
         output_dir.join(".htaccess").exists(),
@@ -135,6 +139,7 @@ fn test_minimal_cv_generation() -> Result<()> {
             website: None,
             location: None,
             summary: "Test summary".to_string(),
+            summaries: Default::default(),
             social_links: Default::default(),
             profile_image: None,
             github_avatar_url: None,
@@ -146,6 +151,9 @@ fn test_minimal_cv_generation() -> Result<()> {
         languages: Default::default(),
         certifications: Vector::new(),
         github_sources: Vector::new(),
+        registry_sources: Vector::new(),
+        testimonials: Vector::new(),
+        github_stats: None,
     };
 
     let site_config = SiteConfig::default();
@@ -281,3 +289,151 @@ fn test_github_cache_operations() -> Result<()> {
 
     Ok(())
 }
+
+/// A post removed from its data source is unpublished on the next build
+#[test]
+fn test_removed_upstream_post_is_unpublished() -> Result<()> {
+    use cv_generator::data_sources::{load_data_sources, DataSource};
+    use im::OrdMap;
+
+    let temp_dir = tempdir()?;
+    let upstream = temp_dir.path().join("upstream");
+    let blog_dir = temp_dir.path().join("content").join("blog");
+    let output_path = temp_dir.path().join("dist").join("cv.html");
+    let manifest = temp_dir.path().join("cache").join("data_sources.json");
+    fs::create_dir_all(&upstream)?;
+    fs::write(
+        upstream.join("hello.md"),
+        "---\ntitle: Hello\ndate: 2025-01-01\n---\nStill here",
+    )?;
+    fs::write(
+        upstream.join("retired.md"),
+        "---\ntitle: Retired\ndate: 2025-02-01\n---\nGone soon",
+    )?;
+
+    let sources = OrdMap::unit(
+        blog_dir.to_string_lossy().into_owned(),
+        DataSource {
+            local: Some(upstream.clone()),
+            ..DataSource::default()
+        },
+    );
+    let site_config: SiteConfig = serde_json::from_value(serde_json::json!({
+        "menu": [],
+        "blog": { "directory": blog_dir }
+    }))?;
+    let cv = Cv::from_json("data/cv_data.json")?;
+    let build = || -> Result<()> {
+        load_data_sources(&sources, &manifest, true, false)?;
+        html_generator::generate_html(&cv, &site_config, output_path.to_str().unwrap())
+    };
+
+    build()?;
+    let retired_page = temp_dir
+        .path()
+        .join("dist")
+        .join("blog")
+        .join("retired.html");
+    assert!(retired_page.is_file());
+
+    fs::remove_file(upstream.join("retired.md"))?;
+    build()?;
+    assert!(!blog_dir.join("retired.md").exists());
+    assert!(
+        !retired_page.exists(),
+        "the removed post is still published"
+    );
+    assert!(temp_dir.path().join("dist/blog/hello.html").is_file());
+    let listing = fs::read_to_string(temp_dir.path().join("dist").join("blog.html"))?;
+    assert!(!listing.contains("Retired"));
+
+    Ok(())
+}
+
+/// An encrypted page's text is in no file of the built site
+#[test]
+fn test_encrypted_page_plaintext_not_published() -> Result<()> {
+    use cv_generator::page_encryption::encrypt_pages;
+    use cv_generator::secrets::SecretSource;
+    use im::{HashMap, OrdMap};
+
+    const PLAINTEXT: &str = "The door code is 4711";
+
+    let temp_dir = tempdir()?;
+    let blog_dir = temp_dir.path().join("blog");
+    let pages_dir = temp_dir.path().join("pages");
+    let output_dir = temp_dir.path().join("dist");
+    fs::create_dir_all(&blog_dir)?;
+    fs::create_dir_all(&pages_dir)?;
+    fs::write(
+        blog_dir.join("hello.md"),
+        "---\ntitle: Hello\ndate: 2025-01-01\n---\nA public post",
+    )?;
+    fs::write(
+        pages_dir.join("members.md"),
+        format!("---\ntitle: Members\nslug: members\n---\n{PLAINTEXT}."),
+    )?;
+    let password_file = temp_dir.path().join("password");
+    fs::write(&password_file, "hunter2")?;
+
+    let site_config: SiteConfig = serde_json::from_value(serde_json::json!({
+        "menu": [],
+        "base_url": "https://example.com",
+        "blog": { "directory": blog_dir, "rss_feed": true },
+        "pages": { "directory": pages_dir },
+        "encrypted_pages": { "members.html": "members_password" }
+    }))?;
+    let cv = Cv::from_json("data/cv_data.json")?;
+    html_generator::generate_html(
+        &cv,
+        &site_config,
+        output_dir.join("cv.html").to_str().unwrap(),
+    )?;
+    let members = output_dir.join("members.html");
+    assert!(fs::read_to_string(&members)?.contains(PLAINTEXT));
+
+    let secrets = HashMap::unit(
+        "members_password".to_string(),
+        SecretSource {
+            file: Some(password_file),
+            ..SecretSource::default()
+        },
+    );
+    assert_eq!(
+        encrypt_pages(&output_dir, &site_config.encrypted_pages, &secrets)?,
+        1
+    );
+
+    fn files(dir: &Path) -> Vec<std::path::PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .flat_map(|path| {
+                if path.is_dir() {
+                    files(&path)
+                } else {
+                    vec![path]
+                }
+            })
+            .collect()
+    }
+    let published = files(&output_dir);
+    assert!(published.iter().any(|path| path.ends_with("feed.xml")));
+    for path in published {
+        let content = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
+        assert!(
+            !content.contains(PLAINTEXT),
+            "{} has the encrypted page's text",
+            path.display()
+        );
+    }
+
+    // Blog posts can't be encrypted, their text is in the feed
+    let blog_post = OrdMap::unit(
+        "blog/hello.html".to_string(),
+        "members_password".to_string(),
+    );
+    assert!(encrypt_pages(&output_dir, &blog_post, &secrets).is_err());
+
+    Ok(())
+}